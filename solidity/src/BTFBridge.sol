@@ -3,6 +3,7 @@ pragma solidity ^0.8.7;
 
 import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
 import "@openzeppelin/contracts/token/ERC20/utils/SafeERC20.sol";
+import "@openzeppelin/contracts/token/ERC20/extensions/IERC20Permit.sol";
 import "src/WrappedToken.sol";
 import "src/interfaces/IFeeCharge.sol";
 import { RingBuffer } from "src/libraries/RingBuffer.sol";
@@ -65,7 +66,7 @@ contract BTFBridge is TokenManager, UUPSUpgradeable, OwnableUpgradeable, Pausabl
     /// Controller AccessList for adding implementations
     mapping(address => bool) public controllerAccessList;
 
-    uint32 private constant MINT_ORDER_DATA_LEN = 269;
+    uint32 private constant MINT_ORDER_DATA_LEN = 406;
 
     struct MintOrderData {
         uint256 amount;
@@ -77,11 +78,16 @@ contract BTFBridge is TokenManager, UUPSUpgradeable, OwnableUpgradeable, Pausabl
         bytes32 name;
         bytes16 symbol;
         uint8 decimals;
-        uint32 senderChainID;
-        uint32 recipientChainID;
+        uint64 senderChainID;
+        uint64 recipientChainID;
         address approveSpender;
         uint256 approveAmount;
         address feePayer;
+        uint256 permitValue;
+        uint256 permitDeadline;
+        uint8 permitV;
+        bytes32 permitR;
+        bytes32 permitS;
     }
 
     // Event for mint operation
@@ -395,8 +401,19 @@ contract BTFBridge is TokenManager, UUPSUpgradeable, OwnableUpgradeable, Pausabl
             _isNonceUsed[order.senderID][order.nonce] = true;
             IERC20(order.toERC20).safeTransfer(order.recipient, order.amount);
 
-            // Approve spender for ERC-20 if applicable
-            if (order.approveSpender != address(0) && order.approveAmount != 0 && isTokenWrapped) {
+            // Forward the recipient's EIP-2612 permit if present, otherwise approve spender for ERC-20 if applicable.
+            // Failed permit doesn't revert the mint: it could be already used by the spender.
+            if (order.approveSpender != address(0) && order.permitDeadline != 0) {
+                try IERC20Permit(order.toERC20).permit(
+                    order.recipient,
+                    order.approveSpender,
+                    order.permitValue,
+                    order.permitDeadline,
+                    order.permitV,
+                    order.permitR,
+                    order.permitS
+                ) { } catch { }
+            } else if (order.approveSpender != address(0) && order.approveAmount != 0 && isTokenWrapped) {
                 WrappedToken(order.toERC20).approveByOwner(order.recipient, order.approveSpender, order.approveAmount);
             }
         }
@@ -628,7 +645,7 @@ contract BTFBridge is TokenManager, UUPSUpgradeable, OwnableUpgradeable, Pausabl
     function _decodeOrderFeePayer(
         bytes calldata encodedOrder
     ) private pure returns (address) {
-        return address(bytes20(encodedOrder[249:269]));
+        return address(bytes20(encodedOrder[257:277]));
     }
 
     /**
@@ -658,14 +675,19 @@ contract BTFBridge is TokenManager, UUPSUpgradeable, OwnableUpgradeable, Pausabl
         order.recipient = address(bytes20(encodedOrder[96:116]));
         order.toERC20 = address(bytes20(encodedOrder[116:136]));
         order.nonce = uint32(bytes4(encodedOrder[136:140]));
-        order.senderChainID = uint32(bytes4(encodedOrder[140:144]));
-        order.recipientChainID = uint32(bytes4(encodedOrder[144:148]));
-        order.name = bytes32(encodedOrder[148:180]);
-        order.symbol = bytes16(encodedOrder[180:196]);
-        order.decimals = uint8(encodedOrder[196]);
-        order.approveSpender = address(bytes20(encodedOrder[197:217]));
-        order.approveAmount = uint256(bytes32(encodedOrder[217:249]));
+        order.senderChainID = uint64(bytes8(encodedOrder[140:148]));
+        order.recipientChainID = uint64(bytes8(encodedOrder[148:156]));
+        order.name = bytes32(encodedOrder[156:188]);
+        order.symbol = bytes16(encodedOrder[188:204]);
+        order.decimals = uint8(encodedOrder[204]);
+        order.approveSpender = address(bytes20(encodedOrder[205:225]));
+        order.approveAmount = uint256(bytes32(encodedOrder[225:257]));
         order.feePayer = _decodeOrderFeePayer(encodedOrder);
+        order.permitValue = uint256(bytes32(encodedOrder[277:309]));
+        order.permitDeadline = uint256(bytes32(encodedOrder[309:341]));
+        order.permitV = uint8(encodedOrder[341]);
+        order.permitR = bytes32(encodedOrder[342:374]);
+        order.permitS = bytes32(encodedOrder[374:406]);
     }
 
     /// Function to check encodedOrder signature
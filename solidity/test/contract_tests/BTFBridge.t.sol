@@ -20,8 +20,8 @@ contract BTFBridgeTest is Test {
         address recipient;
         address toERC20;
         uint32 nonce;
-        uint32 senderChainID;
-        uint32 recipientChainID;
+        uint64 senderChainID;
+        uint64 recipientChainID;
         bytes32 name;
         bytes16 symbol;
         uint8 decimals;
@@ -333,6 +333,48 @@ contract BTFBridgeTest is Test {
         vm.stopPrank(); // Add this line to stop impersonating owner
     }
 
+    function testBatchMint64BitChainId() public {
+        vm.startPrank(_owner);
+        uint64 chainId = type(uint64).max;
+        vm.chainId(chainId);
+
+        MintOrder memory order = _createDefaultMintOrder();
+        order.senderChainID = chainId - 1;
+        order.recipientChainID = chainId;
+
+        MintOrder[] memory orders = new MintOrder[](1);
+        orders[0] = order;
+        bytes memory encodedOrders = _batchMintOrders(orders);
+        bytes memory signature = _batchMintOrdersSignature(encodedOrders, _OWNER_KEY);
+
+        uint32[] memory ordersToProcess = new uint32[](0);
+        uint8[] memory processedOrders = _wrappedBridge.batchMint(encodedOrders, signature, ordersToProcess);
+
+        assertEq(processedOrders[0], _wrappedBridge.MINT_ERROR_CODE_OK());
+        assertEq(WrappedToken(order.toERC20).balanceOf(order.recipient), order.amount);
+        vm.stopPrank();
+    }
+
+    function testBatchMintChainIdIsNotTruncated() public {
+        vm.startPrank(_owner);
+
+        // The lower 32 bits of the chain ID match the current chain.
+        MintOrder memory order = _createDefaultMintOrder();
+        order.recipientChainID = (uint64(1) << 32) | _CHAIN_ID;
+
+        MintOrder[] memory orders = new MintOrder[](1);
+        orders[0] = order;
+        bytes memory encodedOrders = _batchMintOrders(orders);
+        bytes memory signature = _batchMintOrdersSignature(encodedOrders, _OWNER_KEY);
+
+        uint32[] memory ordersToProcess = new uint32[](0);
+        uint8[] memory processedOrders = _wrappedBridge.batchMint(encodedOrders, signature, ordersToProcess);
+
+        assertEq(processedOrders[0], _wrappedBridge.MINT_ERROR_CODE_UNEXPECTED_RECIPIENT_CHAIN_ID());
+        assertEq(WrappedToken(order.toERC20).balanceOf(order.recipient), 0);
+        vm.stopPrank();
+    }
+
     function testBatchMintInvalidPair() public {
         vm.startPrank(_owner); // Start as owner/controller
 
@@ -959,7 +1001,7 @@ contract BTFBridgeTest is Test {
     function _encodeOrder(
         MintOrder memory order
     ) private pure returns (bytes memory) {
-        return abi.encodePacked(
+        bytes memory data = abi.encodePacked(
             order.amount,
             order.senderID,
             order.fromTokenID,
@@ -975,6 +1017,11 @@ contract BTFBridgeTest is Test {
             order.approveAmount,
             address(0)
         );
+        return abi.encodePacked(data, _encodeEmptyPermit());
+    }
+
+    function _encodeEmptyPermit() private pure returns (bytes memory) {
+        return abi.encodePacked(uint256(0), uint256(0), uint8(0), bytes32(0), bytes32(0));
     }
 
     function _batchMintOrdersSignature(
@@ -2,25 +2,50 @@ use std::cell::RefCell;
 use std::collections::HashSet;
 use std::rc::Rc;
 
+use bridge_canister::memory::{memory_by_id, PENDING_MINT_BATCHES_MEMORY_ID};
+use bridge_canister::operator_audit::MAX_AUDIT_ENTRIES_PER_REQUEST;
 use bridge_canister::runtime::service::fetch_logs::FetchBtfBridgeEventsService;
+use bridge_canister::runtime::service::gas_balance::{
+    MonitorGasBalanceService, GAS_BALANCE_QUERY_DELAY,
+};
+use bridge_canister::runtime::service::migrate_records::{
+    MigrateRecordsService, MIGRATE_RECORDS_DELAY,
+};
 use bridge_canister::runtime::service::mint_tx::SendMintTxService;
+use bridge_canister::runtime::service::outbox::{DispatchOutboxService, OUTBOX_DISPATCH_DELAY};
+use bridge_canister::runtime::service::ping_evm::{PingEvmService, PING_EVM_DELAY};
 use bridge_canister::runtime::service::sign_orders::SignMintOrdersService;
+use bridge_canister::runtime::service::timer::ServiceTimer;
 use bridge_canister::runtime::service::update_evm_params::RefreshEvmParamsService;
 use bridge_canister::runtime::service::ServiceOrder;
 use bridge_canister::runtime::state::config::ConfigStorage;
 use bridge_canister::runtime::{BridgeRuntime, RuntimeState};
 use bridge_canister::BridgeCanister;
+use bridge_did::bridge_mode::{OperationBlocksExport, ReplicaSyncStatus};
+use bridge_did::bulk_action::{BulkAction, BulkActionResult, OperationFilter, OperatorAuditEntry};
+use bridge_did::concurrency::DeferredOperationsStats;
+use bridge_did::dead_letter::DeadLetterEvent;
+use bridge_did::error::BTFResult;
+use bridge_did::fee_adjustment::FeePayerAdjustments;
+use bridge_did::icrc3::GetBlocksResult;
+use bridge_did::indexer_failover::{FallbackIndexers, IndexerFailoverStatus};
 use bridge_did::init::brc20::Brc20BridgeConfig;
 use bridge_did::init::BridgeInitData;
-use bridge_did::op_id::OperationId;
+use bridge_did::op_id::{NonceState, OperationId};
 use bridge_did::operation_log::{Memo, OperationLog};
+use bridge_did::operation_replay::ProgressReplayEntry;
+use bridge_did::operation_status::OperationStatusView;
+use bridge_did::outbox::OutboxMessage;
+use bridge_did::processed_event::{ProcessedEventInfo, RejectedEventsStats};
+use bridge_did::task_payload::TaskPayloadStats;
 use bridge_utils::common::Pagination;
-use candid::Principal;
+use candid::{Nat, Principal};
 use did::H160;
 use ic_canister::{generate_idl, init, post_upgrade, query, update, Canister, Idl, PreUpdate};
 use ic_exports::ic_cdk::api::management_canister::ecdsa::{
     ecdsa_public_key, EcdsaPublicKeyArgument,
 };
+use ic_exports::ic_kit::ic;
 use ic_exports::ledger::Subaccount;
 use ic_log::canister::{LogCanister, LogState};
 use ic_metrics::{Metrics, MetricsStorage};
@@ -30,8 +55,9 @@ use crate::canister::inspect::inspect_is_owner;
 use crate::interface::GetAddressError;
 use crate::ops::{
     Brc20BridgeOpImpl, Brc20BtfEventsHandler, Brc20MintOrderHandler, Brc20MintTxHandler,
-    FETCH_BTF_EVENTS_SERVICE_ID, REFRESH_PARAMS_SERVICE_ID, SEND_MINT_TX_SERVICE_ID,
-    SIGN_MINT_ORDER_SERVICE_ID,
+    DISPATCH_OUTBOX_SERVICE_ID, FETCH_BTF_EVENTS_SERVICE_ID, MIGRATE_RECORDS_SERVICE_ID,
+    MONITOR_GAS_BALANCE_SERVICE_ID, PING_EVM_SERVICE_ID, REFRESH_PARAMS_SERVICE_ID,
+    SEND_MINT_TX_SERVICE_ID, SIGN_MINT_ORDER_SERVICE_ID,
 };
 use crate::state::Brc20State;
 
@@ -60,7 +86,7 @@ impl Brc20Bridge {
 
     #[post_upgrade]
     pub fn post_upgrade(&mut self) {
-        self.bridge_post_upgrade(Self::run_scheduler)
+        self.bridge_post_upgrade(Self::run_scheduler);
     }
 
     fn run_scheduler() {
@@ -81,7 +107,8 @@ impl Brc20Bridge {
     /// The operations are then paginated with the given `pagination` parameters,
     /// starting from `offset` returning a max of `count` items
     /// If `offset` is `None`, it starts from the beginning (i.e. the first entry is the min_included_id).
-    /// If `count` is `None`, it returns all operations.
+    /// If `pagination` is `None`, only the most recent operations are returned, 1000 by default
+    /// (see `set_operations_list_default_limit`), so callers should paginate to get the full history.
     #[query]
     pub fn get_operations_list(
         &self,
@@ -89,14 +116,20 @@ impl Brc20Bridge {
         min_included_id: Option<OperationId>,
         pagination: Option<Pagination>,
     ) -> Vec<(OperationId, Brc20BridgeOpImpl)> {
-        get_runtime_state().borrow().operations.get_for_address(
-            &wallet_address,
-            min_included_id,
-            pagination,
-        )
+        let default_limit = self.config().borrow().get_operations_list_default_limit();
+        get_runtime_state()
+            .borrow()
+            .operations
+            .get_recent_for_address(
+                &wallet_address,
+                min_included_id,
+                pagination,
+                default_limit as usize,
+            )
     }
 
-    /// Returns log of an operation by its ID.
+    /// Returns log of an operation by its ID. Logs of the operations with erased user data
+    /// are not returned.
     #[query]
     pub fn get_operation_log(
         &self,
@@ -105,7 +138,113 @@ impl Brc20Bridge {
         get_runtime_state()
             .borrow()
             .operations
-            .get_log(operation_id)
+            .get_public_log(operation_id)
+    }
+
+    /// Returns the compact statuses of the operations in the order of `operation_ids`,
+    /// with `None` for the unknown operations. Up to 200 operations can be requested at once.
+    #[query]
+    pub fn get_operations_status_batch(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> BTFResult<Vec<(OperationId, Option<OperationStatusView>)>> {
+        get_runtime_state()
+            .borrow()
+            .operations
+            .get_status_batch(operation_ids)
+    }
+
+    /// Schedules the progress of the incomplete operations, matching the `filter`, immediately.
+    ///
+    /// Up to 100 operations are processed per call. If more operations match the filter,
+    /// the returned `continuation` should be passed to the next call. The call is recorded
+    /// in the operator audit log.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn bulk_retry(
+        &mut self,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+    ) -> BulkActionResult {
+        bridge_canister::inspect::inspect_bulk_action(self.config());
+        get_runtime().borrow().bulk_action(
+            BulkAction::Retry,
+            filter,
+            continuation,
+            ic::caller(),
+            ic::time(),
+        )
+    }
+
+    /// Cancels the incomplete operations, matching the `filter`, if the operations can be
+    /// cancelled at their current step. See `bulk_retry` for the per-call limit.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn bulk_cancel(
+        &mut self,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+    ) -> BulkActionResult {
+        bridge_canister::inspect::inspect_bulk_action(self.config());
+        get_runtime().borrow().bulk_action(
+            BulkAction::Cancel,
+            filter,
+            continuation,
+            ic::caller(),
+            ic::time(),
+        )
+    }
+
+    /// Lets the operations, matching the `filter` and deferred by the direction concurrency
+    /// limit, progress regardless of the limit. See `bulk_retry` for the per-call limit.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn bulk_approve(
+        &mut self,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+    ) -> BulkActionResult {
+        bridge_canister::inspect::inspect_bulk_action(self.config());
+        get_runtime().borrow().bulk_action(
+            BulkAction::Approve,
+            filter,
+            continuation,
+            ic::caller(),
+            ic::time(),
+        )
+    }
+
+    /// Returns the audit log of the operator bulk actions and notes, starting from the oldest
+    /// entries. At most [`MAX_AUDIT_ENTRIES_PER_REQUEST`] entries are returned.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_operator_audit_log(
+        &self,
+        pagination: Option<Pagination>,
+    ) -> Vec<OperatorAuditEntry> {
+        bridge_canister::inspect::inspect_get_operator_audit_log(self.config());
+        let pagination =
+            pagination.unwrap_or_else(|| Pagination::new(0, MAX_AUDIT_ENTRIES_PER_REQUEST));
+        get_runtime_state()
+            .borrow()
+            .operator_audit
+            .get(pagination.offset, pagination.count)
+    }
+
+    /// Adds the note to the operator audit log, e.g. the configuration changes of the canister
+    /// upgrade. Returns id of the audit log entry.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn add_operator_audit_note(&mut self, note: String) -> BTFResult<u64> {
+        bridge_canister::inspect::inspect_add_operator_audit_note(self.config());
+        get_runtime()
+            .borrow()
+            .add_operator_note(ic::caller(), note, ic::time())
     }
 
     /// Returns operation by memo
@@ -165,6 +304,260 @@ impl Brc20Bridge {
             .configure_indexers(indexer_urls);
     }
 
+    /// Sets the indexers consulted when the primary indexers repeatedly fail to reach
+    /// consensus. `None` disables the failover.
+    #[update]
+    pub fn admin_configure_fallback_indexers(&self, fallback_indexers: Option<FallbackIndexers>) {
+        inspect_is_owner(self.config());
+
+        get_brc20_state()
+            .borrow_mut()
+            .configure_fallback_indexers(fallback_indexers);
+    }
+
+    /// Returns the failover state of the indexer queries and the latest switchover alerts.
+    /// The state is reset on upgrade.
+    #[query]
+    pub fn get_indexer_failover_status(&self) -> IndexerFailoverStatus {
+        get_brc20_state().borrow().indexer_failover_status()
+    }
+
+    /// Returns current state of the operation nonces.
+    #[query]
+    pub fn get_nonce_state(&self) -> NonceState {
+        get_runtime_state().borrow().nonce_state()
+    }
+
+    /// Sets minimal id (and so nonce) of the operations created by the canister.
+    /// The operation id counter is never moved backwards.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_nonce_offset(&mut self, offset: u64) -> BTFResult<()> {
+        bridge_canister::inspect::inspect_set_nonce_offset(self.config());
+        get_runtime_state().borrow_mut().set_nonce_offset(offset);
+
+        log::info!("Bridge canister nonce offset changed to {offset}");
+        Ok(())
+    }
+
+    /// Returns the operation log bytes, as they are stored in stable memory, without decoding.
+    /// Intended to diagnose the operations which can't be decoded after an upgrade.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_operation_raw_bytes(&self, operation_id: OperationId) -> Option<Vec<u8>> {
+        bridge_canister::inspect::inspect_get_operation_raw_bytes(self.config());
+        get_runtime_state()
+            .borrow()
+            .operations
+            .get_raw_bytes(operation_id)
+    }
+
+    /// Replays the operation log steps through the operation transition graph and checks
+    /// that the result is consistent with the stored state of the operation. Returns the
+    /// description of the first inconsistency found.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn verify_operation_consistency(&self, operation_id: OperationId) -> Result<(), String> {
+        bridge_canister::inspect::inspect_verify_operation_consistency(self.config());
+        get_runtime_state()
+            .borrow()
+            .operations
+            .verify_consistency(operation_id)
+    }
+
+    /// Returns the captured progress invocations of the operation, oldest first.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_operation_replay(&self, operation_id: OperationId) -> Vec<ProgressReplayEntry> {
+        bridge_canister::inspect::inspect_get_operation_replay(self.config());
+        get_runtime_state()
+            .borrow()
+            .operation_replays
+            .get(operation_id)
+    }
+
+    /// Enables or disables the capture of the operation progress to the replay log,
+    /// regardless of the sampling. Already captured invocations are kept.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_operation_replay_capture(&mut self, operation_id: OperationId, enabled: bool) {
+        bridge_canister::inspect::inspect_set_operation_replay_capture(self.config());
+        get_runtime_state()
+            .borrow_mut()
+            .operation_replays
+            .set_capture(operation_id, enabled, ic::time());
+
+        log::info!("Replay capture of operation {operation_id} is set to {enabled}");
+    }
+
+    /// Returns the outbox messages, parked after too many failed delivery attempts, oldest first.
+    /// If `pagination` is `None`, only the oldest parked messages are returned, 1000 by default
+    /// (see `set_operations_list_default_limit`), so callers should paginate to get all of them.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_parked_outbox_messages(&self, pagination: Option<Pagination>) -> Vec<OutboxMessage> {
+        bridge_canister::inspect::inspect_get_parked_outbox_messages(self.config());
+        let default_limit = self.config().borrow().get_operations_list_default_limit();
+        let pagination = pagination.unwrap_or_else(|| Pagination::new(0, default_limit as usize));
+        get_runtime_state()
+            .borrow()
+            .operations
+            .outbox()
+            .get_parked(pagination.offset, pagination.count)
+    }
+
+    /// Moves the parked outbox message back to the delivery queue.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn requeue_outbox_message(&mut self, id: u64) -> BTFResult<()> {
+        bridge_canister::inspect::inspect_requeue_outbox_message(self.config());
+        get_runtime_state()
+            .borrow_mut()
+            .operations
+            .outbox_mut()
+            .requeue(id, ic::time())?;
+
+        log::info!("Parked outbox message {id} is requeued");
+        Ok(())
+    }
+
+    /// Removes the parked outbox message without delivery.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn drop_outbox_message(&mut self, id: u64) -> BTFResult<()> {
+        bridge_canister::inspect::inspect_drop_outbox_message(self.config());
+        get_runtime_state()
+            .borrow_mut()
+            .operations
+            .outbox_mut()
+            .drop_parked(id)?;
+
+        log::info!("Parked outbox message {id} is dropped");
+        Ok(())
+    }
+
+    /// Returns the burnt events, rejected by the strict event validation, oldest first.
+    /// Only the most recent 10000 rejected events are kept. If `pagination` is `None`,
+    /// only the oldest kept events are returned, 1000 by default
+    /// (see `set_operations_list_default_limit`), so callers should paginate to get all of them.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_dead_letter_events(&self, pagination: Option<Pagination>) -> Vec<DeadLetterEvent> {
+        bridge_canister::inspect::inspect_get_dead_letter_events(self.config());
+        let default_limit = self.config().borrow().get_operations_list_default_limit();
+        let pagination = pagination.unwrap_or_else(|| Pagination::new(0, default_limit as usize));
+        get_runtime_state()
+            .borrow()
+            .dead_letters
+            .get(pagination.offset, pagination.count)
+    }
+
+    /// Returns the blocks of the operations log in the ICRC-3 `get_blocks` format.
+    /// Every block records one state transition of an operation and contains the hash
+    /// of the previous block. At most 100 blocks are returned per request.
+    #[query]
+    pub fn get_operation_blocks(&self, start: Nat, length: Nat) -> GetBlocksResult {
+        get_runtime_state()
+            .borrow()
+            .operations
+            .blocks()
+            .get_blocks(&start, &length)
+    }
+
+    /// Returns up to `length` blocks of the operations log, starting from the `start` index,
+    /// in the format imported by the replica canisters. At most 100 blocks are returned
+    /// per request.
+    #[query]
+    pub fn export_operation_blocks(&self, start: u64, length: u64) -> OperationBlocksExport {
+        get_runtime_state()
+            .borrow()
+            .operations
+            .blocks()
+            .export(start, length)
+    }
+
+    /// Returns the sync status of the replica canister with its primary canister,
+    /// or `None` if the canister is the primary.
+    #[query]
+    pub fn get_replica_sync_status(&self) -> Option<ReplicaSyncStatus> {
+        get_runtime_state().borrow().replica_sync_status()
+    }
+
+    /// Promotes the replica canister to the primary mode, if the primary canister is halted
+    /// or doesn't respond. Operation blocks of the primary are synced before the promotion.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub async fn promote_to_primary(&mut self) -> BTFResult<()> {
+        bridge_canister::inspect::inspect_promote_to_primary(self.config());
+        BridgeRuntime::promote_to_primary(get_runtime()).await
+    }
+
+    /// Returns counters of the BTF bridge events, which were rejected before processing,
+    /// e.g. events of the previous BTF bridge contract. Counters are reset on upgrade.
+    #[query]
+    pub fn get_rejected_events_stats(&self) -> RejectedEventsStats {
+        get_runtime_state()
+            .borrow()
+            .processed_events
+            .rejected_stats()
+    }
+
+    /// Returns the sizes of the scheduler task payloads, moved to the task payloads store
+    /// because they exceed the inline size limit. Stats are updated on each scheduler run
+    /// and reset on upgrade.
+    #[query]
+    pub fn get_task_payload_stats(&self) -> TaskPayloadStats {
+        get_runtime_state().borrow().task_payloads.stats()
+    }
+
+    /// Returns the number of the operations per direction, deferred because the direction
+    /// concurrency limit is reached. Counters are reset on upgrade.
+    #[query]
+    pub fn get_deferred_operations_stats(&self) -> DeferredOperationsStats {
+        get_runtime_state().borrow().concurrency.deferred_stats()
+    }
+
+    /// Returns the BTF bridge events of the user with the given EVM address, which were
+    /// processed by the bridge, ordered by block. Events are kept for the same number of
+    /// blocks as the processed events deduplication index.
+    /// If `pagination` is `None`, all the stored events are returned.
+    #[query]
+    pub fn get_processed_events(
+        &self,
+        address: H160,
+        pagination: Option<Pagination>,
+    ) -> Vec<ProcessedEventInfo> {
+        get_runtime_state()
+            .borrow()
+            .processed_events
+            .get_for_sender(&address, pagination)
+    }
+
+    /// Returns the adjustments of the mint fees, paid by the given EVM address, to the actual
+    /// cost of the mint transactions, with the totals of the credited and debited amounts.
+    /// The number of the returned adjustments is capped, so a long history is read page by page.
+    #[query]
+    pub fn get_fee_adjustments(
+        &self,
+        fee_payer: H160,
+        pagination: Option<Pagination>,
+    ) -> FeePayerAdjustments {
+        get_runtime_state()
+            .borrow()
+            .fee_adjustments
+            .get_for_payer(&fee_payer, pagination)
+    }
+
     pub fn idl() -> Idl {
         generate_idl!()
     }
@@ -183,13 +576,29 @@ fn init_runtime() -> SharedRuntime {
     let config = state.borrow().config.clone();
 
     let refresh_params_service = RefreshEvmParamsService::new(config.clone());
+    let gas_balance_service = ServiceTimer::new(
+        MonitorGasBalanceService::new(config.clone()),
+        GAS_BALANCE_QUERY_DELAY,
+    );
+    let ping_evm_service = ServiceTimer::new(PingEvmService::new(config.clone()), PING_EVM_DELAY);
+    let outbox_service = ServiceTimer::new(
+        DispatchOutboxService::new(state.clone()),
+        OUTBOX_DISPATCH_DELAY,
+    );
+    let migrate_records_service = ServiceTimer::new(
+        MigrateRecordsService::new(state.clone(), runtime.borrow().scheduler().clone()),
+        MIGRATE_RECORDS_DELAY,
+    );
 
     let sign_orders_handler =
         Brc20MintOrderHandler::new(state.clone(), runtime.borrow().scheduler().clone());
     let sign_mint_orders_service = Rc::new(SignMintOrdersService::new(sign_orders_handler));
 
     let mint_tx_handler = Brc20MintTxHandler::new(state.clone());
-    let mint_tx_service = Rc::new(SendMintTxService::new(mint_tx_handler));
+    let mint_tx_service = Rc::new(SendMintTxService::new(
+        mint_tx_handler,
+        memory_by_id(PENDING_MINT_BATCHES_MEMORY_ID),
+    ));
 
     let btf_events_handler = Brc20BtfEventsHandler::new(get_brc20_state());
     let fetch_btf_events_service = Rc::new(FetchBtfBridgeEventsService::new(
@@ -219,6 +628,26 @@ fn init_runtime() -> SharedRuntime {
         SEND_MINT_TX_SERVICE_ID,
         mint_tx_service,
     );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        MONITOR_GAS_BALANCE_SERVICE_ID,
+        Rc::new(gas_balance_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        PING_EVM_SERVICE_ID,
+        Rc::new(ping_evm_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        DISPATCH_OUTBOX_SERVICE_ID,
+        Rc::new(outbox_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        MIGRATE_RECORDS_SERVICE_ID,
+        Rc::new(migrate_records_service),
+    );
 
     runtime
 }
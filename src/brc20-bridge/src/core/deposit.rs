@@ -128,11 +128,9 @@ impl Brc20Deposit<IcUtxoProvider, OrdIndexProvider<IcHttpClient>> {
     ) -> Result<Self, DepositError> {
         let state_ref = state.borrow();
 
-        let signing_strategy = get_runtime_state()
-            .borrow()
-            .config
-            .borrow()
-            .get_signing_strategy();
+        let runtime_config = get_runtime_state().borrow().config.clone();
+        let signing_strategy = runtime_config.borrow().get_signing_strategy();
+        let request_timeout = runtime_config.borrow().get_rpc_request_timeout();
 
         let network = state_ref.network();
         let ic_network = state_ref.ic_btc_network();
@@ -141,6 +139,8 @@ impl Brc20Deposit<IcUtxoProvider, OrdIndexProvider<IcHttpClient>> {
             .btc_signer(&signing_strategy)
             .ok_or(DepositError::SignerNotInitialized)?;
         let consensus_threshold = state_ref.indexer_consensus_threshold();
+        let fallback_indexers = state_ref.fallback_indexers();
+        let indexer_failover = state_ref.indexer_failover.clone();
 
         drop(state_ref);
 
@@ -151,9 +151,11 @@ impl Brc20Deposit<IcUtxoProvider, OrdIndexProvider<IcHttpClient>> {
             signer,
             utxo_provider: IcUtxoProvider::new(ic_network),
             index_provider: OrdIndexProvider::new(
-                IcHttpClient {},
+                IcHttpClient::new(request_timeout),
                 indexer_urls,
                 consensus_threshold,
+                fallback_indexers,
+                indexer_failover,
             ),
         })
     }
@@ -332,7 +334,7 @@ impl<UTXO: UtxoProvider, INDEX: Brc20IndexProvider> Brc20Deposit<UTXO, INDEX> {
             recipient: dst_address.clone(),
             dst_token: token_address.clone(),
             nonce,
-            sender_chain_id,
+            sender_chain_id: sender_chain_id.into(),
             recipient_chain_id,
             name: brc20_info.tick.name_array(),
             symbol: brc20_info.tick.symbol_array(),
@@ -340,6 +342,7 @@ impl<UTXO: UtxoProvider, INDEX: Brc20IndexProvider> Brc20Deposit<UTXO, INDEX> {
             approve_spender: Default::default(),
             approve_amount: Default::default(),
             fee_payer: H160::default(),
+            permit: None,
         }
     }
 
@@ -1,17 +1,26 @@
+mod failover;
 mod hiro;
 
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::future::Future;
+use std::rc::Rc;
 use std::str::FromStr;
+use std::time::Duration;
 
 use bitcoin::Address;
 use bridge_did::brc20_info::{Brc20Info, Brc20Tick};
+use bridge_did::indexer_failover::{FallbackIndexers, IndexerGroup};
+use bridge_utils::http_transform;
+use bridge_utils::timeout::with_timeout;
 use ic_exports::ic_cdk::api::management_canister::http_request::{
-    http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod,
+    http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, TransformContext,
 };
+use ic_exports::ic_kit::ic;
 use rust_decimal::Decimal;
 use serde::de::DeserializeOwned;
 
+pub use self::failover::IndexerFailover;
 use self::hiro::{GetBrc20BalancesResponse, GetBrc20TokensResponse};
 use crate::interface::DepositError;
 
@@ -30,6 +39,11 @@ const CYCLES_PER_HTTP_REQUEST: u128 = 500_000_000;
 const MAX_RESPONSE_BYTES: u64 = 10_000;
 const HIRO_MAX_LIMIT: u64 = 60;
 
+/// Failover key of the balances query.
+const BALANCES_QUERY: &str = "brc20_balances";
+/// Failover key of the tokens list query.
+const TOKENS_QUERY: &str = "brc20_tokens";
+
 /// Trait for a generic HTTP client that can be used to make requests to the indexer.
 pub trait HttpClient {
     fn http_request<R: DeserializeOwned>(
@@ -40,7 +54,16 @@ pub trait HttpClient {
 }
 
 /// HTTP client implementation for the Internet Computer canisters.
-pub struct IcHttpClient;
+pub struct IcHttpClient {
+    /// Requests not completed in the timeout fail with [`DepositError::Unavailable`].
+    timeout: Duration,
+}
+
+impl IcHttpClient {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
 
 impl HttpClient for IcHttpClient {
     async fn http_request<R: DeserializeOwned>(
@@ -61,13 +84,20 @@ impl HttpClient for IcHttpClient {
                 value: "application/json".to_string(),
             }],
             body: None,
-            transform: None,
+            transform: Some(TransformContext::from_name(
+                http_transform::TRANSFORM_HTTP_RESPONSE.to_string(),
+                vec![],
+            )),
         };
 
-        let result = http_request(request_params, CYCLES_PER_HTTP_REQUEST)
-            .await
-            .map_err(|err| DepositError::Unavailable(format!("Indexer unavailable: {err:?}")))?
-            .0;
+        let result = with_timeout(
+            http_request(request_params, CYCLES_PER_HTTP_REQUEST),
+            self.timeout,
+        )
+        .await
+        .map_err(|err| DepositError::Unavailable(format!("Indexer unavailable: {err}")))?
+        .map_err(|err| DepositError::Unavailable(format!("Indexer unavailable: {err:?}")))?
+        .0;
 
         log::trace!(
             "Indexer responded with: {} {:?} BODY: {}",
@@ -88,33 +118,86 @@ pub struct OrdIndexProvider<C: HttpClient> {
     client: C,
     indexer_urls: HashSet<String>,
     indexer_consensus_threshold: u8,
+    fallback_indexers: Option<FallbackIndexers>,
+    failover: Rc<RefCell<IndexerFailover>>,
 }
 
 impl<C> OrdIndexProvider<C>
 where
     C: HttpClient,
 {
-    pub fn new(client: C, indexer_urls: HashSet<String>, indexer_consensus_threshold: u8) -> Self {
+    pub fn new(
+        client: C,
+        indexer_urls: HashSet<String>,
+        indexer_consensus_threshold: u8,
+        fallback_indexers: Option<FallbackIndexers>,
+        failover: Rc<RefCell<IndexerFailover>>,
+    ) -> Self {
         Self {
             client,
             indexer_urls,
             indexer_consensus_threshold,
+            fallback_indexers,
+            failover,
+        }
+    }
+
+    /// Get consensus response from the indexers.
+    ///
+    /// The primary indexers are always asked first. If they repeatedly fail to reach consensus
+    /// for the `query`, the response of the fallback indexers is returned instead. Responses of
+    /// the two groups are never mixed.
+    async fn get_consensus_response<T>(&self, query: &str, uri: &str) -> Result<T, DepositError>
+    where
+        T: Clone + DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        let result = self
+            .get_group_consensus_response(&self.indexer_urls, self.indexer_consensus_threshold, uri)
+            .await;
+
+        let group = self.failover.borrow_mut().record_primary_result(
+            query,
+            result.is_ok(),
+            self.fallback_indexers
+                .as_ref()
+                .map(|fallback| fallback.failover_after_failures),
+            ic::time(),
+        );
+
+        match (result, group, &self.fallback_indexers) {
+            (Err(err), IndexerGroup::Fallback, Some(fallback)) => {
+                log::warn!(
+                    "Primary indexers failed to reach consensus for {query}: {err}. Asking fallback indexers."
+                );
+                self.get_group_consensus_response(
+                    &fallback.indexer_urls,
+                    fallback.consensus_threshold,
+                    uri,
+                )
+                .await
+            }
+            (result, _, _) => result,
         }
     }
 
-    /// Get consensus response from the indexer.
+    /// Get consensus response from the given group of indexers.
     ///
     /// All indexers must return the same response for the same input, other
     /// the function will return an error.
-    async fn get_consensus_response<T>(&self, uri: &str) -> Result<T, DepositError>
+    async fn get_group_consensus_response<T>(
+        &self,
+        indexer_urls: &HashSet<String>,
+        indexer_consensus_threshold: u8,
+        uri: &str,
+    ) -> Result<T, DepositError>
     where
         T: Clone + DeserializeOwned + PartialEq + std::fmt::Debug,
     {
-        let mut failed_urls = Vec::with_capacity(self.indexer_urls.len());
+        let mut failed_urls = Vec::with_capacity(indexer_urls.len());
         let mut responses: Vec<(String, T)> = Vec::new();
         let mut indexers_agree = true;
 
-        for url in &self.indexer_urls {
+        for url in indexer_urls {
             match self.client.http_request::<T>(url, uri).await {
                 Ok(response) => {
                     if !responses.is_empty() && responses[0].1 != response {
@@ -130,11 +213,11 @@ where
             }
         }
 
-        if responses.len() < self.indexer_consensus_threshold as usize {
+        if responses.len() < indexer_consensus_threshold as usize {
             Err(DepositError::InsufficientConsensus {
                 received_responses: responses.len(),
-                required_responses: self.indexer_consensus_threshold,
-                checked_indexers: self.indexer_urls.len(),
+                required_responses: indexer_consensus_threshold,
+                checked_indexers: indexer_urls.len(),
             })
         } else if !indexers_agree {
             // TODO: After https://infinityswap.atlassian.net/browse/EPROD-971 is done, return
@@ -168,7 +251,7 @@ where
                 "/ordinals/v1/brc-20/balances/{address}?offset={offset}&limit={HIRO_MAX_LIMIT}"
             );
             let response = self
-                .get_consensus_response::<GetBrc20BalancesResponse>(&uri)
+                .get_consensus_response::<GetBrc20BalancesResponse>(BALANCES_QUERY, &uri)
                 .await?;
 
             // update total
@@ -199,7 +282,7 @@ where
         while offset < total {
             let uri = format!("/ordinals/v1/brc-20/tokens?offset={offset}&limit={HIRO_MAX_LIMIT}");
             let response = self
-                .get_consensus_response::<GetBrc20TokensResponse>(&uri)
+                .get_consensus_response::<GetBrc20TokensResponse>(TOKENS_QUERY, &uri)
                 .await?;
 
             // update total
@@ -228,3 +311,225 @@ where
         Ok(tokens)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::indexer_failover::IndexerFailoverAlert;
+    use ic_exports::ic_kit::MockContext;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Client, which returns the configured response of each indexer or an error, if the
+    /// indexer has no response.
+    #[derive(Default)]
+    struct TestHttpClient {
+        responses: RefCell<HashMap<String, Value>>,
+        requested_urls: RefCell<Vec<String>>,
+    }
+
+    impl TestHttpClient {
+        fn set_response(&self, url: &str, response: Option<Value>) {
+            let mut responses = self.responses.borrow_mut();
+            match response {
+                Some(response) => responses.insert(url.to_string(), response),
+                None => responses.remove(url),
+            };
+        }
+    }
+
+    impl HttpClient for TestHttpClient {
+        async fn http_request<R: DeserializeOwned>(
+            &self,
+            url: &str,
+            _uri: &str,
+        ) -> Result<R, DepositError> {
+            self.requested_urls.borrow_mut().push(url.to_string());
+            let response = self
+                .responses
+                .borrow()
+                .get(url)
+                .cloned()
+                .ok_or_else(|| DepositError::Unavailable("indexer is down".to_string()))?;
+
+            Ok(serde_json::from_value(response).unwrap())
+        }
+    }
+
+    const PRIMARY: [&str; 2] = ["https://primary1.com", "https://primary2.com"];
+    const FALLBACK: [&str; 2] = ["https://fallback1.com", "https://fallback2.com"];
+
+    fn tokens(ticker: &str) -> Value {
+        json!({ "total": 1, "results": [{ "ticker": ticker, "decimals": 18 }] })
+    }
+
+    fn urls(urls: &[&str]) -> HashSet<String> {
+        urls.iter().map(|url| url.to_string()).collect()
+    }
+
+    fn provider(
+        fallback_indexers: Option<FallbackIndexers>,
+    ) -> (
+        OrdIndexProvider<TestHttpClient>,
+        Rc<RefCell<IndexerFailover>>,
+    ) {
+        MockContext::new().inject();
+        let failover = Rc::new(RefCell::new(IndexerFailover::default()));
+        let provider = OrdIndexProvider::new(
+            TestHttpClient::default(),
+            urls(&PRIMARY),
+            2,
+            fallback_indexers,
+            failover.clone(),
+        );
+
+        (provider, failover)
+    }
+
+    fn fallback_indexers() -> Option<FallbackIndexers> {
+        Some(FallbackIndexers {
+            indexer_urls: urls(&FALLBACK),
+            consensus_threshold: 2,
+            failover_after_failures: 2,
+        })
+    }
+
+    fn tick(ticker: &str) -> Brc20Tick {
+        Brc20Tick::from_str(ticker).unwrap()
+    }
+
+    fn alerts(failover: &RefCell<IndexerFailover>) -> Vec<IndexerFailoverAlert> {
+        failover
+            .borrow()
+            .status(None)
+            .alerts
+            .into_iter()
+            .map(|record| record.alert)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn primary_failure_without_fallback_is_returned() {
+        let (provider, failover) = provider(None);
+        provider
+            .client
+            .set_response(PRIMARY[0], Some(tokens("ordi")));
+        provider
+            .client
+            .set_response(PRIMARY[1], Some(tokens("sats")));
+
+        for _ in 0..5 {
+            let result = provider.get_brc20_tokens().await;
+            assert!(matches!(result, Err(DepositError::IndexersDisagree { .. })));
+        }
+
+        let status = failover.borrow().status(None);
+        assert_eq!(status.queries[0].active_group, IndexerGroup::Primary);
+        assert_eq!(status.queries[0].consecutive_failures, 5);
+        assert!(status.alerts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn fallback_answers_after_repeated_primary_failures() {
+        let (provider, failover) = provider(fallback_indexers());
+        provider
+            .client
+            .set_response(PRIMARY[0], Some(tokens("ordi")));
+        provider
+            .client
+            .set_response(FALLBACK[0], Some(tokens("sats")));
+        provider
+            .client
+            .set_response(FALLBACK[1], Some(tokens("sats")));
+
+        let result = provider.get_brc20_tokens().await;
+        assert!(matches!(
+            result,
+            Err(DepositError::InsufficientConsensus { .. })
+        ));
+        assert!(provider
+            .client
+            .requested_urls
+            .borrow()
+            .iter()
+            .all(|url| PRIMARY.contains(&url.as_str())));
+
+        let tokens = provider.get_brc20_tokens().await.unwrap();
+        assert!(tokens.contains_key(&tick("sats")));
+        assert_eq!(
+            alerts(&failover),
+            vec![IndexerFailoverAlert::SwitchedToFallback]
+        );
+        assert_eq!(
+            failover.borrow().status(None).queries[0].active_group,
+            IndexerGroup::Fallback
+        );
+    }
+
+    #[tokio::test]
+    async fn primary_is_used_again_after_recovery() {
+        let (provider, failover) = provider(fallback_indexers());
+        provider
+            .client
+            .set_response(FALLBACK[0], Some(tokens("sats")));
+        provider
+            .client
+            .set_response(FALLBACK[1], Some(tokens("sats")));
+
+        let _ = provider.get_brc20_tokens().await;
+        assert!(provider.get_brc20_tokens().await.is_ok());
+
+        provider
+            .client
+            .set_response(PRIMARY[0], Some(tokens("ordi")));
+        provider
+            .client
+            .set_response(PRIMARY[1], Some(tokens("ordi")));
+        provider.client.requested_urls.borrow_mut().clear();
+
+        let tokens = provider.get_brc20_tokens().await.unwrap();
+        assert!(tokens.contains_key(&tick("ordi")));
+        assert!(provider
+            .client
+            .requested_urls
+            .borrow()
+            .iter()
+            .all(|url| PRIMARY.contains(&url.as_str())));
+        assert_eq!(
+            alerts(&failover),
+            vec![
+                IndexerFailoverAlert::SwitchedToFallback,
+                IndexerFailoverAlert::SwitchedToPrimary,
+            ]
+        );
+
+        let status = failover.borrow().status(None);
+        assert_eq!(status.queries[0].active_group, IndexerGroup::Primary);
+        assert_eq!(status.queries[0].consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn indexers_of_different_groups_are_not_mixed() {
+        let (provider, _) = provider(fallback_indexers());
+        // Each group has a single available indexer, which is not enough for the consensus,
+        // even though the two indexers agree.
+        provider
+            .client
+            .set_response(PRIMARY[0], Some(tokens("ordi")));
+        provider
+            .client
+            .set_response(FALLBACK[0], Some(tokens("ordi")));
+
+        for _ in 0..3 {
+            let result = provider.get_brc20_tokens().await;
+            assert!(matches!(
+                result,
+                Err(DepositError::InsufficientConsensus {
+                    received_responses: 1,
+                    required_responses: 2,
+                    checked_indexers: 2,
+                })
+            ));
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use bridge_did::indexer_failover::{
+    FallbackIndexers, IndexerFailoverAlert, IndexerFailoverAlertRecord, IndexerFailoverStatus,
+    IndexerGroup, IndexerQueryStatus,
+};
+
+/// Number of the latest failover alerts to keep.
+pub const INDEXER_FAILOVER_ALERTS_SIZE: usize = 32;
+
+/// Tracks the consensus failures of the primary indexer group per query and decides which
+/// group must answer the query.
+///
+/// A query is switched to the fallback group after the configured number of consecutive
+/// consensus failures of the primary group, and is switched back as soon as the primary
+/// group reaches consensus again. The state is reset on upgrade.
+#[derive(Debug, Default)]
+pub struct IndexerFailover {
+    queries: BTreeMap<String, QueryFailover>,
+    alerts: VecDeque<IndexerFailoverAlertRecord>,
+}
+
+#[derive(Debug, Default)]
+struct QueryFailover {
+    active_group: IndexerGroup,
+    consecutive_failures: u32,
+}
+
+impl IndexerFailover {
+    /// Records whether the primary group reached consensus for the query and returns
+    /// the group, which must answer the query.
+    ///
+    /// `failover_after_failures` is `None` if no fallback group is configured.
+    pub fn record_primary_result(
+        &mut self,
+        query: &str,
+        consensus_reached: bool,
+        failover_after_failures: Option<u32>,
+        timestamp: u64,
+    ) -> IndexerGroup {
+        let state = self.queries.entry(query.to_string()).or_default();
+
+        let alert = if consensus_reached {
+            state.consecutive_failures = 0;
+            (state.active_group == IndexerGroup::Fallback)
+                .then_some(IndexerFailoverAlert::SwitchedToPrimary)
+        } else {
+            state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+            match failover_after_failures {
+                Some(failures)
+                    if state.consecutive_failures >= failures
+                        && state.active_group == IndexerGroup::Primary =>
+                {
+                    Some(IndexerFailoverAlert::SwitchedToFallback)
+                }
+                _ => None,
+            }
+        };
+
+        match alert {
+            Some(IndexerFailoverAlert::SwitchedToFallback) => {
+                state.active_group = IndexerGroup::Fallback
+            }
+            Some(IndexerFailoverAlert::SwitchedToPrimary) => {
+                state.active_group = IndexerGroup::Primary
+            }
+            None => {}
+        }
+        let active_group = state.active_group;
+
+        if let Some(alert) = alert {
+            log::warn!("Indexer failover alert for query {query}: {alert:?}");
+
+            if self.alerts.len() == INDEXER_FAILOVER_ALERTS_SIZE {
+                self.alerts.pop_front();
+            }
+            self.alerts.push_back(IndexerFailoverAlertRecord {
+                timestamp,
+                query: query.to_string(),
+                alert,
+            });
+        }
+
+        active_group
+    }
+
+    /// Switches all the queries back to the primary group and forgets their failures.
+    /// Alerts history is preserved.
+    pub fn reset(&mut self) {
+        self.queries.clear();
+    }
+
+    /// Returns diagnostic info of the failover.
+    pub fn status(&self, fallback_indexers: Option<FallbackIndexers>) -> IndexerFailoverStatus {
+        IndexerFailoverStatus {
+            fallback_indexers,
+            queries: self
+                .queries
+                .iter()
+                .map(|(query, state)| IndexerQueryStatus {
+                    query: query.clone(),
+                    active_group: state.active_group,
+                    consecutive_failures: state.consecutive_failures,
+                })
+                .collect(),
+            alerts: self.alerts.iter().cloned().collect(),
+        }
+    }
+}
@@ -65,11 +65,11 @@ pub fn new_withdraw_payload(
         )));
     };
 
-    let Some(token_id) = Id256::from_slice(&to_token) else {
-        return Err(WithdrawError::InvalidRequest(format!(
-            "Failed to decode token id from the value {to_token:?}"
-        )));
-    };
+    let token_id = Id256::try_from_slice(&to_token).map_err(|e| {
+        WithdrawError::InvalidRequest(format!(
+            "Failed to decode token id from the value {to_token:?}: {e}"
+        ))
+    })?;
 
     let brc20_tick = Brc20Tick::from(token_id);
 
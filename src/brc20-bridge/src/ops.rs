@@ -4,6 +4,8 @@ mod mint_order_handler;
 mod mint_tx_handler;
 mod withdraw;
 
+use std::mem;
+
 use bitcoin::Network;
 use bridge_canister::bridge::{Operation, OperationProgress};
 use bridge_canister::runtime::service::ServiceId;
@@ -12,12 +14,15 @@ use bridge_did::brc20_info::Brc20Tick;
 use bridge_did::error::{BTFResult, Error};
 use bridge_did::event_data::{MinterNotificationType, NotifyMinterEventData};
 use bridge_did::op_id::OperationId;
+use bridge_did::operation_log::OperationLog;
 use bridge_did::operations::{
-    Brc20BridgeDepositOp, Brc20BridgeOp, Brc20BridgeWithdrawOp, DepositRequest,
+    Brc20BridgeDepositOp, Brc20BridgeOp, Brc20BridgeOpV1, Brc20BridgeWithdrawOp, DepositRequest,
 };
 use bridge_did::order::MintOrder;
-use candid::{CandidType, Decode, Deserialize};
-use did::H160;
+use bridge_did::versioned::Versioned;
+use bridge_utils::event_payload::{self, PayloadDecodeError};
+use candid::{CandidType, Deserialize};
+use did::{H160, H256};
 use ic_task_scheduler::task::TaskOptions;
 use serde::Serialize;
 use withdraw::Brc20BridgeWithdrawOpImpl;
@@ -32,6 +37,10 @@ pub const REFRESH_PARAMS_SERVICE_ID: ServiceId = 0;
 pub const FETCH_BTF_EVENTS_SERVICE_ID: ServiceId = 1;
 pub const SIGN_MINT_ORDER_SERVICE_ID: ServiceId = 2;
 pub const SEND_MINT_TX_SERVICE_ID: ServiceId = 3;
+pub const MONITOR_GAS_BALANCE_SERVICE_ID: ServiceId = 4;
+pub const PING_EVM_SERVICE_ID: ServiceId = 5;
+pub const DISPATCH_OUTBOX_SERVICE_ID: ServiceId = 6;
+pub const MIGRATE_RECORDS_SERVICE_ID: ServiceId = 7;
 
 /// BRC20 bridge operations
 #[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
@@ -178,6 +187,29 @@ impl Operation for Brc20BridgeOpImpl {
         }
     }
 
+    /// Operations stored before the chain ids of the mint orders were extended to 64 bits are
+    /// decoded with the legacy shape. An operation, which sends or confirms the order signed in
+    /// the legacy layout, is moved back to signing of the same order.
+    fn decode_legacy_log(bytes: &[u8]) -> Option<OperationLog<Self>> {
+        let log = OperationLog::<Brc20BridgeOpV1>::from_versioned_bytes(bytes).ok()?;
+        let mut log = log.map_payload(|op, _| Self(op.into()));
+
+        let legacy_order = match &log.current_step().0 {
+            Brc20BridgeOp::Deposit(
+                Brc20BridgeDepositOp::SendMintOrder(orders)
+                | Brc20BridgeDepositOp::ConfirmMintOrder { orders, .. },
+            ) => orders.legacy_order(),
+            _ => None,
+        };
+        if let Some(order) = legacy_order {
+            log.add_step(Ok(Self(Brc20BridgeOp::Deposit(
+                Brc20BridgeDepositOp::SignMintOrder(order),
+            ))));
+        }
+
+        Some(log)
+    }
+
     fn is_complete(&self) -> bool {
         match self.0 {
             Brc20BridgeOp::Deposit(Brc20BridgeDepositOp::AwaitInputs { .. }) => false,
@@ -242,6 +274,71 @@ impl Operation for Brc20BridgeOpImpl {
             }) => from_address.clone(),
         }
     }
+
+    fn evm_tx_hash(&self) -> Option<H256> {
+        match &self.0 {
+            Brc20BridgeOp::Deposit(Brc20BridgeDepositOp::ConfirmMintOrder { tx_id, .. }) => {
+                Some(tx_id.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Operation can stay in its state with updated data, or move along its deposit
+    /// or withdrawal flow.
+    fn can_transition(from: &Self, to: &Self) -> bool {
+        match (&from.0, &to.0) {
+            (Brc20BridgeOp::Deposit(from), Brc20BridgeOp::Deposit(to)) => {
+                mem::discriminant(from) == mem::discriminant(to)
+                    || matches!(
+                        (from, to),
+                        (
+                            Brc20BridgeDepositOp::AwaitInputs(_),
+                            Brc20BridgeDepositOp::AwaitConfirmations { .. }
+                        ) | (
+                            Brc20BridgeDepositOp::AwaitConfirmations { .. },
+                            Brc20BridgeDepositOp::SignMintOrder(_)
+                        ) | (
+                            Brc20BridgeDepositOp::SignMintOrder(_),
+                            Brc20BridgeDepositOp::SendMintOrder(_)
+                        ) | (
+                            Brc20BridgeDepositOp::SendMintOrder(_),
+                            Brc20BridgeDepositOp::ConfirmMintOrder { .. }
+                                | Brc20BridgeDepositOp::MintOrderConfirmed { .. }
+                        ) | (
+                            Brc20BridgeDepositOp::ConfirmMintOrder { .. },
+                            Brc20BridgeDepositOp::MintOrderConfirmed { .. }
+                        )
+                    )
+            }
+            (Brc20BridgeOp::Withdraw(from), Brc20BridgeOp::Withdraw(to)) => {
+                mem::discriminant(from) == mem::discriminant(to)
+                    || matches!(
+                        (from, to),
+                        (
+                            Brc20BridgeWithdrawOp::CreateInscriptionTxs(_),
+                            Brc20BridgeWithdrawOp::SendCommitTx { .. }
+                        ) | (
+                            Brc20BridgeWithdrawOp::SendCommitTx { .. },
+                            Brc20BridgeWithdrawOp::SendRevealTx { .. }
+                        ) | (
+                            Brc20BridgeWithdrawOp::SendRevealTx { .. },
+                            Brc20BridgeWithdrawOp::AwaitInscriptionTxs { .. }
+                        ) | (
+                            Brc20BridgeWithdrawOp::AwaitInscriptionTxs { .. },
+                            Brc20BridgeWithdrawOp::CreateTransferTx { .. }
+                        ) | (
+                            Brc20BridgeWithdrawOp::CreateTransferTx { .. },
+                            Brc20BridgeWithdrawOp::SendTransferTx { .. }
+                        ) | (
+                            Brc20BridgeWithdrawOp::SendTransferTx { .. },
+                            Brc20BridgeWithdrawOp::TransferTxSent { .. }
+                        )
+                    )
+            }
+            _ => false,
+        }
+    }
 }
 
 pub enum Brc20MinterNotification {
@@ -257,20 +354,16 @@ pub struct Brc20DepositRequestData {
 }
 
 impl Brc20MinterNotification {
-    fn decode(event_data: NotifyMinterEventData) -> Option<Self> {
+    /// Decodes the notification. Returns `None` for the unsupported notification types.
+    fn decode(event_data: &NotifyMinterEventData) -> Result<Option<Self>, PayloadDecodeError> {
         match event_data.notification_type {
-            MinterNotificationType::DepositRequest => {
-                match Decode!(&event_data.user_data, Brc20DepositRequestData) {
-                    Ok(payload) => Some(Brc20MinterNotification::Deposit(payload)),
-                    Err(err) => {
-                        log::warn!("Failed to decode deposit request event data: {err:?}");
-                        None
-                    }
-                }
-            }
+            MinterNotificationType::DepositRequest => event_payload::decode_candid_payload::<
+                Brc20DepositRequestData,
+            >(&event_data.user_data)
+            .map(|payload| Some(Brc20MinterNotification::Deposit(payload))),
             t => {
                 log::warn!("Unknown minter notify event type: {t}");
-                None
+                Ok(None)
             }
         }
     }
@@ -67,9 +67,16 @@ impl BtfBridgeEventHandler<Brc20BridgeOpImpl> for Brc20BtfEventsHandler {
         log::debug!("on_minter_notification {event:?}");
 
         let memo = event.memo();
-        let Some(notification) = Brc20MinterNotification::decode(event.clone()) else {
-            log::warn!("Invalid minter notification: {event:?}");
-            return None;
+        let notification = match Brc20MinterNotification::decode(&event) {
+            Ok(Some(notification)) => notification,
+            Ok(None) => return None,
+            Err(e) => {
+                log::warn!("Failed to decode deposit request event data: {e}");
+                return Some(OperationAction::reject(
+                    event,
+                    format!("failed to decode deposit request: {e}"),
+                ));
+            }
         };
 
         match notification {
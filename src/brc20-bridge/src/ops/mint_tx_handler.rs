@@ -45,7 +45,7 @@ impl MintTxHandler for Brc20MintTxHandler {
         Some(order)
     }
 
-    fn mint_tx_sent(&self, id: OperationId, tx_hash: H256) {
+    fn mint_tx_sent(&self, id: OperationId, tx_hash: H256, evm_params_snapshot: Option<u64>) {
         log::debug!("Mint transaction sent: {tx_hash}; op_id: {id}");
         let op = self.state.borrow().operations.get(id);
         let Some(Brc20BridgeOp::Deposit(Brc20BridgeDepositOp::SendMintOrder(orders))) =
@@ -57,7 +57,7 @@ impl MintTxHandler for Brc20MintTxHandler {
             return;
         };
 
-        self.state.borrow_mut().operations.update(
+        self.state.borrow_mut().operations.update_with_evm_params(
             id,
             Brc20BridgeOpImpl(Brc20BridgeOp::Deposit(
                 Brc20BridgeDepositOp::ConfirmMintOrder {
@@ -65,6 +65,7 @@ impl MintTxHandler for Brc20MintTxHandler {
                     tx_id: tx_hash,
                 },
             )),
+            evm_params_snapshot,
         )
     }
 }
@@ -2,13 +2,16 @@ mod config;
 mod master_key;
 
 use core::panic;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 use std::time::Duration;
 
 use bitcoin::bip32::ChainCode;
 use bitcoin::{FeeRate, Network, PrivateKey, PublicKey};
 use bridge_canister::memory::MEMORY_MANAGER;
 use bridge_did::brc20_info::{Brc20Info, Brc20Tick};
+use bridge_did::indexer_failover::{FallbackIndexers, IndexerFailoverStatus};
 use bridge_did::init::brc20::Brc20BridgeConfig;
 use bridge_did::schnorr::{SchnorrAlgorithm, SchnorrKeyId};
 use eth_signer::sign_strategy::SigningStrategy;
@@ -25,6 +28,7 @@ use ord_rs::Wallet;
 use self::config::Brc20BridgeConfigStorage;
 pub use self::master_key::MasterKey;
 use self::master_key::MasterKeyStorage;
+use crate::core::index_provider::IndexerFailover;
 use crate::key::{BtcSignerType, IcBtcSigner};
 use crate::ledger::UtxoLedger;
 use crate::{MAINNET_CHAIN_ID, REGTEST_CHAIN_ID, TESTNET_CHAIN_ID};
@@ -36,6 +40,7 @@ pub struct Brc20State {
     pub(crate) brc20_tokens: HashMap<Brc20Tick, Brc20Info>,
     pub(crate) config: Brc20BridgeConfigStorage<VirtualMemory<DefaultMemoryImpl>>,
     pub(crate) fee_rate_state: FeeRateState,
+    pub(crate) indexer_failover: Rc<RefCell<IndexerFailover>>,
     pub(crate) ledger: UtxoLedger<VirtualMemory<DefaultMemoryImpl>>,
     pub(crate) master_key: MasterKeyStorage<VirtualMemory<DefaultMemoryImpl>>,
 }
@@ -48,6 +53,7 @@ impl Default for Brc20State {
             master_key: MasterKeyStorage::new(memory_manager),
             ledger: UtxoLedger::new(memory_manager),
             fee_rate_state: FeeRateState::default(),
+            indexer_failover: Rc::default(),
         })
     }
 }
@@ -185,6 +191,13 @@ impl Brc20State {
             .iter()
             .map(|url| url.strip_suffix('/').unwrap_or(url).to_owned())
             .collect();
+        if let Some(fallback) = &mut config.fallback_indexers {
+            fallback.indexer_urls = fallback
+                .indexer_urls
+                .iter()
+                .map(|url| url.strip_suffix('/').unwrap_or(url).to_owned())
+                .collect();
+        }
 
         self.config.set(config);
     }
@@ -231,6 +244,39 @@ impl Brc20State {
         });
     }
 
+    /// Indexers consulted when the primary indexers repeatedly fail to reach consensus.
+    pub fn fallback_indexers(&self) -> Option<FallbackIndexers> {
+        self.config.get().fallback_indexers.clone()
+    }
+
+    /// Sets the fallback indexers and switches all the indexer queries back to the primary
+    /// indexers. Panics in case the fallback indexers are invalid.
+    pub fn configure_fallback_indexers(&mut self, mut fallback_indexers: Option<FallbackIndexers>) {
+        if let Some(fallback) = &mut fallback_indexers {
+            fallback.indexer_urls = fallback
+                .indexer_urls
+                .iter()
+                .map(|url| url.strip_suffix('/').unwrap_or(url).to_owned())
+                .collect();
+        }
+
+        let mut config = self.config.get().clone();
+        config.fallback_indexers = fallback_indexers;
+        if let Err(err) = config.validate() {
+            panic!("Invalid fallback indexers: {err}");
+        }
+
+        self.config.set(config);
+        self.indexer_failover.borrow_mut().reset();
+    }
+
+    /// Returns diagnostic info of the failover to the fallback indexers.
+    pub fn indexer_failover_status(&self) -> IndexerFailoverStatus {
+        self.indexer_failover
+            .borrow()
+            .status(self.fallback_indexers())
+    }
+
     pub fn mempool_timeout(&self) -> Duration {
         self.config.get().mempool_timeout
     }
@@ -366,6 +412,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_configure_fallback_indexers() {
+        MockContext::new().inject();
+        let mut state = Brc20State::default();
+        state.configure(Brc20BridgeConfig {
+            indexer_urls: HashSet::from(["https://indexer1.com".to_string()]),
+            ..Default::default()
+        });
+        state
+            .indexer_failover
+            .borrow_mut()
+            .record_primary_result("query", false, Some(1), 0);
+
+        let fallback = FallbackIndexers {
+            indexer_urls: HashSet::from(["https://fallback1.com/".to_string()]),
+            consensus_threshold: 1,
+            failover_after_failures: 3,
+        };
+        state.configure_fallback_indexers(Some(fallback.clone()));
+
+        let status = state.indexer_failover_status();
+        assert_eq!(
+            status.fallback_indexers.unwrap().indexer_urls,
+            HashSet::from(["https://fallback1.com".to_string()])
+        );
+        assert!(status.queries.is_empty());
+        assert_eq!(status.alerts.len(), 1);
+
+        state.configure_fallback_indexers(None);
+        assert_eq!(state.fallback_indexers(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid fallback indexers")]
+    fn test_configure_fallback_indexers_with_primary_indexer() {
+        MockContext::new().inject();
+        let mut state = Brc20State::default();
+        state.configure(Brc20BridgeConfig {
+            indexer_urls: HashSet::from(["https://indexer1.com".to_string()]),
+            ..Default::default()
+        });
+
+        state.configure_fallback_indexers(Some(FallbackIndexers {
+            indexer_urls: HashSet::from(["https://indexer1.com".to_string()]),
+            consensus_threshold: 1,
+            failover_after_failures: 3,
+        }));
+    }
+
     #[test]
     #[should_panic(expected = "number of indexers must be at least")]
     fn test_configure_indexers_too_few_indexers() {
@@ -1,20 +1,30 @@
 #![allow(async_fn_in_trait)]
 
+use std::time::Duration;
+
+use bridge_did::concurrency::OperationDirection;
+use bridge_did::dead_letter::DeadLetterEventData;
 use bridge_did::error::{BTFResult, Error};
+use bridge_did::event_filter::EventTopicFilter;
+use bridge_did::evm_finality::EvmFinality;
 use bridge_did::evm_link::EvmLink;
+use bridge_did::id256::Id256;
 use bridge_did::op_id::OperationId;
-use bridge_did::operation_log::Memo;
-use bridge_utils::btf_events::BridgeEvent;
-use bridge_utils::evm_bridge::EvmParams;
+use bridge_did::operation_log::{Memo, OperationLog};
+use bridge_did::retry_profile::RetryProfileKind;
+use bridge_utils::btf_events::{BridgeEvent, BridgeEventLog};
+use bridge_utils::evm_bridge::{self, EvmParams};
 use bridge_utils::evm_link::EvmLinkClient;
-use candid::CandidType;
-use did::H160;
+use candid::{CandidType, Principal};
+use did::{H160, H256};
 use eth_signer::sign_strategy::TransactionSigner;
+use ic_storage::IcStorage;
 use ic_task_scheduler::task::TaskOptions;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 use crate::runtime::service::ServiceId;
+use crate::runtime::state::config::ConfigStorage;
 use crate::runtime::RuntimeState;
 
 /// Defines an operation that can be executed by the bridge.
@@ -35,8 +45,104 @@ pub trait Operation:
     fn evm_wallet_address(&self) -> H160;
 
     /// Describes how the operation execution should be scheduled.
+    ///
+    /// By default, the operation is scheduled with the EVM call retry profile.
     fn scheduling_options(&self) -> Option<TaskOptions> {
-        Some(TaskOptions::default())
+        Some(
+            ConfigStorage::get()
+                .borrow()
+                .get_task_options(RetryProfileKind::EvmCall),
+        )
+    }
+
+    /// Id of the operation which should release its dependents before this operation
+    /// is allowed to progress.
+    fn dependency(&self) -> Option<OperationId> {
+        None
+    }
+
+    /// Check if the operations depending on this one are allowed to progress.
+    ///
+    /// By default, dependents are released when the operation is complete.
+    fn releases_dependents(&self) -> bool {
+        self.is_complete()
+    }
+
+    /// Returns the parked state of the operation, which gave up waiting for its dependency
+    /// after the dependency wait retry profile. Parked operations are not progressed until
+    /// the operator retries them.
+    ///
+    /// By default, operations are not parked, and keep waiting while their task is retried.
+    fn park(&self) -> Option<Self> {
+        None
+    }
+
+    /// Returns the state of the parked operation, retried by the operator, or `None` if
+    /// the operation is not parked.
+    fn unpark(&self) -> Option<Self> {
+        None
+    }
+
+    /// Direction of the operation, which progress calls the downstream canisters.
+    /// Progress of such operations is limited by the direction concurrency limit.
+    ///
+    /// By default, operations are not limited.
+    fn direction(&self) -> Option<OperationDirection> {
+        None
+    }
+
+    /// Priority of the operation progress within its direction. When the direction
+    /// concurrency limit is reached, operations with higher priority are progressed first.
+    ///
+    /// By default, all operations have the same zero priority.
+    fn priority(&self) -> u8 {
+        0
+    }
+
+    /// Hash of the EVM transaction, sent for the operation in its current state.
+    ///
+    /// By default, operations have no EVM transactions.
+    fn evm_tx_hash(&self) -> Option<H256> {
+        None
+    }
+
+    /// Token of the operation, used to select the operations for the operator bulk actions.
+    ///
+    /// By default, the token is unknown.
+    fn token(&self) -> Option<Id256> {
+        None
+    }
+
+    /// IC principal of the user, linked to the operation in its current state, e.g. the sender
+    /// of a deposit. Used to find the operations of the principal, which user data is erased.
+    ///
+    /// By default, operations are not linked to principals.
+    fn principal(&self) -> Option<Principal> {
+        None
+    }
+
+    /// Checks if the operation is allowed to move from the `from` state to the `to` state.
+    /// Updates with illegal transitions are rejected by the operation store.
+    ///
+    /// By default, all transitions are allowed.
+    fn can_transition(_from: &Self, _to: &Self) -> bool {
+        true
+    }
+
+    /// Returns the state of the operation `id`, cancelled by the operator, or `None`
+    /// if the operation can't be cancelled at its current step.
+    ///
+    /// By default, operations can't be cancelled.
+    fn cancel(&self, _id: OperationId) -> Option<Self> {
+        None
+    }
+
+    /// Decodes the stored operation log, which can't be decoded with the current shape of the
+    /// operation, as a log of a legacy shape, and converts it to the current one.
+    ///
+    /// By default, operations have no legacy shapes.
+    fn decode_legacy_log(_bytes: &[u8]) -> Option<OperationLog<Self>> {
+        None
     }
 }
 
@@ -45,6 +151,9 @@ pub trait OperationContext {
     /// Get link to the EVM with wrapped tokens.
     fn get_evm_link(&self) -> EvmLink;
 
+    /// Get timeout of the EVM requests.
+    fn get_rpc_request_timeout(&self) -> Duration;
+
     /// Get address of the Btfbridge contract.
     fn get_bridge_contract_address(&self) -> BTFResult<H160>;
 
@@ -54,28 +163,37 @@ pub trait OperationContext {
     /// Get signer for transactions, orders, etc...
     fn get_signer(&self) -> BTFResult<impl TransactionSigner>;
 
+    /// Get finality of the EVM blocks to collect events from.
+    fn get_evm_finality(&self) -> EvmFinality;
+
+    /// Get filter of the collected events by an indexed parameter topic.
+    fn get_event_topic_filter(&self) -> Option<EventTopicFilter>;
+
     async fn collect_evm_events(&self, max_logs_number: u64) -> BTFResult<CollectedEvents> {
         log::trace!("collecting evm events");
 
         let link = self.get_evm_link();
-        let client = link.get_json_rpc_client();
+        let client = link.get_json_rpc_client_with_timeout(self.get_rpc_request_timeout());
         let evm_params = self.get_evm_params()?;
         let bridge_contract = self.get_bridge_contract_address()?;
 
-        let last_chain_block = match client.get_block_number().await {
+        let finality = self.get_evm_finality();
+        let last_chain_block = match evm_bridge::query_final_block_number(&client, finality).await {
             Ok(block) => block,
             Err(e) => {
-                log::warn!("failed to get evm block number: {e}");
+                log::warn!("failed to get `{finality}` evm block number: {e}");
                 return Err(Error::EvmRequestFailed(e.to_string()));
             }
         };
         let last_request_block = last_chain_block.min(evm_params.next_block + max_logs_number);
 
+        let topic_filter = self.get_event_topic_filter();
         let events = BridgeEvent::collect(
             &client,
             evm_params.next_block,
             last_request_block,
             bridge_contract.0,
+            topic_filter.as_ref(),
         )
         .await?;
 
@@ -95,6 +213,12 @@ pub trait OperationContext {
 pub enum OperationProgress<Op> {
     Progress(Op),
     AddToService(ServiceId),
+    /// Move the operation to the next stage and create a new operation,
+    /// which usually depends on this one.
+    ProgressWithDependent {
+        update_to: Op,
+        dependent: Op,
+    },
 }
 
 /// Action to create or update an operation.
@@ -102,11 +226,41 @@ pub enum OperationProgress<Op> {
 pub enum OperationAction<Op> {
     Create(Op, Option<Memo>),
     CreateWithId(OperationId, Op, Option<Memo>),
-    Update { nonce: u32, update_to: Op },
+    Update {
+        nonce: u32,
+        update_to: Op,
+    },
+    /// The event can't be processed, so it is moved to the dead-letter list.
+    Reject {
+        event: DeadLetterEventData,
+        reason: String,
+    },
+}
+
+impl<Op> OperationAction<Op> {
+    /// Creates the action to move the `event` to the dead-letter list.
+    pub fn reject(event: impl Into<DeadLetterEventData>, reason: impl ToString) -> Self {
+        Self::Reject {
+            event: event.into(),
+            reason: reason.to_string(),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct CollectedEvents {
-    pub events: Vec<BridgeEvent>,
+    /// Events in the order they were emitted.
+    pub events: Vec<BridgeEventLog>,
+    /// Last block of the requested range. All the events up to this block are collected.
     pub last_block_number: u64,
 }
+
+impl CollectedEvents {
+    /// Returns the block to collect the next events from.
+    ///
+    /// The cursor is advanced by the requested range, which is independent of the order
+    /// and the block numbers of the returned logs, so no block of the range is skipped.
+    pub fn next_block(&self) -> u64 {
+        self.last_block_number + 1
+    }
+}
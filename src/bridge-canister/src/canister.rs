@@ -2,23 +2,44 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::time::Duration;
 
+use bridge_did::bridge_mode::BridgeMode;
+use bridge_did::concurrency::OperationConcurrencyLimits;
 use bridge_did::error::{BTFResult, Error};
-use bridge_did::evm_link::EvmLink;
+use bridge_did::event_filter::EventTopicFilter;
+use bridge_did::evm_latency::LatencyStats;
+use bridge_did::evm_link::{EvmLink, PrivateTxRelay};
+use bridge_did::evm_params_history::EvmParamsSnapshot;
+use bridge_did::fee_adjustment::FeeReconciliationSettings;
+use bridge_did::gas_balance::{GasBalanceStatus, GasBalanceThresholds};
+use bridge_did::halt::BridgeHalt;
+use bridge_did::http_transform::HttpTransformSettings;
 use bridge_did::init::BridgeInitData;
+use bridge_did::manifest::BridgeManifest;
+use bridge_did::nonce_drift::{NonceDriftStatus, NonceGap};
+use bridge_did::op_id::OperationId;
+use bridge_did::operation_replay::ReplayCaptureSettings;
+use bridge_did::outbox::OutboxDestination;
+use bridge_did::pending_mint_batch::PendingMintBatchesStats;
+use bridge_did::retry_profile::RetryProfiles;
+use bridge_utils::common::Pagination;
+use bridge_utils::http_transform;
 use candid::Principal;
 use did::H160;
-use eth_signer::sign_strategy::TransactionSigner;
 use ic_canister::{
     generate_exports, generate_idl, query, state_getter, update, Canister, Idl, PreUpdate,
 };
+use ic_exports::ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
 use ic_exports::ic_kit::ic;
 use ic_log::canister::{LogCanister, LogState};
+use ic_log::writer::Log;
 use ic_storage::IcStorage;
 use log::{debug, info};
 
-use crate::inspect;
-use crate::memory::{memory_by_id, LOG_SETTINGS_MEMORY_ID};
+use crate::memory::{memory_by_id, LOG_SETTINGS_MEMORY_ID, PENDING_MINT_BATCHES_MEMORY_ID};
+use crate::pending_mint_batches::PendingMintBatchStore;
+use crate::runtime::service::mint_tx::DEFAULT_MAX_PENDING_BATCH_AGE;
 use crate::runtime::state::config::ConfigStorage;
+use crate::{inspect, log_span};
 
 /// Common API of all bridge canisters.
 pub trait BridgeCanister: Canister + LogCanister {
@@ -46,6 +67,14 @@ pub trait BridgeCanister: Canister + LogCanister {
         info!("Bridge canister owner changed to {owner}");
     }
 
+    /// Returns the user-relevant configuration of the bridge in a single response.
+    /// The `manifest_version` of the response is increased every time any of the
+    /// included settings is changed.
+    #[query(trait = true)]
+    fn get_bridge_manifest(&self) -> BridgeManifest {
+        self.config().borrow().get_bridge_manifest()
+    }
+
     /// Returns principal of EVM canister with which the bridge canister works.
     #[query(trait = true)]
     fn get_evm_principal(&self) -> Principal {
@@ -73,14 +102,481 @@ pub trait BridgeCanister: Canister + LogCanister {
         info!("Bridge canister BTF bridge contract address changed to {address}");
     }
 
+    /// Returns balance monitoring status of the bridge canister EVM address.
+    #[query(trait = true)]
+    fn get_gas_balance_status(&self) -> GasBalanceStatus {
+        self.config().borrow().get_gas_balance_status()
+    }
+
+    /// Sets balance thresholds of the bridge canister EVM address.
+    /// If `None`, the balance is not monitored and mint transactions sponsorship is always enabled.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_gas_balance_thresholds(&mut self, thresholds: Option<GasBalanceThresholds>) {
+        inspect::inspect_set_gas_balance_thresholds(self.config());
+        inspect::inspect_gas_balance_thresholds_are_valid(thresholds.as_ref());
+        self.config()
+            .borrow_mut()
+            .set_gas_balance_thresholds(thresholds.clone());
+
+        info!("Bridge canister gas balance thresholds changed to {thresholds:?}");
+    }
+
+    /// Enables or disables compression of the operation logs in the stable memory.
+    /// The setting is applied to the logs written after the next operations run,
+    /// already stored logs are not changed.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_operation_log_compression(&mut self, enabled: bool) {
+        inspect::inspect_set_operation_log_compression(self.config());
+        self.config()
+            .borrow_mut()
+            .set_operation_log_compression(enabled);
+
+        info!("Bridge canister operation log compression enabled: {enabled}");
+    }
+
+    /// Enables or disables the strict validation of the BTF bridge events. In the strict mode,
+    /// burnt events with malformed token metadata are moved to the dead-letter list instead of
+    /// being processed with the normalized metadata.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_strict_event_validation(&mut self, enabled: bool) {
+        inspect::inspect_set_strict_event_validation(self.config());
+        self.config()
+            .borrow_mut()
+            .set_strict_event_validation(enabled);
+
+        info!("Bridge canister strict event validation enabled: {enabled}");
+    }
+
+    /// Suspends or resumes the timer-driven processing of the bridge: events collection,
+    /// services and operations progress. While the timers are disabled, deposits are still
+    /// accepted, but they don't progress until the timers are enabled again.
+    ///
+    /// The setting is persisted across upgrades. It doesn't change the bridge halt and mode.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_timers_enabled(&mut self, enabled: bool) {
+        inspect::inspect_set_timers_enabled(self.config());
+        self.config().borrow_mut().set_timers_enabled(enabled);
+
+        info!("Bridge canister timers enabled: {enabled}");
+    }
+
+    /// Checks if the timer-driven processing of the bridge is enabled.
+    #[query(trait = true)]
+    fn timers_enabled(&self) -> bool {
+        self.config().borrow().is_timers_enabled()
+    }
+
+    /// Returns snapshots of the EVM params, newest first. A snapshot is taken every time
+    /// the params are changed. Operation log entries of the sent mint transactions contain
+    /// the index of the snapshot used for the transaction.
+    ///
+    /// If `pagination` is `None`, all the stored snapshots are returned.
+    ///
+    /// This method is only for canister owner.
+    #[query(trait = true)]
+    fn get_evm_params_history(&self, pagination: Option<Pagination>) -> Vec<EvmParamsSnapshot> {
+        inspect::inspect_get_evm_params_history(self.config());
+        let config = self.config();
+        let config = config.borrow();
+        let pagination = pagination
+            .unwrap_or_else(|| Pagination::new(0, config.get_evm_params_history_size() as _));
+        config.get_evm_params_history(pagination.offset, pagination.count)
+    }
+
+    /// Sets max number of the EVM params snapshots to keep. The oldest snapshots are evicted.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_evm_params_history_size(&mut self, size: u32) {
+        inspect::inspect_set_evm_params_history_size(self.config());
+        self.config().borrow_mut().set_evm_params_history_size(size);
+
+        info!("Bridge canister EVM params history size changed to {size}");
+    }
+
+    /// Returns the destinations to be notified when an operation is complete.
+    ///
+    /// This method is only for canister owner.
+    #[query(trait = true)]
+    fn get_notification_destinations(&self) -> Vec<OutboxDestination> {
+        inspect::inspect_get_notification_destinations(self.config());
+        self.config().borrow().get_notification_destinations()
+    }
+
+    /// Sets the destinations to be notified when an operation is complete. The notifications
+    /// are added to the outbox together with the operation update and are delivered with retries.
+    /// The messages already added to the outbox are not changed.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_notification_destinations(&mut self, destinations: Vec<OutboxDestination>) {
+        inspect::inspect_set_notification_destinations(self.config());
+        inspect::inspect_notification_destinations_are_valid(&destinations);
+        self.config()
+            .borrow_mut()
+            .set_notification_destinations(destinations.clone());
+
+        info!("Bridge canister notification destinations changed to {destinations:?}");
+    }
+
+    /// Returns percentiles of the EVM node round-trip time, measured by pinging the node
+    /// every 30 seconds. Up to 1000 latest samples are used.
+    #[query(trait = true)]
+    fn get_evm_latency_stats(&self) -> LatencyStats {
+        self.config().borrow().get_evm_latency_stats()
+    }
+
+    /// Returns the number and age of the signed mint orders batches, which wait to be sent
+    /// to the EVM by the bridge.
+    #[query(trait = true)]
+    fn get_pending_mint_batches_stats(&self) -> PendingMintBatchesStats {
+        PendingMintBatchStore::with_memory(memory_by_id(PENDING_MINT_BATCHES_MEMORY_ID))
+            .stats(ic::time(), DEFAULT_MAX_PENDING_BATCH_AGE)
+    }
+
+    /// Sets the latency of the EVM node, above which a warning is logged.
+    /// If `None`, the latency is not checked.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_max_acceptable_evm_latency_ms(&mut self, max_latency_ms: Option<u64>) {
+        inspect::inspect_set_max_acceptable_evm_latency_ms(self.config());
+        self.config()
+            .borrow_mut()
+            .set_max_acceptable_evm_latency_ms(max_latency_ms);
+
+        info!("Bridge canister max acceptable EVM latency changed to {max_latency_ms:?}ms");
+    }
+
+    /// Sets min interval between the gas price and nonce refreshes, done right before sending
+    /// a mint transaction. If `None`, mint transactions are sent with the cached EVM params.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_gas_price_refresh_interval(&mut self, interval_secs: Option<u64>) {
+        inspect::inspect_set_gas_price_refresh_interval(self.config());
+        self.config()
+            .borrow_mut()
+            .set_gas_price_refresh_interval_secs(interval_secs);
+
+        info!("Bridge canister gas price refresh interval changed to {interval_secs:?}s");
+    }
+
+    /// Returns the relay, which mint transactions are sent to instead of the public mempool.
+    ///
+    /// This method is only for canister owner.
+    #[query(trait = true)]
+    fn get_private_tx_relay(&self) -> Option<PrivateTxRelay> {
+        inspect::inspect_get_private_tx_relay(self.config());
+        self.config().borrow().get_private_tx_relay()
+    }
+
+    /// Sets the relay, which mint transactions are sent to instead of the public mempool.
+    /// If the relay rejects a transaction, it is sent to the public mempool.
+    /// If `None`, mint transactions are sent to the public mempool.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_private_tx_relay(&mut self, relay: Option<PrivateTxRelay>) {
+        inspect::inspect_set_private_tx_relay(self.config());
+        self.config()
+            .borrow_mut()
+            .set_private_tx_relay(relay.clone());
+
+        info!("Bridge canister private transaction relay changed to {relay:?}");
+    }
+
+    /// Sets the time after sending a mint transaction, when it is checked to be dropped
+    /// from the EVM mempool. Operations with dropped transactions are re-signed and sent again.
+    /// If `None`, the sent mint transactions are not checked.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_dropped_tx_timeout(&mut self, timeout_secs: Option<u64>) {
+        inspect::inspect_set_dropped_tx_timeout(self.config());
+        self.config()
+            .borrow_mut()
+            .set_dropped_tx_timeout_secs(timeout_secs);
+
+        info!("Bridge canister dropped transaction timeout changed to {timeout_secs:?}s");
+    }
+
+    /// Sets timeout of the EVM JSON-RPC and indexer requests. A request not completed in
+    /// the timeout fails, so the task is retried without waiting for the system timeout
+    /// of the call. If `None`, the default timeout is used.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_rpc_request_timeout(&mut self, timeout_secs: Option<u64>) {
+        inspect::inspect_set_rpc_request_timeout(self.config());
+        self.config()
+            .borrow_mut()
+            .set_rpc_request_timeout_secs(timeout_secs);
+
+        info!("Bridge canister RPC request timeout changed to {timeout_secs:?}s");
+    }
+
+    /// Returns max number of the most recent operations, returned by `get_operations_list`
+    /// if no pagination is requested. The limit also bounds the parked outbox messages and
+    /// the dead letter events, returned without pagination.
+    #[query(trait = true)]
+    fn get_operations_list_default_limit(&self) -> u64 {
+        self.config().borrow().get_operations_list_default_limit()
+    }
+
+    /// Sets max number of the most recent operations, returned by `get_operations_list`
+    /// if no pagination is requested, and of the parked outbox messages and the dead letter
+    /// events, returned without pagination. If `None`, the default limit of 1000 is used.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_operations_list_default_limit(&mut self, limit: Option<u64>) {
+        inspect::inspect_set_operations_list_default_limit(self.config());
+        self.config()
+            .borrow_mut()
+            .set_operations_list_default_limit(limit);
+
+        info!("Bridge canister operations list default limit changed to {limit:?}");
+    }
+
+    /// Returns settings of the mint fees reconciliation, or `None` if it is disabled.
+    #[query(trait = true)]
+    fn get_fee_reconciliation(&self) -> Option<FeeReconciliationSettings> {
+        self.config().borrow().get_fee_reconciliation()
+    }
+
+    /// Sets settings of the mint fees reconciliation. When enabled, the fee, charged by the
+    /// BTF bridge for a minted order, is compared with the order share of the mint transaction
+    /// cost, and the difference is recorded as an adjustment of the fee payer balance.
+    /// If `None`, the reconciliation is disabled.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_fee_reconciliation(&mut self, settings: Option<FeeReconciliationSettings>) {
+        inspect::inspect_set_fee_reconciliation(self.config());
+        self.config()
+            .borrow_mut()
+            .set_fee_reconciliation(settings.clone());
+
+        info!("Bridge canister fee reconciliation settings changed to {settings:?}");
+    }
+
+    /// Returns the filter of the collected events by an indexed parameter topic, if any.
+    #[query(trait = true)]
+    fn get_event_topic_filter(&self) -> Option<EventTopicFilter> {
+        self.config().borrow().get_event_topic_filter()
+    }
+
+    /// Sets the filter of the collected events by an indexed parameter topic, e.g. the minter
+    /// address in a BTF bridge contract shared by several bridges. The filter is applied to all
+    /// the bridge events, so the topic should be present in every event the bridge handles.
+    /// If `None`, all the events of the BTF bridge contract are collected.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_event_topic_filter(&mut self, filter: Option<EventTopicFilter>) {
+        inspect::inspect_set_event_topic_filter(self.config());
+        inspect::inspect_event_topic_filter_is_valid(filter.as_ref());
+        self.config()
+            .borrow_mut()
+            .set_event_topic_filter(filter.clone());
+
+        info!("Bridge canister event topic filter changed to {filter:?}");
+    }
+
+    /// Returns settings of the HTTP outcall response transform.
+    #[query(trait = true)]
+    fn get_http_transform_settings(&self) -> HttpTransformSettings {
+        self.config().borrow().get_http_transform_settings()
+    }
+
+    /// Sets the headers, removed from the HTTP outcall responses, and whether the JSON body
+    /// of the responses is canonicalized, so the replicas reach consensus on the responses.
+    /// If `None`, the common volatile headers are removed and the JSON body is canonicalized.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_http_transform_settings(&mut self, settings: Option<HttpTransformSettings>) {
+        inspect::inspect_set_http_transform_settings(self.config());
+        self.config()
+            .borrow_mut()
+            .set_http_transform_settings(settings.clone());
+
+        info!("Bridge canister HTTP transform settings changed to {settings:?}");
+    }
+
+    /// Returns retry profiles of the scheduled operation tasks.
+    #[query(trait = true)]
+    fn get_retry_profiles(&self) -> RetryProfiles {
+        self.config().borrow().get_retry_profiles()
+    }
+
+    /// Sets retry profiles of the scheduled operation tasks. Profiles apply to the newly
+    /// scheduled tasks only. Profiles with unbounded retries are rejected, unless
+    /// explicitly allowed. If `None`, the default profiles are used.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_retry_profiles(&mut self, profiles: Option<RetryProfiles>) {
+        inspect::inspect_set_retry_profiles(self.config());
+        inspect::inspect_retry_profiles_are_valid(profiles.as_ref());
+        self.config()
+            .borrow_mut()
+            .set_retry_profiles(profiles.clone());
+
+        info!("Bridge canister retry profiles changed to {profiles:?}");
+    }
+
+    /// Returns settings of the operation progress capture to the replay log.
+    #[query(trait = true)]
+    fn get_replay_capture_settings(&self) -> ReplayCaptureSettings {
+        self.config().borrow().get_replay_capture_settings()
+    }
+
+    /// Sets settings of the operation progress capture to the replay log. The sampled share
+    /// of the operations is captured, in addition to the operations with the capture enabled
+    /// explicitly. If `None`, the default is used, which disables the sampling.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_replay_capture_settings(&mut self, settings: Option<ReplayCaptureSettings>) {
+        inspect::inspect_set_replay_capture_settings(self.config());
+        inspect::inspect_replay_capture_settings_are_valid(settings.as_ref());
+        self.config()
+            .borrow_mut()
+            .set_replay_capture_settings(settings);
+
+        info!("Bridge canister replay capture settings changed to {settings:?}");
+    }
+
+    /// Returns max number of the concurrently progressing operations per direction.
+    #[query(trait = true)]
+    fn get_operation_concurrency_limits(&self) -> OperationConcurrencyLimits {
+        self.config().borrow().get_operation_concurrency_limits()
+    }
+
+    /// Sets max number of the concurrently progressing operations per direction.
+    /// Operations over the limit are deferred and progressed in FIFO order.
+    /// Zero limit pauses the progress of the direction operations.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn set_operation_concurrency_limits(&mut self, limits: OperationConcurrencyLimits) {
+        inspect::inspect_set_operation_concurrency_limits(self.config());
+        self.config()
+            .borrow_mut()
+            .set_operation_concurrency_limits(limits.clone());
+
+        info!("Bridge canister operation concurrency limits changed to {limits:?}");
+    }
+
+    /// Returns the reason and time of the bridge halt, if the bridge is halted.
+    /// Directional operations don't progress while the bridge is halted.
+    #[query(trait = true)]
+    fn get_bridge_halt(&self) -> Option<BridgeHalt> {
+        self.config().borrow().get_bridge_halt()
+    }
+
+    /// Resumes the progress of the operations, stopped by the bridge halt.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn resume_bridge(&mut self) {
+        inspect::inspect_resume_bridge(self.config());
+        let halt = self.config().borrow().get_bridge_halt();
+        self.config().borrow_mut().resume_bridge();
+
+        info!("Bridge canister resumed after halt {halt:?}");
+    }
+
+    /// Returns the drift between the nonce, reserved by the bridge canister, and the pending
+    /// nonce of the bridge canister EVM address.
+    #[query(trait = true)]
+    fn get_nonce_drift_status(&self) -> NonceDriftStatus {
+        self.config().borrow().get_nonce_drift_status()
+    }
+
+    /// Closes the open nonce gap and resumes mint transactions sending. The reserved nonce
+    /// is moved back to the pending nonce of the EVM address, observed by the latest check.
+    /// Returns the closed gap, if it was open.
+    ///
+    /// This method is only for canister owner.
+    #[update(trait = true)]
+    fn acknowledge_nonce_gap(&mut self) -> Option<NonceGap> {
+        inspect::inspect_acknowledge_nonce_gap(self.config());
+        let gap = self.config().borrow_mut().acknowledge_nonce_gap();
+
+        info!("Bridge canister nonce gap acknowledged: {gap:?}");
+        gap
+    }
+
+    /// Returns the mode of the canister. A replica canister doesn't process the operations,
+    /// and imports them from the primary canister.
+    #[query(trait = true)]
+    fn get_bridge_mode(&self) -> BridgeMode {
+        self.config().borrow().get_bridge_mode()
+    }
+
+    /// Transforms an HTTP outcall response without a method-specific transform
+    /// for the replicas consensus.
+    #[query(trait = true)]
+    fn transform(&self, args: TransformArgs) -> HttpResponse {
+        let settings = self.config().borrow().get_http_transform_settings();
+        http_transform::transform(args, &settings)
+    }
+
+    /// Transforms the `eth_getLogs` HTTP outcall response for the replicas consensus.
+    #[query(trait = true)]
+    fn transform_eth_get_logs(&self, args: TransformArgs) -> HttpResponse {
+        let settings = self.config().borrow().get_http_transform_settings();
+        http_transform::transform_eth_get_logs(args, &settings)
+    }
+
+    /// Transforms the `eth_blockNumber` HTTP outcall response for the replicas consensus.
+    #[query(trait = true)]
+    fn transform_eth_block_number(&self, args: TransformArgs) -> HttpResponse {
+        let settings = self.config().borrow().get_http_transform_settings();
+        http_transform::transform_eth_block_number(args, &settings)
+    }
+
+    /// Transforms the `eth_chainId` HTTP outcall response for the replicas consensus.
+    #[query(trait = true)]
+    fn transform_eth_chain_id(&self, args: TransformArgs) -> HttpResponse {
+        let settings = self.config().borrow().get_http_transform_settings();
+        http_transform::transform_eth_chain_id(args, &settings)
+    }
+
+    /// Transforms the outbox webhook HTTP outcall response for the replicas consensus.
+    #[query(trait = true)]
+    fn transform_webhook_response(&self, args: TransformArgs) -> HttpResponse {
+        http_transform::transform_webhook_response(args)
+    }
+
+    /// Returns up to `count` latest log records, written during the operation execution.
+    /// Every record is prefixed with the operation id and the execution step.
+    #[query(trait = true)]
+    fn get_logs_for_operation(&self, operation_id: OperationId, count: usize) -> Vec<Log> {
+        inspect::inspect_get_logs_for_operation(self.config());
+        log_span::logs_for_operation(operation_id, count)
+    }
+
     /// Returns evm_address of the bridge canister.
     #[allow(async_fn_in_trait)]
     #[update(trait = true)]
     async fn get_bridge_canister_evm_address(&mut self) -> BTFResult<H160> {
-        let signer = self.config().borrow().get_signer()?;
-        signer.get_address().await.map_err(|e| {
-            Error::Initialization(format!("failed to get bridge canister address: {e}"))
-        })
+        ConfigStorage::get_signer_address(self.config())
+            .await
+            .map_err(|e| {
+                Error::Initialization(format!("failed to get bridge canister address: {e}"))
+            })
     }
 
     /// Initialize the bridge with the given parameters.
@@ -115,6 +611,12 @@ pub trait BridgeCanister: Canister + LogCanister {
 
     /// Re-initializes the bridge after upgrade. This method should be called from the `#[post-upgrade]`
     /// method.
+    ///
+    /// Config stored in a previous version is migrated to the current one. Operations stored in
+    /// an outdated encoding are decoded on read, and should be rewritten by the canister in
+    /// batches with the [`MigrateRecordsService`].
+    ///
+    /// [`MigrateRecordsService`]: crate::runtime::service::migrate_records::MigrateRecordsService
     fn bridge_post_upgrade(&mut self, _run_scheduler: impl Fn() + 'static) {
         if let Err(err) = self
             .log_state()
@@ -124,17 +626,23 @@ pub trait BridgeCanister: Canister + LogCanister {
             ic_exports::ic_cdk::println!("Error configuring the logger. Err: {err:?}")
         }
 
+        self.config().borrow_mut().migrate();
+        if !self.config().borrow().is_timers_enabled() {
+            info!("Bridge canister timers are disabled, operations don't progress");
+        }
+
         #[cfg(target_arch = "wasm32")]
         self.start_timers(_run_scheduler);
 
         debug!("Upgrade completed");
     }
 
-    /// Starts scheduler timer.
+    /// Starts scheduler timer. The scheduler is skipped while the timers are disabled.
     fn start_timers(&mut self, run_scheduler: impl Fn() + 'static) {
         const GLOBAL_TIMER_INTERVAL: Duration = Duration::from_secs(2);
+        let config = self.config();
         ic_exports::ic_cdk_timers::set_timer_interval(GLOBAL_TIMER_INTERVAL, move || {
-            run_scheduler();
+            run_scheduler_if_enabled(&config, &run_scheduler);
         });
     }
 
@@ -148,6 +656,16 @@ pub trait BridgeCanister: Canister + LogCanister {
     }
 }
 
+/// Runs the scheduler, if the timers of the bridge are enabled. Returns `false` if skipped.
+fn run_scheduler_if_enabled(config: &RefCell<ConfigStorage>, run_scheduler: &impl Fn()) -> bool {
+    if !config.borrow().is_timers_enabled() {
+        return false;
+    }
+
+    run_scheduler();
+    true
+}
+
 generate_exports!(BridgeCanister, BridgeCanisterExport);
 
 impl LogCanister for BridgeCanisterExport {
@@ -159,12 +677,17 @@ impl LogCanister for BridgeCanisterExport {
 #[cfg(test)]
 mod tests {
     use bridge_did::evm_link::EvmLink;
+    use bridge_did::http_transform::HeaderFilter;
+    use bridge_did::retry_profile::RetryLimit;
+    use did::H256;
     use eth_signer::sign_strategy::SigningStrategy;
     use ic_canister::{canister_call, init};
+    use ic_exports::ic_cdk::api::management_canister::http_request::HttpHeader;
     use ic_exports::ic_kit::{inject, MockContext};
     use ic_storage::IcStorage;
 
     use super::*;
+    use crate::runtime::state::config::DEFAULT_RPC_REQUEST_TIMEOUT;
 
     #[derive(Debug, Canister)]
     struct TestBridge {
@@ -209,6 +732,9 @@ mod tests {
                 private_key: [1u8; 32],
             },
             log_settings: None,
+            evm_finality: None,
+            nonce_offset: None,
+            mode: None,
         };
         init_with_data(init_data).await
     }
@@ -234,6 +760,9 @@ mod tests {
                 private_key: [1u8; 32],
             },
             log_settings: None,
+            evm_finality: None,
+            nonce_offset: None,
+            mode: None,
         };
         let _ = init_with_data(init_data).await;
     }
@@ -248,6 +777,9 @@ mod tests {
                 private_key: [1u8; 32],
             },
             log_settings: None,
+            evm_finality: None,
+            nonce_offset: None,
+            mode: None,
         };
         let _ = init_with_data(init_data).await;
     }
@@ -262,6 +794,9 @@ mod tests {
                 private_key: [1u8; 32],
             },
             log_settings: None,
+            evm_finality: None,
+            nonce_offset: None,
+            mode: None,
         };
         let _ = init_with_data(init_data).await;
     }
@@ -320,4 +855,732 @@ mod tests {
         let address = H160::from_slice(&[42; 20]);
         let _ = canister_call!(canister.set_btf_bridge_contract(address), ()).await;
     }
+
+    fn gas_balance_thresholds() -> GasBalanceThresholds {
+        GasBalanceThresholds {
+            low_watermark: 1000u64.into(),
+            critical_watermark: 100u64.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn set_gas_balance_thresholds_works() {
+        let mut canister = init_canister().await;
+
+        inject::get_context().update_id(owner());
+        let thresholds = Some(gas_balance_thresholds());
+        canister_call!(canister.set_gas_balance_thresholds(thresholds.clone()), ())
+            .await
+            .unwrap();
+
+        let status = canister_call!(canister.get_gas_balance_status(), GasBalanceStatus)
+            .await
+            .unwrap();
+        assert_eq!(status.thresholds, thresholds);
+        assert!(status.mint_tx_sponsorship_enabled);
+    }
+
+    #[tokio::test]
+    async fn set_operation_log_compression_works() {
+        let mut canister = init_canister().await;
+        assert!(!canister
+            .config()
+            .borrow()
+            .is_operation_log_compression_enabled());
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_operation_log_compression(true), ())
+            .await
+            .unwrap();
+
+        assert!(canister
+            .config()
+            .borrow()
+            .is_operation_log_compression_enabled());
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_operation_log_compression_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_operation_log_compression(true), ()).await;
+    }
+
+    #[tokio::test]
+    async fn set_strict_event_validation_works() {
+        let mut canister = init_canister().await;
+        assert!(!canister
+            .config()
+            .borrow()
+            .is_strict_event_validation_enabled());
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_strict_event_validation(true), ())
+            .await
+            .unwrap();
+
+        assert!(canister
+            .config()
+            .borrow()
+            .is_strict_event_validation_enabled());
+    }
+
+    #[tokio::test]
+    async fn set_timers_enabled_works() {
+        let mut canister = init_canister().await;
+        assert!(canister_call!(canister.timers_enabled(), bool)
+            .await
+            .unwrap());
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_timers_enabled(false), ())
+            .await
+            .unwrap();
+        assert!(!canister_call!(canister.timers_enabled(), bool)
+            .await
+            .unwrap());
+        assert!(!canister.get_bridge_manifest().timers_enabled);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_timers_enabled_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_timers_enabled(false), ()).await;
+    }
+
+    #[tokio::test]
+    async fn scheduler_is_suspended_while_timers_are_disabled() {
+        let mut canister = init_canister().await;
+        let backlog = RefCell::new(vec![1, 2, 3]);
+        let run_scheduler = || backlog.borrow_mut().clear();
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_timers_enabled(false), ())
+            .await
+            .unwrap();
+        assert!(!run_scheduler_if_enabled(
+            &canister.config(),
+            &run_scheduler
+        ));
+        assert_eq!(backlog.borrow().len(), 3);
+
+        canister_call!(canister.set_timers_enabled(true), ())
+            .await
+            .unwrap();
+        assert!(run_scheduler_if_enabled(&canister.config(), &run_scheduler));
+        assert!(backlog.borrow().is_empty());
+    }
+
+    #[tokio::test]
+    async fn evm_params_history_is_returned() {
+        let mut canister = init_canister().await;
+        for nonce in 1..=3 {
+            canister
+                .config()
+                .borrow_mut()
+                .update_evm_params(|p| p.nonce = nonce);
+        }
+
+        inject::get_context().update_id(owner());
+        let history = canister_call!(
+            canister.get_evm_params_history(None),
+            Vec<EvmParamsSnapshot>
+        )
+        .await
+        .unwrap();
+        let nonces: Vec<_> = history.iter().map(|s| s.nonce).collect();
+        assert_eq!(nonces, vec![3, 2, 1]);
+
+        canister_call!(canister.set_evm_params_history_size(2), ())
+            .await
+            .unwrap();
+        let history = canister_call!(
+            canister.get_evm_params_history(Some(Pagination::new(1, 10))),
+            Vec<EvmParamsSnapshot>
+        )
+        .await
+        .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].nonce, 2);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn get_evm_params_history_rejected_for_non_owner() {
+        let canister = init_canister().await;
+        let _ = canister_call!(
+            canister.get_evm_params_history(None),
+            Vec<EvmParamsSnapshot>
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn evm_latency_stats_are_returned() {
+        let mut canister = init_canister().await;
+        for latency_ms in 1..=10 {
+            canister
+                .config()
+                .borrow_mut()
+                .record_evm_latency(latency_ms, latency_ms * 1_000_000);
+        }
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_max_acceptable_evm_latency_ms(Some(5)), ())
+            .await
+            .unwrap();
+
+        let stats = canister_call!(canister.get_evm_latency_stats(), LatencyStats)
+            .await
+            .unwrap();
+        assert_eq!(stats.samples_count, 10);
+        assert_eq!(stats.p50_ns, 5_000_000);
+        assert_eq!(stats.p95_ns, 10_000_000);
+        assert_eq!(stats.max_acceptable_latency_ms, Some(5));
+        assert!(stats.is_degraded());
+    }
+
+    #[tokio::test]
+    async fn bridge_manifest_is_updated_on_config_change() {
+        let mut canister = init_canister().await;
+        let manifest = canister_call!(canister.get_bridge_manifest(), BridgeManifest)
+            .await
+            .unwrap();
+        assert_eq!(manifest.btf_bridge_contract, None);
+
+        inject::get_context().update_id(owner());
+        let address = H160::from_slice(&[42; 20]);
+        canister_call!(canister.set_btf_bridge_contract(address.clone()), ())
+            .await
+            .unwrap();
+
+        inject::get_context().update_id(bob());
+        let updated = canister_call!(canister.get_bridge_manifest(), BridgeManifest)
+            .await
+            .unwrap();
+        assert_eq!(updated.btf_bridge_contract, Some(address));
+        assert_eq!(updated.manifest_version, manifest.manifest_version + 1);
+    }
+
+    #[tokio::test]
+    async fn set_gas_price_refresh_interval_works() {
+        let mut canister = init_canister().await;
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_gas_price_refresh_interval(Some(30)), ())
+            .await
+            .unwrap();
+        assert_eq!(
+            canister
+                .config()
+                .borrow()
+                .get_gas_price_refresh_interval_secs(),
+            Some(30)
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_gas_price_refresh_interval_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_gas_price_refresh_interval(Some(30)), ()).await;
+    }
+
+    #[tokio::test]
+    async fn set_dropped_tx_timeout_works() {
+        let mut canister = init_canister().await;
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_dropped_tx_timeout(Some(600)), ())
+            .await
+            .unwrap();
+        assert_eq!(
+            canister.config().borrow().get_dropped_tx_timeout_secs(),
+            Some(600)
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_dropped_tx_timeout_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_dropped_tx_timeout(Some(600)), ()).await;
+    }
+
+    #[tokio::test]
+    async fn set_rpc_request_timeout_works() {
+        let mut canister = init_canister().await;
+        assert_eq!(
+            canister.config().borrow().get_rpc_request_timeout(),
+            DEFAULT_RPC_REQUEST_TIMEOUT
+        );
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_rpc_request_timeout(Some(5)), ())
+            .await
+            .unwrap();
+        assert_eq!(
+            canister.config().borrow().get_rpc_request_timeout(),
+            Duration::from_secs(5)
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_rpc_request_timeout_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_rpc_request_timeout(Some(5)), ()).await;
+    }
+
+    #[tokio::test]
+    async fn set_operations_list_default_limit_works() {
+        let mut canister = init_canister().await;
+        let limit = canister_call!(canister.get_operations_list_default_limit(), u64)
+            .await
+            .unwrap();
+        assert_eq!(limit, 1000);
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_operations_list_default_limit(Some(50)), ())
+            .await
+            .unwrap();
+        let limit = canister_call!(canister.get_operations_list_default_limit(), u64)
+            .await
+            .unwrap();
+        assert_eq!(limit, 50);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_operations_list_default_limit_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_operations_list_default_limit(Some(50)), ()).await;
+    }
+
+    #[tokio::test]
+    async fn set_fee_reconciliation_works() {
+        let mut canister = init_canister().await;
+        let settings = FeeReconciliationSettings {
+            max_debit: 1000u64.into(),
+        };
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_fee_reconciliation(Some(settings.clone())), ())
+            .await
+            .unwrap();
+        assert_eq!(
+            canister_call!(
+                canister.get_fee_reconciliation(),
+                Option<FeeReconciliationSettings>
+            )
+            .await
+            .unwrap(),
+            Some(settings)
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_fee_reconciliation_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_fee_reconciliation(None), ()).await;
+    }
+
+    #[tokio::test]
+    async fn set_event_topic_filter_works() {
+        let mut canister = init_canister().await;
+        let filter = EventTopicFilter {
+            position: 1,
+            values: vec![H256::from_slice(&[1; 32])],
+        };
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_event_topic_filter(Some(filter.clone())), ())
+            .await
+            .unwrap();
+        assert_eq!(
+            canister_call!(canister.get_event_topic_filter(), Option<EventTopicFilter>)
+                .await
+                .unwrap(),
+            Some(filter)
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Invalid event topic filter")]
+    async fn invalid_event_topic_filter_is_rejected() {
+        let mut canister = init_canister().await;
+        let filter = EventTopicFilter {
+            position: 0,
+            values: vec![H256::from_slice(&[1; 32])],
+        };
+
+        inject::get_context().update_id(owner());
+        let _ = canister_call!(canister.set_event_topic_filter(Some(filter)), ()).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_event_topic_filter_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_event_topic_filter(None), ()).await;
+    }
+
+    #[tokio::test]
+    async fn set_retry_profiles_works() {
+        let mut canister = init_canister().await;
+        let mut profiles = RetryProfiles::default();
+        profiles.icrc_call.retries = RetryLimit::MaxRetries(10);
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_retry_profiles(Some(profiles.clone())), ())
+            .await
+            .unwrap();
+        assert_eq!(
+            canister_call!(canister.get_retry_profiles(), RetryProfiles)
+                .await
+                .unwrap(),
+            profiles
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Invalid retry profiles")]
+    async fn unbounded_retry_profile_is_rejected_unless_allowed() {
+        let mut canister = init_canister().await;
+        let mut profiles = RetryProfiles::default();
+        profiles.evm_call.retries = RetryLimit::Unbounded;
+
+        inject::get_context().update_id(owner());
+        let _ = canister_call!(canister.set_retry_profiles(Some(profiles)), ()).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_retry_profiles_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_retry_profiles(None), ()).await;
+    }
+
+    #[tokio::test]
+    async fn set_replay_capture_settings_works() {
+        let mut canister = init_canister().await;
+        let settings = ReplayCaptureSettings {
+            sample_rate_ppm: 1000,
+            log_size: 4,
+        };
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_replay_capture_settings(Some(settings)), ())
+            .await
+            .unwrap();
+        assert_eq!(
+            canister_call!(
+                canister.get_replay_capture_settings(),
+                ReplayCaptureSettings
+            )
+            .await
+            .unwrap(),
+            settings
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Invalid replay capture settings")]
+    async fn invalid_replay_capture_settings_are_rejected() {
+        let mut canister = init_canister().await;
+        let settings = ReplayCaptureSettings {
+            sample_rate_ppm: 1000,
+            log_size: 0,
+        };
+
+        inject::get_context().update_id(owner());
+        let _ = canister_call!(canister.set_replay_capture_settings(Some(settings)), ()).await;
+    }
+
+    #[tokio::test]
+    async fn transform_removes_configured_headers() {
+        let mut canister = init_canister().await;
+        let header = |name: &str| HttpHeader {
+            name: name.into(),
+            value: "value".into(),
+        };
+        let args = TransformArgs {
+            response: HttpResponse {
+                status: 200u32.into(),
+                headers: vec![header("Date"), header("X-Request-Id"), header("Server")],
+                body: br#"{"result": "0x1"}"#.to_vec(),
+            },
+            context: vec![],
+        };
+
+        let response = canister_call!(canister.transform(args.clone()), HttpResponse)
+            .await
+            .unwrap();
+        assert_eq!(response.headers, vec![header("Server")]);
+        assert_eq!(response.body, args.response.body);
+
+        inject::get_context().update_id(owner());
+        let settings = HttpTransformSettings {
+            headers: HeaderFilter::Deny(vec!["server".into()]),
+            canonicalize_body: false,
+        };
+        canister_call!(canister.set_http_transform_settings(Some(settings)), ())
+            .await
+            .unwrap();
+
+        let response = canister_call!(canister.transform(args.clone()), HttpResponse)
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers,
+            vec![header("Date"), header("X-Request-Id")]
+        );
+        assert_eq!(response.body, args.response.body);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_http_transform_settings_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_http_transform_settings(None), ()).await;
+    }
+
+    #[tokio::test]
+    async fn private_tx_relay_is_set() {
+        let mut canister = init_canister().await;
+        let relay = PrivateTxRelay::new(EvmLink::Http("https://relay.example".into()));
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.set_private_tx_relay(Some(relay.clone())), ())
+            .await
+            .unwrap();
+
+        let stored = canister_call!(canister.get_private_tx_relay(), Option<PrivateTxRelay>)
+            .await
+            .unwrap();
+        assert_eq!(stored, Some(relay));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_private_tx_relay_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_private_tx_relay(None), ()).await;
+    }
+
+    #[tokio::test]
+    async fn operation_concurrency_limits_are_set() {
+        let mut canister = init_canister().await;
+        let limits = OperationConcurrencyLimits {
+            deposit: Some(5),
+            withdrawal: Some(0),
+        };
+
+        inject::get_context().update_id(owner());
+        canister_call!(
+            canister.set_operation_concurrency_limits(limits.clone()),
+            ()
+        )
+        .await
+        .unwrap();
+
+        let stored = canister_call!(
+            canister.get_operation_concurrency_limits(),
+            OperationConcurrencyLimits
+        )
+        .await
+        .unwrap();
+        assert_eq!(stored, limits);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_operation_concurrency_limits_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(
+            canister.set_operation_concurrency_limits(OperationConcurrencyLimits::default()),
+            ()
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn resume_bridge_clears_halt() {
+        let mut canister = init_canister().await;
+        canister.config().borrow_mut().halt_bridge(BridgeHalt {
+            timestamp: 1,
+            reason: "test".into(),
+        });
+        assert_eq!(
+            canister
+                .config()
+                .borrow()
+                .get_effective_operation_concurrency_limits(),
+            OperationConcurrencyLimits::PAUSED
+        );
+
+        inject::get_context().update_id(owner());
+        canister_call!(canister.resume_bridge(), ()).await.unwrap();
+
+        let halt = canister_call!(canister.get_bridge_halt(), Option<BridgeHalt>)
+            .await
+            .unwrap();
+        assert_eq!(halt, None);
+        assert_eq!(
+            canister
+                .config()
+                .borrow()
+                .get_effective_operation_concurrency_limits(),
+            OperationConcurrencyLimits::default()
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn resume_bridge_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.resume_bridge(), ()).await;
+    }
+
+    #[tokio::test]
+    async fn acknowledge_nonce_gap_works() {
+        let mut canister = init_canister().await;
+        {
+            let config = canister.config();
+            let mut config = config.borrow_mut();
+            config.update_evm_params(|p| p.nonce = 8);
+            config.record_refreshed_evm_params(8, 5, 10u64.into(), 1);
+        }
+
+        let status = canister_call!(canister.get_nonce_drift_status(), NonceDriftStatus)
+            .await
+            .unwrap();
+        assert_eq!(status.local_nonce, Some(8));
+        assert!(status.gap.is_some());
+
+        inject::get_context().update_id(owner());
+        let gap = canister_call!(canister.acknowledge_nonce_gap(), Option<NonceGap>)
+            .await
+            .unwrap();
+        assert_eq!(gap, status.gap);
+
+        let status = canister_call!(canister.get_nonce_drift_status(), NonceDriftStatus)
+            .await
+            .unwrap();
+        assert_eq!(status.local_nonce, Some(5));
+        assert_eq!(status.gap, None);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn acknowledge_nonce_gap_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.acknowledge_nonce_gap(), Option<NonceGap>).await;
+    }
+
+    #[tokio::test]
+    async fn bridge_mode_is_set_on_init() {
+        let canister = init_canister().await;
+        let mode = canister_call!(canister.get_bridge_mode(), BridgeMode)
+            .await
+            .unwrap();
+        assert_eq!(mode, BridgeMode::Primary);
+
+        let init_data = BridgeInitData {
+            owner: owner(),
+            evm_link: EvmLink::Ic(bob()),
+            signing_strategy: SigningStrategy::Local {
+                private_key: [1u8; 32],
+            },
+            log_settings: None,
+            evm_finality: None,
+            nonce_offset: None,
+            mode: Some(BridgeMode::Replica { primary: bob() }),
+        };
+        let canister = init_with_data(init_data).await;
+        let mode = canister_call!(canister.get_bridge_mode(), BridgeMode)
+            .await
+            .unwrap();
+        assert_eq!(mode, BridgeMode::Replica { primary: bob() });
+        assert_eq!(
+            canister.config().borrow().check_primary_mode(),
+            Err(Error::ReplicaMode)
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_max_acceptable_evm_latency_ms_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_max_acceptable_evm_latency_ms(Some(100)), ()).await;
+    }
+
+    #[tokio::test]
+    async fn notification_destinations_are_set() {
+        let mut canister = init_canister().await;
+        let destinations = vec![OutboxDestination::Webhook {
+            url: "https://partner.example/hook".into(),
+        }];
+
+        inject::get_context().update_id(owner());
+        canister_call!(
+            canister.set_notification_destinations(destinations.clone()),
+            ()
+        )
+        .await
+        .unwrap();
+
+        let stored = canister_call!(
+            canister.get_notification_destinations(),
+            Vec<OutboxDestination>
+        )
+        .await
+        .unwrap();
+        assert_eq!(stored, destinations);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Invalid notification destination")]
+    async fn set_notification_destinations_rejects_plain_http_webhook() {
+        let mut canister = init_canister().await;
+        let destinations = vec![OutboxDestination::Webhook {
+            url: "http://partner.example/hook".into(),
+        }];
+
+        inject::get_context().update_id(owner());
+        let _ = canister_call!(canister.set_notification_destinations(destinations), ()).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_notification_destinations_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.set_notification_destinations(vec![]), ()).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn set_gas_balance_thresholds_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+
+        let thresholds = Some(gas_balance_thresholds());
+        let _ = canister_call!(canister.set_gas_balance_thresholds(thresholds), ()).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "Critical gas balance watermark cannot be greater than the low watermark"
+    )]
+    async fn set_gas_balance_thresholds_rejects_invalid_watermarks() {
+        let mut canister = init_canister().await;
+
+        inject::get_context().update_id(owner());
+        let thresholds = Some(GasBalanceThresholds {
+            low_watermark: 100u64.into(),
+            critical_watermark: 1000u64.into(),
+        });
+        let _ = canister_call!(canister.set_gas_balance_thresholds(thresholds), ()).await;
+    }
 }
@@ -0,0 +1,143 @@
+//! List of the events, rejected by the strict event validation, and of the logs with unknown
+//! topics. The rejected events are not processed by the bridge, but are kept for the owner
+//! to investigate. Only the most recent [`MAX_DEAD_LETTER_EVENTS`] events are kept.
+
+use bridge_did::dead_letter::{DeadLetterEvent, DeadLetterEventData};
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, StableBTreeMap};
+
+/// Max number of the stored rejected events. The oldest events are removed to store new ones.
+pub const MAX_DEAD_LETTER_EVENTS: u64 = 10_000;
+
+/// Rejected events, stored in IC stable memory.
+pub struct DeadLetterEvents<M: Memory> {
+    events: StableBTreeMap<u64, DeadLetterEvent, M>,
+}
+
+impl<M: Memory> DeadLetterEvents<M> {
+    /// Creates a new instance of the list.
+    pub fn with_memory(memory: M) -> Self {
+        Self {
+            events: StableBTreeMap::new(memory),
+        }
+    }
+
+    /// Appends the rejected event to the list. Returns id of the stored entry.
+    /// If the list is full, the oldest entry is removed. Ids are sequential and never reused.
+    pub fn push(
+        &mut self,
+        event: impl Into<DeadLetterEventData>,
+        reason: String,
+        timestamp: u64,
+    ) -> u64 {
+        let id = self
+            .events
+            .last_key_value()
+            .map(|(id, _)| id + 1)
+            .unwrap_or_default();
+        if self.events.len() >= MAX_DEAD_LETTER_EVENTS {
+            if let Some((oldest, _)) = self.events.iter().next() {
+                log::debug!("Dead letter event {oldest} is removed by the retention limit");
+                self.events.remove(&oldest);
+            }
+        }
+        self.events.insert(
+            id,
+            DeadLetterEvent {
+                id,
+                event: event.into(),
+                reason,
+                timestamp,
+            },
+        );
+
+        id
+    }
+
+    /// Returns up to `count` rejected events, skipping `offset` oldest ones.
+    pub fn get(&self, offset: usize, count: usize) -> Vec<DeadLetterEvent> {
+        self.events
+            .iter()
+            .skip(offset)
+            .take(count)
+            .map(|(_, event)| event)
+            .collect()
+    }
+
+    /// Number of the rejected events.
+    pub fn len(&self) -> u64 {
+        self.events.len()
+    }
+
+    /// Checks if there are no rejected events.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::event_data::{BurntEventData, RawEventLog};
+    use ic_stable_structures::VectorMemory;
+
+    use super::*;
+
+    #[test]
+    fn rejected_events_are_stored_with_raw_data() {
+        let memory = VectorMemory::default();
+        let mut dead_letters = DeadLetterEvents::with_memory(memory.clone());
+        assert!(dead_letters.is_empty());
+
+        let event = BurntEventData {
+            name: vec![1; 40],
+            decimals: 200,
+            ..Default::default()
+        };
+        assert_eq!(dead_letters.push(event.clone(), "bad name".into(), 10), 0);
+        assert_eq!(dead_letters.push(event, "bad name".into(), 20), 1);
+
+        // Entries survive the canister upgrade.
+        let dead_letters = DeadLetterEvents::with_memory(memory);
+        assert_eq!(dead_letters.len(), 2);
+
+        let stored = dead_letters.get(1, 10);
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].id, 1);
+        let event = stored[0].event.burnt().unwrap();
+        assert_eq!(event.name, vec![1; 40]);
+        assert_eq!(event.decimals, 200);
+        assert_eq!(stored[0].reason, "bad name");
+        assert_eq!(stored[0].timestamp, 20);
+    }
+
+    #[test]
+    fn oldest_events_are_removed_over_retention_limit() {
+        let mut dead_letters = DeadLetterEvents::with_memory(VectorMemory::default());
+        for timestamp in 0..MAX_DEAD_LETTER_EVENTS + 2 {
+            dead_letters.push(BurntEventData::default(), "bad name".into(), timestamp);
+        }
+
+        assert_eq!(dead_letters.len(), MAX_DEAD_LETTER_EVENTS);
+        let oldest = dead_letters.get(0, 1);
+        assert_eq!(oldest[0].id, 2);
+        let newest = dead_letters.get(MAX_DEAD_LETTER_EVENTS as usize - 1, 10);
+        assert_eq!(newest.len(), 1);
+        assert_eq!(newest[0].id, MAX_DEAD_LETTER_EVENTS + 1);
+        assert_eq!(newest[0].timestamp, MAX_DEAD_LETTER_EVENTS + 1);
+    }
+
+    #[test]
+    fn unknown_event_logs_are_stored() {
+        let mut dead_letters = DeadLetterEvents::with_memory(VectorMemory::default());
+        let log = RawEventLog {
+            topics: vec![did::H256::from_slice(&[1; 32])],
+            data: vec![2; 64],
+        };
+
+        let id = dead_letters.push(log.clone(), "unknown event topic".into(), 10);
+        let stored = dead_letters.get(0, 10);
+        assert_eq!(stored[0].id, id);
+        assert!(stored[0].event.burnt().is_none());
+        assert!(matches!(&stored[0].event, DeadLetterEventData::Unknown(raw) if *raw == log));
+    }
+}
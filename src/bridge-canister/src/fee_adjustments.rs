@@ -0,0 +1,300 @@
+//! Adjustments of the mint fees, charged by the BTF bridge, to the actual cost of the mint
+//! transactions. Minted orders wait for the reconciliation in the pending list. Adjustments
+//! are indexed by the fee payer, with the totals kept per fee payer.
+//!
+//! Adjustments are accounting records of the bridge canister only: neither credits nor debits
+//! change the fee payer balance in the BTF bridge contract, so they are settled off-chain.
+
+use bridge_did::fee_adjustment::{
+    FeeAdjustment, FeeAdjustmentAmount, FeeAdjustmentBalance, FeePayerAdjustments,
+    FeeReconciliationSettings, PendingFeeReconciliation,
+};
+use bridge_did::op_id::OperationId;
+use bridge_utils::common::Pagination;
+use bridge_utils::evm_bridge::MintTxCost;
+use did::H160;
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, MultimapStructure, StableBTreeMap, StableMultimap};
+
+/// Max number of the fee payer adjustments returned by a single request.
+pub const MAX_FEE_ADJUSTMENTS_PER_REQUEST: usize = 100;
+
+/// Memory regions of the fee adjustments.
+pub struct FeeAdjustmentsMemory<M> {
+    pub pending: M,
+    pub by_payer: M,
+    pub balances: M,
+}
+
+/// Fee adjustments, stored in IC stable memory.
+pub struct FeeAdjustments<M: Memory> {
+    pending: StableBTreeMap<OperationId, PendingFeeReconciliation, M>,
+    by_payer: StableMultimap<H160, OperationId, FeeAdjustment, M>,
+    balances: StableBTreeMap<H160, FeeAdjustmentBalance, M>,
+}
+
+impl<M: Memory> FeeAdjustments<M> {
+    /// Creates a new instance of the storage.
+    pub fn with_memory(memory: FeeAdjustmentsMemory<M>) -> Self {
+        Self {
+            pending: StableBTreeMap::new(memory.pending),
+            by_payer: StableMultimap::new(memory.by_payer),
+            balances: StableBTreeMap::new(memory.balances),
+        }
+    }
+
+    /// Adds the minted order to the pending list.
+    pub fn add_pending(&mut self, pending: PendingFeeReconciliation) {
+        self.pending.insert(pending.operation_id, pending);
+    }
+
+    /// Returns up to `count` pending orders, minted by the `bridge_contract`, ordered by
+    /// operation id.
+    pub fn get_pending(
+        &self,
+        bridge_contract: &H160,
+        count: usize,
+    ) -> Vec<PendingFeeReconciliation> {
+        self.pending
+            .iter()
+            .map(|(_, pending)| pending)
+            .filter(|pending| pending.bridge_contract == *bridge_contract)
+            .take(count)
+            .collect()
+    }
+
+    /// Removes the order from the pending list.
+    pub fn remove_pending(&mut self, operation_id: OperationId) {
+        self.pending.remove(&operation_id);
+    }
+
+    /// Number of the pending orders.
+    pub fn pending_len(&self) -> u64 {
+        self.pending.len()
+    }
+
+    /// Stores the adjustment and adds it to the fee payer totals. The totals are kept by
+    /// the canister, no funds are transferred to or from the fee payer.
+    ///
+    /// The caller is responsible to record every adjustment only once.
+    pub fn record(&mut self, adjustment: FeeAdjustment) {
+        let mut balance = self.balances.get(&adjustment.fee_payer).unwrap_or_default();
+        balance.apply(&adjustment.amount);
+        self.balances.insert(adjustment.fee_payer.clone(), balance);
+
+        self.by_payer.insert(
+            &adjustment.fee_payer,
+            &adjustment.operation_id,
+            adjustment.clone(),
+        );
+    }
+
+    /// Returns the totals and the adjustments of the fee payer, ordered by operation id.
+    /// At most [`MAX_FEE_ADJUSTMENTS_PER_REQUEST`] adjustments are returned.
+    pub fn get_for_payer(
+        &self,
+        fee_payer: &H160,
+        pagination: Option<Pagination>,
+    ) -> FeePayerAdjustments {
+        let pagination =
+            pagination.unwrap_or_else(|| Pagination::new(0, MAX_FEE_ADJUSTMENTS_PER_REQUEST));
+        FeePayerAdjustments {
+            balance: self.balances.get(fee_payer).unwrap_or_default(),
+            adjustments: self
+                .by_payer
+                .range(fee_payer)
+                .skip(pagination.offset)
+                .take(pagination.count.min(MAX_FEE_ADJUSTMENTS_PER_REQUEST))
+                .map(|(_, adjustment)| adjustment)
+                .collect(),
+        }
+    }
+}
+
+/// Computes the adjustment of the fee, charged for the pending order, to the order share
+/// of the mint transaction cost. Returns `None` if the order is not sent in the transaction.
+pub fn reconcile(
+    pending: &PendingFeeReconciliation,
+    cost: &MintTxCost,
+    settings: &FeeReconciliationSettings,
+    timestamp: u64,
+) -> Option<FeeAdjustment> {
+    let fee_payer = cost.fee_payer(pending.nonce)?;
+    let actual = cost.per_order();
+    let amount = FeeAdjustmentAmount::compute(&pending.charged, &actual, &settings.max_debit);
+
+    Some(FeeAdjustment {
+        operation_id: pending.operation_id,
+        fee_payer,
+        tx_hash: pending.tx_hash.clone(),
+        charged: pending.charged.clone(),
+        actual,
+        amount,
+        timestamp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::order::{MintOrder, SignedOrdersData};
+    use did::H256;
+    use ic_stable_structures::VectorMemory;
+
+    use super::*;
+
+    fn storage() -> FeeAdjustments<VectorMemory> {
+        FeeAdjustments::with_memory(FeeAdjustmentsMemory {
+            pending: VectorMemory::default(),
+            by_payer: VectorMemory::default(),
+            balances: VectorMemory::default(),
+        })
+    }
+
+    fn pending(id: u64, bridge_contract: u8) -> PendingFeeReconciliation {
+        PendingFeeReconciliation {
+            operation_id: OperationId::new(id),
+            bridge_contract: H160::from_slice(&[bridge_contract; 20]),
+            tx_hash: H256::from_slice(&[1; 32]),
+            nonce: id as u32,
+            charged: 100u64.into(),
+        }
+    }
+
+    fn adjustment(id: u64, fee_payer: u8, amount: FeeAdjustmentAmount) -> FeeAdjustment {
+        FeeAdjustment {
+            operation_id: OperationId::new(id),
+            fee_payer: H160::from_slice(&[fee_payer; 20]),
+            tx_hash: H256::from_slice(&[1; 32]),
+            charged: 100u64.into(),
+            actual: 70u64.into(),
+            amount,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn pending_orders_are_filtered_by_contract() {
+        let mut storage = storage();
+        storage.add_pending(pending(1, 1));
+        storage.add_pending(pending(2, 2));
+        storage.add_pending(pending(3, 1));
+        assert_eq!(storage.pending_len(), 3);
+
+        let contract = H160::from_slice(&[1; 20]);
+        assert_eq!(
+            storage.get_pending(&contract, 10),
+            vec![pending(1, 1), pending(3, 1)]
+        );
+        assert_eq!(storage.get_pending(&contract, 1), vec![pending(1, 1)]);
+
+        storage.remove_pending(OperationId::new(1));
+        assert_eq!(storage.get_pending(&contract, 10), vec![pending(3, 1)]);
+    }
+
+    /// Mint transaction with the orders of the given nonces, which costs `total`.
+    /// Fee payer of every order is the address filled with the order nonce.
+    fn mint_tx_cost(nonces: &[u32], total: u64) -> MintTxCost {
+        let mut orders_data = vec![];
+        for nonce in nonces {
+            let mut order = vec![0; MintOrder::ENCODED_DATA_SIZE];
+            order[136..140].copy_from_slice(&nonce.to_be_bytes());
+            order[257..277].copy_from_slice(&[*nonce as u8; 20]);
+            orders_data.extend(order);
+        }
+
+        MintTxCost {
+            total: total.into(),
+            minted_orders: nonces.len() as u64,
+            orders: SignedOrdersData {
+                orders_data,
+                signature: vec![0; 65],
+            },
+        }
+    }
+
+    fn settings(max_debit: u64) -> FeeReconciliationSettings {
+        FeeReconciliationSettings {
+            max_debit: max_debit.into(),
+        }
+    }
+
+    #[test]
+    fn overcharged_fee_is_credited() {
+        let cost = mint_tx_cost(&[1, 2], 140);
+        let adjustment = reconcile(&pending(1, 1), &cost, &settings(50), 7).unwrap();
+        assert_eq!(adjustment.fee_payer, H160::from_slice(&[1; 20]));
+        assert_eq!(adjustment.charged, 100u64.into());
+        assert_eq!(adjustment.actual, 70u64.into());
+        assert_eq!(adjustment.amount, FeeAdjustmentAmount::Credit(30u64.into()));
+        assert_eq!(adjustment.timestamp, 7);
+    }
+
+    #[test]
+    fn undercharged_fee_is_debited() {
+        let cost = mint_tx_cost(&[1, 2], 240);
+        let adjustment = reconcile(&pending(2, 1), &cost, &settings(50), 0).unwrap();
+        assert_eq!(adjustment.fee_payer, H160::from_slice(&[2; 20]));
+        assert_eq!(adjustment.actual, 120u64.into());
+        assert_eq!(adjustment.amount, FeeAdjustmentAmount::Debit(20u64.into()));
+    }
+
+    #[test]
+    fn debit_is_capped() {
+        let cost = mint_tx_cost(&[1], 1000);
+        let adjustment = reconcile(&pending(1, 1), &cost, &settings(50), 0).unwrap();
+        assert_eq!(adjustment.actual, 1000u64.into());
+        assert_eq!(adjustment.amount, FeeAdjustmentAmount::Debit(50u64.into()));
+    }
+
+    #[test]
+    fn order_missing_in_transaction_is_not_reconciled() {
+        let cost = mint_tx_cost(&[2, 3], 200);
+        assert!(reconcile(&pending(1, 1), &cost, &settings(50), 0).is_none());
+    }
+
+    #[test]
+    fn adjustments_are_indexed_by_fee_payer() {
+        let mut storage = storage();
+        storage.record(adjustment(1, 1, FeeAdjustmentAmount::Credit(30u64.into())));
+        storage.record(adjustment(2, 2, FeeAdjustmentAmount::Credit(10u64.into())));
+        storage.record(adjustment(3, 1, FeeAdjustmentAmount::Debit(20u64.into())));
+
+        let adjustments = storage.get_for_payer(&H160::from_slice(&[1; 20]), None);
+        assert_eq!(adjustments.balance.credited, 30u64.into());
+        assert_eq!(adjustments.balance.debited, 20u64.into());
+        assert_eq!(
+            adjustments.adjustments,
+            vec![
+                adjustment(1, 1, FeeAdjustmentAmount::Credit(30u64.into())),
+                adjustment(3, 1, FeeAdjustmentAmount::Debit(20u64.into())),
+            ]
+        );
+
+        let page = storage.get_for_payer(&H160::from_slice(&[1; 20]), Some(Pagination::new(1, 10)));
+        assert_eq!(page.adjustments.len(), 1);
+        assert_eq!(page.adjustments[0].operation_id, OperationId::new(3));
+
+        let unknown = storage.get_for_payer(&H160::from_slice(&[3; 20]), None);
+        assert_eq!(unknown, FeePayerAdjustments::default());
+    }
+
+    #[test]
+    fn adjustments_page_is_capped() {
+        let mut storage = storage();
+        let count = MAX_FEE_ADJUSTMENTS_PER_REQUEST as u64 + 1;
+        for id in 0..count {
+            storage.record(adjustment(id, 1, FeeAdjustmentAmount::Credit(1u64.into())));
+        }
+
+        let fee_payer = H160::from_slice(&[1; 20]);
+        let adjustments = storage.get_for_payer(&fee_payer, None);
+        assert_eq!(adjustments.balance.credited, count.into());
+        assert_eq!(
+            adjustments.adjustments.len(),
+            MAX_FEE_ADJUSTMENTS_PER_REQUEST
+        );
+
+        let page = storage.get_for_payer(&fee_payer, Some(Pagination::new(0, usize::MAX)));
+        assert_eq!(page.adjustments.len(), MAX_FEE_ADJUSTMENTS_PER_REQUEST);
+    }
+}
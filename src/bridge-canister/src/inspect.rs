@@ -1,3 +1,8 @@
+use bridge_did::event_filter::EventTopicFilter;
+use bridge_did::gas_balance::GasBalanceThresholds;
+use bridge_did::operation_replay::ReplayCaptureSettings;
+use bridge_did::outbox::OutboxDestination;
+use bridge_did::retry_profile::RetryProfiles;
 use candid::Principal;
 use ic_exports::ic_cdk::api;
 use ic_exports::ic_kit::ic;
@@ -16,12 +21,58 @@ pub fn bridge_inspect() {
     match method.as_str() {
         "set_logger_filter" => inspect_set_logger_filter(config),
         "ic_logs" => inspect_ic_logs(config),
+        "get_logs_for_operation" => inspect_get_logs_for_operation(config),
         "set_owner" => inspect_set_owner(config),
         "set_btf_bridge_contract" => inspect_set_btf_bridge_contract(config),
+        "set_nonce_offset" => inspect_set_nonce_offset(config),
+        "get_operation_raw_bytes" => inspect_get_operation_raw_bytes(config),
+        "verify_operation_consistency" => inspect_verify_operation_consistency(config),
+        "set_gas_balance_thresholds" => inspect_set_gas_balance_thresholds(config),
+        "set_operation_log_compression" => inspect_set_operation_log_compression(config),
+        "set_strict_event_validation" => inspect_set_strict_event_validation(config),
+        "set_timers_enabled" => inspect_set_timers_enabled(config),
+        "get_dead_letter_events" => inspect_get_dead_letter_events(config),
+        "get_evm_params_history" => inspect_get_evm_params_history(config),
+        "set_evm_params_history_size" => inspect_set_evm_params_history_size(config),
+        "set_max_acceptable_evm_latency_ms" => inspect_set_max_acceptable_evm_latency_ms(config),
+        "set_gas_price_refresh_interval" => inspect_set_gas_price_refresh_interval(config),
+        "set_operation_concurrency_limits" => inspect_set_operation_concurrency_limits(config),
+        "get_private_tx_relay" => inspect_get_private_tx_relay(config),
+        "set_private_tx_relay" => inspect_set_private_tx_relay(config),
+        "set_dropped_tx_timeout" => inspect_set_dropped_tx_timeout(config),
+        "set_rpc_request_timeout" => inspect_set_rpc_request_timeout(config),
+        "set_http_transform_settings" => inspect_set_http_transform_settings(config),
+        "set_retry_profiles" => inspect_set_retry_profiles(config),
+        "set_replay_capture_settings" => inspect_set_replay_capture_settings(config),
+        "get_operation_replay" => inspect_get_operation_replay(config),
+        "set_operation_replay_capture" => inspect_set_operation_replay_capture(config),
+        "set_operations_list_default_limit" => inspect_set_operations_list_default_limit(config),
+        "set_fee_reconciliation" => inspect_set_fee_reconciliation(config),
+        "set_event_topic_filter" => inspect_set_event_topic_filter(config),
+        "resume_bridge" => inspect_resume_bridge(config),
+        "acknowledge_nonce_gap" => inspect_acknowledge_nonce_gap(config),
+        "promote_to_primary" => inspect_promote_to_primary(config),
+        "get_notification_destinations" => inspect_get_notification_destinations(config),
+        "set_notification_destinations" => inspect_set_notification_destinations(config),
+        "get_parked_outbox_messages" => inspect_get_parked_outbox_messages(config),
+        "requeue_outbox_message" => inspect_requeue_outbox_message(config),
+        "drop_outbox_message" => inspect_drop_outbox_message(config),
+        "bulk_retry" | "bulk_cancel" | "bulk_approve" => inspect_bulk_action(config),
+        "get_operator_audit_log" => inspect_get_operator_audit_log(config),
+        "add_operator_audit_note" => inspect_add_operator_audit_note(config),
+        "anonymize_user_data" => inspect_anonymize_user_data(config),
         _ => {}
     }
 }
 
+/// Inspects if the canister is the primary. The methods, which create or progress
+/// the operations, are disabled in the replica mode.
+pub fn inspect_primary_mode(config: SharedConfig) {
+    if config.borrow().is_replica() {
+        ic::trap("The method is disabled in the replica mode");
+    }
+}
+
 /// Inspects if owner principal is not an anonymous.
 pub fn inspect_new_owner_is_valid(new_owner: Principal) {
     if new_owner == Principal::anonymous() {
@@ -36,6 +87,13 @@ pub fn inspect_ic_logs(config: SharedConfig) {
     inspect_caller_is_owner(owner, caller)
 }
 
+/// Inspect check for `get_logs_for_operation` API method.
+pub fn inspect_get_logs_for_operation(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
 /// Inspect check for `set_logger_filter` API method.
 pub fn inspect_set_logger_filter(config: SharedConfig) {
     let caller = ic::caller();
@@ -64,6 +122,305 @@ pub fn inspect_set_btf_bridge_contract(config: SharedConfig) {
     inspect_caller_is_owner(owner, caller)
 }
 
+/// Inspect check for `set_nonce_offset` API method.
+pub fn inspect_set_nonce_offset(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `get_operation_raw_bytes` API method.
+pub fn inspect_get_operation_raw_bytes(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `verify_operation_consistency` API method.
+pub fn inspect_verify_operation_consistency(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_operation_log_compression` API method.
+pub fn inspect_set_operation_log_compression(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_strict_event_validation` API method.
+pub fn inspect_set_strict_event_validation(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_timers_enabled` API method.
+pub fn inspect_set_timers_enabled(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_gas_price_refresh_interval` API method.
+pub fn inspect_set_gas_price_refresh_interval(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_operation_concurrency_limits` API method.
+pub fn inspect_set_operation_concurrency_limits(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `resume_bridge` API method.
+pub fn inspect_resume_bridge(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `promote_to_primary` API method.
+pub fn inspect_promote_to_primary(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `acknowledge_nonce_gap` API method.
+pub fn inspect_acknowledge_nonce_gap(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_operations_list_default_limit` API method.
+pub fn inspect_set_operations_list_default_limit(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_dropped_tx_timeout` API method.
+pub fn inspect_set_dropped_tx_timeout(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_rpc_request_timeout` API method.
+pub fn inspect_set_rpc_request_timeout(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_http_transform_settings` API method.
+pub fn inspect_set_http_transform_settings(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_retry_profiles` API method.
+pub fn inspect_set_retry_profiles(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_replay_capture_settings` API method.
+pub fn inspect_set_replay_capture_settings(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `get_operation_replay` API method.
+pub fn inspect_get_operation_replay(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_operation_replay_capture` API method.
+pub fn inspect_set_operation_replay_capture(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_fee_reconciliation` API method.
+pub fn inspect_set_fee_reconciliation(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_event_topic_filter` API method.
+pub fn inspect_set_event_topic_filter(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `get_private_tx_relay` API method.
+pub fn inspect_get_private_tx_relay(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_private_tx_relay` API method.
+pub fn inspect_set_private_tx_relay(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `get_dead_letter_events` API method.
+pub fn inspect_get_dead_letter_events(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `get_evm_params_history` API method.
+pub fn inspect_get_evm_params_history(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_evm_params_history_size` API method.
+pub fn inspect_set_evm_params_history_size(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_max_acceptable_evm_latency_ms` API method.
+pub fn inspect_set_max_acceptable_evm_latency_ms(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `get_notification_destinations` API method.
+pub fn inspect_get_notification_destinations(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_notification_destinations` API method.
+pub fn inspect_set_notification_destinations(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `get_parked_outbox_messages` API method.
+pub fn inspect_get_parked_outbox_messages(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `requeue_outbox_message` API method.
+pub fn inspect_requeue_outbox_message(config: SharedConfig) {
+    inspect_primary_mode(config.clone());
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `drop_outbox_message` API method.
+pub fn inspect_drop_outbox_message(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `bulk_retry`, `bulk_cancel` and `bulk_approve` API methods.
+pub fn inspect_bulk_action(config: SharedConfig) {
+    inspect_primary_mode(config.clone());
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `get_operator_audit_log` API method.
+pub fn inspect_get_operator_audit_log(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `add_operator_audit_note` API method.
+pub fn inspect_add_operator_audit_note(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `anonymize_user_data` API method.
+pub fn inspect_anonymize_user_data(config: SharedConfig) {
+    inspect_primary_mode(config.clone());
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspect check for `set_gas_balance_thresholds` API method.
+pub fn inspect_set_gas_balance_thresholds(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspects if the critical watermark is not greater than the low watermark.
+pub fn inspect_gas_balance_thresholds_are_valid(thresholds: Option<&GasBalanceThresholds>) {
+    if thresholds.is_some_and(|thresholds| !thresholds.is_valid()) {
+        ic::trap("Critical gas balance watermark cannot be greater than the low watermark");
+    }
+}
+
+/// Inspects if the event topic filter is valid.
+pub fn inspect_event_topic_filter_is_valid(filter: Option<&EventTopicFilter>) {
+    if let Some(filter) = filter.filter(|filter| !filter.is_valid()) {
+        ic::trap(&format!("Invalid event topic filter: {filter:?}"));
+    }
+}
+
+/// Inspects if the retry profiles are valid.
+pub fn inspect_retry_profiles_are_valid(profiles: Option<&RetryProfiles>) {
+    if let Some(profiles) = profiles.filter(|profiles| !profiles.is_valid()) {
+        ic::trap(&format!("Invalid retry profiles: {profiles:?}"));
+    }
+}
+
+/// Inspects if the replay capture settings are valid.
+pub fn inspect_replay_capture_settings_are_valid(settings: Option<&ReplayCaptureSettings>) {
+    if let Some(settings) = settings.filter(|settings| !settings.is_valid()) {
+        ic::trap(&format!("Invalid replay capture settings: {settings:?}"));
+    }
+}
+
+/// Inspects if the operation notifications can be delivered to the destinations.
+pub fn inspect_notification_destinations_are_valid(destinations: &[OutboxDestination]) {
+    if let Some(destination) = destinations.iter().find(|d| !d.is_valid()) {
+        ic::trap(&format!(
+            "Invalid notification destination: {destination:?}"
+        ));
+    }
+}
+
 /// Checks if the caller is the owner.
 pub fn inspect_caller_is_owner(owner: Principal, caller: Principal) {
     if ic::caller() != owner {
@@ -10,10 +10,20 @@
 pub mod bridge;
 mod build_data;
 mod canister;
+pub mod dead_letter;
+pub mod fee_adjustments;
 pub mod inspect;
+pub mod log_span;
 pub mod memory;
+pub mod operation_blocks;
+pub mod operation_replay;
 pub mod operation_store;
+pub mod operator_audit;
+pub mod outbox;
+pub mod pending_mint_batches;
+pub mod processed_events;
 pub mod runtime;
+pub mod task_payloads;
 
 pub use canister::BridgeCanister;
 pub use inspect::bridge_inspect;
@@ -0,0 +1,346 @@
+//! Correlation of the canister logs with operations.
+//!
+//! The logs are written by the `ic-log` in-memory writer, so records can't be modified on write.
+//! Instead, an [`OperationSpan`] remembers the offsets of the log records written while it is
+//! active, and the correlation prefix is added to the records when they are read with
+//! [`logs_for_operation`].
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::ops::Range;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use bridge_did::op_id::OperationId;
+use ic_log::writer::Log;
+
+/// Max number of log ranges stored for all operations.
+pub const MAX_SPAN_RECORDS: usize = 4096;
+
+/// Source of the log records to correlate.
+pub trait LogSource {
+    /// Offset of the next log record.
+    fn next_offset(&self) -> usize;
+
+    /// Returns up to `count` log records, starting from the `from_offset`.
+    fn records(&self, from_offset: usize, count: usize) -> Vec<Log>;
+}
+
+/// In-memory log records of the `ic-log` writer.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct InMemoryLogs;
+
+impl LogSource for InMemoryLogs {
+    fn next_offset(&self) -> usize {
+        ic_log::take_memory_records(0, 0).all_logs_count
+    }
+
+    fn records(&self, from_offset: usize, count: usize) -> Vec<Log> {
+        ic_log::take_memory_records(count, from_offset).logs
+    }
+}
+
+/// Part of an operation execution, to which the logs are correlated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperationSpan {
+    pub op_id: OperationId,
+    pub step: &'static str,
+}
+
+impl OperationSpan {
+    /// Creates a span for the given operation step.
+    pub fn new(op_id: OperationId, step: &'static str) -> Self {
+        Self { op_id, step }
+    }
+
+    /// Makes the span active until the returned guard is dropped.
+    pub fn enter(self) -> SpanGuard {
+        let previous = SPANS.with(|spans| spans.borrow_mut().enter(self));
+        SpanGuard { previous }
+    }
+
+    /// Runs the `f` inside the span.
+    pub fn in_scope<R>(self, f: impl FnOnce() -> R) -> R {
+        let _guard = self.enter();
+        f()
+    }
+
+    /// Makes the span active on every poll of the `future`.
+    pub fn instrument<F: Future>(self, future: F) -> Instrumented<F> {
+        Instrumented {
+            span: self,
+            future: Box::pin(future),
+        }
+    }
+}
+
+impl fmt::Display for OperationSpan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[op#{} {}]", self.op_id, self.step)
+    }
+}
+
+/// Restores the previously active span on drop.
+pub struct SpanGuard {
+    previous: Option<OperationSpan>,
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        SPANS.with(|spans| spans.borrow_mut().exit(self.previous));
+    }
+}
+
+/// Future, which is polled inside the span.
+pub struct Instrumented<F> {
+    span: OperationSpan,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Instrumented<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let _guard = this.span.enter();
+        this.future.as_mut().poll(cx)
+    }
+}
+
+/// Returns up to `count` latest log records of the operation, prefixed with the span.
+pub fn logs_for_operation(op_id: OperationId, count: usize) -> Vec<Log> {
+    SPANS.with(|spans| spans.borrow().logs_for_operation(op_id, count))
+}
+
+/// Sets the source of the log records. Spans recorded for the previous source are discarded.
+pub fn set_log_source(source: Rc<dyn LogSource>) {
+    SPANS.with(|spans| *spans.borrow_mut() = SpanLogs::new(source));
+}
+
+thread_local! {
+    static SPANS: RefCell<SpanLogs> = RefCell::new(SpanLogs::new(Rc::new(InMemoryLogs)));
+}
+
+/// Bounded list of log ranges, written inside spans.
+struct SpanLogs {
+    source: Rc<dyn LogSource>,
+    active: Option<(OperationSpan, usize)>,
+    records: VecDeque<(OperationSpan, Range<usize>)>,
+}
+
+impl SpanLogs {
+    fn new(source: Rc<dyn LogSource>) -> Self {
+        Self {
+            source,
+            active: None,
+            records: VecDeque::new(),
+        }
+    }
+
+    fn enter(&mut self, span: OperationSpan) -> Option<OperationSpan> {
+        let offset = self.source.next_offset();
+        let previous = self.active.replace((span, offset));
+        previous.map(|(previous, start)| {
+            self.record(previous, start..offset);
+            previous
+        })
+    }
+
+    fn exit(&mut self, previous: Option<OperationSpan>) {
+        let offset = self.source.next_offset();
+        if let Some((span, start)) = self.active.take() {
+            self.record(span, start..offset);
+        }
+        self.active = previous.map(|previous| (previous, offset));
+    }
+
+    fn record(&mut self, span: OperationSpan, logs: Range<usize>) {
+        if logs.is_empty() {
+            return;
+        }
+
+        if let Some((last_span, last_logs)) = self.records.back_mut() {
+            if *last_span == span && last_logs.end == logs.start {
+                last_logs.end = logs.end;
+                return;
+            }
+        }
+
+        if self.records.len() == MAX_SPAN_RECORDS {
+            self.records.pop_front();
+        }
+        self.records.push_back((span, logs));
+    }
+
+    fn logs_for_operation(&self, op_id: OperationId, count: usize) -> Vec<Log> {
+        let spans: Vec<_> = self
+            .records
+            .iter()
+            .filter(|(span, _)| span.op_id == op_id)
+            .collect();
+
+        let Some((_, first)) = spans.first() else {
+            return vec![];
+        };
+
+        let records = self
+            .source
+            .records(first.start, self.source.next_offset() - first.start);
+        let mut logs: Vec<_> = records
+            .into_iter()
+            .filter_map(|record| {
+                let (span, _) = spans
+                    .iter()
+                    .find(|(_, logs)| logs.contains(&record.offset))?;
+                Some(Log {
+                    log: format!("{span} {}", record.log),
+                    offset: record.offset,
+                })
+            })
+            .collect();
+
+        let skip = logs.len().saturating_sub(count);
+        logs.drain(..skip);
+        logs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct TestLogs(RefCell<Vec<String>>);
+
+    impl TestLogs {
+        fn write(&self, log: String) {
+            self.0.borrow_mut().push(log);
+        }
+    }
+
+    impl LogSource for TestLogs {
+        fn next_offset(&self) -> usize {
+            self.0.borrow().len()
+        }
+
+        fn records(&self, from_offset: usize, count: usize) -> Vec<Log> {
+            self.0
+                .borrow()
+                .iter()
+                .enumerate()
+                .skip(from_offset)
+                .take(count)
+                .map(|(offset, log)| Log {
+                    log: log.clone(),
+                    offset,
+                })
+                .collect()
+        }
+    }
+
+    async fn mock_operation(logs: Rc<TestLogs>, name: &str) {
+        for step in 0..3 {
+            logs.write(format!("{name} step {step}"));
+            tokio::task::yield_now().await;
+        }
+    }
+
+    fn lines(logs: Vec<Log>) -> Vec<String> {
+        logs.into_iter().map(|log| log.log).collect()
+    }
+
+    #[tokio::test]
+    async fn interleaved_operations_logs_are_separated() {
+        let logs = Rc::new(TestLogs::default());
+        set_log_source(logs.clone());
+
+        let first = OperationSpan::new(OperationId::new(1), "progress")
+            .instrument(mock_operation(logs.clone(), "first"));
+        let second = OperationSpan::new(OperationId::new(2), "progress")
+            .instrument(mock_operation(logs.clone(), "second"));
+        logs.write("unrelated".into());
+        tokio::join!(first, second);
+
+        assert_eq!(
+            lines(logs_for_operation(OperationId::new(1), 10)),
+            vec![
+                "[op#1 progress] first step 0",
+                "[op#1 progress] first step 1",
+                "[op#1 progress] first step 2",
+            ]
+        );
+        assert_eq!(
+            lines(logs_for_operation(OperationId::new(2), 10)),
+            vec![
+                "[op#2 progress] second step 0",
+                "[op#2 progress] second step 1",
+                "[op#2 progress] second step 2",
+            ]
+        );
+        assert_eq!(logs.next_offset(), 7);
+    }
+
+    #[test]
+    fn nested_span_logs_are_not_attributed_to_outer_span() {
+        let logs = Rc::new(TestLogs::default());
+        set_log_source(logs.clone());
+
+        OperationSpan::new(OperationId::new(1), "progress").in_scope(|| {
+            logs.write("outer before".into());
+            OperationSpan::new(OperationId::new(2), "sign_mint_order")
+                .in_scope(|| logs.write("inner".into()));
+            logs.write("outer after".into());
+        });
+
+        assert_eq!(
+            lines(logs_for_operation(OperationId::new(1), 10)),
+            vec![
+                "[op#1 progress] outer before",
+                "[op#1 progress] outer after"
+            ]
+        );
+        assert_eq!(
+            lines(logs_for_operation(OperationId::new(2), 10)),
+            vec!["[op#2 sign_mint_order] inner"]
+        );
+    }
+
+    #[test]
+    fn returns_latest_logs() {
+        let logs = Rc::new(TestLogs::default());
+        set_log_source(logs.clone());
+
+        OperationSpan::new(OperationId::new(1), "progress").in_scope(|| {
+            for i in 0..5 {
+                logs.write(format!("log {i}"));
+            }
+        });
+
+        assert_eq!(
+            lines(logs_for_operation(OperationId::new(1), 2)),
+            vec!["[op#1 progress] log 3", "[op#1 progress] log 4"]
+        );
+        assert!(logs_for_operation(OperationId::new(2), 2).is_empty());
+    }
+
+    #[test]
+    fn span_records_are_bounded() {
+        let logs = Rc::new(TestLogs::default());
+        set_log_source(logs.clone());
+
+        for id in 0..MAX_SPAN_RECORDS as u64 + 10 {
+            OperationSpan::new(OperationId::new(id), "progress")
+                .in_scope(|| logs.write(format!("op {id}")));
+        }
+
+        SPANS.with(|spans| assert_eq!(spans.borrow().records.len(), MAX_SPAN_RECORDS));
+        assert!(logs_for_operation(OperationId::new(0), 10).is_empty());
+        assert_eq!(
+            lines(logs_for_operation(OperationId::new(10), 10)),
+            vec!["[op#10 progress] op 10"]
+        );
+    }
+}
@@ -11,6 +11,24 @@ pub const PENDING_TASKS_MEMORY_ID: MemoryId = MemoryId::new(6);
 pub const LOG_SETTINGS_MEMORY_ID: MemoryId = MemoryId::new(7);
 pub const MEMO_OPERATION_MEMORY_ID: MemoryId = MemoryId::new(8);
 pub const PENDING_TASKS_SEQUENCE_MEMORY_ID: MemoryId = MemoryId::new(9);
+pub const OUTBOX_ID_COUNTER_MEMORY_ID: MemoryId = MemoryId::new(30);
+pub const OUTBOX_PENDING_MEMORY_ID: MemoryId = MemoryId::new(31);
+pub const OUTBOX_PARKED_MEMORY_ID: MemoryId = MemoryId::new(32);
+pub const DEAD_LETTER_EVENTS_MEMORY_ID: MemoryId = MemoryId::new(33);
+pub const OPERATION_BLOCKS_MEMORY_ID: MemoryId = MemoryId::new(34);
+pub const PROCESSED_EVENTS_MEMORY_ID: MemoryId = MemoryId::new(35);
+pub const PROCESSED_EVENT_SENDERS_MEMORY_ID: MemoryId = MemoryId::new(36);
+pub const PROCESSED_EVENTS_BY_SENDER_MEMORY_ID: MemoryId = MemoryId::new(37);
+pub const OPERATOR_AUDIT_MEMORY_ID: MemoryId = MemoryId::new(38);
+pub const PENDING_FEE_RECONCILIATIONS_MEMORY_ID: MemoryId = MemoryId::new(39);
+pub const FEE_ADJUSTMENTS_BY_PAYER_MEMORY_ID: MemoryId = MemoryId::new(40);
+pub const FEE_ADJUSTMENT_BALANCES_MEMORY_ID: MemoryId = MemoryId::new(41);
+pub const TASK_PAYLOADS_MEMORY_ID: MemoryId = MemoryId::new(42);
+pub const PENDING_MINT_BATCHES_MEMORY_ID: MemoryId = MemoryId::new(46);
+pub const OPERATION_REPLAYS_MEMORY_ID: MemoryId = MemoryId::new(51);
+pub const OPERATION_REPLAY_CAPTURE_MEMORY_ID: MemoryId = MemoryId::new(52);
+pub const OUTBOX_DUE_MEMORY_ID: MemoryId = MemoryId::new(53);
+pub const OPERATION_BLOCKS_INDEX_MEMORY_ID: MemoryId = MemoryId::new(54);
 
 pub type StableMemory = VirtualMemory<DefaultMemoryImpl>;
 
@@ -0,0 +1,554 @@
+//! Append-only log of the operation state transitions. The blocks are linked into a hash chain
+//! as defined by ICRC-3, so auditors can verify that the exported log is not altered.
+
+use std::borrow::Cow;
+
+use bridge_did::bridge_mode::OperationBlocksExport;
+use bridge_did::data_erasure::AnonymizationMarker;
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::icrc3::{BlockWithId, GetBlocksResult};
+use bridge_did::op_id::OperationId;
+use bridge_did::operation_block::OperationBlock;
+use candid::Nat;
+use did::H160;
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, Bound, StableBTreeMap, Storable};
+
+/// Max number of the blocks returned by a single request.
+pub const MAX_BLOCKS_PER_REQUEST: u64 = 100;
+
+/// Default max number of the blocks kept in the log.
+pub const DEFAULT_MAX_STORED_BLOCKS: u64 = 1_000_000;
+
+/// Memory objects to store the operation blocks.
+#[derive(Clone)]
+pub struct OperationBlocksMemory<Mem> {
+    pub blocks: Mem,
+    pub operation_index: Mem,
+}
+
+/// Key of the block in the index of the blocks by the operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct OperationBlockKey {
+    operation_id: u64,
+    index: u64,
+}
+
+impl OperationBlockKey {
+    const SIZE: usize = 16;
+
+    fn new(operation_id: OperationId, index: u64) -> Self {
+        Self {
+            operation_id: operation_id.as_u64(),
+            index,
+        }
+    }
+}
+
+impl Storable for OperationBlockKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        // Big endian numbers keep the byte order equal to the key order.
+        bytes.extend_from_slice(&self.operation_id.to_be_bytes());
+        bytes.extend_from_slice(&self.index.to_be_bytes());
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let u64_at = |offset: usize| {
+            u64::from_be_bytes(
+                bytes[offset..offset + 8]
+                    .try_into()
+                    .expect("invalid operation block key size"),
+            )
+        };
+
+        Self {
+            operation_id: u64_at(0),
+            index: u64_at(8),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: Self::SIZE as u32,
+        is_fixed_size: true,
+    };
+}
+
+/// Log of the operation blocks, stored in IC stable memory. Indexes of the blocks are
+/// sequential.
+///
+/// Only the latest blocks are kept: when the log grows over the max number of the stored
+/// blocks, the oldest ones are removed. The removed blocks are not available anymore, but
+/// they are still counted in the log length, and the oldest kept block is linked to them.
+pub struct OperationBlockLog<M: Memory> {
+    blocks: StableBTreeMap<u64, OperationBlock, M>,
+    operation_index: StableBTreeMap<OperationBlockKey, (), M>,
+    max_stored_blocks: u64,
+}
+
+impl<M: Memory> OperationBlockLog<M> {
+    /// Creates a new instance of the log, which keeps up to [`DEFAULT_MAX_STORED_BLOCKS`].
+    pub fn with_memory(memory: OperationBlocksMemory<M>) -> Self {
+        let mut log = Self {
+            blocks: StableBTreeMap::new(memory.blocks),
+            operation_index: StableBTreeMap::new(memory.operation_index),
+            max_stored_blocks: DEFAULT_MAX_STORED_BLOCKS,
+        };
+
+        if log.operation_index.is_empty() {
+            for (index, block) in log.blocks.iter() {
+                log.operation_index
+                    .insert(OperationBlockKey::new(block.operation_id, index), ());
+            }
+        }
+
+        log
+    }
+
+    /// Sets the max number of the blocks kept in the log. Should be positive.
+    pub fn with_max_stored_blocks(mut self, max_stored_blocks: u64) -> Self {
+        self.max_stored_blocks = max_stored_blocks.max(1);
+        self
+    }
+
+    /// Appends the block of the operation state transition, linked to the latest block.
+    /// Returns index of the block.
+    pub fn append(
+        &mut self,
+        operation_id: OperationId,
+        wallet_address: H160,
+        step: Result<Vec<u8>, String>,
+        timestamp: u64,
+    ) -> u64 {
+        let index = self.len();
+        let parent_hash = index
+            .checked_sub(1)
+            .and_then(|parent| self.blocks.get(&parent))
+            .map(|parent| parent.hash());
+
+        self.insert(
+            index,
+            OperationBlock {
+                parent_hash,
+                timestamp,
+                operation_id,
+                wallet_address,
+                step,
+                anonymization: None,
+                redacted_hash: None,
+            },
+        );
+
+        index
+    }
+
+    /// Redacts the blocks of the operation with the pseudonym of the `marker` and appends
+    /// the erasure block of the operation. Returns index of the erasure block.
+    pub fn append_erasure(
+        &mut self,
+        operation_id: OperationId,
+        marker: AnonymizationMarker,
+        timestamp: u64,
+    ) -> u64 {
+        self.redact_operation(operation_id, &marker.pseudonym);
+
+        let index = self.len();
+        let parent_hash = index
+            .checked_sub(1)
+            .and_then(|parent| self.blocks.get(&parent))
+            .map(|parent| parent.hash());
+
+        self.insert(
+            index,
+            OperationBlock {
+                parent_hash,
+                timestamp,
+                operation_id,
+                wallet_address: marker.pseudonym.clone(),
+                step: Ok(vec![]),
+                anonymization: Some(marker),
+                redacted_hash: None,
+            },
+        );
+
+        index
+    }
+
+    /// Redacts the stored blocks of the operation, keeping their hashes.
+    fn redact_operation(&mut self, operation_id: OperationId, pseudonym: &H160) {
+        let indexes: Vec<u64> = self
+            .operation_index
+            .range(OperationBlockKey::new(operation_id, 0)..)
+            .take_while(|(key, _)| key.operation_id == operation_id.as_u64())
+            .map(|(key, _)| key.index)
+            .collect();
+
+        for index in indexes {
+            if let Some(mut block) = self.blocks.get(&index) {
+                block.redact(pseudonym);
+                self.blocks.insert(index, block);
+            }
+        }
+    }
+
+    fn insert(&mut self, index: u64, block: OperationBlock) {
+        self.operation_index
+            .insert(OperationBlockKey::new(block.operation_id, index), ());
+        self.blocks.insert(index, block);
+        self.remove_oldest_blocks();
+    }
+
+    /// Removes the oldest blocks over the max number of the stored blocks.
+    fn remove_oldest_blocks(&mut self) {
+        while self.blocks.len() > self.max_stored_blocks {
+            let Some((oldest, block)) = self.blocks.iter().next() else {
+                return;
+            };
+            self.blocks.remove(&oldest);
+            self.operation_index
+                .remove(&OperationBlockKey::new(block.operation_id, oldest));
+        }
+    }
+
+    /// Checks if the block, exported from the log of another canister, can be imported
+    /// at the `index`. The block should have the next index of the log and be linked
+    /// to the latest block.
+    pub fn validate_import(&self, index: u64, block: &OperationBlock) -> BTFResult<()> {
+        if index != self.len() {
+            return Err(Error::FailedToProgress(format!(
+                "block {index} cannot be imported into the log of length {}",
+                self.len()
+            )));
+        }
+
+        let parent_hash = index
+            .checked_sub(1)
+            .and_then(|parent| self.blocks.get(&parent))
+            .map(|parent| parent.hash());
+        if block.parent_hash != parent_hash {
+            return Err(Error::FailedToProgress(format!(
+                "block {index} is not linked to the latest block of the log"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Appends the block, exported from the log of another canister, as is, so the hashes
+    /// of both logs are the same. See [`Self::validate_import`] for the requirements.
+    ///
+    /// An erasure block redacts the blocks of its operation, as it does in the source log.
+    pub fn import(&mut self, index: u64, block: OperationBlock) -> BTFResult<()> {
+        self.validate_import(index, &block)?;
+        if let Some(marker) = &block.anonymization {
+            self.redact_operation(block.operation_id, &marker.pseudonym);
+        }
+        self.insert(index, block);
+        Ok(())
+    }
+
+    /// Returns the block by its index.
+    pub fn get(&self, index: u64) -> Option<OperationBlock> {
+        self.blocks.get(&index)
+    }
+
+    /// Returns up to `length` blocks, starting from the `start` index.
+    /// Removed blocks of the range are skipped.
+    pub fn range(&self, start: u64, length: u64) -> Vec<(u64, OperationBlock)> {
+        let end = start.saturating_add(length).min(self.len());
+        (start.max(self.first_index())..end)
+            .filter_map(|index| self.blocks.get(&index).map(|block| (index, block)))
+            .collect()
+    }
+
+    /// Returns the blocks in the ICRC-3 `get_blocks` format.
+    /// At most [`MAX_BLOCKS_PER_REQUEST`] blocks are returned.
+    pub fn get_blocks(&self, start: &Nat, length: &Nat) -> GetBlocksResult {
+        let start = u64::try_from(&start.0).unwrap_or(u64::MAX);
+        let length = u64::try_from(&length.0)
+            .unwrap_or(u64::MAX)
+            .min(MAX_BLOCKS_PER_REQUEST);
+
+        let blocks = self
+            .range(start, length)
+            .into_iter()
+            .map(|(index, block)| BlockWithId {
+                id: index.into(),
+                block: block.to_value(),
+            })
+            .collect();
+
+        GetBlocksResult {
+            log_length: self.len().into(),
+            blocks,
+            archived_blocks: vec![],
+        }
+    }
+
+    /// Returns the blocks in the format, imported by the replica canisters.
+    /// At most [`MAX_BLOCKS_PER_REQUEST`] blocks are returned.
+    pub fn export(&self, start: u64, length: u64) -> OperationBlocksExport {
+        OperationBlocksExport {
+            log_length: self.len(),
+            blocks: self.range(start, length.min(MAX_BLOCKS_PER_REQUEST)),
+        }
+    }
+
+    /// Number of the blocks in the log, including the removed ones.
+    pub fn len(&self) -> u64 {
+        self.blocks
+            .last_key_value()
+            .map(|(index, _)| index + 1)
+            .unwrap_or_default()
+    }
+
+    /// Index of the oldest block kept in the log.
+    pub fn first_index(&self) -> u64 {
+        self.blocks
+            .iter()
+            .next()
+            .map(|(index, _)| index)
+            .unwrap_or_default()
+    }
+
+    /// Checks if the log is empty.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::icrc3::Icrc3Value;
+    use ic_stable_structures::VectorMemory;
+
+    use super::*;
+
+    fn wallet(seed: u8) -> H160 {
+        H160::from_slice(&[seed; 20])
+    }
+
+    fn memory() -> OperationBlocksMemory<VectorMemory> {
+        OperationBlocksMemory {
+            blocks: VectorMemory::default(),
+            operation_index: VectorMemory::default(),
+        }
+    }
+
+    fn marker(seed: u8) -> AnonymizationMarker {
+        AnonymizationMarker {
+            pseudonym: wallet(seed),
+            timestamp: 0,
+            audit_entry: 7,
+        }
+    }
+
+    fn log_with_blocks(count: u64) -> OperationBlockLog<VectorMemory> {
+        let mut log = OperationBlockLog::with_memory(memory());
+        for i in 0..count {
+            let step = match i % 3 {
+                2 => Err(format!("step {i} failed")),
+                _ => Ok(vec![i as u8; 4]),
+            };
+            log.append(OperationId::new(i / 3), wallet(i as u8), step, i * 10);
+        }
+        log
+    }
+
+    #[test]
+    fn blocks_form_hash_chain() {
+        let log = log_with_blocks(10);
+        assert_eq!(log.len(), 10);
+
+        let blocks = log.range(0, 10);
+        assert_eq!(blocks[0].1.parent_hash, None);
+        for pair in blocks.windows(2) {
+            assert_eq!(pair[1].1.parent_hash, Some(pair[0].1.hash()));
+        }
+
+        // ICRC-3 value of the block contains the parent hash.
+        let Icrc3Value::Map(entries) = blocks[1].1.to_value() else {
+            panic!("block value should be a map");
+        };
+        let parent_hash = blocks[0].1.hash().to_vec();
+        assert!(entries.contains(&("phash".to_string(), Icrc3Value::Blob(parent_hash))));
+    }
+
+    #[test]
+    fn altered_block_breaks_hash_chain() {
+        let log = log_with_blocks(3);
+        let mut altered = log.get(1).unwrap();
+        altered.step = Ok(vec![42]);
+
+        let next = log.get(2).unwrap();
+        assert_eq!(next.parent_hash, Some(log.get(1).unwrap().hash()));
+        assert_ne!(next.parent_hash, Some(altered.hash()));
+    }
+
+    #[test]
+    fn blocks_are_returned_by_range() {
+        let log = log_with_blocks(10);
+
+        let range = log.range(3, 4);
+        let indexes: Vec<u64> = range.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indexes, vec![3, 4, 5, 6]);
+        assert_eq!(range[0].1.timestamp, 30);
+
+        // The range is truncated by the log length.
+        assert_eq!(log.range(8, 10).len(), 2);
+        assert!(log.range(10, 10).is_empty());
+        assert!(log.range(u64::MAX, u64::MAX).is_empty());
+    }
+
+    #[test]
+    fn get_blocks_returns_icrc3_values() {
+        let log = log_with_blocks(MAX_BLOCKS_PER_REQUEST + 10);
+
+        let result = log.get_blocks(&Nat::from(5u64), &Nat::from(2u64));
+        assert_eq!(result.log_length, Nat::from(MAX_BLOCKS_PER_REQUEST + 10));
+        assert!(result.archived_blocks.is_empty());
+        assert_eq!(result.blocks.len(), 2);
+        assert_eq!(result.blocks[0].id, Nat::from(5u64));
+        assert_eq!(result.blocks[0].block, log.get(5).unwrap().to_value());
+
+        // The response size is limited.
+        let result = log.get_blocks(&Nat::from(0u64), &Nat::from(u128::MAX));
+        assert_eq!(result.blocks.len() as u64, MAX_BLOCKS_PER_REQUEST);
+
+        let result = log.get_blocks(&Nat::from(u128::MAX), &Nat::from(1u64));
+        assert!(result.blocks.is_empty());
+    }
+
+    #[test]
+    fn exported_blocks_are_imported() {
+        let source = log_with_blocks(MAX_BLOCKS_PER_REQUEST + 5);
+        let export = source.export(0, u64::MAX);
+        assert_eq!(export.log_length, MAX_BLOCKS_PER_REQUEST + 5);
+        assert_eq!(export.blocks.len() as u64, MAX_BLOCKS_PER_REQUEST);
+
+        let mut log = OperationBlockLog::with_memory(memory());
+        for (index, block) in source.export(0, 5).blocks {
+            log.import(index, block).unwrap();
+        }
+
+        assert_eq!(log.range(0, 5), source.range(0, 5));
+        assert_eq!(log.get(4).unwrap().hash(), source.get(4).unwrap().hash());
+    }
+
+    #[test]
+    fn unlinked_blocks_are_not_imported() {
+        let source = log_with_blocks(3);
+        let mut log = OperationBlockLog::with_memory(memory());
+
+        // Index gap.
+        assert!(log.import(1, source.get(1).unwrap()).is_err());
+
+        log.import(0, source.get(0).unwrap()).unwrap();
+        let mut altered = source.get(1).unwrap();
+        altered.parent_hash = Some([0; 32]);
+        assert!(log.import(1, altered).is_err());
+        assert_eq!(log.len(), 1);
+    }
+
+    #[test]
+    fn blocks_survive_upgrade() {
+        let memory = memory();
+        let mut log = OperationBlockLog::with_memory(memory.clone());
+        log.append(OperationId::new(0), wallet(1), Ok(vec![1]), 0);
+
+        let mut log = OperationBlockLog::with_memory(memory);
+        let index = log.append(OperationId::new(0), wallet(1), Ok(vec![2]), 1);
+        assert_eq!(index, 1);
+        assert_eq!(
+            log.get(1).unwrap().parent_hash,
+            Some(log.get(0).unwrap().hash())
+        );
+    }
+
+    #[test]
+    fn oldest_blocks_are_removed() {
+        let mut log = OperationBlockLog::with_memory(memory()).with_max_stored_blocks(3);
+        for i in 0..5 {
+            log.append(OperationId::new(i), wallet(1), Ok(vec![i as u8]), i);
+        }
+
+        assert_eq!(log.len(), 5);
+        assert_eq!(log.first_index(), 2);
+        assert!(log.get(1).is_none());
+        let indexes: Vec<u64> = log.range(0, 10).iter().map(|(index, _)| *index).collect();
+        assert_eq!(indexes, vec![2, 3, 4]);
+        assert_eq!(
+            log.get_blocks(&Nat::from(0u64), &Nat::from(10u64))
+                .log_length,
+            Nat::from(5u64)
+        );
+
+        // New blocks are still linked to the kept ones.
+        let index = log.append(OperationId::new(5), wallet(1), Ok(vec![5]), 5);
+        assert_eq!(index, 5);
+        assert_eq!(
+            log.get(5).unwrap().parent_hash,
+            Some(log.get(4).unwrap().hash())
+        );
+        assert_eq!(log.first_index(), 3);
+    }
+
+    #[test]
+    fn erased_operation_blocks_are_redacted() {
+        let mut log = log_with_blocks(6);
+        let hashes: Vec<_> = log.range(0, 6).iter().map(|(_, b)| b.hash()).collect();
+
+        let erasure = log.append_erasure(OperationId::new(0), marker(42), 100);
+        assert_eq!(erasure, 6);
+
+        for (index, block) in log.range(0, 3) {
+            assert!(block.is_redacted());
+            assert_eq!(block.wallet_address, wallet(42));
+            assert_eq!(block.step, Ok(vec![]));
+            assert_eq!(block.hash(), hashes[index as usize]);
+        }
+        // Blocks of the other operations are kept.
+        assert_eq!(log.get(3).unwrap().wallet_address, wallet(3));
+        assert!(!log.get(3).unwrap().is_redacted());
+
+        // The chain is still linked.
+        let blocks = log.range(0, 7);
+        for pair in blocks.windows(2) {
+            assert_eq!(pair[1].1.parent_hash, Some(pair[0].1.hash()));
+        }
+        let erasure = log.get(erasure).unwrap();
+        assert_eq!(erasure.anonymization, Some(marker(42)));
+        assert_eq!(erasure.wallet_address, wallet(42));
+    }
+
+    #[test]
+    fn imported_erasure_redacts_blocks() {
+        let mut source = log_with_blocks(3);
+        let mut log = OperationBlockLog::with_memory(memory());
+        for (index, block) in source.export(0, 3).blocks {
+            log.import(index, block).unwrap();
+        }
+
+        source.append_erasure(OperationId::new(0), marker(42), 100);
+        log.import(3, source.get(3).unwrap()).unwrap();
+
+        assert_eq!(log.range(0, 4), source.range(0, 4));
+        assert!(log.get(0).unwrap().is_redacted());
+    }
+
+    #[test]
+    fn operation_index_is_rebuilt() {
+        let memory = memory();
+        let mut log = OperationBlockLog::with_memory(memory.clone());
+        log.append(OperationId::new(0), wallet(1), Ok(vec![1]), 0);
+
+        // The blocks are stored before the index is added.
+        let mut log = OperationBlockLog::with_memory(OperationBlocksMemory {
+            blocks: memory.blocks,
+            operation_index: VectorMemory::default(),
+        });
+        log.append_erasure(OperationId::new(0), marker(42), 1);
+        assert!(log.get(0).unwrap().is_redacted());
+    }
+}
@@ -0,0 +1,423 @@
+//! Replay log of the operation progress invocations. For the captured operations, every
+//! invocation of [`Operation::progress`] is recorded with the input state, the digest of the
+//! observed context and the outcome, so the divergence of the operation state machine can be
+//! investigated and reproduced.
+//!
+//! Operations are captured if the operator enabled the capture for them, or if they are
+//! sampled by the [`ReplayCaptureSettings`] of the config. Replays of at most
+//! [`MAX_REPLAY_OPERATIONS`] operations are kept, the oldest operations are evicted.
+//!
+//! Responses of the EVM RPC and ICRC ledger calls, made by the progress, are not captured:
+//! the runtime doesn't intercept them, so the context digest contains only the runtime
+//! state. Replays of the progress, which makes such calls, need the calls to be stubbed
+//! by the test environment.
+
+use bridge_did::error::BTFResult;
+use bridge_did::op_id::OperationId;
+use bridge_did::operation_replay::{
+    OperationReplay, ProgressReplayEntry, ProgressReplayOutcome, ReplayCaptureSettings,
+    ReplayContextDigest,
+};
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, StableBTreeMap};
+
+use crate::bridge::{Operation, OperationProgress};
+
+/// Max number of the operations, which replays are kept.
+pub const MAX_REPLAY_OPERATIONS: u64 = 1_000;
+
+/// Memory regions of the operation replays.
+pub struct OperationReplaysMemory<M> {
+    pub replays: M,
+    pub captured: M,
+}
+
+/// Captured progress invocations of the operations, stored in IC stable memory.
+pub struct OperationReplays<M: Memory> {
+    replays: StableBTreeMap<OperationId, OperationReplay, M>,
+    /// Operations with the capture enabled by the operator, with the IC time of enabling.
+    captured: StableBTreeMap<OperationId, u64, M>,
+}
+
+impl<M: Memory> OperationReplays<M> {
+    /// Creates a new instance of the replays.
+    pub fn with_memory(memory: OperationReplaysMemory<M>) -> Self {
+        Self {
+            replays: StableBTreeMap::new(memory.replays),
+            captured: StableBTreeMap::new(memory.captured),
+        }
+    }
+
+    /// Enables or disables the capture of the operation, regardless of the sampling.
+    /// Already captured invocations are kept.
+    pub fn set_capture(&mut self, operation_id: OperationId, enabled: bool, timestamp: u64) {
+        if enabled {
+            self.captured.insert(operation_id, timestamp);
+        } else {
+            self.captured.remove(&operation_id);
+        }
+    }
+
+    /// Checks if the capture of the operation is enabled by the operator.
+    pub fn is_capture_enabled(&self, operation_id: OperationId) -> bool {
+        self.captured.contains_key(&operation_id)
+    }
+
+    /// Checks if the progress of the operation should be captured.
+    pub fn should_capture(
+        &self,
+        operation_id: OperationId,
+        settings: &ReplayCaptureSettings,
+    ) -> bool {
+        self.is_capture_enabled(operation_id) || settings.samples(operation_id)
+    }
+
+    /// Records the progress invocation of the operation, keeping at most `log_size` latest
+    /// invocations. If the replays exceed [`MAX_REPLAY_OPERATIONS`], the replay of the oldest
+    /// other operation is evicted.
+    pub fn record(
+        &mut self,
+        operation_id: OperationId,
+        timestamp: u64,
+        input: Vec<u8>,
+        context: ReplayContextDigest,
+        outcome: ProgressReplayOutcome,
+        log_size: u32,
+    ) {
+        let mut replay = self.replays.get(&operation_id).unwrap_or_default();
+        replay.push(timestamp, input, context, outcome, log_size as usize);
+        let is_new = self.replays.insert(operation_id, replay).is_none();
+
+        if is_new && self.replays.len() > MAX_REPLAY_OPERATIONS {
+            let oldest = self
+                .replays
+                .iter()
+                .map(|(id, _)| id)
+                .find(|id| *id != operation_id);
+            if let Some(oldest) = oldest {
+                self.replays.remove(&oldest);
+            }
+        }
+    }
+
+    /// Removes the captured progress invocations and the capture flag of the operation.
+    pub fn remove(&mut self, operation_id: OperationId) {
+        self.replays.remove(&operation_id);
+        self.captured.remove(&operation_id);
+    }
+
+    /// Returns the captured progress invocations of the operation, oldest first.
+    pub fn get(&self, operation_id: OperationId) -> Vec<ProgressReplayEntry> {
+        self.replays
+            .get(&operation_id)
+            .map(|replay| replay.entries)
+            .unwrap_or_default()
+    }
+}
+
+/// Encodes the operation for the replay log.
+pub fn encode_replay_operation<Op: Operation>(operation: &Op) -> Vec<u8> {
+    candid::encode_one(operation).expect("failed to encode operation")
+}
+
+/// Converts the result of the operation progress to the replay log outcome.
+pub fn replay_outcome<Op: Operation>(
+    result: &BTFResult<OperationProgress<Op>>,
+) -> ProgressReplayOutcome {
+    match result {
+        Ok(OperationProgress::Progress(operation)) => {
+            ProgressReplayOutcome::Progress(encode_replay_operation(operation))
+        }
+        Ok(OperationProgress::ProgressWithDependent {
+            update_to,
+            dependent,
+        }) => ProgressReplayOutcome::ProgressWithDependent {
+            update_to: encode_replay_operation(update_to),
+            dependent: encode_replay_operation(dependent),
+        },
+        Ok(OperationProgress::AddToService(service_id)) => {
+            ProgressReplayOutcome::AddToService(*service_id)
+        }
+        Err(e) => ProgressReplayOutcome::Error(e.clone()),
+    }
+}
+
+/// Difference of the replayed progress from the captured one.
+#[cfg(any(test, feature = "test_helpers"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayDivergence {
+    /// The captured input can't be decoded as the operation.
+    InvalidInput(String),
+    /// The context stub differs from the captured context.
+    Context {
+        captured: ReplayContextDigest,
+        replayed: ReplayContextDigest,
+    },
+    /// The progress outcome differs from the captured one.
+    Outcome {
+        captured: ProgressReplayOutcome,
+        replayed: ProgressReplayOutcome,
+    },
+}
+
+/// Re-executes the progress of the captured invocation against the runtime state `ctx`,
+/// which stubs the captured context, and checks that the outcome is the same.
+///
+/// Test-only harness, available with the `test_helpers` feature, to detect the divergence
+/// of the operations state machine on the captured fixtures after a code change.
+#[cfg(any(test, feature = "test_helpers"))]
+pub async fn replay_progress<Op: Operation>(
+    operation_id: OperationId,
+    entry: &ProgressReplayEntry,
+    ctx: crate::runtime::RuntimeState<Op>,
+) -> Result<(), ReplayDivergence> {
+    let operation: Op = candid::decode_one(&entry.input)
+        .map_err(|e| ReplayDivergence::InvalidInput(e.to_string()))?;
+
+    let context = ctx.borrow().config.borrow().get_replay_context_digest();
+    if context != entry.context {
+        return Err(ReplayDivergence::Context {
+            captured: entry.context.clone(),
+            replayed: context,
+        });
+    }
+
+    let result = operation.progress(operation_id, ctx).await;
+    let outcome = replay_outcome(&result);
+    if outcome != entry.outcome {
+        return Err(ReplayDivergence::Outcome {
+            captured: entry.outcome.clone(),
+            replayed: outcome,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use bridge_did::error::Error;
+    use bridge_did::operation_replay::FULL_REPLAY_SAMPLE_RATE_PPM;
+    use candid::CandidType;
+    use did::H160;
+    use ic_exports::ic_kit::MockContext;
+    use ic_stable_structures::VectorMemory;
+    use ic_storage::IcStorage;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::runtime::scheduler::BridgeTask;
+    use crate::runtime::state::config::ConfigStorage;
+    use crate::runtime::{BridgeRuntime, RuntimeState};
+
+    thread_local! {
+        /// Step of the operation transition, changed to simulate a code change.
+        static TRANSITION_STEP: Cell<u64> = const { Cell::new(1) };
+    }
+
+    #[derive(Debug, CandidType, Serialize, Deserialize, Clone, Eq, PartialEq)]
+    struct StepOperation {
+        step: u64,
+    }
+
+    impl Operation for StepOperation {
+        async fn progress(
+            self,
+            _id: OperationId,
+            _ctx: RuntimeState<Self>,
+        ) -> BTFResult<OperationProgress<Self>> {
+            if self.step >= 3 {
+                return Err(Error::FailedToProgress("last step".into()));
+            }
+
+            Ok(OperationProgress::Progress(Self {
+                step: self.step + TRANSITION_STEP.with(Cell::get),
+            }))
+        }
+
+        fn is_complete(&self) -> bool {
+            false
+        }
+
+        fn evm_wallet_address(&self) -> H160 {
+            H160::from_slice(&[1; 20])
+        }
+    }
+
+    fn replays() -> OperationReplays<VectorMemory> {
+        OperationReplays::with_memory(OperationReplaysMemory {
+            replays: VectorMemory::default(),
+            captured: VectorMemory::default(),
+        })
+    }
+
+    fn context() -> ReplayContextDigest {
+        ReplayContextDigest {
+            evm_params_index: None,
+            evm_params_hash: None,
+            signer_address: None,
+        }
+    }
+
+    async fn run(runtime: &BridgeRuntime<StepOperation>, id: OperationId) {
+        let ctx = runtime.state().clone();
+        let operation = ctx.borrow().operations.get(id).unwrap();
+        let _ = BridgeTask::new(id, operation)
+            .execute_inner(ctx, Box::new(runtime.scheduler().clone()))
+            .await;
+    }
+
+    #[test]
+    fn capture_is_enabled_per_operation_or_sampled() {
+        let mut replays = replays();
+        let id = OperationId::new(1);
+        let settings = ReplayCaptureSettings::default();
+        assert!(!replays.should_capture(id, &settings));
+
+        replays.set_capture(id, true, 10);
+        assert!(replays.should_capture(id, &settings));
+        assert!(!replays.should_capture(OperationId::new(2), &settings));
+
+        replays.set_capture(id, false, 20);
+        assert!(!replays.should_capture(id, &settings));
+
+        let settings = ReplayCaptureSettings {
+            sample_rate_ppm: FULL_REPLAY_SAMPLE_RATE_PPM,
+            ..Default::default()
+        };
+        assert!(replays.should_capture(id, &settings));
+    }
+
+    #[test]
+    fn replay_log_is_bounded() {
+        let mut replays = replays();
+        let id = OperationId::new(1);
+        for i in 0..10 {
+            replays.record(
+                id,
+                i,
+                vec![i as u8],
+                context(),
+                ProgressReplayOutcome::AddToService(i),
+                4,
+            );
+        }
+
+        let entries = replays.get(id);
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].index, 6);
+        assert_eq!(entries[3].outcome, ProgressReplayOutcome::AddToService(9));
+        assert!(replays.get(OperationId::new(2)).is_empty());
+    }
+
+    #[test]
+    fn replays_of_oldest_operations_are_evicted() {
+        let mut replays = replays();
+        let record = |replays: &mut OperationReplays<VectorMemory>, id: u64| {
+            replays.record(
+                OperationId::new(id),
+                id,
+                vec![],
+                context(),
+                ProgressReplayOutcome::AddToService(id),
+                4,
+            )
+        };
+        for id in 1..=MAX_REPLAY_OPERATIONS {
+            record(&mut replays, id);
+        }
+        assert_eq!(replays.get(OperationId::new(1)).len(), 1);
+
+        // New invocations of the captured operation don't evict others.
+        record(&mut replays, MAX_REPLAY_OPERATIONS);
+        assert_eq!(replays.get(OperationId::new(1)).len(), 1);
+
+        record(&mut replays, MAX_REPLAY_OPERATIONS + 1);
+        assert!(replays.get(OperationId::new(1)).is_empty());
+        assert_eq!(replays.get(OperationId::new(2)).len(), 1);
+
+        // Replay of the operation with the lowest id, captured last, is kept.
+        record(&mut replays, 1);
+        assert_eq!(replays.get(OperationId::new(1)).len(), 1);
+        assert!(replays.get(OperationId::new(2)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn progress_is_captured_only_if_enabled() {
+        MockContext::new().inject();
+        let runtime: BridgeRuntime<StepOperation> = BridgeRuntime::default(ConfigStorage::get());
+        let ctx = runtime.state().clone();
+        let id = ctx
+            .borrow_mut()
+            .operations
+            .new_operation(StepOperation { step: 0 }, None);
+
+        run(&runtime, id).await;
+        assert!(ctx.borrow().operation_replays.get(id).is_empty());
+
+        ctx.borrow_mut().operation_replays.set_capture(id, true, 0);
+        for _ in 0..3 {
+            run(&runtime, id).await;
+        }
+
+        let entries = ctx.borrow().operation_replays.get(id);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0].input,
+            encode_replay_operation(&StepOperation { step: 1 })
+        );
+        assert_eq!(
+            entries[0].outcome,
+            ProgressReplayOutcome::Progress(encode_replay_operation(&StepOperation { step: 2 }))
+        );
+        assert_eq!(
+            entries[2].outcome,
+            ProgressReplayOutcome::Error(Error::FailedToProgress("last step".into()))
+        );
+        assert_eq!(
+            entries[0].context,
+            ctx.borrow().config.borrow().get_replay_context_digest()
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_detects_changed_transition() {
+        MockContext::new().inject();
+        let runtime: BridgeRuntime<StepOperation> = BridgeRuntime::default(ConfigStorage::get());
+        let ctx = runtime.state().clone();
+        let id = ctx
+            .borrow_mut()
+            .operations
+            .new_operation(StepOperation { step: 0 }, None);
+        ctx.borrow_mut().operation_replays.set_capture(id, true, 0);
+        run(&runtime, id).await;
+
+        let entry = ctx.borrow().operation_replays.get(id).remove(0);
+        assert_eq!(replay_progress(id, &entry, ctx.clone()).await, Ok(()));
+
+        TRANSITION_STEP.with(|step| step.set(2));
+        let divergence = replay_progress(id, &entry, ctx.clone()).await.unwrap_err();
+        assert_eq!(
+            divergence,
+            ReplayDivergence::Outcome {
+                captured: ProgressReplayOutcome::Progress(encode_replay_operation(
+                    &StepOperation { step: 1 }
+                )),
+                replayed: ProgressReplayOutcome::Progress(encode_replay_operation(
+                    &StepOperation { step: 2 }
+                )),
+            }
+        );
+        TRANSITION_STEP.with(|step| step.set(1));
+
+        // Replay against another context is reported, not executed.
+        let mut stale = entry.clone();
+        stale.context.evm_params_index = Some(100);
+        assert!(matches!(
+            replay_progress(id, &stale, ctx).await,
+            Err(ReplayDivergence::Context { .. })
+        ));
+    }
+}
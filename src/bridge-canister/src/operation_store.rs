@@ -3,22 +3,40 @@
 
 use std::borrow::Cow;
 
+use bridge_did::data_erasure::AnonymizationMarker;
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::fee_adjustment::FeeAdjustment;
 use bridge_did::op_id::OperationId;
+use bridge_did::operation_block::OperationBlock;
 use bridge_did::operation_log::{Memo, OperationLog};
+use bridge_did::operation_status::{
+    OperationStateClass, OperationStatusView, MAX_OPERATIONS_STATUS_BATCH,
+};
+use bridge_did::outbox::{OperationNotification, OutboxDestination};
+use bridge_did::versioned::{self, Versioned};
 use bridge_utils::common::Pagination;
-use candid::{CandidType, Decode, Deserialize, Encode};
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
 use did::H160;
+use ic_exports::ic_kit::ic;
 use ic_stable_structures::stable_structures::Memory;
 use ic_stable_structures::{
-    BTreeMapStructure, Bound, CachedStableBTreeMap, CellStructure, MultimapStructure,
-    StableBTreeMap, StableCell, StableMultimap, Storable,
+    BTreeMapStructure, Bound, CachedStableBTreeMap, CellStructure, IterableSortedMapStructure,
+    MultimapStructure, StableBTreeMap, StableCell, StableMultimap, Storable,
 };
 
 use crate::bridge::Operation;
+use crate::operation_blocks::{
+    OperationBlockLog, OperationBlocksMemory, DEFAULT_MAX_STORED_BLOCKS,
+};
+use crate::outbox::{Outbox, OutboxMemory};
 
 const DEFAULT_CACHE_SIZE: u32 = 1000;
 const DEFAULT_MAX_REQUEST_COUNT: u64 = 100_000;
 
+/// Default max number of the operations, returned by the operations list of an address,
+/// if no pagination is requested.
+pub const DEFAULT_OPERATIONS_LIST_LIMIT: u64 = 1000;
+
 #[derive(Default, Debug, Clone, CandidType, Deserialize)]
 struct OperationIdList(Vec<OperationId>);
 
@@ -34,11 +52,68 @@ impl Storable for OperationIdList {
     const BOUND: Bound = Bound::Unbounded;
 }
 
+/// Operation log, stored compressed or not, depending on the store settings.
+#[derive(Clone)]
+struct StoredOperationLog<P>
+where
+    P: CandidType,
+{
+    log: OperationLog<P>,
+    compressed: bool,
+    /// Set, if the log is stored in an outdated encoding version, or with a legacy shape
+    /// of the operation, so it should be rewritten by the migration.
+    outdated: bool,
+}
+
+impl<P> Storable for StoredOperationLog<P>
+where
+    P: Operation,
+{
+    fn to_bytes(&self) -> Cow<[u8]> {
+        match self.compressed {
+            true => Cow::Owned(self.log.to_compressed_bytes()),
+            false => Cow::Owned(self.log.to_uncompressed_bytes()),
+        }
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let (version, _) = versioned::split_version(&bytes);
+        let (log, legacy) = match OperationLog::from_versioned_bytes(&bytes) {
+            Ok(log) => (log, false),
+            Err(e) => match P::decode_legacy_log(&bytes) {
+                Some(log) => (log, true),
+                None => panic!("failed to decode operation log entry: {e}"),
+            },
+        };
+
+        Self {
+            compressed: OperationLog::<P>::is_compressed(&bytes),
+            outdated: legacy
+                || (version != OperationLog::<P>::UNCOMPRESSED_VERSION
+                    && version != OperationLog::<P>::COMPRESSED_VERSION),
+            log,
+        }
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Result of the [`OperationStore::migrate_records_batch`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RecordsMigrationBatch {
+    /// Operations, rewritten in the current encoding.
+    pub migrated: Vec<OperationId>,
+    /// Id of the operation to continue the migration from, or `None` if all the operations
+    /// are checked.
+    pub next: Option<OperationId>,
+}
+
 /// Parameters of the [`OperationStore`].
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct OperationStoreOptions {
     max_operations_count: u64,
     cache_size: u32,
+    max_stored_blocks: u64,
 }
 
 impl Default for OperationStoreOptions {
@@ -46,17 +121,21 @@ impl Default for OperationStoreOptions {
         Self {
             max_operations_count: DEFAULT_MAX_REQUEST_COUNT,
             cache_size: DEFAULT_CACHE_SIZE,
+            max_stored_blocks: DEFAULT_MAX_STORED_BLOCKS,
         }
     }
 }
 
 /// Memory objects to store operations.
+#[derive(Clone)]
 pub struct OperationsMemory<Mem> {
     pub id_counter: Mem,
     pub incomplete_operations: Mem,
     pub operations_log: Mem,
     pub operations_map: Mem,
     pub memo_operations_map: Mem,
+    pub outbox: OutboxMemory<Mem>,
+    pub blocks: OperationBlocksMemory<Mem>,
 }
 
 /// A structure to store user-initiated operations in IC stable memory.
@@ -72,11 +151,17 @@ where
     P: Operation,
 {
     operation_id_counter: StableCell<u64, M>,
-    incomplete_operations: CachedStableBTreeMap<OperationId, OperationLog<P>, M>,
-    operations_log: StableBTreeMap<OperationId, OperationLog<P>, M>,
+    incomplete_operations: CachedStableBTreeMap<OperationId, StoredOperationLog<P>, M>,
+    operations_log: StableBTreeMap<OperationId, StoredOperationLog<P>, M>,
     address_operation_map: StableBTreeMap<H160, OperationIdList, M>,
     memo_operation_map: StableMultimap<H160, Memo, OperationId, M>,
     max_operation_log_size: u64,
+    compress_logs: bool,
+    outbox: Outbox<M>,
+    blocks: OperationBlockLog<M>,
+    notification_destinations: Vec<OutboxDestination>,
+    incomplete_operations_memory: M,
+    operations_log_memory: M,
 }
 
 impl<M, P> OperationStore<M, P>
@@ -85,12 +170,14 @@ where
     P: Operation,
 {
     /// Creates a new instance of the store.
-    pub fn with_memory(
-        memory: OperationsMemory<M>,
-        options: Option<OperationStoreOptions>,
-    ) -> Self {
+    pub fn with_memory(memory: OperationsMemory<M>, options: Option<OperationStoreOptions>) -> Self
+    where
+        M: Clone,
+    {
         let options = options.unwrap_or_default();
         Self {
+            incomplete_operations_memory: memory.incomplete_operations.clone(),
+            operations_log_memory: memory.operations_log.clone(),
             operation_id_counter: StableCell::new(memory.id_counter, 0)
                 .expect("failed to initialize operation id counter"),
             incomplete_operations: CachedStableBTreeMap::new(
@@ -101,6 +188,60 @@ where
             address_operation_map: StableBTreeMap::new(memory.operations_map),
             memo_operation_map: StableMultimap::new(memory.memo_operations_map),
             max_operation_log_size: options.max_operations_count,
+            compress_logs: false,
+            outbox: Outbox::with_memory(memory.outbox),
+            blocks: OperationBlockLog::with_memory(memory.blocks)
+                .with_max_stored_blocks(options.max_stored_blocks),
+            notification_destinations: vec![],
+        }
+    }
+
+    /// Enables or disables compression of the operation logs written to the stable memory.
+    /// Already stored logs are not rewritten, but are decoded transparently in any case.
+    pub fn set_log_compression(&mut self, enabled: bool) {
+        self.compress_logs = enabled;
+    }
+
+    /// Sets the destinations to be notified when an operation is complete.
+    /// The notifications are appended to the outbox in the same step as the operation update.
+    pub fn set_notification_destinations(&mut self, destinations: Vec<OutboxDestination>) {
+        self.notification_destinations = destinations;
+    }
+
+    /// Returns the outbox of the notifications about the complete operations.
+    pub fn outbox(&self) -> &Outbox<M> {
+        &self.outbox
+    }
+
+    /// Returns the outbox of the notifications about the complete operations.
+    pub fn outbox_mut(&mut self) -> &mut Outbox<M> {
+        &mut self.outbox
+    }
+
+    /// Returns the append-only log of the operation state transitions.
+    pub fn blocks(&self) -> &OperationBlockLog<M> {
+        &self.blocks
+    }
+
+    /// Appends the operation state transition to the blocks log.
+    fn record_block(
+        &mut self,
+        operation_id: OperationId,
+        wallet_address: H160,
+        step: Result<&P, String>,
+    ) {
+        let step = step.map(|payload| Encode!(payload).expect("failed to encode operation state"));
+        let index = self
+            .blocks
+            .append(operation_id, wallet_address, step, ic::time());
+        log::trace!("Operation {operation_id} transition is recorded in block {index}.");
+    }
+
+    fn stored(&self, log: OperationLog<P>) -> StoredOperationLog<P> {
+        StoredOperationLog {
+            log,
+            compressed: self.compress_logs,
+            outdated: false,
         }
     }
 
@@ -115,6 +256,26 @@ where
         OperationId::new(current)
     }
 
+    /// Returns the id which will be assigned to the next created operation.
+    pub fn peek_next_operation_id(&self) -> OperationId {
+        OperationId::new(*self.operation_id_counter.get())
+    }
+
+    /// Makes sure that ids of all the next created operations are not less than `min_id`.
+    /// The counter is never moved backwards.
+    pub fn skip_operation_ids_below(&mut self, min_id: u64) {
+        let current = *self.operation_id_counter.get();
+        if current >= min_id {
+            return;
+        }
+
+        self.operation_id_counter
+            .set(min_id)
+            .expect("failed to update operation id counter");
+
+        log::info!("Operation id counter moved from {current} to {min_id}");
+    }
+
     /// Initializes a new operation with the given payload for the given ETH wallet address
     /// and stores it.
     pub fn new_operation(&mut self, payload: P, memo: Option<Memo>) -> OperationId {
@@ -131,6 +292,19 @@ where
         payload: P,
         memo: Option<Memo>,
     ) -> OperationId {
+        self.record_block(id, payload.evm_wallet_address(), Ok(&payload));
+        self.insert_new_operation(id, payload, memo, true);
+        id
+    }
+
+    /// Stores the new operation without recording its block.
+    fn insert_new_operation(
+        &mut self,
+        id: OperationId,
+        payload: P,
+        memo: Option<Memo>,
+        notify: bool,
+    ) {
         let wallet_address = payload.evm_wallet_address();
         let is_complete = payload.is_complete();
         let log = OperationLog::new(payload, wallet_address.clone(), memo);
@@ -138,9 +312,9 @@ where
         log::trace!("Operation {id} is created.");
 
         if is_complete {
-            self.move_to_log(id, log);
+            self.move_to_log(id, log, notify);
         } else {
-            self.incomplete_operations.insert(id, log);
+            self.incomplete_operations.insert(id, self.stored(log));
         }
 
         let mut ids = self
@@ -154,8 +328,6 @@ where
         if let Some(memo) = memo {
             self.memo_operation_map.insert(&wallet_address, &memo, id);
         }
-
-        id
     }
 
     /// Retrieves an operation by its ID.
@@ -168,13 +340,221 @@ where
         self.incomplete_operations
             .get(&operation_id)
             .or_else(|| self.operations_log.get(&operation_id))
+            .map(|stored| stored.log)
+    }
+
+    /// Returns log of an operation by its ID, unless the user data of the operation is erased.
+    /// Steps of an anonymized operation still contain the user data, so its log is not exposed.
+    pub fn get_public_log(&self, operation_id: OperationId) -> Option<OperationLog<P>> {
+        self.get_log(operation_id)
+            .filter(|log| log.anonymization().is_none())
+    }
+
+    /// Returns the bytes of the operation log, as they are stored in stable memory.
+    ///
+    /// The bytes are not decoded, so they can be retrieved even if the stored operation
+    /// can't be decoded by the current version of the canister.
+    pub fn get_raw_bytes(&self, operation_id: OperationId) -> Option<Vec<u8>>
+    where
+        M: Clone,
+    {
+        // A new map loads the current state of the stored tree, and doesn't modify it.
+        let raw_incomplete: StableBTreeMap<OperationId, Vec<u8>, M> =
+            StableBTreeMap::new(self.incomplete_operations_memory.clone());
+        if let Some(bytes) = raw_incomplete.get(&operation_id) {
+            return Some(bytes);
+        }
+
+        let raw_log: StableBTreeMap<OperationId, Vec<u8>, M> =
+            StableBTreeMap::new(self.operations_log_memory.clone());
+        raw_log.get(&operation_id)
+    }
+
+    /// Replays the successful steps of the operation log through the operation transition
+    /// graph and checks that the result is consistent with the stored state of the operation.
+    ///
+    /// Returns the description of the first inconsistency found.
+    pub fn verify_consistency(&self, operation_id: OperationId) -> Result<(), String> {
+        let (log, stored_as_complete) = match self.incomplete_operations.get(&operation_id) {
+            Some(stored) => (stored.log, false),
+            None => match self.operations_log.get(&operation_id) {
+                Some(stored) => (stored.log, true),
+                None => return Err(format!("operation {operation_id} is not found")),
+            },
+        };
+
+        let mut steps = log.log().iter().enumerate();
+        let Some(Ok(mut replayed)) = steps.next().map(|(_, entry)| entry.step_result.as_ref())
+        else {
+            return Err("step 0: operation is not created by the first step".to_string());
+        };
+
+        for (index, entry) in steps {
+            let Ok(step) = &entry.step_result else {
+                continue;
+            };
+
+            if replayed.is_complete() {
+                return Err(format!(
+                    "step {index}: operation is updated after completion"
+                ));
+            }
+
+            if !P::can_transition(replayed, step) {
+                return Err(format!(
+                    "step {index}: illegal transition from the state of the previous step"
+                ));
+            }
+
+            replayed = step;
+        }
+
+        if Encode!(replayed).ok() != Encode!(log.current_step()).ok() {
+            return Err("replayed state differs from the current state".to_string());
+        }
+
+        if replayed.is_complete() != stored_as_complete {
+            return Err(format!(
+                "replayed state is {}complete, but the operation is stored as {}complete",
+                if replayed.is_complete() { "" } else { "not " },
+                if stored_as_complete { "" } else { "not " },
+            ));
+        }
+
+        // Anonymized operations are removed from the index of the wallet addresses.
+        let indexed = self
+            .address_operation_map
+            .get(log.wallet_address())
+            .is_some_and(|ids| ids.0.contains(&operation_id));
+        if !indexed && log.anonymization().is_none() {
+            return Err("operation is not indexed by its wallet address".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Returns all incomplete operations, ordered by id.
+    pub fn get_incomplete(&self) -> Vec<(OperationId, P)> {
+        let mut operations: Vec<_> = self
+            .address_operation_map
+            .iter()
+            .flat_map(|(_, ids)| ids.0)
+            .filter_map(|id| {
+                self.incomplete_operations
+                    .get(&id)
+                    .map(|stored| (id, stored.log.current_step().clone()))
+            })
+            .collect();
+        operations.sort_by_key(|(id, _)| *id);
+        operations
+    }
+
+    /// Returns operations, which logs match the `filter`, ordered by id.
+    /// The matching operations are paginated with the given `pagination` parameters.
+    /// If `pagination` is `None`, all the matching operations are returned.
+    ///
+    /// The method scans all the stored operations.
+    pub fn get_filtered(
+        &self,
+        filter: impl Fn(&OperationLog<P>) -> bool,
+        pagination: Option<Pagination>,
+    ) -> Vec<(OperationId, P)> {
+        let offset = pagination.as_ref().map(|p| p.offset).unwrap_or(0);
+        let count = pagination.map(|p| p.count).unwrap_or(usize::MAX);
+
+        let mut ids: Vec<_> = self
+            .address_operation_map
+            .iter()
+            .flat_map(|(_, ids)| ids.0)
+            .collect();
+        ids.sort();
+
+        ids.into_iter()
+            .filter_map(|id| self.get_log(id).map(|log| (id, log)))
+            .filter(|(_, log)| filter(log))
+            .skip(offset)
+            .take(count)
+            .map(|(id, log)| (id, log.current_step().clone()))
+            .collect()
+    }
+
+    /// Returns logs of up to `count` incomplete operations with ids greater than or equal to
+    /// `min_included_id`, which match the `filter`, ordered by id.
+    pub fn get_incomplete_filtered(
+        &self,
+        filter: impl Fn(OperationId, &OperationLog<P>) -> bool,
+        min_included_id: Option<OperationId>,
+        count: usize,
+    ) -> Vec<(OperationId, OperationLog<P>)> {
+        let min_included_id = min_included_id.unwrap_or_default();
+        self.incomplete_operations
+            .range(min_included_id..)
+            .filter(|(id, stored)| filter(*id, &stored.log))
+            .take(count)
+            .map(|(id, stored)| (id, stored.log))
+            .collect()
+    }
+
+    /// Returns logs of up to `count` latest complete operations, ordered by id.
+    pub fn get_latest_complete_logs(&self, count: usize) -> Vec<OperationLog<P>> {
+        let skip = (self.operations_log.len() as usize).saturating_sub(count);
+        self.operations_log
+            .iter()
+            .skip(skip)
+            .map(|(_, stored)| stored.log)
+            .collect()
+    }
+
+    /// Returns the compact status of an operation by its ID.
+    pub fn get_status(&self, operation_id: OperationId) -> Option<OperationStatusView> {
+        let log = self.get_log(operation_id)?;
+        let last_entry = log.log().last()?;
+
+        let state_class = state_class(&log);
+
+        let mut tx_hashes = vec![];
+        for step in log
+            .log()
+            .iter()
+            .filter_map(|entry| entry.step_result.as_ref().ok())
+        {
+            if let Some(tx_hash) = step.evm_tx_hash() {
+                if !tx_hashes.contains(&tx_hash) {
+                    tx_hashes.push(tx_hash);
+                }
+            }
+        }
+
+        Some(OperationStatusView {
+            state_class,
+            last_transition_timestamp: last_entry.time_stamp,
+            tx_hashes,
+        })
+    }
+
+    /// Returns the compact statuses of the operations in the order of `operation_ids`,
+    /// with `None` for the unknown operations.
+    ///
+    /// Up to [`MAX_OPERATIONS_STATUS_BATCH`] operations can be requested at once.
+    pub fn get_status_batch(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> BTFResult<Vec<(OperationId, Option<OperationStatusView>)>> {
+        if operation_ids.len() as u64 > MAX_OPERATIONS_STATUS_BATCH {
+            return Err(Error::OperationsBatchTooLarge(MAX_OPERATIONS_STATUS_BATCH));
+        }
+
+        Ok(operation_ids
+            .into_iter()
+            .map(|id| (id, self.get_status(id)))
+            .collect())
     }
 
     fn get_with_id(&self, operation_id: OperationId) -> Option<(OperationId, P)> {
         self.incomplete_operations
             .get(&operation_id)
             .or_else(|| self.operations_log.get(&operation_id))
-            .map(|log| (operation_id, log.current_step().clone()))
+            .map(|stored| (operation_id, stored.log.current_step().clone()))
     }
 
     /// Returns operation for the given address with the given nonce, if present.
@@ -219,6 +599,37 @@ where
             .collect()
     }
 
+    /// Retrieves operations for the given ETH wallet address like [`Self::get_for_address`].
+    /// If `pagination` is `None`, only `default_count` most recent operations are returned
+    /// to limit the response size, so the full history should be retrieved page by page.
+    pub fn get_recent_for_address(
+        &self,
+        dst_address: &H160,
+        min_included_id: Option<OperationId>,
+        pagination: Option<Pagination>,
+        default_count: usize,
+    ) -> Vec<(OperationId, P)> {
+        if pagination.is_some() {
+            return self.get_for_address(dst_address, min_included_id, pagination);
+        }
+
+        let min_included_id = min_included_id.unwrap_or_default();
+        let ids: Vec<_> = self
+            .address_operation_map
+            .get(dst_address)
+            .unwrap_or_default()
+            .0
+            .into_iter()
+            .filter(|id| id >= &min_included_id)
+            .collect();
+        let skip = ids.len().saturating_sub(default_count);
+
+        ids.into_iter()
+            .skip(skip)
+            .filter_map(|id| self.get_with_id(id))
+            .collect()
+    }
+
     /// Retrieve operations for the given memo.
     pub fn get_operation_by_memo_and_user(
         &self,
@@ -239,32 +650,282 @@ where
             .collect()
     }
 
+    /// Anonymizes the operations of the `address`, so they can't be found by the address
+    /// anymore. See [`Self::anonymize_operations`] for details.
+    ///
+    /// Fails without any changes, if some operations of the address are incomplete.
+    pub fn anonymize_address(
+        &mut self,
+        address: &H160,
+        marker: AnonymizationMarker,
+    ) -> BTFResult<Vec<OperationId>> {
+        let ids = self
+            .address_operation_map
+            .get(address)
+            .unwrap_or_default()
+            .0;
+        let anonymized = self.anonymize_operations(ids, marker)?;
+
+        self.address_operation_map.remove(address);
+        self.memo_operation_map.remove_partial(address);
+
+        Ok(anonymized)
+    }
+
+    /// Anonymizes the operations, which steps are linked to the `principal`.
+    /// See [`Self::anonymize_operations`] for details.
+    ///
+    /// Operations are not indexed by principals, so all the stored operations are checked.
+    /// Fails without any changes, if some operations of the principal are incomplete.
+    pub fn anonymize_principal(
+        &mut self,
+        principal: &Principal,
+        marker: AnonymizationMarker,
+    ) -> BTFResult<Vec<OperationId>> {
+        let is_linked = |log: &OperationLog<P>| {
+            log.anonymization().is_none()
+                && log
+                    .log()
+                    .iter()
+                    .filter_map(|entry| entry.step_result.as_ref().ok())
+                    .any(|payload| payload.principal().as_ref() == Some(principal))
+        };
+
+        let mut ids: Vec<_> = self
+            .get_incomplete()
+            .into_iter()
+            .map(|(id, _)| id)
+            .filter(|id| {
+                self.incomplete_operations
+                    .get(id)
+                    .is_some_and(|stored| is_linked(&stored.log))
+            })
+            .collect();
+        ids.extend(
+            self.operations_log
+                .iter()
+                .filter(|(_, stored)| is_linked(&stored.log))
+                .map(|(id, _)| id),
+        );
+
+        self.anonymize_operations(ids, marker)
+    }
+
+    /// Replaces the wallet address of the operations with the pseudonym of the `marker`,
+    /// removes their memos and removes them from the address and memo indexes.
+    ///
+    /// Steps of the anonymized operations are kept as the financial records, but their logs
+    /// are not returned by [`Self::get_public_log`]. Blocks of the operations are redacted,
+    /// and an erasure block is appended for every operation, so the replicas anonymize
+    /// the operations the same way. Returns ids of the anonymized operations.
+    ///
+    /// Fails without any changes, if some of the operations are incomplete.
+    fn anonymize_operations(
+        &mut self,
+        ids: Vec<OperationId>,
+        marker: AnonymizationMarker,
+    ) -> BTFResult<Vec<OperationId>> {
+        let incomplete: Vec<_> = ids
+            .iter()
+            .copied()
+            .filter(|id| self.incomplete_operations.contains_key(id))
+            .collect();
+        if !incomplete.is_empty() {
+            return Err(Error::UserDataErasurePending(incomplete));
+        }
+
+        let mut anonymized = Vec::with_capacity(ids.len());
+        for id in ids {
+            if self.anonymize_operation(id, &marker) {
+                self.blocks
+                    .append_erasure(id, marker.clone(), marker.timestamp);
+                anonymized.push(id);
+            }
+        }
+
+        log::trace!("Operations {anonymized:?} are anonymized.");
+
+        Ok(anonymized)
+    }
+
+    /// Anonymizes the complete operation and removes it from the address and memo indexes.
+    /// Returns `false` if the complete operation is not found.
+    fn anonymize_operation(&mut self, id: OperationId, marker: &AnonymizationMarker) -> bool {
+        let Some(StoredOperationLog { mut log, .. }) = self.operations_log.get(&id) else {
+            return false;
+        };
+
+        let address = log.wallet_address().clone();
+        if let Some(mut ids) = self.address_operation_map.get(&address) {
+            ids.0.retain(|op_id| *op_id != id);
+            if ids.0.is_empty() {
+                self.address_operation_map.remove(&address);
+            } else {
+                self.address_operation_map.insert(address.clone(), ids);
+            }
+        }
+        if let Some(memo) = log.memo() {
+            self.memo_operation_map.remove(&address, memo);
+        }
+
+        log.anonymize(marker.clone());
+        self.operations_log.insert(id, self.stored(log));
+
+        true
+    }
+
     /// Update the payload of the operation with the given id. If no operation with the given ID
     /// is found, nothing is done (except an error message in the log).
-    pub fn update(&mut self, operation_id: OperationId, payload: P) {
-        let Some(mut log) = self.incomplete_operations.get(&operation_id) else {
+    pub fn update(&mut self, operation_id: OperationId, payload: P) -> bool {
+        self.update_with_evm_params(operation_id, payload, None)
+    }
+
+    /// Update the payload of the operation with the given id after a transaction is sent to EVM.
+    /// The log entry of the step is annotated with the index of the EVM params snapshot, used for
+    /// the transaction.
+    ///
+    /// Returns `false` if the operation is not found, or the transition from its current state
+    /// to the `payload` is illegal.
+    pub fn update_with_evm_params(
+        &mut self,
+        operation_id: OperationId,
+        payload: P,
+        evm_params_snapshot: Option<u64>,
+    ) -> bool {
+        let Some(StoredOperationLog { mut log, .. }) =
+            self.incomplete_operations.get(&operation_id)
+        else {
             log::error!("Cannot update operation {operation_id} status: not found");
-            return;
+            return false;
         };
 
+        if !P::can_transition(log.current_step(), &payload) {
+            log::error!("Cannot update operation {operation_id} status: illegal state transition");
+            return false;
+        }
+
         let is_complete = payload.is_complete();
-        log.add_step(Ok(payload));
+        self.record_block(operation_id, log.wallet_address().clone(), Ok(&payload));
+        log.add_step_with_evm_params(payload, evm_params_snapshot);
 
         if is_complete {
-            self.move_to_log(operation_id, log);
+            self.move_to_log(operation_id, log, true);
         } else {
-            self.incomplete_operations.insert(operation_id, log);
+            self.incomplete_operations
+                .insert(operation_id, self.stored(log));
         }
+
+        true
     }
 
     pub fn update_with_err(&mut self, operation_id: OperationId, error_message: String) {
-        let Some(mut log) = self.incomplete_operations.get(&operation_id) else {
+        let Some(StoredOperationLog { mut log, .. }) =
+            self.incomplete_operations.get(&operation_id)
+        else {
             log::error!("Cannot update operation {operation_id} status: not found");
             return;
         };
 
+        // Repeated failures of the step are coalesced in the operation log, so only
+        // the first of them is recorded in the blocks log.
+        let is_repeated = log
+            .log()
+            .last()
+            .is_some_and(|entry| entry.step_result.as_ref().err() == Some(&error_message));
+        if !is_repeated {
+            self.record_block(
+                operation_id,
+                log.wallet_address().clone(),
+                Err(error_message.clone()),
+            );
+        }
         log.add_step(Err(error_message));
-        self.incomplete_operations.insert(operation_id, log);
+        self.incomplete_operations
+            .insert(operation_id, self.stored(log));
+    }
+
+    /// Applies the operation state transition, exported from the blocks log of the primary
+    /// canister, and imports its block as is. Notifications about the complete operations
+    /// are not sent for the imported transitions, as they are sent by the primary.
+    ///
+    /// An erasure block anonymizes its operation as on the primary. A redacted block is
+    /// imported without restoring its operation.
+    pub fn import_block(&mut self, index: u64, block: OperationBlock) -> BTFResult<()> {
+        self.blocks.validate_import(index, &block)?;
+
+        let id = block.operation_id;
+        if let Some(marker) = &block.anonymization {
+            self.anonymize_operation(id, marker);
+            return self.blocks.import(index, block);
+        }
+
+        // User data of the redacted block is erased, so the operation can't be restored.
+        if block.is_redacted() {
+            self.skip_operation_ids_below(id.as_u64() + 1);
+            return self.blocks.import(index, block);
+        }
+
+        let step = match &block.step {
+            Ok(payload) => {
+                Ok(Decode!(payload, P).map_err(|e| Error::Serialization(e.to_string()))?)
+            }
+            Err(e) => Err(e.clone()),
+        };
+
+        match (self.incomplete_operations.get(&id), step) {
+            (Some(StoredOperationLog { mut log, .. }), step) => {
+                let is_complete = matches!(&step, Ok(payload) if payload.is_complete());
+                log.add_step(step);
+                if is_complete {
+                    self.move_to_log(id, log, false);
+                } else {
+                    self.incomplete_operations.insert(id, self.stored(log));
+                }
+            }
+            (None, Ok(payload)) if self.operations_log.get(&id).is_none() => {
+                self.insert_new_operation(id, payload, None, false);
+                self.skip_operation_ids_below(id.as_u64() + 1);
+            }
+            (None, _) => {
+                return Err(Error::FailedToProgress(format!(
+                    "block {index} updates operation {id}, which is not incomplete"
+                )));
+            }
+        }
+
+        self.blocks.import(index, block)
+    }
+
+    /// Records the fee adjustment in the log of the operation. Returns `false` if the
+    /// operation is not found, or its fee is already reconciled.
+    pub fn set_fee_adjustment(
+        &mut self,
+        operation_id: OperationId,
+        adjustment: FeeAdjustment,
+    ) -> bool {
+        if let Some(StoredOperationLog { mut log, .. }) =
+            self.incomplete_operations.get(&operation_id)
+        {
+            if !log.set_fee_adjustment(adjustment) {
+                return false;
+            }
+
+            self.incomplete_operations
+                .insert(operation_id, self.stored(log));
+            return true;
+        }
+
+        if let Some(StoredOperationLog { mut log, .. }) = self.operations_log.get(&operation_id) {
+            if !log.set_fee_adjustment(adjustment) {
+                return false;
+            }
+
+            self.operations_log.insert(operation_id, self.stored(log));
+            return true;
+        }
+
+        log::warn!("Cannot set fee adjustment of operation {operation_id}: not found");
+        false
     }
 
     pub fn update_by_nonce(&mut self, dst_address: &H160, nonce: u32, payload: P) {
@@ -279,12 +940,66 @@ where
             return;
         };
 
-        self.update(op_id, payload)
+        self.update(op_id, payload);
+    }
+
+    /// Rewrites the stored operations with ids starting from `from`, which are encoded in an
+    /// outdated version or with a legacy shape of the operation. At most `batch_size` operations
+    /// are checked, and the rewritten ones are compressed if the log compression is enabled.
+    ///
+    /// Operations of the outdated shape are decoded on read, so the migration can be performed
+    /// in batches after the upgrade.
+    pub fn migrate_records_batch(
+        &mut self,
+        from: OperationId,
+        batch_size: usize,
+    ) -> RecordsMigrationBatch {
+        let mut checked: Vec<_> = self
+            .incomplete_operations
+            .range(from..)
+            .take(batch_size)
+            .map(|(id, stored)| (id, stored, true))
+            .chain(
+                self.operations_log
+                    .range(from..)
+                    .take(batch_size)
+                    .map(|(id, stored)| (id, stored, false)),
+            )
+            .collect();
+        checked.sort_by_key(|(id, ..)| *id);
+        checked.truncate(batch_size);
+
+        let next = match checked.last() {
+            Some((id, ..)) if checked.len() == batch_size => {
+                Some(OperationId::new(id.as_u64() + 1))
+            }
+            _ => None,
+        };
+
+        let mut migrated = vec![];
+        for (id, stored, incomplete) in checked {
+            if !stored.outdated {
+                continue;
+            }
+
+            if incomplete {
+                self.incomplete_operations
+                    .insert(id, self.stored(stored.log));
+            } else {
+                self.operations_log.insert(id, self.stored(stored.log));
+            }
+            migrated.push(id);
+        }
+
+        RecordsMigrationBatch { migrated, next }
     }
 
-    fn move_to_log(&mut self, operation_id: OperationId, log: OperationLog<P>) {
+    fn move_to_log(&mut self, operation_id: OperationId, log: OperationLog<P>, notify: bool) {
         self.incomplete_operations.remove(&operation_id);
-        self.operations_log.insert(operation_id, log);
+        if notify {
+            self.notify_complete(operation_id, &log);
+        }
+        self.operations_log.insert(operation_id, self.stored(log));
 
         log::trace!("Operation {operation_id} is marked as complete and moved to the log.");
 
@@ -293,12 +1008,34 @@ where
         }
     }
 
+    fn notify_complete(&mut self, operation_id: OperationId, log: &OperationLog<P>) {
+        if self.notification_destinations.is_empty() {
+            return;
+        }
+
+        let timestamp = ic::time();
+        let notification = OperationNotification {
+            operation_id,
+            wallet_address: log.wallet_address().clone(),
+            memo: log.memo().copied(),
+            timestamp,
+        };
+        for destination in &self.notification_destinations {
+            let id = self
+                .outbox
+                .push(destination.clone(), notification.clone(), timestamp);
+            log::trace!("Notification {id} about operation {operation_id} is added to the outbox.");
+        }
+    }
+
     fn max_operation_log_size(&self) -> u64 {
         self.max_operation_log_size
     }
 
     fn remove_oldest(&mut self) {
-        if let Some((id, oldest)) = self.operations_log.iter().next() {
+        if let Some((id, StoredOperationLog { log: oldest, .. })) =
+            self.operations_log.iter().next()
+        {
             self.operations_log.remove(&id);
             let mut ids = self
                 .address_operation_map
@@ -336,9 +1073,26 @@ where
     }
 }
 
+/// Returns the state class of the operation with the given log.
+pub fn state_class<P: Operation>(log: &OperationLog<P>) -> OperationStateClass {
+    if log.current_step().is_complete() {
+        OperationStateClass::Complete
+    } else if log.current_step().unpark().is_some() {
+        OperationStateClass::Parked
+    } else if log
+        .log()
+        .last()
+        .is_some_and(|entry| entry.step_result.is_err())
+    {
+        OperationStateClass::Retrying
+    } else {
+        OperationStateClass::InProgress
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use bridge_did::error::BTFResult;
+    use did::H256;
     use ic_exports::ic_kit::MockContext;
     use ic_stable_structures::VectorMemory;
     use serde::Serialize;
@@ -354,6 +1108,8 @@ mod tests {
     }
 
     const COMPLETE: u32 = u32::MAX;
+    /// Stages starting from this one have the EVM transaction sent.
+    const TX_SENT: u32 = 100;
 
     impl TestOp {
         pub fn new(addr: u32, stage: u32) -> Self {
@@ -384,6 +1140,28 @@ mod tests {
         fn evm_wallet_address(&self) -> H160 {
             eth_address(self.addr as _)
         }
+
+        fn evm_tx_hash(&self) -> Option<H256> {
+            (self.stage >= TX_SENT && !self.is_complete())
+                .then(|| H256::from_low_u64_be(self.stage as _))
+        }
+
+        fn principal(&self) -> Option<Principal> {
+            (self.stage == 1).then(|| test_principal(self.addr))
+        }
+
+        fn can_transition(from: &Self, to: &Self) -> bool {
+            from.stage <= to.stage
+        }
+
+        fn decode_legacy_log(bytes: &[u8]) -> Option<OperationLog<Self>> {
+            let log = OperationLog::<LegacyTestOp>::from_versioned_bytes(bytes).ok()?;
+            Some(log.map_payload(|op, _| TestOp::new(op.addr, 1)))
+        }
+    }
+
+    fn test_principal(seed: u32) -> Principal {
+        Principal::from_slice(&seed.to_be_bytes())
     }
 
     fn test_store(max_operations: u64) -> OperationStore<VectorMemory, TestOp> {
@@ -394,12 +1172,23 @@ mod tests {
             operations_log: VectorMemory::default(),
             operations_map: VectorMemory::default(),
             memo_operations_map: VectorMemory::default(),
+            outbox: OutboxMemory {
+                id_counter: VectorMemory::default(),
+                pending: VectorMemory::default(),
+                parked: VectorMemory::default(),
+                due: VectorMemory::default(),
+            },
+            blocks: OperationBlocksMemory {
+                blocks: VectorMemory::default(),
+                operation_index: VectorMemory::default(),
+            },
         };
         OperationStore::with_memory(
             memory,
             Some(OperationStoreOptions {
                 max_operations_count: max_operations,
                 cache_size: DEFAULT_CACHE_SIZE,
+                max_stored_blocks: DEFAULT_MAX_STORED_BLOCKS,
             }),
         )
     }
@@ -438,6 +1227,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_get_latest_complete_logs() {
+        let mut store = test_store(100);
+
+        for i in 0..5 {
+            store.new_operation(TestOp::complete(i), None);
+        }
+        store.new_operation(TestOp::new(5, 0), None);
+
+        let addresses = |logs: Vec<OperationLog<TestOp>>| {
+            logs.iter()
+                .map(|log| log.current_step().addr)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(addresses(store.get_latest_complete_logs(3)), vec![2, 3, 4]);
+        assert_eq!(
+            addresses(store.get_latest_complete_logs(10)),
+            vec![0, 1, 2, 3, 4]
+        );
+        assert!(store.get_latest_complete_logs(0).is_empty());
+    }
+
     #[test]
     fn should_get_page_for_operations() {
         const LIMIT: u64 = 100;
@@ -467,6 +1278,27 @@ mod tests {
         assert!(page.is_empty());
     }
 
+    #[test]
+    fn recent_operations_are_returned_without_pagination() {
+        let mut store = test_store(100);
+        let ids: Vec<_> = (0..10)
+            .map(|stage| store.new_operation(TestOp::new(1, stage), None))
+            .collect();
+
+        let recent = store.get_recent_for_address(&eth_address(1), None, None, 3);
+        let recent_ids: Vec<_> = recent.iter().map(|(id, _)| *id).collect();
+        assert_eq!(recent_ids, ids[7..]);
+
+        // The default count is not applied to the explicit page.
+        let page =
+            store.get_recent_for_address(&eth_address(1), None, Some(Pagination::new(0, 5)), 3);
+        assert_eq!(page.len(), 5);
+        assert_eq!(page[0].0, ids[0]);
+
+        let from_min_id = store.get_recent_for_address(&eth_address(1), Some(ids[8]), None, 3);
+        assert_eq!(from_min_id.len(), 2);
+    }
+
     #[test]
     fn operations_limit_with_same_address() {
         const LIMIT: u64 = 10;
@@ -601,6 +1433,204 @@ mod tests {
         assert_eq!(store.address_operation_map.len(), LIMIT);
     }
 
+    #[test]
+    fn migrate_records_batch_skips_current_records() {
+        let mut store = test_store(10);
+        let incomplete = store.new_operation(TestOp::new(1, 1), None);
+        let complete = store.new_operation(TestOp::complete(2), None);
+        store.new_operation(TestOp::new(3, 1), None);
+
+        let batch = store.migrate_records_batch(OperationId::new(0), 2);
+        assert!(batch.migrated.is_empty());
+        assert_eq!(batch.next, Some(OperationId::new(2)));
+
+        let batch = store.migrate_records_batch(OperationId::new(2), 2);
+        assert!(batch.migrated.is_empty());
+        assert_eq!(batch.next, None);
+
+        assert_eq!(store.get(incomplete).unwrap().stage, 1);
+        assert!(store.get(complete).unwrap().is_complete());
+        assert_eq!(store.incomplete_operations.len(), 2);
+        assert_eq!(store.operations_log.len(), 1);
+    }
+
+    #[derive(Debug, Copy, Clone, Serialize, Deserialize, CandidType)]
+    struct LegacyTestOp {
+        pub addr: u32,
+    }
+
+    impl Operation for LegacyTestOp {
+        fn is_complete(&self) -> bool {
+            false
+        }
+
+        async fn progress(
+            self,
+            _id: OperationId,
+            _ctx: RuntimeState<Self>,
+        ) -> BTFResult<OperationProgress<Self>> {
+            todo!()
+        }
+
+        fn evm_wallet_address(&self) -> H160 {
+            eth_address(self.addr as _)
+        }
+    }
+
+    #[test]
+    fn legacy_records_are_decoded_and_migrated() {
+        MockContext::new().inject();
+        let memory = OperationsMemory {
+            id_counter: VectorMemory::default(),
+            incomplete_operations: VectorMemory::default(),
+            operations_log: VectorMemory::default(),
+            operations_map: VectorMemory::default(),
+            memo_operations_map: VectorMemory::default(),
+            outbox: OutboxMemory {
+                id_counter: VectorMemory::default(),
+                pending: VectorMemory::default(),
+                parked: VectorMemory::default(),
+                due: VectorMemory::default(),
+            },
+            blocks: OperationBlocksMemory {
+                blocks: VectorMemory::default(),
+                operation_index: VectorMemory::default(),
+            },
+        };
+        let mut legacy_store: OperationStore<VectorMemory, LegacyTestOp> =
+            OperationStore::with_memory(memory.clone(), None);
+        let legacy = legacy_store.new_operation(LegacyTestOp { addr: 1 }, None);
+        let legacy_timestamp = legacy_store.get_log(legacy).unwrap().log()[0].time_stamp;
+        drop(legacy_store);
+
+        let mut store: OperationStore<VectorMemory, TestOp> =
+            OperationStore::with_memory(memory, None);
+        let current = store.new_operation(TestOp::new(2, 5), None);
+
+        // Legacy records are readable before the migration.
+        assert_eq!(store.get(legacy).unwrap().stage, 1);
+        assert_eq!(
+            store.get_log(legacy).unwrap().log()[0].time_stamp,
+            legacy_timestamp
+        );
+
+        let batch = store.migrate_records_batch(OperationId::new(0), 10);
+        assert_eq!(batch.migrated, vec![legacy]);
+        assert_eq!(batch.next, None);
+        assert!(!store.incomplete_operations.get(&legacy).unwrap().outdated);
+
+        let batch = store.migrate_records_batch(OperationId::new(0), 10);
+        assert!(batch.migrated.is_empty());
+
+        assert_eq!(store.get(legacy).unwrap().stage, 1);
+        assert_eq!(store.get(current).unwrap().stage, 5);
+        assert_eq!(store.get_incomplete().len(), 2);
+    }
+
+    #[test]
+    fn get_incomplete_returns_only_incomplete_operations() {
+        let mut store = test_store(10);
+        let first = store.new_operation(TestOp::new(1, 1), None);
+        store.new_operation(TestOp::complete(2), None);
+        let second = store.new_operation(TestOp::new(1, 2), None);
+
+        let ids: Vec<_> = store
+            .get_incomplete()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(ids, vec![first, second]);
+
+        store.update(first, TestOp::complete(1));
+        assert_eq!(store.get_incomplete().len(), 1);
+    }
+
+    #[test]
+    fn get_filtered_returns_only_matching_operations() {
+        let mut store = test_store(10);
+        let first = store.new_operation(TestOp::new(1, 1), None);
+        store.new_operation(TestOp::new(2, 1), None);
+        let second = store.new_operation(TestOp::new(1, 2), None);
+        let third = store.new_operation(TestOp::new(3, 1), None);
+        store.update(third, TestOp::complete(1));
+
+        let created_by_first_address = |log: &OperationLog<TestOp>| {
+            log.log()
+                .first()
+                .and_then(|entry| entry.step_result.as_ref().ok())
+                .is_some_and(|op| op.addr == 1)
+        };
+
+        let ids: Vec<_> = store
+            .get_filtered(created_by_first_address, None)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(ids, vec![first, second]);
+
+        let page = store.get_filtered(created_by_first_address, Some(Pagination::new(1, 10)));
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].0, second);
+
+        assert!(store.get_filtered(|_| false, None).is_empty());
+    }
+
+    #[test]
+    fn get_raw_bytes_returns_stored_bytes() {
+        let mut store = test_store(10);
+        let incomplete = store.new_operation(TestOp::new(1, 1), None);
+        let complete = store.new_operation(TestOp::complete(2), None);
+
+        for id in [incomplete, complete] {
+            let log = store.get_log(id).unwrap();
+            assert_eq!(store.get_raw_bytes(id), Some(log.to_bytes().into_owned()));
+        }
+
+        store.update(incomplete, TestOp::new(1, 2));
+        let log = store.get_log(incomplete).unwrap();
+        assert_eq!(
+            store.get_raw_bytes(incomplete),
+            Some(log.to_bytes().into_owned())
+        );
+
+        assert_eq!(store.get_raw_bytes(OperationId::new(42)), None);
+    }
+
+    #[test]
+    fn compressed_operation_logs_are_decoded_transparently() {
+        let mut store = test_store(10);
+        let plain = store.new_operation(TestOp::new(1, 1), None);
+
+        store.set_log_compression(true);
+        let compressed = store.new_operation(TestOp::new(2, 1), None);
+        for i in 0..50 {
+            let err = match i % 2 {
+                0 => "failed to send mint tx: request timed out",
+                _ => "failed to send mint tx: nonce is too low",
+            };
+            store.update_with_err(compressed, err.into());
+        }
+
+        let raw = store.get_raw_bytes(compressed).unwrap();
+        assert!(OperationLog::<TestOp>::is_compressed(&raw));
+        let log = store.get_log(compressed).unwrap();
+        assert_eq!(log.log().len(), 51);
+        assert!(raw.len() < log.to_bytes().len());
+
+        // Logs stored before the compression was enabled are still readable.
+        let raw = store.get_raw_bytes(plain).unwrap();
+        assert!(!OperationLog::<TestOp>::is_compressed(&raw));
+        assert_eq!(store.get(plain).unwrap().stage, 1);
+
+        // Logs in the current encoding are not rewritten by the migration.
+        store.set_log_compression(false);
+        let batch = store.migrate_records_batch(OperationId::new(0), 10);
+        assert!(batch.migrated.is_empty());
+        let raw = store.get_raw_bytes(compressed).unwrap();
+        assert!(OperationLog::<TestOp>::is_compressed(&raw));
+        assert_eq!(store.get_log(compressed).unwrap().log().len(), 51);
+    }
+
     #[test]
     fn test_get_operation_by_memo() {
         const COUNT: u64 = 42;
@@ -636,6 +1666,182 @@ mod tests {
         assert_eq!(memos.len(), 10);
     }
 
+    #[test]
+    fn anonymized_operations_are_removed_from_indexes() {
+        let mut store = test_store(10);
+        let first = store.new_operation(TestOp::complete(1), Some([1; 32]));
+        let second = store.new_operation(TestOp::complete(1), Some([2; 32]));
+        let other = store.new_operation(TestOp::complete(2), Some([1; 32]));
+        let marker = AnonymizationMarker {
+            pseudonym: eth_address(42),
+            timestamp: 7,
+            audit_entry: 0,
+        };
+
+        let anonymized = store
+            .anonymize_address(&eth_address(1), marker.clone())
+            .unwrap();
+        assert_eq!(anonymized, vec![first, second]);
+
+        assert!(store
+            .get_for_address(&eth_address(1), None, None)
+            .is_empty());
+        assert!(store.get_memos_by_user_address(&eth_address(1)).is_empty());
+        assert!(store
+            .get_for_address(&eth_address(42), None, None)
+            .is_empty());
+
+        for id in [first, second] {
+            let log = store.get_log(id).unwrap();
+            assert_eq!(log.wallet_address(), &eth_address(42));
+            assert_eq!(log.memo(), None);
+            assert_eq!(log.anonymization(), Some(&marker));
+            assert_eq!(log.current_step().stage, COMPLETE);
+            assert!(store.get_public_log(id).is_none());
+            store.verify_consistency(id).unwrap();
+        }
+
+        // Blocks of the operations are redacted, and the erasure blocks are appended.
+        let blocks = store.blocks().range(0, 10);
+        assert_eq!(blocks.len(), 5);
+        assert!(blocks[0].1.is_redacted());
+        assert!(blocks[1].1.is_redacted());
+        assert!(!blocks[2].1.is_redacted());
+        assert_eq!(blocks[3].1.operation_id, first);
+        assert_eq!(blocks[3].1.anonymization, Some(marker.clone()));
+        assert_eq!(blocks[4].1.operation_id, second);
+        for pair in blocks.windows(2) {
+            assert_eq!(pair[1].1.parent_hash, Some(pair[0].1.hash()));
+        }
+
+        // Operations of the other users are not changed.
+        assert_eq!(
+            store
+                .get_operation_by_memo_and_user(&[1; 32], &eth_address(2))
+                .map(|(id, _)| id),
+            Some(other)
+        );
+        assert!(store.get_log(other).unwrap().anonymization().is_none());
+    }
+
+    #[test]
+    fn address_with_incomplete_operations_is_not_anonymized() {
+        let mut store = test_store(10);
+        let complete = store.new_operation(TestOp::complete(1), None);
+        let incomplete = store.new_operation(TestOp::new(1, 1), Some([1; 32]));
+        let marker = AnonymizationMarker {
+            pseudonym: eth_address(42),
+            timestamp: 7,
+            audit_entry: 0,
+        };
+
+        assert_eq!(
+            store.anonymize_address(&eth_address(1), marker),
+            Err(Error::UserDataErasurePending(vec![incomplete]))
+        );
+        assert_eq!(store.get_for_address(&eth_address(1), None, None).len(), 2);
+        assert!(store.get_log(complete).unwrap().anonymization().is_none());
+    }
+
+    #[test]
+    fn operations_linked_to_principal_are_anonymized() {
+        let mut store = test_store(10);
+        let linked = store.new_operation(TestOp::new(1, 1), Some([1; 32]));
+        store.update(linked, TestOp::complete(1));
+        let not_linked = store.new_operation(TestOp::complete(1), None);
+        let other = store.new_operation(TestOp::new(2, 1), None);
+        store.update(other, TestOp::complete(2));
+        let marker = AnonymizationMarker {
+            pseudonym: eth_address(42),
+            timestamp: 7,
+            audit_entry: 0,
+        };
+
+        let anonymized = store
+            .anonymize_principal(&test_principal(1), marker.clone())
+            .unwrap();
+        assert_eq!(anonymized, vec![linked]);
+        assert!(store.get_public_log(linked).is_none());
+        assert!(store
+            .get_operation_by_memo_and_user(&[1; 32], &eth_address(1))
+            .is_none());
+        store.verify_consistency(linked).unwrap();
+
+        // Other operations of the wallet address are still indexed.
+        let ids: Vec<_> = store
+            .get_for_address(&eth_address(1), None, None)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(ids, vec![not_linked]);
+        assert!(store.get_public_log(other).is_some());
+
+        // Anonymized operations are not erased twice.
+        assert!(store
+            .anonymize_principal(&test_principal(1), marker)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn principal_with_incomplete_operations_is_not_anonymized() {
+        let mut store = test_store(10);
+        let incomplete = store.new_operation(TestOp::new(1, 1), None);
+        let marker = AnonymizationMarker {
+            pseudonym: eth_address(42),
+            timestamp: 7,
+            audit_entry: 0,
+        };
+
+        assert_eq!(
+            store.anonymize_principal(&test_principal(1), marker),
+            Err(Error::UserDataErasurePending(vec![incomplete]))
+        );
+    }
+
+    #[test]
+    fn imported_erasure_anonymizes_operations() {
+        let mut primary = test_store(100);
+        let id = primary.new_operation(TestOp::complete(1), Some([1; 32]));
+        let mut replica = test_store(100);
+        for (index, block) in primary.blocks().range(0, 10) {
+            replica.import_block(index, block).unwrap();
+        }
+
+        let marker = AnonymizationMarker {
+            pseudonym: eth_address(42),
+            timestamp: 7,
+            audit_entry: 0,
+        };
+        primary
+            .anonymize_address(&eth_address(1), marker.clone())
+            .unwrap();
+        replica
+            .import_block(1, primary.blocks().get(1).unwrap())
+            .unwrap();
+
+        assert_eq!(replica.blocks().range(0, 10), primary.blocks().range(0, 10));
+        assert_eq!(replica.get_log(id).unwrap().anonymization(), Some(&marker));
+        assert!(replica
+            .get_for_address(&eth_address(1), None, None)
+            .is_empty());
+
+        // A replica, synced after the erasure, imports the redacted blocks only.
+        let mut late_replica = test_store(100);
+        for (index, block) in primary.blocks().range(0, 10) {
+            late_replica.import_block(index, block).unwrap();
+        }
+        assert_eq!(
+            late_replica.blocks().range(0, 10),
+            primary.blocks().range(0, 10)
+        );
+        assert!(late_replica.get_log(id).is_none());
+        assert_eq!(
+            late_replica.peek_next_operation_id(),
+            primary.peek_next_operation_id()
+        );
+    }
+
     #[test]
     fn test_memo_operations_are_cleared_when_evicted() {
         const LIMIT: u64 = 10;
@@ -684,4 +1890,260 @@ mod tests {
                 .is_none());
         }
     }
+
+    #[test]
+    fn complete_operation_is_added_to_outbox() {
+        let mut store = test_store(10);
+        let destinations = vec![
+            OutboxDestination::Webhook {
+                url: "https://partner.example/hook".into(),
+            },
+            OutboxDestination::Canister {
+                canister_id: candid::Principal::from_slice(&[1; 29]),
+                method: "on_operation_complete".into(),
+            },
+        ];
+        store.set_notification_destinations(destinations.clone());
+
+        let id = store.new_operation(TestOp::new(1, 1), Some([7; 32]));
+        store.update(id, TestOp::new(1, 2));
+        store.update_with_err(id, "failed".into());
+        assert!(store.outbox().get_pending(0, 10).is_empty());
+
+        store.update(id, TestOp::complete(1));
+        let messages = store.outbox().get_pending(0, 10);
+        assert_eq!(messages.len(), 2);
+        for (message, destination) in messages.iter().zip(&destinations) {
+            assert_eq!(&message.destination, destination);
+            assert_eq!(message.payload.operation_id, id);
+            assert_eq!(message.payload.wallet_address, eth_address(1));
+            assert_eq!(message.payload.memo, Some([7; 32]));
+        }
+
+        // Without destinations, nothing is added.
+        store.set_notification_destinations(vec![]);
+        store.new_operation(TestOp::complete(2), None);
+        assert_eq!(store.outbox().get_pending(0, 10).len(), 2);
+    }
+
+    #[test]
+    fn illegal_transitions_are_rejected() {
+        let mut store = test_store(10);
+        let id = store.new_operation(TestOp::new(1, 2), None);
+
+        assert!(!store.update(id, TestOp::new(1, 1)));
+        assert_eq!(store.get(id).map(|op| op.stage), Some(2));
+        assert_eq!(store.get_log(id).unwrap().log().len(), 1);
+        assert_eq!(store.blocks().len(), 1);
+
+        assert!(store.update(id, TestOp::new(1, 3)));
+        assert_eq!(store.get(id).map(|op| op.stage), Some(3));
+        assert_eq!(store.blocks().len(), 2);
+    }
+
+    #[test]
+    fn consistent_operations_are_verified() {
+        let mut store = test_store(10);
+        let id = store.new_operation(TestOp::new(1, 1), None);
+        store.update(id, TestOp::new(1, 2));
+        store.update_with_err(id, "failed".into());
+        assert_eq!(store.verify_consistency(id), Ok(()));
+
+        store.update(id, TestOp::complete(1));
+        assert_eq!(store.verify_consistency(id), Ok(()));
+
+        assert!(store.verify_consistency(OperationId::new(42)).is_err());
+    }
+
+    #[test]
+    fn drifted_operations_are_reported() {
+        let mut store = test_store(10);
+
+        // The log contains an illegal transition, e.g. written by a bug before the
+        // transitions were validated.
+        let id = store.new_operation(TestOp::new(1, 1), None);
+        let mut log = store.get_log(id).unwrap();
+        log.add_step(Ok(TestOp::new(1, 3)));
+        log.add_step(Err("failed".into()));
+        log.add_step(Ok(TestOp::new(1, 2)));
+        store.incomplete_operations.insert(id, store.stored(log));
+
+        assert_eq!(
+            store.verify_consistency(id),
+            Err("step 3: illegal transition from the state of the previous step".to_string())
+        );
+
+        // Complete operation is left among the incomplete ones.
+        let id = store.new_operation(TestOp::new(2, 1), None);
+        let mut log = store.get_log(id).unwrap();
+        log.add_step(Ok(TestOp::complete(2)));
+        store.incomplete_operations.insert(id, store.stored(log));
+
+        let err = store.verify_consistency(id).unwrap_err();
+        assert!(err.contains("stored as not complete"), "{err}");
+    }
+
+    #[test]
+    fn operation_transitions_are_recorded_in_blocks() {
+        const LIMIT: u64 = 2;
+        let mut store = test_store(LIMIT);
+
+        let id = store.new_operation(TestOp::new(1, 1), None);
+        store.update(id, TestOp::new(1, 2));
+        store.update_with_err(id, "failed".into());
+        // Repeated failure is not recorded again.
+        store.update_with_err(id, "failed".into());
+        store.update(id, TestOp::complete(1));
+
+        let blocks = store.blocks().range(0, 10);
+        assert_eq!(blocks.len(), 4);
+        for (_, block) in &blocks {
+            assert_eq!(block.operation_id, id);
+            assert_eq!(block.wallet_address, eth_address(1));
+        }
+        let state = Decode!(blocks[1].1.step.as_ref().unwrap(), TestOp).unwrap();
+        assert_eq!((state.addr, state.stage), (1, 2));
+        assert_eq!(blocks[2].1.step, Err("failed".to_string()));
+        assert_eq!(blocks[3].1.parent_hash, Some(blocks[2].1.hash()));
+
+        // Blocks are kept after the operations are removed from the store.
+        for i in 2..(LIMIT as u32 + 4) {
+            store.new_operation(TestOp::complete(i), None);
+        }
+        assert!(store.get_log(id).is_none());
+        assert_eq!(store.blocks().len(), 4 + LIMIT + 2);
+        assert_eq!(store.blocks().get(0).unwrap().operation_id, id);
+    }
+
+    #[test]
+    fn imported_blocks_reproduce_operations() {
+        let mut primary = test_store(100);
+        let first = primary.new_operation(TestOp::new(1, 1), None);
+        primary.update(first, TestOp::new(1, 2));
+        let second = primary.new_operation(TestOp::new(2, 1), None);
+        primary.update_with_err(second, "failed".into());
+        primary.update(first, TestOp::complete(1));
+
+        let mut replica = test_store(100);
+        replica.set_notification_destinations(vec![OutboxDestination::Webhook {
+            url: "https://example.com".into(),
+        }]);
+        for (index, block) in primary.blocks().range(0, 10) {
+            replica.import_block(index, block).unwrap();
+        }
+
+        assert_eq!(replica.blocks().range(0, 10), primary.blocks().range(0, 10));
+        assert!(replica.get(first).unwrap().is_complete());
+        assert_eq!(replica.get(second).unwrap().stage, 1);
+        assert_eq!(
+            replica.get_status(second).unwrap().state_class,
+            OperationStateClass::Retrying
+        );
+        assert_eq!(
+            replica.peek_next_operation_id(),
+            primary.peek_next_operation_id()
+        );
+
+        // Notifications are sent by the primary only.
+        assert!(replica.outbox().get_pending(0, 10).is_empty());
+    }
+
+    #[test]
+    fn update_of_complete_operation_is_not_imported() {
+        let mut primary = test_store(100);
+        let id = primary.new_operation(TestOp::new(1, 1), None);
+
+        let mut replica = test_store(100);
+        replica
+            .import_block(0, primary.blocks().get(0).unwrap())
+            .unwrap();
+        let complete = OperationBlock {
+            parent_hash: Some(replica.blocks().get(0).unwrap().hash()),
+            timestamp: 0,
+            operation_id: id,
+            wallet_address: eth_address(1),
+            step: Ok(Encode!(&TestOp::complete(1)).unwrap()),
+            anonymization: None,
+            redacted_hash: None,
+        };
+        replica.import_block(1, complete.clone()).unwrap();
+        let update = OperationBlock {
+            parent_hash: Some(complete.hash()),
+            ..complete
+        };
+        assert!(replica.import_block(2, update).is_err());
+        assert_eq!(replica.blocks().len(), 2);
+    }
+
+    #[test]
+    fn should_get_status_batch_in_request_order() {
+        let mut store = test_store(100);
+
+        let pending = store.new_operation(TestOp::new(1, 1), None);
+        let complete = store.new_operation(TestOp::complete(2), None);
+        let retrying = store.new_operation(TestOp::new(3, 1), None);
+        store.update(retrying, TestOp::new(3, TX_SENT));
+        store.update(retrying, TestOp::new(3, TX_SENT + 1));
+        store.update_with_err(retrying, "failed".into());
+        let unknown = OperationId::new(42);
+
+        let statuses = store
+            .get_status_batch(vec![retrying, unknown, complete, pending])
+            .unwrap();
+        let ids: Vec<_> = statuses.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![retrying, unknown, complete, pending]);
+
+        let retrying_status = statuses[0].1.as_ref().unwrap();
+        assert_eq!(retrying_status.state_class, OperationStateClass::Retrying);
+        assert_eq!(
+            retrying_status.tx_hashes,
+            vec![
+                H256::from_low_u64_be(TX_SENT as _),
+                H256::from_low_u64_be(TX_SENT as u64 + 1)
+            ]
+        );
+        assert_eq!(
+            retrying_status.last_transition_timestamp,
+            store
+                .get_log(retrying)
+                .unwrap()
+                .log()
+                .last()
+                .unwrap()
+                .time_stamp
+        );
+        assert_eq!(statuses[1].1, None);
+        assert_eq!(
+            statuses[2].1.as_ref().unwrap().state_class,
+            OperationStateClass::Complete
+        );
+        assert_eq!(
+            statuses[3].1.as_ref().unwrap().state_class,
+            OperationStateClass::InProgress
+        );
+        assert!(statuses[3].1.as_ref().unwrap().tx_hashes.is_empty());
+    }
+
+    #[test]
+    fn status_batch_should_be_limited() {
+        let mut store = test_store(MAX_OPERATIONS_STATUS_BATCH + 1);
+
+        let ids: Vec<_> = (0..=MAX_OPERATIONS_STATUS_BATCH as u32)
+            .map(|i| store.new_operation(TestOp::new(i, TX_SENT + i), None))
+            .collect();
+        assert_eq!(
+            store.get_status_batch(ids.clone()),
+            Err(Error::OperationsBatchTooLarge(MAX_OPERATIONS_STATUS_BATCH))
+        );
+
+        let statuses = store
+            .get_status_batch(ids[..MAX_OPERATIONS_STATUS_BATCH as usize].to_vec())
+            .unwrap();
+        assert_eq!(statuses.len(), MAX_OPERATIONS_STATUS_BATCH as usize);
+        assert!(statuses.iter().all(|(_, status)| status.is_some()));
+
+        // The maximal batch response is well below the query response limit.
+        let response = Encode!(&statuses).unwrap();
+        assert!(response.len() < 32 * 1024, "{} bytes", response.len());
+    }
 }
@@ -0,0 +1,117 @@
+//! Log of the bulk actions, performed by the operator. Every action is recorded with
+//! the acting principal, the filter and the outcomes for the affected operations.
+//! The operator can also add free-form notes to the log. User data erasures are recorded
+//! as notes with the pseudonym of the erased identity.
+
+use bridge_did::bulk_action::{BulkAction, BulkActionOutcome, OperationFilter, OperatorAuditEntry};
+use bridge_did::op_id::OperationId;
+use candid::Principal;
+use did::H160;
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, StableBTreeMap};
+
+/// Max number of the entries returned by a single request.
+pub const MAX_AUDIT_ENTRIES_PER_REQUEST: usize = 100;
+
+/// Operator audit entries, stored in IC stable memory.
+pub struct OperatorAuditLog<M: Memory> {
+    entries: StableBTreeMap<u64, OperatorAuditEntry, M>,
+}
+
+impl<M: Memory> OperatorAuditLog<M> {
+    /// Creates a new instance of the log.
+    pub fn with_memory(memory: M) -> Self {
+        Self {
+            entries: StableBTreeMap::new(memory),
+        }
+    }
+
+    /// Appends the entry of the bulk action. Returns id of the stored entry.
+    /// Entries are never removed, so the ids are sequential.
+    pub fn push(
+        &mut self,
+        caller: Principal,
+        action: BulkAction,
+        filter: OperationFilter,
+        outcomes: Vec<(OperationId, BulkActionOutcome)>,
+        timestamp: u64,
+    ) -> u64 {
+        self.insert(|id| OperatorAuditEntry {
+            id,
+            timestamp,
+            caller,
+            action: Some(action),
+            filter,
+            outcomes,
+            note: None,
+        })
+    }
+
+    /// Appends the operator note. Returns id of the stored entry.
+    pub fn push_note(&mut self, caller: Principal, note: String, timestamp: u64) -> u64 {
+        self.insert(|id| OperatorAuditEntry {
+            id,
+            timestamp,
+            caller,
+            action: None,
+            filter: OperationFilter::default(),
+            outcomes: vec![],
+            note: Some(note),
+        })
+    }
+
+    /// Appends the entry of the user data erasure. The erased identity is not recorded,
+    /// only its pseudonym and the anonymized operations. Returns id of the stored entry.
+    pub fn push_erasure(
+        &mut self,
+        caller: Principal,
+        pseudonym: &H160,
+        operations: &[OperationId],
+        timestamp: u64,
+    ) -> u64 {
+        self.insert(|id| OperatorAuditEntry {
+            id,
+            timestamp,
+            caller,
+            action: None,
+            filter: OperationFilter::default(),
+            outcomes: operations
+                .iter()
+                .map(|id| (*id, BulkActionOutcome::Applied))
+                .collect(),
+            note: Some(format!("user data erased, pseudonym {pseudonym}")),
+        })
+    }
+
+    /// Returns id of the next appended entry.
+    pub fn next_id(&self) -> u64 {
+        self.entries.len()
+    }
+
+    fn insert(&mut self, entry: impl FnOnce(u64) -> OperatorAuditEntry) -> u64 {
+        let id = self.entries.len();
+        self.entries.insert(id, entry(id));
+
+        id
+    }
+
+    /// Returns up to `count` entries, skipping `offset` oldest ones.
+    /// At most [`MAX_AUDIT_ENTRIES_PER_REQUEST`] entries are returned.
+    pub fn get(&self, offset: usize, count: usize) -> Vec<OperatorAuditEntry> {
+        self.entries
+            .range(offset as u64..)
+            .take(count.min(MAX_AUDIT_ENTRIES_PER_REQUEST))
+            .map(|(_, entry)| entry)
+            .collect()
+    }
+
+    /// Number of the entries.
+    pub fn len(&self) -> u64 {
+        self.entries.len()
+    }
+
+    /// Checks if there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,389 @@
+//! Transactional outbox of the bridge canisters. Notifications about the operations are appended
+//! to the outbox in the same step as the operation state transition, and are delivered by the
+//! [`DispatchOutboxService`](crate::runtime::service::outbox::DispatchOutboxService).
+
+use std::borrow::Cow;
+use std::time::Duration;
+
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::outbox::{OperationNotification, OutboxDestination, OutboxMessage};
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{
+    BTreeMapStructure, Bound, CellStructure, StableBTreeMap, StableCell, Storable,
+};
+
+/// Number of the failed delivery attempts, after which the message is parked.
+pub const OUTBOX_MAX_ATTEMPTS: u32 = 10;
+
+/// Delay before the second delivery attempt. Doubled after every failed attempt.
+const BACKOFF_BASE: Duration = Duration::from_secs(10);
+
+/// Max delay between the delivery attempts.
+const BACKOFF_MAX: Duration = Duration::from_secs(60 * 60);
+
+/// Memory objects to store the outbox.
+#[derive(Clone)]
+pub struct OutboxMemory<Mem> {
+    pub id_counter: Mem,
+    pub pending: Mem,
+    pub parked: Mem,
+    pub due: Mem,
+}
+
+/// Key of the pending message, ordered by the time of the next delivery attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct DueKey {
+    next_attempt_at: u64,
+    id: u64,
+}
+
+impl DueKey {
+    const SIZE: usize = 16;
+
+    fn of(message: &OutboxMessage) -> Self {
+        Self {
+            next_attempt_at: message.next_attempt_at,
+            id: message.id,
+        }
+    }
+}
+
+impl Storable for DueKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        // Big endian numbers keep the byte order equal to the key order.
+        bytes.extend_from_slice(&self.next_attempt_at.to_be_bytes());
+        bytes.extend_from_slice(&self.id.to_be_bytes());
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let u64_at = |offset: usize| {
+            u64::from_be_bytes(
+                bytes[offset..offset + 8]
+                    .try_into()
+                    .expect("invalid outbox due key size"),
+            )
+        };
+
+        Self {
+            next_attempt_at: u64_at(0),
+            id: u64_at(8),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: Self::SIZE as u32,
+        is_fixed_size: true,
+    };
+}
+
+/// A queue of the messages to be delivered to external destinations, stored in IC stable memory.
+///
+/// A message stays in the queue until it is delivered, so a message is delivered at least once,
+/// even if the canister is upgraded during the delivery. Messages, failed to be delivered
+/// [`OUTBOX_MAX_ATTEMPTS`] times, are parked until they are requeued or dropped by the owner.
+pub struct Outbox<M: Memory> {
+    id_counter: StableCell<u64, M>,
+    pending: StableBTreeMap<u64, OutboxMessage, M>,
+    parked: StableBTreeMap<u64, OutboxMessage, M>,
+    /// Index of the pending messages by the time of the next delivery attempt.
+    due: StableBTreeMap<DueKey, (), M>,
+}
+
+impl<M: Memory> Outbox<M> {
+    /// Creates a new instance of the outbox. The due index is built, if the outbox has
+    /// the pending messages stored before the index was introduced.
+    pub fn with_memory(memory: OutboxMemory<M>) -> Self {
+        let mut outbox = Self {
+            id_counter: StableCell::new(memory.id_counter, 0)
+                .expect("failed to initialize outbox id counter"),
+            pending: StableBTreeMap::new(memory.pending),
+            parked: StableBTreeMap::new(memory.parked),
+            due: StableBTreeMap::new(memory.due),
+        };
+
+        if outbox.due.is_empty() {
+            for (_, message) in outbox.pending.iter() {
+                outbox.due.insert(DueKey::of(&message), ());
+            }
+        }
+
+        outbox
+    }
+
+    /// Appends a new message to the queue. The message is due immediately.
+    pub fn push(
+        &mut self,
+        destination: OutboxDestination,
+        payload: OperationNotification,
+        timestamp: u64,
+    ) -> u64 {
+        let id = *self.id_counter.get();
+        self.id_counter
+            .set(id + 1)
+            .expect("failed to update outbox id counter");
+
+        self.insert_pending(OutboxMessage {
+            id,
+            destination,
+            payload,
+            created_at: timestamp,
+            attempts: 0,
+            next_attempt_at: timestamp,
+            last_error: None,
+        });
+
+        id
+    }
+
+    /// Returns up to `limit` pending messages, due to be delivered at `now`.
+    pub fn due(&self, now: u64, limit: usize) -> Vec<OutboxMessage> {
+        self.due
+            .iter()
+            .map(|(key, _)| key)
+            .take_while(|key| key.next_attempt_at <= now)
+            .take(limit)
+            .filter_map(|key| self.pending.get(&key.id))
+            .collect()
+    }
+
+    /// Registers the delivery attempt of the message. The next attempt is delayed with the
+    /// exponential backoff, so the message is not delivered concurrently, and is retried
+    /// if the attempt is never finished, e.g. due to canister upgrade.
+    pub fn start_attempt(&mut self, id: u64, now: u64) -> Option<OutboxMessage> {
+        let mut message = self.remove_pending(id)?;
+        message.attempts += 1;
+        message.next_attempt_at = now + backoff(message.attempts).as_nanos() as u64;
+        self.insert_pending(message.clone());
+
+        Some(message)
+    }
+
+    /// Removes the delivered message from the queue.
+    pub fn delivered(&mut self, id: u64) {
+        self.remove_pending(id);
+    }
+
+    /// Records the delivery failure. If the message has no attempts left, it is parked.
+    /// Returns `true` if the message is parked.
+    pub fn failed(&mut self, id: u64, error: String) -> bool {
+        let Some(mut message) = self.pending.get(&id) else {
+            return false;
+        };
+        message.last_error = Some(error);
+
+        if message.attempts >= OUTBOX_MAX_ATTEMPTS {
+            self.remove_pending(id);
+            self.parked.insert(id, message);
+            true
+        } else {
+            self.pending.insert(id, message);
+            false
+        }
+    }
+
+    /// Returns up to `count` pending messages, skipping `offset` oldest ones.
+    pub fn get_pending(&self, offset: usize, count: usize) -> Vec<OutboxMessage> {
+        Self::page(&self.pending, offset, count)
+    }
+
+    /// Returns up to `count` parked messages, skipping `offset` oldest ones.
+    pub fn get_parked(&self, offset: usize, count: usize) -> Vec<OutboxMessage> {
+        Self::page(&self.parked, offset, count)
+    }
+
+    /// Moves the parked message back to the queue with all the delivery attempts available.
+    pub fn requeue(&mut self, id: u64, now: u64) -> BTFResult<()> {
+        let mut message = self
+            .parked
+            .remove(&id)
+            .ok_or(Error::OutboxMessageNotFound(id))?;
+        message.attempts = 0;
+        message.next_attempt_at = now;
+        self.insert_pending(message);
+
+        Ok(())
+    }
+
+    /// Removes the parked message without delivery.
+    pub fn drop_parked(&mut self, id: u64) -> BTFResult<()> {
+        self.parked
+            .remove(&id)
+            .map(|_| ())
+            .ok_or(Error::OutboxMessageNotFound(id))
+    }
+
+    fn insert_pending(&mut self, message: OutboxMessage) {
+        self.due.insert(DueKey::of(&message), ());
+        self.pending.insert(message.id, message);
+    }
+
+    fn remove_pending(&mut self, id: u64) -> Option<OutboxMessage> {
+        let message = self.pending.remove(&id)?;
+        self.due.remove(&DueKey::of(&message));
+        Some(message)
+    }
+
+    fn page(
+        messages: &StableBTreeMap<u64, OutboxMessage, M>,
+        offset: usize,
+        count: usize,
+    ) -> Vec<OutboxMessage> {
+        messages
+            .iter()
+            .skip(offset)
+            .take(count)
+            .map(|(_, message)| message)
+            .collect()
+    }
+}
+
+/// Delay before the next delivery attempt after the given number of attempts.
+pub fn backoff(attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    BACKOFF_BASE.saturating_mul(factor).min(BACKOFF_MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::op_id::OperationId;
+    use candid::Principal;
+    use did::H160;
+    use ic_stable_structures::VectorMemory;
+
+    use super::*;
+
+    fn memory() -> OutboxMemory<VectorMemory> {
+        OutboxMemory {
+            id_counter: VectorMemory::default(),
+            pending: VectorMemory::default(),
+            parked: VectorMemory::default(),
+            due: VectorMemory::default(),
+        }
+    }
+
+    fn destination() -> OutboxDestination {
+        OutboxDestination::Canister {
+            canister_id: Principal::from_slice(&[1; 29]),
+            method: "on_operation_complete".into(),
+        }
+    }
+
+    fn notification(id: u64) -> OperationNotification {
+        OperationNotification {
+            operation_id: OperationId::new(id),
+            wallet_address: H160::from_slice(&[2; 20]),
+            memo: None,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn backoff_is_exponential_and_capped() {
+        assert_eq!(backoff(1), Duration::from_secs(10));
+        assert_eq!(backoff(2), Duration::from_secs(20));
+        assert_eq!(backoff(4), Duration::from_secs(80));
+        assert_eq!(backoff(20), BACKOFF_MAX);
+        assert_eq!(backoff(u32::MAX), BACKOFF_MAX);
+    }
+
+    #[test]
+    fn failed_message_is_retried_with_backoff() {
+        let mut outbox = Outbox::with_memory(memory());
+        let id = outbox.push(destination(), notification(1), 100);
+        assert_eq!(outbox.due(100, 10).len(), 1);
+
+        let message = outbox.start_attempt(id, 100).unwrap();
+        assert_eq!(message.attempts, 1);
+        assert!(!outbox.failed(id, "rejected".into()));
+
+        let retry_at = 100 + backoff(1).as_nanos() as u64;
+        assert!(outbox.due(retry_at - 1, 10).is_empty());
+        let due = outbox.due(retry_at, 10);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].last_error.as_deref(), Some("rejected"));
+
+        outbox.start_attempt(id, retry_at).unwrap();
+        outbox.delivered(id);
+        assert!(outbox.due(u64::MAX, 10).is_empty());
+        assert!(outbox.get_pending(0, 10).is_empty());
+    }
+
+    #[test]
+    fn message_is_delivered_at_least_once_across_upgrade() {
+        let memory = memory();
+        let mut outbox = Outbox::with_memory(memory.clone());
+        let id = outbox.push(destination(), notification(1), 0);
+        outbox.start_attempt(id, 0).unwrap();
+
+        // The canister is upgraded before the delivery result is recorded.
+        let mut outbox = Outbox::with_memory(memory);
+        let retry_at = backoff(1).as_nanos() as u64;
+        let due = outbox.due(retry_at, 10);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, id);
+        assert_eq!(due[0].attempts, 1);
+
+        // Ids are not reused after the upgrade.
+        assert_eq!(outbox.push(destination(), notification(2), 0), id + 1);
+    }
+
+    #[test]
+    fn message_is_parked_after_max_attempts() {
+        let mut outbox = Outbox::with_memory(memory());
+        let id = outbox.push(destination(), notification(1), 0);
+
+        for attempt in 1..=OUTBOX_MAX_ATTEMPTS {
+            let message = outbox.due(u64::MAX, 10).pop().unwrap();
+            outbox.start_attempt(message.id, 0).unwrap();
+            let parked = outbox.failed(id, format!("attempt {attempt} failed"));
+            assert_eq!(parked, attempt == OUTBOX_MAX_ATTEMPTS);
+        }
+
+        assert!(outbox.due(u64::MAX, 10).is_empty());
+        let parked = outbox.get_parked(0, 10);
+        assert_eq!(parked.len(), 1);
+        assert_eq!(parked[0].attempts, OUTBOX_MAX_ATTEMPTS);
+        assert_eq!(parked[0].last_error.as_deref(), Some("attempt 10 failed"));
+    }
+
+    #[test]
+    fn parked_message_is_requeued_or_dropped() {
+        let mut outbox = Outbox::with_memory(memory());
+        for op in 0..2 {
+            let id = outbox.push(destination(), notification(op), 0);
+            for _ in 0..OUTBOX_MAX_ATTEMPTS {
+                outbox.start_attempt(id, 0).unwrap();
+                outbox.failed(id, "unreachable".into());
+            }
+        }
+        assert_eq!(outbox.get_parked(0, 10).len(), 2);
+
+        outbox.requeue(0, 500).unwrap();
+        let due = outbox.due(500, 10);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].attempts, 0);
+
+        outbox.drop_parked(1).unwrap();
+        assert!(outbox.get_parked(0, 10).is_empty());
+
+        assert_eq!(outbox.requeue(1, 0), Err(Error::OutboxMessageNotFound(1)));
+        assert_eq!(outbox.drop_parked(0), Err(Error::OutboxMessageNotFound(0)));
+    }
+
+    #[test]
+    fn due_messages_are_ordered_by_next_attempt() {
+        let mut outbox = Outbox::with_memory(memory());
+        for op in 0..3 {
+            outbox.push(destination(), notification(op), 100);
+        }
+        outbox.start_attempt(0, 100).unwrap();
+        outbox.failed(0, "rejected".into());
+
+        let ids = |due: Vec<OutboxMessage>| due.iter().map(|m| m.id).collect::<Vec<_>>();
+        assert_eq!(ids(outbox.due(100, 10)), vec![1, 2]);
+        assert_eq!(ids(outbox.due(100, 1)), vec![1]);
+        assert_eq!(ids(outbox.due(u64::MAX, 10)), vec![1, 2, 0]);
+    }
+}
@@ -0,0 +1,143 @@
+//! Store of the signed mint orders batches, which wait to be sent to the EVM by
+//! the [`SendMintTxService`]. The service keeps the batches in heap memory, and mirrors
+//! their operations in this store, so the batches are restored after a canister upgrade.
+//!
+//! [`SendMintTxService`]: crate::runtime::service::mint_tx::SendMintTxService
+
+use std::time::Duration;
+
+use bridge_did::op_id::OperationId;
+use bridge_did::pending_mint_batch::{
+    PendingMintBatch, PendingMintBatchKey, PendingMintBatchesStats,
+};
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, StableBTreeMap};
+
+/// Pending mint batches, stored in IC stable memory.
+pub struct PendingMintBatchStore<M: Memory> {
+    batches: StableBTreeMap<PendingMintBatchKey, PendingMintBatch, M>,
+}
+
+impl<M: Memory> PendingMintBatchStore<M> {
+    /// Creates a new instance of the store. Batches, stored in the memory, are kept.
+    pub fn with_memory(memory: M) -> Self {
+        Self {
+            batches: StableBTreeMap::new(memory),
+        }
+    }
+
+    /// Adds the operation to the batch. The batch is created at the `now` IC time,
+    /// if it is not stored yet.
+    pub fn add(&mut self, key: PendingMintBatchKey, op_id: OperationId, now: u64) {
+        let mut batch = self.batches.get(&key).unwrap_or(PendingMintBatch {
+            operations: vec![],
+            pushed_at: now,
+        });
+        if !batch.operations.contains(&op_id) {
+            batch.operations.push(op_id);
+            self.batches.insert(key, batch);
+        }
+    }
+
+    /// Removes the operation from the batch. The batch is removed, if it has no operations left.
+    pub fn remove_operation(&mut self, key: &PendingMintBatchKey, op_id: OperationId) {
+        let Some(mut batch) = self.batches.get(key) else {
+            return;
+        };
+        batch.operations.retain(|id| *id != op_id);
+        if batch.operations.is_empty() {
+            self.batches.remove(key);
+        } else {
+            self.batches.insert(key.clone(), batch);
+        }
+    }
+
+    /// Removes the batch and returns it.
+    pub fn remove(&mut self, key: &PendingMintBatchKey) -> Option<PendingMintBatch> {
+        self.batches.remove(key)
+    }
+
+    /// Returns all the pending batches.
+    pub fn batches(&self) -> Vec<(PendingMintBatchKey, PendingMintBatch)> {
+        self.batches.iter().collect()
+    }
+
+    /// Returns the batches, pending longer than `max_age` at the `now` IC time.
+    pub fn expired(
+        &self,
+        now: u64,
+        max_age: Duration,
+    ) -> Vec<(PendingMintBatchKey, PendingMintBatch)> {
+        let max_age = max_age.as_nanos() as u64;
+        self.batches
+            .iter()
+            .filter(|(_, batch)| now.saturating_sub(batch.pushed_at) > max_age)
+            .collect()
+    }
+
+    /// Returns the number and age of the pending batches at the `now` IC time.
+    pub fn stats(&self, now: u64, max_age: Duration) -> PendingMintBatchesStats {
+        let mut stats = PendingMintBatchesStats {
+            max_batch_age_secs: max_age.as_secs(),
+            ..Default::default()
+        };
+        for (_, batch) in self.batches.iter() {
+            stats.batches += 1;
+            stats.operations += batch.operations.len() as u64;
+
+            let age = Duration::from_nanos(now.saturating_sub(batch.pushed_at)).as_secs();
+            stats.oldest_batch_age_secs = stats.oldest_batch_age_secs.max(Some(age));
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use did::H256;
+    use ic_stable_structures::VectorMemory;
+
+    use super::*;
+
+    fn key(byte: u8) -> PendingMintBatchKey {
+        PendingMintBatchKey {
+            digest: H256::from_slice(&[byte; 32]),
+            isolated: None,
+        }
+    }
+
+    #[test]
+    fn batches_keep_their_first_push_time() {
+        let mut store = PendingMintBatchStore::with_memory(VectorMemory::default());
+        let secs = |secs: u64| Duration::from_secs(secs).as_nanos() as u64;
+
+        store.add(key(1), OperationId::new(1), secs(10));
+        store.add(key(1), OperationId::new(2), secs(50));
+        store.add(key(1), OperationId::new(2), secs(60));
+        store.add(key(2), OperationId::new(3), secs(70));
+
+        let max_age = Duration::from_secs(30);
+        let expired = store.expired(secs(80), max_age);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].0, key(1));
+        assert_eq!(
+            expired[0].1.operations,
+            vec![OperationId::new(1), OperationId::new(2)]
+        );
+
+        assert_eq!(
+            store.stats(secs(80), max_age),
+            PendingMintBatchesStats {
+                batches: 2,
+                operations: 3,
+                oldest_batch_age_secs: Some(70),
+                max_batch_age_secs: 30,
+            }
+        );
+
+        store.remove(&key(1));
+        assert!(store.expired(secs(80), max_age).is_empty());
+        assert_eq!(store.batches().len(), 1);
+    }
+}
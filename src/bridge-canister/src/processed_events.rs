@@ -0,0 +1,427 @@
+//! Index of the processed BTF bridge events. The index is scoped by the address of the
+//! contract which emitted the event, so after the BTF bridge contract is re-deployed
+//! the events of the previous contract are never processed again.
+//!
+//! The processed events are also indexed by the EVM address of the user, the event belongs to.
+//! Both indexes are pruned together.
+
+use bridge_did::op_id::OperationId;
+use bridge_did::processed_event::{
+    EventKey, ProcessedEvent, ProcessedEventInfo, RejectedEventsStats,
+};
+use bridge_utils::btf_events::BridgeEventLog;
+use bridge_utils::common::Pagination;
+use did::H160;
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, MultimapStructure, StableBTreeMap, StableMultimap};
+
+/// Number of the blocks, for which the processed events are kept in the index.
+pub const PROCESSED_EVENTS_RETENTION_BLOCKS: u64 = 10_000;
+
+/// Memory of the processed events indexes.
+#[derive(Clone)]
+pub struct ProcessedEventsMemory<M> {
+    pub events: M,
+    pub senders: M,
+    pub by_sender: M,
+}
+
+/// Processed events, stored in IC stable memory.
+pub struct ProcessedEvents<M: Memory> {
+    events: StableBTreeMap<EventKey, (), M>,
+    /// Senders of the indexed events, ordered by the block, so they can be pruned by range.
+    senders: StableBTreeMap<EventKey, H160, M>,
+    by_sender: StableMultimap<H160, EventKey, ProcessedEvent, M>,
+    stats: RejectedEventsStats,
+}
+
+impl<M: Memory> ProcessedEvents<M> {
+    /// Creates a new instance of the index.
+    pub fn with_memory(memory: ProcessedEventsMemory<M>) -> Self {
+        Self {
+            events: StableBTreeMap::new(memory.events),
+            senders: StableBTreeMap::new(memory.senders),
+            by_sender: StableMultimap::new(memory.by_sender),
+            stats: RejectedEventsStats::default(),
+        }
+    }
+
+    /// Checks if the event log should be processed and, if so, records it as processed
+    /// at the `processed_at` IC time.
+    ///
+    /// The log is rejected if it is emitted by a contract other than `bridge_contract`,
+    /// if it is emitted before the contract `activation_block`, or if it was already processed.
+    pub fn accept(
+        &mut self,
+        log: &BridgeEventLog,
+        bridge_contract: &H160,
+        activation_block: u64,
+        processed_at: u64,
+    ) -> bool {
+        if log.contract != *bridge_contract {
+            log::warn!(
+                "Ignoring event of contract {}, current BTF bridge contract is {bridge_contract}",
+                log.contract
+            );
+            self.stats.foreign_contract += 1;
+            return false;
+        }
+
+        if log
+            .block_number
+            .is_some_and(|block_number| block_number < activation_block)
+        {
+            log::warn!(
+                "Ignoring event from block {:?} emitted before the contract activation block {activation_block}",
+                log.block_number
+            );
+            self.stats.before_activation += 1;
+            return false;
+        }
+
+        let Some(key) = log.key() else {
+            log::warn!("Event log location is unknown, the event is not deduplicated: {log:?}");
+            return true;
+        };
+
+        if self.events.contains_key(&key) {
+            log::warn!("Ignoring already processed event: {key:?}");
+            self.stats.duplicate += 1;
+            return false;
+        }
+
+        // Unknown events are deduplicated, but don't belong to any user.
+        if let (Some(sender), Some(kind)) = (log.event.user_address(), log.event.kind()) {
+            let event = ProcessedEvent {
+                kind,
+                operation_id: None,
+                processed_at,
+            };
+            self.by_sender.insert(&sender, &key, event);
+            self.senders.insert(key.clone(), sender);
+        }
+        self.events.insert(key, ());
+        true
+    }
+
+    /// Records the operation, created or updated by the processed event with the given key.
+    pub fn set_operation_id(&mut self, key: &EventKey, operation_id: OperationId) {
+        let Some(sender) = self.senders.get(key) else {
+            return;
+        };
+        let Some(mut event) = self.by_sender.get(&sender, key) else {
+            return;
+        };
+
+        event.operation_id = Some(operation_id);
+        self.by_sender.insert(&sender, key, event);
+    }
+
+    /// Returns the processed events of the user with the given EVM address, ordered by block.
+    /// If `pagination` is `None`, all the events are returned.
+    pub fn get_for_sender(
+        &self,
+        sender: &H160,
+        pagination: Option<Pagination>,
+    ) -> Vec<ProcessedEventInfo> {
+        let pagination = pagination.unwrap_or_else(|| Pagination::new(0, usize::MAX));
+        self.by_sender
+            .range(sender)
+            .skip(pagination.offset)
+            .take(pagination.count)
+            .map(|(key, event)| event.info(&key))
+            .collect()
+    }
+
+    /// Removes the events emitted before the `block_number` from the index.
+    pub fn prune_before(&mut self, block_number: u64) {
+        let first_kept = EventKey::first_in_block(block_number);
+        let to_remove: Vec<EventKey> = self
+            .events
+            .iter()
+            .map(|(key, _)| key)
+            .take_while(|key| *key < first_kept)
+            .collect();
+
+        for key in to_remove {
+            self.events.remove(&key);
+        }
+
+        let senders_to_remove: Vec<(EventKey, H160)> = self
+            .senders
+            .iter()
+            .take_while(|(key, _)| *key < first_kept)
+            .collect();
+
+        for (key, sender) in senders_to_remove {
+            self.by_sender.remove(&sender, &key);
+            self.senders.remove(&key);
+        }
+    }
+
+    /// Returns counters of the rejected events since the canister start.
+    pub fn rejected_stats(&self) -> RejectedEventsStats {
+        self.stats.clone()
+    }
+
+    /// Number of the events in the index.
+    pub fn len(&self) -> u64 {
+        self.events.len()
+    }
+
+    /// Checks if the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::event_data::{
+        BurntEventData, MintedEventData, MinterNotificationType, NotifyMinterEventData, RawEventLog,
+    };
+    use bridge_did::processed_event::ProcessedEventKind;
+    use bridge_utils::btf_events::BridgeEvent;
+    use did::H256;
+    use ic_stable_structures::VectorMemory;
+
+    use super::*;
+
+    fn test_memory() -> ProcessedEventsMemory<VectorMemory> {
+        ProcessedEventsMemory {
+            events: VectorMemory::default(),
+            senders: VectorMemory::default(),
+            by_sender: VectorMemory::default(),
+        }
+    }
+
+    fn contract(seed: u8) -> H160 {
+        H160::from_slice(&[seed; 20])
+    }
+
+    fn burnt_log(contract: H160, block_number: u64, log_index: u64) -> BridgeEventLog {
+        BridgeEventLog {
+            event: BridgeEvent::Burnt(BurntEventData::default()),
+            contract,
+            block_number: Some(block_number),
+            tx_hash: Some(H256::from_slice(&[block_number as u8; 32])),
+            log_index: Some(log_index),
+        }
+    }
+
+    #[test]
+    fn duplicate_events_are_rejected() {
+        let mut index = ProcessedEvents::with_memory(test_memory());
+        let log = burnt_log(contract(1), 10, 0);
+
+        assert!(index.accept(&log, &contract(1), 0, 0));
+        assert!(!index.accept(&log, &contract(1), 0, 0));
+        assert!(index.accept(&burnt_log(contract(1), 10, 1), &contract(1), 0, 0));
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.rejected_stats().duplicate, 1);
+    }
+
+    #[test]
+    fn old_contract_events_are_ignored_after_switch() {
+        let mut index = ProcessedEvents::with_memory(test_memory());
+        let (old, new) = (contract(1), contract(2));
+
+        // The contract is switched at block 20, while the collected range is 10..=30.
+        let activation_block = 20;
+        let logs = [
+            burnt_log(old.clone(), 10, 0),
+            burnt_log(new.clone(), 15, 0),
+            burnt_log(new.clone(), 20, 0),
+            burnt_log(old.clone(), 25, 0),
+            burnt_log(new.clone(), 30, 0),
+        ];
+
+        let accepted: Vec<u64> = logs
+            .iter()
+            .filter(|log| index.accept(log, &new, activation_block, 0))
+            .filter_map(|log| log.block_number)
+            .collect();
+        assert_eq!(accepted, vec![20, 30]);
+
+        let stats = index.rejected_stats();
+        assert_eq!(stats.foreign_contract, 2);
+        assert_eq!(stats.before_activation, 1);
+        assert_eq!(stats.duplicate, 0);
+    }
+
+    #[test]
+    fn same_log_of_different_contracts_is_not_duplicate() {
+        let mut index = ProcessedEvents::with_memory(test_memory());
+        let old_log = burnt_log(contract(1), 10, 0);
+        let mut new_log = old_log.clone();
+        new_log.contract = contract(2);
+
+        assert!(index.accept(&old_log, &contract(1), 0, 0));
+        assert!(index.accept(&new_log, &contract(2), 0, 0));
+    }
+
+    #[test]
+    fn old_events_are_pruned() {
+        let memory = test_memory();
+        let mut index = ProcessedEvents::with_memory(memory.clone());
+        for block_number in 1..=5 {
+            assert!(index.accept(&burnt_log(contract(1), block_number, 0), &contract(1), 0, 0));
+        }
+
+        index.prune_before(3);
+        assert_eq!(index.len(), 3);
+
+        // Index survives the canister upgrade.
+        let mut index = ProcessedEvents::with_memory(memory);
+        assert!(!index.accept(&burnt_log(contract(1), 3, 0), &contract(1), 0, 0));
+        assert!(index.accept(&burnt_log(contract(1), 2, 0), &contract(1), 0, 0));
+    }
+
+    #[test]
+    fn unknown_events_are_deduplicated_without_sender() {
+        let mut index = ProcessedEvents::with_memory(test_memory());
+        let log = BridgeEventLog {
+            event: BridgeEvent::Unknown {
+                raw: RawEventLog::default(),
+            },
+            ..burnt_log(contract(1), 10, 0)
+        };
+
+        assert!(index.accept(&log, &contract(1), 0, 0));
+        assert!(!index.accept(&log, &contract(1), 0, 0));
+        assert_eq!(index.len(), 1);
+        assert!(index.get_for_sender(&H160::default(), None).is_empty());
+    }
+
+    fn user(seed: u8) -> H160 {
+        H160::from_slice(&[seed; 20])
+    }
+
+    fn user_log(event: BridgeEvent, block_number: u64, log_index: u64) -> BridgeEventLog {
+        BridgeEventLog {
+            event,
+            ..burnt_log(contract(1), block_number, log_index)
+        }
+    }
+
+    fn burnt_by(sender: H160) -> BridgeEvent {
+        BridgeEvent::Burnt(BurntEventData {
+            sender,
+            ..Default::default()
+        })
+    }
+
+    fn minted_to(recipient: H160) -> BridgeEvent {
+        BridgeEvent::Minted(MintedEventData {
+            recipient,
+            ..Default::default()
+        })
+    }
+
+    fn notified_by(tx_sender: H160) -> BridgeEvent {
+        BridgeEvent::Notify(NotifyMinterEventData {
+            notification_type: MinterNotificationType::DepositRequest,
+            tx_sender,
+            user_data: vec![],
+            memo: vec![],
+        })
+    }
+
+    #[test]
+    fn events_are_indexed_by_sender() {
+        let mut index = ProcessedEvents::with_memory(test_memory());
+        let logs = [
+            user_log(burnt_by(user(10)), 1, 0),
+            user_log(minted_to(user(10)), 2, 0),
+            user_log(burnt_by(user(11)), 2, 1),
+            user_log(notified_by(user(10)), 3, 0),
+        ];
+        for (time, log) in logs.iter().enumerate() {
+            assert!(index.accept(log, &contract(1), 0, time as u64));
+        }
+        index.set_operation_id(&logs[0].key().unwrap(), OperationId::new(7));
+
+        let events = index.get_for_sender(&user(10), None);
+        let kinds: Vec<_> = events.iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ProcessedEventKind::Burnt,
+                ProcessedEventKind::Minted,
+                ProcessedEventKind::Notify
+            ]
+        );
+        assert_eq!(events[0].operation_id, Some(OperationId::new(7)));
+        assert_eq!(events[0].block_number, 1);
+        assert_eq!(events[0].tx_hash, H256::from_slice(&[1; 32]));
+        assert_eq!(events[1].operation_id, None);
+        assert_eq!(events[2].processed_at, 3);
+
+        let other = index.get_for_sender(&user(11), None);
+        assert_eq!(other.len(), 1);
+        assert_eq!(other[0].log_index, 1);
+
+        assert!(index.get_for_sender(&user(12), None).is_empty());
+    }
+
+    #[test]
+    fn rejected_events_are_not_indexed_by_sender() {
+        let mut index = ProcessedEvents::with_memory(test_memory());
+        let log = user_log(burnt_by(user(10)), 1, 0);
+        assert!(index.accept(&log, &contract(1), 0, 0));
+        assert!(!index.accept(&log, &contract(1), 0, 1));
+
+        let mut foreign = user_log(burnt_by(user(10)), 2, 0);
+        foreign.contract = contract(2);
+        assert!(!index.accept(&foreign, &contract(1), 0, 2));
+
+        let events = index.get_for_sender(&user(10), None);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].processed_at, 0);
+    }
+
+    #[test]
+    fn sender_events_are_paginated() {
+        let mut index = ProcessedEvents::with_memory(test_memory());
+        for block_number in 1..=5 {
+            let log = user_log(burnt_by(user(10)), block_number, 0);
+            assert!(index.accept(&log, &contract(1), 0, 0));
+        }
+
+        let page = index.get_for_sender(&user(10), Some(Pagination::new(1, 2)));
+        let blocks: Vec<_> = page.iter().map(|event| event.block_number).collect();
+        assert_eq!(blocks, vec![2, 3]);
+
+        let last = index.get_for_sender(&user(10), Some(Pagination::new(4, 10)));
+        assert_eq!(last.len(), 1);
+        assert!(index
+            .get_for_sender(&user(10), Some(Pagination::new(5, 10)))
+            .is_empty());
+    }
+
+    #[test]
+    fn sender_index_is_pruned_with_dedup_index() {
+        let mut index = ProcessedEvents::with_memory(test_memory());
+        for block_number in 1..=5 {
+            let sender = user(10 + (block_number % 2) as u8);
+            let log = user_log(burnt_by(sender), block_number, 0);
+            assert!(index.accept(&log, &contract(1), 0, 0));
+        }
+
+        index.prune_before(4);
+        assert_eq!(index.len(), 2);
+
+        let remaining: Vec<_> = [user(10), user(11)]
+            .iter()
+            .flat_map(|sender| index.get_for_sender(sender, None))
+            .map(|event| event.block_number)
+            .collect();
+        assert_eq!(remaining, vec![4, 5]);
+
+        // Pruned events are accepted again, and are indexed by the sender again.
+        let log = user_log(burnt_by(user(11)), 3, 0);
+        assert!(index.accept(&log, &contract(1), 0, 0));
+        assert_eq!(index.get_for_sender(&user(11), None).len(), 2);
+    }
+}
@@ -1,14 +1,22 @@
+pub mod bulk_action;
+pub mod replica;
 pub mod scheduler;
 pub mod service;
 pub mod state;
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
 use std::rc::Rc;
+use std::time::Duration;
 
 use bridge_did::error::BTFResult;
+use bridge_did::event_filter::EventTopicFilter;
+use bridge_did::evm_finality::EvmFinality;
 use bridge_did::evm_link::EvmLink;
 use bridge_did::op_id::OperationId;
+use bridge_did::retry_profile::RetryProfileKind;
 use bridge_utils::evm_bridge::EvmParams;
+use candid::Principal;
 use eth_signer::sign_strategy::TransactionSigner;
 use ic_exports::ic_kit::ic;
 use ic_stable_structures::{StableBTreeMap, StableCell};
@@ -17,7 +25,7 @@ use ic_task_scheduler::scheduler::TaskScheduler;
 use ic_task_scheduler::task::ScheduledTask;
 use jsonrpc_core::futures;
 
-use self::scheduler::{BridgeTask, SharedScheduler};
+use self::scheduler::SharedScheduler;
 use self::service::{DynService, ServiceOrder};
 use self::state::config::ConfigStorage;
 use self::state::{SharedConfig, State};
@@ -25,9 +33,14 @@ use crate::bridge::{Operation, OperationContext};
 use crate::memory::{
     memory_by_id, StableMemory, CONFIG_MEMORY_ID, MEMO_OPERATION_MEMORY_ID,
     OPERATIONS_ID_COUNTER_MEMORY_ID, OPERATIONS_LOG_MEMORY_ID, OPERATIONS_MAP_MEMORY_ID,
-    OPERATIONS_MEMORY_ID, PENDING_TASKS_MEMORY_ID, PENDING_TASKS_SEQUENCE_MEMORY_ID,
+    OPERATIONS_MEMORY_ID, OPERATION_BLOCKS_INDEX_MEMORY_ID, OPERATION_BLOCKS_MEMORY_ID,
+    OUTBOX_DUE_MEMORY_ID, OUTBOX_ID_COUNTER_MEMORY_ID, OUTBOX_PARKED_MEMORY_ID,
+    OUTBOX_PENDING_MEMORY_ID, PENDING_TASKS_MEMORY_ID, PENDING_TASKS_SEQUENCE_MEMORY_ID,
 };
+use crate::operation_blocks::OperationBlocksMemory;
 use crate::operation_store::OperationsMemory;
+use crate::outbox::OutboxMemory;
+use crate::task_payloads::TaskPayloadKey;
 
 pub type RuntimeState<Op> = Rc<RefCell<State<Op>>>;
 pub type SharedRuntime<Op> = Rc<RefCell<BridgeRuntime<Op>>>;
@@ -58,19 +71,43 @@ impl<Op: Operation> BridgeRuntime<Op> {
     }
 
     /// Schedules operation with the given ID according to it's schedulling options.
+    /// Operation without the options is scheduled with the event collection retry profile.
     pub fn schedule_operation(&self, op_id: OperationId, operation: Op) {
-        let options = operation.scheduling_options().unwrap_or_default();
-        let scheduled_task =
-            ScheduledTask::with_options(BridgeTask::new(op_id, operation), options);
-        self.scheduler.append_task(scheduled_task);
+        let options = operation.scheduling_options().unwrap_or_else(|| {
+            self.state
+                .borrow()
+                .config
+                .borrow()
+                .get_task_options(RetryProfileKind::EventCollection)
+        });
+        let task = self.state.borrow_mut().new_task(op_id, operation);
+        self.scheduler
+            .append_task(ScheduledTask::with_options(task, options));
     }
 
     /// Run the scheduled tasks.
+    ///
+    /// A replica canister doesn't process the operations, and imports them from
+    /// the primary canister instead.
     pub fn run(&mut self) {
         if !self.state.borrow().should_process_operations() {
             return;
         }
 
+        let mode = self.state.borrow().config.borrow().get_bridge_mode();
+        if let Some(primary) = mode.primary() {
+            self.run_replica_sync(primary);
+            return;
+        }
+
+        // Nonce offset, log compression and notification destinations could be changed in config after the state creation.
+        self.state.borrow_mut().apply_nonce_offset();
+        self.state.borrow_mut().apply_operation_log_compression();
+        self.state.borrow_mut().apply_notification_destinations();
+
+        self.schedule_admitted_operations();
+        self.sweep_task_payloads();
+
         let services_before_ops = self.list_services(ServiceOrder::BeforeOperations);
         let services_after_ops = self.list_services(ServiceOrder::ConcurrentWithOperations);
         let scheduler = self.scheduler.clone();
@@ -93,11 +130,103 @@ impl<Op: Operation> BridgeRuntime<Op> {
         });
     }
 
+    /// Imports the operation blocks of the `primary` canister.
+    fn run_replica_sync(&self, primary: Principal) {
+        let state = self.state.clone();
+        state.borrow_mut().operations_run_ts = Some(ic::time());
+
+        ic::spawn(async move {
+            let _guard = drop_guard::guard(state.clone(), |state| {
+                state.borrow_mut().operations_run_ts = None
+            });
+
+            if let Err(e) = replica::sync(&state, primary).await {
+                log::warn!("replica sync failed: {e}");
+            }
+        });
+    }
+
+    /// Promotes the replica canister to the primary mode, and schedules the progress of the
+    /// imported incomplete operations. See [`replica::promote_to_primary`] for the conditions.
+    pub async fn promote_to_primary(runtime: SharedRuntime<Op>) -> BTFResult<()> {
+        let state = runtime.borrow().state.clone();
+        replica::promote_to_primary(&state).await?;
+
+        let incomplete = state.borrow().operations.get_incomplete();
+        let runtime = runtime.borrow();
+        for (op_id, operation) in incomplete {
+            if operation.scheduling_options().is_some() {
+                runtime.schedule_operation(op_id, operation);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Schedules the deferred operations, which fit into the direction concurrency limits.
+    fn schedule_admitted_operations(&self) {
+        let limits = self
+            .state
+            .borrow()
+            .config
+            .borrow()
+            .get_effective_operation_concurrency_limits();
+        let admitted = self.state.borrow_mut().concurrency.admit_deferred(&limits);
+
+        for (op_id, direction) in admitted {
+            let operation = self.state.borrow().operations.get(op_id);
+            match operation {
+                Some(operation) => self.schedule_operation(op_id, operation),
+                None => self.state.borrow_mut().concurrency.finish(direction),
+            }
+        }
+    }
+
+    /// Removes the task payloads, which are not referenced by the pending tasks anymore,
+    /// e.g. payloads of the completed tasks, and updates the payload stats.
+    fn sweep_task_payloads(&self) {
+        let referenced = RefCell::new(BTreeMap::<TaskPayloadKey, u64>::new());
+        let oversized_inline_tasks = Cell::new(0);
+        self.scheduler.find_id(&|task| {
+            if let Some(key) = task.payload_key {
+                *referenced.borrow_mut().entry(key).or_default() += 1;
+            } else if task.is_oversized_inline() {
+                oversized_inline_tasks.set(oversized_inline_tasks.get() + 1);
+            }
+            false
+        });
+
+        self.state
+            .borrow_mut()
+            .task_payloads
+            .sweep(&referenced.into_inner(), oversized_inline_tasks.get());
+    }
+
     /// Get the state.
     pub fn state(&self) -> &RuntimeState<Op> {
         &self.state
     }
 
+    /// Runs `f` with a shared reference to the runtime state.
+    ///
+    /// Test-only accessor, available with the `test_helpers` feature. It bypasses the access
+    /// control of the canister API, so the feature must never be enabled for the canister WASM
+    /// deployed to production.
+    #[cfg(any(test, feature = "test_helpers"))]
+    pub fn with_state_for_test<R>(&self, f: impl FnOnce(&State<Op>) -> R) -> R {
+        f(&self.state.borrow())
+    }
+
+    /// Runs `f` with a mutable reference to the runtime state.
+    ///
+    /// Test-only accessor, available with the `test_helpers` feature. It bypasses the access
+    /// control and the invariants checks of the canister API, so the feature must never be
+    /// enabled for the canister WASM deployed to production.
+    #[cfg(any(test, feature = "test_helpers"))]
+    pub fn mutate_state_for_test<R>(&self, f: impl FnOnce(&mut State<Op>) -> R) -> R {
+        f(&mut self.state.borrow_mut())
+    }
+
     /// Get the state.
     pub fn scheduler(&self) -> &SharedScheduler<StableMemory, Op> {
         &self.scheduler
@@ -143,9 +272,8 @@ impl<Op: Operation> BridgeRuntime<Op> {
                 log::trace!("Updated schedule for operation #{operation_id} task #{task_id} to {task_options:?}");
             }
             None => {
-                let task_id = self
-                    .scheduler
-                    .append_task((BridgeTask::new(operation_id, operation), task_options).into());
+                let task = self.state.borrow_mut().new_task(operation_id, operation);
+                let task_id = self.scheduler.append_task((task, task_options).into());
                 log::trace!("Restarted operation #{operation_id} with task id #{task_id}");
             }
         }
@@ -157,6 +285,10 @@ impl<Op: Operation> OperationContext for RuntimeState<Op> {
         self.borrow().config.get_evm_link()
     }
 
+    fn get_rpc_request_timeout(&self) -> Duration {
+        self.borrow().config.get_rpc_request_timeout()
+    }
+
     fn get_bridge_contract_address(&self) -> BTFResult<did::H160> {
         self.borrow().config.get_bridge_contract_address()
     }
@@ -168,6 +300,14 @@ impl<Op: Operation> OperationContext for RuntimeState<Op> {
     fn get_signer(&self) -> BTFResult<impl TransactionSigner> {
         self.borrow().config.borrow().get_signer()
     }
+
+    fn get_evm_finality(&self) -> EvmFinality {
+        self.borrow().config.get_evm_finality()
+    }
+
+    fn get_event_topic_filter(&self) -> Option<EventTopicFilter> {
+        self.borrow().config.get_event_topic_filter()
+    }
 }
 
 impl IcStorage for ConfigStorage {
@@ -188,6 +328,16 @@ fn operation_storage_memory() -> OperationsMemory<StableMemory> {
         operations_log: memory_by_id(OPERATIONS_LOG_MEMORY_ID),
         operations_map: memory_by_id(OPERATIONS_MAP_MEMORY_ID),
         memo_operations_map: memory_by_id(MEMO_OPERATION_MEMORY_ID),
+        outbox: OutboxMemory {
+            id_counter: memory_by_id(OUTBOX_ID_COUNTER_MEMORY_ID),
+            pending: memory_by_id(OUTBOX_PENDING_MEMORY_ID),
+            parked: memory_by_id(OUTBOX_PARKED_MEMORY_ID),
+            due: memory_by_id(OUTBOX_DUE_MEMORY_ID),
+        },
+        blocks: OperationBlocksMemory {
+            blocks: memory_by_id(OPERATION_BLOCKS_MEMORY_ID),
+            operation_index: memory_by_id(OPERATION_BLOCKS_INDEX_MEMORY_ID),
+        },
     }
 }
 
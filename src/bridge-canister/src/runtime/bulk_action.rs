@@ -0,0 +1,691 @@
+//! Operator actions, applied at once to the incomplete operations, matching a filter.
+
+use std::time::Duration;
+
+use bridge_did::bulk_action::{
+    BulkAction, BulkActionOutcome, BulkActionResult, OperationFilter, MAX_BULK_ACTION_OPERATIONS,
+    MAX_OPERATOR_NOTE_LENGTH,
+};
+use bridge_did::data_erasure::{AnonymizationMarker, UserDataErasure, UserIdentity};
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::op_id::OperationId;
+use bridge_did::operation_log::OperationLog;
+use candid::Principal;
+use ic_exports::ic_cdk::api::management_canister::main::raw_rand;
+
+use super::BridgeRuntime;
+use crate::bridge::Operation;
+use crate::operation_store;
+
+impl<Op: Operation> BridgeRuntime<Op> {
+    /// Applies the `action` to the incomplete operations, matching the `filter`, in the order
+    /// of their ids, starting from the `continuation` id, returned by the previous call.
+    ///
+    /// Up to [`MAX_BULK_ACTION_OPERATIONS`] operations are processed per call. The action is
+    /// recorded in the operator audit log with the `caller`, the filter and the outcomes.
+    pub fn bulk_action(
+        &self,
+        action: BulkAction,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+        caller: Principal,
+        now: u64,
+    ) -> BulkActionResult {
+        let limit = MAX_BULK_ACTION_OPERATIONS as usize;
+        let mut matching = self.state.borrow().operations.get_incomplete_filtered(
+            |_, log| matches_filter(&filter, log, now),
+            continuation,
+            limit + 1,
+        );
+        let continuation = if matching.len() > limit {
+            matching.pop().map(|(id, _)| id)
+        } else {
+            None
+        };
+
+        let outcomes: Vec<_> = matching
+            .into_iter()
+            .map(|(id, log)| {
+                (
+                    id,
+                    self.apply_action(action, id, log.current_step().clone()),
+                )
+            })
+            .collect();
+
+        log::info!(
+            "Operator {caller} applied {action:?} to {} operations, matching {filter:?}",
+            outcomes.len()
+        );
+        self.state
+            .borrow_mut()
+            .operator_audit
+            .push(caller, action, filter, outcomes.clone(), now);
+
+        BulkActionResult {
+            outcomes,
+            continuation,
+        }
+    }
+
+    /// Adds the `note` of the `caller` to the operator audit log.
+    /// Returns id of the audit log entry.
+    pub fn add_operator_note(&self, caller: Principal, note: String, now: u64) -> BTFResult<u64> {
+        if note.len() > MAX_OPERATOR_NOTE_LENGTH {
+            return Err(Error::OperatorNoteTooLong(MAX_OPERATOR_NOTE_LENGTH as _));
+        }
+
+        log::info!("Operator {caller} added audit note");
+        Ok(self
+            .state
+            .borrow_mut()
+            .operator_audit
+            .push_note(caller, note, now))
+    }
+
+    /// Erases the personal data linkage of the `identity` from the historical operations.
+    ///
+    /// Wallet address of the operations is replaced with the pseudonym, derived from
+    /// the `salt` and the identity, and the memos are removed. The operations are removed from
+    /// the address and memo indexes, so they are not returned by the identity queries anymore.
+    /// Operation steps are kept as the financial records, but the logs of the operations are
+    /// not exposed, and their blocks are redacted. Captured progress replays of the operations
+    /// are removed. For a principal identity, the operations linked to the principal by
+    /// [`Operation::principal`] are anonymized, and the bridge canister should erase its own
+    /// data linked to the principal.
+    ///
+    /// Fails if the identity has incomplete operations. The erasure is recorded in the operator
+    /// audit log with the pseudonym only.
+    pub fn anonymize_user_data(
+        &self,
+        identity: &UserIdentity,
+        salt: &[u8],
+        caller: Principal,
+        now: u64,
+    ) -> BTFResult<UserDataErasure> {
+        let pseudonym = identity.pseudonym(salt);
+        let mut state = self.state.borrow_mut();
+        let marker = AnonymizationMarker {
+            pseudonym: pseudonym.clone(),
+            timestamp: now,
+            audit_entry: state.operator_audit.next_id(),
+        };
+
+        let operations = match identity {
+            UserIdentity::Address(address) => {
+                state.operations.anonymize_address(address, marker)?
+            }
+            UserIdentity::Principal(principal) => {
+                state.operations.anonymize_principal(principal, marker)?
+            }
+        };
+        for id in &operations {
+            state.operation_replays.remove(*id);
+        }
+
+        let audit_entry = state
+            .operator_audit
+            .push_erasure(caller, &pseudonym, &operations, now);
+        log::info!(
+            "Operator {caller} erased user data of {} operations, pseudonym {pseudonym}",
+            operations.len()
+        );
+
+        Ok(UserDataErasure {
+            pseudonym,
+            operations,
+            audit_entry,
+        })
+    }
+
+    fn apply_action(
+        &self,
+        action: BulkAction,
+        id: OperationId,
+        operation: Op,
+    ) -> BulkActionOutcome {
+        match action {
+            BulkAction::Retry => {
+                let operation = match operation.unpark() {
+                    Some(unparked) => {
+                        self.state
+                            .borrow_mut()
+                            .operations
+                            .update(id, unparked.clone());
+                        unparked
+                    }
+                    None => operation,
+                };
+                if operation.scheduling_options().is_none() {
+                    return BulkActionOutcome::Skipped(
+                        "operation is not progressed by the scheduler".into(),
+                    );
+                }
+                self.reschedule_operation(id);
+            }
+            BulkAction::Cancel => {
+                let Some(cancelled) = operation.cancel(id) else {
+                    return BulkActionOutcome::Skipped(
+                        "operation can't be cancelled at its current step".into(),
+                    );
+                };
+                let mut state = self.state.borrow_mut();
+                if let Some(direction) = operation.direction() {
+                    state.concurrency.forget(id, direction);
+                }
+                state.operations.update(id, cancelled);
+            }
+            BulkAction::Approve => {
+                let admitted = self.state.borrow_mut().concurrency.admit_now(id);
+                if admitted.is_none() {
+                    return BulkActionOutcome::Skipped(
+                        "operation is not deferred by the concurrency limit".into(),
+                    );
+                }
+                self.schedule_operation(id, operation);
+            }
+        }
+
+        log::info!("Operator action {action:?} is applied to operation #{id}");
+        BulkActionOutcome::Applied
+    }
+}
+
+/// Returns random salt for the pseudonym of the erased user identity.
+pub async fn random_salt() -> BTFResult<Vec<u8>> {
+    let (salt,) = raw_rand()
+        .await
+        .map_err(|(code, msg)| Error::Randomness(format!("{code:?}: {msg}")))?;
+
+    Ok(salt)
+}
+
+/// Checks if the operation with the given log matches the `filter` at the `now` IC time.
+fn matches_filter<Op: Operation>(
+    filter: &OperationFilter,
+    log: &OperationLog<Op>,
+    now: u64,
+) -> bool {
+    let operation = log.current_step();
+    let created_at = log.log().first().map_or(now, |entry| entry.time_stamp);
+    let age_secs = Duration::from_nanos(now.saturating_sub(created_at)).as_secs();
+
+    filter
+        .state_class
+        .map_or(true, |class| operation_store::state_class(log) == class)
+        && filter
+            .token
+            .map_or(true, |token| operation.token() == Some(token))
+        && filter
+            .direction
+            .map_or(true, |direction| operation.direction() == Some(direction))
+        && filter.min_age_secs.map_or(true, |min| age_secs >= min)
+        && filter.max_age_secs.map_or(true, |max| age_secs <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::bulk_action::OperatorAuditEntry;
+    use bridge_did::concurrency::OperationDirection;
+    use bridge_did::id256::Id256;
+    use bridge_did::operation_replay::ProgressReplayOutcome;
+    use bridge_did::operation_status::OperationStateClass;
+    use candid::CandidType;
+    use did::H160;
+    use ic_exports::ic_kit::{ic, MockContext};
+    use ic_storage::IcStorage;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::bridge::OperationProgress;
+    use crate::runtime::state::config::ConfigStorage;
+    use crate::runtime::RuntimeState;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    #[derive(Debug, Clone, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+    struct TestOperation {
+        token: u8,
+        direction: Option<OperationDirection>,
+        cancellable: bool,
+        cancelled: bool,
+    }
+
+    impl TestOperation {
+        fn new(token: u8, direction: Option<OperationDirection>, cancellable: bool) -> Self {
+            Self {
+                token,
+                direction,
+                cancellable,
+                cancelled: false,
+            }
+        }
+    }
+
+    impl Operation for TestOperation {
+        async fn progress(
+            self,
+            _id: OperationId,
+            _ctx: RuntimeState<Self>,
+        ) -> BTFResult<OperationProgress<Self>> {
+            Err(Error::FailedToProgress("test error".into()))
+        }
+
+        fn is_complete(&self) -> bool {
+            self.cancelled
+        }
+
+        fn evm_wallet_address(&self) -> H160 {
+            H160::from_slice(&[1; 20])
+        }
+
+        fn direction(&self) -> Option<OperationDirection> {
+            self.direction
+        }
+
+        fn token(&self) -> Option<Id256> {
+            Some(token(self.token))
+        }
+
+        fn cancel(&self, _id: OperationId) -> Option<Self> {
+            self.cancellable.then(|| Self {
+                cancelled: true,
+                ..self.clone()
+            })
+        }
+    }
+
+    fn token(id: u8) -> Id256 {
+        Id256::from_evm_address(&H160::from_slice(&[id; 20]), 1)
+    }
+
+    fn operator() -> Principal {
+        Principal::from_slice(&[7; 29])
+    }
+
+    fn new_runtime() -> BridgeRuntime<TestOperation> {
+        BridgeRuntime::default(ConfigStorage::get())
+    }
+
+    fn new_operation(runtime: &BridgeRuntime<TestOperation>, op: TestOperation) -> OperationId {
+        runtime
+            .state
+            .borrow_mut()
+            .operations
+            .new_operation(op, None)
+    }
+
+    fn applied_ids(result: &BulkActionResult) -> Vec<OperationId> {
+        result
+            .outcomes
+            .iter()
+            .filter(|(_, outcome)| *outcome == BulkActionOutcome::Applied)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    fn has_task(runtime: &BridgeRuntime<TestOperation>, id: OperationId) -> bool {
+        runtime
+            .scheduler
+            .find_id(&|task| task.op_id == id)
+            .is_some()
+    }
+
+    #[test]
+    fn filter_should_select_matching_operations() {
+        let context = MockContext::new().inject();
+        let runtime = new_runtime();
+
+        let old_deposit = new_operation(
+            &runtime,
+            TestOperation::new(1, Some(OperationDirection::Deposit), false),
+        );
+        context.add_time(100 * SECOND);
+        let failed_deposit = new_operation(
+            &runtime,
+            TestOperation::new(1, Some(OperationDirection::Deposit), false),
+        );
+        runtime
+            .state
+            .borrow_mut()
+            .operations
+            .update_with_err(failed_deposit, "rpc outage".into());
+        let withdrawal = new_operation(
+            &runtime,
+            TestOperation::new(2, Some(OperationDirection::Withdrawal), false),
+        );
+        let cancelled = new_operation(&runtime, TestOperation::new(1, None, true));
+        let cancelled_op = TestOperation::new(1, None, true).cancel(cancelled).unwrap();
+        runtime
+            .state
+            .borrow_mut()
+            .operations
+            .update(cancelled, cancelled_op);
+
+        let select = |filter: OperationFilter| {
+            let now = ic::time();
+            runtime
+                .state
+                .borrow()
+                .operations
+                .get_incomplete_filtered(|_, log| matches_filter(&filter, log, now), None, 10)
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            select(OperationFilter::default()),
+            vec![old_deposit, failed_deposit, withdrawal]
+        );
+        assert_eq!(
+            select(OperationFilter {
+                state_class: Some(OperationStateClass::Retrying),
+                ..Default::default()
+            }),
+            vec![failed_deposit]
+        );
+        assert_eq!(
+            select(OperationFilter {
+                token: Some(token(1)),
+                ..Default::default()
+            }),
+            vec![old_deposit, failed_deposit]
+        );
+        assert_eq!(
+            select(OperationFilter {
+                direction: Some(OperationDirection::Withdrawal),
+                ..Default::default()
+            }),
+            vec![withdrawal]
+        );
+        assert_eq!(
+            select(OperationFilter {
+                min_age_secs: Some(100),
+                ..Default::default()
+            }),
+            vec![old_deposit]
+        );
+        assert_eq!(
+            select(OperationFilter {
+                token: Some(token(1)),
+                max_age_secs: Some(99),
+                ..Default::default()
+            }),
+            vec![failed_deposit]
+        );
+    }
+
+    #[test]
+    fn bulk_action_should_be_limited_per_call_with_continuation() {
+        MockContext::new().inject();
+        let runtime = new_runtime();
+
+        let count = MAX_BULK_ACTION_OPERATIONS as usize + 5;
+        let ids: Vec<_> = (0..count)
+            .map(|_| new_operation(&runtime, TestOperation::new(1, None, false)))
+            .collect();
+
+        let first = runtime.bulk_action(
+            BulkAction::Retry,
+            OperationFilter::default(),
+            None,
+            operator(),
+            ic::time(),
+        );
+        assert_eq!(
+            applied_ids(&first),
+            ids[..MAX_BULK_ACTION_OPERATIONS as usize]
+        );
+        assert_eq!(
+            first.continuation,
+            Some(ids[MAX_BULK_ACTION_OPERATIONS as usize])
+        );
+
+        let second = runtime.bulk_action(
+            BulkAction::Retry,
+            OperationFilter::default(),
+            first.continuation,
+            operator(),
+            ic::time(),
+        );
+        assert_eq!(
+            applied_ids(&second),
+            ids[MAX_BULK_ACTION_OPERATIONS as usize..]
+        );
+        assert_eq!(second.continuation, None);
+        assert!(ids.iter().all(|id| has_task(&runtime, *id)));
+    }
+
+    #[test]
+    fn bulk_action_should_report_outcome_per_operation() {
+        MockContext::new().inject();
+        let runtime = new_runtime();
+
+        let cancellable = new_operation(&runtime, TestOperation::new(1, None, true));
+        let not_cancellable = new_operation(&runtime, TestOperation::new(1, None, false));
+        let cancel = runtime.bulk_action(
+            BulkAction::Cancel,
+            OperationFilter::default(),
+            None,
+            operator(),
+            ic::time(),
+        );
+        assert_eq!(cancel.outcomes.len(), 2);
+        assert_eq!(
+            cancel.outcomes[0],
+            (cancellable, BulkActionOutcome::Applied)
+        );
+        assert_eq!(cancel.outcomes[1].0, not_cancellable);
+        assert!(matches!(
+            cancel.outcomes[1].1,
+            BulkActionOutcome::Skipped(_)
+        ));
+        assert!(runtime
+            .state
+            .borrow()
+            .operations
+            .get(cancellable)
+            .unwrap()
+            .is_complete());
+
+        let deferred = new_operation(
+            &runtime,
+            TestOperation::new(1, Some(OperationDirection::Deposit), false),
+        );
+        assert!(!runtime.state.borrow_mut().concurrency.try_start(
+            deferred,
+            OperationDirection::Deposit,
+            Some(0),
+            0
+        ));
+        let approve = runtime.bulk_action(
+            BulkAction::Approve,
+            OperationFilter::default(),
+            None,
+            operator(),
+            ic::time(),
+        );
+        assert_eq!(applied_ids(&approve), vec![deferred]);
+        assert_eq!(approve.outcomes.len(), 2);
+        assert!(has_task(&runtime, deferred));
+        assert!(!has_task(&runtime, not_cancellable));
+    }
+
+    #[test]
+    fn bulk_action_should_be_audited() {
+        let context = MockContext::new().inject();
+        let runtime = new_runtime();
+
+        let id = new_operation(&runtime, TestOperation::new(1, None, false));
+        context.add_time(SECOND);
+        let filter = OperationFilter {
+            token: Some(token(1)),
+            ..Default::default()
+        };
+        let result = runtime.bulk_action(
+            BulkAction::Retry,
+            filter.clone(),
+            None,
+            operator(),
+            ic::time(),
+        );
+        runtime.bulk_action(
+            BulkAction::Cancel,
+            OperationFilter::default(),
+            None,
+            operator(),
+            ic::time(),
+        );
+
+        let audit = runtime.state.borrow().operator_audit.get(0, 10);
+        assert_eq!(audit.len(), 2);
+        assert_eq!(
+            audit[0],
+            OperatorAuditEntry {
+                id: 0,
+                timestamp: ic::time(),
+                caller: operator(),
+                action: Some(BulkAction::Retry),
+                filter,
+                outcomes: result.outcomes,
+                note: None,
+            }
+        );
+        assert_eq!(audit[1].action, Some(BulkAction::Cancel));
+        assert_eq!(audit[1].outcomes[0].0, id);
+    }
+
+    #[test]
+    fn user_data_should_be_anonymized() {
+        let _context = MockContext::new().inject();
+        let runtime = new_runtime();
+        let address = H160::from_slice(&[1; 20]);
+
+        let op = TestOperation::new(1, Some(OperationDirection::Deposit), true);
+        let completed = runtime
+            .state
+            .borrow_mut()
+            .operations
+            .new_operation(op.clone(), Some([3; 32]));
+        let cancelled = op.cancel(completed).unwrap();
+        runtime
+            .state
+            .borrow_mut()
+            .operations
+            .update(completed, cancelled.clone());
+        let context = ConfigStorage::get().borrow().get_replay_context_digest();
+        runtime.state.borrow_mut().operation_replays.record(
+            completed,
+            0,
+            vec![],
+            context,
+            ProgressReplayOutcome::AddToService(1),
+            1,
+        );
+        let note = runtime
+            .add_operator_note(operator(), "note".into(), ic::time())
+            .unwrap();
+
+        let erasure = runtime
+            .anonymize_user_data(
+                &UserIdentity::Address(address.clone()),
+                &[9; 32],
+                operator(),
+                ic::time(),
+            )
+            .unwrap();
+        assert_eq!(erasure.operations, vec![completed]);
+        assert_eq!(erasure.audit_entry, note + 1);
+        assert_ne!(erasure.pseudonym, address);
+
+        // Queries by the erased identity return nothing.
+        let state = runtime.state.borrow();
+        assert!(state
+            .operations
+            .get_for_address(&address, None, None)
+            .is_empty());
+        assert!(state
+            .operations
+            .get_memos_by_user_address(&address)
+            .is_empty());
+        assert!(state
+            .operations
+            .get_operation_by_memo_and_user(&[3; 32], &address)
+            .is_none());
+
+        assert!(state.operation_replays.get(completed).is_empty());
+
+        // Financial fields of the operation are kept.
+        let log = state.operations.get_log(completed).unwrap();
+        assert_eq!(log.log().len(), 2);
+        assert_eq!(log.log()[0].step_result, Ok(op));
+        assert_eq!(log.current_step(), &cancelled);
+        assert_eq!(log.wallet_address(), &erasure.pseudonym);
+        assert_eq!(log.memo(), None);
+        assert_eq!(
+            log.anonymization(),
+            Some(&AnonymizationMarker {
+                pseudonym: erasure.pseudonym.clone(),
+                timestamp: ic::time(),
+                audit_entry: erasure.audit_entry,
+            })
+        );
+
+        let audit = state.operator_audit.get(erasure.audit_entry as _, 1);
+        assert_eq!(audit[0].id, erasure.audit_entry);
+        assert_eq!(audit[0].caller, operator());
+        assert_eq!(audit[0].action, None);
+        assert_eq!(
+            audit[0].outcomes,
+            vec![(completed, BulkActionOutcome::Applied)]
+        );
+        let audit_note = audit[0].note.clone().unwrap();
+        assert!(audit_note.contains(&erasure.pseudonym.to_string()));
+        assert!(!audit_note.contains(&address.to_string()));
+    }
+
+    #[test]
+    fn user_data_with_incomplete_operations_should_not_be_anonymized() {
+        let _context = MockContext::new().inject();
+        let runtime = new_runtime();
+
+        let id = new_operation(&runtime, TestOperation::new(1, None, false));
+        assert_eq!(
+            runtime.anonymize_user_data(
+                &UserIdentity::Address(H160::from_slice(&[1; 20])),
+                &[9; 32],
+                operator(),
+                ic::time(),
+            ),
+            Err(Error::UserDataErasurePending(vec![id]))
+        );
+        assert!(runtime.state.borrow().operator_audit.is_empty());
+    }
+
+    #[test]
+    fn operator_note_should_be_audited() {
+        let _context = MockContext::new().inject();
+        let runtime = new_runtime();
+
+        let note = "owner: unchanged".to_string();
+        let id = runtime
+            .add_operator_note(operator(), note.clone(), ic::time())
+            .unwrap();
+        assert_eq!(
+            runtime.add_operator_note(
+                operator(),
+                "a".repeat(MAX_OPERATOR_NOTE_LENGTH + 1),
+                ic::time()
+            ),
+            Err(Error::OperatorNoteTooLong(MAX_OPERATOR_NOTE_LENGTH as _))
+        );
+
+        let audit = runtime.state.borrow().operator_audit.get(0, 10);
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].id, id);
+        assert_eq!(audit[0].action, None);
+        assert_eq!(audit[0].note, Some(note));
+        assert!(audit[0].outcomes.is_empty());
+    }
+}
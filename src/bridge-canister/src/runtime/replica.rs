@@ -0,0 +1,367 @@
+//! Synchronization of the replica canister with the primary canister. The replica imports
+//! the operation blocks log of the primary, so the operations can be queried from both
+//! canisters, and the replica can be promoted if the primary fails.
+
+use std::future::Future;
+
+use bridge_did::bridge_mode::{OperationBlocksExport, ReplicaSyncStatus};
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::halt::BridgeHalt;
+use candid::Principal;
+use ic_exports::ic_cdk::api::call::call;
+use ic_exports::ic_kit::ic;
+
+use super::state::Timestamp;
+use super::RuntimeState;
+use crate::bridge::Operation;
+use crate::operation_blocks::MAX_BLOCKS_PER_REQUEST;
+
+/// Max number of the export requests to the primary canister per sync.
+const MAX_EXPORT_REQUESTS_PER_SYNC: usize = 10;
+
+/// Progress of the replica synchronization. Reset on upgrade.
+#[derive(Debug, Default)]
+pub struct ReplicaSync {
+    primary_blocks: Option<u64>,
+    last_sync_at: Option<Timestamp>,
+    last_error: Option<String>,
+}
+
+impl ReplicaSync {
+    /// Returns the sync status of the replica of the `primary`, which log has `synced_blocks`.
+    pub fn status(&self, primary: Principal, synced_blocks: u64) -> ReplicaSyncStatus {
+        ReplicaSyncStatus {
+            primary,
+            synced_blocks,
+            primary_blocks: self.primary_blocks,
+            last_sync_at: self.last_sync_at,
+            last_error: self.last_error.clone(),
+        }
+    }
+
+    fn record(&mut self, result: &BTFResult<()>, timestamp: Timestamp) {
+        match result {
+            Ok(()) => {
+                self.last_sync_at = Some(timestamp);
+                self.last_error = None;
+            }
+            Err(e) => self.last_error = Some(e.to_string()),
+        }
+    }
+}
+
+/// Imports the operation blocks of the `primary` canister, missing in the local log.
+pub async fn sync<Op: Operation>(state: &RuntimeState<Op>, primary: Principal) -> BTFResult<()> {
+    sync_with(state, |start| export_blocks(primary, start)).await
+}
+
+/// Promotes the replica to the primary mode. The promotion is allowed only if the primary
+/// canister is halted or doesn't respond. Before the promotion, the blocks of the halted
+/// primary are synced.
+///
+/// The primary state is checked on a best-effort basis: the operator is responsible to make
+/// sure that the primary doesn't resume processing the operations after the promotion.
+pub async fn promote_to_primary<Op: Operation>(state: &RuntimeState<Op>) -> BTFResult<()> {
+    let config = state.borrow().config.clone();
+    let Some(primary) = config.borrow().get_bridge_mode().primary() else {
+        return Err(Error::PromotionRejected(
+            "the canister is not a replica".into(),
+        ));
+    };
+
+    let primary_halt = call::<_, (Option<BridgeHalt>,)>(primary, "get_bridge_halt", ())
+        .await
+        .map(|(halt,)| halt)
+        .map_err(|(code, msg)| {
+            Error::PromotionRejected(format!("primary call rejected with {code:?}: {msg}"))
+        });
+
+    promote_with(state, primary_halt, |start| export_blocks(primary, start)).await
+}
+
+async fn export_blocks(primary: Principal, start: u64) -> BTFResult<OperationBlocksExport> {
+    call::<_, (OperationBlocksExport,)>(
+        primary,
+        "export_operation_blocks",
+        (start, MAX_BLOCKS_PER_REQUEST),
+    )
+    .await
+    .map(|(export,)| export)
+    .map_err(|(code, msg)| {
+        Error::FailedToProgress(format!("blocks export rejected with {code:?}: {msg}"))
+    })
+}
+
+/// Imports the blocks, returned by `fetch` from the given index, and records the sync result.
+async fn sync_with<Op, F, Fut>(state: &RuntimeState<Op>, fetch: F) -> BTFResult<()>
+where
+    Op: Operation,
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = BTFResult<OperationBlocksExport>>,
+{
+    let result = import_missing_blocks(state, fetch).await;
+    state.borrow_mut().replica_sync.record(&result, ic::time());
+    result
+}
+
+async fn import_missing_blocks<Op, F, Fut>(state: &RuntimeState<Op>, fetch: F) -> BTFResult<()>
+where
+    Op: Operation,
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = BTFResult<OperationBlocksExport>>,
+{
+    for _ in 0..MAX_EXPORT_REQUESTS_PER_SYNC {
+        let start = state.borrow().operations.blocks().len();
+        let export = fetch(start).await?;
+
+        let mut state = state.borrow_mut();
+        state.replica_sync.primary_blocks = Some(export.log_length);
+        for (index, block) in export.blocks {
+            state.operations.import_block(index, block)?;
+        }
+
+        let synced = state.operations.blocks().len();
+        if synced >= export.log_length || synced == start {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+async fn promote_with<Op, F, Fut>(
+    state: &RuntimeState<Op>,
+    primary_halt: BTFResult<Option<BridgeHalt>>,
+    fetch: F,
+) -> BTFResult<()>
+where
+    Op: Operation,
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = BTFResult<OperationBlocksExport>>,
+{
+    match primary_halt {
+        Ok(None) => {
+            return Err(Error::PromotionRejected(
+                "the primary canister is running".into(),
+            ))
+        }
+        Ok(Some(halt)) => {
+            log::info!("Primary canister is halted: {}", halt.reason);
+            sync_with(state, fetch).await?;
+        }
+        Err(e) => {
+            log::warn!("Primary canister doesn't respond: {e}");
+            if let Err(e) = sync_with(state, fetch).await {
+                log::warn!("Final sync with the primary canister failed: {e}");
+            }
+        }
+    }
+
+    let config = state.borrow().config.clone();
+    config.borrow_mut().set_primary_mode();
+    log::warn!(
+        "Replica is promoted to the primary mode with {} synced blocks",
+        state.borrow().operations.blocks().len()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use bridge_did::bridge_mode::BridgeMode;
+    use bridge_did::evm_link::EvmLink;
+    use bridge_did::init::BridgeInitData;
+    use bridge_did::op_id::OperationId;
+    use candid::CandidType;
+    use did::H160;
+    use eth_signer::sign_strategy::SigningStrategy;
+    use ic_exports::ic_kit::MockContext;
+    use ic_stable_structures::{MemoryId, VectorMemory};
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::bridge::OperationProgress;
+    use crate::memory::memory_by_id;
+    use crate::operation_blocks::{OperationBlockLog, OperationBlocksMemory};
+    use crate::runtime::default_state;
+    use crate::runtime::state::config::ConfigStorage;
+    use crate::runtime::state::SharedConfig;
+
+    #[derive(Debug, Clone, CandidType, Serialize, Deserialize)]
+    struct TestOp {
+        stage: u32,
+    }
+
+    impl Operation for TestOp {
+        async fn progress(
+            self,
+            _id: OperationId,
+            _ctx: RuntimeState<Self>,
+        ) -> BTFResult<OperationProgress<Self>> {
+            Ok(OperationProgress::Progress(self))
+        }
+
+        fn is_complete(&self) -> bool {
+            self.stage == 2
+        }
+
+        fn evm_wallet_address(&self) -> H160 {
+            H160::from_slice(&[1; 20])
+        }
+    }
+
+    fn replica_state() -> RuntimeState<TestOp> {
+        MockContext::new().inject();
+        let config: SharedConfig = Rc::new(RefCell::new(ConfigStorage::default(memory_by_id(
+            MemoryId::new(1),
+        ))));
+        config.borrow_mut().init(&BridgeInitData {
+            owner: Principal::from_slice(&[1; 20]),
+            evm_link: EvmLink::Ic(Principal::from_slice(&[2; 20])),
+            signing_strategy: SigningStrategy::Local {
+                private_key: [1u8; 32],
+            },
+            log_settings: None,
+            evm_finality: None,
+            nonce_offset: None,
+            mode: Some(BridgeMode::Replica {
+                primary: Principal::from_slice(&[3; 20]),
+            }),
+        });
+        default_state(config)
+    }
+
+    /// Log of the primary canister with `count` blocks of the operations, which are created
+    /// and then complete.
+    fn primary_log(count: u64) -> OperationBlockLog<VectorMemory> {
+        let mut log = OperationBlockLog::with_memory(OperationBlocksMemory {
+            blocks: VectorMemory::default(),
+            operation_index: VectorMemory::default(),
+        });
+        for i in 0..count {
+            let op = TestOp {
+                stage: (i % 2) as u32 * 2,
+            };
+            log.append(
+                OperationId::new(i / 2),
+                op.evm_wallet_address(),
+                Ok(candid::Encode!(&op).unwrap()),
+                i,
+            );
+        }
+        log
+    }
+
+    fn export(log: &OperationBlockLog<VectorMemory>, start: u64) -> OperationBlocksExport {
+        OperationBlocksExport {
+            log_length: log.len(),
+            blocks: log.range(start, 3),
+        }
+    }
+
+    #[tokio::test]
+    async fn replica_converges_with_primary() {
+        let state = replica_state();
+        let primary = primary_log(8);
+
+        sync_with(&state, |start| {
+            let export = export(&primary, start);
+            async move { Ok(export) }
+        })
+        .await
+        .unwrap();
+
+        let state = state.borrow();
+        assert_eq!(state.operations.blocks().range(0, 10), primary.range(0, 10));
+        for id in 0..4 {
+            assert!(state
+                .operations
+                .get(OperationId::new(id))
+                .unwrap()
+                .is_complete());
+        }
+
+        let status = state
+            .replica_sync
+            .status(Principal::anonymous(), state.operations.blocks().len());
+        assert!(status.is_synced());
+        assert!(status.last_sync_at.is_some());
+        assert_eq!(status.last_error, None);
+    }
+
+    #[tokio::test]
+    async fn sync_failure_is_recorded() {
+        let state = replica_state();
+
+        let result = sync_with(&state, |_| async {
+            Err(Error::FailedToProgress("unreachable".into()))
+        })
+        .await;
+        assert!(result.is_err());
+
+        let status = state
+            .borrow()
+            .replica_sync
+            .status(Principal::anonymous(), 0);
+        assert!(!status.is_synced());
+        assert!(status.last_error.unwrap().contains("unreachable"));
+    }
+
+    #[tokio::test]
+    async fn running_primary_prevents_promotion() {
+        let state = replica_state();
+        let primary = primary_log(2);
+
+        let result = promote_with(&state, Ok(None), |start| {
+            let export = export(&primary, start);
+            async move { Ok(export) }
+        })
+        .await;
+
+        assert!(matches!(result, Err(Error::PromotionRejected(_))));
+        assert!(state.borrow().config.borrow().is_replica());
+    }
+
+    #[tokio::test]
+    async fn halted_primary_is_synced_before_promotion() {
+        let state = replica_state();
+        let primary = primary_log(4);
+        let halt = BridgeHalt {
+            timestamp: 0,
+            reason: "maintenance".into(),
+        };
+
+        promote_with(&state, Ok(Some(halt)), |start| {
+            let export = export(&primary, start);
+            async move { Ok(export) }
+        })
+        .await
+        .unwrap();
+
+        let state = state.borrow();
+        assert!(!state.config.borrow().is_replica());
+        assert_eq!(state.operations.blocks().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn unreachable_primary_allows_promotion() {
+        let state = replica_state();
+
+        promote_with(
+            &state,
+            Err(Error::PromotionRejected("unreachable".into())),
+            |_| async { Err(Error::FailedToProgress("unreachable".into())) },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            state.borrow().config.borrow().get_bridge_mode(),
+            BridgeMode::Primary
+        );
+    }
+}
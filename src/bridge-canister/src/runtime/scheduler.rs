@@ -5,6 +5,7 @@ use std::rc::Rc;
 use bridge_did::error::{BTFResult, Error};
 use bridge_did::op_id::OperationId;
 use candid::CandidType;
+use ic_exports::ic_kit::ic;
 use ic_stable_structures::stable_structures::Memory;
 use ic_stable_structures::{StableBTreeMap, StableCell};
 use ic_task_scheduler::scheduler::{Scheduler, TaskScheduler};
@@ -14,6 +15,8 @@ use serde::{Deserialize, Serialize};
 
 use super::RuntimeState;
 use crate::bridge::{Operation, OperationProgress};
+use crate::log_span::OperationSpan;
+use crate::task_payloads::{TaskPayloadKey, TaskPayloadStore, MAX_INLINE_TASK_PAYLOAD_SIZE};
 
 pub type TasksStorage<Mem, Op> = StableBTreeMap<u64, InnerScheduledTask<BridgeTask<Op>>, Mem>;
 pub type BridgeScheduler<Mem, Op> =
@@ -95,18 +98,75 @@ pub fn log_task_execution_error<Op: Operation>(task: InnerScheduledTask<BridgeTa
 }
 
 /// Task type used by `BridgeRuntime`.
+///
+/// The operation payload is stored either inline, or in the [`TaskPayloadStore`] if it exceeds
+/// [`MAX_INLINE_TASK_PAYLOAD_SIZE`]. Use [`BridgeTask::operation`] to read the payload.
+///
+/// Tasks, stored before the payload store was introduced, are decoded with the inline payload.
 #[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
 pub struct BridgeTask<Op> {
     pub op_id: OperationId,
-    pub operation: Op,
+    /// Inline operation payload.
+    pub operation: Option<Op>,
+    /// Key of the operation payload in the task payloads store.
+    pub payload_key: Option<TaskPayloadKey>,
 }
 
 impl<Op: Operation> BridgeTask<Op> {
+    /// Creates a task with the inline operation payload.
     pub fn new(op_id: OperationId, operation: Op) -> Self {
-        Self { op_id, operation }
+        Self {
+            op_id,
+            operation: Some(operation),
+            payload_key: None,
+        }
     }
 
-    async fn execute_inner(
+    /// Creates a task, which operation payload is moved to the `payloads` store if it exceeds
+    /// the inline size limit.
+    pub fn with_payload_store<M: Memory>(
+        op_id: OperationId,
+        operation: Op,
+        payloads: &mut TaskPayloadStore<M>,
+    ) -> Self {
+        let encoded = match candid::encode_one(&operation) {
+            Ok(encoded) if encoded.len() > MAX_INLINE_TASK_PAYLOAD_SIZE => encoded,
+            _ => return Self::new(op_id, operation),
+        };
+
+        Self {
+            op_id,
+            operation: None,
+            payload_key: Some(payloads.put(encoded)),
+        }
+    }
+
+    /// Returns the operation payload of the task, either inline or from the `payloads` store.
+    pub fn operation<M: Memory>(&self, payloads: &TaskPayloadStore<M>) -> Option<Op> {
+        if let Some(operation) = &self.operation {
+            return Some(operation.clone());
+        }
+
+        let encoded = payloads.get(self.payload_key.as_ref()?)?;
+        candid::decode_one(&encoded)
+            .inspect_err(|e| {
+                log::warn!(
+                    "Failed to decode payload of operation #{} task: {e}",
+                    self.op_id
+                )
+            })
+            .ok()
+    }
+
+    /// Checks if the task keeps an inline payload over the inline size limit.
+    pub fn is_oversized_inline(&self) -> bool {
+        self.operation.as_ref().is_some_and(|operation| {
+            candid::encode_one(operation)
+                .is_ok_and(|encoded| encoded.len() > MAX_INLINE_TASK_PAYLOAD_SIZE)
+        })
+    }
+
+    pub(crate) async fn execute_inner(
         self,
         ctx: RuntimeState<Op>,
         task_scheduler: DynScheduler<Op>,
@@ -116,44 +176,138 @@ impl<Op: Operation> BridgeTask<Op> {
             return Err(Error::OperationNotFound(self.op_id));
         };
 
+        // The operation could be completed while its task waited, e.g. cancelled by the operator.
+        if operation.is_complete() {
+            log::trace!("Operation #{} is already complete.", self.op_id);
+            return Ok(());
+        }
+
+        if let Some(dependency_id) = operation.dependency() {
+            let released = ctx
+                .borrow()
+                .operations
+                .get(dependency_id)
+                .is_some_and(|dependency| dependency.releases_dependents());
+            if !released {
+                if let Some(parked) = operation.park() {
+                    if ctx
+                        .borrow()
+                        .dependency_wait_exhausted(self.op_id, ic::time())
+                    {
+                        log::warn!(
+                            "Operation #{} is parked, operation #{dependency_id} is not released in time.",
+                            self.op_id
+                        );
+                        ctx.borrow_mut().operations.update(self.op_id, parked);
+                        return Ok(());
+                    }
+                }
+
+                log::trace!(
+                    "Operation #{} waits for operation #{dependency_id}.",
+                    self.op_id
+                );
+                return Err(Error::DependencyNotReleased(dependency_id));
+            }
+        }
+
+        let _concurrency_guard = match operation.direction() {
+            Some(direction) => {
+                let limit = ctx
+                    .borrow()
+                    .config
+                    .borrow()
+                    .get_effective_operation_concurrency_limits()
+                    .limit(direction);
+                let started = ctx.borrow_mut().concurrency.try_start(
+                    self.op_id,
+                    direction,
+                    limit,
+                    operation.priority(),
+                );
+                if !started {
+                    log::trace!(
+                        "Operation #{} is deferred by the {direction:?} concurrency limit.",
+                        self.op_id
+                    );
+                    return Ok(());
+                }
+
+                Some(drop_guard::guard(ctx.clone(), move |ctx| {
+                    ctx.borrow_mut().concurrency.finish(direction)
+                }))
+            }
+            None => None,
+        };
+
+        let replay_capture = ctx.borrow().capture_progress_replay(self.op_id, &operation);
+        let result = operation.progress(self.op_id, ctx.clone()).await;
+        if let Some((input, context)) = replay_capture {
+            ctx.borrow_mut()
+                .record_progress_replay(self.op_id, input, context, &result);
+        }
+
         let ctx_clone = ctx.clone();
-        let progress = operation
-            .progress(self.op_id, ctx.clone())
-            .await
-            .inspect_err(move |err| {
-                ctx_clone
-                    .borrow_mut()
-                    .operations
-                    .update_with_err(self.op_id, err.to_string())
-            })?;
-
-        let new_op = match progress {
-            OperationProgress::Progress(op) => op,
+        let progress = result.inspect_err(move |err| {
+            ctx_clone
+                .borrow_mut()
+                .operations
+                .update_with_err(self.op_id, err.to_string())
+        })?;
+
+        let (new_op, dependent) = match progress {
+            OperationProgress::Progress(op) => (op, None),
             OperationProgress::AddToService(service_id) => {
                 ctx.borrow()
                     .push_operation_to_service(service_id, self.op_id)?;
                 return Ok(());
             }
+            OperationProgress::ProgressWithDependent {
+                update_to,
+                dependent,
+            } => (update_to, Some(dependent)),
         };
 
-        let scheduling_options = new_op.scheduling_options();
-        ctx.borrow_mut()
+        let updated = ctx
+            .borrow_mut()
             .operations
             .update(self.op_id, new_op.clone());
-
-        if let Some(options) = scheduling_options {
-            let scheduled_task = ScheduledTask::with_options(
-                Self {
-                    op_id: self.op_id,
-                    operation: new_op,
-                },
-                options,
+        if !updated {
+            return Err(Error::CannotProgress(format!(
+                "operation #{} update is rejected",
+                self.op_id
+            )));
+        }
+        Self::schedule(&ctx, &task_scheduler, self.op_id, new_op);
+
+        // The dependent operation is created after the update, so it observes the new state.
+        if let Some(dependent) = dependent {
+            let dependent_id = ctx
+                .borrow_mut()
+                .operations
+                .new_operation(dependent.clone(), None);
+            log::trace!(
+                "Operation #{dependent_id} created as dependent of operation #{}.",
+                self.op_id
             );
-            task_scheduler.append_task(scheduled_task);
+            Self::schedule(&ctx, &task_scheduler, dependent_id, dependent);
         }
 
         Ok(())
     }
+
+    fn schedule(
+        ctx: &RuntimeState<Op>,
+        task_scheduler: &DynScheduler<Op>,
+        op_id: OperationId,
+        operation: Op,
+    ) {
+        if let Some(options) = operation.scheduling_options() {
+            let task = ctx.borrow_mut().new_task(op_id, operation);
+            let scheduled_task = ScheduledTask::with_options(task, options);
+            task_scheduler.append_task(scheduled_task);
+        }
+    }
 }
 
 impl<Op: Operation> Task for BridgeTask<Op> {
@@ -165,9 +319,9 @@ impl<Op: Operation> Task for BridgeTask<Op> {
         task_scheduler: Box<dyn 'static + TaskScheduler<Self>>,
     ) -> Pin<Box<dyn Future<Output = Result<(), SchedulerError>>>> {
         let self_clone = self.clone();
-        Box::pin(async {
-            self_clone
-                .execute_inner(ctx, task_scheduler)
+        let span = OperationSpan::new(self.op_id, "progress");
+        Box::pin(async move {
+            span.instrument(self_clone.execute_inner(ctx, task_scheduler))
                 .await
                 .map_err(|e| match e {
                     Error::CannotProgress(_) => {
@@ -185,6 +339,7 @@ impl<Op: Operation> Task for BridgeTask<Op> {
 
 #[cfg(test)]
 mod tests {
+    use bridge_did::retry_profile::{RetryLimit, RetryProfileKind, RetryProfiles};
     use did::H160;
     use ic_exports::ic_kit::MockContext;
     use ic_storage::IcStorage;
@@ -200,6 +355,8 @@ mod tests {
         successful: bool,
         successful_runs: usize,
         recoverable: bool,
+        dependency: Option<OperationId>,
+        data: Vec<u8>,
     }
 
     impl TestOperation {
@@ -210,6 +367,8 @@ mod tests {
                 successful: false,
                 successful_runs: 0,
                 recoverable: true,
+                dependency: None,
+                data: vec![],
             }
         }
 
@@ -218,6 +377,8 @@ mod tests {
                 successful: true,
                 successful_runs: 0,
                 recoverable: true,
+                dependency: None,
+                data: vec![],
             }
         }
 
@@ -226,6 +387,8 @@ mod tests {
                 successful: false,
                 successful_runs: 0,
                 recoverable: false,
+                dependency: None,
+                data: vec![],
             }
         }
     }
@@ -239,8 +402,7 @@ mod tests {
             if self.successful {
                 Ok(OperationProgress::Progress(Self {
                     successful_runs: self.successful_runs + 1,
-                    successful: self.successful,
-                    recoverable: self.recoverable,
+                    ..self
                 }))
             } else if self.recoverable {
                 Err(Error::FailedToProgress(Self::ERR_MESSAGE.to_string()))
@@ -256,6 +418,52 @@ mod tests {
         fn evm_wallet_address(&self) -> H160 {
             H160::from_slice(&[1; 20])
         }
+
+        fn dependency(&self) -> Option<OperationId> {
+            self.dependency
+        }
+
+        fn releases_dependents(&self) -> bool {
+            self.successful_runs > 0
+        }
+    }
+
+    #[test]
+    fn state_accessors_for_test_use_runtime_state() {
+        MockContext::new().inject();
+
+        let runtime: BridgeRuntime<TestOperation> = BridgeRuntime::default(ConfigStorage::get());
+        let id = runtime.mutate_state_for_test(|state| {
+            state
+                .operations
+                .new_operation(TestOperation::new_ok(), None)
+        });
+
+        let op = runtime.with_state_for_test(|state| state.operations.get(id));
+        assert_eq!(op, Some(TestOperation::new_ok()));
+        assert_eq!(runtime.state.borrow().operations.get(id), op);
+    }
+
+    #[test]
+    fn default_scheduling_options_follow_evm_call_profile() {
+        MockContext::new().inject();
+        let config = ConfigStorage::get();
+        let options = TestOperation::new_ok().scheduling_options();
+        assert_eq!(
+            options,
+            Some(config.borrow().get_task_options(RetryProfileKind::EvmCall))
+        );
+
+        let mut profiles = RetryProfiles::default();
+        profiles.evm_call.retries = RetryLimit::MaxRetries(9);
+        config.borrow_mut().set_retry_profiles(Some(profiles));
+
+        let new_options = TestOperation::new_ok().scheduling_options();
+        assert_ne!(new_options, options);
+        assert_eq!(
+            new_options,
+            Some(config.borrow().get_task_options(RetryProfileKind::EvmCall))
+        );
     }
 
     #[tokio::test]
@@ -281,16 +489,15 @@ mod tests {
             .operations
             .get_log(id)
             .expect("operation is not in the log");
-        assert_eq!(log.log().len(), COUNT + 1);
+        // Identical errors are coalesced into a single step.
+        assert_eq!(log.log().len(), 2);
         assert_eq!(log.log()[0].step_result, Ok(op));
-
-        for i in 1..COUNT + 1 {
-            assert!(log.log()[i]
-                .step_result
-                .as_ref()
-                .unwrap_err()
-                .contains(TestOperation::ERR_MESSAGE));
-        }
+        assert_eq!(log.log()[1].repeat_count, COUNT as u32);
+        assert!(log.log()[1]
+            .step_result
+            .as_ref()
+            .unwrap_err()
+            .contains(TestOperation::ERR_MESSAGE));
     }
 
     #[tokio::test]
@@ -326,6 +533,8 @@ mod tests {
                     successful: true,
                     successful_runs: i,
                     recoverable: true,
+                    dependency: None,
+                    data: vec![],
                 }
             );
         }
@@ -376,4 +585,157 @@ mod tests {
             str!["Unrecoverable task error: operation cannot progress: test error"]
         )
     }
+
+    #[tokio::test]
+    async fn dependent_operation_waits_for_dependency() {
+        MockContext::new().inject();
+
+        let runtime: BridgeRuntime<TestOperation> = BridgeRuntime::default(ConfigStorage::get());
+        let ctx = runtime.state.clone();
+        let dependency = TestOperation::new_ok();
+        let dependency_id = ctx
+            .borrow_mut()
+            .operations
+            .new_operation(dependency.clone(), None);
+        let dependent = TestOperation {
+            dependency: Some(dependency_id),
+            ..TestOperation::new_ok()
+        };
+        let dependent_id = ctx
+            .borrow_mut()
+            .operations
+            .new_operation(dependent.clone(), None);
+
+        let err = BridgeTask::new(dependent_id, dependent.clone())
+            .execute_inner(ctx.clone(), Box::new(runtime.scheduler.clone()))
+            .await
+            .unwrap_err();
+        assert_eq!(err, Error::DependencyNotReleased(dependency_id));
+        assert_eq!(ctx.borrow().operations.get(dependent_id), Some(dependent));
+
+        BridgeTask::new(dependency_id, dependency)
+            .execute_inner(ctx.clone(), Box::new(runtime.scheduler.clone()))
+            .await
+            .unwrap();
+
+        let dependent = ctx.borrow().operations.get(dependent_id).unwrap();
+        BridgeTask::new(dependent_id, dependent)
+            .execute_inner(ctx.clone(), Box::new(runtime.scheduler.clone()))
+            .await
+            .unwrap();
+        assert_eq!(
+            ctx.borrow()
+                .operations
+                .get(dependent_id)
+                .unwrap()
+                .successful_runs,
+            1
+        );
+    }
+
+    fn large_operation() -> TestOperation {
+        TestOperation {
+            data: vec![1; MAX_INLINE_TASK_PAYLOAD_SIZE],
+            ..TestOperation::new_ok()
+        }
+    }
+
+    #[test]
+    fn payloads_over_limit_are_moved_to_store() {
+        MockContext::new().inject();
+
+        let runtime: BridgeRuntime<TestOperation> = BridgeRuntime::default(ConfigStorage::get());
+        let mut state = runtime.state.borrow_mut();
+        let id = OperationId::new(1);
+
+        let small = state.new_task(id, TestOperation::new_ok());
+        assert!(small.payload_key.is_none());
+        assert_eq!(
+            small.operation(&state.task_payloads),
+            Some(TestOperation::new_ok())
+        );
+
+        let large = state.new_task(id, large_operation());
+        assert!(large.operation.is_none());
+        assert!(large.payload_key.is_some());
+        assert_eq!(
+            large.operation(&state.task_payloads),
+            Some(large_operation())
+        );
+
+        let duplicate = state.new_task(OperationId::new(2), large_operation());
+        assert_eq!(duplicate.payload_key, large.payload_key);
+        assert_eq!(state.task_payloads.len(), 1);
+    }
+
+    #[test]
+    fn sweep_keeps_payloads_of_pending_tasks() {
+        MockContext::new().inject();
+
+        let runtime: BridgeRuntime<TestOperation> = BridgeRuntime::default(ConfigStorage::get());
+        let id = runtime
+            .state
+            .borrow_mut()
+            .operations
+            .new_operation(large_operation(), None);
+        runtime.schedule_operation(id, large_operation());
+        let orphan = runtime
+            .state
+            .borrow_mut()
+            .task_payloads
+            .put(vec![2; 2 * MAX_INLINE_TASK_PAYLOAD_SIZE]);
+
+        runtime.sweep_task_payloads();
+
+        let state = runtime.state.borrow();
+        assert_eq!(state.task_payloads.len(), 1);
+        assert_eq!(state.task_payloads.get(&orphan), None);
+
+        let stats = state.task_payloads.stats();
+        assert_eq!(stats.spilled_tasks, 1);
+        assert_eq!(stats.stored_payloads, 1);
+        assert_eq!(stats.spilled_bytes, stats.stored_bytes);
+        assert_eq!(stats.oversized_inline_tasks, 0);
+    }
+
+    #[tokio::test]
+    async fn stored_inline_payloads_are_moved_on_next_step() {
+        MockContext::new().inject();
+
+        /// Task layout before the payload store was introduced.
+        #[derive(CandidType)]
+        struct InlineTask {
+            op_id: OperationId,
+            operation: TestOperation,
+        }
+
+        let runtime: BridgeRuntime<TestOperation> = BridgeRuntime::default(ConfigStorage::get());
+        let ctx = runtime.state.clone();
+        let id = ctx
+            .borrow_mut()
+            .operations
+            .new_operation(large_operation(), None);
+
+        let stored = candid::encode_one(InlineTask {
+            op_id: id,
+            operation: large_operation(),
+        })
+        .unwrap();
+        let task: BridgeTask<TestOperation> = candid::decode_one(&stored).unwrap();
+        assert!(task.is_oversized_inline());
+        assert_eq!(
+            task.operation(&ctx.borrow().task_payloads),
+            Some(large_operation())
+        );
+
+        task.execute_inner(ctx.clone(), Box::new(runtime.scheduler.clone()))
+            .await
+            .unwrap();
+
+        let next_step = runtime
+            .scheduler
+            .find_id(&|task| task.op_id == id && task.payload_key.is_some());
+        assert!(next_step.is_some());
+        assert_eq!(ctx.borrow().task_payloads.len(), 1);
+    }
 }
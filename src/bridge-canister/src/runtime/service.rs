@@ -4,8 +4,13 @@ use std::rc::Rc;
 use bridge_did::error::{BTFResult, Error};
 use bridge_did::op_id::OperationId;
 
+pub mod dropped_tx;
 pub mod fetch_logs;
+pub mod gas_balance;
+pub mod migrate_records;
 pub mod mint_tx;
+pub mod outbox;
+pub mod ping_evm;
 pub mod sign_orders;
 pub mod timer;
 pub mod update_evm_params;
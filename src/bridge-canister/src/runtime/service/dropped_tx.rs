@@ -0,0 +1,242 @@
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::op_id::OperationId;
+use bridge_utils::evm_bridge;
+use did::H256;
+use ic_exports::ic_kit::ic;
+
+use super::BridgeService;
+use crate::log_span::OperationSpan;
+use crate::runtime::state::config::ConfigStorage;
+use crate::runtime::state::SharedConfig;
+
+/// Delay between the checks of the sent mint transactions.
+pub const CHECK_DROPPED_TX_DELAY: Duration = Duration::from_secs(60);
+
+pub trait DroppedMintTxHandler {
+    fn get_evm_config(&self) -> SharedConfig;
+    /// Returns the operations, which mint transactions were sent before the `sent_before`
+    /// IC time and are not confirmed yet, with the hashes of the transactions and the indices
+    /// of the EVM params snapshots the transactions were signed with.
+    fn get_unconfirmed_mint_txs(&self, sent_before: u64) -> Vec<(OperationId, H256, Option<u64>)>;
+    /// Returns the operation to the mint transaction sending, if its mint transaction
+    /// is still `tx_hash`.
+    fn mint_tx_dropped(&self, id: OperationId, tx_hash: H256);
+}
+
+/// Service to re-send the mint transactions, dropped from the EVM mempool.
+///
+/// Mint transactions, which are not confirmed in `dropped_tx_timeout_secs` after sending,
+/// are queried by hash. If the EVM node doesn't know the transaction, the operation is returned
+/// to the mint transaction sending, so the transaction is re-signed with the pending nonce
+/// of the bridge and sent again. Mint orders can't be minted twice, so the re-sent transaction
+/// is harmless even if the dropped one is mined later.
+///
+/// The nonce gap, left by the dropped transactions, is closed only if it consists exactly of
+/// their nonces. Otherwise the gap may contain transactions, which are still to be mined.
+pub struct CheckDroppedMintTxService<H> {
+    handler: H,
+}
+
+impl<H> CheckDroppedMintTxService<H> {
+    /// Creates a new service with the given handler.
+    pub fn new(handler: H) -> Self {
+        Self { handler }
+    }
+}
+
+/// Mint transaction, which is not confirmed yet.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct UnconfirmedMintTx {
+    operations: Vec<OperationId>,
+    /// Nonce of the transaction, if its EVM params snapshot is still kept.
+    nonce: Option<u64>,
+}
+
+impl<H: DroppedMintTxHandler> CheckDroppedMintTxService<H> {
+    /// Returns the unconfirmed mint transactions, sent before the timeout, grouped by hash.
+    fn unconfirmed_txs(&self, now: u64) -> HashMap<H256, UnconfirmedMintTx> {
+        let config = self.handler.get_evm_config();
+        let Some(timeout_secs) = config.borrow().get_dropped_tx_timeout_secs() else {
+            return HashMap::new();
+        };
+
+        let sent_before = now.saturating_sub(Duration::from_secs(timeout_secs).as_nanos() as u64);
+        let mut txs: HashMap<H256, UnconfirmedMintTx> = HashMap::new();
+        for (id, tx_hash, evm_params_snapshot) in self.handler.get_unconfirmed_mint_txs(sent_before)
+        {
+            let tx = txs.entry(tx_hash).or_default();
+            tx.operations.push(id);
+            tx.nonce = tx.nonce.or_else(|| {
+                evm_params_snapshot
+                    .and_then(|index| config.borrow().get_evm_params_snapshot(index))
+                    .map(|snapshot| snapshot.nonce)
+            });
+        }
+
+        txs
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<H: DroppedMintTxHandler> BridgeService for CheckDroppedMintTxService<H> {
+    async fn run(&self) -> BTFResult<()> {
+        let unconfirmed = self.unconfirmed_txs(ic::time());
+        if unconfirmed.is_empty() {
+            return Ok(());
+        }
+
+        let config = self.handler.get_evm_config();
+        let client = config.borrow().get_json_rpc_client();
+
+        let mut dropped = vec![];
+        for (tx_hash, tx) in unconfirmed {
+            let known = evm_bridge::is_transaction_known(&client, tx_hash.0)
+                .await
+                .map_err(|e| {
+                    Error::EvmRequestFailed(format!("failed to query mint transaction: {e}"))
+                })?;
+            if !known {
+                dropped.push((tx_hash, tx));
+            }
+        }
+
+        if dropped.is_empty() {
+            return Ok(());
+        }
+
+        // The nonce of a dropped transaction is either still free, or is consumed by another
+        // transaction. The pending nonce is correct to re-sign the transaction in both cases.
+        // Dropped transactions leave a gap in the nonces, which is closed here only if it
+        // consists of the dropped transactions alone.
+        let dropped_nonces: Option<BTreeSet<u64>> =
+            dropped.iter().map(|(_, tx)| tx.nonce).collect();
+        ConfigStorage::refresh_evm_params(config.clone()).await?;
+        let closed_gap =
+            dropped_nonces.and_then(|nonces| config.borrow_mut().acknowledge_nonce_gap_of(&nonces));
+        if let Some(gap) = closed_gap {
+            log::info!(
+                "Nonce gap from {} to {} is closed due to the dropped mint transactions",
+                gap.chain_nonce,
+                gap.local_nonce
+            );
+        } else if config.borrow().is_nonce_gap_open() {
+            log::warn!(
+                "Nonce gap is kept open: it doesn't match the nonces of the dropped mint transactions"
+            );
+        }
+
+        for (tx_hash, tx) in dropped {
+            for id in tx.operations {
+                OperationSpan::new(id, "check_dropped_mint_tx").in_scope(|| {
+                    log::warn!(
+                        "Mint transaction {:#x} of operation {id} is dropped from the EVM mempool. Sending it again.",
+                        tx_hash.0
+                    );
+                    self.handler.mint_tx_dropped(id, tx_hash.clone())
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn push_operation(&self, _: OperationId) -> BTFResult<()> {
+        let msg = "Operations should not be pushed to the CheckDroppedMintTxService service";
+        log::warn!("{msg}");
+        Err(Error::FailedToProgress(msg.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use ic_exports::ic_kit::MockContext;
+    use ic_stable_structures::MemoryId;
+
+    use super::*;
+    use crate::memory::memory_by_id;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    struct TestHandler {
+        config: SharedConfig,
+        /// Sent transactions with their sending time and EVM params snapshot.
+        sent: Vec<(OperationId, H256, u64, Option<u64>)>,
+    }
+
+    impl DroppedMintTxHandler for TestHandler {
+        fn get_evm_config(&self) -> SharedConfig {
+            self.config.clone()
+        }
+
+        fn get_unconfirmed_mint_txs(
+            &self,
+            sent_before: u64,
+        ) -> Vec<(OperationId, H256, Option<u64>)> {
+            self.sent
+                .iter()
+                .filter(|(_, _, sent_at, _)| *sent_at < sent_before)
+                .map(|(id, tx_hash, _, snapshot)| (*id, tx_hash.clone(), *snapshot))
+                .collect()
+        }
+
+        fn mint_tx_dropped(&self, _: OperationId, _: H256) {
+            unreachable!("dropped transactions are not detected without EVM")
+        }
+    }
+
+    fn service(timeout_secs: Option<u64>) -> CheckDroppedMintTxService<TestHandler> {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        config.set_dropped_tx_timeout_secs(timeout_secs);
+        config.update_evm_params(|p| p.nonce = 10);
+        config.update_evm_params(|p| p.nonce = 11);
+
+        let tx = H256::from_slice(&[1; 32]);
+        let batch_tx = H256::from_slice(&[2; 32]);
+        CheckDroppedMintTxService::new(TestHandler {
+            config: Rc::new(RefCell::new(config)),
+            sent: vec![
+                (OperationId::new(1), tx.clone(), 100 * SECOND, Some(1)),
+                (OperationId::new(2), batch_tx.clone(), 200 * SECOND, Some(0)),
+                (OperationId::new(3), batch_tx, 200 * SECOND, Some(0)),
+                (OperationId::new(4), tx, 900 * SECOND, Some(1)),
+            ],
+        })
+    }
+
+    #[test]
+    fn should_not_check_transactions_without_timeout() {
+        assert!(service(None).unconfirmed_txs(1000 * SECOND).is_empty());
+    }
+
+    #[test]
+    fn should_check_transactions_sent_before_timeout() {
+        let service = service(Some(600));
+
+        let unconfirmed = service.unconfirmed_txs(1000 * SECOND);
+        assert_eq!(unconfirmed.len(), 2);
+        assert_eq!(
+            unconfirmed[&H256::from_slice(&[1; 32])],
+            UnconfirmedMintTx {
+                operations: vec![OperationId::new(1)],
+                nonce: Some(11),
+            }
+        );
+        assert_eq!(
+            unconfirmed[&H256::from_slice(&[2; 32])],
+            UnconfirmedMintTx {
+                operations: vec![OperationId::new(2), OperationId::new(3)],
+                nonce: Some(10),
+            }
+        );
+
+        assert_eq!(service.unconfirmed_txs(750 * SECOND).len(), 1);
+        assert!(service.unconfirmed_txs(650 * SECOND).is_empty());
+    }
+}
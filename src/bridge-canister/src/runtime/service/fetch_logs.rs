@@ -1,11 +1,22 @@
+use std::collections::HashMap;
+
 use bridge_did::error::{BTFResult, Error};
-use bridge_did::event_data::{BurntEventData, MintedEventData, NotifyMinterEventData};
+use bridge_did::event_data::{
+    BurntEventData, MintedEventData, MinterNotificationType, NotifyMinterEventData,
+};
+use bridge_did::fee_adjustment::PendingFeeReconciliation;
 use bridge_did::op_id::OperationId;
 use bridge_did::operation_log::Memo;
 use bridge_utils::btf_events::BridgeEvent;
+use bridge_utils::event_payload;
+use bridge_utils::evm_bridge::{self, MintTxCost};
+use did::{H160, H256, U256};
+use ic_exports::ic_kit::ic;
 
 use super::BridgeService;
 use crate::bridge::{Operation, OperationAction, OperationContext};
+use crate::fee_adjustments;
+use crate::processed_events::PROCESSED_EVENTS_RETENTION_BLOCKS;
 use crate::runtime::state::SharedConfig;
 use crate::runtime::{RuntimeState, SharedRuntime};
 
@@ -30,6 +41,9 @@ pub struct FetchBtfBridgeEventsService<Op: Operation, H> {
 impl<Op: Operation, H: BtfBridgeEventHandler<Op>> FetchBtfBridgeEventsService<Op, H> {
     const MAX_LOG_REQUEST_COUNT: u64 = 1000;
 
+    /// Max number of the minted orders, which fees are reconciled per run.
+    const MAX_FEE_RECONCILIATIONS_PER_RUN: usize = 20;
+
     /// Creates new instance of the service, which will fetch events using the `evm_config`
     /// and process it using the `handler`.
     pub fn new(handler: H, runtime: SharedRuntime<Op>, evm_config: SharedConfig) -> Self {
@@ -49,25 +63,83 @@ impl<Op: Operation, H: BtfBridgeEventHandler<Op>> FetchBtfBridgeEventsService<Op
             .evm_config
             .collect_evm_events(Self::MAX_LOG_REQUEST_COUNT)
             .await?;
-        let events = collected.events;
-
         self.evm_config
             .borrow_mut()
-            .update_evm_params(|params| params.next_block = collected.last_block_number + 1);
-
-        for event in events {
-            log::trace!("handling event: {event:?}");
-
-            let op_action = match event {
-                BridgeEvent::Burnt(event) => self.handler.on_wrapped_token_burnt(event),
-                BridgeEvent::Minted(event) => self.handler.on_wrapped_token_minted(event),
-                BridgeEvent::Notify(event) => {
-                    if let Ok(operation_id) = event.try_decode_reschedule_operation_id() {
-                        self.runtime.borrow().reschedule_operation(operation_id);
-                        return Ok(());
-                    }
+            .update_evm_params(|params| params.next_block = collected.next_block());
+        let events = collected.events;
+
+        let (bridge_contract, activation_block) = {
+            let config = self.evm_config.borrow();
+            (
+                config.get_btf_bridge_contract(),
+                config.get_btf_bridge_activation_block(),
+            )
+        };
+        let Some(bridge_contract) = bridge_contract else {
+            return Err(Error::Initialization(
+                "BTF bridge contract is not configured".into(),
+            ));
+        };
+
+        for event_log in events {
+            log::trace!("handling event: {event_log:?}");
+
+            let accepted = self.state().borrow_mut().processed_events.accept(
+                &event_log,
+                &bridge_contract,
+                activation_block,
+                ic::time(),
+            );
+            if !accepted {
+                continue;
+            }
+
+            let event_key = event_log.key();
+            let mut minted_fee = None;
 
-                    self.handler.on_minter_notification(event)
+            let op_action = match event_log.event {
+                BridgeEvent::Burnt(event) => {
+                    let strict = self
+                        .evm_config
+                        .borrow()
+                        .is_strict_event_validation_enabled();
+                    let Some(event) = self
+                        .state()
+                        .borrow_mut()
+                        .sanitize_burnt_event(event, strict)
+                    else {
+                        continue;
+                    };
+
+                    self.handler.on_wrapped_token_burnt(event)
+                }
+                BridgeEvent::Minted(event) => {
+                    minted_fee = Some((event.nonce, event.fee_charged.clone()));
+                    self.handler.on_wrapped_token_minted(event)
+                }
+                BridgeEvent::Notify(event)
+                    if event.notification_type == MinterNotificationType::RescheduleOperation =>
+                {
+                    match event_payload::decode_candid_payload::<OperationId>(&event.user_data) {
+                        Ok(operation_id) => {
+                            self.runtime.borrow().reschedule_operation(operation_id);
+                            return Ok(());
+                        }
+                        Err(e) => Some(OperationAction::reject(
+                            event,
+                            format!("failed to decode reschedule operation ID: {e}"),
+                        )),
+                    }
+                }
+                BridgeEvent::Notify(event) => self.handler.on_minter_notification(event),
+                BridgeEvent::Unknown { raw } => {
+                    let id = self.state().borrow_mut().dead_letters.push(
+                        raw,
+                        "no decoder registered for the event topic".into(),
+                        ic::time(),
+                    );
+                    log::warn!("Event log with unknown topic is moved to the dead-letter list with id {id}");
+                    continue;
                 }
             };
 
@@ -75,12 +147,107 @@ impl<Op: Operation, H: BtfBridgeEventHandler<Op>> FetchBtfBridgeEventsService<Op
                 continue;
             };
 
+            if let Some(key) = &event_key {
+                self.state()
+                    .borrow_mut()
+                    .processed_events
+                    .set_operation_id(key, to_schedule.0);
+            }
+
+            if let (Some((nonce, charged)), Some(tx_hash)) = (minted_fee, &event_log.tx_hash) {
+                self.add_pending_fee_reconciliation(PendingFeeReconciliation {
+                    operation_id: to_schedule.0,
+                    bridge_contract: bridge_contract.clone(),
+                    tx_hash: tx_hash.clone(),
+                    nonce,
+                    charged,
+                });
+            }
+
             self.runtime
                 .borrow()
                 .schedule_operation(to_schedule.0, to_schedule.1);
         }
 
+        self.state().borrow_mut().processed_events.prune_before(
+            collected
+                .last_block_number
+                .saturating_sub(PROCESSED_EVENTS_RETENTION_BLOCKS),
+        );
+
         log::debug!("EVM logs collected");
+
+        if let Err(e) = self.reconcile_fees(&bridge_contract).await {
+            log::warn!("Failed to reconcile mint fees: {e}");
+        }
+
+        Ok(())
+    }
+
+    /// Adds the minted order to the fee reconciliation, if it is enabled and the fee is charged.
+    fn add_pending_fee_reconciliation(&self, pending: PendingFeeReconciliation) {
+        if self.evm_config.borrow().get_fee_reconciliation().is_none()
+            || pending.charged == U256::zero()
+        {
+            return;
+        }
+
+        self.state()
+            .borrow_mut()
+            .fee_adjustments
+            .add_pending(pending);
+    }
+
+    /// Reconciles the fees of the orders, minted by the `bridge_contract`, with the cost of
+    /// their mint transactions.
+    async fn reconcile_fees(&self, bridge_contract: &H160) -> BTFResult<()> {
+        let Some(settings) = self.evm_config.borrow().get_fee_reconciliation() else {
+            return Ok(());
+        };
+
+        let pending = self
+            .state()
+            .borrow()
+            .fee_adjustments
+            .get_pending(bridge_contract, Self::MAX_FEE_RECONCILIATIONS_PER_RUN);
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let client = self.evm_config.borrow().get_json_rpc_client();
+        let mut costs: HashMap<H256, Option<MintTxCost>> = HashMap::new();
+        for pending in pending {
+            if !costs.contains_key(&pending.tx_hash) {
+                let cost =
+                    evm_bridge::query_mint_tx_cost(&client, pending.tx_hash.0, bridge_contract)
+                        .await
+                        .map_err(|e| {
+                            Error::EvmRequestFailed(format!(
+                                "failed to query mint transaction cost: {e}"
+                            ))
+                        })?;
+                costs.insert(pending.tx_hash.clone(), cost);
+            }
+
+            let id = pending.operation_id;
+            let adjustment = costs[&pending.tx_hash]
+                .as_ref()
+                .and_then(|cost| fee_adjustments::reconcile(&pending, cost, &settings, ic::time()));
+            let Some(adjustment) = adjustment else {
+                log::warn!(
+                    "Mint order of operation {id} is not found in transaction {:#x}. Fee is not reconciled.",
+                    pending.tx_hash.0
+                );
+                self.state().borrow_mut().fee_adjustments.remove_pending(id);
+                continue;
+            };
+
+            let amount = adjustment.amount.clone();
+            if self.state().borrow_mut().apply_fee_adjustment(adjustment) {
+                log::info!("Mint fee of operation {id} is adjusted: {amount:?}");
+            }
+        }
+
         Ok(())
     }
 
@@ -93,6 +260,15 @@ impl<Op: Operation, H: BtfBridgeEventHandler<Op>> FetchBtfBridgeEventsService<Op
             OperationAction::Update { nonce, update_to } => {
                 self.update_operation(nonce, update_to)?
             }
+            OperationAction::Reject { event, reason } => {
+                let id =
+                    self.state()
+                        .borrow_mut()
+                        .dead_letters
+                        .push(event, reason.clone(), ic::time());
+                log::warn!("Event is moved to the dead-letter list with id {id}: {reason}");
+                return None;
+            }
         };
 
         Some(to_schedule)
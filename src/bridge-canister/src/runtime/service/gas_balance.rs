@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::op_id::OperationId;
+use bridge_utils::query::{self, Query, QueryType, BALANCE_ID};
+use did::U256;
+use ic_exports::ic_kit::ic;
+use jsonrpc_core::Id;
+
+use super::BridgeService;
+use crate::runtime::state::config::ConfigStorage;
+use crate::runtime::state::SharedConfig;
+
+/// Delay between the bridge canister EVM address balance queries.
+pub const GAS_BALANCE_QUERY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// Service to monitor the native token balance of the bridge canister EVM address.
+///
+/// Does nothing, if gas balance thresholds are not configured.
+pub struct MonitorGasBalanceService {
+    config: SharedConfig,
+}
+
+impl MonitorGasBalanceService {
+    pub fn new(config: SharedConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl BridgeService for MonitorGasBalanceService {
+    async fn run(&self) -> BTFResult<()> {
+        if self.config.borrow().get_gas_balance_thresholds().is_none() {
+            log::trace!("Gas balance thresholds are not configured. Skipping balance check.");
+            return Ok(());
+        }
+
+        let address = ConfigStorage::get_signer_address(self.config.clone()).await?;
+
+        let client = self.config.borrow().get_json_rpc_client();
+        let responses = query::batch_query(
+            &client,
+            &[QueryType::Balance {
+                address: address.clone().into(),
+            }],
+        )
+        .await
+        .map_err(|e| Error::EvmRequestFailed(format!("failed to query balance: {e}")))?;
+
+        let balance: U256 = responses
+            .get_value_by_id(Id::Str(BALANCE_ID.into()))
+            .map_err(|e| Error::EvmRequestFailed(format!("failed to query balance: {e}")))?;
+
+        self.config
+            .borrow_mut()
+            .record_gas_balance(address, balance, ic::time());
+
+        Ok(())
+    }
+
+    fn push_operation(&self, _: OperationId) -> BTFResult<()> {
+        let msg = "Operations should not be pushed to the MonitorGasBalanceService service";
+        log::warn!("{msg}");
+        Err(Error::FailedToProgress(msg.into()))
+    }
+}
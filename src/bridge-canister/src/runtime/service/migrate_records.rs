@@ -0,0 +1,163 @@
+use std::cell::Cell;
+use std::time::Duration;
+
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::op_id::OperationId;
+use ic_task_scheduler::scheduler::TaskScheduler;
+use ic_task_scheduler::task::ScheduledTask;
+
+use super::BridgeService;
+use crate::bridge::Operation;
+use crate::memory::StableMemory;
+use crate::runtime::scheduler::SharedScheduler;
+use crate::runtime::RuntimeState;
+
+/// Delay between the migration batches.
+pub const MIGRATE_RECORDS_DELAY: Duration = Duration::from_secs(10);
+
+/// Max number of the operations checked by a single migration batch.
+const MIGRATE_RECORDS_BATCH_SIZE: usize = 100;
+
+/// Service to rewrite the stored operations, which are encoded in an outdated version or with
+/// a legacy shape of the operation, in the current encoding.
+///
+/// Outdated operations are decoded on read, so they are not rewritten during the upgrade.
+/// Instead, the service checks all the stored operations in batches, starting after every
+/// canister start, and stops when all of them are checked.
+///
+/// Decoding of a legacy operation can move it to another state, e.g. to signing of a mint
+/// order again, so the migrated incomplete operations without a task are scheduled.
+pub struct MigrateRecordsService<Op: Operation> {
+    state: RuntimeState<Op>,
+    scheduler: SharedScheduler<StableMemory, Op>,
+    next: Cell<Option<OperationId>>,
+}
+
+impl<Op: Operation> MigrateRecordsService<Op> {
+    pub fn new(state: RuntimeState<Op>, scheduler: SharedScheduler<StableMemory, Op>) -> Self {
+        Self {
+            state,
+            scheduler,
+            next: Cell::new(Some(OperationId::default())),
+        }
+    }
+
+    fn schedule_if_missing(&self, id: OperationId) {
+        let Some(operation) = self.state.borrow().operations.get(id) else {
+            return;
+        };
+        if operation.is_complete() || self.scheduler.find_id(&|task| task.op_id == id).is_some() {
+            return;
+        }
+        let Some(options) = operation.scheduling_options() else {
+            return;
+        };
+
+        let task = self.state.borrow_mut().new_task(id, operation);
+        let task_id = self
+            .scheduler
+            .append_task(ScheduledTask::with_options(task, options));
+        log::info!("Migrated operation #{id} is scheduled with task #{task_id}");
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<Op: Operation> BridgeService for MigrateRecordsService<Op> {
+    async fn run(&self) -> BTFResult<()> {
+        let Some(from) = self.next.get() else {
+            return Ok(());
+        };
+
+        let batch = self
+            .state
+            .borrow_mut()
+            .operations
+            .migrate_records_batch(from, MIGRATE_RECORDS_BATCH_SIZE);
+
+        if !batch.migrated.is_empty() {
+            log::info!(
+                "{} operations migrated to the current encoding version",
+                batch.migrated.len()
+            );
+        }
+        if batch.next.is_none() {
+            log::info!("All the stored operations are checked for migration");
+        }
+
+        for id in batch.migrated {
+            self.schedule_if_missing(id);
+        }
+
+        self.next.set(batch.next);
+        Ok(())
+    }
+
+    fn push_operation(&self, _: OperationId) -> BTFResult<()> {
+        let msg = "Operations should not be pushed to the MigrateRecordsService service";
+        log::warn!("{msg}");
+        Err(Error::FailedToProgress(msg.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use candid::CandidType;
+    use did::H160;
+    use ic_exports::ic_kit::MockContext;
+    use ic_stable_structures::MemoryId;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::bridge::OperationProgress;
+    use crate::memory::memory_by_id;
+    use crate::runtime::state::config::ConfigStorage;
+    use crate::runtime::BridgeRuntime;
+
+    #[derive(Clone, Deserialize, Debug, Serialize, CandidType)]
+    struct TestOp;
+
+    impl Operation for TestOp {
+        async fn progress(
+            self,
+            _id: OperationId,
+            _ctx: RuntimeState<Self>,
+        ) -> BTFResult<OperationProgress<Self>> {
+            unimplemented!()
+        }
+
+        fn is_complete(&self) -> bool {
+            false
+        }
+
+        fn evm_wallet_address(&self) -> H160 {
+            H160::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn migration_stops_when_all_operations_are_checked() {
+        MockContext::new().inject();
+        let config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        let runtime = BridgeRuntime::<TestOp>::default(Rc::new(RefCell::new(config)));
+        let state = runtime.state().clone();
+        for _ in 0..MIGRATE_RECORDS_BATCH_SIZE + 1 {
+            state.borrow_mut().operations.new_operation(TestOp, None);
+        }
+
+        let service = MigrateRecordsService::new(state, runtime.scheduler().clone());
+        service.run().await.unwrap();
+        assert_eq!(
+            service.next.get(),
+            Some(OperationId::new(MIGRATE_RECORDS_BATCH_SIZE as u64))
+        );
+
+        service.run().await.unwrap();
+        assert_eq!(service.next.get(), None);
+
+        service.run().await.unwrap();
+        assert_eq!(service.next.get(), None);
+    }
+}
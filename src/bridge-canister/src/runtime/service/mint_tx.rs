@@ -1,57 +1,313 @@
-use std::cell::RefCell;
-use std::collections::{HashMap, HashSet};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::time::Duration;
 
 use bridge_did::error::{BTFResult, Error};
+use bridge_did::evm_tx::EvmTxRequest;
 use bridge_did::op_id::OperationId;
-use bridge_did::order::{SignedOrders, SignedOrdersData};
+use bridge_did::order::{OrderIdx, SignedOrders, SignedOrdersData, DEFAULT_TX_GAS_LIMIT};
+use bridge_did::pending_mint_batch::{PendingMintBatchKey, PendingMintBatchesStats};
 use bridge_utils::btf_events::{self};
+use bridge_utils::evm_bridge;
 use bridge_utils::evm_link::EvmLinkClient;
 use did::H256;
 use eth_signer::sign_strategy::TransactionSigner;
+use ic_exports::ic_kit::ic;
+use ic_stable_structures::stable_structures::Memory;
 
 use super::BridgeService;
+use crate::log_span::OperationSpan;
+use crate::memory::StableMemory;
+use crate::pending_mint_batches::PendingMintBatchStore;
+use crate::runtime::state::config::ConfigStorage;
 use crate::runtime::state::SharedConfig;
 
-/// Contains signed batch of mint orders and set of operations related to the batch.
+/// Contains signed batch of mint orders and operations related to the batch.
 #[derive(Debug, Clone)]
 pub struct MintOrderBatchInfo {
     orders_batch: SignedOrdersData,
-    related_operations: HashSet<OperationId>,
+    /// Operations by the index of their order in the batch.
+    related_operations: HashMap<OrderIdx, OperationId>,
+    /// Indices of the orders to mint. Empty list means all the orders of the batch.
+    orders_to_process: Vec<u32>,
 }
 
+impl MintOrderBatchInfo {
+    fn new(orders_batch: SignedOrdersData, orders_to_process: Vec<u32>) -> Self {
+        Self {
+            orders_batch,
+            related_operations: HashMap::new(),
+            orders_to_process,
+        }
+    }
+
+    /// Checks that the order `idx` of the `orders_batch` is a distinct order slot of the batch,
+    /// not claimed by other operations. Returns the conflict otherwise.
+    fn find_conflict(
+        &self,
+        op_id: OperationId,
+        idx: OrderIdx,
+        orders_batch: &SignedOrdersData,
+    ) -> Option<OrderConflict> {
+        if self.orders_batch.orders_data != orders_batch.orders_data {
+            return Some(OrderConflict::DigestCollision);
+        }
+
+        let order_data = self.orders_batch.order_data(idx);
+        for (other_idx, other_op_id) in &self.related_operations {
+            if *other_op_id == op_id {
+                continue;
+            }
+
+            if *other_idx == idx {
+                return Some(OrderConflict::Duplicate(format!(
+                    "order slot {idx} is already taken by operation {other_op_id}"
+                )));
+            }
+
+            if self.orders_batch.order_data(*other_idx) == order_data {
+                return Some(OrderConflict::Duplicate(format!(
+                    "order is identical to the order of operation {other_op_id}"
+                )));
+            }
+        }
+
+        None
+    }
+}
+
+/// Conflict of the mint order with the orders of other operations in the batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OrderConflict {
+    /// Other orders batch has the same digest, so the order can be minted only separately.
+    DigestCollision,
+    /// The order is an exact duplicate of the order of other operation: it takes the same
+    /// order slot or has identical data. The duplicate can never be minted, as the nonce
+    /// of the order is used by the other operation.
+    Duplicate(String),
+}
+
+impl std::fmt::Display for OrderConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DigestCollision => write!(f, "other orders batch has the same digest"),
+            Self::Duplicate(description) => write!(f, "{description}"),
+        }
+    }
+}
+
+/// Handling of the operation, whose mint orders batch has the same digest as the batch
+/// with other orders. Exact duplicates of the orders of other operations, which take the same
+/// order slot or have identical order data, are always rejected, as they can't be minted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateOrderPolicy {
+    /// The order is sent in a separate transaction, which mints only this order,
+    /// so the operation is tracked by its own transaction.
+    #[default]
+    Isolate,
+    /// The operation is not added to the service and fails with an error.
+    Reject,
+}
+
+/// Default age of the pending batch, after which its operations are pushed to the service
+/// again, e.g. if the batch was sent but its operations were not updated.
+pub const DEFAULT_MAX_PENDING_BATCH_AGE: Duration = Duration::from_secs(30 * 60);
+
 pub trait MintTxHandler {
     fn get_signer(&self) -> BTFResult<impl TransactionSigner>;
     fn get_evm_config(&self) -> SharedConfig;
     fn get_signed_orders(&self, id: OperationId) -> Option<SignedOrders>;
-    fn mint_tx_sent(&self, id: OperationId, tx_hash: H256);
+    /// Updates the operation after the mint transaction is sent. `evm_params_snapshot` is the
+    /// index of the EVM params snapshot, used to send the transaction.
+    fn mint_tx_sent(&self, id: OperationId, tx_hash: H256, evm_params_snapshot: Option<u64>);
+    /// Moves the operation to the confirmation of the mint transaction, sent by the user,
+    /// when the mint transactions sponsorship is disabled. Returns `false`, if the operation
+    /// doesn't support minting by the user, so it waits for the sponsorship to be enabled.
+    fn mint_tx_not_sponsored(&self, _id: OperationId) -> bool {
+        false
+    }
+}
+
+/// Returns the `batchMint` transaction request, which mints only the given order of the batch,
+/// or all the orders of the atomic batch, for the user to send it to the EVM without
+/// the bridge canister.
+pub fn mint_tx_request(config: &ConfigStorage, order: &SignedOrders) -> BTFResult<EvmTxRequest> {
+    let bridge_contract = config
+        .get_btf_bridge_contract()
+        .ok_or(Error::Initialization(
+            "BTF bridge contract is not configured".into(),
+        ))?;
+    let evm_params = config.get_evm_params()?;
+
+    let orders = order.all_orders();
+    let orders_to_process = match order.is_atomic() {
+        true => vec![],
+        false => vec![order.idx() as u32],
+    };
+    let data =
+        btf_events::batch_mint_calldata(&orders.orders_data, &orders.signature, &orders_to_process);
+
+    Ok(EvmTxRequest {
+        to: bridge_contract,
+        chain_id: evm_params.chain_id,
+        data,
+        gas_limit: DEFAULT_TX_GAS_LIMIT.into(),
+        gas_price: evm_params.gas_price,
+    })
 }
 
 /// Service to send mint transaction with signed mint orders batch.
-pub struct SendMintTxService<H> {
+///
+/// Operations of the pending batches are mirrored in the stable memory, so the batches
+/// are restored on the first run after a canister upgrade.
+pub struct SendMintTxService<H, M: Memory = StableMemory> {
     handler: H,
-    orders_to_send: RefCell<HashMap<H256, MintOrderBatchInfo>>,
+    orders_to_send: RefCell<HashMap<PendingMintBatchKey, MintOrderBatchInfo>>,
+    pending_batches: RefCell<PendingMintBatchStore<M>>,
+    restored: Cell<bool>,
+    duplicate_order_policy: DuplicateOrderPolicy,
+    max_pending_batch_age: Duration,
 }
 
-impl<H> SendMintTxService<H> {
-    /// Creates a new service with the given handler.
-    pub fn new(handler: H) -> Self {
+impl<H, M: Memory> SendMintTxService<H, M> {
+    /// Creates a new service with the given handler. Pending batches are stored
+    /// in the given memory.
+    pub fn new(handler: H, memory: M) -> Self {
         Self {
             handler,
             orders_to_send: Default::default(),
+            pending_batches: RefCell::new(PendingMintBatchStore::with_memory(memory)),
+            restored: Cell::new(false),
+            duplicate_order_policy: DuplicateOrderPolicy::default(),
+            max_pending_batch_age: DEFAULT_MAX_PENDING_BATCH_AGE,
+        }
+    }
+
+    /// Sets handling of the orders, conflicting with the orders of other operations.
+    pub fn with_duplicate_order_policy(mut self, policy: DuplicateOrderPolicy) -> Self {
+        self.duplicate_order_policy = policy;
+        self
+    }
+
+    /// Sets the age of the pending batch, after which its operations are pushed again.
+    pub fn with_max_pending_batch_age(mut self, max_age: Duration) -> Self {
+        self.max_pending_batch_age = max_age;
+        self
+    }
+
+    /// Returns the number and age of the pending batches.
+    pub fn pending_batches_stats(&self) -> PendingMintBatchesStats {
+        self.pending_batches
+            .borrow()
+            .stats(ic::time(), self.max_pending_batch_age)
+    }
+}
+
+impl<H: MintTxHandler, M: Memory> SendMintTxService<H, M> {
+    /// Restores the pending batches from the stable memory, taking the signed orders
+    /// from their operations.
+    fn restore_batches(&self) {
+        let batches = self.pending_batches.borrow().batches();
+        let mut orders_to_send = self.orders_to_send.borrow_mut();
+        for (key, batch) in batches {
+            for op_id in batch.operations {
+                let Some(order) = self.handler.get_signed_orders(op_id) else {
+                    log::warn!("Signed order of the pending operation {op_id} not found.");
+                    continue;
+                };
+
+                let idx = order.idx();
+                let orders_batch = order.into_inner();
+                if orders_batch.digest() != key.digest {
+                    log::warn!("Signed orders of the pending operation {op_id} are changed.");
+                    continue;
+                }
+
+                let orders_to_process = match key.isolated {
+                    Some(_) => vec![idx as u32],
+                    None => vec![],
+                };
+                orders_to_send
+                    .entry(key.clone())
+                    .or_insert_with(|| MintOrderBatchInfo::new(orders_batch, orders_to_process))
+                    .related_operations
+                    .insert(idx, op_id);
+            }
+        }
+
+        log::trace!(
+            "Restored {} pending mint orders batches.",
+            orders_to_send.len()
+        );
+    }
+
+    /// Pushes again the operations of the batches, pending longer than the max age.
+    /// Operations, whose signed orders are not available anymore, are dropped.
+    fn requeue_expired_batches(&self) {
+        let expired = self
+            .pending_batches
+            .borrow()
+            .expired(ic::time(), self.max_pending_batch_age);
+        for (key, batch) in expired {
+            log::warn!(
+                "Mint orders batch {} is pending longer than {:?}. Pushing its {} operations again.",
+                key.digest,
+                self.max_pending_batch_age,
+                batch.operations.len()
+            );
+
+            self.orders_to_send.borrow_mut().remove(&key);
+            self.pending_batches.borrow_mut().remove(&key);
+            for op_id in batch.operations {
+                if let Err(e) = self.push_operation(op_id) {
+                    log::warn!("Failed to push operation {op_id} of the expired batch: {e}");
+                }
+            }
+        }
+    }
+
+    /// Hands the queued operations over to the users to send the mint transactions themselves,
+    /// while the sponsorship is disabled. Operations, which don't support it, stay queued.
+    fn fall_back_to_self_submission(&self) {
+        let mut orders_to_send = self.orders_to_send.borrow_mut();
+        let mut pending_batches = self.pending_batches.borrow_mut();
+        orders_to_send.retain(|key, batch_info| {
+            batch_info.related_operations.retain(|_, op_id| {
+                if !self.handler.mint_tx_not_sponsored(*op_id) {
+                    return true;
+                }
+
+                log::info!("Mint transaction of operation {op_id} is left to the user to send.");
+                pending_batches.remove_operation(key, *op_id);
+                false
+            });
+            !batch_info.related_operations.is_empty()
+        });
+
+        if !orders_to_send.is_empty() {
+            log::warn!(
+                "Mint transactions sponsorship is disabled due to low gas balance. Sending of {} mint orders batches is postponed.",
+                orders_to_send.len()
+            );
         }
     }
 }
 
 #[async_trait::async_trait(?Send)]
-impl<H: MintTxHandler> BridgeService for SendMintTxService<H> {
+impl<H: MintTxHandler, M: Memory> BridgeService for SendMintTxService<H, M> {
     async fn run(&self) -> BTFResult<()> {
         log::trace!("Running SendMintTxService");
 
-        let Some((digest, batch_info)) = self
+        if !self.restored.replace(true) {
+            self.restore_batches();
+        }
+        self.requeue_expired_batches();
+
+        let Some((batch_key, batch_info)) = self
             .orders_to_send
             .borrow()
             .iter()
-            .map(|(digest, batch_info)| (digest.clone(), batch_info.clone()))
+            .map(|(batch_key, batch_info)| (batch_key.clone(), batch_info.clone()))
             .next()
         else {
             log::trace!("No mint orders batch ready to be sent.");
@@ -59,9 +315,12 @@ impl<H: MintTxHandler> BridgeService for SendMintTxService<H> {
         };
 
         let config = self.handler.get_evm_config();
+        if !config.borrow().is_mint_tx_sponsorship_enabled() {
+            self.fall_back_to_self_submission();
+            return Ok(());
+        }
 
-        let signer = config.borrow().get_signer()?;
-        let sender = signer.get_address().await?;
+        let sender = ConfigStorage::get_signer_address(config.clone()).await?;
 
         let bridge_contract =
             config
@@ -71,7 +330,27 @@ impl<H: MintTxHandler> BridgeService for SendMintTxService<H> {
                     "Singing service failed to get Btfbridge address".into(),
                 ))?;
 
+        if config
+            .borrow()
+            .should_refresh_evm_params_before_send(ic::time())
+        {
+            // Stale params are still good enough to send the transaction,
+            // so the refresh failure doesn't prevent sending.
+            if let Err(e) = ConfigStorage::refresh_evm_params(config.clone()).await {
+                log::warn!("Failed to refresh EVM params before sending mint transaction: {e}");
+            }
+        }
+
+        if config.borrow().is_nonce_gap_open() {
+            log::warn!(
+                "Transactions sent by the bridge are not visible to the EVM node. Sending of {} mint orders batches is paused until the nonce gap resolves or is acknowledged.",
+                self.orders_to_send.borrow().len()
+            );
+            return Ok(());
+        }
+
         let evm_params = config.borrow().get_evm_params()?;
+        let evm_params_snapshot = config.borrow().get_evm_params_snapshot_index();
         let tx_params = evm_params.create_tx_params(sender, bridge_contract);
 
         log::trace!(
@@ -82,21 +361,31 @@ impl<H: MintTxHandler> BridgeService for SendMintTxService<H> {
             tx_params,
             &batch_info.orders_batch.orders_data,
             &batch_info.orders_batch.signature,
-            &[],
+            &batch_info.orders_to_process,
         );
 
+        let signer = config.borrow().get_signer()?;
         let signature = signer.sign_transaction(&(&tx).into()).await?;
         tx.r = signature.r.0;
         tx.s = signature.s.0;
         tx.v = signature.v.0;
         tx.hash = tx.hash();
 
-        let link = config.borrow().get_evm_link();
-        let client = link.get_json_rpc_client();
-        let tx_hash = client.send_raw_transaction(tx).await.map_err(|e| {
-            log::error!("Failed to send batch mint tx to EVM: {e}");
-            Error::EvmRequestFailed(format!("failed to send batch mint tx to EVM: {e}"))
-        })?;
+        let client = config.borrow().get_json_rpc_client();
+        let relay = config.borrow().get_private_tx_relay();
+        let timeout = config.borrow().get_rpc_request_timeout();
+        let relay_client = relay
+            .as_ref()
+            .map(|relay| relay.link.get_json_rpc_client_with_timeout(timeout));
+        let private_relay = relay_client
+            .as_ref()
+            .zip(relay.as_ref().map(|relay| relay.method.as_str()));
+        let tx_hash = evm_bridge::send_transaction(&client, private_relay, tx)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to send batch mint tx to EVM: {e}");
+                Error::EvmRequestFailed(format!("failed to send batch mint tx to EVM: {e}"))
+            })?;
 
         // Increase nonce after tx sending.
         self.handler
@@ -110,7 +399,8 @@ impl<H: MintTxHandler> BridgeService for SendMintTxService<H> {
         );
 
         // Remove sent orders batch from service.
-        let sent_batch_info = match self.orders_to_send.borrow_mut().remove(&digest) {
+        self.pending_batches.borrow_mut().remove(&batch_key);
+        let sent_batch_info = match self.orders_to_send.borrow_mut().remove(&batch_key) {
             Some(batch_info) => batch_info,
             None => {
                 log::warn!("Failed to remove signed mint orders which was just sent.");
@@ -119,9 +409,14 @@ impl<H: MintTxHandler> BridgeService for SendMintTxService<H> {
         };
 
         // Update state for all operations related with the orders batch.
-        for op_id in sent_batch_info.related_operations {
-            log::trace!("Updating state `mint_tx_sent` for operation {op_id} and tx {tx_hash}.");
-            self.handler.mint_tx_sent(op_id, tx_hash.into())
+        for op_id in sent_batch_info.related_operations.into_values() {
+            OperationSpan::new(op_id, "send_mint_tx").in_scope(|| {
+                log::trace!(
+                    "Updating state `mint_tx_sent` for operation {op_id} and tx {tx_hash}."
+                );
+                self.handler
+                    .mint_tx_sent(op_id, tx_hash.into(), evm_params_snapshot)
+            });
         }
 
         log::trace!("SendMintTxService run finished.");
@@ -137,18 +432,451 @@ impl<H: MintTxHandler> BridgeService for SendMintTxService<H> {
             )));
         };
 
+        let idx = order.idx();
+        let atomic = order.is_atomic();
         let orders_batch = order.into_inner();
         let digest = orders_batch.digest();
-        self.orders_to_send
+
+        let mut orders_to_send = self.orders_to_send.borrow_mut();
+        let shared_key = PendingMintBatchKey {
+            digest,
+            isolated: None,
+        };
+        let conflict = orders_to_send
+            .get(&shared_key)
+            .and_then(|batch_info| batch_info.find_conflict(op_id, idx, &orders_batch));
+
+        // Orders of the atomic batch must be minted together, so they are never isolated.
+        let can_isolate = !atomic && self.duplicate_order_policy == DuplicateOrderPolicy::Isolate;
+        let (batch_key, orders_to_process) = match conflict {
+            None => (shared_key, vec![]),
+            Some(conflict @ OrderConflict::DigestCollision) if can_isolate => {
+                log::warn!("Mint order of operation {op_id} is sent separately: {conflict}.");
+                let isolated_key = PendingMintBatchKey {
+                    isolated: Some(op_id),
+                    ..shared_key
+                };
+                (isolated_key, vec![idx as u32])
+            }
+            Some(conflict) => {
+                log::warn!("Mint order of operation {op_id} is rejected: {conflict}.");
+                return Err(Error::FailedToProgress(format!(
+                    "mint order of operation {op_id} is rejected: {conflict}"
+                )));
+            }
+        };
+
+        self.pending_batches
             .borrow_mut()
-            .entry(digest)
-            .or_insert_with(|| MintOrderBatchInfo {
-                orders_batch,
-                related_operations: HashSet::new(),
-            })
+            .add(batch_key.clone(), op_id, ic::time());
+        orders_to_send
+            .entry(batch_key)
+            .or_insert_with(|| MintOrderBatchInfo::new(orders_batch, orders_to_process))
             .related_operations
-            .insert(op_id);
+            .insert(idx, op_id);
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::order::{MintOrder, SIGNATURE_LEN};
+    use did::H160;
+    use eth_signer::sign_strategy::SigningStrategy;
+    use ic_exports::ic_kit::{inject, MockContext};
+    use ic_stable_structures::{MemoryId, VectorMemory};
+
+    use super::*;
+    use crate::memory::memory_by_id;
+
+    struct TestHandler {
+        orders: HashMap<OperationId, SignedOrders>,
+        self_submitted: RefCell<Vec<OperationId>>,
+    }
+
+    impl MintTxHandler for TestHandler {
+        fn get_signer(&self) -> BTFResult<impl TransactionSigner> {
+            SigningStrategy::Local {
+                private_key: [3; 32],
+            }
+            .make_signer(1)
+            .map_err(|e| Error::Signing(e.to_string()))
+        }
+
+        fn get_evm_config(&self) -> SharedConfig {
+            unreachable!("the service is not run by the tests")
+        }
+
+        fn get_signed_orders(&self, id: OperationId) -> Option<SignedOrders> {
+            self.orders.get(&id).cloned()
+        }
+
+        fn mint_tx_sent(&self, _: OperationId, _: H256, _: Option<u64>) {}
+
+        fn mint_tx_not_sponsored(&self, id: OperationId) -> bool {
+            // Odd operations support minting by the user.
+            if id.as_u64() % 2 == 0 {
+                return false;
+            }
+            self.self_submitted.borrow_mut().push(id);
+            true
+        }
+    }
+
+    type TestService = SendMintTxService<TestHandler, VectorMemory>;
+
+    fn service(orders: Vec<(u64, SignedOrders)>, policy: DuplicateOrderPolicy) -> TestService {
+        service_with_memory(orders, policy, VectorMemory::default())
+    }
+
+    fn service_with_memory(
+        orders: Vec<(u64, SignedOrders)>,
+        policy: DuplicateOrderPolicy,
+        memory: VectorMemory,
+    ) -> TestService {
+        MockContext::new().inject();
+        let handler = TestHandler {
+            orders: orders
+                .into_iter()
+                .map(|(id, order)| (OperationId::new(id), order))
+                .collect(),
+            self_submitted: RefCell::default(),
+        };
+        SendMintTxService::new(handler, memory).with_duplicate_order_policy(policy)
+    }
+
+    /// Returns the queued batches as `(isolated operation, related operations, orders to process)`.
+    fn queued_batches(
+        service: &TestService,
+    ) -> Vec<(Option<OperationId>, Vec<(OrderIdx, OperationId)>, Vec<u32>)> {
+        let mut batches: Vec<_> = service
+            .orders_to_send
+            .borrow()
+            .iter()
+            .map(|(PendingMintBatchKey { isolated, .. }, info)| {
+                let mut related: Vec<_> = info
+                    .related_operations
+                    .iter()
+                    .map(|(idx, id)| (*idx, *id))
+                    .collect();
+                related.sort();
+                (*isolated, related, info.orders_to_process.clone())
+            })
+            .collect();
+        batches.sort();
+        batches
+    }
+
+    /// Batch of two orders with identical data.
+    fn identical_orders(idx: usize) -> SignedOrders {
+        let order: Vec<u8> = (0..MintOrder::ENCODED_DATA_SIZE).map(|i| i as u8).collect();
+        let orders = SignedOrdersData {
+            orders_data: [order.clone(), order].concat(),
+            signature: vec![7; SIGNATURE_LEN],
+        };
+        SignedOrders::new(orders, idx).unwrap()
+    }
+
+    fn signed_orders(idx: usize) -> SignedOrders {
+        let orders = SignedOrdersData {
+            orders_data: (0..3 * MintOrder::ENCODED_DATA_SIZE)
+                .map(|i| i as u8)
+                .collect(),
+            signature: vec![7; SIGNATURE_LEN],
+        };
+        SignedOrders::new(orders, idx).unwrap()
+    }
+
+    /// Queues the batch of other orders under the digest of the `order` batch, as if
+    /// the digests of the batches collide.
+    fn queue_colliding_batch(service: &TestService, order: &SignedOrders, op_id: u64) {
+        let key = PendingMintBatchKey {
+            digest: order.all_orders().digest(),
+            isolated: None,
+        };
+        let mut batch = MintOrderBatchInfo::new(
+            SignedOrdersData {
+                orders_data: vec![42; MintOrder::ENCODED_DATA_SIZE],
+                signature: vec![7; SIGNATURE_LEN],
+            },
+            vec![],
+        );
+        batch.related_operations.insert(0, OperationId::new(op_id));
+        service.orders_to_send.borrow_mut().insert(key, batch);
+    }
+
+    #[test]
+    fn mint_tx_request_contains_batch_mint_call_of_the_order() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        let bridge = H160::from_slice(&[1; 20]);
+        config.set_btf_bridge_contract(bridge.clone());
+        config.update_evm_params(|p| {
+            p.chain_id = 355113;
+            p.gas_price = 42u64.into();
+        });
+
+        let order = signed_orders(1);
+        let request = mint_tx_request(&config, &order).unwrap();
+
+        let params = config
+            .get_evm_params()
+            .unwrap()
+            .create_tx_params(H160::default(), bridge.clone());
+        let tx = btf_events::batch_mint_transaction(
+            params,
+            &order.all_orders().orders_data,
+            &order.all_orders().signature,
+            &[1],
+        );
+        assert_eq!(request.data, tx.input.to_vec());
+        assert_eq!(request.to, bridge);
+        assert_eq!(request.chain_id, 355113);
+        assert_eq!(request.gas_price, 42u64.into());
+        assert_eq!(request.gas_limit, DEFAULT_TX_GAS_LIMIT.into());
+    }
+
+    #[test]
+    fn mint_tx_request_requires_bridge_contract() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        config.update_evm_params(|p| p.chain_id = 1);
+
+        let result = mint_tx_request(&config, &signed_orders(0));
+        assert!(matches!(result, Err(Error::Initialization(_))));
+    }
+
+    #[test]
+    fn distinct_orders_of_the_batch_are_sent_together() {
+        let service = service(
+            (0..3).map(|idx| (idx as u64, signed_orders(idx))).collect(),
+            DuplicateOrderPolicy::Reject,
+        );
+        for id in 0..3 {
+            service.push_operation(OperationId::new(id)).unwrap();
+        }
+        // Pushing the operation again doesn't create a conflict with itself.
+        service.push_operation(OperationId::new(1)).unwrap();
+
+        let related = (0..3)
+            .map(|idx| (idx, OperationId::new(idx as u64)))
+            .collect();
+        assert_eq!(queued_batches(&service), vec![(None, related, vec![])]);
+    }
+
+    #[test]
+    fn identical_order_is_rejected_by_isolate_policy() {
+        let service = service(
+            vec![(1, identical_orders(0)), (2, identical_orders(1))],
+            DuplicateOrderPolicy::Isolate,
+        );
+        service.push_operation(OperationId::new(1)).unwrap();
+
+        // The duplicate has the nonce of the order of operation 1, so it can't be minted.
+        let result = service.push_operation(OperationId::new(2));
+        assert!(matches!(result, Err(Error::FailedToProgress(_))));
+        assert_eq!(
+            queued_batches(&service),
+            vec![(None, vec![(0, OperationId::new(1))], vec![])]
+        );
+        assert_eq!(service.pending_batches_stats().operations, 1);
+    }
+
+    #[test]
+    fn identical_order_is_rejected_by_reject_policy() {
+        let service = service(
+            vec![(1, identical_orders(0)), (2, identical_orders(1))],
+            DuplicateOrderPolicy::Reject,
+        );
+        service.push_operation(OperationId::new(1)).unwrap();
+
+        let result = service.push_operation(OperationId::new(2));
+        assert!(matches!(result, Err(Error::FailedToProgress(_))));
+        assert_eq!(
+            queued_batches(&service),
+            vec![(None, vec![(0, OperationId::new(1))], vec![])]
+        );
+    }
+
+    #[test]
+    fn taken_order_slot_is_not_shared() {
+        let service = service(
+            vec![(1, signed_orders(0)), (2, signed_orders(0))],
+            DuplicateOrderPolicy::Isolate,
+        );
+        service.push_operation(OperationId::new(1)).unwrap();
+
+        let result = service.push_operation(OperationId::new(2));
+        assert!(matches!(result, Err(Error::FailedToProgress(_))));
+        assert_eq!(
+            queued_batches(&service),
+            vec![(None, vec![(0, OperationId::new(1))], vec![])]
+        );
+    }
+
+    #[test]
+    fn order_of_colliding_batch_is_isolated() {
+        let isolating = service(vec![(2, signed_orders(1))], DuplicateOrderPolicy::Isolate);
+        queue_colliding_batch(&isolating, &signed_orders(1), 1);
+        isolating.push_operation(OperationId::new(2)).unwrap();
+
+        assert_eq!(
+            queued_batches(&isolating),
+            vec![
+                (None, vec![(0, OperationId::new(1))], vec![]),
+                (
+                    Some(OperationId::new(2)),
+                    vec![(1, OperationId::new(2))],
+                    vec![1]
+                ),
+            ]
+        );
+
+        let rejecting = service(vec![(2, signed_orders(1))], DuplicateOrderPolicy::Reject);
+        queue_colliding_batch(&rejecting, &signed_orders(1), 1);
+        let result = rejecting.push_operation(OperationId::new(2));
+        assert!(matches!(result, Err(Error::FailedToProgress(_))));
+    }
+
+    #[test]
+    fn atomic_batch_order_is_never_isolated() {
+        let service = service(
+            vec![
+                (1, signed_orders(0).with_atomic(true)),
+                (2, signed_orders(0).with_atomic(true)),
+            ],
+            DuplicateOrderPolicy::Isolate,
+        );
+        service.push_operation(OperationId::new(1)).unwrap();
+
+        let result = service.push_operation(OperationId::new(2));
+        assert!(matches!(result, Err(Error::FailedToProgress(_))));
+        assert_eq!(
+            queued_batches(&service),
+            vec![(None, vec![(0, OperationId::new(1))], vec![])]
+        );
+    }
+
+    #[test]
+    fn mint_tx_request_of_atomic_batch_mints_all_orders() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        config.set_btf_bridge_contract(H160::from_slice(&[1; 20]));
+        config.update_evm_params(|p| p.chain_id = 1);
+
+        let order = signed_orders(1).with_atomic(true);
+        let request = mint_tx_request(&config, &order).unwrap();
+
+        let orders = order.all_orders();
+        let data = btf_events::batch_mint_calldata(&orders.orders_data, &orders.signature, &[]);
+        assert_eq!(request.data, data);
+    }
+
+    #[test]
+    fn pending_batches_survive_upgrade() {
+        let memory = VectorMemory::default();
+        let orders = vec![
+            (1, signed_orders(0)),
+            (2, identical_orders(1)),
+            (3, signed_orders(2)),
+        ];
+        let service = service_with_memory(
+            orders.clone(),
+            DuplicateOrderPolicy::Isolate,
+            memory.clone(),
+        );
+        queue_colliding_batch(&service, &identical_orders(1), 4);
+        for id in 1..=3 {
+            service.push_operation(OperationId::new(id)).unwrap();
+        }
+        // The colliding batch is not pending, so it is not restored.
+        let colliding_key = PendingMintBatchKey {
+            digest: identical_orders(1).all_orders().digest(),
+            isolated: None,
+        };
+        service.orders_to_send.borrow_mut().remove(&colliding_key);
+        let before_upgrade = queued_batches(&service);
+        assert_eq!(before_upgrade.len(), 2);
+        drop(service);
+
+        // The service is created again after the upgrade, with the heap state lost.
+        let service = service_with_memory(orders, DuplicateOrderPolicy::Isolate, memory);
+        assert!(queued_batches(&service).is_empty());
+        assert_eq!(service.pending_batches_stats().operations, 3);
+
+        service.restore_batches();
+        assert_eq!(queued_batches(&service), before_upgrade);
+    }
+
+    #[test]
+    fn expired_batch_operations_are_pushed_again() {
+        let mut service = service(
+            vec![(1, signed_orders(0)), (2, signed_orders(1))],
+            DuplicateOrderPolicy::Reject,
+        )
+        .with_max_pending_batch_age(Duration::from_secs(60));
+        service.push_operation(OperationId::new(1)).unwrap();
+        service.push_operation(OperationId::new(2)).unwrap();
+
+        // The batch is not expired yet.
+        inject::get_context().add_time(Duration::from_secs(60).as_nanos() as u64);
+        service.requeue_expired_batches();
+        assert_eq!(
+            service.pending_batches_stats().oldest_batch_age_secs,
+            Some(60)
+        );
+
+        // The operation 2 progressed, so its order is not available anymore.
+        inject::get_context().add_time(Duration::from_secs(1).as_nanos() as u64);
+        service.handler.orders.remove(&OperationId::new(2));
+        assert_eq!(
+            service.pending_batches_stats().oldest_batch_age_secs,
+            Some(61)
+        );
+        service.requeue_expired_batches();
+
+        assert_eq!(
+            queued_batches(&service),
+            vec![(None, vec![(0, OperationId::new(1))], vec![])]
+        );
+        assert_eq!(
+            service.pending_batches_stats(),
+            PendingMintBatchesStats {
+                batches: 1,
+                operations: 1,
+                oldest_batch_age_secs: Some(0),
+                max_batch_age_secs: 60,
+            }
+        );
+    }
+
+    #[test]
+    fn not_sponsored_operations_are_left_to_users() {
+        let service = service(
+            vec![
+                (1, signed_orders(0)),
+                (2, signed_orders(1)),
+                (3, signed_orders(2)),
+            ],
+            DuplicateOrderPolicy::Reject,
+        );
+        for id in 1..=3 {
+            service.push_operation(OperationId::new(id)).unwrap();
+        }
+
+        service.fall_back_to_self_submission();
+
+        let mut self_submitted = service.handler.self_submitted.take();
+        self_submitted.sort();
+        assert_eq!(
+            self_submitted,
+            vec![OperationId::new(1), OperationId::new(3)]
+        );
+        assert_eq!(
+            queued_batches(&service),
+            vec![(None, vec![(1, OperationId::new(2))], vec![])]
+        );
+        assert_eq!(service.pending_batches_stats().operations, 1);
+    }
+}
@@ -0,0 +1,323 @@
+use std::time::Duration;
+
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::op_id::OperationId;
+use bridge_did::outbox::{OperationNotification, OutboxDestination, OutboxMessage};
+use bridge_utils::http_transform;
+use candid::{Encode, Nat, Principal};
+use ic_exports::ic_cdk::api::call::call_raw;
+use ic_exports::ic_cdk::api::management_canister::http_request::{
+    http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, TransformContext,
+};
+use ic_exports::ic_kit::ic;
+
+use super::BridgeService;
+use crate::bridge::Operation;
+use crate::runtime::RuntimeState;
+
+/// Delay between the outbox dispatches.
+pub const OUTBOX_DISPATCH_DELAY: Duration = Duration::from_secs(10);
+
+/// Max number of the messages delivered during a single dispatch.
+const OUTBOX_DISPATCH_BATCH_SIZE: usize = 20;
+
+const CYCLES_PER_WEBHOOK_REQUEST: u128 = 500_000_000;
+const WEBHOOK_MAX_RESPONSE_BYTES: u64 = 2_000;
+
+/// Delivers the outbox messages to their destinations.
+#[async_trait::async_trait(?Send)]
+pub trait DeliveryAdapter {
+    async fn deliver(&self, message: &OutboxMessage) -> Result<(), String>;
+}
+
+/// Delivers the outbox messages with the adapter of their destination kind.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IcDelivery;
+
+#[async_trait::async_trait(?Send)]
+impl DeliveryAdapter for IcDelivery {
+    async fn deliver(&self, message: &OutboxMessage) -> Result<(), String> {
+        match &message.destination {
+            OutboxDestination::Canister {
+                canister_id,
+                method,
+            } => notify_canister(*canister_id, method, &message.payload).await,
+            OutboxDestination::Webhook { url } => post_webhook(url, &message.payload).await,
+        }
+    }
+}
+
+/// Calls the canister method with the candid encoded notification.
+async fn notify_canister(
+    canister_id: Principal,
+    method: &str,
+    notification: &OperationNotification,
+) -> Result<(), String> {
+    let args = Encode!(notification).map_err(|e| format!("failed to encode notification: {e}"))?;
+    call_raw(canister_id, method, args, 0)
+        .await
+        .map(|_| ())
+        .map_err(|(code, msg)| format!("canister call rejected with {code:?}: {msg}"))
+}
+
+/// Posts the JSON encoded notification to the webhook URL.
+async fn post_webhook(url: &str, notification: &OperationNotification) -> Result<(), String> {
+    let body = serde_json::to_vec(notification)
+        .map_err(|e| format!("failed to encode notification: {e}"))?;
+    let request = CanisterHttpRequestArgument {
+        url: url.to_string(),
+        max_response_bytes: Some(WEBHOOK_MAX_RESPONSE_BYTES),
+        method: HttpMethod::POST,
+        headers: vec![HttpHeader {
+            name: "Content-Type".to_string(),
+            value: "application/json".to_string(),
+        }],
+        body: Some(body),
+        transform: Some(TransformContext::from_name(
+            http_transform::TRANSFORM_WEBHOOK_RESPONSE.to_string(),
+            vec![],
+        )),
+    };
+
+    let (response,) = http_request(request, CYCLES_PER_WEBHOOK_REQUEST)
+        .await
+        .map_err(|(code, msg)| format!("webhook request rejected with {code:?}: {msg}"))?;
+
+    if !(Nat::from(200u32)..Nat::from(300u32)).contains(&response.status) {
+        return Err(format!("webhook responded with status {}", response.status));
+    }
+
+    Ok(())
+}
+
+/// Service to deliver the messages from the outbox of the operation store.
+///
+/// A message is removed from the outbox only after the successful delivery, so it is delivered
+/// at least once. Failed deliveries are retried with exponential backoff, and the messages
+/// failed too many times are parked.
+pub struct DispatchOutboxService<Op: Operation, D: DeliveryAdapter = IcDelivery> {
+    state: RuntimeState<Op>,
+    delivery: D,
+}
+
+impl<Op: Operation> DispatchOutboxService<Op> {
+    pub fn new(state: RuntimeState<Op>) -> Self {
+        Self::with_delivery(state, IcDelivery)
+    }
+}
+
+impl<Op: Operation, D: DeliveryAdapter> DispatchOutboxService<Op, D> {
+    pub fn with_delivery(state: RuntimeState<Op>, delivery: D) -> Self {
+        Self { state, delivery }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<Op: Operation, D: DeliveryAdapter> BridgeService for DispatchOutboxService<Op, D> {
+    async fn run(&self) -> BTFResult<()> {
+        let due = self
+            .state
+            .borrow()
+            .operations
+            .outbox()
+            .due(ic::time(), OUTBOX_DISPATCH_BATCH_SIZE);
+
+        for message in due {
+            let id = message.id;
+            let Some(message) = self
+                .state
+                .borrow_mut()
+                .operations
+                .outbox_mut()
+                .start_attempt(id, ic::time())
+            else {
+                continue;
+            };
+
+            let result = self.delivery.deliver(&message).await;
+
+            let mut state = self.state.borrow_mut();
+            let outbox = state.operations.outbox_mut();
+            match result {
+                Ok(()) => {
+                    outbox.delivered(id);
+                    log::debug!(
+                        "Outbox message {id} is delivered to {:?}",
+                        message.destination
+                    );
+                }
+                Err(e) => {
+                    let attempts = message.attempts;
+                    if outbox.failed(id, e.clone()) {
+                        log::error!(
+                            "Outbox message {id} is parked after {attempts} failed attempts: {e}"
+                        );
+                    } else {
+                        log::warn!(
+                            "Failed to deliver outbox message {id}, attempt {attempts}: {e}"
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn push_operation(&self, _: OperationId) -> BTFResult<()> {
+        let msg = "Operations should not be pushed to the DispatchOutboxService service";
+        log::warn!("{msg}");
+        Err(Error::FailedToProgress(msg.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    use bridge_did::outbox::OutboxDestination;
+    use candid::CandidType;
+    use did::H160;
+    use ic_exports::ic_kit::MockContext;
+    use ic_stable_structures::MemoryId;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::bridge::OperationProgress;
+    use crate::memory::memory_by_id;
+    use crate::outbox::{backoff, OUTBOX_MAX_ATTEMPTS};
+    use crate::runtime::default_state;
+    use crate::runtime::state::config::ConfigStorage;
+
+    #[derive(Clone, Deserialize, Debug, Serialize, CandidType)]
+    struct TestOp;
+
+    impl Operation for TestOp {
+        async fn progress(
+            self,
+            _id: OperationId,
+            _ctx: RuntimeState<Self>,
+        ) -> BTFResult<OperationProgress<Self>> {
+            unimplemented!()
+        }
+
+        fn is_complete(&self) -> bool {
+            true
+        }
+
+        fn evm_wallet_address(&self) -> H160 {
+            H160::default()
+        }
+    }
+
+    /// Returns the queued results and records the delivered messages.
+    #[derive(Default, Clone)]
+    struct MockDelivery {
+        results: Rc<RefCell<VecDeque<Result<(), String>>>>,
+        delivered: Rc<RefCell<Vec<u64>>>,
+    }
+
+    #[async_trait::async_trait(?Send)]
+    impl DeliveryAdapter for MockDelivery {
+        async fn deliver(&self, message: &OutboxMessage) -> Result<(), String> {
+            let result = self.results.borrow_mut().pop_front().unwrap_or(Ok(()));
+            if result.is_ok() {
+                self.delivered.borrow_mut().push(message.id);
+            }
+            result
+        }
+    }
+
+    fn test_state() -> RuntimeState<TestOp> {
+        let config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        let state = default_state(Rc::new(RefCell::new(config)));
+        state
+            .borrow()
+            .config
+            .borrow_mut()
+            .set_notification_destinations(vec![OutboxDestination::Webhook {
+                url: "https://partner.example/hook".into(),
+            }]);
+        state.borrow_mut().apply_notification_destinations();
+        state
+    }
+
+    #[tokio::test]
+    async fn complete_operation_is_delivered() {
+        MockContext::new().inject();
+        let state = test_state();
+        let op_id = state.borrow_mut().operations.new_operation(TestOp, None);
+
+        let delivery = MockDelivery::default();
+        let service = DispatchOutboxService::with_delivery(state.clone(), delivery.clone());
+        service.run().await.unwrap();
+
+        assert_eq!(*delivery.delivered.borrow(), vec![0]);
+        let state = state.borrow();
+        assert!(state.operations.outbox().get_pending(0, 10).is_empty());
+        assert!(state.operations.get_log(op_id).is_some());
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_is_retried_after_backoff() {
+        let ctx = MockContext::new().inject();
+        let state = test_state();
+        state.borrow_mut().operations.new_operation(TestOp, None);
+
+        let delivery = MockDelivery::default();
+        delivery
+            .results
+            .borrow_mut()
+            .push_back(Err("unavailable".into()));
+        let service = DispatchOutboxService::with_delivery(state.clone(), delivery.clone());
+
+        service.run().await.unwrap();
+        assert!(delivery.delivered.borrow().is_empty());
+
+        // The message is not retried before the backoff delay.
+        service.run().await.unwrap();
+        assert!(delivery.delivered.borrow().is_empty());
+
+        ctx.add_time(backoff(1).as_nanos() as u64);
+        service.run().await.unwrap();
+        assert_eq!(*delivery.delivered.borrow(), vec![0]);
+    }
+
+    #[tokio::test]
+    async fn message_is_parked_after_max_attempts() {
+        let ctx = MockContext::new().inject();
+        let state = test_state();
+        state.borrow_mut().operations.new_operation(TestOp, None);
+
+        let delivery = MockDelivery::default();
+        for _ in 0..OUTBOX_MAX_ATTEMPTS {
+            delivery
+                .results
+                .borrow_mut()
+                .push_back(Err("unavailable".into()));
+        }
+        let service = DispatchOutboxService::with_delivery(state.clone(), delivery.clone());
+
+        for attempt in 1..=OUTBOX_MAX_ATTEMPTS {
+            service.run().await.unwrap();
+            ctx.add_time(backoff(attempt).as_nanos() as u64);
+        }
+        service.run().await.unwrap();
+
+        assert!(delivery.delivered.borrow().is_empty());
+        let parked = state.borrow().operations.outbox().get_parked(0, 10);
+        assert_eq!(parked.len(), 1);
+        assert_eq!(parked[0].attempts, OUTBOX_MAX_ATTEMPTS);
+
+        state
+            .borrow_mut()
+            .operations
+            .outbox_mut()
+            .requeue(parked[0].id, ic::time())
+            .unwrap();
+        service.run().await.unwrap();
+        assert_eq!(*delivery.delivered.borrow(), vec![parked[0].id]);
+    }
+}
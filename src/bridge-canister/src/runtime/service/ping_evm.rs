@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::op_id::OperationId;
+use bridge_utils::query::{self, QueryType};
+use ic_exports::ic_kit::ic;
+
+use super::BridgeService;
+use crate::runtime::state::SharedConfig;
+
+/// Delay between the EVM node pings.
+pub const PING_EVM_DELAY: Duration = Duration::from_secs(30);
+
+/// Service to measure the round-trip time of the EVM node requests.
+///
+/// Requests the latest block number and records the latency of the request.
+pub struct PingEvmService {
+    config: SharedConfig,
+}
+
+impl PingEvmService {
+    pub fn new(config: SharedConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl BridgeService for PingEvmService {
+    async fn run(&self) -> BTFResult<()> {
+        let client = self.config.borrow().get_json_rpc_client();
+
+        let start = ic::time();
+        query::batch_query(&client, &[QueryType::LatestBlock])
+            .await
+            .map_err(|e| Error::EvmRequestFailed(format!("failed to ping EVM node: {e}")))?;
+        let latency = ic::time().saturating_sub(start);
+
+        self.config.borrow_mut().record_evm_latency(start, latency);
+
+        Ok(())
+    }
+
+    fn push_operation(&self, _: OperationId) -> BTFResult<()> {
+        let msg = "Operations should not be pushed to the PingEvmService service";
+        log::warn!("{msg}");
+        Err(Error::FailedToProgress(msg.into()))
+    }
+}
@@ -1,13 +1,17 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
 use bridge_did::error::{BTFResult, Error};
 use bridge_did::op_id::OperationId;
-use bridge_did::order::{MintOrder, SignedOrders, SignedOrdersData};
+use bridge_did::order::{MintOrder, SignedOrders, SignedOrdersData, SIGNATURE_LEN};
+use bridge_utils::btf_events;
 use did::keccak;
 use eth_signer::sign_strategy::TransactionSigner;
+use ic_exports::ic_kit::ic;
 
 use super::BridgeService;
+use crate::log_span::OperationSpan;
 
 pub trait MintOrderHandler {
     /// Get signer to sign mint orders batch.
@@ -18,14 +22,27 @@ pub trait MintOrderHandler {
 
     /// Set signed mint orders data to the given operation.
     fn set_signed_order(&self, id: OperationId, signed: SignedOrders);
+
+    /// Returns the operations, which mint orders must be minted atomically with the order of
+    /// the given operation, including the operation itself. Such orders are signed only when
+    /// all of them are pushed to the service, and are never split across batches.
+    fn get_atomic_group(&self, _id: OperationId) -> Option<Vec<OperationId>> {
+        None
+    }
 }
 
 pub const MAX_MINT_ORDERS_IN_BATCH: usize = 16;
 
+/// Default limit of the `batchMint` transaction calldata size in bytes.
+/// Ethereum nodes reject transactions larger than 128 KiB, so the limit leaves room
+/// for the rest of the transaction.
+pub const DEFAULT_MAX_BATCH_CALLDATA_SIZE: usize = 64 * 1024;
+
 /// Service to sign mint order batches.
 pub struct SignMintOrdersService<H: MintOrderHandler> {
     order_handler: H,
     orders: RefCell<HashMap<OperationId, MintOrder>>,
+    max_batch_calldata_size: usize,
 }
 
 impl<H: MintOrderHandler> SignMintOrdersService<H> {
@@ -34,10 +51,36 @@ impl<H: MintOrderHandler> SignMintOrdersService<H> {
         Self {
             order_handler,
             orders: Default::default(),
+            max_batch_calldata_size: DEFAULT_MAX_BATCH_CALLDATA_SIZE,
+        }
+    }
+
+    /// Sets the limit of the `batchMint` transaction calldata size in bytes. Orders which
+    /// don't fit into the limit are signed in the next batches. An order or an atomic group
+    /// which doesn't fit into the limit alone is signed in a batch of its own.
+    pub fn with_max_batch_calldata_size(mut self, size: usize) -> Self {
+        self.max_batch_calldata_size = size;
+        self
+    }
+
+    /// Removes the waiting orders of the atomic group, which operations don't have
+    /// a mint order to sign anymore.
+    fn remove_stale_orders(&self, group: &[OperationId]) {
+        let mut orders = self.orders.borrow_mut();
+        for id in group {
+            if orders.contains_key(id) && self.order_handler.get_order(*id).is_none() {
+                log::trace!("Mint order {id} is removed: the operation has no order to sign.");
+                orders.remove(id);
+            }
         }
     }
 }
 
+/// Size of the `batchMint` transaction calldata, which sends the orders of the given size.
+pub fn batch_mint_calldata_size(orders_data_len: usize) -> usize {
+    btf_events::batch_mint_calldata(&vec![0; orders_data_len], &[0; SIGNATURE_LEN], &[]).len()
+}
+
 #[async_trait::async_trait(?Send)]
 impl<H: MintOrderHandler> BridgeService for SignMintOrdersService<H> {
     fn push_operation(&self, id: OperationId) -> BTFResult<()> {
@@ -53,26 +96,80 @@ impl<H: MintOrderHandler> BridgeService for SignMintOrdersService<H> {
     async fn run(&self) -> BTFResult<()> {
         log::trace!("Running SignMintOrdersService");
 
-        let orders_number = self.orders.borrow().len().min(MAX_MINT_ORDERS_IN_BATCH);
-        if orders_number == 0 {
+        if self.orders.borrow().is_empty() {
             log::trace!("No mint orders to sign.");
             return Ok(());
         }
 
-        log::trace!("Singing batch of {orders_number} mint orders.");
+        let mut pending: Vec<OperationId> = self.orders.borrow().keys().copied().collect();
+        pending.sort();
+
+        // Orders are signed by units: a single order or a complete atomic group.
+        let mut units: Vec<(bool, Vec<OperationId>)> = vec![];
+        let mut grouped = HashSet::new();
+        for id in pending {
+            if grouped.contains(&id) {
+                continue;
+            }
+
+            let Some(group) = self.order_handler.get_atomic_group(id) else {
+                units.push((false, vec![id]));
+                continue;
+            };
+
+            grouped.extend(group.iter().copied());
+            if group.iter().all(|id| self.orders.borrow().contains_key(id)) {
+                units.push((true, group));
+            } else {
+                log::trace!("Mint order {id} waits for the orders of its atomic group.");
+                self.remove_stale_orders(&group);
+            }
+        }
+
+        if units.is_empty() {
+            log::trace!("No mint orders ready to be signed.");
+            return Ok(());
+        }
+
+        // The source tokens are already burnt, so orders with expired permits are
+        // signed without them, instead of being rejected.
+        let now_secs = Duration::from_nanos(ic::time()).as_secs();
 
-        let order_ops: Vec<(OperationId, MintOrder)> = self
-            .orders
-            .borrow()
-            .iter()
-            .map(|(id, order)| (*id, order.clone()))
-            .collect();
+        // Units which don't fit into the orders number or the calldata size limits are left
+        // for the next batches. Atomic groups are never split.
+        let mut order_ops: Vec<OperationId> = vec![];
+        let mut orders_data =
+            Vec::with_capacity(MAX_MINT_ORDERS_IN_BATCH * MintOrder::ENCODED_DATA_SIZE);
+        let mut atomic = false;
+        for (is_atomic, unit) in units {
+            let mut unit_data = Vec::with_capacity(unit.len() * MintOrder::ENCODED_DATA_SIZE);
+            for id in &unit {
+                let mut order = self.orders.borrow()[id].clone();
+                if let Some(permit) = order.remove_expired_permit(now_secs) {
+                    log::warn!(
+                        "permit of the mint order {id} expired at {}, signing without it",
+                        permit.deadline
+                    );
+                }
+                unit_data.extend_from_slice(&order.encode());
+            }
 
-        let mut orders_data = Vec::with_capacity(orders_number * MintOrder::ENCODED_DATA_SIZE);
-        for order_op in &order_ops {
-            let encoded_order = order_op.1.encode();
-            orders_data.extend_from_slice(&encoded_order);
+            let orders_number = order_ops.len() + unit.len();
+            let calldata_size = batch_mint_calldata_size(orders_data.len() + unit_data.len());
+            if !order_ops.is_empty()
+                && (orders_number > MAX_MINT_ORDERS_IN_BATCH
+                    || calldata_size > self.max_batch_calldata_size)
+            {
+                break;
+            }
+
+            orders_data.extend_from_slice(&unit_data);
+            order_ops.extend(unit);
+            atomic |= is_atomic;
         }
+        let orders_number = order_ops.len();
+
+        log::trace!("Singing batch of {orders_number} mint orders.");
 
         let signer = self.order_handler.get_signer()?;
         let digest = keccak::keccak_hash(&orders_data);
@@ -85,12 +182,13 @@ impl<H: MintOrderHandler> BridgeService for SignMintOrdersService<H> {
             signature: signature_bytes.to_vec(),
         };
 
-        for (idx, order_op) in order_ops.into_iter().enumerate() {
-            self.orders.borrow_mut().remove(&order_op.0);
+        for (idx, op_id) in order_ops.into_iter().enumerate() {
+            self.orders.borrow_mut().remove(&op_id);
             let signed_order = SignedOrders::new(signed_orders.clone(), idx)
-                .expect("index inside the signed orders list");
-            self.order_handler
-                .set_signed_order(order_op.0, signed_order);
+                .expect("index inside the signed orders list")
+                .with_atomic(atomic);
+            OperationSpan::new(op_id, "sign_mint_order")
+                .in_scope(|| self.order_handler.set_signed_order(op_id, signed_order));
         }
 
         log::trace!("Operations updated for batch of {orders_number} mint orders");
@@ -100,3 +198,195 @@ impl<H: MintOrderHandler> BridgeService for SignMintOrdersService<H> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::id256::Id256;
+    use candid::Principal;
+    use did::{H160, H256, U256};
+    use eth_signer::sign_strategy::SigningStrategy;
+    use ic_exports::ic_kit::MockContext;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct TestHandler {
+        orders: HashMap<OperationId, MintOrder>,
+        signed: RefCell<Vec<(OperationId, SignedOrders)>>,
+        groups: HashMap<OperationId, Vec<OperationId>>,
+    }
+
+    impl MintOrderHandler for TestHandler {
+        fn get_signer(&self) -> BTFResult<impl TransactionSigner> {
+            SigningStrategy::Local {
+                private_key: [3; 32],
+            }
+            .make_signer(1)
+            .map_err(|e| Error::Signing(e.to_string()))
+        }
+
+        fn get_order(&self, id: OperationId) -> Option<MintOrder> {
+            self.orders.get(&id).cloned()
+        }
+
+        fn set_signed_order(&self, id: OperationId, signed: SignedOrders) {
+            self.signed.borrow_mut().push((id, signed));
+        }
+
+        fn get_atomic_group(&self, id: OperationId) -> Option<Vec<OperationId>> {
+            self.groups.get(&id).cloned()
+        }
+    }
+
+    fn order(nonce: u32) -> MintOrder {
+        MintOrder {
+            amount: U256::from(1000u64),
+            sender: Id256::from(&Principal::from_slice(&[1; 29])),
+            src_token: Id256::from(&Principal::from_slice(&[2; 29])),
+            recipient: H160::from_slice(&[3; 20]),
+            dst_token: H160::from_slice(&[4; 20]),
+            nonce,
+            sender_chain_id: 0,
+            recipient_chain_id: 1,
+            name: [5; 32],
+            symbol: [6; 16],
+            decimals: 18,
+            approve_spender: H160::default(),
+            approve_amount: U256::zero(),
+            fee_payer: H160::default(),
+            permit: None,
+        }
+    }
+
+    fn service(orders_number: u64) -> SignMintOrdersService<TestHandler> {
+        let handler = TestHandler {
+            orders: (0..orders_number)
+                .map(|nonce| (OperationId::new(nonce), order(nonce as _)))
+                .collect(),
+            ..Default::default()
+        };
+        let service = SignMintOrdersService::new(handler);
+        for nonce in 0..orders_number {
+            service.push_operation(OperationId::new(nonce)).unwrap();
+        }
+
+        service
+    }
+
+    fn ids(range: std::ops::Range<u64>) -> Vec<OperationId> {
+        range.map(OperationId::new).collect()
+    }
+
+    fn set_atomic_group(service: &mut SignMintOrdersService<TestHandler>, group: Vec<OperationId>) {
+        for id in &group {
+            service.order_handler.groups.insert(*id, group.clone());
+        }
+    }
+
+    fn signed_batches(service: &SignMintOrdersService<TestHandler>) -> Vec<Vec<OperationId>> {
+        let signed = service.order_handler.signed.borrow();
+        let mut batches: Vec<(H256, Vec<OperationId>)> = vec![];
+        for (id, order) in signed.iter() {
+            let digest = order.all_orders().digest();
+            match batches.iter_mut().find(|(batch, _)| *batch == digest) {
+                Some((_, ids)) => ids.push(*id),
+                None => batches.push((digest, vec![*id])),
+            }
+        }
+
+        batches.into_iter().map(|(_, ids)| ids).collect()
+    }
+
+    #[tokio::test]
+    async fn should_split_batch_at_calldata_size_limit() {
+        MockContext::new().inject();
+        let limit = batch_mint_calldata_size(3 * MintOrder::ENCODED_DATA_SIZE);
+        let service = service(5).with_max_batch_calldata_size(limit);
+
+        service.run().await.unwrap();
+        assert_eq!(service.orders.borrow().len(), 2);
+        service.run().await.unwrap();
+        assert!(service.orders.borrow().is_empty());
+
+        assert_eq!(signed_batches(&service), vec![ids(0..3), ids(3..5)]);
+    }
+
+    #[tokio::test]
+    async fn limit_one_byte_below_the_batch_size_moves_last_order_to_next_batch() {
+        MockContext::new().inject();
+        let limit = batch_mint_calldata_size(2 * MintOrder::ENCODED_DATA_SIZE) - 1;
+        let service = service(2).with_max_batch_calldata_size(limit);
+
+        service.run().await.unwrap();
+        service.run().await.unwrap();
+
+        assert_eq!(
+            signed_batches(&service),
+            vec![vec![OperationId::new(0)], vec![OperationId::new(1)]]
+        );
+    }
+
+    #[tokio::test]
+    async fn order_exceeding_the_limit_is_signed_alone() {
+        MockContext::new().inject();
+        let service = service(2).with_max_batch_calldata_size(0);
+
+        service.run().await.unwrap();
+        assert_eq!(signed_batches(&service), vec![vec![OperationId::new(0)]]);
+    }
+
+    #[tokio::test]
+    async fn batch_is_limited_by_orders_number() {
+        MockContext::new().inject();
+        let service = service(MAX_MINT_ORDERS_IN_BATCH as u64 + 1);
+
+        service.run().await.unwrap();
+        assert_eq!(signed_batches(&service)[0].len(), MAX_MINT_ORDERS_IN_BATCH);
+        assert_eq!(service.orders.borrow().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn atomic_group_is_not_split_between_batches() {
+        MockContext::new().inject();
+        let mut service = service(MAX_MINT_ORDERS_IN_BATCH as u64 + 2);
+        let first_batch_len = MAX_MINT_ORDERS_IN_BATCH as u64 - 2;
+        set_atomic_group(&mut service, ids(first_batch_len..first_batch_len + 4));
+
+        service.run().await.unwrap();
+        service.run().await.unwrap();
+        assert!(service.orders.borrow().is_empty());
+
+        assert_eq!(
+            signed_batches(&service),
+            vec![
+                ids(0..first_batch_len),
+                ids(first_batch_len..first_batch_len + 4)
+            ]
+        );
+        for (id, signed) in service.order_handler.signed.borrow().iter() {
+            assert_eq!(signed.is_atomic(), id.as_u64() >= first_batch_len);
+        }
+    }
+
+    #[tokio::test]
+    async fn atomic_group_waits_for_all_its_orders() {
+        MockContext::new().inject();
+        let mut service = service(3);
+        set_atomic_group(
+            &mut service,
+            vec![
+                OperationId::new(1),
+                OperationId::new(2),
+                OperationId::new(5),
+            ],
+        );
+
+        service.run().await.unwrap();
+        assert_eq!(signed_batches(&service), vec![ids(0..1)]);
+        assert_eq!(service.orders.borrow().len(), 2);
+
+        service.run().await.unwrap();
+        assert_eq!(signed_batches(&service).len(), 1);
+        assert_eq!(service.orders.borrow().len(), 2);
+    }
+}
@@ -1,22 +1,52 @@
+pub mod concurrency;
 pub mod config;
+pub mod evm_latency;
+pub mod evm_params_history;
+pub mod gas_balance;
+pub mod nonce_drift;
 
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::time::Duration;
 
+use bridge_did::bridge_mode::ReplicaSyncStatus;
 use bridge_did::error::{BTFResult, Error};
+use bridge_did::event_data::BurntEventData;
+use bridge_did::event_filter::EventTopicFilter;
+use bridge_did::evm_finality::EvmFinality;
 use bridge_did::evm_link::EvmLink;
-use bridge_did::op_id::OperationId;
+use bridge_did::fee_adjustment::FeeAdjustment;
+use bridge_did::op_id::{NonceState, OperationId};
+use bridge_did::operation_replay::ReplayContextDigest;
+use bridge_did::retry_profile::RetryProfileKind;
+use bridge_utils::btf_events::sanitize_burnt_event;
 use bridge_utils::evm_bridge::EvmParams;
 use did::H160;
 use eth_signer::sign_strategy::TransactionSigner;
 use ic_exports::ic_kit::ic;
 
+use self::concurrency::OperationConcurrency;
 use self::config::ConfigStorage;
+use super::replica::ReplicaSync;
+use super::scheduler::BridgeTask;
 use super::service::{ServiceId, Services};
-use crate::bridge::{Operation, OperationContext};
-use crate::memory::StableMemory;
+use crate::bridge::{Operation, OperationContext, OperationProgress};
+use crate::dead_letter::DeadLetterEvents;
+use crate::fee_adjustments::{FeeAdjustments, FeeAdjustmentsMemory};
+use crate::memory::{
+    memory_by_id, StableMemory, DEAD_LETTER_EVENTS_MEMORY_ID, FEE_ADJUSTMENTS_BY_PAYER_MEMORY_ID,
+    FEE_ADJUSTMENT_BALANCES_MEMORY_ID, OPERATION_REPLAYS_MEMORY_ID,
+    OPERATION_REPLAY_CAPTURE_MEMORY_ID, OPERATOR_AUDIT_MEMORY_ID,
+    PENDING_FEE_RECONCILIATIONS_MEMORY_ID, PROCESSED_EVENTS_BY_SENDER_MEMORY_ID,
+    PROCESSED_EVENTS_MEMORY_ID, PROCESSED_EVENT_SENDERS_MEMORY_ID, TASK_PAYLOADS_MEMORY_ID,
+};
+use crate::operation_replay::{
+    encode_replay_operation, replay_outcome, OperationReplays, OperationReplaysMemory,
+};
 use crate::operation_store::{OperationStore, OperationsMemory};
+use crate::operator_audit::OperatorAuditLog;
+use crate::processed_events::{ProcessedEvents, ProcessedEventsMemory};
+use crate::task_payloads::TaskPayloadStore;
 
 const SYS_TASK_LOCK_TIMEOUT: Duration = Duration::from_secs(60);
 const SCHEDULER_RUN_LOCK_TIMEOUT: Duration = Duration::from_secs(60);
@@ -30,23 +60,140 @@ pub type Timestamp = u64;
 pub struct State<Op: Operation> {
     pub config: SharedConfig,
     pub operations: OperationStore<StableMemory, Op>,
+    pub dead_letters: DeadLetterEvents<StableMemory>,
+    pub operator_audit: OperatorAuditLog<StableMemory>,
+    pub processed_events: ProcessedEvents<StableMemory>,
+    pub fee_adjustments: FeeAdjustments<StableMemory>,
+    pub task_payloads: TaskPayloadStore<StableMemory>,
+    pub operation_replays: OperationReplays<StableMemory>,
     pub collecting_logs_ts: Option<Timestamp>,
     pub refreshing_evm_params_ts: Option<Timestamp>,
     pub operations_run_ts: Option<Timestamp>,
     pub services: SharedServices,
+    pub concurrency: OperationConcurrency,
+    pub replica_sync: ReplicaSync,
 }
 
 impl<Op: Operation> State<Op> {
     /// Load the state from the stable memory, or initialize it with default values.
     pub fn default(memory: OperationsMemory<StableMemory>, config: SharedConfig) -> Self {
-        Self {
+        let mut state = Self {
             config,
             operations: OperationStore::with_memory(memory, None),
+            dead_letters: DeadLetterEvents::with_memory(memory_by_id(DEAD_LETTER_EVENTS_MEMORY_ID)),
+            operator_audit: OperatorAuditLog::with_memory(memory_by_id(OPERATOR_AUDIT_MEMORY_ID)),
+            processed_events: ProcessedEvents::with_memory(ProcessedEventsMemory {
+                events: memory_by_id(PROCESSED_EVENTS_MEMORY_ID),
+                senders: memory_by_id(PROCESSED_EVENT_SENDERS_MEMORY_ID),
+                by_sender: memory_by_id(PROCESSED_EVENTS_BY_SENDER_MEMORY_ID),
+            }),
+            fee_adjustments: FeeAdjustments::with_memory(FeeAdjustmentsMemory {
+                pending: memory_by_id(PENDING_FEE_RECONCILIATIONS_MEMORY_ID),
+                by_payer: memory_by_id(FEE_ADJUSTMENTS_BY_PAYER_MEMORY_ID),
+                balances: memory_by_id(FEE_ADJUSTMENT_BALANCES_MEMORY_ID),
+            }),
+            task_payloads: TaskPayloadStore::with_memory(memory_by_id(TASK_PAYLOADS_MEMORY_ID)),
+            operation_replays: OperationReplays::with_memory(OperationReplaysMemory {
+                replays: memory_by_id(OPERATION_REPLAYS_MEMORY_ID),
+                captured: memory_by_id(OPERATION_REPLAY_CAPTURE_MEMORY_ID),
+            }),
             collecting_logs_ts: None,
             refreshing_evm_params_ts: None,
             operations_run_ts: None,
             services: Default::default(),
+            concurrency: Default::default(),
+            replica_sync: Default::default(),
+        };
+        state.apply_nonce_offset();
+        state.apply_operation_log_compression();
+        state.apply_notification_destinations();
+        state
+    }
+
+    /// Returns the sync status of the replica canister, or `None` if the canister
+    /// is the primary.
+    pub fn replica_sync_status(&self) -> Option<ReplicaSyncStatus> {
+        let primary = self.config.borrow().get_bridge_mode().primary()?;
+        Some(
+            self.replica_sync
+                .status(primary, self.operations.blocks().len()),
+        )
+    }
+
+    /// Creates a scheduler task for the operation. The operation payload is moved to the task
+    /// payloads store, if it exceeds the inline size limit.
+    pub fn new_task(&mut self, op_id: OperationId, operation: Op) -> BridgeTask<Op> {
+        BridgeTask::with_payload_store(op_id, operation, &mut self.task_payloads)
+    }
+
+    /// Returns current state of the operation nonces.
+    pub fn nonce_state(&self) -> NonceState {
+        let next_operation_id = self.operations.peek_next_operation_id();
+        NonceState {
+            next_operation_id,
+            next_nonce: next_operation_id.nonce(),
+            nonce_offset: self.config.borrow().get_nonce_offset(),
+        }
+    }
+
+    /// Sets the nonce offset and applies it to the operation store.
+    pub fn set_nonce_offset(&mut self, offset: u64) {
+        self.config.borrow_mut().set_nonce_offset(offset);
+        self.apply_nonce_offset();
+    }
+
+    /// Moves the operation id counter to the configured nonce offset, if it is behind.
+    pub fn apply_nonce_offset(&mut self) {
+        let offset = self.config.borrow().get_nonce_offset();
+        self.operations.skip_operation_ids_below(offset);
+    }
+
+    /// Enables or disables the operation logs compression according to the config.
+    pub fn apply_operation_log_compression(&mut self) {
+        let enabled = self.config.borrow().is_operation_log_compression_enabled();
+        self.operations.set_log_compression(enabled);
+    }
+
+    /// Sets the destinations of the operation notifications according to the config.
+    pub fn apply_notification_destinations(&mut self) {
+        let destinations = self.config.borrow().get_notification_destinations();
+        self.operations.set_notification_destinations(destinations);
+    }
+
+    /// Sanitizes token metadata of the burnt event. In the strict mode, malformed events are
+    /// moved to the dead-letter list and `None` is returned.
+    pub fn sanitize_burnt_event(
+        &mut self,
+        event: BurntEventData,
+        strict: bool,
+    ) -> Option<BurntEventData> {
+        match sanitize_burnt_event(event.clone(), strict) {
+            Ok(event) => Some(event),
+            Err(e) => {
+                let id = self.dead_letters.push(event, e.to_string(), ic::time());
+                log::warn!("Burnt event is moved to the dead-letter list with id {id}: {e}");
+                None
+            }
+        }
+    }
+
+    /// Records the fee adjustment in the operation log and in the fee payer adjustments,
+    /// and removes the operation from the pending reconciliations.
+    ///
+    /// The fee of an operation is adjusted only once. Returns `false` if the fee is already
+    /// reconciled, or the operation is not found.
+    pub fn apply_fee_adjustment(&mut self, adjustment: FeeAdjustment) -> bool {
+        let operation_id = adjustment.operation_id;
+        self.fee_adjustments.remove_pending(operation_id);
+        if !self
+            .operations
+            .set_fee_adjustment(operation_id, adjustment.clone())
+        {
+            return false;
         }
+
+        self.fee_adjustments.record(adjustment);
+        true
     }
 
     /// Checks if the EVM parameters should be refreshed.
@@ -82,6 +229,69 @@ impl<Op: Operation> State<Op> {
             .unwrap_or(true)
     }
 
+    /// Returns the encoded input and the context digest of the operation progress, if the
+    /// progress of the operation should be captured to the replay log.
+    pub fn capture_progress_replay(
+        &self,
+        operation_id: OperationId,
+        operation: &Op,
+    ) -> Option<(Vec<u8>, ReplayContextDigest)> {
+        let settings = self.config.borrow().get_replay_capture_settings();
+        if !self
+            .operation_replays
+            .should_capture(operation_id, &settings)
+        {
+            return None;
+        }
+
+        Some((
+            encode_replay_operation(operation),
+            self.config.borrow().get_replay_context_digest(),
+        ))
+    }
+
+    /// Checks if the operation waited for its dependency for the whole dependency wait
+    /// retry profile at the `now` IC time. The wait starts at the latest operation step.
+    pub fn dependency_wait_exhausted(&self, operation_id: OperationId, now: u64) -> bool {
+        let Some(max_wait_secs) = self
+            .config
+            .borrow()
+            .get_retry_profiles()
+            .get(RetryProfileKind::DependencyWait)
+            .total_backoff_secs()
+        else {
+            return false;
+        };
+        let Some(waits_since) = self
+            .operations
+            .get_log(operation_id)
+            .and_then(|log| log.log().last().map(|entry| entry.time_stamp))
+        else {
+            return false;
+        };
+
+        now.saturating_sub(waits_since) >= Duration::from_secs(max_wait_secs).as_nanos() as u64
+    }
+
+    /// Records the captured progress of the operation to the replay log.
+    pub fn record_progress_replay(
+        &mut self,
+        operation_id: OperationId,
+        input: Vec<u8>,
+        context: ReplayContextDigest,
+        result: &BTFResult<OperationProgress<Op>>,
+    ) {
+        let log_size = self.config.borrow().get_replay_capture_settings().log_size;
+        self.operation_replays.record(
+            operation_id,
+            ic::time(),
+            input,
+            context,
+            replay_outcome(result),
+            log_size,
+        );
+    }
+
     /// Adds the given operation to the given service processing.
     pub fn push_operation_to_service(
         &self,
@@ -99,6 +309,10 @@ impl OperationContext for SharedConfig {
         self.borrow().get_evm_link()
     }
 
+    fn get_rpc_request_timeout(&self) -> Duration {
+        self.borrow().get_rpc_request_timeout()
+    }
+
     fn get_bridge_contract_address(&self) -> BTFResult<H160> {
         self.borrow().get_btf_bridge_contract().ok_or_else(|| {
             Error::Initialization("btf bridge contract expected to be initialized".to_string())
@@ -112,12 +326,21 @@ impl OperationContext for SharedConfig {
     fn get_signer(&self) -> BTFResult<impl TransactionSigner> {
         self.borrow().get_signer()
     }
+
+    fn get_evm_finality(&self) -> EvmFinality {
+        self.borrow().get_evm_finality()
+    }
+
+    fn get_event_topic_filter(&self) -> Option<EventTopicFilter> {
+        self.borrow().get_event_topic_filter()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use bridge_did::error::BTFResult;
-    use bridge_did::op_id::OperationId;
+    use bridge_did::fee_adjustment::{FeeAdjustmentAmount, PendingFeeReconciliation};
+    use bridge_did::op_id::{NonceState, OperationId};
     use candid::CandidType;
     use ic_exports::ic_kit::MockContext;
     use ic_stable_structures::MemoryId;
@@ -141,11 +364,11 @@ mod tests {
         }
 
         fn is_complete(&self) -> bool {
-            unimplemented!()
+            false
         }
 
         fn evm_wallet_address(&self) -> did::H160 {
-            unimplemented!()
+            did::H160::default()
         }
     }
 
@@ -186,4 +409,158 @@ mod tests {
         context.add_time(SYS_TASK_LOCK_TIMEOUT.as_nanos() as u64 + 1);
         assert!(state.borrow().should_collect_evm_logs());
     }
+
+    #[test]
+    fn test_nonce_offset_applied_to_new_operations() {
+        MockContext::new().inject();
+        let config = Rc::new(RefCell::new(ConfigStorage::default(memory_by_id(
+            MEMORY_ID,
+        ))));
+        config.borrow_mut().set_nonce_offset(1000);
+        let state: RuntimeState<TestOp> = default_state(config);
+
+        let id = state.borrow_mut().operations.new_operation(TestOp, None);
+        assert_eq!(id.nonce(), 1000);
+        assert_eq!(
+            state.borrow().nonce_state(),
+            NonceState {
+                next_operation_id: OperationId::new(1001),
+                next_nonce: 1001,
+                nonce_offset: 1000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_nonce_offset_does_not_move_counter_backwards() {
+        MockContext::new().inject();
+        let state = create_test_state();
+        for _ in 0..5 {
+            state.borrow_mut().operations.new_operation(TestOp, None);
+        }
+
+        state.borrow_mut().set_nonce_offset(3);
+        let id = state.borrow_mut().operations.new_operation(TestOp, None);
+        assert_eq!(id.nonce(), 5);
+
+        state.borrow_mut().set_nonce_offset(100);
+        let id = state.borrow_mut().operations.new_operation(TestOp, None);
+        assert_eq!(id.nonce(), 100);
+    }
+
+    #[test]
+    fn test_counter_unchanged_after_upgrade_without_offset() {
+        MockContext::new().inject();
+        let state = create_test_state();
+        for _ in 0..3 {
+            state.borrow_mut().operations.new_operation(TestOp, None);
+        }
+        drop(state);
+
+        // State is restored from the same stable memory, as it happens after upgrade.
+        let state = create_test_state();
+        assert_eq!(
+            state.borrow().nonce_state(),
+            NonceState {
+                next_operation_id: OperationId::new(3),
+                next_nonce: 3,
+                nonce_offset: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_fee_adjustment_is_applied_once() {
+        MockContext::new().inject();
+        let state = create_test_state();
+        let id = state.borrow_mut().operations.new_operation(TestOp, None);
+        let fee_payer = did::H160::from_slice(&[1; 20]);
+        let adjustment = |amount: FeeAdjustmentAmount| FeeAdjustment {
+            operation_id: id,
+            fee_payer: fee_payer.clone(),
+            tx_hash: did::H256::from_slice(&[2; 32]),
+            charged: 100u64.into(),
+            actual: 70u64.into(),
+            amount,
+            timestamp: 0,
+        };
+        state
+            .borrow_mut()
+            .fee_adjustments
+            .add_pending(PendingFeeReconciliation {
+                operation_id: id,
+                bridge_contract: did::H160::from_slice(&[3; 20]),
+                tx_hash: did::H256::from_slice(&[2; 32]),
+                nonce: id.nonce(),
+                charged: 100u64.into(),
+            });
+
+        let credit = adjustment(FeeAdjustmentAmount::Credit(30u64.into()));
+        assert!(state.borrow_mut().apply_fee_adjustment(credit.clone()));
+        assert_eq!(state.borrow().fee_adjustments.pending_len(), 0);
+
+        // The repeated reconciliation of the same operation is ignored.
+        assert!(!state.borrow_mut().apply_fee_adjustment(credit.clone()));
+        assert!(!state
+            .borrow_mut()
+            .apply_fee_adjustment(adjustment(FeeAdjustmentAmount::Debit(10u64.into()))));
+
+        let log = state.borrow().operations.get_log(id).unwrap();
+        assert_eq!(log.fee_adjustment(), Some(&credit));
+
+        let adjustments = state
+            .borrow()
+            .fee_adjustments
+            .get_for_payer(&fee_payer, None);
+        assert_eq!(adjustments.adjustments, vec![credit]);
+        assert_eq!(adjustments.balance.credited, 30u64.into());
+        assert_eq!(adjustments.balance.debited, 0u64.into());
+
+        // Fee of an unknown operation is not recorded.
+        let mut unknown = adjustment(FeeAdjustmentAmount::Credit(1u64.into()));
+        unknown.operation_id = OperationId::new(100);
+        assert!(!state.borrow_mut().apply_fee_adjustment(unknown));
+        assert_eq!(
+            state
+                .borrow()
+                .fee_adjustments
+                .get_for_payer(&fee_payer, None)
+                .adjustments
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_malformed_burnt_event_routing() {
+        MockContext::new().inject();
+        let state = create_test_state();
+        let event = BurntEventData {
+            name: vec![1; 40],
+            symbol: vec![2; 16],
+            decimals: 18,
+            ..Default::default()
+        };
+
+        // Without the strict mode the event is normalized.
+        let sanitized = state
+            .borrow_mut()
+            .sanitize_burnt_event(event.clone(), false)
+            .unwrap();
+        assert_eq!(sanitized.name, vec![1; 32]);
+        assert!(state.borrow().dead_letters.is_empty());
+
+        // In the strict mode the event is moved to the dead-letter list as is.
+        assert!(state
+            .borrow_mut()
+            .sanitize_burnt_event(event, true)
+            .is_none());
+        let dead_letters = state.borrow().dead_letters.get(0, 10);
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].event.burnt().unwrap().name, vec![1; 40]);
+        assert_eq!(
+            dead_letters[0].reason,
+            "token name has 40 bytes, expected 32"
+        );
+    }
 }
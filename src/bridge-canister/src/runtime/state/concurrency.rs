@@ -0,0 +1,296 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bridge_did::concurrency::{
+    DeferredOperationsStats, OperationConcurrencyLimits, OperationDirection,
+};
+use bridge_did::op_id::OperationId;
+
+/// Tracks the number of the concurrently progressing operations per direction
+/// and the operations, deferred because the direction limit is reached.
+///
+/// Deferred operations are admitted in the order of their priority, when the direction has
+/// free capacity. Operations with equal priority are admitted in FIFO order.
+#[derive(Debug, Default, Clone)]
+pub struct OperationConcurrency {
+    in_flight: HashMap<OperationDirection, u32>,
+    admitted: HashSet<OperationId>,
+    deferred: HashMap<OperationDirection, VecDeque<DeferredOperation>>,
+}
+
+/// Operation, deferred because its direction limit is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DeferredOperation {
+    id: OperationId,
+    priority: u8,
+}
+
+impl OperationConcurrency {
+    /// Tries to start the operation progress. Returns `false` if the operation is deferred.
+    ///
+    /// Operations, admitted by `admit_deferred()`, are started without the limit check,
+    /// because their slot is already taken.
+    pub fn try_start(
+        &mut self,
+        id: OperationId,
+        direction: OperationDirection,
+        limit: Option<u32>,
+        priority: u8,
+    ) -> bool {
+        if self.admitted.remove(&id) {
+            return true;
+        }
+
+        let queue = self.deferred.entry(direction).or_default();
+        if queue.iter().any(|deferred| deferred.id == id) {
+            return false;
+        }
+
+        let in_flight = self.in_flight.entry(direction).or_default();
+        if let Some(limit) = limit {
+            // Operations are not allowed to overtake the deferred ones with the same or
+            // higher priority.
+            let overtakes = queue.iter().any(|deferred| deferred.priority >= priority);
+            if overtakes || *in_flight >= limit {
+                let position = queue
+                    .iter()
+                    .position(|deferred| deferred.priority < priority)
+                    .unwrap_or(queue.len());
+                queue.insert(position, DeferredOperation { id, priority });
+                return false;
+            }
+        }
+
+        *in_flight += 1;
+        true
+    }
+
+    /// Releases the slot of the finished operation progress.
+    pub fn finish(&mut self, direction: OperationDirection) {
+        let in_flight = self.in_flight.entry(direction).or_default();
+        *in_flight = in_flight.saturating_sub(1);
+    }
+
+    /// Admits the deferred operations up to the free capacity of their direction.
+    /// Admitted operations take their slots and should be scheduled for progress.
+    pub fn admit_deferred(
+        &mut self,
+        limits: &OperationConcurrencyLimits,
+    ) -> Vec<(OperationId, OperationDirection)> {
+        let mut admitted = vec![];
+        for direction in OperationDirection::ALL {
+            let Some(queue) = self.deferred.get_mut(&direction) else {
+                continue;
+            };
+
+            let in_flight = self.in_flight.entry(direction).or_default();
+            while limits
+                .limit(direction)
+                .map_or(true, |limit| *in_flight < limit)
+            {
+                let Some(DeferredOperation { id, .. }) = queue.pop_front() else {
+                    break;
+                };
+
+                *in_flight += 1;
+                self.admitted.insert(id);
+                admitted.push((id, direction));
+            }
+        }
+
+        admitted
+    }
+
+    /// Admits the deferred operation regardless of its direction limit. The admitted
+    /// operation takes its slot and should be scheduled for progress.
+    ///
+    /// Returns the direction of the operation, or `None` if the operation is not deferred.
+    pub fn admit_now(&mut self, id: OperationId) -> Option<OperationDirection> {
+        let direction = self.remove_deferred(id)?;
+        *self.in_flight.entry(direction).or_default() += 1;
+        self.admitted.insert(id);
+        Some(direction)
+    }
+
+    /// Forgets the deferred or admitted operation, which is not going to progress anymore.
+    pub fn forget(&mut self, id: OperationId, direction: OperationDirection) {
+        self.remove_deferred(id);
+        if self.admitted.remove(&id) {
+            self.finish(direction);
+        }
+    }
+
+    fn remove_deferred(&mut self, id: OperationId) -> Option<OperationDirection> {
+        self.deferred.iter_mut().find_map(|(direction, queue)| {
+            let position = queue.iter().position(|deferred| deferred.id == id)?;
+            queue.remove(position);
+            Some(*direction)
+        })
+    }
+
+    /// Returns the number of the deferred operations per direction.
+    pub fn deferred_stats(&self) -> DeferredOperationsStats {
+        let deferred = |direction| {
+            self.deferred
+                .get(&direction)
+                .map_or(0, |queue| queue.len() as u64)
+        };
+
+        DeferredOperationsStats {
+            deposit: deferred(OperationDirection::Deposit),
+            withdrawal: deferred(OperationDirection::Withdrawal),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(nonce: u64) -> OperationId {
+        OperationId::new(nonce)
+    }
+
+    fn limits(deposit: Option<u32>, withdrawal: Option<u32>) -> OperationConcurrencyLimits {
+        OperationConcurrencyLimits {
+            deposit,
+            withdrawal,
+        }
+    }
+
+    #[test]
+    fn should_respect_direction_limit() {
+        let mut concurrency = OperationConcurrency::default();
+
+        assert!(concurrency.try_start(id(1), OperationDirection::Deposit, Some(2), 0));
+        assert!(concurrency.try_start(id(2), OperationDirection::Deposit, Some(2), 0));
+        assert!(!concurrency.try_start(id(3), OperationDirection::Deposit, Some(2), 0));
+
+        // Other direction is not affected.
+        assert!(concurrency.try_start(id(4), OperationDirection::Withdrawal, Some(1), 0));
+
+        assert_eq!(
+            concurrency.deferred_stats(),
+            DeferredOperationsStats {
+                deposit: 1,
+                withdrawal: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn should_admit_deferred_in_fifo_order() {
+        let mut concurrency = OperationConcurrency::default();
+        let limits = limits(Some(1), None);
+
+        assert!(concurrency.try_start(id(1), OperationDirection::Deposit, Some(1), 0));
+        assert!(!concurrency.try_start(id(2), OperationDirection::Deposit, Some(1), 0));
+        assert!(!concurrency.try_start(id(3), OperationDirection::Deposit, Some(1), 0));
+        assert!(concurrency.admit_deferred(&limits).is_empty());
+
+        concurrency.finish(OperationDirection::Deposit);
+        // New operation doesn't overtake the deferred ones.
+        assert!(!concurrency.try_start(id(4), OperationDirection::Deposit, Some(1), 0));
+
+        assert_eq!(
+            concurrency.admit_deferred(&limits),
+            vec![(id(2), OperationDirection::Deposit)]
+        );
+        assert!(concurrency.try_start(id(2), OperationDirection::Deposit, Some(1), 0));
+
+        concurrency.finish(OperationDirection::Deposit);
+        assert_eq!(
+            concurrency.admit_deferred(&limits),
+            vec![(id(3), OperationDirection::Deposit)]
+        );
+        assert_eq!(concurrency.deferred_stats().deposit, 1);
+    }
+
+    #[test]
+    fn should_admit_deferred_operation_now() {
+        let mut concurrency = OperationConcurrency::default();
+        let limits = limits(Some(1), None);
+
+        assert!(concurrency.try_start(id(1), OperationDirection::Deposit, Some(1), 0));
+        assert!(!concurrency.try_start(id(2), OperationDirection::Deposit, Some(1), 0));
+        assert!(!concurrency.try_start(id(3), OperationDirection::Deposit, Some(1), 0));
+
+        assert_eq!(concurrency.admit_now(id(1)), None);
+        assert_eq!(
+            concurrency.admit_now(id(3)),
+            Some(OperationDirection::Deposit)
+        );
+        assert!(concurrency.try_start(id(3), OperationDirection::Deposit, Some(1), 0));
+        assert_eq!(concurrency.deferred_stats().deposit, 1);
+
+        concurrency.forget(id(2), OperationDirection::Deposit);
+        concurrency.finish(OperationDirection::Deposit);
+        concurrency.finish(OperationDirection::Deposit);
+        assert!(concurrency.admit_deferred(&limits).is_empty());
+        assert_eq!(concurrency.deferred_stats().deposit, 0);
+    }
+
+    #[test]
+    fn should_admit_high_priority_before_queued_low_priority() {
+        let mut concurrency = OperationConcurrency::default();
+        let limits = limits(Some(1), None);
+
+        assert!(concurrency.try_start(id(1), OperationDirection::Deposit, Some(1), 0));
+        assert!(!concurrency.try_start(id(2), OperationDirection::Deposit, Some(1), 0));
+        assert!(!concurrency.try_start(id(3), OperationDirection::Deposit, Some(1), 0));
+        assert!(!concurrency.try_start(id(4), OperationDirection::Deposit, Some(1), 5));
+        assert!(!concurrency.try_start(id(5), OperationDirection::Deposit, Some(1), 5));
+
+        concurrency.finish(OperationDirection::Deposit);
+        assert_eq!(
+            concurrency.admit_deferred(&limits),
+            vec![(id(4), OperationDirection::Deposit)]
+        );
+        assert!(concurrency.try_start(id(4), OperationDirection::Deposit, Some(1), 5));
+
+        concurrency.finish(OperationDirection::Deposit);
+        assert_eq!(
+            concurrency.admit_deferred(&limits),
+            vec![(id(5), OperationDirection::Deposit)]
+        );
+        assert!(concurrency.try_start(id(5), OperationDirection::Deposit, Some(1), 5));
+
+        // With free capacity, high priority operation overtakes the deferred low priority ones.
+        concurrency.finish(OperationDirection::Deposit);
+        assert!(concurrency.try_start(id(6), OperationDirection::Deposit, Some(1), 5));
+
+        concurrency.finish(OperationDirection::Deposit);
+        assert_eq!(
+            concurrency.admit_deferred(&limits),
+            vec![(id(2), OperationDirection::Deposit)]
+        );
+        assert_eq!(concurrency.deferred_stats().deposit, 1);
+    }
+
+    #[test]
+    fn zero_limit_should_pause_direction() {
+        let mut concurrency = OperationConcurrency::default();
+
+        assert!(!concurrency.try_start(id(1), OperationDirection::Withdrawal, Some(0), 0));
+        assert!(concurrency
+            .admit_deferred(&limits(None, Some(0)))
+            .is_empty());
+
+        assert_eq!(
+            concurrency.admit_deferred(&limits(None, None)),
+            vec![(id(1), OperationDirection::Withdrawal)]
+        );
+    }
+
+    #[test]
+    fn should_not_limit_without_limit() {
+        let mut concurrency = OperationConcurrency::default();
+
+        for nonce in 0..100 {
+            assert!(concurrency.try_start(id(nonce), OperationDirection::Deposit, None, 0));
+        }
+        assert_eq!(
+            concurrency.deferred_stats(),
+            DeferredOperationsStats::default()
+        );
+    }
+}
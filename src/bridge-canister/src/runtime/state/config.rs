@@ -1,26 +1,71 @@
 use std::borrow::Cow;
 use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::future::Future;
 use std::rc::Rc;
+use std::time::Duration;
 
+use bridge_did::bridge_mode::BridgeMode;
+use bridge_did::concurrency::OperationConcurrencyLimits;
 use bridge_did::error::{BTFResult, Error};
-use bridge_did::evm_link::EvmLink;
+use bridge_did::event_filter::EventTopicFilter;
+use bridge_did::evm_finality::EvmFinality;
+use bridge_did::evm_latency::LatencyStats;
+use bridge_did::evm_link::{EvmLink, PrivateTxRelay};
+use bridge_did::evm_params_history::EvmParamsSnapshot;
+use bridge_did::fee_adjustment::FeeReconciliationSettings;
+use bridge_did::gas_balance::{GasBalanceAlert, GasBalanceStatus, GasBalanceThresholds};
+use bridge_did::halt::BridgeHalt;
+use bridge_did::http_transform::HttpTransformSettings;
 use bridge_did::init::BridgeInitData;
-use bridge_utils::evm_bridge::EvmParams;
-use bridge_utils::evm_link::EvmLinkClient;
+use bridge_did::manifest::{BridgeLimits, BridgeManifest};
+use bridge_did::nonce_drift::{NonceDriftStatus, NonceGap};
+use bridge_did::operation_replay::{ReplayCaptureSettings, ReplayContextDigest};
+use bridge_did::outbox::OutboxDestination;
+use bridge_did::retry_profile::{RetryBackoff, RetryLimit, RetryProfileKind, RetryProfiles};
+use bridge_did::versioned::Versioned;
+use bridge_utils::evm_bridge::{self, EvmParams};
+use bridge_utils::evm_link::{EvmLinkClient, TimeoutClient};
 use bridge_utils::query::{
     self, Query, QueryType, CHAINID_ID, GAS_PRICE_ID, LATEST_BLOCK_ID, NONCE_ID,
 };
-use candid::{CandidType, Principal};
-use did::{codec, H160, U256};
+use candid::{CandidType, Decode, Encode, Principal};
+use did::{codec, H160, H256, U256};
 use eth_signer::sign_strategy::{SigningStrategy, TransactionSigner};
+use ethereum_json_rpc_client::{Client, EthJsonRpcClient};
+use ethers_core::utils::keccak256;
+use ic_exports::ic_kit::ic;
 use ic_stable_structures::{CellStructure, StableCell, Storable};
+use ic_task_scheduler::retry::{BackoffPolicy, RetryPolicy};
+use ic_task_scheduler::task::TaskOptions;
 use jsonrpc_core::Id;
 use serde::{Deserialize, Serialize};
 
+use super::evm_latency::EvmLatencyMonitor;
+use super::evm_params_history::{EvmParamsHistory, DEFAULT_EVM_PARAMS_HISTORY_SIZE};
+use super::gas_balance::GasBalanceMonitor;
+use super::nonce_drift::{NonceDrift, NonceDriftMonitor};
+use super::Timestamp;
 use crate::memory::StableMemory;
+use crate::operation_blocks::MAX_BLOCKS_PER_REQUEST;
+use crate::operation_store::DEFAULT_OPERATIONS_LIST_LIMIT;
+use crate::runtime::service::sign_orders::MAX_MINT_ORDERS_IN_BATCH;
+
+/// Timeout of the EVM JSON-RPC and indexer requests, if not configured.
+pub const DEFAULT_RPC_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// Stores configuration to work with EVM.
-pub struct ConfigStorage(StableCell<Config, StableMemory>);
+pub struct ConfigStorage {
+    config: StableCell<Config, StableMemory>,
+    gas_balance: GasBalanceMonitor,
+    evm_params_history: EvmParamsHistory,
+    evm_latency: EvmLatencyMonitor,
+    nonce_drift: NonceDriftMonitor,
+    /// IC time of the latest EVM params refresh. Not persisted across upgrades.
+    evm_params_refreshed_at: Option<Timestamp>,
+    /// Signer address with the signing strategy it was derived for. Not persisted across upgrades.
+    signer_address: Option<(SigningStrategy, H160)>,
+}
 
 impl ConfigStorage {
     /// Stores a new SignerInfo in the given memory.
@@ -28,7 +73,15 @@ impl ConfigStorage {
         let cell =
             StableCell::new(memory, Config::default()).expect("failed to initialize evm config");
 
-        Self(cell)
+        Self {
+            config: cell,
+            gas_balance: GasBalanceMonitor::default(),
+            evm_params_history: EvmParamsHistory::default(),
+            evm_latency: EvmLatencyMonitor::default(),
+            nonce_drift: NonceDriftMonitor::default(),
+            evm_params_refreshed_at: None,
+            signer_address: None,
+        }
     }
 
     /// Creates a new instance of config struct and stores it in the stable memory.
@@ -51,17 +104,41 @@ impl ConfigStorage {
             evm_params: None,
             btf_bridge_contract_address: None,
             signing_strategy: init_data.signing_strategy.clone(),
+            evm_finality: init_data.evm_finality,
+            nonce_offset: init_data.nonce_offset.unwrap_or_default(),
+            gas_balance_thresholds: None,
+            compress_operation_logs: None,
+            evm_params_history_size: None,
+            max_acceptable_evm_latency_ms: None,
+            notification_destinations: None,
+            strict_event_validation: None,
+            btf_bridge_activation_block: None,
+            manifest_version: None,
+            gas_price_refresh_interval_secs: None,
+            operation_concurrency_limits: None,
+            private_tx_relay: None,
+            dropped_tx_timeout_secs: None,
+            fee_reconciliation: None,
+            event_topic_filter: None,
+            halt: None,
+            mode: init_data.mode.clone(),
+            operations_list_default_limit: None,
+            timers_enabled: None,
+            rpc_request_timeout_secs: None,
+            http_transform: None,
+            retry_profiles: None,
+            replay_capture: None,
         };
 
         self.update(|stored| *stored = new_config);
+        self.signer_address = None;
     }
 
     /// Query EVM params using the EvmLink in the config data.
     pub async fn init_evm_params(config: Rc<RefCell<Self>>) -> BTFResult<()> {
         log::trace!("initializing evm params");
 
-        let link = config.borrow().get_evm_link();
-        let client = link.get_json_rpc_client();
+        let client = config.borrow().get_json_rpc_client();
         let responses = query::batch_query(
             &client,
             &[
@@ -85,10 +162,20 @@ impl ConfigStorage {
             .get_value_by_id(Id::Str(LATEST_BLOCK_ID.into()))
             .map_err(|e| Error::EvmRequestFailed(format!("failed to query latest block: {e}")))?;
 
+        // Make sure the EVM supports the configured finality before starting to collect events.
+        let finality = config.borrow().get_evm_finality();
+        evm_bridge::query_final_block_number(&client, finality)
+            .await
+            .map_err(|e| {
+                Error::Initialization(format!(
+                    "EVM doesn't support `{finality}` block finality: {e}"
+                ))
+            })?;
+
         let params = EvmParams {
             nonce: 0,
             gas_price,
-            chain_id: chain_id.0.as_u32(),
+            chain_id: chain_id.0.as_u64(),
             next_block: latest_block.0.as_u64(),
         };
 
@@ -105,16 +192,13 @@ impl ConfigStorage {
     pub async fn refresh_evm_params(config: Rc<RefCell<Self>>) -> BTFResult<()> {
         log::trace!("updating evm params");
 
-        let link = config.borrow().get_evm_link();
-        let client = link.get_json_rpc_client();
+        let client = config.borrow().get_json_rpc_client();
         if config.borrow().get_evm_params().is_err() {
             ConfigStorage::init_evm_params(config.clone()).await?;
         };
 
-        let address = {
-            let signer = config.borrow().get_signer()?;
-            signer.get_address().await?
-        };
+        let address = ConfigStorage::get_signer_address(config.clone()).await?;
+        let reserved_nonce = config.borrow().get_evm_params()?.nonce;
 
         let responses = query::batch_query(
             &client,
@@ -135,10 +219,12 @@ impl ConfigStorage {
             .get_value_by_id(Id::Str(GAS_PRICE_ID.into()))
             .map_err(|e| Error::EvmRequestFailed(format!("failed to query gas price: {e}")))?;
 
-        config.borrow_mut().update_evm_params(|p| {
-            p.nonce = nonce.0.as_u64();
-            p.gas_price = gas_price;
-        });
+        config.borrow_mut().record_refreshed_evm_params(
+            reserved_nonce,
+            nonce.0.as_u64(),
+            gas_price,
+            ic::time(),
+        );
 
         log::trace!("evm params updated: {:?}", config.borrow().get_evm_params());
 
@@ -152,7 +238,7 @@ impl ConfigStorage {
 
     /// Returns owner principal.
     pub fn get_owner(&self) -> Principal {
-        self.0.get().owner
+        self.config.get().owner
     }
 
     /// Checks if the caller is owner.
@@ -166,18 +252,51 @@ impl ConfigStorage {
 
     /// Returns parameters of EVM canister with which the bridge canister works.
     pub fn get_evm_params(&self) -> BTFResult<EvmParams> {
-        self.0.get().evm_params.clone().ok_or_else(|| {
+        self.config.get().evm_params.clone().ok_or_else(|| {
             Error::Initialization("failed to get uninitialized get evm params".into())
         })
     }
 
     /// Updates parameters of EVM canister with which the bridge canister works.
+    /// If the params are changed, their snapshot is added to the params history.
     pub fn update_evm_params<F: FnOnce(&mut EvmParams)>(&mut self, f: F) {
-        self.update(|config| {
-            let mut params = config.evm_params.clone().unwrap_or_default();
-            f(&mut params);
-            config.evm_params = Some(params);
-        })
+        let mut params = self.config.get().evm_params.clone().unwrap_or_default();
+        f(&mut params);
+
+        let capacity = self.get_evm_params_history_size() as usize;
+        self.evm_params_history
+            .record(&params, ic::time(), capacity);
+        self.update(|config| config.evm_params = Some(params));
+    }
+
+    /// Returns index of the snapshot of the current EVM params in the params history.
+    pub fn get_evm_params_snapshot_index(&self) -> Option<u64> {
+        self.evm_params_history.latest_index()
+    }
+
+    /// Returns up to `count` snapshots of the EVM params, newest first,
+    /// skipping `offset` newest ones.
+    pub fn get_evm_params_history(&self, offset: usize, count: usize) -> Vec<EvmParamsSnapshot> {
+        self.evm_params_history.get(offset, count)
+    }
+
+    /// Returns the EVM params snapshot with the given index, if it is still kept.
+    pub fn get_evm_params_snapshot(&self, index: u64) -> Option<EvmParamsSnapshot> {
+        self.evm_params_history.find(index).cloned()
+    }
+
+    /// Returns max number of the EVM params snapshots to keep.
+    pub fn get_evm_params_history_size(&self) -> u32 {
+        self.config
+            .get()
+            .evm_params_history_size
+            .unwrap_or(DEFAULT_EVM_PARAMS_HISTORY_SIZE)
+    }
+
+    /// Sets max number of the EVM params snapshots to keep, evicting the oldest ones.
+    pub fn set_evm_params_history_size(&mut self, size: u32) {
+        self.evm_params_history.truncate(size as usize);
+        self.update(|config| config.evm_params_history_size = Some(size));
     }
 
     /// Sets EVM link
@@ -187,22 +306,537 @@ impl ConfigStorage {
 
     /// Returns EVM link
     pub fn get_evm_link(&self) -> EvmLink {
-        self.0.get().evm_link.clone()
+        self.config.get().evm_link.clone()
+    }
+
+    /// Returns the JSON-RPC client of the EVM link, which fails the requests not completed
+    /// in the configured request timeout.
+    pub fn get_json_rpc_client(&self) -> EthJsonRpcClient<TimeoutClient<impl Client>> {
+        self.get_evm_link()
+            .get_json_rpc_client_with_timeout(self.get_rpc_request_timeout())
+    }
+
+    /// Returns timeout of the EVM JSON-RPC and indexer requests.
+    pub fn get_rpc_request_timeout(&self) -> Duration {
+        self.config
+            .get()
+            .rpc_request_timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_RPC_REQUEST_TIMEOUT)
+    }
+
+    /// Sets timeout of the EVM JSON-RPC and indexer requests. If `None`, the default is used.
+    pub fn set_rpc_request_timeout_secs(&mut self, timeout_secs: Option<u64>) {
+        self.update(|config| config.rpc_request_timeout_secs = timeout_secs);
+    }
+
+    /// Returns settings of the HTTP outcall response transform.
+    pub fn get_http_transform_settings(&self) -> HttpTransformSettings {
+        self.config.get().http_transform.clone().unwrap_or_default()
+    }
+
+    /// Sets settings of the HTTP outcall response transform. If `None`, the default is used.
+    pub fn set_http_transform_settings(&mut self, settings: Option<HttpTransformSettings>) {
+        self.update(|config| config.http_transform = settings);
+    }
+
+    /// Returns retry profiles of the scheduled tasks.
+    pub fn get_retry_profiles(&self) -> RetryProfiles {
+        self.config.get().retry_profiles.clone().unwrap_or_default()
+    }
+
+    /// Sets retry profiles of the scheduled tasks. If `None`, the default is used.
+    /// Profiles apply to the newly scheduled tasks only.
+    pub fn set_retry_profiles(&mut self, profiles: Option<RetryProfiles>) {
+        self.update(|config| config.retry_profiles = profiles);
+    }
+
+    /// Returns settings of the operation progress capture.
+    pub fn get_replay_capture_settings(&self) -> ReplayCaptureSettings {
+        self.config.get().replay_capture.unwrap_or_default()
+    }
+
+    /// Sets settings of the operation progress capture. If `None`, the default is used.
+    pub fn set_replay_capture_settings(&mut self, settings: Option<ReplayCaptureSettings>) {
+        self.update(|config| config.replay_capture = settings);
+    }
+
+    /// Returns options of a new task, scheduled with the retry profile of the given category.
+    pub fn get_task_options(&self, kind: RetryProfileKind) -> TaskOptions {
+        let profile = *self.get_retry_profiles().get(kind);
+
+        let options = match profile.retries {
+            RetryLimit::MaxRetries(retries) => TaskOptions::new().with_max_retries_policy(retries),
+            RetryLimit::Unbounded => TaskOptions::new().with_retry_policy(RetryPolicy::Infinite),
+        };
+
+        options.with_backoff_policy(match profile.backoff {
+            RetryBackoff::None => BackoffPolicy::None,
+            RetryBackoff::Fixed { secs } => BackoffPolicy::Fixed { secs },
+            RetryBackoff::Exponential { secs, multiplier } => {
+                BackoffPolicy::Exponential { secs, multiplier }
+            }
+        })
     }
 
     /// Returns bridge contract address for EVM.
     pub fn get_btf_bridge_contract(&self) -> Option<H160> {
-        self.0.get().btf_bridge_contract_address.clone()
+        self.config.get().btf_bridge_contract_address.clone()
     }
 
     /// Set bridge contract address for EVM.
+    ///
+    /// If the address is changed, the next block to collect events from is recorded as the
+    /// activation block of the contract. Events emitted before the activation block are ignored.
     pub fn set_btf_bridge_contract(&mut self, address: H160) {
-        self.update(|config| config.btf_bridge_contract_address = Some(address));
+        if self.get_btf_bridge_contract().as_ref() == Some(&address) {
+            return;
+        }
+
+        let activation_block = self
+            .get_evm_params()
+            .map(|params| params.next_block)
+            .unwrap_or_default();
+        self.update(|config| {
+            config.btf_bridge_contract_address = Some(address);
+            config.btf_bridge_activation_block = Some(activation_block);
+        });
+    }
+
+    /// Returns the first EVM block, events of the bridge contract are processed from.
+    pub fn get_btf_bridge_activation_block(&self) -> u64 {
+        self.config
+            .get()
+            .btf_bridge_activation_block
+            .unwrap_or_default()
+    }
+
+    /// Returns finality of the EVM blocks to collect events from.
+    pub fn get_evm_finality(&self) -> EvmFinality {
+        self.config.get().evm_finality.unwrap_or_default()
+    }
+
+    /// Sets finality of the EVM blocks to collect events from.
+    pub fn set_evm_finality(&mut self, finality: EvmFinality) {
+        self.update(|config| config.evm_finality = Some(finality));
+    }
+
+    /// Returns minimal id of the operations created by the canister.
+    pub fn get_nonce_offset(&self) -> u64 {
+        self.config.get().nonce_offset
+    }
+
+    /// Sets minimal id of the operations created by the canister.
+    pub fn set_nonce_offset(&mut self, offset: u64) {
+        self.update(|config| config.nonce_offset = offset);
+    }
+
+    /// Returns balance thresholds of the bridge canister EVM address.
+    pub fn get_gas_balance_thresholds(&self) -> Option<GasBalanceThresholds> {
+        self.config.get().gas_balance_thresholds.clone()
+    }
+
+    /// Sets balance thresholds of the bridge canister EVM address.
+    /// If thresholds are removed, the mint transactions sponsorship is enabled.
+    pub fn set_gas_balance_thresholds(&mut self, thresholds: Option<GasBalanceThresholds>) {
+        if thresholds.is_none() {
+            self.gas_balance.reset();
+        }
+        self.update(|config| config.gas_balance_thresholds = thresholds);
+    }
+
+    /// Records the balance of the bridge canister EVM address and reports the health change.
+    pub fn record_gas_balance(&mut self, address: H160, balance: U256, timestamp: Timestamp) {
+        let Some(thresholds) = self.get_gas_balance_thresholds() else {
+            return;
+        };
+
+        let alert =
+            self.gas_balance
+                .record(&thresholds, address.clone(), balance.clone(), timestamp);
+        match alert {
+            Some(GasBalanceAlert::Low) => log::warn!(
+                "Bridge canister EVM address {address} balance {balance} is below the low watermark {}",
+                thresholds.low_watermark
+            ),
+            Some(GasBalanceAlert::Critical) => log::error!(
+                "Bridge canister EVM address {address} balance {balance} is below the critical watermark {}. Mint transactions sponsorship is disabled",
+                thresholds.critical_watermark
+            ),
+            Some(GasBalanceAlert::Recovered) => log::info!(
+                "Bridge canister EVM address {address} balance {balance} is restored. Mint transactions sponsorship is enabled"
+            ),
+            None => log::trace!("Bridge canister EVM address {address} balance: {balance}"),
+        }
+    }
+
+    /// Checks if the bridge canister should send mint transactions paid by the fee payer.
+    pub fn is_mint_tx_sponsorship_enabled(&self) -> bool {
+        self.gas_balance.is_sponsorship_enabled()
+    }
+
+    /// Returns diagnostic info of the bridge canister EVM address balance.
+    pub fn get_gas_balance_status(&self) -> GasBalanceStatus {
+        self.gas_balance.status(self.get_gas_balance_thresholds())
+    }
+
+    /// Returns the latency above which the EVM node is considered degraded.
+    pub fn get_max_acceptable_evm_latency_ms(&self) -> Option<u64> {
+        self.config.get().max_acceptable_evm_latency_ms
+    }
+
+    /// Sets the latency above which the EVM node is considered degraded.
+    pub fn set_max_acceptable_evm_latency_ms(&mut self, max_latency_ms: Option<u64>) {
+        self.update(|config| config.max_acceptable_evm_latency_ms = max_latency_ms);
+    }
+
+    /// Records the round-trip time of the EVM node request and reports the latency degradation.
+    pub fn record_evm_latency(&mut self, timestamp: Timestamp, latency_ns: u64) {
+        let max_latency_ms = self.get_max_acceptable_evm_latency_ms();
+        let stats = self
+            .evm_latency
+            .record(timestamp, latency_ns, max_latency_ms);
+        match self.evm_latency.update_degraded(&stats) {
+            Some(true) => log::warn!(
+                "EVM node latency p95 {}ms exceeds the acceptable {}ms",
+                stats.p95_ns / 1_000_000,
+                max_latency_ms.unwrap_or_default()
+            ),
+            Some(false) => log::info!(
+                "EVM node latency p95 {}ms is restored",
+                stats.p95_ns / 1_000_000
+            ),
+            None => log::trace!("EVM node latency: {latency_ns}ns"),
+        }
+    }
+
+    /// Updates the EVM params with the values, queried from the EVM node. `reserved_nonce` is
+    /// the nonce reserved for the next transaction when the chain nonce was queried.
+    pub(crate) fn record_refreshed_evm_params(
+        &mut self,
+        reserved_nonce: u64,
+        chain_nonce: u64,
+        gas_price: U256,
+        timestamp: Timestamp,
+    ) {
+        let fast_forward = self.check_nonce_drift(reserved_nonce, chain_nonce, timestamp);
+        self.update_evm_params(|p| {
+            if let Some(nonce) = fast_forward {
+                p.nonce = nonce;
+            }
+            p.gas_price = gas_price;
+        });
+        self.evm_params_refreshed_at = Some(timestamp);
+    }
+
+    /// Compares the `reserved_nonce` with the pending nonce of the bridge canister EVM address.
+    /// Returns the nonce to fast-forward the reserved one to, if the chain is ahead.
+    ///
+    /// If a transaction was sent while the chain nonce was queried, the reserved nonce is
+    /// changed and can't be compared, so the check is skipped.
+    fn check_nonce_drift(
+        &mut self,
+        reserved_nonce: u64,
+        chain_nonce: u64,
+        timestamp: Timestamp,
+    ) -> Option<u64> {
+        let current_nonce = self.get_evm_params().ok()?.nonce;
+        if current_nonce != reserved_nonce {
+            log::trace!("Nonce {reserved_nonce} was used during the chain nonce query. Skipping the nonce drift check.");
+            return None;
+        }
+
+        let first_check = self.nonce_drift.last_check().is_none();
+        match self
+            .nonce_drift
+            .check(reserved_nonce, chain_nonce, timestamp)
+        {
+            NonceDrift::InSync => None,
+            NonceDrift::Ahead { from, to } if first_check => {
+                log::info!("Reserved nonce {from} is synchronized with the EVM address nonce {to}");
+                Some(to)
+            }
+            NonceDrift::Ahead { from, to } => {
+                log::warn!(
+                    "EVM address nonce {to} is ahead of the reserved nonce {from}, a transaction was probably sent outside the bridge. The reserved nonce is fast-forwarded to {to}"
+                );
+                Some(to)
+            }
+            NonceDrift::Behind { gap, new_gap: true } => {
+                log::error!(
+                    "EVM address nonce {} is behind the reserved nonce {}, transactions sent by the bridge are not visible. Mint transactions sending is paused until the gap resolves or is acknowledged",
+                    gap.chain_nonce,
+                    gap.local_nonce
+                );
+                None
+            }
+            NonceDrift::Behind {
+                gap,
+                new_gap: false,
+            } => {
+                log::warn!(
+                    "EVM address nonce {} is still behind the reserved nonce {}",
+                    gap.chain_nonce,
+                    gap.local_nonce
+                );
+                None
+            }
+            NonceDrift::GapResolved(gap) => {
+                log::info!(
+                    "EVM address nonce gap, detected at {}, is resolved. Mint transactions sending is resumed",
+                    gap.detected_at
+                );
+                None
+            }
+        }
+    }
+
+    /// Checks if mint transactions sending is paused due to the nonce gap.
+    pub fn is_nonce_gap_open(&self) -> bool {
+        self.nonce_drift.is_gap_open()
+    }
+
+    /// Closes the open nonce gap and resumes mint transactions sending. The reserved nonce
+    /// is moved back to the chain nonce, so the next transaction fills the gap.
+    /// Returns the closed gap, if it was open.
+    pub fn acknowledge_nonce_gap(&mut self) -> Option<NonceGap> {
+        let gap = self.nonce_drift.acknowledge_gap()?;
+        self.update_evm_params(|p| p.nonce = gap.chain_nonce);
+        Some(gap)
+    }
+
+    /// Closes the open nonce gap only if it consists exactly of the given nonces,
+    /// e.g. of the transactions known to be dropped from the EVM mempool.
+    /// Returns the closed gap, if it was open and matched the nonces.
+    pub fn acknowledge_nonce_gap_of(&mut self, nonces: &BTreeSet<u64>) -> Option<NonceGap> {
+        let gap = self.nonce_drift.gap()?;
+        if !(gap.chain_nonce..gap.local_nonce).eq(nonces.iter().copied()) {
+            return None;
+        }
+
+        self.acknowledge_nonce_gap()
+    }
+
+    /// Returns diagnostic info of the nonce drift of the bridge canister EVM address.
+    pub fn get_nonce_drift_status(&self) -> NonceDriftStatus {
+        let local_nonce = self.get_evm_params().ok().map(|params| params.nonce);
+        self.nonce_drift.status(local_nonce)
+    }
+
+    /// Returns percentiles of the EVM node latency.
+    pub fn get_evm_latency_stats(&self) -> LatencyStats {
+        self.evm_latency
+            .stats(self.get_max_acceptable_evm_latency_ms())
+    }
+
+    /// Returns the destinations to be notified when an operation is complete.
+    pub fn get_notification_destinations(&self) -> Vec<OutboxDestination> {
+        self.config
+            .get()
+            .notification_destinations
+            .clone()
+            .unwrap_or_default()
+    }
+
+    /// Sets the destinations to be notified when an operation is complete.
+    pub fn set_notification_destinations(&mut self, destinations: Vec<OutboxDestination>) {
+        self.update(|config| config.notification_destinations = Some(destinations));
+    }
+
+    /// Checks if the operation logs should be compressed in the stable memory.
+    pub fn is_operation_log_compression_enabled(&self) -> bool {
+        self.config
+            .get()
+            .compress_operation_logs
+            .unwrap_or_default()
+    }
+
+    /// Enables or disables compression of the operation logs in the stable memory.
+    pub fn set_operation_log_compression(&mut self, enabled: bool) {
+        self.update(|config| config.compress_operation_logs = Some(enabled));
+    }
+
+    /// Checks if the malformed BTF bridge events are rejected instead of being normalized.
+    pub fn is_strict_event_validation_enabled(&self) -> bool {
+        self.config
+            .get()
+            .strict_event_validation
+            .unwrap_or_default()
+    }
+
+    /// Enables or disables rejection of the malformed BTF bridge events.
+    pub fn set_strict_event_validation(&mut self, enabled: bool) {
+        self.update(|config| config.strict_event_validation = Some(enabled));
+    }
+
+    /// Checks if the timer-driven processing of the bridge is enabled. Enabled by default.
+    pub fn is_timers_enabled(&self) -> bool {
+        self.config.get().timers_enabled.unwrap_or(true)
+    }
+
+    /// Suspends or resumes the timer-driven processing of the bridge.
+    pub fn set_timers_enabled(&mut self, enabled: bool) {
+        self.update(|config| config.timers_enabled = Some(enabled));
+    }
+
+    /// Returns min interval between the EVM params refreshes before sending a mint transaction.
+    /// If `None`, the mint transactions are sent with the cached params.
+    pub fn get_gas_price_refresh_interval_secs(&self) -> Option<u64> {
+        self.config.get().gas_price_refresh_interval_secs
+    }
+
+    /// Sets min interval between the EVM params refreshes before sending a mint transaction.
+    pub fn set_gas_price_refresh_interval_secs(&mut self, interval_secs: Option<u64>) {
+        self.update(|config| config.gas_price_refresh_interval_secs = interval_secs);
+    }
+
+    /// Returns max number of the concurrently progressing operations per direction.
+    pub fn get_operation_concurrency_limits(&self) -> OperationConcurrencyLimits {
+        self.config
+            .get()
+            .operation_concurrency_limits
+            .clone()
+            .unwrap_or_default()
+    }
+
+    /// Sets max number of the concurrently progressing operations per direction.
+    pub fn set_operation_concurrency_limits(&mut self, limits: OperationConcurrencyLimits) {
+        self.update(|config| config.operation_concurrency_limits = Some(limits));
+    }
+
+    /// Returns the concurrency limits, applied to the operations progress.
+    /// All the directions are paused while the bridge is halted.
+    pub fn get_effective_operation_concurrency_limits(&self) -> OperationConcurrencyLimits {
+        if self.config.get().halt.is_some() {
+            return OperationConcurrencyLimits::PAUSED;
+        }
+
+        self.get_operation_concurrency_limits()
+    }
+
+    /// Returns the bridge halt, if the bridge is halted.
+    pub fn get_bridge_halt(&self) -> Option<BridgeHalt> {
+        self.config.get().halt.clone()
+    }
+
+    /// Halts the bridge. Returns `false` and keeps the original halt, if the bridge
+    /// is already halted.
+    pub fn halt_bridge(&mut self, halt: BridgeHalt) -> bool {
+        if self.config.get().halt.is_some() {
+            return false;
+        }
+
+        log::error!("bridge is halted: {}", halt.reason);
+        self.update(|config| config.halt = Some(halt));
+        true
+    }
+
+    /// Resumes the progress of the halted bridge operations.
+    pub fn resume_bridge(&mut self) {
+        self.update(|config| config.halt = None);
+    }
+
+    /// Returns the mode of the canister.
+    pub fn get_bridge_mode(&self) -> BridgeMode {
+        self.config.get().mode.clone().unwrap_or_default()
+    }
+
+    /// Checks if the canister is a replica, which doesn't process the operations.
+    pub fn is_replica(&self) -> bool {
+        self.get_bridge_mode().is_replica()
+    }
+
+    /// Returns an error if the canister is a replica. Should be checked by the methods,
+    /// which create or progress the operations.
+    pub fn check_primary_mode(&self) -> BTFResult<()> {
+        match self.is_replica() {
+            true => Err(Error::ReplicaMode),
+            false => Ok(()),
+        }
+    }
+
+    /// Switches the canister to the primary mode.
+    pub fn set_primary_mode(&mut self) {
+        self.update(|config| config.mode = Some(BridgeMode::Primary));
+    }
+
+    /// Returns the relay, which mint transactions are sent to instead of the public mempool.
+    pub fn get_private_tx_relay(&self) -> Option<PrivateTxRelay> {
+        self.config.get().private_tx_relay.clone()
+    }
+
+    /// Sets the relay, which mint transactions are sent to instead of the public mempool.
+    /// If `None`, mint transactions are sent to the public mempool.
+    pub fn set_private_tx_relay(&mut self, relay: Option<PrivateTxRelay>) {
+        self.update(|config| config.private_tx_relay = relay);
+    }
+
+    /// Returns the time after sending a mint transaction, when it is checked to be dropped
+    /// from the EVM mempool. If `None`, the sent mint transactions are not checked.
+    pub fn get_dropped_tx_timeout_secs(&self) -> Option<u64> {
+        self.config.get().dropped_tx_timeout_secs
+    }
+
+    /// Sets the time after sending a mint transaction, when it is checked to be dropped
+    /// from the EVM mempool.
+    pub fn set_dropped_tx_timeout_secs(&mut self, timeout_secs: Option<u64>) {
+        self.update(|config| config.dropped_tx_timeout_secs = timeout_secs);
+    }
+
+    /// Returns settings of the mint fees reconciliation, or `None` if it is disabled.
+    pub fn get_fee_reconciliation(&self) -> Option<FeeReconciliationSettings> {
+        self.config.get().fee_reconciliation.clone()
+    }
+
+    /// Sets settings of the mint fees reconciliation. If `None`, the reconciliation is disabled.
+    pub fn set_fee_reconciliation(&mut self, settings: Option<FeeReconciliationSettings>) {
+        self.update(|config| config.fee_reconciliation = settings);
+    }
+
+    /// Returns max number of the operations, returned by the operations list of an address,
+    /// if no pagination is requested.
+    pub fn get_operations_list_default_limit(&self) -> u64 {
+        self.config
+            .get()
+            .operations_list_default_limit
+            .unwrap_or(DEFAULT_OPERATIONS_LIST_LIMIT)
+    }
+
+    /// Sets max number of the operations, returned by the operations list of an address,
+    /// if no pagination is requested. If `None`, the default limit is used.
+    pub fn set_operations_list_default_limit(&mut self, limit: Option<u64>) {
+        self.update(|config| config.operations_list_default_limit = limit);
+    }
+
+    /// Returns the filter of the collected events by an indexed parameter topic, if any.
+    pub fn get_event_topic_filter(&self) -> Option<EventTopicFilter> {
+        self.config.get().event_topic_filter.clone()
+    }
+
+    /// Sets the filter of the collected events by an indexed parameter topic.
+    /// If `None`, all the events of the BTF bridge contract are collected.
+    pub fn set_event_topic_filter(&mut self, filter: Option<EventTopicFilter>) {
+        self.update(|config| config.event_topic_filter = filter);
+    }
+
+    /// Checks if the gas price and nonce should be refreshed before sending a mint transaction
+    /// at the `now` IC time.
+    pub fn should_refresh_evm_params_before_send(&self, now: Timestamp) -> bool {
+        let Some(interval_secs) = self.get_gas_price_refresh_interval_secs() else {
+            return false;
+        };
+
+        match self.evm_params_refreshed_at {
+            Some(refreshed_at) => {
+                now.saturating_sub(refreshed_at) >= interval_secs.saturating_mul(1_000_000_000)
+            }
+            None => true,
+        }
     }
 
     /// Creates a signer according to `Self::signing_strategy`.
     pub fn get_signer(&self) -> BTFResult<impl TransactionSigner> {
-        let config = self.0.get();
+        let config = self.config.get();
         let chain_id = self.get_evm_params()?.chain_id;
         config
             .signing_strategy
@@ -211,21 +845,131 @@ impl ConfigStorage {
             .map_err(|e| Error::Signing(e.to_string()))
     }
 
-    /// Updates signing strategy.
+    /// Updates signing strategy. The cached signer address is invalidated.
     pub fn set_signing_strategy(&mut self, strategy: SigningStrategy) {
         self.update(|config| config.signing_strategy = strategy);
+        self.signer_address = None;
+    }
+
+    /// Returns the address of the signer.
+    ///
+    /// Deriving the address may require a management canister call, so the address is
+    /// resolved once and then served from the cache until the signing strategy is changed.
+    pub async fn get_signer_address(config: Rc<RefCell<Self>>) -> BTFResult<H160> {
+        Self::get_signer_address_with(config.clone(), || async move {
+            let signer = config.borrow().get_signer()?;
+            Ok(signer.get_address().await?)
+        })
+        .await
+    }
+
+    async fn get_signer_address_with<F, Fut>(config: Rc<RefCell<Self>>, fetch: F) -> BTFResult<H160>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = BTFResult<H160>>,
+    {
+        let strategy = config.borrow().get_signing_strategy();
+        if let Some(address) = config.borrow().get_cached_signer_address(&strategy) {
+            return Ok(address);
+        }
+
+        let address = fetch().await?;
+
+        // The strategy could be changed while the address was resolved. In this case the
+        // cached address is never served, since it is bound to the previous strategy.
+        config.borrow_mut().signer_address = Some((strategy, address.clone()));
+
+        Ok(address)
+    }
+
+    /// Returns the digest of the context, observed by the operation progress. The signer
+    /// address is included only if it is already resolved.
+    pub fn get_replay_context_digest(&self) -> ReplayContextDigest {
+        let evm_params_hash = self.config.get().evm_params.as_ref().map(|params| {
+            H256::from_slice(&keccak256(
+                Encode!(params).expect("failed to encode evm params"),
+            ))
+        });
+
+        ReplayContextDigest {
+            evm_params_index: self.get_evm_params_snapshot_index(),
+            evm_params_hash,
+            signer_address: self.get_cached_signer_address(&self.get_signing_strategy()),
+        }
+    }
+
+    fn get_cached_signer_address(&self, strategy: &SigningStrategy) -> Option<H160> {
+        self.signer_address
+            .as_ref()
+            .filter(|(cached_strategy, _)| cached_strategy == strategy)
+            .map(|(_, address)| address.clone())
     }
 
     /// Returns signing strategy.
     pub fn get_signing_strategy(&self) -> SigningStrategy {
-        self.0.get().signing_strategy.clone()
+        self.config.get().signing_strategy.clone()
+    }
+
+    /// Rewrites the stored config in the current encoding version.
+    pub fn migrate(&mut self) {
+        self.update(|_| {});
     }
 
-    /// Updates config data.
+    /// Returns the user-relevant configuration of the bridge.
+    pub fn get_bridge_manifest(&self) -> BridgeManifest {
+        let config = self.config.get();
+        BridgeManifest {
+            manifest_version: config.manifest_version.unwrap_or_default(),
+            btf_bridge_contract: config.btf_bridge_contract_address.clone(),
+            chain_id: config.evm_params.as_ref().map(|params| params.chain_id),
+            evm_finality: config.evm_finality.unwrap_or_default(),
+            strict_event_validation: config.strict_event_validation.unwrap_or_default(),
+            timers_enabled: config.timers_enabled.unwrap_or(true),
+            limits: BridgeLimits {
+                max_operation_blocks_per_request: MAX_BLOCKS_PER_REQUEST,
+                max_mint_orders_in_batch: MAX_MINT_ORDERS_IN_BATCH as u64,
+            },
+        }
+    }
+
+    /// Updates the config. If any of the settings included into the bridge manifest
+    /// is changed, the manifest version is increased.
     pub fn update(&mut self, f: impl FnOnce(&mut Config)) {
-        let mut config = self.0.get().clone();
+        let current = self.config.get();
+        let manifest_version = current.manifest_version.unwrap_or_default();
+        let manifest_settings = ManifestSettings::from(current);
+
+        let mut config = current.clone();
         f(&mut config);
-        self.0.set(config).expect("failed to update config");
+
+        config.manifest_version = if ManifestSettings::from(&config) == manifest_settings {
+            Some(manifest_version)
+        } else {
+            Some(manifest_version + 1)
+        };
+        self.config.set(config).expect("failed to update config");
+    }
+}
+
+/// Config values included into the bridge manifest.
+#[derive(PartialEq, Eq)]
+struct ManifestSettings {
+    btf_bridge_contract: Option<H160>,
+    chain_id: Option<u64>,
+    evm_finality: EvmFinality,
+    strict_event_validation: Option<bool>,
+    timers_enabled: bool,
+}
+
+impl From<&Config> for ManifestSettings {
+    fn from(config: &Config) -> Self {
+        Self {
+            btf_bridge_contract: config.btf_bridge_contract_address.clone(),
+            chain_id: config.evm_params.as_ref().map(|params| params.chain_id),
+            evm_finality: config.evm_finality.unwrap_or_default(),
+            strict_event_validation: config.strict_event_validation,
+            timers_enabled: config.timers_enabled.unwrap_or(true),
+        }
     }
 }
 
@@ -236,6 +980,30 @@ pub struct Config {
     pub evm_params: Option<EvmParams>,
     pub btf_bridge_contract_address: Option<H160>,
     pub signing_strategy: SigningStrategy,
+    pub evm_finality: Option<EvmFinality>,
+    pub nonce_offset: u64,
+    pub gas_balance_thresholds: Option<GasBalanceThresholds>,
+    pub compress_operation_logs: Option<bool>,
+    pub evm_params_history_size: Option<u32>,
+    pub max_acceptable_evm_latency_ms: Option<u64>,
+    pub notification_destinations: Option<Vec<OutboxDestination>>,
+    pub strict_event_validation: Option<bool>,
+    pub btf_bridge_activation_block: Option<u64>,
+    pub manifest_version: Option<u64>,
+    pub gas_price_refresh_interval_secs: Option<u64>,
+    pub operation_concurrency_limits: Option<OperationConcurrencyLimits>,
+    pub private_tx_relay: Option<PrivateTxRelay>,
+    pub dropped_tx_timeout_secs: Option<u64>,
+    pub fee_reconciliation: Option<FeeReconciliationSettings>,
+    pub event_topic_filter: Option<EventTopicFilter>,
+    pub halt: Option<BridgeHalt>,
+    pub mode: Option<BridgeMode>,
+    pub operations_list_default_limit: Option<u64>,
+    pub timers_enabled: Option<bool>,
+    pub rpc_request_timeout_secs: Option<u64>,
+    pub http_transform: Option<HttpTransformSettings>,
+    pub retry_profiles: Option<RetryProfiles>,
+    pub replay_capture: Option<ReplayCaptureSettings>,
 }
 
 impl Default for Config {
@@ -248,27 +1016,199 @@ impl Default for Config {
             signing_strategy: SigningStrategy::ManagementCanister {
                 key_id: eth_signer::ic_sign::SigningKeyId::Test,
             },
+            evm_finality: None,
+            nonce_offset: 0,
+            gas_balance_thresholds: None,
+            compress_operation_logs: None,
+            evm_params_history_size: None,
+            max_acceptable_evm_latency_ms: None,
+            notification_destinations: None,
+            strict_event_validation: None,
+            btf_bridge_activation_block: None,
+            manifest_version: None,
+            gas_price_refresh_interval_secs: None,
+            operation_concurrency_limits: None,
+            private_tx_relay: None,
+            dropped_tx_timeout_secs: None,
+            fee_reconciliation: None,
+            event_topic_filter: None,
+            halt: None,
+            mode: None,
+            operations_list_default_limit: None,
+            timers_enabled: None,
+            rpc_request_timeout_secs: None,
+            http_transform: None,
+            retry_profiles: None,
+            replay_capture: None,
         }
     }
 }
 
 impl Storable for Config {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
-        codec::encode(&self).into()
+        self.to_versioned_bytes().into()
     }
 
     fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
-        codec::decode(bytes.as_ref())
+        Self::from_versioned_bytes(&bytes).expect("failed to decode config")
     }
 
     const BOUND: ic_stable_structures::Bound = ic_stable_structures::Bound::Unbounded;
 }
 
+/// Version 2 added `evm_finality` and `nonce_offset` fields and is encoded with candid.
+/// Version 3 changed type of the `EvmParams::chain_id` from `u32` to `u64`.
+/// The `evm_finality` field is optional since then, and its values stored in the older
+/// payloads of version 3 are decoded as `Some`.
+///
+/// Optional fields added later, like `gas_balance_thresholds`, `compress_operation_logs`,
+/// `evm_params_history_size`, `max_acceptable_evm_latency_ms`, `notification_destinations` and
+/// `strict_event_validation`, are decoded as `None` from the older payloads of the same version, so they don't need a version bump.
+impl Versioned for Config {
+    const CURRENT_VERSION: u16 = 3;
+
+    fn encode_payload(&self) -> Vec<u8> {
+        Encode!(self).expect("failed to encode config")
+    }
+
+    fn decode_payload(version: u16, payload: &[u8]) -> BTFResult<Self> {
+        match version {
+            1 => Ok(codec::decode::<ConfigV1>(payload).into()),
+            2 => Decode!(payload, ConfigV2)
+                .map(Into::into)
+                .map_err(|e| Error::Serialization(e.to_string())),
+            3 => Decode!(payload, Self).map_err(|e| Error::Serialization(e.to_string())),
+            _ => Err(Error::Serialization(format!(
+                "unknown config version {version}"
+            ))),
+        }
+    }
+}
+
+/// EVM params shape stored in config versions 1 and 2.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+struct EvmParamsV1 {
+    chain_id: u32,
+    next_block: u64,
+    nonce: u64,
+    gas_price: U256,
+}
+
+impl From<EvmParamsV1> for EvmParams {
+    fn from(v1: EvmParamsV1) -> Self {
+        Self {
+            chain_id: v1.chain_id.into(),
+            next_block: v1.next_block,
+            nonce: v1.nonce,
+            gas_price: v1.gas_price,
+        }
+    }
+}
+
+/// Config shape stored without version header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ConfigV1 {
+    owner: Principal,
+    evm_link: EvmLink,
+    evm_params: Option<EvmParamsV1>,
+    btf_bridge_contract_address: Option<H160>,
+    signing_strategy: SigningStrategy,
+}
+
+impl From<ConfigV1> for Config {
+    fn from(v1: ConfigV1) -> Self {
+        Self {
+            owner: v1.owner,
+            evm_link: v1.evm_link,
+            evm_params: v1.evm_params.map(Into::into),
+            btf_bridge_contract_address: v1.btf_bridge_contract_address,
+            signing_strategy: v1.signing_strategy,
+            evm_finality: None,
+            nonce_offset: 0,
+            gas_balance_thresholds: None,
+            compress_operation_logs: None,
+            evm_params_history_size: None,
+            max_acceptable_evm_latency_ms: None,
+            notification_destinations: None,
+            strict_event_validation: None,
+            btf_bridge_activation_block: None,
+            manifest_version: None,
+            gas_price_refresh_interval_secs: None,
+            operation_concurrency_limits: None,
+            private_tx_relay: None,
+            dropped_tx_timeout_secs: None,
+            fee_reconciliation: None,
+            event_topic_filter: None,
+            halt: None,
+            mode: None,
+            operations_list_default_limit: None,
+            timers_enabled: None,
+            rpc_request_timeout_secs: None,
+            http_transform: None,
+            retry_profiles: None,
+            replay_capture: None,
+        }
+    }
+}
+
+/// Config shape of version 2.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+struct ConfigV2 {
+    owner: Principal,
+    evm_link: EvmLink,
+    evm_params: Option<EvmParamsV1>,
+    btf_bridge_contract_address: Option<H160>,
+    signing_strategy: SigningStrategy,
+    evm_finality: EvmFinality,
+    nonce_offset: u64,
+}
+
+impl From<ConfigV2> for Config {
+    fn from(v2: ConfigV2) -> Self {
+        Self {
+            owner: v2.owner,
+            evm_link: v2.evm_link,
+            evm_params: v2.evm_params.map(Into::into),
+            btf_bridge_contract_address: v2.btf_bridge_contract_address,
+            signing_strategy: v2.signing_strategy,
+            evm_finality: Some(v2.evm_finality),
+            nonce_offset: v2.nonce_offset,
+            gas_balance_thresholds: None,
+            compress_operation_logs: None,
+            evm_params_history_size: None,
+            max_acceptable_evm_latency_ms: None,
+            notification_destinations: None,
+            strict_event_validation: None,
+            btf_bridge_activation_block: None,
+            manifest_version: None,
+            gas_price_refresh_interval_secs: None,
+            operation_concurrency_limits: None,
+            private_tx_relay: None,
+            dropped_tx_timeout_secs: None,
+            fee_reconciliation: None,
+            event_topic_filter: None,
+            halt: None,
+            mode: None,
+            operations_list_default_limit: None,
+            timers_enabled: None,
+            rpc_request_timeout_secs: None,
+            http_transform: None,
+            retry_profiles: None,
+            replay_capture: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use ic_stable_structures::Storable;
+    use bridge_did::gas_balance::GasBalanceHealth;
+    use bridge_did::nonce_drift::NonceFastForward;
+    use bridge_did::versioned::{split_version, VERSION_MARK};
+    use ic_exports::ic_kit::MockContext;
+    use ic_stable_structures::{MemoryId, Storable};
 
-    use crate::runtime::state::config::Config;
+    use super::*;
+    use crate::memory::memory_by_id;
 
     #[test]
     fn config_serialization() {
@@ -277,4 +1217,592 @@ mod tests {
         let decoded = Config::from_bytes(encoded);
         assert_eq!(config, decoded);
     }
+
+    #[test]
+    fn config_v1_is_migrated() {
+        let v1 = ConfigV1 {
+            owner: Principal::from_slice(&[1; 29]),
+            evm_link: EvmLink::Http("https://evm.example".into()),
+            evm_params: Some(EvmParamsV1 {
+                nonce: 3,
+                gas_price: 10u64.into(),
+                chain_id: 355113,
+                next_block: 42,
+            }),
+            btf_bridge_contract_address: Some(H160::from_slice(&[2; 20])),
+            signing_strategy: SigningStrategy::Local {
+                private_key: [3; 32],
+            },
+        };
+        let legacy_bytes = codec::encode(&v1);
+
+        let config = Config::from_bytes(legacy_bytes.into());
+
+        assert_eq!(config.owner, v1.owner);
+        assert_eq!(config.evm_link, v1.evm_link);
+        assert_eq!(config.evm_params, v1.evm_params.clone().map(Into::into));
+        assert_eq!(
+            config.btf_bridge_contract_address,
+            v1.btf_bridge_contract_address
+        );
+        assert_eq!(config.signing_strategy, v1.signing_strategy);
+        assert_eq!(config.evm_finality, None);
+        assert_eq!(config.nonce_offset, 0);
+
+        let stored = config.to_bytes();
+        assert_eq!(split_version(&stored).0, Config::CURRENT_VERSION);
+        assert_eq!(Config::from_bytes(stored), config);
+    }
+
+    #[test]
+    fn config_v2_is_migrated() {
+        let v2 = ConfigV2 {
+            owner: Principal::from_slice(&[1; 29]),
+            evm_link: EvmLink::Http("https://evm.example".into()),
+            evm_params: Some(EvmParamsV1 {
+                nonce: 3,
+                gas_price: 10u64.into(),
+                chain_id: u32::MAX,
+                next_block: 42,
+            }),
+            btf_bridge_contract_address: Some(H160::from_slice(&[2; 20])),
+            signing_strategy: SigningStrategy::Local {
+                private_key: [3; 32],
+            },
+            evm_finality: EvmFinality::Safe,
+            nonce_offset: 100,
+        };
+        let mut bytes = VERSION_MARK.to_vec();
+        bytes.extend_from_slice(&2u16.to_be_bytes());
+        bytes.extend_from_slice(&Encode!(&v2).unwrap());
+
+        let config = Config::from_bytes(bytes.into());
+
+        assert_eq!(config.owner, v2.owner);
+        assert_eq!(
+            config.evm_params.as_ref().unwrap().chain_id,
+            u32::MAX as u64
+        );
+        assert_eq!(config.evm_finality, Some(EvmFinality::Safe));
+        assert_eq!(config.nonce_offset, 100);
+    }
+
+    #[test]
+    fn config_v3_without_gas_balance_thresholds_is_decoded() {
+        #[derive(CandidType)]
+        struct ConfigV3WithoutThresholds {
+            owner: Principal,
+            evm_link: EvmLink,
+            evm_params: Option<EvmParams>,
+            btf_bridge_contract_address: Option<H160>,
+            signing_strategy: SigningStrategy,
+            evm_finality: EvmFinality,
+            nonce_offset: u64,
+        }
+
+        let stored = ConfigV3WithoutThresholds {
+            owner: Principal::from_slice(&[1; 29]),
+            evm_link: EvmLink::Http("https://evm.example".into()),
+            evm_params: None,
+            btf_bridge_contract_address: None,
+            signing_strategy: SigningStrategy::Local {
+                private_key: [3; 32],
+            },
+            evm_finality: EvmFinality::Safe,
+            nonce_offset: 7,
+        };
+        let mut bytes = VERSION_MARK.to_vec();
+        bytes.extend_from_slice(&3u16.to_be_bytes());
+        bytes.extend_from_slice(&Encode!(&stored).unwrap());
+
+        let config = Config::from_bytes(bytes.into());
+
+        assert_eq!(config.owner, stored.owner);
+        assert_eq!(config.evm_finality, Some(EvmFinality::Safe));
+        assert_eq!(config.nonce_offset, 7);
+        assert_eq!(config.gas_balance_thresholds, None);
+        assert_eq!(config.compress_operation_logs, None);
+        assert_eq!(config.evm_params_history_size, None);
+        assert_eq!(config.max_acceptable_evm_latency_ms, None);
+        assert_eq!(config.notification_destinations, None);
+        assert_eq!(config.strict_event_validation, None);
+    }
+
+    #[test]
+    fn config_with_u64_chain_id_roundtrip() {
+        let config = Config {
+            evm_params: Some(EvmParams {
+                chain_id: u64::MAX,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let decoded = Config::from_bytes(config.to_bytes());
+        assert_eq!(decoded, config);
+    }
+
+    #[test]
+    fn gas_balance_switches_mint_tx_sponsorship() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        let address = H160::from_slice(&[1; 20]);
+
+        // Balance is ignored until thresholds are configured.
+        config.record_gas_balance(address.clone(), 0u64.into(), 1);
+        assert!(config.is_mint_tx_sponsorship_enabled());
+        assert!(config.get_gas_balance_status().history.is_empty());
+
+        config.set_gas_balance_thresholds(Some(GasBalanceThresholds {
+            low_watermark: 1000u64.into(),
+            critical_watermark: 100u64.into(),
+        }));
+        config.record_gas_balance(address.clone(), 50u64.into(), 2);
+        assert!(!config.is_mint_tx_sponsorship_enabled());
+
+        let status = config.get_gas_balance_status();
+        assert_eq!(status.address, Some(address.clone()));
+        assert_eq!(status.health, GasBalanceHealth::Failing);
+        assert!(!status.mint_tx_sponsorship_enabled);
+        assert_eq!(status.last_alert.unwrap().alert, GasBalanceAlert::Critical);
+
+        config.record_gas_balance(address.clone(), 5000u64.into(), 3);
+        assert!(config.is_mint_tx_sponsorship_enabled());
+        assert_eq!(
+            config.get_gas_balance_status().last_alert.unwrap().alert,
+            GasBalanceAlert::Recovered
+        );
+
+        // Removing the thresholds enables sponsorship.
+        config.record_gas_balance(address, 0u64.into(), 4);
+        assert!(!config.is_mint_tx_sponsorship_enabled());
+        config.set_gas_balance_thresholds(None);
+        assert!(config.is_mint_tx_sponsorship_enabled());
+        assert_eq!(
+            config.get_gas_balance_status().health,
+            GasBalanceHealth::Unknown
+        );
+    }
+
+    #[test]
+    fn evm_params_snapshot_is_taken_on_change() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        assert_eq!(config.get_evm_params_snapshot_index(), None);
+
+        config.update_evm_params(|p| p.gas_price = 10u64.into());
+        assert_eq!(config.get_evm_params_snapshot_index(), Some(0));
+
+        // No-op refresh doesn't create a snapshot.
+        config.update_evm_params(|p| p.gas_price = 10u64.into());
+        assert_eq!(config.get_evm_params_snapshot_index(), Some(0));
+
+        config.update_evm_params(|p| p.nonce += 1);
+        assert_eq!(config.get_evm_params_snapshot_index(), Some(1));
+
+        let history = config.get_evm_params_history(0, 10);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].nonce, 1);
+        assert_eq!(history[1].gas_price, 10u64.into());
+
+        config.set_evm_params_history_size(1);
+        assert_eq!(config.get_evm_params_history_size(), 1);
+        assert_eq!(config.get_evm_params_history(0, 10).len(), 1);
+        config.update_evm_params(|p| p.nonce += 1);
+        assert_eq!(config.get_evm_params_history(0, 10)[0].index, 2);
+        assert_eq!(config.get_evm_params_history(0, 10).len(), 1);
+        assert_eq!(config.get_evm_params_snapshot(1), None);
+        assert_eq!(config.get_evm_params_snapshot(2).unwrap().nonce, 2);
+    }
+
+    #[test]
+    fn evm_latency_is_recorded() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        assert_eq!(config.get_evm_latency_stats(), LatencyStats::default());
+
+        for latency_ms in 1..=100 {
+            config.record_evm_latency(latency_ms, latency_ms * 1_000_000);
+        }
+        let stats = config.get_evm_latency_stats();
+        assert_eq!(stats.samples_count, 100);
+        assert_eq!(stats.p95_ns, 95_000_000);
+        assert!(!stats.is_degraded());
+
+        config.set_max_acceptable_evm_latency_ms(Some(90));
+        let stats = config.get_evm_latency_stats();
+        assert_eq!(stats.max_acceptable_latency_ms, Some(90));
+        assert!(stats.is_degraded());
+    }
+
+    #[test]
+    fn btf_bridge_activation_block_is_recorded_on_switch() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+
+        // Contract set before the EVM params are initialized is active from the genesis.
+        config.set_btf_bridge_contract(H160::from_slice(&[1; 20]));
+        assert_eq!(config.get_btf_bridge_activation_block(), 0);
+
+        config.update_evm_params(|p| p.next_block = 100);
+        config.set_btf_bridge_contract(H160::from_slice(&[2; 20]));
+        assert_eq!(config.get_btf_bridge_activation_block(), 100);
+
+        // Setting the same address doesn't move the activation block.
+        config.update_evm_params(|p| p.next_block = 200);
+        config.set_btf_bridge_contract(H160::from_slice(&[2; 20]));
+        assert_eq!(config.get_btf_bridge_activation_block(), 100);
+    }
+
+    #[test]
+    fn manifest_version_is_increased_on_manifest_settings_change() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        let initial = config.get_bridge_manifest().manifest_version;
+
+        config.set_btf_bridge_contract(H160::from_slice(&[1; 20]));
+        assert_eq!(config.get_bridge_manifest().manifest_version, initial + 1);
+
+        config.update_evm_params(|p| p.chain_id = 355113);
+        assert_eq!(config.get_bridge_manifest().manifest_version, initial + 2);
+
+        config.set_evm_finality(EvmFinality::Finalized);
+        assert_eq!(config.get_bridge_manifest().manifest_version, initial + 3);
+
+        config.set_strict_event_validation(true);
+        assert_eq!(config.get_bridge_manifest().manifest_version, initial + 4);
+
+        config.set_timers_enabled(false);
+        assert_eq!(config.get_bridge_manifest().manifest_version, initial + 5);
+
+        // Settings not included into the manifest don't change the version.
+        config.update_evm_params(|p| {
+            p.gas_price = 42u64.into();
+            p.next_block += 1;
+        });
+        config.set_nonce_offset(1000);
+        config.set_operation_log_compression(true);
+        config.set_strict_event_validation(true);
+        config.set_timers_enabled(false);
+        assert_eq!(config.get_bridge_manifest().manifest_version, initial + 5);
+    }
+
+    #[test]
+    fn manifest_contains_config_values() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        let contract = H160::from_slice(&[2; 20]);
+        config.set_btf_bridge_contract(contract.clone());
+        config.update_evm_params(|p| p.chain_id = 42);
+        config.set_evm_finality(EvmFinality::Confirmations(12));
+        config.set_strict_event_validation(true);
+
+        let manifest = config.get_bridge_manifest();
+        assert_eq!(
+            manifest,
+            BridgeManifest {
+                manifest_version: manifest.manifest_version,
+                btf_bridge_contract: Some(contract),
+                chain_id: Some(42),
+                evm_finality: EvmFinality::Confirmations(12),
+                strict_event_validation: true,
+                timers_enabled: true,
+                limits: BridgeLimits {
+                    max_operation_blocks_per_request: MAX_BLOCKS_PER_REQUEST,
+                    max_mint_orders_in_batch: MAX_MINT_ORDERS_IN_BATCH as u64,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn timers_enabled_flag_persists_across_upgrade() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        assert!(config.is_timers_enabled());
+
+        config.set_timers_enabled(false);
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        config.migrate();
+        assert!(!config.is_timers_enabled());
+
+        config.set_timers_enabled(true);
+        assert!(config.is_timers_enabled());
+    }
+
+    #[test]
+    fn task_options_follow_retry_profiles() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        assert_eq!(
+            config.get_task_options(RetryProfileKind::MintSend),
+            TaskOptions::new()
+                .with_max_retries_policy(3)
+                .with_backoff_policy(BackoffPolicy::Exponential {
+                    secs: 2,
+                    multiplier: 4,
+                })
+        );
+
+        let mut profiles = RetryProfiles::default();
+        profiles.mint_send.backoff = RetryBackoff::Fixed { secs: 30 };
+        profiles.dependency_wait.retries = RetryLimit::Unbounded;
+        profiles.allow_unbounded_retries = true;
+        config.set_retry_profiles(Some(profiles));
+
+        assert_eq!(
+            config.get_task_options(RetryProfileKind::MintSend),
+            TaskOptions::new()
+                .with_max_retries_policy(3)
+                .with_fixed_backoff_policy(30)
+        );
+        assert_eq!(
+            config.get_task_options(RetryProfileKind::DependencyWait),
+            TaskOptions::new()
+                .with_retry_policy(RetryPolicy::Infinite)
+                .with_fixed_backoff_policy(10)
+        );
+
+        config.set_retry_profiles(None);
+        assert_eq!(config.get_retry_profiles(), RetryProfiles::default());
+    }
+
+    #[test]
+    fn evm_params_refresh_before_send() {
+        const SECOND: u64 = 1_000_000_000;
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        let now = 1000 * SECOND;
+
+        // Disabled by default, the cached params are used.
+        assert!(!config.should_refresh_evm_params_before_send(now));
+
+        config.set_gas_price_refresh_interval_secs(Some(10));
+        assert!(config.should_refresh_evm_params_before_send(now));
+
+        config.evm_params_refreshed_at = Some(now - 5 * SECOND);
+        assert!(!config.should_refresh_evm_params_before_send(now));
+
+        config.evm_params_refreshed_at = Some(now - 10 * SECOND);
+        assert!(config.should_refresh_evm_params_before_send(now));
+
+        config.set_gas_price_refresh_interval_secs(None);
+        assert!(!config.should_refresh_evm_params_before_send(now));
+    }
+
+    #[test]
+    fn chain_nonce_ahead_is_fast_forwarded() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        config.update_evm_params(|p| p.nonce = 5);
+
+        // Initial synchronization.
+        config.record_refreshed_evm_params(5, 5, 10u64.into(), 1);
+        assert_eq!(config.get_evm_params().unwrap().nonce, 5);
+
+        // A transaction was sent from the address outside the bridge.
+        config.record_refreshed_evm_params(5, 7, 20u64.into(), 2);
+        let params = config.get_evm_params().unwrap();
+        assert_eq!(params.nonce, 7);
+        assert_eq!(params.gas_price, 20u64.into());
+        assert!(!config.is_nonce_gap_open());
+
+        let status = config.get_nonce_drift_status();
+        assert_eq!(status.local_nonce, Some(7));
+        assert_eq!(status.last_check.unwrap().drift, 2);
+        assert_eq!(
+            status.last_fast_forward,
+            Some(NonceFastForward {
+                from: 5,
+                to: 7,
+                timestamp: 2
+            })
+        );
+    }
+
+    #[test]
+    fn chain_nonce_behind_pauses_sending_until_resolved() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        config.update_evm_params(|p| p.nonce = 8);
+
+        // Transactions with nonces 5..8 are not visible, the reserved nonce is kept.
+        config.record_refreshed_evm_params(8, 5, 10u64.into(), 1);
+        assert_eq!(config.get_evm_params().unwrap().nonce, 8);
+        assert!(config.is_nonce_gap_open());
+        let status = config.get_nonce_drift_status();
+        assert_eq!(status.last_check.unwrap().drift, -3);
+        assert_eq!(
+            status.gap,
+            Some(NonceGap {
+                local_nonce: 8,
+                chain_nonce: 5,
+                detected_at: 1
+            })
+        );
+
+        // The transactions become visible.
+        config.record_refreshed_evm_params(8, 8, 10u64.into(), 2);
+        assert_eq!(config.get_evm_params().unwrap().nonce, 8);
+        assert!(!config.is_nonce_gap_open());
+        assert_eq!(config.get_nonce_drift_status().gap, None);
+    }
+
+    #[test]
+    fn nonce_gap_is_acknowledged() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        config.update_evm_params(|p| p.nonce = 8);
+        assert_eq!(config.acknowledge_nonce_gap(), None);
+        assert_eq!(config.get_evm_params().unwrap().nonce, 8);
+
+        config.record_refreshed_evm_params(8, 5, 10u64.into(), 1);
+        config.record_refreshed_evm_params(8, 6, 10u64.into(), 2);
+        assert!(config.is_nonce_gap_open());
+
+        // The reserved nonce is moved back to the latest chain nonce.
+        let gap = config.acknowledge_nonce_gap().unwrap();
+        assert_eq!(gap.chain_nonce, 6);
+        assert_eq!(gap.detected_at, 1);
+        assert!(!config.is_nonce_gap_open());
+        assert_eq!(config.get_evm_params().unwrap().nonce, 6);
+
+        config.record_refreshed_evm_params(6, 6, 10u64.into(), 3);
+        assert!(!config.is_nonce_gap_open());
+        assert_eq!(config.get_evm_params().unwrap().nonce, 6);
+    }
+
+    #[test]
+    fn nonce_gap_is_acknowledged_only_for_matching_nonces() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        config.update_evm_params(|p| p.nonce = 8);
+        config.record_refreshed_evm_params(8, 6, 10u64.into(), 1);
+        assert!(config.is_nonce_gap_open());
+
+        // Nonce 7 is not known to be dropped, so its transaction may still be mined.
+        assert_eq!(config.acknowledge_nonce_gap_of(&BTreeSet::from([6])), None);
+        assert_eq!(
+            config.acknowledge_nonce_gap_of(&BTreeSet::from([6, 7, 8])),
+            None
+        );
+        assert!(config.is_nonce_gap_open());
+        assert_eq!(config.get_evm_params().unwrap().nonce, 8);
+
+        let gap = config
+            .acknowledge_nonce_gap_of(&BTreeSet::from([6, 7]))
+            .unwrap();
+        assert_eq!((gap.chain_nonce, gap.local_nonce), (6, 8));
+        assert!(!config.is_nonce_gap_open());
+        assert_eq!(config.get_evm_params().unwrap().nonce, 6);
+    }
+
+    #[test]
+    fn nonce_drift_check_is_skipped_if_nonce_was_reserved_during_query() {
+        MockContext::new().inject();
+        let mut config = ConfigStorage::default(memory_by_id(MemoryId::new(1)));
+        config.update_evm_params(|p| p.nonce = 5);
+
+        // A mint transaction was sent with nonce 5 while the chain nonce was queried.
+        config.update_evm_params(|p| p.nonce += 1);
+        config.record_refreshed_evm_params(5, 5, 10u64.into(), 1);
+
+        assert_eq!(config.get_evm_params().unwrap().nonce, 6);
+        assert!(!config.is_nonce_gap_open());
+        assert_eq!(config.get_nonce_drift_status().last_check, None);
+    }
+
+    #[tokio::test]
+    async fn signer_address_is_cached_until_strategy_change() {
+        MockContext::new().inject();
+        let config = Rc::new(RefCell::new(ConfigStorage::default(memory_by_id(
+            MemoryId::new(1),
+        ))));
+        let fetches = Rc::new(RefCell::new(0));
+        let fetch = |address: u8| {
+            let fetches = fetches.clone();
+            move || async move {
+                *fetches.borrow_mut() += 1;
+                Ok(H160::from_slice(&[address; 20]))
+            }
+        };
+
+        let address = ConfigStorage::get_signer_address_with(config.clone(), fetch(1))
+            .await
+            .unwrap();
+        assert_eq!(address, H160::from_slice(&[1; 20]));
+        assert_eq!(*fetches.borrow(), 1);
+
+        // Served from the cache.
+        let address = ConfigStorage::get_signer_address_with(config.clone(), fetch(2))
+            .await
+            .unwrap();
+        assert_eq!(address, H160::from_slice(&[1; 20]));
+        assert_eq!(*fetches.borrow(), 1);
+
+        // Key rotation invalidates the cache.
+        config
+            .borrow_mut()
+            .set_signing_strategy(SigningStrategy::Local {
+                private_key: [3; 32],
+            });
+        let address = ConfigStorage::get_signer_address_with(config.clone(), fetch(2))
+            .await
+            .unwrap();
+        assert_eq!(address, H160::from_slice(&[2; 20]));
+        assert_eq!(*fetches.borrow(), 2);
+    }
+
+    #[tokio::test]
+    async fn signer_address_fetch_failure_is_not_cached() {
+        MockContext::new().inject();
+        let config = Rc::new(RefCell::new(ConfigStorage::default(memory_by_id(
+            MemoryId::new(1),
+        ))));
+
+        let result = ConfigStorage::get_signer_address_with(config.clone(), || async {
+            Err(Error::Signing("management canister is unavailable".into()))
+        })
+        .await;
+        assert!(result.is_err());
+
+        let address = ConfigStorage::get_signer_address_with(config.clone(), || async {
+            Ok(H160::from_slice(&[1; 20]))
+        })
+        .await
+        .unwrap();
+        assert_eq!(address, H160::from_slice(&[1; 20]));
+    }
+
+    #[tokio::test]
+    async fn replay_context_digest_follows_context() {
+        MockContext::new().inject();
+        let config = Rc::new(RefCell::new(ConfigStorage::default(memory_by_id(
+            MemoryId::new(1),
+        ))));
+        let initial = config.borrow().get_replay_context_digest();
+        assert_eq!(initial.evm_params_index, None);
+        assert_eq!(initial.evm_params_hash, None);
+        assert_eq!(initial.signer_address, None);
+
+        config.borrow_mut().update_evm_params(|p| p.nonce = 1);
+        let digest = config.borrow().get_replay_context_digest();
+        assert_eq!(digest.evm_params_index, Some(0));
+        assert!(digest.evm_params_hash.is_some());
+
+        config.borrow_mut().update_evm_params(|p| p.nonce = 2);
+        let updated = config.borrow().get_replay_context_digest();
+        assert_eq!(updated.evm_params_index, Some(1));
+        assert_ne!(updated.evm_params_hash, digest.evm_params_hash);
+
+        ConfigStorage::get_signer_address_with(config.clone(), || async {
+            Ok(H160::from_slice(&[1; 20]))
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            config.borrow().get_replay_context_digest().signer_address,
+            Some(H160::from_slice(&[1; 20]))
+        );
+    }
 }
@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+
+use bridge_did::evm_latency::LatencyStats;
+
+use super::Timestamp;
+
+/// Number of the latest latency samples to keep.
+pub const EVM_LATENCY_HISTORY_SIZE: usize = 1000;
+
+/// Latency of a single EVM node request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySample {
+    pub timestamp: Timestamp,
+    pub latency_ns: u64,
+}
+
+/// Tracks the round-trip time of the EVM node requests.
+#[derive(Debug, Default, Clone)]
+pub struct EvmLatencyMonitor {
+    samples: VecDeque<LatencySample>,
+    degraded: bool,
+}
+
+impl EvmLatencyMonitor {
+    /// Records the latency sample, evicting the oldest one if the history is full.
+    /// Returns the updated stats.
+    pub fn record(
+        &mut self,
+        timestamp: Timestamp,
+        latency_ns: u64,
+        max_acceptable_latency_ms: Option<u64>,
+    ) -> LatencyStats {
+        if self.samples.len() == EVM_LATENCY_HISTORY_SIZE {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(LatencySample {
+            timestamp,
+            latency_ns,
+        });
+
+        self.stats(max_acceptable_latency_ms)
+    }
+
+    /// Updates the degradation state. Returns `Some(degraded)`, if the state is changed.
+    pub fn update_degraded(&mut self, stats: &LatencyStats) -> Option<bool> {
+        let degraded = stats.is_degraded();
+        if degraded == self.degraded {
+            return None;
+        }
+
+        self.degraded = degraded;
+        Some(degraded)
+    }
+
+    /// Returns the latest latency samples, oldest first.
+    pub fn samples(&self) -> impl Iterator<Item = &LatencySample> {
+        self.samples.iter()
+    }
+
+    /// Calculates percentiles of the recorded latency.
+    pub fn stats(&self, max_acceptable_latency_ms: Option<u64>) -> LatencyStats {
+        let mut latencies: Vec<u64> = self.samples.iter().map(|s| s.latency_ns).collect();
+        latencies.sort_unstable();
+
+        LatencyStats {
+            samples_count: latencies.len() as u64,
+            p50_ns: percentile(&latencies, 50),
+            p95_ns: percentile(&latencies, 95),
+            p99_ns: percentile(&latencies, 99),
+            max_acceptable_latency_ms,
+        }
+    }
+}
+
+/// Nearest-rank percentile of the sorted values.
+fn percentile(sorted: &[u64], percentile: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+
+    let rank = (sorted.len() * percentile).div_ceil(100);
+    sorted[rank.saturating_sub(1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_are_calculated() {
+        let mut monitor = EvmLatencyMonitor::default();
+        assert_eq!(monitor.stats(None), LatencyStats::default());
+
+        // Record in reverse order to check the samples are sorted.
+        for latency in (1..=100).rev() {
+            monitor.record(latency, latency, None);
+        }
+
+        let stats = monitor.stats(Some(1));
+        assert_eq!(stats.samples_count, 100);
+        assert_eq!(stats.p50_ns, 50);
+        assert_eq!(stats.p95_ns, 95);
+        assert_eq!(stats.p99_ns, 99);
+        assert_eq!(stats.max_acceptable_latency_ms, Some(1));
+    }
+
+    #[test]
+    fn percentiles_of_few_samples() {
+        assert_eq!(percentile(&[7], 50), 7);
+        assert_eq!(percentile(&[7], 99), 7);
+        assert_eq!(percentile(&[1, 2, 3], 50), 2);
+        assert_eq!(percentile(&[1, 2, 3], 95), 3);
+        assert_eq!(percentile(&[], 95), 0);
+    }
+
+    #[test]
+    fn latency_samples_are_recorded_and_capped() {
+        let mut monitor = EvmLatencyMonitor::default();
+        for i in 0..EVM_LATENCY_HISTORY_SIZE as u64 + 10 {
+            monitor.record(i, i * 1000, None);
+        }
+
+        assert_eq!(monitor.samples().count(), EVM_LATENCY_HISTORY_SIZE);
+        let oldest = monitor.samples().next().unwrap();
+        assert_eq!(
+            *oldest,
+            LatencySample {
+                timestamp: 10,
+                latency_ns: 10_000
+            }
+        );
+    }
+
+    #[test]
+    fn degradation_is_reported_on_change() {
+        const MS: u64 = 1_000_000;
+        let mut monitor = EvmLatencyMonitor::default();
+
+        let stats = monitor.record(1, 50 * MS, Some(100));
+        assert_eq!(monitor.update_degraded(&stats), None);
+
+        let stats = monitor.record(2, 500 * MS, Some(100));
+        assert!(stats.is_degraded());
+        assert_eq!(monitor.update_degraded(&stats), Some(true));
+        let stats = monitor.record(3, 500 * MS, Some(100));
+        assert_eq!(monitor.update_degraded(&stats), None);
+
+        // Without the threshold the latency is never degraded.
+        let stats = monitor.stats(None);
+        assert!(!stats.is_degraded());
+        assert_eq!(monitor.update_degraded(&stats), Some(false));
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::VecDeque;
+
+use bridge_did::evm_params_history::EvmParamsSnapshot;
+use bridge_utils::evm_bridge::EvmParams;
+
+use super::Timestamp;
+
+/// Default number of the latest EVM params snapshots to keep.
+pub const DEFAULT_EVM_PARAMS_HISTORY_SIZE: u32 = 128;
+
+/// Ring buffer of the EVM params snapshots, taken every time the params are changed.
+#[derive(Debug, Default, Clone)]
+pub struct EvmParamsHistory {
+    snapshots: VecDeque<EvmParamsSnapshot>,
+    next_index: u64,
+}
+
+impl EvmParamsHistory {
+    /// Records the snapshot of the params, if they differ from the latest snapshot.
+    /// The oldest snapshots are evicted to keep at most `capacity` of them.
+    pub fn record(&mut self, params: &EvmParams, timestamp: Timestamp, capacity: usize) {
+        if self
+            .snapshots
+            .back()
+            .is_some_and(|latest| Self::is_same(latest, params))
+        {
+            return;
+        }
+
+        self.snapshots.push_back(EvmParamsSnapshot {
+            index: self.next_index,
+            timestamp,
+            chain_id: params.chain_id,
+            next_block: params.next_block,
+            nonce: params.nonce,
+            gas_price: params.gas_price.clone(),
+        });
+        self.next_index += 1;
+        self.truncate(capacity);
+    }
+
+    /// Evicts the oldest snapshots to keep at most `capacity` of them.
+    pub fn truncate(&mut self, capacity: usize) {
+        while self.snapshots.len() > capacity {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Index of the snapshot of the current params.
+    pub fn latest_index(&self) -> Option<u64> {
+        self.snapshots.back().map(|snapshot| snapshot.index)
+    }
+
+    /// Returns the snapshot with the given index, if it is not evicted yet.
+    pub fn find(&self, index: u64) -> Option<&EvmParamsSnapshot> {
+        let first = self.snapshots.front()?.index;
+        let position = index.checked_sub(first)?;
+        self.snapshots.get(position as usize)
+    }
+
+    /// Returns up to `count` snapshots, newest first, skipping `offset` newest ones.
+    pub fn get(&self, offset: usize, count: usize) -> Vec<EvmParamsSnapshot> {
+        self.snapshots
+            .iter()
+            .rev()
+            .skip(offset)
+            .take(count)
+            .cloned()
+            .collect()
+    }
+
+    fn is_same(snapshot: &EvmParamsSnapshot, params: &EvmParams) -> bool {
+        snapshot.chain_id == params.chain_id
+            && snapshot.next_block == params.next_block
+            && snapshot.nonce == params.nonce
+            && snapshot.gas_price == params.gas_price
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(nonce: u64, gas_price: u64) -> EvmParams {
+        EvmParams::new(1, 10, nonce, gas_price.into())
+    }
+
+    #[test]
+    fn snapshot_is_recorded_on_change_only() {
+        let mut history = EvmParamsHistory::default();
+        assert_eq!(history.latest_index(), None);
+
+        history.record(&params(0, 10), 1, 10);
+        history.record(&params(0, 10), 2, 10);
+        assert_eq!(history.latest_index(), Some(0));
+
+        history.record(&params(0, 400), 3, 10);
+        history.record(&params(1, 400), 4, 10);
+        history.record(&params(1, 400), 5, 10);
+        assert_eq!(history.latest_index(), Some(2));
+
+        let snapshots = history.get(0, 10);
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(snapshots[0].index, 2);
+        assert_eq!(snapshots[0].timestamp, 4);
+        assert_eq!(snapshots[1].gas_price, 400u64.into());
+        assert_eq!(snapshots[2].timestamp, 1);
+    }
+
+    #[test]
+    fn oldest_snapshots_are_evicted() {
+        let mut history = EvmParamsHistory::default();
+        for nonce in 0..10 {
+            history.record(&params(nonce, 10), nonce, 4);
+        }
+
+        let snapshots = history.get(0, 10);
+        let indices: Vec<_> = snapshots.iter().map(|s| s.index).collect();
+        assert_eq!(indices, vec![9, 8, 7, 6]);
+
+        history.truncate(2);
+        assert_eq!(history.get(0, 10).len(), 2);
+        assert_eq!(history.latest_index(), Some(9));
+
+        // Indices are not reused after eviction.
+        history.record(&params(42, 10), 42, 2);
+        assert_eq!(history.latest_index(), Some(10));
+    }
+
+    #[test]
+    fn snapshots_are_paginated() {
+        let mut history = EvmParamsHistory::default();
+        for nonce in 0..5 {
+            history.record(&params(nonce, 10), nonce, 10);
+        }
+
+        let page: Vec<_> = history.get(1, 2).iter().map(|s| s.index).collect();
+        assert_eq!(page, vec![3, 2]);
+        assert!(history.get(5, 2).is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+
+use bridge_did::gas_balance::{
+    GasBalanceAlert, GasBalanceAlertRecord, GasBalanceHealth, GasBalanceRecord, GasBalanceStatus,
+    GasBalanceThresholds,
+};
+use did::{H160, U256};
+
+use super::Timestamp;
+
+/// Number of the latest balance records to keep.
+pub const GAS_BALANCE_HISTORY_SIZE: usize = 32;
+
+/// Tracks the balance of the bridge canister EVM address and decides
+/// whether the canister can sponsor mint transactions.
+///
+/// Sponsorship is disabled when the balance drops below the critical watermark,
+/// and is enabled again only when the balance is restored above the low watermark.
+#[derive(Debug, Default, Clone)]
+pub struct GasBalanceMonitor {
+    address: Option<H160>,
+    history: VecDeque<GasBalanceRecord>,
+    health: GasBalanceHealth,
+    sponsorship_disabled: bool,
+    last_alert: Option<GasBalanceAlertRecord>,
+}
+
+impl GasBalanceMonitor {
+    /// Records the balance of the address and updates the health state.
+    /// Returns an alert, if the health change should be reported.
+    pub fn record(
+        &mut self,
+        thresholds: &GasBalanceThresholds,
+        address: H160,
+        balance: U256,
+        timestamp: Timestamp,
+    ) -> Option<GasBalanceAlert> {
+        let health = thresholds.health(&balance);
+
+        if self.history.len() == GAS_BALANCE_HISTORY_SIZE {
+            self.history.pop_front();
+        }
+        self.history
+            .push_back(GasBalanceRecord { timestamp, balance });
+        self.address = Some(address);
+
+        let alert = match health {
+            GasBalanceHealth::Failing if !self.sponsorship_disabled => {
+                self.sponsorship_disabled = true;
+                Some(GasBalanceAlert::Critical)
+            }
+            GasBalanceHealth::Degraded
+                if !self.sponsorship_disabled && self.health != GasBalanceHealth::Degraded =>
+            {
+                Some(GasBalanceAlert::Low)
+            }
+            GasBalanceHealth::Healthy
+                if self.sponsorship_disabled || self.health == GasBalanceHealth::Degraded =>
+            {
+                self.sponsorship_disabled = false;
+                Some(GasBalanceAlert::Recovered)
+            }
+            _ => None,
+        };
+
+        self.health = health;
+        if let Some(alert) = alert {
+            self.last_alert = Some(GasBalanceAlertRecord { timestamp, alert });
+        }
+
+        alert
+    }
+
+    /// Forgets the health state and enables sponsorship. Balance history is preserved.
+    pub fn reset(&mut self) {
+        self.health = GasBalanceHealth::Unknown;
+        self.sponsorship_disabled = false;
+    }
+
+    /// Checks if the bridge canister should send mint transactions for the users.
+    pub fn is_sponsorship_enabled(&self) -> bool {
+        !self.sponsorship_disabled
+    }
+
+    /// Returns diagnostic info of the monitored balance.
+    pub fn status(&self, thresholds: Option<GasBalanceThresholds>) -> GasBalanceStatus {
+        GasBalanceStatus {
+            address: self.address.clone(),
+            health: self.health,
+            mint_tx_sponsorship_enabled: self.is_sponsorship_enabled(),
+            thresholds,
+            history: self.history.iter().cloned().collect(),
+            last_alert: self.last_alert.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thresholds() -> GasBalanceThresholds {
+        GasBalanceThresholds {
+            low_watermark: 1000u64.into(),
+            critical_watermark: 100u64.into(),
+        }
+    }
+
+    fn record(monitor: &mut GasBalanceMonitor, balance: u64) -> Option<GasBalanceAlert> {
+        let timestamp = monitor.history.len() as u64;
+        monitor.record(
+            &thresholds(),
+            H160::from_slice(&[1; 20]),
+            balance.into(),
+            timestamp,
+        )
+    }
+
+    #[test]
+    fn balance_transitions_across_watermarks() {
+        let mut monitor = GasBalanceMonitor::default();
+        assert_eq!(monitor.status(None).health, GasBalanceHealth::Unknown);
+        assert!(monitor.is_sponsorship_enabled());
+
+        assert_eq!(record(&mut monitor, 5000), None);
+        assert_eq!(monitor.health, GasBalanceHealth::Healthy);
+        assert!(monitor.is_sponsorship_enabled());
+
+        assert_eq!(record(&mut monitor, 500), Some(GasBalanceAlert::Low));
+        assert_eq!(record(&mut monitor, 400), None);
+        assert_eq!(monitor.health, GasBalanceHealth::Degraded);
+        assert!(monitor.is_sponsorship_enabled());
+
+        assert_eq!(record(&mut monitor, 50), Some(GasBalanceAlert::Critical));
+        assert_eq!(record(&mut monitor, 40), None);
+        assert_eq!(monitor.health, GasBalanceHealth::Failing);
+        assert!(!monitor.is_sponsorship_enabled());
+
+        // Sponsorship stays disabled until the balance is above the low watermark.
+        assert_eq!(record(&mut monitor, 500), None);
+        assert_eq!(monitor.health, GasBalanceHealth::Degraded);
+        assert!(!monitor.is_sponsorship_enabled());
+
+        assert_eq!(record(&mut monitor, 2000), Some(GasBalanceAlert::Recovered));
+        assert_eq!(monitor.health, GasBalanceHealth::Healthy);
+        assert!(monitor.is_sponsorship_enabled());
+        assert_eq!(
+            monitor.status(None).last_alert.unwrap().alert,
+            GasBalanceAlert::Recovered
+        );
+    }
+
+    #[test]
+    fn critical_balance_from_unknown_state_disables_sponsorship() {
+        let mut monitor = GasBalanceMonitor::default();
+        assert_eq!(record(&mut monitor, 0), Some(GasBalanceAlert::Critical));
+        assert!(!monitor.is_sponsorship_enabled());
+
+        monitor.reset();
+        assert!(monitor.is_sponsorship_enabled());
+        assert_eq!(monitor.health, GasBalanceHealth::Unknown);
+    }
+
+    #[test]
+    fn degraded_balance_recovery_is_reported() {
+        let mut monitor = GasBalanceMonitor::default();
+        assert_eq!(record(&mut monitor, 999), Some(GasBalanceAlert::Low));
+        assert_eq!(record(&mut monitor, 1000), Some(GasBalanceAlert::Recovered));
+        assert_eq!(record(&mut monitor, 1000), None);
+    }
+
+    #[test]
+    fn history_is_limited() {
+        let mut monitor = GasBalanceMonitor::default();
+        for balance in 0..GAS_BALANCE_HISTORY_SIZE as u64 + 10 {
+            record(&mut monitor, 5000 + balance);
+        }
+
+        let history = monitor.status(None).history;
+        assert_eq!(history.len(), GAS_BALANCE_HISTORY_SIZE);
+        assert_eq!(history.last().unwrap().balance, U256::from(5000u64 + 41));
+        assert_eq!(history[0].balance, U256::from(5000u64 + 10));
+    }
+}
@@ -0,0 +1,212 @@
+use bridge_did::nonce_drift::{NonceCheck, NonceDriftStatus, NonceFastForward, NonceGap};
+
+use super::Timestamp;
+
+/// Result of the nonce check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NonceDrift {
+    /// The reserved nonce matches the chain nonce.
+    InSync,
+    /// The chain is ahead, e.g. a transaction was sent from the address outside the bridge.
+    /// The reserved nonce should be moved to the chain nonce.
+    Ahead { from: u64, to: u64 },
+    /// The chain is behind: transactions, sent by the bridge, are not visible to the node.
+    /// `new_gap` is false if the gap was detected by one of the previous checks.
+    Behind { gap: NonceGap, new_gap: bool },
+    /// The nonces match again after the gap.
+    GapResolved(NonceGap),
+}
+
+/// Compares the nonce, reserved by the bridge canister, with the pending nonce of its
+/// EVM address.
+///
+/// If the chain is ahead, the reserved nonce is fast-forwarded. If the chain is behind,
+/// the gap is kept open until a check finds the nonces matching, or the gap is
+/// acknowledged. Mint transactions are not sent while the gap is open.
+#[derive(Debug, Default, Clone)]
+pub struct NonceDriftMonitor {
+    last_check: Option<NonceCheck>,
+    gap: Option<NonceGap>,
+    last_fast_forward: Option<NonceFastForward>,
+}
+
+impl NonceDriftMonitor {
+    /// Records the nonce check and updates the gap state.
+    pub fn check(
+        &mut self,
+        local_nonce: u64,
+        chain_nonce: u64,
+        timestamp: Timestamp,
+    ) -> NonceDrift {
+        self.last_check = Some(NonceCheck::new(local_nonce, chain_nonce, timestamp));
+
+        if chain_nonce > local_nonce {
+            self.gap = None;
+            self.last_fast_forward = Some(NonceFastForward {
+                from: local_nonce,
+                to: chain_nonce,
+                timestamp,
+            });
+            return NonceDrift::Ahead {
+                from: local_nonce,
+                to: chain_nonce,
+            };
+        }
+
+        if chain_nonce == local_nonce {
+            return match self.gap.take() {
+                Some(gap) => NonceDrift::GapResolved(gap),
+                None => NonceDrift::InSync,
+            };
+        }
+
+        let new_gap = self.gap.is_none();
+        let detected_at = self
+            .gap
+            .as_ref()
+            .map(|gap| gap.detected_at)
+            .unwrap_or(timestamp);
+        let gap = NonceGap {
+            local_nonce,
+            chain_nonce,
+            detected_at,
+        };
+        self.gap = Some(gap.clone());
+
+        NonceDrift::Behind { gap, new_gap }
+    }
+
+    /// Closes the open gap. Returns the gap, if it was open.
+    pub fn acknowledge_gap(&mut self) -> Option<NonceGap> {
+        self.gap.take()
+    }
+
+    /// Returns the latest nonce check, if any.
+    pub fn last_check(&self) -> Option<&NonceCheck> {
+        self.last_check.as_ref()
+    }
+
+    /// Checks if the nonce gap is open.
+    pub fn is_gap_open(&self) -> bool {
+        self.gap.is_some()
+    }
+
+    /// Returns the open gap, if any.
+    pub fn gap(&self) -> Option<&NonceGap> {
+        self.gap.as_ref()
+    }
+
+    /// Returns diagnostic info of the nonce drift.
+    pub fn status(&self, local_nonce: Option<u64>) -> NonceDriftStatus {
+        NonceDriftStatus {
+            local_nonce,
+            last_check: self.last_check.clone(),
+            gap: self.gap.clone(),
+            last_fast_forward: self.last_fast_forward.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_nonces_are_in_sync() {
+        let mut monitor = NonceDriftMonitor::default();
+        assert_eq!(monitor.check(5, 5, 1), NonceDrift::InSync);
+        assert!(!monitor.is_gap_open());
+
+        let status = monitor.status(Some(5));
+        assert_eq!(status.last_check, Some(NonceCheck::new(5, 5, 1)));
+        assert_eq!(status.last_check.unwrap().drift, 0);
+        assert_eq!(status.gap, None);
+        assert_eq!(status.last_fast_forward, None);
+    }
+
+    #[test]
+    fn chain_ahead_is_fast_forwarded() {
+        let mut monitor = NonceDriftMonitor::default();
+        assert_eq!(monitor.check(5, 8, 1), NonceDrift::Ahead { from: 5, to: 8 });
+        assert!(!monitor.is_gap_open());
+
+        let status = monitor.status(Some(8));
+        assert_eq!(status.last_check.unwrap().drift, 3);
+        assert_eq!(
+            status.last_fast_forward,
+            Some(NonceFastForward {
+                from: 5,
+                to: 8,
+                timestamp: 1
+            })
+        );
+    }
+
+    #[test]
+    fn chain_behind_opens_gap_until_resolved() {
+        let mut monitor = NonceDriftMonitor::default();
+        let gap = NonceGap {
+            local_nonce: 8,
+            chain_nonce: 5,
+            detected_at: 1,
+        };
+        assert_eq!(
+            monitor.check(8, 5, 1),
+            NonceDrift::Behind {
+                gap: gap.clone(),
+                new_gap: true
+            }
+        );
+        assert!(monitor.is_gap_open());
+        assert_eq!(monitor.status(Some(8)).last_check.unwrap().drift, -3);
+
+        // The gap is still open, detection time is kept.
+        let gap = NonceGap {
+            chain_nonce: 7,
+            ..gap
+        };
+        assert_eq!(
+            monitor.check(8, 7, 2),
+            NonceDrift::Behind {
+                gap: gap.clone(),
+                new_gap: false
+            }
+        );
+        assert!(monitor.is_gap_open());
+
+        assert_eq!(monitor.check(8, 8, 3), NonceDrift::GapResolved(gap));
+        assert!(!monitor.is_gap_open());
+        assert_eq!(monitor.check(8, 8, 4), NonceDrift::InSync);
+    }
+
+    #[test]
+    fn chain_moving_ahead_closes_gap() {
+        let mut monitor = NonceDriftMonitor::default();
+        monitor.check(8, 5, 1);
+        assert!(monitor.is_gap_open());
+
+        assert_eq!(
+            monitor.check(8, 10, 2),
+            NonceDrift::Ahead { from: 8, to: 10 }
+        );
+        assert!(!monitor.is_gap_open());
+    }
+
+    #[test]
+    fn gap_is_acknowledged() {
+        let mut monitor = NonceDriftMonitor::default();
+        assert_eq!(monitor.acknowledge_gap(), None);
+
+        monitor.check(8, 5, 1);
+        assert_eq!(
+            monitor.acknowledge_gap(),
+            Some(NonceGap {
+                local_nonce: 8,
+                chain_nonce: 5,
+                detected_at: 1
+            })
+        );
+        assert!(!monitor.is_gap_open());
+        assert_eq!(monitor.status(None).gap, None);
+    }
+}
@@ -0,0 +1,152 @@
+//! Store of the scheduler task payloads, which exceed the inline size limit. Such payloads
+//! are stored once, keyed by their hash, and the task keeps only the key, so large operations
+//! don't bloat the scheduler storage when they are rescheduled many times.
+//!
+//! Payloads are cleaned up by sweeps: a sweep removes the payloads, which are not referenced
+//! by any pending task.
+
+use std::collections::BTreeMap;
+
+use bridge_did::task_payload::TaskPayloadStats;
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, StableBTreeMap};
+
+/// Max size in bytes of the encoded operation, which is stored inline in the scheduler task.
+pub const MAX_INLINE_TASK_PAYLOAD_SIZE: usize = 1024;
+
+/// Key of the payload in the store: keccak256 hash of the encoded payload.
+pub type TaskPayloadKey = [u8; 32];
+
+/// Task payloads, stored in IC stable memory.
+pub struct TaskPayloadStore<M: Memory> {
+    payloads: StableBTreeMap<TaskPayloadKey, Vec<u8>, M>,
+    stats: TaskPayloadStats,
+}
+
+impl<M: Memory> TaskPayloadStore<M> {
+    /// Creates a new instance of the store.
+    pub fn with_memory(memory: M) -> Self {
+        Self {
+            payloads: StableBTreeMap::new(memory),
+            stats: TaskPayloadStats::default(),
+        }
+    }
+
+    /// Stores the encoded payload and returns its key. Equal payloads are stored once.
+    pub fn put(&mut self, payload: Vec<u8>) -> TaskPayloadKey {
+        let key = ethers_core::utils::keccak256(&payload);
+        if self.payloads.get(&key).is_none() {
+            self.payloads.insert(key, payload);
+        }
+
+        key
+    }
+
+    /// Returns the encoded payload with the given key.
+    pub fn get(&self, key: &TaskPayloadKey) -> Option<Vec<u8>> {
+        self.payloads.get(key)
+    }
+
+    /// Number of the payloads in the store.
+    pub fn len(&self) -> u64 {
+        self.payloads.len()
+    }
+
+    /// Checks if the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes the payloads, which are not in the `referenced` map of the payload keys to the
+    /// number of the pending tasks referencing them, and updates the stats.
+    ///
+    /// `oversized_inline_tasks` is the number of the pending tasks, which keep a payload over
+    /// the inline size limit.
+    pub fn sweep(
+        &mut self,
+        referenced: &BTreeMap<TaskPayloadKey, u64>,
+        oversized_inline_tasks: u64,
+    ) {
+        let unreferenced: Vec<_> = self
+            .payloads
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| !referenced.contains_key(key))
+            .collect();
+        for key in &unreferenced {
+            self.payloads.remove(key);
+        }
+
+        if !unreferenced.is_empty() {
+            log::trace!("Removed {} unreferenced task payloads", unreferenced.len());
+        }
+
+        let mut stats = TaskPayloadStats {
+            oversized_inline_tasks,
+            ..Default::default()
+        };
+        for (key, payload) in self.payloads.iter() {
+            let size = payload.len() as u64;
+            let tasks = referenced.get(&key).copied().unwrap_or_default();
+            stats.spilled_tasks += tasks;
+            stats.spilled_bytes += size * tasks;
+            stats.stored_payloads += 1;
+            stats.stored_bytes += size;
+        }
+        self.stats = stats;
+    }
+
+    /// Returns the stats of the latest sweep. Stats are reset on upgrade.
+    pub fn stats(&self) -> TaskPayloadStats {
+        self.stats.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_stable_structures::VectorMemory;
+
+    use super::*;
+
+    fn store() -> TaskPayloadStore<VectorMemory> {
+        TaskPayloadStore::with_memory(VectorMemory::default())
+    }
+
+    #[test]
+    fn equal_payloads_are_stored_once() {
+        let mut store = store();
+
+        let key = store.put(vec![1; 2000]);
+        assert_eq!(store.put(vec![1; 2000]), key);
+        assert_ne!(store.put(vec![2; 2000]), key);
+
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get(&key), Some(vec![1; 2000]));
+    }
+
+    #[test]
+    fn sweep_removes_unreferenced_payloads() {
+        let mut store = store();
+        let referenced_key = store.put(vec![1; 2000]);
+        let unreferenced_key = store.put(vec![2; 3000]);
+
+        store.sweep(&BTreeMap::from([(referenced_key, 3)]), 1);
+
+        assert_eq!(store.get(&referenced_key), Some(vec![1; 2000]));
+        assert_eq!(store.get(&unreferenced_key), None);
+        assert_eq!(
+            store.stats(),
+            TaskPayloadStats {
+                spilled_tasks: 3,
+                spilled_bytes: 6000,
+                stored_payloads: 1,
+                stored_bytes: 2000,
+                oversized_inline_tasks: 1,
+            }
+        );
+
+        store.sweep(&BTreeMap::new(), 0);
+        assert!(store.is_empty());
+        assert_eq!(store.stats(), TaskPayloadStats::default());
+    }
+}
@@ -21,7 +21,8 @@ impl<C: CanisterClient> Brc20BridgeClient<C> {
     /// The operations are then paginated with the given `pagination` parameters,
     /// starting from `offset` returning a max of `count` items
     /// If `offset` is `None`, it starts from the beginning (i.e. the first entry is the min_included_id).
-    /// If `count` is `None`, it returns all operations.
+    /// If `pagination` is `None`, only the most recent operations are returned, 1000 by default
+    /// (see `set_operations_list_default_limit`), so callers should paginate to get the full history.
     pub async fn get_operations_list(
         &self,
         wallet_address: &H160,
@@ -1,12 +1,36 @@
+use bridge_did::bridge_mode::{BridgeMode, OperationBlocksExport, ReplicaSyncStatus};
+use bridge_did::bulk_action::{BulkActionResult, OperationFilter, OperatorAuditEntry};
+use bridge_did::concurrency::{DeferredOperationsStats, OperationConcurrencyLimits};
+use bridge_did::data_erasure::{UserDataErasure, UserIdentity};
+use bridge_did::dead_letter::DeadLetterEvent;
 use bridge_did::error::BTFResult;
+use bridge_did::event_filter::EventTopicFilter;
+use bridge_did::evm_latency::LatencyStats;
+use bridge_did::evm_link::PrivateTxRelay;
+use bridge_did::evm_params_history::EvmParamsSnapshot;
+use bridge_did::fee_adjustment::{FeePayerAdjustments, FeeReconciliationSettings};
+use bridge_did::gas_balance::{GasBalanceStatus, GasBalanceThresholds};
+use bridge_did::halt::BridgeHalt;
+use bridge_did::http_transform::HttpTransformSettings;
+use bridge_did::icrc3::GetBlocksResult;
 use bridge_did::id256::Id256;
+use bridge_did::manifest::BridgeManifest;
+use bridge_did::nonce_drift::{NonceDriftStatus, NonceGap};
+use bridge_did::op_id::{NonceState, OperationId};
+use bridge_did::operation_replay::{ProgressReplayEntry, ReplayCaptureSettings};
+use bridge_did::operation_status::OperationStatusView;
 use bridge_did::order::SignedMintOrder;
-use candid::Principal;
+use bridge_did::outbox::{OutboxDestination, OutboxMessage};
+use bridge_did::pending_mint_batch::PendingMintBatchesStats;
+use bridge_did::processed_event::{ProcessedEventInfo, RejectedEventsStats};
+use bridge_did::retry_profile::RetryProfiles;
+use bridge_did::task_payload::TaskPayloadStats;
+use candid::{Nat, Principal};
 use did::build::BuildData;
 use did::H160;
 use ic_canister_client::{CanisterClient, CanisterClientResult};
 use ic_log::did::{LogCanisterError, LogCanisterSettings, LoggerPermission, Pagination};
-use ic_log::writer::Logs;
+use ic_log::writer::{Log, Logs};
 
 #[async_trait::async_trait]
 pub trait BridgeCanisterClient<C: CanisterClient> {
@@ -37,6 +61,20 @@ pub trait BridgeCanisterClient<C: CanisterClient> {
         self.client().query("ic_logs", (pagination,)).await
     }
 
+    /// Returns up to `count` latest log records of the operation,
+    /// prefixed with the operation id and execution step.
+    ///
+    /// This method is only for canister owner.
+    async fn get_logs_for_operation(
+        &self,
+        operation_id: OperationId,
+        count: usize,
+    ) -> CanisterClientResult<Vec<Log>> {
+        self.client()
+            .query("get_logs_for_operation", (operation_id, count))
+            .await
+    }
+
     async fn set_logger_in_memory_records(&self, max_log_count: usize) -> CanisterClientResult<()> {
         self.client()
             .update("set_logger_in_memory_records", (max_log_count,))
@@ -86,6 +124,11 @@ pub trait BridgeCanisterClient<C: CanisterClient> {
             .await
     }
 
+    /// Returns the user-relevant configuration of the bridge in a single response.
+    async fn get_bridge_manifest(&self) -> CanisterClientResult<BridgeManifest> {
+        self.client().query("get_bridge_manifest", ()).await
+    }
+
     /// Returns principal of EVM canister with which the bridge canister works.
     async fn get_evm_principal(&self) -> CanisterClientResult<Principal> {
         self.client().query("get_evm_principal", ()).await
@@ -99,8 +142,604 @@ pub trait BridgeCanisterClient<C: CanisterClient> {
     }
 
     /// Returns the address of the BTF bridge contract in EVM canister.
-    async fn get_btf_bridge_contract(&self) -> CanisterClientResult<BTFResult<Option<H160>>> {
-        self.client().update("get_btf_bridge_contract", ()).await
+    /// If contract isn't initialized yet - returns None.
+    async fn get_btf_bridge_contract(&self) -> CanisterClientResult<Option<H160>> {
+        self.client().query("get_btf_bridge_contract", ()).await
+    }
+
+    /// Returns balance monitoring status of the bridge canister EVM address.
+    async fn get_gas_balance_status(&self) -> CanisterClientResult<GasBalanceStatus> {
+        self.client().query("get_gas_balance_status", ()).await
+    }
+
+    /// Sets balance thresholds of the bridge canister EVM address.
+    async fn set_gas_balance_thresholds(
+        &self,
+        thresholds: Option<GasBalanceThresholds>,
+    ) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_gas_balance_thresholds", (thresholds,))
+            .await
+    }
+
+    /// Enables or disables compression of the operation logs in the stable memory.
+    async fn set_operation_log_compression(&self, enabled: bool) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_operation_log_compression", (enabled,))
+            .await
+    }
+
+    /// Enables or disables the strict validation of the BTF bridge events.
+    ///
+    /// This method is only for canister owner.
+    async fn set_strict_event_validation(&self, enabled: bool) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_strict_event_validation", (enabled,))
+            .await
+    }
+
+    /// Suspends or resumes the timer-driven processing of the bridge.
+    ///
+    /// This method is only for canister owner.
+    async fn set_timers_enabled(&self, enabled: bool) -> CanisterClientResult<()> {
+        self.client().update("set_timers_enabled", (enabled,)).await
+    }
+
+    /// Checks if the timer-driven processing of the bridge is enabled.
+    async fn timers_enabled(&self) -> CanisterClientResult<bool> {
+        self.client().query("timers_enabled", ()).await
+    }
+
+    /// Sets min interval between the gas price refreshes before sending a mint transaction.
+    ///
+    /// This method is only for canister owner.
+    async fn set_gas_price_refresh_interval(
+        &self,
+        interval_secs: Option<u64>,
+    ) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_gas_price_refresh_interval", (interval_secs,))
+            .await
+    }
+
+    /// Returns the relay, which mint transactions are sent to instead of the public mempool.
+    ///
+    /// This method is only for canister owner.
+    async fn get_private_tx_relay(&self) -> CanisterClientResult<Option<PrivateTxRelay>> {
+        self.client().query("get_private_tx_relay", ()).await
+    }
+
+    /// Sets the relay, which mint transactions are sent to instead of the public mempool.
+    /// If `None`, mint transactions are sent to the public mempool.
+    ///
+    /// This method is only for canister owner.
+    async fn set_private_tx_relay(
+        &self,
+        relay: Option<PrivateTxRelay>,
+    ) -> CanisterClientResult<()> {
+        self.client().update("set_private_tx_relay", (relay,)).await
+    }
+
+    /// Sets the time after sending a mint transaction, when it is checked to be dropped
+    /// from the EVM mempool. If `None`, the sent mint transactions are not checked.
+    ///
+    /// This method is only for canister owner.
+    async fn set_dropped_tx_timeout(&self, timeout_secs: Option<u64>) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_dropped_tx_timeout", (timeout_secs,))
+            .await
+    }
+
+    /// Sets timeout of the EVM JSON-RPC and indexer requests. If `None`, the default is used.
+    ///
+    /// This method is only for canister owner.
+    async fn set_rpc_request_timeout(&self, timeout_secs: Option<u64>) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_rpc_request_timeout", (timeout_secs,))
+            .await
+    }
+
+    /// Returns settings of the HTTP outcall response transform.
+    async fn get_http_transform_settings(&self) -> CanisterClientResult<HttpTransformSettings> {
+        self.client().query("get_http_transform_settings", ()).await
+    }
+
+    /// Sets settings of the HTTP outcall response transform. If `None`, the default is used.
+    ///
+    /// This method is only for canister owner.
+    async fn set_http_transform_settings(
+        &self,
+        settings: Option<HttpTransformSettings>,
+    ) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_http_transform_settings", (settings,))
+            .await
+    }
+
+    /// Returns retry profiles of the scheduled operation tasks.
+    async fn get_retry_profiles(&self) -> CanisterClientResult<RetryProfiles> {
+        self.client().query("get_retry_profiles", ()).await
+    }
+
+    /// Sets retry profiles of the scheduled operation tasks. If `None`, the default is used.
+    ///
+    /// This method is only for canister owner.
+    async fn set_retry_profiles(
+        &self,
+        profiles: Option<RetryProfiles>,
+    ) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_retry_profiles", (profiles,))
+            .await
+    }
+
+    /// Returns settings of the operation progress capture to the replay log.
+    async fn get_replay_capture_settings(&self) -> CanisterClientResult<ReplayCaptureSettings> {
+        self.client().query("get_replay_capture_settings", ()).await
+    }
+
+    /// Sets settings of the operation progress capture to the replay log. If `None`,
+    /// the default is used.
+    ///
+    /// This method is only for canister owner.
+    async fn set_replay_capture_settings(
+        &self,
+        settings: Option<ReplayCaptureSettings>,
+    ) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_replay_capture_settings", (settings,))
+            .await
+    }
+
+    /// Returns max number of the most recent operations, returned by `get_operations_list`
+    /// if no pagination is requested.
+    async fn get_operations_list_default_limit(&self) -> CanisterClientResult<u64> {
+        self.client()
+            .query("get_operations_list_default_limit", ())
+            .await
+    }
+
+    /// Sets max number of the most recent operations, returned by `get_operations_list`
+    /// if no pagination is requested.
+    ///
+    /// This method is only for canister owner.
+    async fn set_operations_list_default_limit(
+        &self,
+        limit: Option<u64>,
+    ) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_operations_list_default_limit", (limit,))
+            .await
+    }
+
+    /// Returns settings of the mint fees reconciliation, or `None` if it is disabled.
+    async fn get_fee_reconciliation(
+        &self,
+    ) -> CanisterClientResult<Option<FeeReconciliationSettings>> {
+        self.client().query("get_fee_reconciliation", ()).await
+    }
+
+    /// Sets settings of the mint fees reconciliation. If `None`, the reconciliation is disabled.
+    ///
+    /// This method is only for canister owner.
+    async fn set_fee_reconciliation(
+        &self,
+        settings: Option<FeeReconciliationSettings>,
+    ) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_fee_reconciliation", (settings,))
+            .await
+    }
+
+    /// Returns the filter of the collected events by an indexed parameter topic, if any.
+    async fn get_event_topic_filter(&self) -> CanisterClientResult<Option<EventTopicFilter>> {
+        self.client().query("get_event_topic_filter", ()).await
+    }
+
+    /// Sets the filter of the collected events by an indexed parameter topic.
+    /// If `None`, all the events of the BTF bridge contract are collected.
+    ///
+    /// This method is only for canister owner.
+    async fn set_event_topic_filter(
+        &self,
+        filter: Option<EventTopicFilter>,
+    ) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_event_topic_filter", (filter,))
+            .await
+    }
+
+    /// Returns max number of the concurrently progressing operations per direction.
+    async fn get_operation_concurrency_limits(
+        &self,
+    ) -> CanisterClientResult<OperationConcurrencyLimits> {
+        self.client()
+            .query("get_operation_concurrency_limits", ())
+            .await
+    }
+
+    /// Sets max number of the concurrently progressing operations per direction.
+    /// Zero limit pauses the progress of the direction operations.
+    ///
+    /// This method is only for canister owner.
+    async fn set_operation_concurrency_limits(
+        &self,
+        limits: OperationConcurrencyLimits,
+    ) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_operation_concurrency_limits", (limits,))
+            .await
+    }
+
+    /// Returns the reason and time of the bridge halt, if the bridge is halted.
+    async fn get_bridge_halt(&self) -> CanisterClientResult<Option<BridgeHalt>> {
+        self.client().query("get_bridge_halt", ()).await
+    }
+
+    /// Resumes the progress of the operations, stopped by the bridge halt.
+    ///
+    /// This method is only for canister owner.
+    async fn resume_bridge(&self) -> CanisterClientResult<()> {
+        self.client().update("resume_bridge", ()).await
+    }
+
+    /// Returns the drift between the nonce, reserved by the bridge canister, and the pending
+    /// nonce of the bridge canister EVM address.
+    async fn get_nonce_drift_status(&self) -> CanisterClientResult<NonceDriftStatus> {
+        self.client().query("get_nonce_drift_status", ()).await
+    }
+
+    /// Closes the open nonce gap and resumes mint transactions sending.
+    ///
+    /// This method is only for canister owner.
+    async fn acknowledge_nonce_gap(&self) -> CanisterClientResult<Option<NonceGap>> {
+        self.client().update("acknowledge_nonce_gap", ()).await
+    }
+
+    /// Returns the mode of the canister.
+    async fn get_bridge_mode(&self) -> CanisterClientResult<BridgeMode> {
+        self.client().query("get_bridge_mode", ()).await
+    }
+
+    /// Returns the number of the operations per direction, deferred because the direction
+    /// concurrency limit is reached.
+    async fn get_deferred_operations_stats(&self) -> CanisterClientResult<DeferredOperationsStats> {
+        self.client()
+            .query("get_deferred_operations_stats", ())
+            .await
+    }
+
+    /// Returns snapshots of the EVM params, newest first.
+    ///
+    /// This method is only for canister owner.
+    async fn get_evm_params_history(
+        &self,
+        pagination: Option<bridge_utils::common::Pagination>,
+    ) -> CanisterClientResult<Vec<EvmParamsSnapshot>> {
+        self.client()
+            .query("get_evm_params_history", (pagination,))
+            .await
+    }
+
+    /// Sets max number of the EVM params snapshots to keep.
+    ///
+    /// This method is only for canister owner.
+    async fn set_evm_params_history_size(&self, size: u32) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_evm_params_history_size", (size,))
+            .await
+    }
+
+    /// Returns percentiles of the EVM node round-trip time.
+    async fn get_evm_latency_stats(&self) -> CanisterClientResult<LatencyStats> {
+        self.client().query("get_evm_latency_stats", ()).await
+    }
+
+    /// Returns the number and age of the signed mint orders batches, which wait to be sent.
+    async fn get_pending_mint_batches_stats(
+        &self,
+    ) -> CanisterClientResult<PendingMintBatchesStats> {
+        self.client()
+            .query("get_pending_mint_batches_stats", ())
+            .await
+    }
+
+    /// Sets the latency of the EVM node, above which a warning is logged.
+    ///
+    /// This method is only for canister owner.
+    async fn set_max_acceptable_evm_latency_ms(
+        &self,
+        max_latency_ms: Option<u64>,
+    ) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_max_acceptable_evm_latency_ms", (max_latency_ms,))
+            .await
+    }
+
+    /// Returns current state of the operation nonces.
+    async fn get_nonce_state(&self) -> CanisterClientResult<NonceState> {
+        self.client().query("get_nonce_state", ()).await
+    }
+
+    /// Sets minimal id (and so nonce) of the operations created by the canister.
+    /// The ERC-20 bridge rejects offsets above the max mint order nonce.
+    ///
+    /// This method is only for canister owner.
+    async fn set_nonce_offset(&self, offset: u64) -> CanisterClientResult<BTFResult<()>> {
+        self.client().update("set_nonce_offset", (offset,)).await
+    }
+
+    /// Returns the operation log bytes, as they are stored in the canister stable memory.
+    ///
+    /// This method is only for canister owner.
+    async fn get_operation_raw_bytes(
+        &self,
+        operation_id: OperationId,
+    ) -> CanisterClientResult<Option<Vec<u8>>> {
+        self.client()
+            .query("get_operation_raw_bytes", (operation_id,))
+            .await
+    }
+
+    /// Replays the operation log steps through the operation transition graph and returns
+    /// the first inconsistency with the stored state of the operation, if any.
+    ///
+    /// This method is only for canister owner.
+    async fn verify_operation_consistency(
+        &self,
+        operation_id: OperationId,
+    ) -> CanisterClientResult<Result<(), String>> {
+        self.client()
+            .query("verify_operation_consistency", (operation_id,))
+            .await
+    }
+
+    /// Returns the captured progress invocations of the operation, oldest first.
+    ///
+    /// This method is only for canister owner.
+    async fn get_operation_replay(
+        &self,
+        operation_id: OperationId,
+    ) -> CanisterClientResult<Vec<ProgressReplayEntry>> {
+        self.client()
+            .query("get_operation_replay", (operation_id,))
+            .await
+    }
+
+    /// Enables or disables the capture of the operation progress to the replay log.
+    ///
+    /// This method is only for canister owner.
+    async fn set_operation_replay_capture(
+        &self,
+        operation_id: OperationId,
+        enabled: bool,
+    ) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_operation_replay_capture", (operation_id, enabled))
+            .await
+    }
+
+    /// Returns the compact statuses of the operations in the order of `operation_ids`,
+    /// with `None` for the unknown operations.
+    ///
+    /// Up to [`MAX_OPERATIONS_STATUS_BATCH`] operations can be requested at once.
+    ///
+    /// [`MAX_OPERATIONS_STATUS_BATCH`]: bridge_did::operation_status::MAX_OPERATIONS_STATUS_BATCH
+    async fn get_operations_status_batch(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> CanisterClientResult<BTFResult<Vec<(OperationId, Option<OperationStatusView>)>>> {
+        self.client()
+            .query("get_operations_status_batch", (operation_ids,))
+            .await
+    }
+
+    /// Schedules the progress of the incomplete operations, matching the `filter`, immediately.
+    ///
+    /// This method is only for canister owner.
+    async fn bulk_retry(
+        &self,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+    ) -> CanisterClientResult<BulkActionResult> {
+        self.client()
+            .update("bulk_retry", (filter, continuation))
+            .await
+    }
+
+    /// Cancels the incomplete operations, matching the `filter`, if they can be cancelled.
+    ///
+    /// This method is only for canister owner.
+    async fn bulk_cancel(
+        &self,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+    ) -> CanisterClientResult<BulkActionResult> {
+        self.client()
+            .update("bulk_cancel", (filter, continuation))
+            .await
+    }
+
+    /// Lets the deferred operations, matching the `filter`, progress regardless of the
+    /// direction concurrency limit.
+    ///
+    /// This method is only for canister owner.
+    async fn bulk_approve(
+        &self,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+    ) -> CanisterClientResult<BulkActionResult> {
+        self.client()
+            .update("bulk_approve", (filter, continuation))
+            .await
+    }
+
+    /// Returns the audit log of the operator bulk actions and notes.
+    ///
+    /// This method is only for canister owner.
+    async fn get_operator_audit_log(
+        &self,
+        pagination: Option<bridge_utils::common::Pagination>,
+    ) -> CanisterClientResult<Vec<OperatorAuditEntry>> {
+        self.client()
+            .query("get_operator_audit_log", (pagination,))
+            .await
+    }
+
+    /// Adds the note to the operator audit log. Returns id of the audit log entry.
+    ///
+    /// This method is only for canister owner.
+    async fn add_operator_audit_note(&self, note: String) -> CanisterClientResult<BTFResult<u64>> {
+        self.client()
+            .update("add_operator_audit_note", (note,))
+            .await
+    }
+
+    /// Erases the personal data linkage of the user from the historical operations.
+    ///
+    /// This method is only for canister owner.
+    async fn anonymize_user_data(
+        &self,
+        identity: UserIdentity,
+    ) -> CanisterClientResult<BTFResult<UserDataErasure>> {
+        self.client()
+            .update("anonymize_user_data", (identity,))
+            .await
+    }
+
+    /// Returns the destinations to be notified when an operation is complete.
+    ///
+    /// This method is only for canister owner.
+    async fn get_notification_destinations(&self) -> CanisterClientResult<Vec<OutboxDestination>> {
+        self.client()
+            .query("get_notification_destinations", ())
+            .await
+    }
+
+    /// Sets the destinations to be notified when an operation is complete.
+    ///
+    /// This method is only for canister owner.
+    async fn set_notification_destinations(
+        &self,
+        destinations: Vec<OutboxDestination>,
+    ) -> CanisterClientResult<()> {
+        self.client()
+            .update("set_notification_destinations", (destinations,))
+            .await
+    }
+
+    /// Returns the outbox messages, parked after too many failed delivery attempts.
+    /// Without `pagination`, only a bounded window of the oldest messages is returned.
+    ///
+    /// This method is only for canister owner.
+    async fn get_parked_outbox_messages(
+        &self,
+        pagination: Option<bridge_utils::common::Pagination>,
+    ) -> CanisterClientResult<Vec<OutboxMessage>> {
+        self.client()
+            .query("get_parked_outbox_messages", (pagination,))
+            .await
+    }
+
+    /// Moves the parked outbox message back to the delivery queue.
+    ///
+    /// This method is only for canister owner.
+    async fn requeue_outbox_message(&self, id: u64) -> CanisterClientResult<BTFResult<()>> {
+        self.client().update("requeue_outbox_message", (id,)).await
+    }
+
+    /// Removes the parked outbox message without delivery.
+    ///
+    /// This method is only for canister owner.
+    async fn drop_outbox_message(&self, id: u64) -> CanisterClientResult<BTFResult<()>> {
+        self.client().update("drop_outbox_message", (id,)).await
+    }
+
+    /// Returns the burnt events, rejected by the strict event validation.
+    /// Without `pagination`, only a bounded window of the oldest kept events is returned.
+    ///
+    /// This method is only for canister owner.
+    async fn get_dead_letter_events(
+        &self,
+        pagination: Option<bridge_utils::common::Pagination>,
+    ) -> CanisterClientResult<Vec<DeadLetterEvent>> {
+        self.client()
+            .query("get_dead_letter_events", (pagination,))
+            .await
+    }
+
+    /// Returns the blocks of the operations log in the ICRC-3 `get_blocks` format.
+    async fn get_operation_blocks(
+        &self,
+        start: u64,
+        length: u64,
+    ) -> CanisterClientResult<GetBlocksResult> {
+        self.client()
+            .query(
+                "get_operation_blocks",
+                (Nat::from(start), Nat::from(length)),
+            )
+            .await
+    }
+
+    /// Returns up to `length` blocks of the operations log, starting from the `start` index,
+    /// in the format imported by the replica canisters.
+    async fn export_operation_blocks(
+        &self,
+        start: u64,
+        length: u64,
+    ) -> CanisterClientResult<OperationBlocksExport> {
+        self.client()
+            .query("export_operation_blocks", (start, length))
+            .await
+    }
+
+    /// Returns the sync status of the replica canister, or `None` if the canister
+    /// is the primary.
+    async fn get_replica_sync_status(&self) -> CanisterClientResult<Option<ReplicaSyncStatus>> {
+        self.client().query("get_replica_sync_status", ()).await
+    }
+
+    /// Promotes the replica canister to the primary mode, if the primary canister is halted
+    /// or doesn't respond.
+    ///
+    /// This method is only for canister owner.
+    async fn promote_to_primary(&self) -> CanisterClientResult<BTFResult<()>> {
+        self.client().update("promote_to_primary", ()).await
+    }
+
+    /// Returns counters of the BTF bridge events, which were rejected before processing.
+    async fn get_rejected_events_stats(&self) -> CanisterClientResult<RejectedEventsStats> {
+        self.client().query("get_rejected_events_stats", ()).await
+    }
+
+    /// Returns the sizes of the scheduler task payloads, moved to the task payloads store.
+    async fn get_task_payload_stats(&self) -> CanisterClientResult<TaskPayloadStats> {
+        self.client().query("get_task_payload_stats", ()).await
+    }
+
+    /// Returns the BTF bridge events of the user with the given EVM address,
+    /// which were processed by the bridge.
+    async fn get_processed_events(
+        &self,
+        address: H160,
+        pagination: Option<bridge_utils::common::Pagination>,
+    ) -> CanisterClientResult<Vec<ProcessedEventInfo>> {
+        self.client()
+            .query("get_processed_events", (address, pagination))
+            .await
+    }
+
+    /// Returns the adjustments of the mint fees, paid by the given EVM address,
+    /// to the actual cost of the mint transactions.
+    async fn get_fee_adjustments(
+        &self,
+        fee_payer: H160,
+        pagination: Option<bridge_utils::common::Pagination>,
+    ) -> CanisterClientResult<FeePayerAdjustments> {
+        self.client()
+            .query("get_fee_adjustments", (fee_payer, pagination))
+            .await
     }
 
     /// Returns `(nonce, mint_order)` pairs for the given sender id.
@@ -124,6 +763,11 @@ pub trait BridgeCanisterClient<C: CanisterClient> {
         self.client().update("add_to_whitelist", (principal,)).await
     }
 
+    /// Returns the list of all principals in the whitelist.
+    async fn get_whitelist(&self) -> CanisterClientResult<Vec<Principal>> {
+        self.client().query("get_whitelist", ()).await
+    }
+
     /// Removes the given principal from the whitelist.
     async fn remove_from_whitelist(
         &self,
@@ -1,20 +1,58 @@
+use std::time::Duration;
+
 use bridge_did::error::BTFResult;
+use bridge_did::evm_latency::LatencyStats;
+use bridge_did::evm_link::PrivateTxRelay;
+use bridge_did::evm_params_history::EvmParamsSnapshot;
+use bridge_did::evm_tx::EvmTxRequest;
+use bridge_did::gas_balance::{GasBalanceStatus, GasBalanceThresholds};
+use bridge_did::nonce_drift::{NonceDriftStatus, NonceGap};
 use bridge_did::op_id::OperationId;
 use bridge_did::operation_log::{Memo, OperationLog};
 use bridge_did::operations::Erc20BridgeOp;
+use bridge_did::pending_mint_batch::PendingMintBatchesStats;
 use bridge_utils::common::Pagination;
 use did::H160;
 use ic_canister_client::{CanisterClient, CanisterClientResult};
 
 use crate::bridge_client::BridgeCanisterClient;
 
+/// Deposit confirmation time estimate, used if the canister has no deposit latency history.
+pub const DEFAULT_DEPOSIT_CONFIRMATION_ESTIMATE: Duration = Duration::from_secs(5 * 60);
+
 pub struct Erc20BridgeClient<C> {
     client: C,
+    default_deposit_estimate: Duration,
 }
 
 impl<C: CanisterClient> Erc20BridgeClient<C> {
     pub fn new(client: C) -> Self {
-        Self { client }
+        Self {
+            client,
+            default_deposit_estimate: DEFAULT_DEPOSIT_CONFIRMATION_ESTIMATE,
+        }
+    }
+
+    /// Sets the deposit confirmation time estimate, returned while the canister
+    /// has no deposit latency history.
+    pub fn with_default_deposit_estimate(mut self, default_estimate: Duration) -> Self {
+        self.default_deposit_estimate = default_estimate;
+        self
+    }
+
+    /// Returns the latency stats of the completed deposits.
+    pub async fn get_deposit_latency_stats(&self) -> CanisterClientResult<LatencyStats> {
+        self.client.query("get_deposit_latency_stats", ()).await
+    }
+
+    /// Estimates the deposit confirmation time as the 95th percentile of the latency
+    /// of the completed deposits. Returns the default estimate if there is no history yet.
+    pub async fn estimate_deposit_confirmation_time(&self) -> CanisterClientResult<Duration> {
+        let stats = self.get_deposit_latency_stats().await?;
+        Ok(deposit_confirmation_estimate(
+            &stats,
+            self.default_deposit_estimate,
+        ))
     }
 
     /// Retrieves all operations for the given ETH wallet address whose
@@ -22,7 +60,8 @@ impl<C: CanisterClient> Erc20BridgeClient<C> {
     /// The operations are then paginated with the given `pagination` parameters,
     /// starting from `offset` returning a max of `count` items
     /// If `offset` is `None`, it starts from the beginning (i.e. the first entry is the min_included_id).
-    /// If `count` is `None`, it returns all operations.
+    /// If `pagination` is `None`, only the most recent operations are returned, 1000 by default
+    /// (see `set_operations_list_default_limit`), so callers should paginate to get the full history.
     pub async fn get_operations_list(
         &self,
         wallet_address: &H160,
@@ -65,12 +104,98 @@ impl<C: CanisterClient> Erc20BridgeClient<C> {
             .await
     }
 
+    /// Returns the `batchMint` transaction request of the operation, which signed mint order
+    /// should be sent to the EVM by the user.
+    pub async fn get_mint_tx_request(
+        &self,
+        operation_id: OperationId,
+    ) -> CanisterClientResult<BTFResult<EvmTxRequest>> {
+        self.client
+            .query("get_mint_tx_request", (operation_id,))
+            .await
+    }
+
     pub async fn set_base_btf_bridge_contract(&self, address: &H160) -> CanisterClientResult<()> {
         self.client
             .update("set_base_btf_bridge_contract", (address,))
             .await
     }
 
+    pub async fn get_base_gas_balance_status(&self) -> CanisterClientResult<GasBalanceStatus> {
+        self.client.query("get_base_gas_balance_status", ()).await
+    }
+
+    pub async fn set_base_gas_balance_thresholds(
+        &self,
+        thresholds: Option<GasBalanceThresholds>,
+    ) -> CanisterClientResult<()> {
+        self.client
+            .update("set_base_gas_balance_thresholds", (thresholds,))
+            .await
+    }
+
+    pub async fn get_base_evm_params_history(
+        &self,
+        pagination: Option<Pagination>,
+    ) -> CanisterClientResult<Vec<EvmParamsSnapshot>> {
+        self.client
+            .query("get_base_evm_params_history", (pagination,))
+            .await
+    }
+
+    pub async fn set_base_evm_params_history_size(&self, size: u32) -> CanisterClientResult<()> {
+        self.client
+            .update("set_base_evm_params_history_size", (size,))
+            .await
+    }
+
+    pub async fn get_base_evm_latency_stats(&self) -> CanisterClientResult<LatencyStats> {
+        self.client.query("get_base_evm_latency_stats", ()).await
+    }
+
+    pub async fn get_base_pending_mint_batches_stats(
+        &self,
+    ) -> CanisterClientResult<PendingMintBatchesStats> {
+        self.client
+            .query("get_base_pending_mint_batches_stats", ())
+            .await
+    }
+
+    pub async fn set_base_max_acceptable_evm_latency_ms(
+        &self,
+        max_latency_ms: Option<u64>,
+    ) -> CanisterClientResult<()> {
+        self.client
+            .update("set_base_max_acceptable_evm_latency_ms", (max_latency_ms,))
+            .await
+    }
+
+    pub async fn set_base_strict_event_validation(
+        &self,
+        enabled: bool,
+    ) -> CanisterClientResult<()> {
+        self.client
+            .update("set_base_strict_event_validation", (enabled,))
+            .await
+    }
+
+    pub async fn set_base_private_tx_relay(
+        &self,
+        relay: Option<PrivateTxRelay>,
+    ) -> CanisterClientResult<()> {
+        self.client
+            .update("set_base_private_tx_relay", (relay,))
+            .await
+    }
+
+    pub async fn get_base_nonce_drift_status(&self) -> CanisterClientResult<NonceDriftStatus> {
+        self.client.query("get_base_nonce_drift_status", ()).await
+    }
+
+    pub async fn acknowledge_base_nonce_gap(&self) -> CanisterClientResult<Option<NonceGap>> {
+        self.client.update("acknowledge_base_nonce_gap", ()).await
+    }
+
     pub async fn get_bridge_canister_base_evm_address(
         &self,
     ) -> CanisterClientResult<BTFResult<H160>> {
@@ -85,3 +210,44 @@ impl<C: CanisterClient> BridgeCanisterClient<C> for Erc20BridgeClient<C> {
         &self.client
     }
 }
+
+fn deposit_confirmation_estimate(stats: &LatencyStats, default_estimate: Duration) -> Duration {
+    if stats.samples_count == 0 {
+        return default_estimate;
+    }
+
+    Duration::from_nanos(stats.p95_ns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND_NS: u64 = 1_000_000_000;
+
+    #[test]
+    fn estimate_is_p95_latency() {
+        // Nearest-rank 95th percentile of the latencies 1..=100 seconds.
+        let stats = LatencyStats {
+            samples_count: 100,
+            p50_ns: 50 * SECOND_NS,
+            p95_ns: 95 * SECOND_NS,
+            p99_ns: 99 * SECOND_NS,
+            max_acceptable_latency_ms: None,
+        };
+
+        assert_eq!(
+            deposit_confirmation_estimate(&stats, DEFAULT_DEPOSIT_CONFIRMATION_ESTIMATE),
+            Duration::from_secs(95)
+        );
+    }
+
+    #[test]
+    fn default_estimate_without_history() {
+        let default_estimate = Duration::from_secs(42);
+        assert_eq!(
+            deposit_confirmation_estimate(&LatencyStats::default(), default_estimate),
+            default_estimate
+        );
+    }
+}
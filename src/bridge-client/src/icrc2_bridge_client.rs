@@ -1,8 +1,19 @@
+use bridge_did::burn_intent::BurnIntent;
+use bridge_did::deposit_account::{DepositAccountInfo, DepositAllowance};
+use bridge_did::deposit_bundle::{BundleId, DepositBundleStatus};
+use bridge_did::error::BTFResult;
+use bridge_did::evm_tx::EvmTxRequest;
+use bridge_did::icrc21::{ConsentInfo, ConsentMessageRequest, Icrc21Error};
 use bridge_did::op_id::OperationId;
 use bridge_did::operation_log::{Memo, OperationLog};
 use bridge_did::operations::IcrcBridgeOp;
+use bridge_did::preferences::BridgePreferences;
+use bridge_did::reason::{ApproveAmountPolicy, Icrc2Burn};
+use bridge_did::supply_guard::{SupplyCheck, SupplyGuardConfig};
+use bridge_did::token_decimals::{DecimalsChangeStrategy, TokenDecimals};
 use bridge_utils::common::Pagination;
-use did::H160;
+use candid::Principal;
+use did::{H160, U256};
 use ic_canister_client::{CanisterClient, CanisterClientResult};
 
 use crate::bridge_client::BridgeCanisterClient;
@@ -21,7 +32,8 @@ impl<C: CanisterClient> Icrc2BridgeClient<C> {
     /// The operations are then paginated with the given `pagination` parameters,
     /// starting from `offset` returning a max of `count` items
     /// If `offset` is `None`, it starts from the beginning (i.e. the first entry is the min_included_id).
-    /// If `count` is `None`, it returns all operations.
+    /// If `pagination` is `None`, only the most recent operations are returned, 1000 by default
+    /// (see `set_operations_list_default_limit`), so callers should paginate to get the full history.
     pub async fn get_operations_list(
         &self,
         wallet_address: &H160,
@@ -36,6 +48,18 @@ impl<C: CanisterClient> Icrc2BridgeClient<C> {
             .await
     }
 
+    /// Retrieves all operations, which use the given ICRC token, ordered by id.
+    /// If `pagination` is `None`, all the operations are returned.
+    pub async fn get_operations_by_src_token(
+        &self,
+        token: Principal,
+        pagination: Option<Pagination>,
+    ) -> CanisterClientResult<Vec<(OperationId, IcrcBridgeOp)>> {
+        self.client
+            .query("get_operations_by_src_token", (token, pagination))
+            .await
+    }
+
     pub async fn get_operation_log(
         &self,
         operation_id: OperationId,
@@ -63,6 +87,314 @@ impl<C: CanisterClient> Icrc2BridgeClient<C> {
             .query("get_memos_by_user_address", (user_id,))
             .await
     }
+
+    /// Returns the `batchMint` transaction request of the operation, which signed mint order
+    /// should be sent to the EVM by the user.
+    pub async fn get_mint_tx_request(
+        &self,
+        operation_id: OperationId,
+    ) -> CanisterClientResult<BTFResult<EvmTxRequest>> {
+        self.client
+            .query("get_mint_tx_request", (operation_id,))
+            .await
+    }
+
+    /// Returns incomplete operations, which are pending in their current state
+    /// for longer than `max_age_secs` seconds.
+    pub async fn get_operations_exceeding_sla(
+        &self,
+        max_age_secs: u64,
+    ) -> CanisterClientResult<Vec<(OperationId, IcrcBridgeOp)>> {
+        self.client
+            .query("get_operations_exceeding_sla", (max_age_secs,))
+            .await
+    }
+
+    /// Returns the estimated number of seconds till the operation completion.
+    pub async fn estimate_completion(
+        &self,
+        operation_id: OperationId,
+    ) -> CanisterClientResult<Option<u64>> {
+        self.client
+            .query("estimate_completion", (operation_id,))
+            .await
+    }
+
+    /// Returns the bridge account, which deposit senders approve as the spender of the
+    /// `token`, with the ledger fee and the minimal deposit amount.
+    pub async fn get_deposit_account_info(
+        &self,
+        recipient_address: &H160,
+        token: Principal,
+    ) -> CanisterClientResult<BTFResult<DepositAccountInfo>> {
+        self.client
+            .query("get_deposit_account_info", (recipient_address, token))
+            .await
+    }
+
+    /// Returns the allowance, which the caller should approve for the deposit spender
+    /// before the deposit of the `amount` of the `token`.
+    pub async fn get_required_allowance(
+        &self,
+        token: Principal,
+        amount: &U256,
+    ) -> CanisterClientResult<BTFResult<U256>> {
+        self.client
+            .query("get_required_allowance", (token, amount))
+            .await
+    }
+
+    /// Returns the deposit account info with the remaining allowance of the caller
+    /// for the deposit spender.
+    pub async fn get_deposit_allowance(
+        &self,
+        recipient_address: &H160,
+        token: Principal,
+        from_subaccount: Option<[u8; 32]>,
+    ) -> CanisterClientResult<BTFResult<DepositAllowance>> {
+        self.client
+            .update(
+                "get_deposit_allowance",
+                (recipient_address, token, from_subaccount),
+            )
+            .await
+    }
+
+    /// Creates deposits for the burns of the caller's ICRC-2 tokens.
+    /// Returns the result of each burn.
+    pub async fn burn_icrc2_batch(
+        &self,
+        reasons: Vec<Icrc2Burn>,
+    ) -> CanisterClientResult<BTFResult<Vec<BTFResult<OperationId>>>> {
+        self.client.update("burn_icrc2_batch", (reasons,)).await
+    }
+
+    /// Creates an atomic bundle of deposits for the burns of the caller's ICRC-2 tokens.
+    pub async fn create_deposit_bundle(
+        &self,
+        reasons: Vec<Icrc2Burn>,
+    ) -> CanisterClientResult<BTFResult<BundleId>> {
+        self.client
+            .update("create_deposit_bundle", (reasons,))
+            .await
+    }
+
+    /// Returns the progress of the deposit bundle and its deposits.
+    pub async fn get_bundle_status(
+        &self,
+        bundle_id: BundleId,
+    ) -> CanisterClientResult<Option<DepositBundleStatus>> {
+        self.client.query("get_bundle_status", (bundle_id,)).await
+    }
+
+    /// Confirms the deposit, waiting for the user confirmation.
+    pub async fn confirm_deposit(
+        &self,
+        operation_id: OperationId,
+    ) -> CanisterClientResult<BTFResult<()>> {
+        self.client.update("confirm_deposit", (operation_id,)).await
+    }
+
+    /// Aborts the deposit, waiting for the user confirmation.
+    pub async fn abort_deposit(
+        &self,
+        operation_id: OperationId,
+    ) -> CanisterClientResult<BTFResult<()>> {
+        self.client.update("abort_deposit", (operation_id,)).await
+    }
+
+    /// Cancels the approval after mint of the deposit, so the wrapped tokens are minted
+    /// without the approval.
+    pub async fn cancel_approval(
+        &self,
+        operation_id: OperationId,
+    ) -> CanisterClientResult<BTFResult<()>> {
+        self.client.update("cancel_approval", (operation_id,)).await
+    }
+
+    /// Returns maximum delay in seconds, which deposits can wait for the user confirmation.
+    pub async fn get_max_deposit_confirmation_delay_secs(&self) -> CanisterClientResult<u64> {
+        self.client
+            .query("get_max_deposit_confirmation_delay_secs", ())
+            .await
+    }
+
+    /// Sets maximum delay in seconds, which deposits can wait for the user confirmation.
+    pub async fn set_max_deposit_confirmation_delay_secs(
+        &self,
+        delay_secs: u64,
+    ) -> CanisterClientResult<BTFResult<()>> {
+        self.client
+            .update("set_max_deposit_confirmation_delay_secs", (delay_secs,))
+            .await
+    }
+
+    /// Returns deposit fee in millionths of a token.
+    pub async fn get_deposit_fee(&self) -> CanisterClientResult<u64> {
+        self.client.query("get_deposit_fee", ()).await
+    }
+
+    /// Sets deposit fee in millionths of a token.
+    pub async fn set_deposit_fee(&self, fee: u64) -> CanisterClientResult<BTFResult<()>> {
+        self.client.update("set_deposit_fee", (fee,)).await
+    }
+
+    /// Returns delay in seconds between the deposit burn and the mint order signing.
+    pub async fn get_mint_order_signing_delay_secs(&self) -> CanisterClientResult<u64> {
+        self.client
+            .query("get_mint_order_signing_delay_secs", ())
+            .await
+    }
+
+    /// Sets delay in seconds between the deposit burn and the mint order signing.
+    pub async fn set_mint_order_signing_delay_secs(
+        &self,
+        delay_secs: u64,
+    ) -> CanisterClientResult<BTFResult<()>> {
+        self.client
+            .update("set_mint_order_signing_delay_secs", (delay_secs,))
+            .await
+    }
+
+    /// Returns time in seconds, which the spender has to claim the withdrawal, delivered
+    /// with an approval.
+    pub async fn get_approval_delivery_expiry_secs(&self) -> CanisterClientResult<u64> {
+        self.client
+            .query("get_approval_delivery_expiry_secs", ())
+            .await
+    }
+
+    /// Sets time in seconds, which the spender has to claim the withdrawal, delivered
+    /// with an approval.
+    pub async fn set_approval_delivery_expiry_secs(
+        &self,
+        expiry_secs: u64,
+    ) -> CanisterClientResult<BTFResult<()>> {
+        self.client
+            .update("set_approval_delivery_expiry_secs", (expiry_secs,))
+            .await
+    }
+
+    /// Returns handling of the approve after mint amounts, which exceed the minted amount.
+    pub async fn get_approve_amount_policy(&self) -> CanisterClientResult<ApproveAmountPolicy> {
+        self.client.query("get_approve_amount_policy", ()).await
+    }
+
+    /// Sets handling of the approve after mint amounts, which exceed the minted amount.
+    ///
+    /// This method is only for canister owner.
+    pub async fn set_approve_amount_policy(
+        &self,
+        policy: ApproveAmountPolicy,
+    ) -> CanisterClientResult<BTFResult<()>> {
+        self.client
+            .update("set_approve_amount_policy", (policy,))
+            .await
+    }
+
+    /// Adds the ICRC-2 token to the list of tokens, allowed to be bridged.
+    pub async fn add_allowed_token(&self, token: Principal) -> CanisterClientResult<BTFResult<()>> {
+        self.client.update("add_allowed_token", (token,)).await
+    }
+
+    /// Removes the ICRC-2 token from the list of tokens, allowed to be bridged.
+    pub async fn remove_allowed_token(
+        &self,
+        token: Principal,
+    ) -> CanisterClientResult<BTFResult<()>> {
+        self.client.update("remove_allowed_token", (token,)).await
+    }
+
+    /// Disables the token allow list, so all the ICRC-2 tokens are allowed to be bridged.
+    pub async fn disable_token_allow_list(&self) -> CanisterClientResult<BTFResult<()>> {
+        self.client.update("disable_token_allow_list", ()).await
+    }
+
+    /// Returns the ICRC-2 tokens, allowed to be bridged, or `None` if all tokens are allowed.
+    pub async fn get_allowed_tokens(&self) -> CanisterClientResult<Option<Vec<Principal>>> {
+        self.client.query("get_allowed_tokens", ()).await
+    }
+
+    /// Stores the deposit preferences of the caller.
+    pub async fn set_bridge_preferences(
+        &self,
+        preferences: BridgePreferences,
+    ) -> CanisterClientResult<BTFResult<()>> {
+        self.client
+            .update("set_bridge_preferences", (preferences,))
+            .await
+    }
+
+    /// Returns the deposit preferences of the caller.
+    pub async fn get_bridge_preferences(&self) -> CanisterClientResult<Option<BridgePreferences>> {
+        self.client.query("get_bridge_preferences", ()).await
+    }
+
+    /// Deletes the deposit preferences of the caller.
+    pub async fn delete_bridge_preferences(&self) -> CanisterClientResult<BTFResult<()>> {
+        self.client.update("delete_bridge_preferences", ()).await
+    }
+
+    /// Returns decimals of the ICRC token and its wrapped token, tracked by the bridge.
+    pub async fn get_token_decimals(
+        &self,
+        token: Principal,
+    ) -> CanisterClientResult<Option<TokenDecimals>> {
+        self.client.query("get_token_decimals", (token,)).await
+    }
+
+    /// Resolves the detected decimals change of the ICRC token ledger.
+    ///
+    /// This method is only for canister owner.
+    pub async fn resolve_decimals_change(
+        &self,
+        token: Principal,
+        strategy: DecimalsChangeStrategy,
+    ) -> CanisterClientResult<BTFResult<()>> {
+        self.client
+            .update("resolve_decimals_change", (token, strategy))
+            .await
+    }
+
+    /// Returns the supply guard configuration, or `None` if the guard is disabled.
+    pub async fn get_supply_guard_config(&self) -> CanisterClientResult<Option<SupplyGuardConfig>> {
+        self.client.query("get_supply_guard_config", ()).await
+    }
+
+    /// Sets the supply guard configuration. `None` disables the guard.
+    ///
+    /// This method is only for canister owner.
+    pub async fn set_supply_guard_config(
+        &self,
+        config: Option<SupplyGuardConfig>,
+    ) -> CanisterClientResult<BTFResult<()>> {
+        self.client
+            .update("set_supply_guard_config", (config,))
+            .await
+    }
+
+    /// Returns the latest supply check of the supply guard.
+    pub async fn get_last_supply_check(&self) -> CanisterClientResult<Option<SupplyCheck>> {
+        self.client.query("get_last_supply_check", ()).await
+    }
+
+    /// Returns the burn intent of the deposit operation, recorded before the ICRC tokens burn.
+    pub async fn get_burn_intent(
+        &self,
+        operation_id: OperationId,
+    ) -> CanisterClientResult<Option<BurnIntent>> {
+        self.client.query("get_burn_intent", (operation_id,)).await
+    }
+
+    /// Returns ICRC-21 consent message for the bridge canister call.
+    pub async fn icrc21_canister_call_consent_message(
+        &self,
+        request: ConsentMessageRequest,
+    ) -> CanisterClientResult<Result<ConsentInfo, Icrc21Error>> {
+        self.client
+            .update("icrc21_canister_call_consent_message", (request,))
+            .await
+    }
 }
 
 impl<C: CanisterClient> BridgeCanisterClient<C> for Icrc2BridgeClient<C> {
@@ -3,6 +3,8 @@ mod bridge_client;
 mod btc_bridge_client;
 mod erc20_bridge_client;
 mod icrc2_bridge_client;
+pub mod macros;
+pub mod operation_stream;
 #[cfg(feature = "runes")]
 mod rune_bridge_client;
 
@@ -11,5 +13,6 @@ pub use bridge_client::*;
 pub use btc_bridge_client::*;
 pub use erc20_bridge_client::*;
 pub use icrc2_bridge_client::*;
+pub use operation_stream::{OperationStream, OperationUpdate, OperationsSource};
 #[cfg(feature = "runes")]
 pub use rune_bridge_client::*;
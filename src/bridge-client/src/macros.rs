@@ -0,0 +1,153 @@
+//! Encoding of the bridge canister method arguments with compile-time type checks.
+
+use bridge_did::concurrency::OperationConcurrencyLimits;
+use bridge_did::event_filter::EventTopicFilter;
+use bridge_did::evm_link::PrivateTxRelay;
+use bridge_did::fee_adjustment::FeeReconciliationSettings;
+use bridge_did::gas_balance::GasBalanceThresholds;
+use bridge_did::http_transform::HttpTransformSettings;
+use bridge_did::id256::Id256;
+use bridge_did::op_id::OperationId;
+use bridge_did::operation_replay::ReplayCaptureSettings;
+use bridge_did::outbox::OutboxDestination;
+use bridge_did::retry_profile::RetryProfiles;
+use bridge_utils::common::Pagination;
+use candid::types::Type;
+use candid::{CandidType, Nat, Principal};
+use did::H160;
+
+#[doc(hidden)]
+pub use candid::encode_args as __encode_args;
+
+/// Declares the expected argument types of the bridge canister methods.
+///
+/// For every method generates a function with the method arguments in the [`signatures`]
+/// module, and an entry of the [`EXPECTED_CANDID_TYPES`] map.
+macro_rules! candid_signatures {
+    ($($method:ident($($ty:ty),*);)*) => {
+        /// Functions with the expected arguments of the bridge canister methods.
+        /// Used by [`typed_args`](crate::typed_args) to check the argument types.
+        pub mod signatures {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $(
+                pub fn $method($(_: $ty),*) {}
+            )*
+        }
+
+        /// Expected Candid types of the bridge canister methods arguments, by the method name.
+        pub const EXPECTED_CANDID_TYPES: &[(&str, fn() -> Vec<Type>)] = &[
+            $((stringify!($method), || vec![$(<$ty as CandidType>::ty()),*]),)*
+        ];
+    };
+}
+
+candid_signatures! {
+    get_owner();
+    get_bridge_manifest();
+    set_owner(Principal);
+    get_evm_principal();
+    set_btf_bridge_contract(H160);
+    get_btf_bridge_contract();
+    set_gas_balance_thresholds(Option<GasBalanceThresholds>);
+    set_operation_log_compression(bool);
+    set_strict_event_validation(bool);
+    set_timers_enabled(bool);
+    timers_enabled();
+    get_evm_params_history(Option<Pagination>);
+    set_evm_params_history_size(u32);
+    set_max_acceptable_evm_latency_ms(Option<u64>);
+    set_gas_price_refresh_interval(Option<u64>);
+    set_operation_concurrency_limits(OperationConcurrencyLimits);
+    set_private_tx_relay(Option<PrivateTxRelay>);
+    set_dropped_tx_timeout(Option<u64>);
+    set_rpc_request_timeout(Option<u64>);
+    set_http_transform_settings(Option<HttpTransformSettings>);
+    set_retry_profiles(Option<RetryProfiles>);
+    set_replay_capture_settings(Option<ReplayCaptureSettings>);
+    set_operations_list_default_limit(Option<u64>);
+    set_fee_reconciliation(Option<FeeReconciliationSettings>);
+    set_event_topic_filter(Option<EventTopicFilter>);
+    set_nonce_offset(u64);
+    get_operation_raw_bytes(OperationId);
+    verify_operation_consistency(OperationId);
+    get_operation_replay(OperationId);
+    set_operation_replay_capture(OperationId, bool);
+    set_notification_destinations(Vec<OutboxDestination>);
+    get_parked_outbox_messages(Option<Pagination>);
+    requeue_outbox_message(u64);
+    drop_outbox_message(u64);
+    get_dead_letter_events(Option<Pagination>);
+    get_processed_events(H160, Option<Pagination>);
+    get_fee_adjustments(H160, Option<Pagination>);
+    get_operation_blocks(Nat, Nat);
+    export_operation_blocks(u64, u64);
+    list_mint_orders(Id256, Id256);
+    add_to_whitelist(Principal);
+    remove_from_whitelist(Principal);
+}
+
+/// Returns the expected Candid types of the method arguments, if the method is known.
+pub fn expected_candid_types(method: &str) -> Option<Vec<Type>> {
+    EXPECTED_CANDID_TYPES
+        .iter()
+        .find(|(name, _)| *name == method)
+        .map(|(_, types)| types())
+}
+
+/// Encodes the arguments of the bridge canister method call.
+///
+/// Every argument is given with its type, e.g.
+/// `typed_args!(get_operation_blocks, start: Nat, length: Nat)`.
+/// The code fails to compile if the method is unknown, if the number or the types of the
+/// arguments differ from the ones in [`EXPECTED_CANDID_TYPES`], or if an argument value
+/// doesn't have the declared type.
+#[macro_export]
+macro_rules! typed_args {
+    ($method:ident $(, $arg:ident: $ty:ty)* $(,)?) => {{
+        let _: fn($($ty),*) = $crate::macros::signatures::$method;
+        $crate::macros::__encode_args(($({
+            let $arg: $ty = $arg;
+            $arg
+        },)*))
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::{Decode, Encode};
+
+    use super::*;
+
+    #[test]
+    fn typed_args_are_encoded_as_tuple() {
+        let start = Nat::from(5u64);
+        let length = Nat::from(10u64);
+        let encoded = typed_args!(get_operation_blocks, start: Nat, length: Nat).unwrap();
+
+        let (start, length) = Decode!(&encoded, Nat, Nat).unwrap();
+        assert_eq!(start, Nat::from(5u64));
+        assert_eq!(length, Nat::from(10u64));
+    }
+
+    #[test]
+    fn typed_args_without_arguments() {
+        let encoded = typed_args!(get_owner).unwrap();
+        assert_eq!(encoded, Encode!().unwrap());
+    }
+
+    #[test]
+    fn expected_candid_types_are_generated() {
+        assert_eq!(
+            expected_candid_types("get_operation_blocks"),
+            Some(vec![Nat::ty(), Nat::ty()])
+        );
+        assert_eq!(
+            expected_candid_types("set_max_acceptable_evm_latency_ms"),
+            Some(vec![Option::<u64>::ty()])
+        );
+        assert_eq!(expected_candid_types("get_owner"), Some(vec![]));
+        assert_eq!(expected_candid_types("unknown_method"), None);
+    }
+}
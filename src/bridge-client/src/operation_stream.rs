@@ -0,0 +1,371 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bridge_did::op_id::OperationId;
+#[cfg(feature = "runes")]
+use bridge_did::operations::RuneBridgeOp;
+use bridge_did::operations::{Brc20BridgeOp, Erc20BridgeOp, IcrcBridgeOp};
+use bridge_utils::common::Pagination;
+use did::H160;
+use futures::future::BoxFuture;
+use futures::Stream;
+use ic_canister_client::{CanisterClient, CanisterClientResult};
+
+#[cfg(feature = "runes")]
+use crate::RuneBridgeClient;
+use crate::{Brc20BridgeClient, Erc20BridgeClient, Icrc2BridgeClient};
+
+/// Default delay between two polls of the canister if no new operations were found.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Default max number of operations requested from the canister in a single poll.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Operation observed by the [`OperationStream`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationUpdate<Op> {
+    /// Id of the operation.
+    pub id: OperationId,
+    /// Operation state at the moment of the poll.
+    pub op: Op,
+    /// IC timestamp of the last step of the operation.
+    pub timestamp: u64,
+}
+
+/// Source of operations for the [`OperationStream`].
+#[async_trait::async_trait]
+pub trait OperationsSource: Send + Sync + 'static {
+    type Op: Send + 'static;
+
+    /// Returns at most `count` operations of the given wallet with id greater or equal to
+    /// `min_included_id`, ordered by id.
+    async fn operations_since(
+        &self,
+        wallet_address: &H160,
+        min_included_id: Option<OperationId>,
+        count: usize,
+    ) -> CanisterClientResult<Vec<OperationUpdate<Self::Op>>>;
+}
+
+macro_rules! impl_operations_source {
+    ($client:ident, $op:ty) => {
+        #[async_trait::async_trait]
+        impl<C: CanisterClient + 'static> OperationsSource for $client<C> {
+            type Op = $op;
+
+            async fn operations_since(
+                &self,
+                wallet_address: &H160,
+                min_included_id: Option<OperationId>,
+                count: usize,
+            ) -> CanisterClientResult<Vec<OperationUpdate<Self::Op>>> {
+                let operations = self
+                    .get_operations_list(
+                        wallet_address,
+                        min_included_id,
+                        Some(Pagination::new(0, count)),
+                    )
+                    .await?;
+
+                let mut updates = Vec::with_capacity(operations.len());
+                for (id, op) in operations {
+                    let timestamp = self
+                        .get_operation_log(id)
+                        .await?
+                        .and_then(|log| log.log().last().map(|entry| entry.time_stamp))
+                        .unwrap_or_default();
+                    updates.push(OperationUpdate { id, op, timestamp });
+                }
+
+                Ok(updates)
+            }
+        }
+    };
+}
+
+impl_operations_source!(Icrc2BridgeClient, IcrcBridgeOp);
+impl_operations_source!(Erc20BridgeClient, Erc20BridgeOp);
+impl_operations_source!(Brc20BridgeClient, Brc20BridgeOp);
+#[cfg(feature = "runes")]
+impl_operations_source!(RuneBridgeClient, RuneBridgeOp);
+
+type FetchFuture<Op> = BoxFuture<'static, CanisterClientResult<Vec<OperationUpdate<Op>>>>;
+
+enum StreamState<Op> {
+    Idle,
+    Fetching(FetchFuture<Op>),
+    Waiting(Pin<Box<tokio::time::Sleep>>),
+}
+
+/// Stream of the new operations of a wallet.
+///
+/// The stream polls the bridge canister only for operations newer than the current `cursor`,
+/// and moves the cursor forward with every emitted operation. If a poll returns no new
+/// operations, the next one is made after the poll interval.
+///
+/// Errors returned by the canister are yielded to the consumer, and the stream keeps polling
+/// after the poll interval.
+pub struct OperationStream<C: OperationsSource> {
+    source: Arc<C>,
+    wallet_address: H160,
+    cursor: Option<OperationId>,
+    poll_interval: Duration,
+    page_size: usize,
+    buffer: VecDeque<OperationUpdate<C::Op>>,
+    state: StreamState<C::Op>,
+}
+
+impl<C: OperationsSource> OperationStream<C> {
+    /// Creates a stream of all the operations of the given wallet.
+    pub fn new(source: C, wallet_address: H160) -> Self {
+        Self {
+            source: Arc::new(source),
+            wallet_address,
+            cursor: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            page_size: DEFAULT_PAGE_SIZE,
+            buffer: VecDeque::new(),
+            state: StreamState::Idle,
+        }
+    }
+
+    /// Emit only operations with id greater than the given `cursor`.
+    pub fn with_cursor(mut self, cursor: OperationId) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// Sets the delay between polls if no new operations were found.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets the max number of operations requested in a single poll.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Id of the last emitted operation.
+    pub fn cursor(&self) -> Option<OperationId> {
+        self.cursor
+    }
+
+    fn fetch(&self) -> FetchFuture<C::Op> {
+        let source = self.source.clone();
+        let wallet_address = self.wallet_address.clone();
+        let min_included_id = self
+            .cursor
+            .map(|cursor| OperationId::new(cursor.as_u64() + 1));
+        let count = self.page_size;
+
+        Box::pin(async move {
+            source
+                .operations_since(&wallet_address, min_included_id, count)
+                .await
+        })
+    }
+
+    fn wait(&self) -> StreamState<C::Op> {
+        StreamState::Waiting(Box::pin(tokio::time::sleep(self.poll_interval)))
+    }
+
+    fn enqueue(&mut self, mut updates: Vec<OperationUpdate<C::Op>>) {
+        updates.sort_by_key(|update| update.id);
+        for update in updates {
+            if self.cursor.is_some_and(|cursor| update.id <= cursor) {
+                continue;
+            }
+
+            self.cursor = Some(update.id);
+            self.buffer.push_back(update);
+        }
+    }
+}
+
+// The stream never creates pinned references to its fields.
+impl<C: OperationsSource> Unpin for OperationStream<C> {}
+
+impl<C: OperationsSource> Stream for OperationStream<C> {
+    type Item = CanisterClientResult<OperationUpdate<C::Op>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(update) = this.buffer.pop_front() {
+                return Poll::Ready(Some(Ok(update)));
+            }
+
+            match &mut this.state {
+                StreamState::Idle => this.state = StreamState::Fetching(this.fetch()),
+                StreamState::Fetching(fetch) => match fetch.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Ok(updates)) => {
+                        this.enqueue(updates);
+                        this.state = if this.buffer.is_empty() {
+                            this.wait()
+                        } else {
+                            StreamState::Idle
+                        };
+                    }
+                    Poll::Ready(Err(err)) => {
+                        this.state = this.wait();
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                },
+                StreamState::Waiting(sleep) => match sleep.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(()) => this.state = StreamState::Idle,
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use futures::StreamExt;
+
+    use super::*;
+
+    /// Mock canister, which creates a new operation for the wallet on every poll.
+    #[derive(Default)]
+    struct MockSource {
+        operations: Mutex<Vec<OperationUpdate<u32>>>,
+        polls: Mutex<Vec<Option<OperationId>>>,
+    }
+
+    impl MockSource {
+        fn with_operations(count: u64) -> Self {
+            let source = Self::default();
+            for _ in 0..count {
+                source.add_operation();
+            }
+            source
+        }
+
+        fn add_operation(&self) {
+            let mut operations = self.operations.lock().unwrap();
+            let id = operations.len() as u64;
+            operations.push(OperationUpdate {
+                id: OperationId::new(id),
+                op: id as u32 * 10,
+                timestamp: id * 1000,
+            });
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl OperationsSource for Arc<MockSource> {
+        type Op = u32;
+
+        async fn operations_since(
+            &self,
+            _wallet_address: &H160,
+            min_included_id: Option<OperationId>,
+            count: usize,
+        ) -> CanisterClientResult<Vec<OperationUpdate<u32>>> {
+            self.polls.lock().unwrap().push(min_included_id);
+
+            let result = self
+                .operations
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|update| min_included_id.map_or(true, |min| update.id >= min))
+                .take(count)
+                .cloned()
+                .collect();
+
+            // new operation arrives between the polls
+            self.add_operation();
+
+            Ok(result)
+        }
+    }
+
+    fn stream(source: &Arc<MockSource>) -> OperationStream<Arc<MockSource>> {
+        OperationStream::new(source.clone(), H160::from_slice(&[1; 20]))
+            .with_poll_interval(Duration::from_millis(1))
+    }
+
+    #[tokio::test]
+    async fn should_emit_operations_in_order() {
+        let source = Arc::new(MockSource::with_operations(3));
+        let updates: Vec<_> = stream(&source)
+            .take(5)
+            .map(|update| update.unwrap())
+            .collect()
+            .await;
+
+        let ids: Vec<_> = updates.iter().map(|update| update.id.as_u64()).collect();
+        assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+        assert_eq!(updates[2].op, 20);
+        assert_eq!(updates[2].timestamp, 2000);
+    }
+
+    #[tokio::test]
+    async fn should_poll_only_for_operations_after_cursor() {
+        let source = Arc::new(MockSource::with_operations(2));
+        let mut stream = stream(&source);
+
+        assert_eq!(
+            stream.next().await.unwrap().unwrap().id,
+            OperationId::new(0)
+        );
+        assert_eq!(
+            stream.next().await.unwrap().unwrap().id,
+            OperationId::new(1)
+        );
+        assert_eq!(
+            stream.next().await.unwrap().unwrap().id,
+            OperationId::new(2)
+        );
+        assert_eq!(stream.cursor(), Some(OperationId::new(2)));
+
+        let polls = source.polls.lock().unwrap().clone();
+        assert_eq!(polls, vec![None, Some(OperationId::new(2))]);
+    }
+
+    #[tokio::test]
+    async fn should_start_from_given_cursor() {
+        let source = Arc::new(MockSource::with_operations(5));
+        let mut stream = stream(&source).with_cursor(OperationId::new(3));
+
+        assert_eq!(
+            stream.next().await.unwrap().unwrap().id,
+            OperationId::new(4)
+        );
+        assert_eq!(
+            source.polls.lock().unwrap().first().copied(),
+            Some(Some(OperationId::new(4)))
+        );
+    }
+
+    #[tokio::test]
+    async fn should_respect_page_size() {
+        let source = Arc::new(MockSource::with_operations(5));
+        let mut stream = stream(&source).with_page_size(2);
+
+        for id in 0..5 {
+            assert_eq!(
+                stream.next().await.unwrap().unwrap().id,
+                OperationId::new(id)
+            );
+        }
+
+        let polls = source.polls.lock().unwrap().clone();
+        assert_eq!(
+            polls,
+            vec![None, Some(OperationId::new(2)), Some(OperationId::new(4))]
+        );
+    }
+}
@@ -0,0 +1,8 @@
+#[test]
+fn typed_args_checks_argument_types() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/typed_args_pass.rs");
+    cases.compile_fail("tests/ui/typed_args_wrong_type.rs");
+    cases.compile_fail("tests/ui/typed_args_wrong_count.rs");
+    cases.compile_fail("tests/ui/typed_args_unknown_method.rs");
+}
@@ -0,0 +1,12 @@
+use candid::{Nat, Principal};
+
+fn main() {
+    let start = Nat::from(0u64);
+    let length = Nat::from(10u64);
+    bridge_client::typed_args!(get_operation_blocks, start: Nat, length: Nat).unwrap();
+
+    let owner = Principal::anonymous();
+    bridge_client::typed_args!(set_owner, owner: Principal).unwrap();
+
+    bridge_client::typed_args!(get_owner).unwrap();
+}
@@ -0,0 +1,3 @@
+fn main() {
+    bridge_client::typed_args!(get_unknown_value).unwrap();
+}
@@ -0,0 +1,6 @@
+use candid::Nat;
+
+fn main() {
+    let start = Nat::from(0u64);
+    bridge_client::typed_args!(get_operation_blocks, start: Nat).unwrap();
+}
@@ -0,0 +1,7 @@
+use candid::Nat;
+
+fn main() {
+    let start = 0u64;
+    let length = Nat::from(10u64);
+    bridge_client::typed_args!(get_operation_blocks, start: u64, length: Nat).unwrap();
+}
@@ -0,0 +1,260 @@
+//! A module to perform I/O operations on the `registry.toml` file, which tracks the deployed
+//! bridge canisters of all networks.
+
+use std::path::{Path, PathBuf};
+
+use candid::Principal;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::canister_ids::CanisterType;
+use crate::contracts::EvmNetwork;
+
+/// Default path of the registry file.
+pub const DEFAULT_REGISTRY_PATH: &str = "registry.toml";
+
+/// A deployed bridge canister.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    pub canister_name: String,
+    pub canister_id: Principal,
+    pub bridge_type: CanisterType,
+    pub network: EvmNetwork,
+}
+
+/// Content of the `registry.toml` file.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct RegistryFile {
+    #[serde(default)]
+    canisters: Vec<RegistryEntry>,
+}
+
+/// Registry of the deployed bridge canisters, which is serialized into file `registry.toml`.
+#[derive(Debug, Clone)]
+pub struct CanisterRegistry {
+    file: RegistryFile,
+    path: PathBuf,
+}
+
+impl CanisterRegistry {
+    /// Creates a new empty registry, stored at the `path`.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            file: RegistryFile::default(),
+            path,
+        }
+    }
+
+    /// Read the registry file.
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        debug!("Reading canister registry from file: {}", path.display());
+
+        let content = std::fs::read_to_string(path)?;
+        let file = toml::from_str(&content)?;
+
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Read the registry file or return a default new instance.
+    pub fn read_or_default(path: &Path) -> Self {
+        Self::read(path).unwrap_or_else(|_| Self::new(path.to_path_buf()))
+    }
+
+    /// Write the registry file.
+    pub fn write(&self) -> anyhow::Result<()> {
+        debug!("Writing canister registry to file: {}", self.path.display());
+
+        // create parent directories if not exists
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+
+        let content = toml::to_string_pretty(&self.file)?;
+        std::fs::write(&self.path, content)?;
+
+        Ok(())
+    }
+
+    /// Adds the canister to the registry.
+    ///
+    /// If the canister with the same id and network is already registered, it will be updated.
+    pub fn add(&mut self, entry: RegistryEntry) {
+        let existing = self.file.canisters.iter_mut().find(|registered| {
+            registered.canister_id == entry.canister_id && registered.network == entry.network
+        });
+
+        match existing {
+            Some(registered) => *registered = entry,
+            None => self.file.canisters.push(entry),
+        }
+    }
+
+    /// Returns the registered canisters of the given bridge type and network.
+    /// Filters, which are `None`, match any canister.
+    pub fn filtered<'a>(
+        &'a self,
+        bridge_type: Option<&'a CanisterType>,
+        network: Option<EvmNetwork>,
+    ) -> impl Iterator<Item = &'a RegistryEntry> {
+        self.file.canisters.iter().filter(move |entry| {
+            bridge_type.map_or(true, |bridge_type| entry.bridge_type == *bridge_type)
+                && network.map_or(true, |network| entry.network == network)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(
+        name: &str,
+        id: &str,
+        bridge_type: CanisterType,
+        network: EvmNetwork,
+    ) -> RegistryEntry {
+        RegistryEntry {
+            canister_name: name.to_string(),
+            canister_id: Principal::from_text(id).unwrap(),
+            bridge_type,
+            network,
+        }
+    }
+
+    #[test]
+    fn test_should_read_registry() {
+        let content = r#"
+[[canisters]]
+canister_name = "icrc2-bridge"
+canister_id = "v5vof-zqaaa-aaaal-ai5cq-cai"
+bridge_type = "icrc2-bridge"
+network = "mainnet"
+
+[[canisters]]
+canister_name = "btc-bridge-local"
+canister_id = "v2uir-uiaaa-aaaal-ai5ca-cai"
+bridge_type = "btc-bridge"
+network = "localhost"
+"#;
+
+        let tempfile = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(tempfile.path(), content).unwrap();
+
+        let registry = CanisterRegistry::read(tempfile.path()).unwrap();
+        let entries: Vec<_> = registry.filtered(None, None).cloned().collect();
+        assert_eq!(
+            entries,
+            vec![
+                entry(
+                    "icrc2-bridge",
+                    "v5vof-zqaaa-aaaal-ai5cq-cai",
+                    CanisterType::Icrc2,
+                    EvmNetwork::Mainnet
+                ),
+                entry(
+                    "btc-bridge-local",
+                    "v2uir-uiaaa-aaaal-ai5ca-cai",
+                    CanisterType::Btc,
+                    EvmNetwork::Localhost
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_write_and_read_registry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deploy").join(DEFAULT_REGISTRY_PATH);
+
+        let mut registry = CanisterRegistry::read_or_default(&path);
+        assert_eq!(registry.filtered(None, None).count(), 0);
+
+        registry.add(entry(
+            "icrc2-bridge",
+            "v5vof-zqaaa-aaaal-ai5cq-cai",
+            CanisterType::Icrc2,
+            EvmNetwork::Testnet,
+        ));
+        registry.add(entry(
+            "custom",
+            "vtxdn-caaaa-aaaal-ai5dq-cai",
+            CanisterType::Other("custom-bridge".to_string()),
+            EvmNetwork::Localhost,
+        ));
+        registry.write().unwrap();
+
+        let read = CanisterRegistry::read(&path).unwrap();
+        assert_eq!(read.file, registry.file);
+    }
+
+    #[test]
+    fn test_should_update_existing_entry() {
+        let mut registry = CanisterRegistry::new(PathBuf::from(DEFAULT_REGISTRY_PATH));
+        let id = "v5vof-zqaaa-aaaal-ai5cq-cai";
+
+        registry.add(entry("old", id, CanisterType::Icrc2, EvmNetwork::Localhost));
+        registry.add(entry("new", id, CanisterType::Icrc2, EvmNetwork::Localhost));
+        // Same id on another network is another canister.
+        registry.add(entry(
+            "mainnet",
+            id,
+            CanisterType::Icrc2,
+            EvmNetwork::Mainnet,
+        ));
+
+        let names: Vec<_> = registry
+            .filtered(None, None)
+            .map(|entry| entry.canister_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["new", "mainnet"]);
+    }
+
+    #[test]
+    fn test_should_filter_entries() {
+        let mut registry = CanisterRegistry::new(PathBuf::from(DEFAULT_REGISTRY_PATH));
+        registry.add(entry(
+            "icrc-local",
+            "v5vof-zqaaa-aaaal-ai5cq-cai",
+            CanisterType::Icrc2,
+            EvmNetwork::Localhost,
+        ));
+        registry.add(entry(
+            "icrc-mainnet",
+            "v2uir-uiaaa-aaaal-ai5ca-cai",
+            CanisterType::Icrc2,
+            EvmNetwork::Mainnet,
+        ));
+        registry.add(entry(
+            "btc-mainnet",
+            "vtxdn-caaaa-aaaal-ai5dq-cai",
+            CanisterType::Btc,
+            EvmNetwork::Mainnet,
+        ));
+
+        let names = |bridge_type: Option<&CanisterType>, network: Option<EvmNetwork>| {
+            registry
+                .filtered(bridge_type, network)
+                .map(|entry| entry.canister_name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(None, None).len(), 3);
+        assert_eq!(
+            names(Some(&CanisterType::Icrc2), None),
+            vec!["icrc-local", "icrc-mainnet"]
+        );
+        assert_eq!(
+            names(None, Some(EvmNetwork::Mainnet)),
+            vec!["icrc-mainnet", "btc-mainnet"]
+        );
+        assert_eq!(
+            names(Some(&CanisterType::Btc), Some(EvmNetwork::Localhost)),
+            Vec::<String>::new()
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::bail;
+use candid::Principal;
+use clap::Parser;
+use did::build::BuildData;
+use ic_canister_client::agent::identity::GenericIdentity;
+use ic_canister_client::{CanisterClient, IcAgentClient};
+use tracing::{info, warn};
+
+/// The build data command.
+///
+/// This command fetches the build data of the deployed canisters and checks that all of them
+/// run the expected version.
+#[derive(Debug, Parser)]
+pub struct BuildDataCommands {
+    /// Ids of the canisters to check.
+    #[arg(long = "canister-id", value_name = "CANISTER_ID", required = true, num_args = 1..)]
+    canister_ids: Vec<Principal>,
+
+    /// Expected package version of the canisters.
+    ///
+    /// If neither expected version nor expected commit is set, the build data
+    /// of the first canister is expected.
+    #[arg(long, value_name = "VERSION")]
+    expected_version: Option<String>,
+
+    /// Expected git commit of the canisters. A commit hash prefix is accepted.
+    #[arg(long, value_name = "COMMIT")]
+    expected_commit: Option<String>,
+}
+
+/// Build data the canisters are expected to have.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ExpectedBuild {
+    version: Option<String>,
+    commit: Option<String>,
+}
+
+impl ExpectedBuild {
+    fn from_build_data(data: &BuildData) -> Self {
+        Self {
+            version: Some(data.pkg_version.clone()),
+            commit: Some(data.git_sha.clone()),
+        }
+    }
+
+    fn matches(&self, data: &BuildData) -> bool {
+        let version_matches = self
+            .version
+            .as_ref()
+            .map_or(true, |version| *version == data.pkg_version);
+        let commit_matches = self.commit.as_ref().map_or(true, |commit| {
+            !commit.is_empty() && data.git_sha.starts_with(commit.as_str())
+        });
+
+        version_matches && commit_matches
+    }
+}
+
+impl BuildDataCommands {
+    pub async fn check_build_data(
+        &self,
+        identity: GenericIdentity,
+        ic_host: &str,
+    ) -> anyhow::Result<()> {
+        let agent = ic_agent::Agent::builder()
+            .with_url(ic_host)
+            .with_identity(identity)
+            .build()?;
+
+        super::fetch_root_key(ic_host, &agent).await?;
+
+        let mut results = Vec::with_capacity(self.canister_ids.len());
+        for canister_id in &self.canister_ids {
+            info!("Fetching build data of canister {canister_id}");
+            let client = IcAgentClient::with_agent(*canister_id, agent.clone());
+            let result = client
+                .query::<_, BuildData>("get_canister_build_data", ())
+                .await
+                .map_err(|e| e.to_string());
+            if let Err(e) = &result {
+                warn!("Failed to fetch build data of canister {canister_id}: {e}");
+            }
+
+            results.push((*canister_id, result));
+        }
+
+        let expected = self.expected_build(&results);
+        println!("{}", build_data_table(&results, &expected));
+
+        let mismatched = results
+            .iter()
+            .filter(|(_, result)| !result.as_ref().is_ok_and(|data| expected.matches(data)))
+            .count();
+        if mismatched > 0 {
+            bail!("{mismatched} canisters don't run the expected build");
+        }
+
+        println!("All {} canisters run the expected build", results.len());
+
+        Ok(())
+    }
+
+    fn expected_build(&self, results: &[(Principal, Result<BuildData, String>)]) -> ExpectedBuild {
+        if self.expected_version.is_some() || self.expected_commit.is_some() {
+            return ExpectedBuild {
+                version: self.expected_version.clone(),
+                commit: self.expected_commit.clone(),
+            };
+        }
+
+        results
+            .iter()
+            .find_map(|(_, result)| result.as_ref().ok())
+            .map(ExpectedBuild::from_build_data)
+            .unwrap_or_default()
+    }
+}
+
+/// Formats the build data of the canisters as a table, flagging the unexpected builds.
+fn build_data_table(
+    results: &[(Principal, Result<BuildData, String>)],
+    expected: &ExpectedBuild,
+) -> String {
+    let header = [
+        "CANISTER",
+        "PACKAGE",
+        "VERSION",
+        "COMMIT",
+        "BUILD TIME",
+        "STATUS",
+    ];
+
+    let rows: Vec<[String; 6]> = results
+        .iter()
+        .map(|(canister_id, result)| match result {
+            Ok(data) => [
+                canister_id.to_text(),
+                data.pkg_name.clone(),
+                data.pkg_version.clone(),
+                data.git_sha.clone(),
+                data.build_timestamp.clone(),
+                if expected.matches(data) {
+                    "OK".to_string()
+                } else {
+                    "MISMATCH".to_string()
+                },
+            ],
+            Err(e) => [
+                canister_id.to_text(),
+                "-".to_string(),
+                "-".to_string(),
+                "-".to_string(),
+                "-".to_string(),
+                format!("ERROR: {e}"),
+            ],
+        })
+        .collect();
+
+    super::format_table(header, &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_data(version: &str, sha: &str) -> BuildData {
+        BuildData {
+            cargo_target_triple: "wasm32-unknown-unknown".to_string(),
+            cargo_features: "export-api".to_string(),
+            pkg_name: "icrc2-bridge".to_string(),
+            pkg_version: version.to_string(),
+            rustc_semver: "1.81.0".to_string(),
+            build_timestamp: "2024-10-01T12:00:00.000000000Z".to_string(),
+            cargo_debug: "false".to_string(),
+            git_branch: "main".to_string(),
+            git_sha: sha.to_string(),
+            git_commit_timestamp: "2024-10-01T11:00:00.000000000Z".to_string(),
+        }
+    }
+
+    fn command(expected_version: Option<&str>, expected_commit: Option<&str>) -> BuildDataCommands {
+        BuildDataCommands {
+            canister_ids: vec![],
+            expected_version: expected_version.map(String::from),
+            expected_commit: expected_commit.map(String::from),
+        }
+    }
+
+    #[test]
+    fn expected_build_matches_version_and_commit_prefix() {
+        let data = build_data("0.2.0", "acf6c5744b1f4f29c5960a25f4fb4056e2ceedc3");
+
+        let expected = command(Some("0.2.0"), Some("acf6c57")).expected_build(&[]);
+        assert!(expected.matches(&data));
+
+        let expected = command(Some("0.1.0"), None).expected_build(&[]);
+        assert!(!expected.matches(&data));
+
+        let expected = command(None, Some("0123456")).expected_build(&[]);
+        assert!(!expected.matches(&data));
+
+        let expected = command(None, Some("")).expected_build(&[]);
+        assert!(!expected.matches(&data));
+    }
+
+    #[test]
+    fn first_fetched_build_is_expected_by_default() {
+        let results = vec![
+            (
+                Principal::anonymous(),
+                Err("canister not found".to_string()),
+            ),
+            (
+                Principal::management_canister(),
+                Ok(build_data("0.2.0", "acf6c57")),
+            ),
+        ];
+
+        let expected = command(None, None).expected_build(&results);
+        assert_eq!(
+            expected,
+            ExpectedBuild {
+                version: Some("0.2.0".to_string()),
+                commit: Some("acf6c57".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn table_flags_unexpected_builds() {
+        let results = vec![
+            (
+                Principal::from_slice(&[1; 29]),
+                Ok(build_data("0.2.0", "acf6c57")),
+            ),
+            (
+                Principal::from_slice(&[2; 29]),
+                Ok(build_data("0.1.0", "0123456")),
+            ),
+            (Principal::from_slice(&[3; 29]), Err("timeout".to_string())),
+        ];
+        let expected = command(Some("0.2.0"), None).expected_build(&results);
+
+        let table = build_data_table(&results, &expected);
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("CANISTER"));
+        assert!(lines[1].ends_with("OK"));
+        assert!(lines[2].ends_with("MISMATCH"));
+        assert!(lines[3].ends_with("ERROR: timeout"));
+
+        // Columns are aligned.
+        let status_column = lines[0].find("STATUS").unwrap();
+        assert!(lines[1..].iter().all(|line| line.len() > status_column));
+        assert_eq!(&lines[2][status_column..], "MISMATCH");
+    }
+}
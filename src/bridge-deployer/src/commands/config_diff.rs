@@ -0,0 +1,543 @@
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display, Formatter};
+
+use bridge_client::{
+    BridgeCanisterClient, Erc20BridgeClient, GenericBridgeClient, Icrc2BridgeClient,
+};
+use bridge_did::concurrency::OperationConcurrencyLimits;
+use bridge_did::evm_finality::EvmFinality;
+use bridge_did::evm_link::PrivateTxRelay;
+use bridge_did::gas_balance::GasBalanceThresholds;
+use bridge_did::outbox::OutboxDestination;
+use bridge_did::preferences::BridgePreferences;
+use bridge_did::supply_guard::SupplyGuardConfig;
+use candid::Principal;
+use did::H160;
+use ic_agent::Agent;
+use ic_canister_client::IcAgentClient;
+
+use crate::canister_ids::CanisterType;
+
+/// Configuration of the bridge canister, read with the config queries of the canister.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BridgeConfig {
+    pub common: CommonBridgeConfig,
+    /// ICRC-2 bridge only.
+    pub icrc2: Option<Icrc2BridgeConfig>,
+    /// ERC-20 bridge only.
+    pub erc20: Option<Erc20BaseConfig>,
+}
+
+/// Configuration of every bridge canister.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommonBridgeConfig {
+    pub owner: Principal,
+    pub btf_bridge_contract: Option<H160>,
+    pub strict_event_validation: bool,
+    pub evm_finality: EvmFinality,
+    pub gas_balance_thresholds: Option<GasBalanceThresholds>,
+    pub max_acceptable_evm_latency_ms: Option<u64>,
+    pub notification_destinations: Vec<OutboxDestination>,
+    pub nonce_offset: u64,
+    pub whitelist: Vec<Principal>,
+    pub concurrency_limits: OperationConcurrencyLimits,
+    pub private_tx_relay: Option<PrivateTxRelay>,
+}
+
+/// Configuration of the ICRC-2 bridge canister.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Icrc2BridgeConfig {
+    /// `None` if all the tokens are allowed.
+    pub allowed_tokens: Option<Vec<Principal>>,
+    pub max_deposit_confirmation_delay_secs: u64,
+    pub deposit_fee: u64,
+    pub mint_order_signing_delay_secs: u64,
+    pub supply_guard: Option<SupplyGuardConfig>,
+    pub preferences: Option<BridgePreferences>,
+}
+
+/// Configuration of the base EVM side of the ERC-20 bridge canister.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Erc20BaseConfig {
+    pub gas_balance_thresholds: Option<GasBalanceThresholds>,
+    pub max_acceptable_evm_latency_ms: Option<u64>,
+}
+
+impl BridgeConfig {
+    /// Reads the configuration of the canister. The bridge specific configuration is read
+    /// only if the `bridge_type` is known.
+    pub async fn fetch(
+        agent: &Agent,
+        canister_id: Principal,
+        bridge_type: Option<&CanisterType>,
+    ) -> anyhow::Result<Self> {
+        let client = || IcAgentClient::with_agent(canister_id, agent.clone());
+        let bridge = GenericBridgeClient::new(client());
+
+        let manifest = bridge.get_bridge_manifest().await?;
+        let common = CommonBridgeConfig {
+            owner: bridge.get_owner().await?,
+            btf_bridge_contract: bridge.get_btf_bridge_contract().await?,
+            strict_event_validation: manifest.strict_event_validation,
+            evm_finality: manifest.evm_finality,
+            gas_balance_thresholds: bridge.get_gas_balance_status().await?.thresholds,
+            max_acceptable_evm_latency_ms: bridge
+                .get_evm_latency_stats()
+                .await?
+                .max_acceptable_latency_ms,
+            notification_destinations: bridge.get_notification_destinations().await?,
+            nonce_offset: bridge.get_nonce_state().await?.nonce_offset,
+            whitelist: bridge.get_whitelist().await?,
+            concurrency_limits: bridge.get_operation_concurrency_limits().await?,
+            private_tx_relay: bridge.get_private_tx_relay().await?,
+        };
+
+        let icrc2 = match bridge_type {
+            Some(CanisterType::Icrc2) => {
+                let icrc2 = Icrc2BridgeClient::new(client());
+                Some(Icrc2BridgeConfig {
+                    allowed_tokens: icrc2.get_allowed_tokens().await?,
+                    max_deposit_confirmation_delay_secs: icrc2
+                        .get_max_deposit_confirmation_delay_secs()
+                        .await?,
+                    deposit_fee: icrc2.get_deposit_fee().await?,
+                    mint_order_signing_delay_secs: icrc2
+                        .get_mint_order_signing_delay_secs()
+                        .await?,
+                    supply_guard: icrc2.get_supply_guard_config().await?,
+                    preferences: icrc2.get_bridge_preferences().await?,
+                })
+            }
+            _ => None,
+        };
+
+        let erc20 = match bridge_type {
+            Some(CanisterType::Erc20) => {
+                let erc20 = Erc20BridgeClient::new(client());
+                Some(Erc20BaseConfig {
+                    gas_balance_thresholds: erc20.get_base_gas_balance_status().await?.thresholds,
+                    max_acceptable_evm_latency_ms: erc20
+                        .get_base_evm_latency_stats()
+                        .await?
+                        .max_acceptable_latency_ms,
+                })
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            common,
+            icrc2,
+            erc20,
+        })
+    }
+
+    /// Flattens the configuration into the named fields.
+    pub fn snapshot(&self) -> ConfigSnapshot {
+        let mut snapshot = ConfigSnapshot::default();
+
+        let common = &self.common;
+        snapshot.value("owner", &common.owner);
+        snapshot.field("btf_bridge_contract", &common.btf_bridge_contract);
+        snapshot.field("strict_event_validation", &common.strict_event_validation);
+        snapshot.field("evm_finality", &common.evm_finality);
+        snapshot.field("gas_balance_thresholds", &common.gas_balance_thresholds);
+        snapshot.field(
+            "max_acceptable_evm_latency_ms",
+            &common.max_acceptable_evm_latency_ms,
+        );
+        snapshot.list(
+            "notification_destinations",
+            &common.notification_destinations,
+        );
+        snapshot.field("nonce_offset", &common.nonce_offset);
+        snapshot.list("whitelist", &common.whitelist);
+        snapshot.field("concurrency_limits", &common.concurrency_limits);
+        snapshot.field("private_tx_relay", &common.private_tx_relay);
+
+        if let Some(icrc2) = &self.icrc2 {
+            snapshot.field(
+                "icrc2.token_allow_list_enabled",
+                &icrc2.allowed_tokens.is_some(),
+            );
+            snapshot.list(
+                "icrc2.allowed_tokens",
+                icrc2.allowed_tokens.as_deref().unwrap_or_default(),
+            );
+            snapshot.field(
+                "icrc2.max_deposit_confirmation_delay_secs",
+                &icrc2.max_deposit_confirmation_delay_secs,
+            );
+            snapshot.field("icrc2.deposit_fee", &icrc2.deposit_fee);
+            snapshot.field(
+                "icrc2.mint_order_signing_delay_secs",
+                &icrc2.mint_order_signing_delay_secs,
+            );
+            snapshot.field("icrc2.supply_guard", &icrc2.supply_guard);
+            snapshot.field("icrc2.preferences", &icrc2.preferences);
+        }
+
+        if let Some(erc20) = &self.erc20 {
+            snapshot.field(
+                "erc20.base_gas_balance_thresholds",
+                &erc20.gas_balance_thresholds,
+            );
+            snapshot.field(
+                "erc20.base_max_acceptable_evm_latency_ms",
+                &erc20.max_acceptable_evm_latency_ms,
+            );
+        }
+
+        snapshot
+    }
+}
+
+/// Value of the configuration field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldValue {
+    pub value: String,
+    /// The value is the default value of the field type, e.g. `None`, `0` or `false`.
+    pub is_default: bool,
+}
+
+/// Configuration of the bridge canister as the named fields. Every item of the list
+/// settings is a separate field, so the removed items are reported one by one.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConfigSnapshot {
+    fields: BTreeMap<String, FieldValue>,
+}
+
+impl ConfigSnapshot {
+    /// Adds the field, which can be reset to the default value.
+    fn field<T: Debug + Default + PartialEq>(&mut self, name: &str, value: &T) {
+        self.insert(name.to_string(), value, *value == T::default());
+    }
+
+    /// Adds the field without a default value.
+    fn value(&mut self, name: &str, value: &impl Debug) {
+        self.insert(name.to_string(), value, false);
+    }
+
+    /// Adds a field per item of the list.
+    fn list<T: Debug>(&mut self, name: &str, items: &[T]) {
+        for item in items {
+            self.insert(format!("{name}[{item:?}]"), item, false);
+        }
+    }
+
+    fn insert(&mut self, name: String, value: &impl Debug, is_default: bool) {
+        self.fields.insert(
+            name,
+            FieldValue {
+                value: format!("{value:?}"),
+                is_default,
+            },
+        );
+    }
+
+    /// Returns the changes of the fields from `self` to `new`, ordered by the field name.
+    pub fn diff(&self, new: &Self) -> Vec<FieldDiff> {
+        let names = self
+            .fields
+            .keys()
+            .chain(new.fields.keys())
+            .collect::<std::collections::BTreeSet<_>>();
+
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let change = match (self.fields.get(name), new.fields.get(name)) {
+                    (Some(old), Some(new)) if old == new => return None,
+                    (Some(old), Some(new)) if new.is_default && !old.is_default => {
+                        FieldChange::ResetToDefault {
+                            old: old.value.clone(),
+                            new: new.value.clone(),
+                        }
+                    }
+                    (Some(old), Some(new)) => FieldChange::Changed {
+                        old: old.value.clone(),
+                        new: new.value.clone(),
+                    },
+                    (Some(old), None) => FieldChange::Removed {
+                        old: old.value.clone(),
+                    },
+                    (None, Some(new)) => FieldChange::Added {
+                        new: new.value.clone(),
+                    },
+                    (None, None) => return None,
+                };
+
+                Some(FieldDiff {
+                    field: name.clone(),
+                    change,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Change of the configuration field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldChange {
+    Added {
+        new: String,
+    },
+    Changed {
+        old: String,
+        new: String,
+    },
+    /// The field is changed to the default value of its type.
+    ResetToDefault {
+        old: String,
+        new: String,
+    },
+    Removed {
+        old: String,
+    },
+}
+
+/// Change of the named configuration field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    pub field: String,
+    pub change: FieldChange,
+}
+
+impl FieldDiff {
+    /// Checks if the change may be an unintended loss of the configuration.
+    pub fn is_loss(&self) -> bool {
+        matches!(
+            self.change,
+            FieldChange::ResetToDefault { .. } | FieldChange::Removed { .. }
+        )
+    }
+}
+
+impl Display for FieldDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let field = &self.field;
+        match &self.change {
+            FieldChange::Added { new } => write!(f, "+ {field}: {new}"),
+            FieldChange::Changed { old, new } => write!(f, "~ {field}: {old} -> {new}"),
+            FieldChange::ResetToDefault { old, new } => {
+                write!(f, "! {field}: {old} -> {new} (RESET TO DEFAULT)")
+            }
+            FieldChange::Removed { old } => write!(f, "- {field}: {old} (REMOVED)"),
+        }
+    }
+}
+
+/// Formats the diff, one field per line, with the number of the changes which may be
+/// an unintended loss of the configuration.
+pub fn diff_report(diff: &[FieldDiff]) -> String {
+    if diff.is_empty() {
+        return "No configuration changes".to_string();
+    }
+
+    let losses = diff.iter().filter(|change| change.is_loss()).count();
+    let mut report = format!(
+        "{} configuration changes, {losses} resets to default or removals:",
+        diff.len()
+    );
+    for change in diff {
+        report.push_str(&format!("\n  {change}"));
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::evm_link::EvmLink;
+
+    use super::*;
+
+    fn principal(byte: u8) -> Principal {
+        Principal::from_slice(&[byte; 29])
+    }
+
+    fn common_config() -> CommonBridgeConfig {
+        CommonBridgeConfig {
+            owner: principal(1),
+            btf_bridge_contract: Some(H160::from_slice(&[2; 20])),
+            strict_event_validation: true,
+            evm_finality: EvmFinality::default(),
+            gas_balance_thresholds: None,
+            max_acceptable_evm_latency_ms: Some(5_000),
+            notification_destinations: vec![OutboxDestination::Webhook {
+                url: "https://partner.example/hook".into(),
+            }],
+            nonce_offset: 100,
+            whitelist: vec![principal(3), principal(4)],
+            concurrency_limits: OperationConcurrencyLimits::default(),
+            private_tx_relay: None,
+        }
+    }
+
+    fn config() -> BridgeConfig {
+        BridgeConfig {
+            common: common_config(),
+            icrc2: None,
+            erc20: None,
+        }
+    }
+
+    fn fields(diff: &[FieldDiff]) -> Vec<&str> {
+        diff.iter().map(|change| change.field.as_str()).collect()
+    }
+
+    #[test]
+    fn same_config_has_no_diff() {
+        let config = config();
+        assert!(config.snapshot().diff(&config.snapshot()).is_empty());
+        assert_eq!(diff_report(&[]), "No configuration changes");
+    }
+
+    #[test]
+    fn should_diff_common_config() {
+        let old = config();
+        let mut new = old.clone();
+        new.common.owner = principal(9);
+        new.common.nonce_offset = 200;
+        new.common.private_tx_relay = Some(PrivateTxRelay::new(EvmLink::Http(
+            "https://relay.example".into(),
+        )));
+        new.common.whitelist = vec![principal(4)];
+
+        let diff = old.snapshot().diff(&new.snapshot());
+        assert_eq!(
+            diff,
+            vec![
+                FieldDiff {
+                    field: "nonce_offset".into(),
+                    change: FieldChange::Changed {
+                        old: "100".into(),
+                        new: "200".into()
+                    },
+                },
+                FieldDiff {
+                    field: "owner".into(),
+                    change: FieldChange::Changed {
+                        old: format!("{:?}", principal(1)),
+                        new: format!("{:?}", principal(9)),
+                    },
+                },
+                FieldDiff {
+                    field: "private_tx_relay".into(),
+                    change: FieldChange::Changed {
+                        old: "None".into(),
+                        new: format!("{:?}", new.common.private_tx_relay),
+                    },
+                },
+                FieldDiff {
+                    field: format!("whitelist[{:?}]", principal(3)),
+                    change: FieldChange::Removed {
+                        old: format!("{:?}", principal(3)),
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_diff_icrc2_config() {
+        let mut old = config();
+        old.icrc2 = Some(Icrc2BridgeConfig {
+            allowed_tokens: Some(vec![principal(7)]),
+            max_deposit_confirmation_delay_secs: 600,
+            deposit_fee: 10,
+            mint_order_signing_delay_secs: 0,
+            supply_guard: None,
+            preferences: None,
+        });
+        let mut new = old.clone();
+        let icrc2 = new.icrc2.as_mut().unwrap();
+        icrc2.allowed_tokens = None;
+        icrc2.mint_order_signing_delay_secs = 30;
+
+        let diff = old.snapshot().diff(&new.snapshot());
+        assert_eq!(
+            fields(&diff),
+            vec![
+                format!("icrc2.allowed_tokens[{:?}]", principal(7)).as_str(),
+                "icrc2.mint_order_signing_delay_secs",
+                "icrc2.token_allow_list_enabled",
+            ]
+        );
+        assert!(matches!(diff[0].change, FieldChange::Removed { .. }));
+        assert!(matches!(diff[1].change, FieldChange::Changed { .. }));
+        assert!(matches!(diff[2].change, FieldChange::ResetToDefault { .. }));
+    }
+
+    #[test]
+    fn should_diff_erc20_config() {
+        let mut old = config();
+        old.erc20 = Some(Erc20BaseConfig {
+            gas_balance_thresholds: None,
+            max_acceptable_evm_latency_ms: None,
+        });
+        let mut new = old.clone();
+        new.erc20.as_mut().unwrap().max_acceptable_evm_latency_ms = Some(1_000);
+
+        let diff = old.snapshot().diff(&new.snapshot());
+        assert_eq!(
+            diff,
+            vec![FieldDiff {
+                field: "erc20.base_max_acceptable_evm_latency_ms".into(),
+                change: FieldChange::Changed {
+                    old: "None".into(),
+                    new: "Some(1000)".into()
+                },
+            }]
+        );
+
+        // Config of the other bridge type is reported as added or removed.
+        let diff = config().snapshot().diff(&old.snapshot());
+        assert_eq!(diff.len(), 2);
+        assert!(diff
+            .iter()
+            .all(|change| matches!(change.change, FieldChange::Added { .. })));
+
+        let diff = old.snapshot().diff(&config().snapshot());
+        assert_eq!(diff.len(), 2);
+        assert!(diff.iter().all(FieldDiff::is_loss));
+    }
+
+    #[test]
+    fn should_highlight_resets_to_default() {
+        let old = config();
+        let mut new = old.clone();
+        new.common.btf_bridge_contract = None;
+        new.common.strict_event_validation = false;
+        new.common.max_acceptable_evm_latency_ms = Some(1_000);
+        new.common.notification_destinations = vec![];
+
+        let diff = old.snapshot().diff(&new.snapshot());
+        assert_eq!(
+            diff.iter().filter(|change| change.is_loss()).count(),
+            3,
+            "unexpected diff: {diff:?}"
+        );
+
+        let report = diff_report(&diff);
+        assert!(report.starts_with("4 configuration changes, 3 resets to default or removals:"));
+        assert!(report.contains(&format!(
+            "! btf_bridge_contract: {:?} -> None (RESET TO DEFAULT)",
+            old.common.btf_bridge_contract
+        )));
+        assert!(report.contains("! strict_event_validation: true -> false (RESET TO DEFAULT)"));
+        assert!(report.contains("~ max_acceptable_evm_latency_ms: Some(5000) -> Some(1000)"));
+        assert!(report.contains("(REMOVED)"));
+    }
+
+    #[test]
+    fn change_from_default_is_not_a_reset() {
+        let mut old = config();
+        old.common.btf_bridge_contract = None;
+        let new = config();
+
+        let diff = old.snapshot().diff(&new.snapshot());
+        assert_eq!(diff.len(), 1);
+        assert!(!diff[0].is_loss());
+    }
+}
@@ -0,0 +1,589 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context};
+use bridge_client::{BridgeCanisterClient, GenericBridgeClient, Icrc2BridgeClient};
+use bridge_did::gas_balance::GasBalanceThresholds;
+use bridge_did::outbox::OutboxDestination;
+use candid::Principal;
+use clap::Parser;
+use did::H160;
+use ic_agent::Agent;
+use ic_canister_client::agent::identity::GenericIdentity;
+use ic_canister_client::IcAgentClient;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use super::config_diff::BridgeConfig;
+use crate::encrypted_config::read_encrypted_config;
+
+/// The configure command.
+///
+/// This command applies the post-deploy configuration from a JSON file to a deployed bridge.
+/// Settings which already have the expected value are skipped, so the command can be re-run
+/// to retry the failed calls.
+#[derive(Debug, Parser)]
+pub struct ConfigureCommands {
+    #[arg(long, value_name = "CANISTER_ID")]
+    canister_id: Principal,
+
+    /// Path to the JSON file with the bridge settings.
+    #[arg(
+        long,
+        value_name = "CONFIG_PATH",
+        required_unless_present = "encrypted_config"
+    )]
+    config: Option<PathBuf>,
+
+    /// Path to the bridge settings file, encrypted with the `encrypt-config` command.
+    /// The file is decrypted in memory.
+    #[arg(
+        long,
+        value_name = "CONFIG_PATH",
+        conflicts_with = "config",
+        requires = "key_file"
+    )]
+    encrypted_config: Option<PathBuf>,
+
+    /// Path to the key file the encrypted settings are decrypted with.
+    #[arg(long, value_name = "KEY_FILE", requires = "encrypted_config")]
+    key_file: Option<PathBuf>,
+
+    /// List the calls to be made without calling the canister.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// Post-deploy settings of the bridge canister. Settings which are not set are not changed.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct BridgeSettings {
+    btf_bridge_contract: Option<H160>,
+    gas_balance_thresholds: Option<GasBalanceThresholds>,
+    operation_log_compression: Option<bool>,
+    strict_event_validation: Option<bool>,
+    evm_params_history_size: Option<u32>,
+    max_acceptable_evm_latency_ms: Option<u64>,
+    notification_destinations: Option<Vec<OutboxDestination>>,
+    nonce_offset: Option<u64>,
+    logger_filter: Option<String>,
+    /// ICRC-2 bridge only.
+    #[serde(default)]
+    whitelist: Vec<Principal>,
+    /// ICRC-2 bridge only.
+    #[serde(default)]
+    allowed_tokens: Vec<Principal>,
+    /// ICRC-2 bridge only.
+    max_deposit_confirmation_delay_secs: Option<u64>,
+    /// The owner is changed last, as the other setters are only for the owner.
+    owner: Option<Principal>,
+}
+
+impl BridgeSettings {
+    /// Returns the setter calls in the order they should be made.
+    pub(super) fn calls(&self) -> Vec<SettingCall> {
+        let mut calls = vec![];
+        calls.extend(
+            self.btf_bridge_contract
+                .clone()
+                .map(SettingCall::BtfBridgeContract),
+        );
+        calls.extend(
+            self.gas_balance_thresholds
+                .clone()
+                .map(SettingCall::GasBalanceThresholds),
+        );
+        calls.extend(
+            self.operation_log_compression
+                .map(SettingCall::OperationLogCompression),
+        );
+        calls.extend(
+            self.strict_event_validation
+                .map(SettingCall::StrictEventValidation),
+        );
+        calls.extend(
+            self.evm_params_history_size
+                .map(SettingCall::EvmParamsHistorySize),
+        );
+        calls.extend(
+            self.max_acceptable_evm_latency_ms
+                .map(SettingCall::MaxAcceptableEvmLatencyMs),
+        );
+        calls.extend(
+            self.notification_destinations
+                .clone()
+                .map(SettingCall::NotificationDestinations),
+        );
+        calls.extend(self.nonce_offset.map(SettingCall::NonceOffset));
+        calls.extend(self.logger_filter.clone().map(SettingCall::LoggerFilter));
+        calls.extend(
+            self.whitelist
+                .iter()
+                .copied()
+                .map(SettingCall::AddToWhitelist),
+        );
+        calls.extend(
+            self.allowed_tokens
+                .iter()
+                .copied()
+                .map(SettingCall::AddAllowedToken),
+        );
+        calls.extend(
+            self.max_deposit_confirmation_delay_secs
+                .map(SettingCall::MaxDepositConfirmationDelaySecs),
+        );
+        calls.extend(self.owner.map(SettingCall::Owner));
+
+        calls
+    }
+
+    /// Returns the configuration the `current` one is expected to become after the settings
+    /// are applied. Settings without a config query are not reflected.
+    pub(super) fn expected_config(&self, current: &BridgeConfig) -> BridgeConfig {
+        let mut config = current.clone();
+        let common = &mut config.common;
+        if let Some(address) = &self.btf_bridge_contract {
+            common.btf_bridge_contract = Some(address.clone());
+        }
+        if let Some(thresholds) = &self.gas_balance_thresholds {
+            common.gas_balance_thresholds = Some(thresholds.clone());
+        }
+        if let Some(enabled) = self.strict_event_validation {
+            common.strict_event_validation = enabled;
+        }
+        if let Some(latency) = self.max_acceptable_evm_latency_ms {
+            common.max_acceptable_evm_latency_ms = Some(latency);
+        }
+        if let Some(destinations) = &self.notification_destinations {
+            common.notification_destinations = destinations.clone();
+        }
+        if let Some(offset) = self.nonce_offset {
+            common.nonce_offset = offset;
+        }
+        for principal in &self.whitelist {
+            if !common.whitelist.contains(principal) {
+                common.whitelist.push(*principal);
+            }
+        }
+        if let Some(owner) = self.owner {
+            common.owner = owner;
+        }
+
+        if let Some(icrc2) = &mut config.icrc2 {
+            for token in &self.allowed_tokens {
+                let tokens = icrc2.allowed_tokens.get_or_insert_with(Vec::new);
+                if !tokens.contains(token) {
+                    tokens.push(*token);
+                }
+            }
+            if let Some(delay) = self.max_deposit_confirmation_delay_secs {
+                icrc2.max_deposit_confirmation_delay_secs = delay;
+            }
+        }
+
+        config
+    }
+}
+
+/// Reads the bridge settings from the JSON file.
+pub(super) fn read_settings_file(path: &Path) -> anyhow::Result<BridgeSettings> {
+    let settings =
+        std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    parse_settings(path, &settings)
+}
+
+fn parse_settings(path: &Path, settings: &[u8]) -> anyhow::Result<BridgeSettings> {
+    serde_json::from_slice(settings).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// A setter call of the bridge canister.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum SettingCall {
+    BtfBridgeContract(H160),
+    GasBalanceThresholds(GasBalanceThresholds),
+    OperationLogCompression(bool),
+    StrictEventValidation(bool),
+    EvmParamsHistorySize(u32),
+    MaxAcceptableEvmLatencyMs(u64),
+    NotificationDestinations(Vec<OutboxDestination>),
+    NonceOffset(u64),
+    LoggerFilter(String),
+    AddToWhitelist(Principal),
+    AddAllowedToken(Principal),
+    MaxDepositConfirmationDelaySecs(u64),
+    Owner(Principal),
+}
+
+impl Display for SettingCall {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BtfBridgeContract(address) => write!(f, "set_btf_bridge_contract({address})"),
+            Self::GasBalanceThresholds(thresholds) => write!(
+                f,
+                "set_gas_balance_thresholds(low: {}, critical: {})",
+                thresholds.low_watermark, thresholds.critical_watermark
+            ),
+            Self::OperationLogCompression(enabled) => {
+                write!(f, "set_operation_log_compression({enabled})")
+            }
+            Self::StrictEventValidation(enabled) => {
+                write!(f, "set_strict_event_validation({enabled})")
+            }
+            Self::EvmParamsHistorySize(size) => write!(f, "set_evm_params_history_size({size})"),
+            Self::MaxAcceptableEvmLatencyMs(latency) => {
+                write!(f, "set_max_acceptable_evm_latency_ms({latency})")
+            }
+            Self::NotificationDestinations(destinations) => {
+                write!(f, "set_notification_destinations({destinations:?})")
+            }
+            Self::NonceOffset(offset) => write!(f, "set_nonce_offset({offset})"),
+            Self::LoggerFilter(filter) => write!(f, "set_logger_filter({filter:?})"),
+            Self::AddToWhitelist(principal) => write!(f, "add_to_whitelist({principal})"),
+            Self::AddAllowedToken(token) => write!(f, "add_allowed_token({token})"),
+            Self::MaxDepositConfirmationDelaySecs(delay) => {
+                write!(f, "set_max_deposit_confirmation_delay_secs({delay})")
+            }
+            Self::Owner(owner) => write!(f, "set_owner({owner})"),
+        }
+    }
+}
+
+/// Result of a setter call.
+#[derive(Debug)]
+enum CallOutcome {
+    Applied,
+    /// The setting already has the expected value.
+    Skipped,
+    Failed(anyhow::Error),
+}
+
+/// Clients of the configured bridge canister.
+struct Clients {
+    bridge: GenericBridgeClient<IcAgentClient>,
+    icrc2: Icrc2BridgeClient<IcAgentClient>,
+}
+
+impl SettingCall {
+    /// Checks if the setting already has the expected value. Settings without a getter are
+    /// never considered applied, as setting them again has no effect.
+    async fn is_applied(&self, clients: &Clients) -> anyhow::Result<bool> {
+        let bridge = &clients.bridge;
+        let applied = match self {
+            Self::BtfBridgeContract(address) => {
+                bridge.get_btf_bridge_contract().await?.as_ref() == Some(address)
+            }
+            Self::GasBalanceThresholds(thresholds) => {
+                bridge.get_gas_balance_status().await?.thresholds.as_ref() == Some(thresholds)
+            }
+            Self::MaxAcceptableEvmLatencyMs(latency) => {
+                bridge
+                    .get_evm_latency_stats()
+                    .await?
+                    .max_acceptable_latency_ms
+                    == Some(*latency)
+            }
+            Self::NotificationDestinations(destinations) => {
+                bridge.get_notification_destinations().await? == *destinations
+            }
+            Self::NonceOffset(offset) => bridge.get_nonce_state().await?.nonce_offset == *offset,
+            Self::AddToWhitelist(principal) => bridge.get_whitelist().await?.contains(principal),
+            Self::AddAllowedToken(token) => clients
+                .icrc2
+                .get_allowed_tokens()
+                .await?
+                .is_some_and(|tokens| tokens.contains(token)),
+            Self::MaxDepositConfirmationDelaySecs(delay) => {
+                clients
+                    .icrc2
+                    .get_max_deposit_confirmation_delay_secs()
+                    .await?
+                    == *delay
+            }
+            Self::Owner(owner) => bridge.get_owner().await? == *owner,
+            Self::OperationLogCompression(_)
+            | Self::StrictEventValidation(_)
+            | Self::EvmParamsHistorySize(_)
+            | Self::LoggerFilter(_) => false,
+        };
+
+        Ok(applied)
+    }
+
+    async fn apply(&self, clients: &mut Clients) -> anyhow::Result<()> {
+        let bridge = &mut clients.bridge;
+        match self {
+            Self::BtfBridgeContract(address) => bridge.set_btf_bridge_contract(address).await?,
+            Self::GasBalanceThresholds(thresholds) => {
+                bridge
+                    .set_gas_balance_thresholds(Some(thresholds.clone()))
+                    .await?
+            }
+            Self::OperationLogCompression(enabled) => {
+                bridge.set_operation_log_compression(*enabled).await?
+            }
+            Self::StrictEventValidation(enabled) => {
+                bridge.set_strict_event_validation(*enabled).await?
+            }
+            Self::EvmParamsHistorySize(size) => bridge.set_evm_params_history_size(*size).await?,
+            Self::MaxAcceptableEvmLatencyMs(latency) => {
+                bridge
+                    .set_max_acceptable_evm_latency_ms(Some(*latency))
+                    .await?
+            }
+            Self::NotificationDestinations(destinations) => {
+                bridge
+                    .set_notification_destinations(destinations.clone())
+                    .await?
+            }
+            Self::NonceOffset(offset) => bridge.set_nonce_offset(*offset).await??,
+            Self::LoggerFilter(filter) => bridge
+                .set_logger_filter(filter.clone())
+                .await?
+                .map_err(|e| anyhow!("failed to set logger filter: {e:?}"))?,
+            Self::AddToWhitelist(principal) => bridge.add_to_whitelist(*principal).await??,
+            Self::AddAllowedToken(token) => clients.icrc2.add_allowed_token(*token).await??,
+            Self::MaxDepositConfirmationDelaySecs(delay) => {
+                clients
+                    .icrc2
+                    .set_max_deposit_confirmation_delay_secs(*delay)
+                    .await??
+            }
+            Self::Owner(owner) => bridge.set_owner(*owner).await?,
+        }
+
+        Ok(())
+    }
+}
+
+impl ConfigureCommands {
+    pub async fn configure(&self, identity: GenericIdentity, ic_host: &str) -> anyhow::Result<()> {
+        let settings = self.read_settings()?;
+        let calls = settings.calls();
+
+        if self.dry_run {
+            println!(
+                "{} calls would be made to canister {}:",
+                calls.len(),
+                self.canister_id
+            );
+            for call in &calls {
+                println!("  {call}");
+            }
+            return Ok(());
+        }
+
+        let agent = ic_agent::Agent::builder()
+            .with_url(ic_host)
+            .with_identity(identity)
+            .build()?;
+
+        super::fetch_root_key(ic_host, &agent).await?;
+
+        apply_settings(self.canister_id, agent, calls).await
+    }
+
+    /// Reads the settings from the plain or the encrypted file.
+    fn read_settings(&self) -> anyhow::Result<BridgeSettings> {
+        match (&self.config, &self.encrypted_config, &self.key_file) {
+            (Some(path), _, _) => read_settings_file(path),
+            (None, Some(path), Some(key_file)) => {
+                parse_settings(path, &read_encrypted_config(path, key_file)?)
+            }
+            _ => bail!("either config or encrypted config with its key file must be set"),
+        }
+    }
+}
+
+/// Makes the setter `calls` to the canister, skipping the settings which already have
+/// the expected value.
+pub(super) async fn apply_settings(
+    canister_id: Principal,
+    agent: Agent,
+    calls: Vec<SettingCall>,
+) -> anyhow::Result<()> {
+    let mut clients = Clients {
+        bridge: GenericBridgeClient::new(IcAgentClient::with_agent(canister_id, agent.clone())),
+        icrc2: Icrc2BridgeClient::new(IcAgentClient::with_agent(canister_id, agent)),
+    };
+
+    let mut outcomes = Vec::with_capacity(calls.len());
+    for call in calls {
+        let outcome = match call.is_applied(&clients).await {
+            Ok(true) => CallOutcome::Skipped,
+            Ok(false) => match call.apply(&mut clients).await {
+                Ok(()) => CallOutcome::Applied,
+                Err(e) => CallOutcome::Failed(e),
+            },
+            Err(e) => CallOutcome::Failed(e.context("failed to query the current value")),
+        };
+
+        match &outcome {
+            CallOutcome::Applied => info!("{call}: applied"),
+            CallOutcome::Skipped => info!("{call}: already set"),
+            CallOutcome::Failed(e) => warn!("{call}: failed: {e:#}"),
+        }
+
+        outcomes.push((call, outcome));
+    }
+
+    println!("{}", outcome_report(&outcomes));
+
+    let failed = outcomes
+        .iter()
+        .filter(|(_, outcome)| matches!(outcome, CallOutcome::Failed(_)))
+        .count();
+    if failed > 0 {
+        bail!(
+            "{failed} of {} calls failed, re-run the command to retry",
+            outcomes.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Formats the outcome of every call, one call per line.
+fn outcome_report(outcomes: &[(SettingCall, CallOutcome)]) -> String {
+    outcomes
+        .iter()
+        .map(|(call, outcome)| match outcome {
+            CallOutcome::Applied => format!("APPLIED  {call}"),
+            CallOutcome::Skipped => format!("SKIPPED  {call}"),
+            CallOutcome::Failed(e) => format!("FAILED   {call}: {e:#}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_are_parsed_from_json() {
+        let settings: BridgeSettings = serde_json::from_str(
+            r#"{
+                "btf_bridge_contract": "0x0101010101010101010101010101010101010101",
+                "operation_log_compression": true,
+                "notification_destinations": [
+                    { "Webhook": { "url": "https://partner.example/hook" } }
+                ],
+                "nonce_offset": 1000,
+                "whitelist": ["2chl6-4hpzw-vqaaa-aaaaa-c"],
+                "owner": "2vxsx-fae"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            settings.btf_bridge_contract,
+            Some(H160::from_slice(&[1; 20]))
+        );
+        assert_eq!(settings.operation_log_compression, Some(true));
+        assert_eq!(settings.nonce_offset, Some(1000));
+        assert_eq!(settings.whitelist.len(), 1);
+        assert_eq!(settings.owner, Some(Principal::anonymous()));
+        assert_eq!(settings.gas_balance_thresholds, None);
+    }
+
+    #[test]
+    fn unknown_settings_are_rejected() {
+        let result = serde_json::from_str::<BridgeSettings>(r#"{ "nonce_ofset": 1000 }"#);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn owner_is_changed_last() {
+        let settings = BridgeSettings {
+            owner: Some(Principal::anonymous()),
+            nonce_offset: Some(10),
+            allowed_tokens: vec![Principal::management_canister()],
+            strict_event_validation: Some(true),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            settings.calls(),
+            vec![
+                SettingCall::StrictEventValidation(true),
+                SettingCall::NonceOffset(10),
+                SettingCall::AddAllowedToken(Principal::management_canister()),
+                SettingCall::Owner(Principal::anonymous()),
+            ]
+        );
+        assert!(BridgeSettings::default().calls().is_empty());
+    }
+
+    #[test]
+    fn expected_config_reflects_settings() {
+        use bridge_did::concurrency::OperationConcurrencyLimits;
+        use bridge_did::evm_finality::EvmFinality;
+
+        use crate::commands::config_diff::{CommonBridgeConfig, Icrc2BridgeConfig};
+
+        let current = BridgeConfig {
+            common: CommonBridgeConfig {
+                owner: Principal::management_canister(),
+                btf_bridge_contract: None,
+                strict_event_validation: false,
+                evm_finality: EvmFinality::default(),
+                gas_balance_thresholds: None,
+                max_acceptable_evm_latency_ms: None,
+                notification_destinations: vec![],
+                nonce_offset: 0,
+                whitelist: vec![Principal::management_canister()],
+                concurrency_limits: OperationConcurrencyLimits::default(),
+                private_tx_relay: None,
+            },
+            icrc2: Some(Icrc2BridgeConfig {
+                allowed_tokens: None,
+                max_deposit_confirmation_delay_secs: 600,
+                deposit_fee: 0,
+                mint_order_signing_delay_secs: 0,
+                supply_guard: None,
+                preferences: None,
+            }),
+            erc20: None,
+        };
+        let settings = BridgeSettings {
+            owner: Some(Principal::anonymous()),
+            nonce_offset: Some(10),
+            whitelist: vec![Principal::management_canister()],
+            allowed_tokens: vec![Principal::management_canister()],
+            // Not reflected in the config.
+            logger_filter: Some("debug".into()),
+            ..Default::default()
+        };
+
+        let mut expected = current.clone();
+        expected.common.owner = Principal::anonymous();
+        expected.common.nonce_offset = 10;
+        expected.icrc2.as_mut().unwrap().allowed_tokens =
+            Some(vec![Principal::management_canister()]);
+
+        assert_eq!(settings.expected_config(&current), expected);
+        assert_eq!(BridgeSettings::default().expected_config(&current), current);
+    }
+
+    #[test]
+    fn report_lists_every_call() {
+        let outcomes = vec![
+            (SettingCall::NonceOffset(10), CallOutcome::Applied),
+            (
+                SettingCall::StrictEventValidation(true),
+                CallOutcome::Skipped,
+            ),
+            (
+                SettingCall::Owner(Principal::anonymous()),
+                CallOutcome::Failed(anyhow!("access denied")),
+            ),
+        ];
+
+        assert_eq!(
+            outcome_report(&outcomes),
+            "APPLIED  set_nonce_offset(10)\n\
+             SKIPPED  set_strict_event_validation(true)\n\
+             FAILED   set_owner(2vxsx-fae): access denied"
+        );
+    }
+}
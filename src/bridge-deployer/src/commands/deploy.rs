@@ -15,6 +15,7 @@ use tracing::info;
 use super::{BTFArgs, Bridge};
 use crate::bridge_deployer::BridgeDeployer;
 use crate::canister_ids::{CanisterIds, CanisterIdsPath};
+use crate::canister_registry::{CanisterRegistry, RegistryEntry, DEFAULT_REGISTRY_PATH};
 use crate::commands::BtfDeployedContracts;
 use crate::config::BtcBridgeConnection;
 use crate::contracts::{EvmNetwork, SolidityContractDeployer};
@@ -68,6 +69,14 @@ pub struct DeployCommands {
     #[arg(long, value_name = "WALLET_CANISTER", env)]
     wallet_canister: Option<Principal>,
 
+    /// Path to the registry of the deployed canisters, which is updated with the new canister.
+    #[arg(long, value_name = "REGISTRY_PATH", default_value = DEFAULT_REGISTRY_PATH)]
+    registry: PathBuf,
+
+    /// Name of the canister in the registry. If not set, the bridge type name is used.
+    #[arg(long, value_name = "CANISTER_NAME")]
+    canister_name: Option<String>,
+
     /// These are extra arguments for the BTF bridge.
     #[command(flatten, next_help_heading = "BTF Bridge deployment")]
     btf_args: BTFArgs,
@@ -116,6 +125,19 @@ impl DeployCommands {
         canister_ids.set((&self.bridge_type).into(), canister_id);
         canister_ids.write()?;
 
+        // add the canister to the registry of the deployed canisters
+        let mut registry = CanisterRegistry::read_or_default(&self.registry);
+        registry.add(RegistryEntry {
+            canister_name: self
+                .canister_name
+                .clone()
+                .unwrap_or_else(|| self.bridge_type.kind().to_string()),
+            canister_id,
+            bridge_type: (&self.bridge_type).into(),
+            network,
+        });
+        registry.write()?;
+
         info!("Deploying BTF bridge");
         let BtfDeployedContracts {
             btf_bridge,
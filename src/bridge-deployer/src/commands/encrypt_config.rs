@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Parser;
+use tracing::info;
+
+use crate::encrypted_config::{read_key_file, EncryptedConfig};
+
+/// The encrypt config command.
+///
+/// This command encrypts a config file with sensitive data, so it can be stored and passed
+/// to the other commands only in the encrypted form.
+#[derive(Debug, Parser)]
+pub struct EncryptConfigCommands {
+    /// Path to the key file. The encryption key is derived from the file content.
+    #[arg(long, value_name = "KEY_FILE")]
+    key: PathBuf,
+
+    /// Path to the config file to encrypt.
+    #[arg(long, value_name = "INPUT")]
+    config: PathBuf,
+
+    /// Path to write the encrypted config to.
+    #[arg(long, value_name = "OUTPUT")]
+    output: PathBuf,
+}
+
+impl EncryptConfigCommands {
+    pub fn encrypt_config(&self) -> anyhow::Result<()> {
+        let key_material = read_key_file(&self.key)?;
+        let config = std::fs::read(&self.config)
+            .with_context(|| format!("failed to read {}", self.config.display()))?;
+
+        let encrypted = EncryptedConfig::encrypt(&config, &key_material)?;
+        std::fs::write(&self.output, encrypted.to_bytes())
+            .with_context(|| format!("failed to write {}", self.output.display()))?;
+
+        info!(
+            "Config {} encrypted to {}",
+            self.config.display(),
+            self.output.display()
+        );
+        println!("Encrypted config written to {}", self.output.display());
+
+        Ok(())
+    }
+}
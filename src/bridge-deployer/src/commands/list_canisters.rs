@@ -0,0 +1,190 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use did::build::BuildData;
+use ic_canister_client::agent::identity::GenericIdentity;
+use ic_canister_client::{CanisterClient, IcAgentClient};
+use tracing::{info, warn};
+
+use crate::canister_ids::CanisterType;
+use crate::canister_registry::{CanisterRegistry, RegistryEntry, DEFAULT_REGISTRY_PATH};
+use crate::contracts::EvmNetwork;
+
+/// The list canisters command.
+///
+/// This command lists the bridge canisters from the registry of the deployed canisters,
+/// which is updated by the `deploy` command, and checks that they respond.
+#[derive(Debug, Parser)]
+pub struct ListCanistersCommands {
+    /// Path to the registry of the deployed canisters.
+    #[arg(long, value_name = "REGISTRY_PATH", default_value = DEFAULT_REGISTRY_PATH)]
+    registry: PathBuf,
+
+    /// List only the canisters of the given bridge type, e.g. `icrc2-bridge`.
+    #[arg(long, value_name = "BRIDGE_TYPE")]
+    filter_type: Option<CanisterType>,
+
+    /// List only the canisters deployed for the given network.
+    #[arg(long, value_name = "NETWORK")]
+    filter_network: Option<EvmNetwork>,
+}
+
+/// Status of a registered canister.
+#[derive(Debug)]
+enum CanisterStatus {
+    /// The canister responded with its build data.
+    Running(BuildData),
+    /// The canister query failed.
+    Error(String),
+    /// The canister is deployed for another network than the one the tool is connected to.
+    Skipped,
+}
+
+impl ListCanistersCommands {
+    pub async fn list_canisters(
+        &self,
+        identity: GenericIdentity,
+        ic_host: &str,
+        network: EvmNetwork,
+    ) -> anyhow::Result<()> {
+        let registry = CanisterRegistry::read(&self.registry)?;
+
+        let agent = ic_agent::Agent::builder()
+            .with_url(ic_host)
+            .with_identity(identity)
+            .build()?;
+
+        super::fetch_root_key(ic_host, &agent).await?;
+
+        let mut statuses = vec![];
+        for entry in registry.filtered(self.filter_type.as_ref(), self.filter_network) {
+            if entry.network != network {
+                statuses.push((entry, CanisterStatus::Skipped));
+                continue;
+            }
+
+            info!("Fetching status of canister {}", entry.canister_id);
+            let client = IcAgentClient::with_agent(entry.canister_id, agent.clone());
+            let status = match client
+                .query::<_, BuildData>("get_canister_build_data", ())
+                .await
+            {
+                Ok(data) => CanisterStatus::Running(data),
+                Err(e) => {
+                    warn!(
+                        "Failed to fetch status of canister {}: {e}",
+                        entry.canister_id
+                    );
+                    CanisterStatus::Error(e.to_string())
+                }
+            };
+
+            statuses.push((entry, status));
+        }
+
+        if statuses.is_empty() {
+            println!("No canisters found in {}", self.registry.display());
+            return Ok(());
+        }
+
+        println!("{}", canisters_table(&statuses));
+
+        Ok(())
+    }
+}
+
+/// Formats the registered canisters with their status as a table.
+fn canisters_table(statuses: &[(&RegistryEntry, CanisterStatus)]) -> String {
+    let header = [
+        "NAME",
+        "CANISTER ID",
+        "TYPE",
+        "NETWORK",
+        "VERSION",
+        "STATUS",
+    ];
+
+    let rows: Vec<[String; 6]> = statuses
+        .iter()
+        .map(|(entry, status)| {
+            let (version, status) = match status {
+                CanisterStatus::Running(data) => (data.pkg_version.clone(), "OK".to_string()),
+                CanisterStatus::Error(e) => ("-".to_string(), format!("ERROR: {e}")),
+                CanisterStatus::Skipped => ("-".to_string(), "SKIPPED".to_string()),
+            };
+
+            [
+                entry.canister_name.clone(),
+                entry.canister_id.to_text(),
+                entry.bridge_type.to_string(),
+                entry.network.to_string(),
+                version,
+                status,
+            ]
+        })
+        .collect();
+
+    super::format_table(header, &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use super::*;
+
+    fn entry(name: &str, bridge_type: CanisterType, network: EvmNetwork) -> RegistryEntry {
+        RegistryEntry {
+            canister_name: name.to_string(),
+            canister_id: Principal::from_slice(&[1; 29]),
+            bridge_type,
+            network,
+        }
+    }
+
+    fn build_data(version: &str) -> BuildData {
+        BuildData {
+            cargo_target_triple: "wasm32-unknown-unknown".to_string(),
+            cargo_features: "export-api".to_string(),
+            pkg_name: "icrc2-bridge".to_string(),
+            pkg_version: version.to_string(),
+            rustc_semver: "1.81.0".to_string(),
+            build_timestamp: "2024-10-01T12:00:00.000000000Z".to_string(),
+            cargo_debug: "false".to_string(),
+            git_branch: "main".to_string(),
+            git_sha: "acf6c57".to_string(),
+            git_commit_timestamp: "2024-10-01T11:00:00.000000000Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn table_shows_canister_statuses() {
+        let icrc = entry("icrc2-bridge", CanisterType::Icrc2, EvmNetwork::Localhost);
+        let btc = entry("btc", CanisterType::Btc, EvmNetwork::Localhost);
+        let rune = entry("rune-mainnet", CanisterType::Rune, EvmNetwork::Mainnet);
+        let statuses = vec![
+            (&icrc, CanisterStatus::Running(build_data("0.2.0"))),
+            (&btc, CanisterStatus::Error("timeout".to_string())),
+            (&rune, CanisterStatus::Skipped),
+        ];
+
+        let table = canisters_table(&statuses);
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("NAME"));
+        assert!(lines[1].starts_with("icrc2-bridge"));
+        assert!(lines[1].contains("localhost"));
+        assert!(lines[1].ends_with("0.2.0    OK"));
+        assert!(lines[2].ends_with("ERROR: timeout"));
+        assert!(lines[3].contains("rune-bridge"));
+        assert!(lines[3].contains("mainnet"));
+        assert!(lines[3].ends_with("SKIPPED"));
+
+        // Columns are aligned.
+        let type_column = lines[0].find("TYPE").unwrap();
+        assert_eq!(&lines[1][type_column..type_column + 12], "icrc2-bridge");
+        assert_eq!(&lines[2][type_column..type_column + 10], "btc-bridge");
+        let status_column = lines[0].find("STATUS").unwrap();
+        assert_eq!(&lines[1][status_column..], "OK");
+    }
+}
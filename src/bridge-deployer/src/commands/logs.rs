@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use anyhow::bail;
+use bridge_client::{BridgeCanisterClient, GenericBridgeClient};
+use candid::Principal;
+use clap::Parser;
+use ic_canister_client::agent::identity::GenericIdentity;
+use ic_canister_client::IcAgentClient;
+use ic_log::did::Pagination;
+use ic_log::writer::Log;
+use tracing::{debug, Level};
+
+/// Max number of the log records requested in a single call.
+const LOGS_PAGE_SIZE: usize = 100;
+
+/// The logs command.
+///
+/// This command prints the in-memory logs of a bridge canister and, optionally,
+/// keeps polling the canister for the new records.
+#[derive(Debug, Parser)]
+pub struct LogsCommands {
+    /// The canister to read the logs of.
+    #[arg(value_name = "CANISTER_ID")]
+    canister_id: Principal,
+
+    /// Number of the latest records to print.
+    #[arg(long, value_name = "COUNT", default_value_t = 100)]
+    count: usize,
+
+    /// Keep polling the canister and print the new records.
+    #[arg(long)]
+    follow: bool,
+
+    /// Delay between the canister queries in the follow mode, in seconds.
+    #[arg(long, value_name = "SECONDS", default_value_t = 2)]
+    poll_interval_secs: u64,
+
+    /// Print only the records with the given or more severe level.
+    /// Records without a level are always printed.
+    #[arg(long, value_name = "LEVEL")]
+    level: Option<Level>,
+}
+
+impl LogsCommands {
+    pub async fn logs(&self, identity: GenericIdentity, ic_host: &str) -> anyhow::Result<()> {
+        let agent = ic_agent::Agent::builder()
+            .with_url(ic_host)
+            .with_identity(identity)
+            .build()?;
+
+        super::fetch_root_key(ic_host, &agent).await?;
+
+        let client = GenericBridgeClient::new(IcAgentClient::with_agent(self.canister_id, agent));
+
+        let total = self.fetch_logs(&client, 0, 0).await?.all_logs_count;
+        let mut next_offset = self
+            .print_logs(&client, total.saturating_sub(self.count))
+            .await?;
+
+        while self.follow {
+            tokio::time::sleep(Duration::from_secs(self.poll_interval_secs)).await;
+            next_offset = self.print_logs(&client, next_offset).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Prints all the records starting from the `offset`. Returns offset of the next record.
+    async fn print_logs(
+        &self,
+        client: &GenericBridgeClient<IcAgentClient>,
+        mut offset: usize,
+    ) -> anyhow::Result<usize> {
+        loop {
+            let logs = self.fetch_logs(client, offset, LOGS_PAGE_SIZE).await?;
+            debug!(
+                "Got {} log records from offset {offset}, total {}",
+                logs.logs.len(),
+                logs.all_logs_count
+            );
+
+            for record in &logs.logs {
+                if is_level_enabled(record, self.level) {
+                    println!("{}", record.log.trim_end());
+                }
+            }
+
+            // Old records may be already removed from the canister memory,
+            // so the next offset is taken from the returned records.
+            offset = match logs.logs.last() {
+                Some(last) => last.offset + 1,
+                None => return Ok(offset.max(logs.all_logs_count)),
+            };
+            if offset >= logs.all_logs_count {
+                return Ok(offset);
+            }
+        }
+    }
+
+    async fn fetch_logs(
+        &self,
+        client: &GenericBridgeClient<IcAgentClient>,
+        offset: usize,
+        count: usize,
+    ) -> anyhow::Result<ic_log::writer::Logs> {
+        match client.ic_logs(Pagination { offset, count }).await {
+            Ok(logs) => Ok(logs),
+            Err(e) if is_not_authorized(&e.to_string()) => bail!(
+                "the current identity is not allowed to read the logs of canister {}: \
+                 use the owner identity, or ask the owner to grant the logger read permission \
+                 with `add_logger_permission` ({e})",
+                self.canister_id
+            ),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Checks if the canister rejected the call because of the caller permissions.
+fn is_not_authorized(error_message: &str) -> bool {
+    let message = error_message.to_lowercase();
+    [
+        "only allowed for the owner",
+        "not authorized",
+        "notauthorized",
+        "permission",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// Checks if the record should be printed with the `max_level` filter.
+fn is_level_enabled(record: &Log, max_level: Option<Level>) -> bool {
+    match (max_level, record_level(&record.log)) {
+        (Some(max_level), Some(level)) => level <= max_level,
+        _ => true,
+    }
+}
+
+/// Returns the level of the log record, if the record contains it.
+fn record_level(record: &str) -> Option<Level> {
+    record
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_ascii_alphabetic()))
+        .find_map(|word| match word {
+            "ERROR" => Some(Level::ERROR),
+            "WARN" => Some(Level::WARN),
+            "INFO" => Some(Level::INFO),
+            "DEBUG" => Some(Level::DEBUG),
+            "TRACE" => Some(Level::TRACE),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(text: &str) -> Log {
+        Log {
+            log: text.to_string(),
+            offset: 0,
+        }
+    }
+
+    #[test]
+    fn should_parse_record_level() {
+        assert_eq!(
+            record_level("[2024-01-01T00:00:00Z WARN bridge_canister] gas is low"),
+            Some(Level::WARN)
+        );
+        assert_eq!(
+            record_level("2024-01-01 ERROR failed to send INFO"),
+            Some(Level::ERROR)
+        );
+        assert_eq!(record_level("no level here"), None);
+    }
+
+    #[test]
+    fn should_detect_not_authorized_error() {
+        assert!(is_not_authorized(
+            "Canister trapped: Running this method is only allowed for the owner of the canister"
+        ));
+        assert!(is_not_authorized("Logger error: NotAuthorized"));
+        assert!(!is_not_authorized("canister is stopped"));
+    }
+
+    #[test]
+    fn should_filter_records_by_level() {
+        let warn = log("[WARN] gas is low");
+        let debug = log("[DEBUG] collecting logs");
+        let plain = log("plain record");
+
+        assert!(is_level_enabled(&debug, None));
+        assert!(is_level_enabled(&warn, Some(Level::INFO)));
+        assert!(!is_level_enabled(&debug, Some(Level::INFO)));
+        assert!(is_level_enabled(&plain, Some(Level::ERROR)));
+    }
+}
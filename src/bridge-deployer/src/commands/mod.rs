@@ -6,27 +6,43 @@ use bridge_did::error::BTFResult;
 use bridge_did::evm_link::EvmLink;
 use bridge_did::init::erc20::{BaseEvmSettings, QueryDelays};
 use bridge_did::init::BtcBridgeConfig;
+use build_data::BuildDataCommands;
 use candid::{Encode, Principal};
 use clap::{Args, Subcommand};
+use configure::ConfigureCommands;
 use deploy::DeployCommands;
+use encrypt_config::EncryptConfigCommands;
 use eth_signer::sign_strategy::SigningStrategy;
 use ethereum_types::{H160, H256};
 use ic_agent::Agent;
 use ic_canister_client::agent::identity::GenericIdentity;
 use ic_canister_client::{CanisterClient, IcAgentClient};
+use list_canisters::ListCanistersCommands;
+use logs::LogsCommands;
 use reinstall::ReinstallCommands;
+use rotate_controller::RotateControllerCommands;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, trace};
 use upgrade::UpgradeCommands;
+use wait_btf_bridge::WaitBtfBridgeCommands;
 
 use crate::canister_ids::{CanisterIdsPath, CanisterType};
 use crate::commands::wrap_token_type::WrapTokenType;
 use crate::config::{self, BaseEvmSettingsConfig};
 use crate::contracts::{EvmNetwork, NetworkConfig, SolidityContractDeployer};
 
+mod build_data;
+mod config_diff;
+mod configure;
 mod deploy;
+mod encrypt_config;
+mod list_canisters;
+mod logs;
 mod reinstall;
+mod rotate_controller;
 mod upgrade;
+mod upgrade_check;
+mod wait_btf_bridge;
 mod wasm;
 mod wrap_token_type;
 
@@ -56,6 +72,55 @@ pub enum Commands {
 
     #[command(subcommand)]
     Wrap(WrapTokenType),
+
+    #[command(
+        name = "build-data",
+        about = "Check build data of the deployed Bridge canisters",
+        next_help_heading = "Check Build Data"
+    )]
+    BuildData(BuildDataCommands),
+
+    #[command(
+        name = "wait-btf-bridge",
+        about = "Wait for the BTF bridge contract of a deployed Bridge and verify it",
+        next_help_heading = "Wait BTF Bridge"
+    )]
+    WaitBtfBridge(WaitBtfBridgeCommands),
+
+    #[command(
+        name = "configure",
+        about = "Apply post-deploy configuration of a Bridge from a file",
+        next_help_heading = "Configure Bridge"
+    )]
+    Configure(ConfigureCommands),
+
+    #[command(
+        name = "rotate-controller",
+        about = "Add a new controller to a Bridge and optionally remove the old one",
+        next_help_heading = "Rotate Controller"
+    )]
+    RotateController(RotateControllerCommands),
+
+    #[command(
+        name = "logs",
+        about = "Print and follow the logs of a Bridge",
+        next_help_heading = "Bridge Logs"
+    )]
+    Logs(LogsCommands),
+
+    #[command(
+        name = "list-canisters",
+        about = "List the deployed Bridge canisters from the registry with their status",
+        next_help_heading = "List Canisters"
+    )]
+    ListCanisters(ListCanistersCommands),
+
+    #[command(
+        name = "encrypt-config",
+        about = "Encrypt a config file with sensitive data",
+        next_help_heading = "Encrypt Config"
+    )]
+    EncryptConfig(EncryptConfigCommands),
 }
 
 #[derive(Subcommand, Clone, Serialize, Deserialize, Debug)]
@@ -168,6 +233,7 @@ impl Bridge {
                         evm_params_query,
                         logs_query,
                     },
+                    evm_finality: None,
                 };
 
                 Encode!(&init, &erc)?
@@ -281,6 +347,22 @@ impl Commands {
             }
             Commands::Upgrade(upgrade) => upgrade.upgrade_canister(identity, ic_host).await?,
             Commands::Wrap(wrap_token_type) => wrap_token_type.wrap(network, pk, evm).await?,
+            Commands::BuildData(build_data) => {
+                build_data.check_build_data(identity, ic_host).await?
+            }
+            Commands::Configure(configure) => configure.configure(identity, ic_host).await?,
+            Commands::RotateController(rotate) => {
+                rotate.rotate_controller(identity, ic_host).await?
+            }
+            Commands::Logs(logs) => logs.logs(identity, ic_host).await?,
+            Commands::ListCanisters(list) => {
+                list.list_canisters(identity, ic_host, network).await?
+            }
+            Commands::EncryptConfig(encrypt) => encrypt.encrypt_config()?,
+            Commands::WaitBtfBridge(wait) => {
+                wait.wait_btf_bridge(identity, ic_host, network, evm, pk)
+                    .await?
+            }
         };
 
         Ok(())
@@ -384,3 +466,29 @@ pub(crate) async fn fetch_root_key(ic_host: &str, agent: &Agent) -> anyhow::Resu
 
     Ok(())
 }
+
+/// Formats the rows as a table with the given header and left-aligned columns.
+pub(crate) fn format_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) -> String {
+    let header = header.map(String::from);
+
+    let mut widths = header.clone().map(|column| column.len());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    std::iter::once(&header)
+        .chain(rows)
+        .map(|row| {
+            row.iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
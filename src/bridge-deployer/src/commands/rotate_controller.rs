@@ -0,0 +1,225 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+use candid::Principal;
+use clap::{ArgAction, Parser};
+use ic_agent::{Agent, Identity};
+use ic_canister_client::agent::identity::GenericIdentity;
+use ic_utils::interfaces::ManagementCanister;
+use tracing::{info, warn};
+
+/// The rotate controller command.
+///
+/// This command adds a new controller to a canister, verifies that the new controller
+/// can call the canister status method and, optionally, removes the old controller.
+#[derive(Debug, Parser)]
+pub struct RotateControllerCommands {
+    #[arg(long, value_name = "CANISTER_ID")]
+    canister_id: Principal,
+
+    /// The path to the PEM file of the new controller identity.
+    ///
+    /// The identity is used to check that the new controller can manage the canister
+    /// before the old controller is removed.
+    #[arg(long, value_name = "IDENTITY_PATH")]
+    new_controller_identity: PathBuf,
+
+    /// The controller to remove. If not set, the principal of the current identity is used.
+    #[arg(long, value_name = "PRINCIPAL")]
+    old_controller: Option<Principal>,
+
+    /// Keep the old controller. Set to `false` to remove it after the new controller is verified.
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = ArgAction::Set)]
+    keep_old: bool,
+}
+
+impl RotateControllerCommands {
+    pub async fn rotate_controller(
+        &self,
+        identity: GenericIdentity,
+        ic_host: &str,
+    ) -> anyhow::Result<()> {
+        let new_identity = GenericIdentity::try_from(self.new_controller_identity.as_ref())
+            .context("failed to load the new controller identity")?;
+        let new_controller = new_identity
+            .sender()
+            .map_err(|e| anyhow::anyhow!("invalid new controller identity: {e}"))?;
+
+        let agent = build_agent(ic_host, identity).await?;
+        let old_controller = match self.old_controller {
+            Some(principal) => principal,
+            None => agent
+                .get_principal()
+                .map_err(|e| anyhow::anyhow!("invalid agent identity: {e}"))?,
+        };
+
+        let management_canister = ManagementCanister::create(&agent);
+        let controllers = canister_controllers(&management_canister, &self.canister_id).await?;
+        info!("Canister {} controllers: {controllers:?}", self.canister_id);
+
+        if controllers.contains(&new_controller) {
+            info!("{new_controller} is already a controller of the canister");
+        } else {
+            info!("Adding controller {new_controller}");
+            update_controllers(
+                &management_canister,
+                &self.canister_id,
+                controllers_with(&controllers, new_controller),
+            )
+            .await?;
+        }
+
+        info!("Verifying controller {new_controller}");
+        let new_agent = build_agent(ic_host, new_identity).await?;
+        let new_management_canister = ManagementCanister::create(&new_agent);
+        let verified_controllers = canister_controllers(&new_management_canister, &self.canister_id)
+                .await
+                .with_context(|| {
+                    format!(
+                        "new controller {new_controller} failed to query the canister status, no controller was removed"
+                    )
+                })?;
+        if !verified_controllers.contains(&new_controller) {
+            bail!(
+                "{new_controller} is not listed in the canister controllers, no controller was removed"
+            );
+        }
+        info!("Controller {new_controller} is verified");
+
+        if self.keep_old {
+            println!(
+                "Controller {new_controller} added to canister {}, old controller {old_controller} is kept",
+                self.canister_id
+            );
+            return Ok(());
+        }
+
+        if old_controller == new_controller {
+            warn!("Old and new controllers are the same, nothing to remove");
+            return Ok(());
+        }
+
+        let controllers = controllers_without(&verified_controllers, old_controller)?;
+        info!("Removing controller {old_controller}");
+        update_controllers(&new_management_canister, &self.canister_id, controllers).await?;
+
+        println!(
+            "Controller of canister {} rotated from {old_controller} to {new_controller}",
+            self.canister_id
+        );
+
+        Ok(())
+    }
+}
+
+async fn build_agent(ic_host: &str, identity: impl Identity + 'static) -> anyhow::Result<Agent> {
+    let agent = Agent::builder()
+        .with_url(ic_host)
+        .with_identity(identity)
+        .build()?;
+
+    super::fetch_root_key(ic_host, &agent).await?;
+
+    Ok(agent)
+}
+
+async fn canister_controllers(
+    management_canister: &ManagementCanister<'_>,
+    canister_id: &Principal,
+) -> anyhow::Result<Vec<Principal>> {
+    let (status,) = management_canister
+        .canister_status(canister_id)
+        .call_and_wait()
+        .await?;
+
+    Ok(status.settings.controllers)
+}
+
+async fn update_controllers(
+    management_canister: &ManagementCanister<'_>,
+    canister_id: &Principal,
+    controllers: Vec<Principal>,
+) -> anyhow::Result<()> {
+    controllers
+        .into_iter()
+        .fold(
+            management_canister.update_settings(canister_id),
+            |builder, controller| builder.with_controller(controller),
+        )
+        .call_and_wait()
+        .await?;
+
+    Ok(())
+}
+
+/// Returns the controllers list with the `new` controller added.
+fn controllers_with(controllers: &[Principal], new: Principal) -> Vec<Principal> {
+    let mut controllers = controllers.to_vec();
+    if !controllers.contains(&new) {
+        controllers.push(new);
+    }
+    controllers
+}
+
+/// Returns the controllers list with the `old` controller removed.
+/// Fails if `old` is not a controller, or if it is the last one.
+fn controllers_without(
+    controllers: &[Principal],
+    old: Principal,
+) -> anyhow::Result<Vec<Principal>> {
+    if !controllers.contains(&old) {
+        bail!("{old} is not a controller of the canister");
+    }
+
+    let remaining: Vec<Principal> = controllers
+        .iter()
+        .copied()
+        .filter(|controller| *controller != old)
+        .collect();
+    if remaining.is_empty() {
+        bail!("refusing to remove {old}: it is the last controller of the canister");
+    }
+
+    Ok(remaining)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(seed: u8) -> Principal {
+        Principal::from_slice(&[seed; 29])
+    }
+
+    #[test]
+    fn should_add_controller_once() {
+        let controllers = vec![principal(1)];
+
+        let updated = controllers_with(&controllers, principal(2));
+        assert_eq!(updated, vec![principal(1), principal(2)]);
+
+        assert_eq!(controllers_with(&updated, principal(2)), updated);
+    }
+
+    #[test]
+    fn should_remove_old_controller() {
+        let controllers = vec![principal(1), principal(2), principal(3)];
+
+        let updated = controllers_without(&controllers, principal(2)).unwrap();
+        assert_eq!(updated, vec![principal(1), principal(3)]);
+    }
+
+    #[test]
+    fn should_refuse_to_remove_last_controller() {
+        let controllers = vec![principal(1)];
+
+        assert!(controllers_without(&controllers, principal(1)).is_err());
+    }
+
+    #[test]
+    fn should_refuse_to_remove_unknown_controller() {
+        let controllers = vec![principal(1), principal(2)];
+
+        assert!(controllers_without(&controllers, principal(3)).is_err());
+    }
+}
@@ -1,11 +1,23 @@
 use std::path::PathBuf;
 
+use anyhow::{bail, Context};
+use bridge_client::{BridgeCanisterClient, GenericBridgeClient};
 use candid::Principal;
 use clap::Parser;
+use ic_agent::Agent;
 use ic_canister_client::agent::identity::GenericIdentity;
+use ic_canister_client::IcAgentClient;
 use ic_utils::interfaces::management_canister::builders::InstallMode;
 use ic_utils::interfaces::ManagementCanister;
-use tracing::info;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use super::config_diff::{diff_report, BridgeConfig, ConfigSnapshot};
+use super::configure::{self, BridgeSettings};
+use super::upgrade_check::{
+    check_candid_compatibility, deployed_candid, wasm_candid, wasm_size_warning,
+};
+use crate::canister_ids::CanisterType;
 
 /// The upgrade command.
 ///
@@ -16,8 +28,53 @@ pub struct UpgradeCommands {
     canister_id: Principal,
 
     /// The path to the wasm file to deploy
-    #[arg(long, value_name = "WASM_PATH")]
-    wasm: PathBuf,
+    #[arg(long, value_name = "WASM_PATH", required_unless_present = "wasm_url")]
+    wasm: Option<PathBuf>,
+
+    /// The URL to download the wasm file to deploy from, e.g. a release artifact
+    /// or a file of an IC asset canister
+    #[arg(
+        long,
+        value_name = "URL",
+        conflicts_with = "wasm",
+        requires = "wasm_sha256"
+    )]
+    wasm_url: Option<String>,
+
+    /// The expected SHA-256 hash of the downloaded wasm file, hex encoded.
+    /// The upgrade is aborted if the hash doesn't match.
+    #[arg(long, value_name = "HASH", requires = "wasm_url")]
+    wasm_sha256: Option<String>,
+
+    /// The path to the candid file of the new wasm. If not set, the candid interface
+    /// is read from the `candid:service` metadata section of the wasm.
+    #[arg(long, value_name = "DID_PATH")]
+    candid: Option<PathBuf>,
+
+    /// Upgrade the canister even if the new candid interface is incompatible
+    /// with the deployed one.
+    #[arg(long)]
+    allow_incompatible_candid: bool,
+
+    /// Path to the JSON file with the bridge settings in the format of the `configure` command.
+    /// The settings are applied right after the upgrade.
+    #[arg(long, value_name = "CONFIG_PATH")]
+    config: Option<PathBuf>,
+
+    /// Type of the bridge, e.g. `icrc2-bridge`. If set, the bridge specific configuration
+    /// is included into the configuration diff.
+    #[arg(long, value_name = "BRIDGE_TYPE")]
+    bridge_type: Option<CanisterType>,
+
+    /// Print the diff of the current canister configuration and the configuration expected
+    /// after the upgrade, and exit without upgrading the canister unless `--yes` is set.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Upgrade the canister after printing the configuration diff. The diff is also added
+    /// to the operator audit log of the canister after the upgrade.
+    #[arg(long, requires = "dry_run")]
+    yes: bool,
 }
 
 impl UpgradeCommands {
@@ -28,7 +85,12 @@ impl UpgradeCommands {
     ) -> anyhow::Result<()> {
         info!("Upgrading canister with ID: {}", self.canister_id.to_text());
 
-        let canister_wasm = std::fs::read(&self.wasm)?;
+        let canister_wasm = self.load_wasm().await?;
+        let settings = self
+            .config
+            .as_deref()
+            .map(configure::read_settings_file)
+            .transpose()?;
 
         let agent = ic_agent::Agent::builder()
             .with_url(ic_host)
@@ -37,6 +99,21 @@ impl UpgradeCommands {
 
         super::fetch_root_key(ic_host, &agent).await?;
 
+        self.check_upgrade(&agent, &canister_wasm).await?;
+
+        let config_diff = if self.dry_run {
+            Some(self.config_diff(&agent, settings.as_ref()).await?)
+        } else {
+            None
+        };
+        if !self.should_install() {
+            println!(
+                "Dry run, canister {} is not upgraded. Use --yes to upgrade it",
+                self.canister_id
+            );
+            return Ok(());
+        }
+
         let management_canister = ManagementCanister::create(&agent);
 
         management_canister
@@ -51,6 +128,174 @@ impl UpgradeCommands {
             canister_id = self.canister_id
         );
 
+        if let Some(settings) = &settings {
+            configure::apply_settings(self.canister_id, agent.clone(), settings.calls()).await?;
+        }
+
+        if let Some((expected, report)) = config_diff {
+            self.check_upgraded_config(&agent, &expected).await?;
+            self.add_audit_note(&agent, &canister_wasm, &report).await;
+        }
+
+        Ok(())
+    }
+
+    /// Checks if the canister should be upgraded. The dry run only prints the configuration
+    /// diff, unless it is confirmed with `--yes`.
+    fn should_install(&self) -> bool {
+        !self.dry_run || self.yes
+    }
+
+    /// Prints the diff of the current canister configuration and the configuration expected
+    /// after the upgrade. Returns the expected configuration and the diff report.
+    async fn config_diff(
+        &self,
+        agent: &Agent,
+        settings: Option<&BridgeSettings>,
+    ) -> anyhow::Result<(ConfigSnapshot, String)> {
+        let current = BridgeConfig::fetch(agent, self.canister_id, self.bridge_type.as_ref())
+            .await
+            .context("failed to read the current canister configuration")?;
+        let expected = match settings {
+            Some(settings) => settings.expected_config(&current),
+            None => current.clone(),
+        };
+
+        let diff = current.snapshot().diff(&expected.snapshot());
+        let report = diff_report(&diff);
+        if diff.iter().any(|change| change.is_loss()) {
+            warn!("The upgrade resets or removes some of the canister configuration");
+        }
+        println!(
+            "Configuration diff of canister {}:\n{report}",
+            self.canister_id
+        );
+
+        Ok((expected.snapshot(), report))
+    }
+
+    /// Warns if the canister configuration after the upgrade differs from the expected one.
+    async fn check_upgraded_config(
+        &self,
+        agent: &Agent,
+        expected: &ConfigSnapshot,
+    ) -> anyhow::Result<()> {
+        let actual = BridgeConfig::fetch(agent, self.canister_id, self.bridge_type.as_ref())
+            .await
+            .context("failed to read the canister configuration after the upgrade")?
+            .snapshot();
+
+        let unexpected = expected.diff(&actual);
+        if !unexpected.is_empty() {
+            warn!(
+                "The canister configuration after the upgrade differs from the expected one:\n{}",
+                diff_report(&unexpected)
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Adds the configuration diff of the upgrade to the operator audit log of the canister.
+    async fn add_audit_note(&self, agent: &Agent, wasm: &[u8], report: &str) {
+        let note = format!(
+            "Upgrade with wasm sha256 {}\n{report}",
+            hex::encode(Sha256::digest(wasm))
+        );
+        let client =
+            GenericBridgeClient::new(IcAgentClient::with_agent(self.canister_id, agent.clone()));
+
+        match client.add_operator_audit_note(note).await {
+            Ok(Ok(id)) => info!("Configuration diff is added to the audit log with id {id}"),
+            Ok(Err(e)) => warn!("Failed to add the configuration diff to the audit log: {e}"),
+            Err(e) => warn!("Failed to add the configuration diff to the audit log: {e}"),
+        }
+    }
+
+    /// Checks the wasm size and the compatibility of the new candid interface
+    /// with the deployed one before the upgrade.
+    async fn check_upgrade(&self, agent: &Agent, wasm: &[u8]) -> anyhow::Result<()> {
+        if let Some(warning) = wasm_size_warning(wasm) {
+            warn!("{warning}");
+        }
+
+        let new_candid = match &self.candid {
+            Some(path) => Some(
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read {}", path.display()))?,
+            ),
+            None => wasm_candid(wasm)?,
+        };
+        let Some(new_candid) = new_candid else {
+            warn!("The new wasm has no candid interface, skipping the candid compatibility check");
+            return Ok(());
+        };
+        let Some(deployed) = deployed_candid(agent, self.canister_id).await else {
+            warn!("The deployed canister doesn't expose its candid interface, skipping the candid compatibility check");
+            return Ok(());
+        };
+
+        match check_candid_compatibility(&new_candid, &deployed) {
+            Ok(()) => info!("The new candid interface is compatible with the deployed one"),
+            Err(e) if self.allow_incompatible_candid => warn!("{e}"),
+            Err(e) => bail!("{e}\nUse --allow-incompatible-candid to upgrade anyway"),
+        }
+
         Ok(())
     }
+
+    /// Reads the wasm from the local file, or downloads it from the URL.
+    async fn load_wasm(&self) -> anyhow::Result<Vec<u8>> {
+        match (&self.wasm, &self.wasm_url, &self.wasm_sha256) {
+            (Some(path), _, _) => Ok(std::fs::read(path)?),
+            (None, Some(url), Some(sha256)) => super::wasm::download_wasm(url, sha256).await,
+            _ => anyhow::bail!("either wasm path or wasm URL with its sha256 must be set"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<UpgradeCommands, clap::Error> {
+        UpgradeCommands::try_parse_from(
+            [
+                "upgrade",
+                "--canister-id",
+                "2vxsx-fae",
+                "--wasm",
+                "bridge.wasm",
+            ]
+            .iter()
+            .chain(args)
+            .copied(),
+        )
+    }
+
+    #[test]
+    fn upgrade_is_installed_without_dry_run() {
+        let command = parse(&[]).unwrap();
+        assert!(command.should_install());
+    }
+
+    #[test]
+    fn dry_run_is_installed_only_with_confirmation() {
+        let command = parse(&["--dry-run"]).unwrap();
+        assert!(!command.should_install());
+
+        let command = parse(&["--dry-run", "--yes"]).unwrap();
+        assert!(command.should_install());
+    }
+
+    #[test]
+    fn confirmation_requires_dry_run() {
+        assert!(parse(&["--yes"]).is_err());
+    }
+
+    #[test]
+    fn bridge_type_is_parsed() {
+        let command = parse(&["--dry-run", "--bridge-type", "icrc2-bridge"]).unwrap();
+        assert_eq!(command.bridge_type, Some(CanisterType::Icrc2));
+    }
 }
@@ -0,0 +1,206 @@
+use std::borrow::Cow;
+use std::io::Read;
+
+use anyhow::{bail, Context};
+use candid::Principal;
+use candid_parser::utils::{service_compatible, CandidSource};
+use ic_agent::Agent;
+
+/// Max size of the wasm module, which can be installed with a single `install_code` call.
+pub const MAX_WASM_MODULE_SIZE: usize = 2 * 1024 * 1024;
+
+/// Canister metadata entry with the candid interface of the canister.
+const CANDID_METADATA: &str = "candid:service";
+
+/// Names of the wasm custom sections, which may contain the candid interface.
+const CANDID_SECTIONS: &[&str] = &["icp:public candid:service", "icp:private candid:service"];
+
+const WASM_MAGIC: &[u8] = b"\0asm";
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const CUSTOM_SECTION_ID: u8 = 0;
+
+/// Returns a warning if the wasm module is too large to be installed.
+pub fn wasm_size_warning(wasm: &[u8]) -> Option<String> {
+    (wasm.len() > MAX_WASM_MODULE_SIZE).then(|| {
+        format!(
+            "wasm module size {} bytes exceeds the canister module limit of {MAX_WASM_MODULE_SIZE} bytes",
+            wasm.len()
+        )
+    })
+}
+
+/// Checks that the `new` candid service is a subtype of the `deployed` one,
+/// so the clients of the deployed interface keep working after the upgrade.
+pub fn check_candid_compatibility(new: &str, deployed: &str) -> anyhow::Result<()> {
+    service_compatible(CandidSource::Text(new), CandidSource::Text(deployed)).map_err(|e| {
+        anyhow::anyhow!("the new candid interface is incompatible with the deployed one: {e}")
+    })
+}
+
+/// Reads the candid interface from the metadata of the deployed canister.
+/// Returns `None` if the canister doesn't expose it.
+pub async fn deployed_candid(agent: &Agent, canister_id: Principal) -> Option<String> {
+    let metadata = agent
+        .read_state_canister_metadata(canister_id, CANDID_METADATA)
+        .await
+        .ok()?;
+
+    String::from_utf8(metadata).ok()
+}
+
+/// Reads the candid interface from the custom section of the wasm module, which may be gzipped.
+/// Returns `None` if the module has no candid section.
+pub fn wasm_candid(wasm: &[u8]) -> anyhow::Result<Option<String>> {
+    let module = decompress_wasm(wasm)?;
+
+    for section in CANDID_SECTIONS {
+        if let Some(candid) = wasm_custom_section(&module, section)? {
+            let candid = String::from_utf8(candid.to_vec())
+                .context("candid section of the wasm module is not utf-8")?;
+            return Ok(Some(candid));
+        }
+    }
+
+    Ok(None)
+}
+
+fn decompress_wasm(wasm: &[u8]) -> anyhow::Result<Cow<'_, [u8]>> {
+    if !wasm.starts_with(GZIP_MAGIC) {
+        return Ok(Cow::Borrowed(wasm));
+    }
+
+    let mut module = vec![];
+    flate2::read::GzDecoder::new(wasm)
+        .read_to_end(&mut module)
+        .context("failed to decompress wasm module")?;
+
+    Ok(Cow::Owned(module))
+}
+
+/// Returns the content of the wasm module custom section with the given name.
+fn wasm_custom_section<'a>(module: &'a [u8], name: &str) -> anyhow::Result<Option<&'a [u8]>> {
+    if !module.starts_with(WASM_MAGIC) || module.len() < 8 {
+        bail!("not a wasm module");
+    }
+
+    let mut sections = &module[8..];
+    while let Some((&id, rest)) = sections.split_first() {
+        let (size, rest) = read_leb128(rest)?;
+        if rest.len() < size {
+            bail!("wasm module section is truncated");
+        }
+        let (content, rest) = rest.split_at(size);
+        sections = rest;
+
+        if id != CUSTOM_SECTION_ID {
+            continue;
+        }
+
+        let (name_len, content) = read_leb128(content)?;
+        if content.len() < name_len {
+            bail!("wasm module custom section name is truncated");
+        }
+        let (section_name, content) = content.split_at(name_len);
+        if section_name == name.as_bytes() {
+            return Ok(Some(content));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Reads unsigned LEB128 encoded integer. Returns the value and the remaining bytes.
+fn read_leb128(bytes: &[u8]) -> anyhow::Result<(usize, &[u8])> {
+    let mut value = 0usize;
+    for (i, byte) in bytes.iter().enumerate().take(5) {
+        value |= ((byte & 0x7f) as usize) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, &bytes[i + 1..]));
+        }
+    }
+
+    bail!("invalid LEB128 integer in wasm module")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const DEPLOYED_CANDID: &str = r#"service : {
+        get_owner : () -> (principal) query;
+        set_owner : (principal) -> ();
+    }"#;
+
+    /// Builds a wasm module with a single custom section.
+    fn wasm_with_section(name: &str, content: &[u8]) -> Vec<u8> {
+        let mut payload = vec![name.len() as u8];
+        payload.extend_from_slice(name.as_bytes());
+        payload.extend_from_slice(content);
+
+        let mut wasm = [WASM_MAGIC, &[1, 0, 0, 0]].concat();
+        wasm.push(CUSTOM_SECTION_ID);
+        let mut size = payload.len();
+        loop {
+            let byte = (size & 0x7f) as u8;
+            size >>= 7;
+            if size == 0 {
+                wasm.push(byte);
+                break;
+            }
+            wasm.push(byte | 0x80);
+        }
+        wasm.extend_from_slice(&payload);
+        wasm
+    }
+
+    #[test]
+    fn should_accept_compatible_interface() {
+        let new = r#"service : {
+            get_owner : () -> (principal) query;
+            set_owner : (principal) -> ();
+            get_bridge_manifest : () -> (text) query;
+        }"#;
+
+        assert!(check_candid_compatibility(new, DEPLOYED_CANDID).is_ok());
+        assert!(check_candid_compatibility(DEPLOYED_CANDID, DEPLOYED_CANDID).is_ok());
+    }
+
+    #[test]
+    fn should_reject_incompatible_interface() {
+        let removed_method = r#"service : {
+            get_owner : () -> (principal) query;
+        }"#;
+        assert!(check_candid_compatibility(removed_method, DEPLOYED_CANDID).is_err());
+
+        let changed_signature = r#"service : {
+            get_owner : () -> (text) query;
+            set_owner : (principal) -> ();
+        }"#;
+        assert!(check_candid_compatibility(changed_signature, DEPLOYED_CANDID).is_err());
+    }
+
+    #[test]
+    fn should_read_candid_from_wasm() {
+        let content = "x".repeat(200);
+        let wasm = wasm_with_section("icp:public candid:service", content.as_bytes());
+        assert_eq!(wasm_candid(&wasm).unwrap(), Some(content.clone()));
+
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&wasm).unwrap();
+        let gzipped = encoder.finish().unwrap();
+        assert_eq!(wasm_candid(&gzipped).unwrap(), Some(content));
+
+        let wasm = wasm_with_section("name", b"module name");
+        assert_eq!(wasm_candid(&wasm).unwrap(), None);
+
+        assert!(wasm_candid(b"not a wasm").is_err());
+    }
+
+    #[test]
+    fn should_warn_about_large_wasm() {
+        assert!(wasm_size_warning(&[0; 1024]).is_none());
+        assert!(wasm_size_warning(&vec![0; MAX_WASM_MODULE_SIZE + 1]).is_some());
+    }
+}
@@ -0,0 +1,178 @@
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::bail;
+use bridge_client::{BridgeCanisterClient, GenericBridgeClient};
+use candid::Principal;
+use clap::Parser;
+use ethereum_types::H256;
+use ic_canister_client::agent::identity::GenericIdentity;
+use ic_canister_client::IcAgentClient;
+use tracing::{debug, info};
+
+use crate::contracts::{EvmNetwork, NetworkConfig, SolidityContractDeployer};
+
+/// The wait BTF bridge command.
+///
+/// This command waits until the bridge canister is configured with the BTF bridge contract
+/// and checks that the contract is deployed to the EVM.
+#[derive(Debug, Parser)]
+pub struct WaitBtfBridgeCommands {
+    #[arg(long, value_name = "CANISTER_ID")]
+    canister_id: Principal,
+
+    /// Max time to wait for the BTF bridge contract, in seconds.
+    #[arg(long, value_name = "SECONDS", default_value_t = 300)]
+    timeout_secs: u64,
+
+    /// Delay between the bridge canister queries, in seconds.
+    #[arg(long, value_name = "SECONDS", default_value_t = 5)]
+    poll_interval_secs: u64,
+
+    /// Don't check the BTF bridge contract bytecode in the EVM.
+    #[arg(long)]
+    skip_bytecode_check: bool,
+
+    /// Custom EVM network URL to check the contract bytecode with.
+    #[arg(long, value_name = "URL")]
+    evm_url: Option<String>,
+}
+
+impl WaitBtfBridgeCommands {
+    pub async fn wait_btf_bridge(
+        &self,
+        identity: GenericIdentity,
+        ic_host: &str,
+        network: EvmNetwork,
+        evm: Principal,
+        pk: H256,
+    ) -> anyhow::Result<()> {
+        let agent = ic_agent::Agent::builder()
+            .with_url(ic_host)
+            .with_identity(identity)
+            .build()?;
+
+        super::fetch_root_key(ic_host, &agent).await?;
+
+        info!(
+            "Waiting for BTF bridge contract of canister {}",
+            self.canister_id
+        );
+        let client = &GenericBridgeClient::new(IcAgentClient::with_agent(self.canister_id, agent));
+        let address = poll_until_some(
+            Duration::from_secs(self.timeout_secs),
+            Duration::from_secs(self.poll_interval_secs),
+            || async move {
+                client
+                    .get_btf_bridge_contract()
+                    .await
+                    .map_err(anyhow::Error::from)
+            },
+        )
+        .await?;
+
+        let Some(address) = address else {
+            bail!(
+                "BTF bridge contract of canister {} is not configured after {} seconds",
+                self.canister_id,
+                self.timeout_secs
+            );
+        };
+
+        info!("Bridge canister is configured with BTF bridge contract {address}");
+
+        if self.skip_bytecode_check {
+            info!("Skipping BTF bridge contract bytecode check");
+        } else {
+            let network = NetworkConfig {
+                evm_network: network,
+                custom_network: self.evm_url.clone(),
+            };
+            let contract_deployer = SolidityContractDeployer::new(network, pk, evm);
+            if !contract_deployer.is_contract_deployed(&address.0).await? {
+                bail!("No contract bytecode is deployed at BTF bridge address {address}");
+            }
+
+            info!("BTF bridge contract bytecode is deployed");
+        }
+
+        println!("BTF bridge: {address}");
+
+        Ok(())
+    }
+}
+
+/// Calls `query` every `interval` until it returns `Some` value or the `timeout` is reached.
+/// Returns `None` on timeout. Query errors are not retried.
+async fn poll_until_some<T, F, Fut>(
+    timeout: Duration,
+    interval: Duration,
+    mut query: F,
+) -> anyhow::Result<Option<T>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<Option<T>>>,
+{
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if let Some(value) = query().await? {
+            return Ok(Some(value));
+        }
+
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+
+        debug!("Value is not available yet, retrying in {interval:?}");
+        tokio::time::sleep(interval.min(deadline - now)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn poll_returns_value_once_available() {
+        let calls = Cell::new(0);
+        let value = poll_until_some(Duration::from_secs(1), Duration::from_millis(1), || {
+            calls.set(calls.get() + 1);
+            let value = (calls.get() == 3).then_some(42);
+            async move { Ok(value) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(value, Some(42));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn poll_returns_none_on_timeout() {
+        let value = poll_until_some(
+            Duration::from_millis(20),
+            Duration::from_millis(5),
+            || async { Ok(None::<u32>) },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(value, None);
+    }
+
+    #[tokio::test]
+    async fn poll_stops_on_query_error() {
+        let calls = Cell::new(0);
+        let result = poll_until_some(Duration::from_secs(1), Duration::from_millis(1), || {
+            calls.set(calls.get() + 1);
+            async { Err::<Option<u32>, _>(anyhow::anyhow!("canister is stopped")) }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+}
@@ -1,5 +1,9 @@
 use std::path::Path;
 
+use anyhow::{bail, Context};
+use sha2::{Digest, Sha256};
+use tracing::info;
+
 use super::Bridge;
 
 const BRC20_BRIDGE_DEFAULT_PATH: &str = ".artifact/brc20-bridge.wasm.gz";
@@ -20,3 +24,66 @@ pub fn get_default_wasm_path(bridge: &Bridge) -> &Path {
         Bridge::Rune { .. } => RUNE_BRIDGE_DEFAULT_PATH,
     })
 }
+
+/// Downloads the wasm file from the `url` and checks that its SHA-256 hash
+/// equals to the `expected_sha256` hex string.
+///
+/// The url can point to any HTTP server, including an IC asset canister.
+pub async fn download_wasm(url: &str, expected_sha256: &str) -> anyhow::Result<Vec<u8>> {
+    info!("Downloading wasm from {url}");
+
+    let response = reqwest::get(url)
+        .await
+        .with_context(|| format!("failed to download wasm from {url}"))?
+        .error_for_status()
+        .with_context(|| format!("failed to download wasm from {url}"))?;
+    let wasm = response.bytes().await?.to_vec();
+
+    verify_wasm_sha256(&wasm, expected_sha256)?;
+    info!("Downloaded wasm checksum is verified");
+
+    Ok(wasm)
+}
+
+/// Checks that the SHA-256 hash of the `wasm` equals to the `expected_sha256` hex string.
+pub fn verify_wasm_sha256(wasm: &[u8], expected_sha256: &str) -> anyhow::Result<()> {
+    let expected_sha256 = expected_sha256.trim().trim_start_matches("0x");
+    let expected = hex::decode(expected_sha256)
+        .with_context(|| format!("invalid wasm sha256 {expected_sha256}"))?;
+
+    let actual = Sha256::digest(wasm);
+    if actual.as_slice() != expected.as_slice() {
+        bail!(
+            "wasm checksum mismatch: expected {expected_sha256}, got {}",
+            hex::encode(actual)
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SHA-256 of the `b"wasm"` bytes.
+    const WASM_SHA256: &str = "336154bf67f765f8f75d16a0accee61b5ee5f6a75b2a2905703df913bd550f3e";
+
+    #[test]
+    fn should_accept_matching_checksum() {
+        assert!(verify_wasm_sha256(b"wasm", WASM_SHA256).is_ok());
+        assert!(verify_wasm_sha256(b"wasm", &format!("0x{}", WASM_SHA256.to_uppercase())).is_ok());
+    }
+
+    #[test]
+    fn should_abort_on_checksum_mismatch() {
+        let err = verify_wasm_sha256(b"other wasm", WASM_SHA256).unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+    }
+
+    #[test]
+    fn should_reject_invalid_checksum() {
+        assert!(verify_wasm_sha256(b"wasm", "not a hash").is_err());
+        assert!(verify_wasm_sha256(b"wasm", &WASM_SHA256[..10]).is_err());
+    }
+}
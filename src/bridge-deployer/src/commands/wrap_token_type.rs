@@ -120,8 +120,10 @@ impl WrapTokenType {
             true,
         )? as u8;
         let chain_id = client.get_chain_id().await?;
+        let chain_id = u32::try_from(chain_id)
+            .map_err(|_| anyhow::anyhow!("chain id {chain_id} doesn't fit into Id256"))?;
 
-        let id = Id256::from_evm_address(&did::H160::new(*token_address), chain_id as u32);
+        let id = Id256::from_evm_address(&did::H160::new(*token_address), chain_id);
 
         Ok(TokenParameters {
             name,
@@ -57,6 +57,7 @@ impl From<Brc20BridgeConfig> for bridge_did::init::brc20::Brc20BridgeConfig {
             mempool_timeout: Duration::from_secs(value.mempool_timeout),
             indexer_consensus_threshold: value.indexer_consensus_threshold,
             schnorr_key_id: SchnorrKeyIds::ProductionKey1,
+            fallback_indexers: None,
         }
     }
 }
@@ -46,6 +46,9 @@ impl InitBridgeConfig {
                         .collect()
                 }),
             }),
+            evm_finality: None,
+            nonce_offset: None,
+            mode: None,
         }
     }
 
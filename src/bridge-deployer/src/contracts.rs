@@ -16,6 +16,7 @@ use ethereum_types::H256;
 use ethers_core::k256::ecdsa::SigningKey;
 use ethers_core::types::{BlockNumber, H160};
 use ethers_core::utils::hex::ToHexExt;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info};
 
 use crate::evm::dfx_webserver_port;
@@ -61,8 +62,9 @@ impl From<EvmNetwork> for NetworkConfig {
     }
 }
 
-#[derive(Debug, Clone, Copy, strum::Display, ValueEnum, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, strum::Display, ValueEnum, PartialEq, Eq, Serialize, Deserialize)]
 #[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum EvmNetwork {
     Localhost,
     Testnet,
@@ -364,6 +366,18 @@ impl SolidityContractDeployer<'_> {
         Ok(nonce)
     }
 
+    /// Checks if a contract bytecode is deployed at the given address.
+    pub async fn is_contract_deployed(&self, address: &H160) -> Result<bool> {
+        let client = self.rpc_client()?;
+        let code = client.get_code(*address, BlockNumber::Latest).await?;
+        debug!(
+            "Bytecode at address {}: {code}",
+            address.encode_hex_with_prefix()
+        );
+
+        Ok(!code.trim_start_matches("0x").is_empty())
+    }
+
     pub async fn transfer_eth(&self, to: &H160, amount: u128) -> Result<()> {
         info!(
             "Transferring {amount} ETH tokens to address {}",
@@ -0,0 +1,171 @@
+//! Encryption of the config files with sensitive data.
+//!
+//! Configs are encrypted with AES-256-GCM. The encryption key is derived from the content
+//! of a key file with HKDF-SHA256, using a random salt stored with the encrypted config.
+
+use std::path::Path;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, OsRng};
+use aes_gcm::{AeadCore, Aes256Gcm, Key, KeyInit, Nonce};
+use anyhow::{anyhow, bail, Context};
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+/// Prefix of the encrypted config files, which includes the format version.
+const MAGIC: &[u8] = b"BTFCFG1";
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 12;
+const HKDF_INFO: &[u8] = b"bridge-deployer config encryption";
+
+/// Config file, encrypted with a key file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedConfig {
+    salt: [u8; SALT_SIZE],
+    nonce: [u8; NONCE_SIZE],
+    ciphertext: Vec<u8>,
+}
+
+impl EncryptedConfig {
+    /// Encrypts the `config` with the key derived from the `key_material`.
+    pub fn encrypt(config: &[u8], key_material: &[u8]) -> anyhow::Result<Self> {
+        let mut salt = [0; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        let ciphertext = cipher(key_material, &salt)?
+            .encrypt(&nonce, config)
+            .map_err(|e| anyhow!("failed to encrypt config: {e}"))?;
+
+        Ok(Self {
+            salt,
+            nonce: nonce.into(),
+            ciphertext,
+        })
+    }
+
+    /// Decrypts the config with the key derived from the `key_material`.
+    /// Fails if the key is wrong or the encrypted data is altered.
+    pub fn decrypt(&self, key_material: &[u8]) -> anyhow::Result<Vec<u8>> {
+        cipher(key_material, &self.salt)?
+            .decrypt(Nonce::from_slice(&self.nonce), self.ciphertext.as_slice())
+            .map_err(|_| anyhow!("failed to decrypt config: wrong key or corrupted file"))
+    }
+
+    /// Serializes the encrypted config to the file content.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [MAGIC, &self.salt, &self.nonce, self.ciphertext.as_slice()].concat()
+    }
+
+    /// Deserializes the encrypted config from the file content.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let Some(data) = bytes.strip_prefix(MAGIC) else {
+            bail!("not an encrypted config file");
+        };
+        if data.len() < SALT_SIZE + NONCE_SIZE {
+            bail!("encrypted config file is truncated");
+        }
+
+        let (salt, data) = data.split_at(SALT_SIZE);
+        let (nonce, ciphertext) = data.split_at(NONCE_SIZE);
+        Ok(Self {
+            salt: salt.try_into()?,
+            nonce: nonce.try_into()?,
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
+}
+
+/// Reads the encrypted config file and decrypts it in memory with the key file.
+pub fn read_encrypted_config(config_path: &Path, key_path: &Path) -> anyhow::Result<Vec<u8>> {
+    let key_material = read_key_file(key_path)?;
+    let bytes = std::fs::read(config_path)
+        .with_context(|| format!("failed to read {}", config_path.display()))?;
+
+    EncryptedConfig::from_bytes(&bytes)
+        .and_then(|config| config.decrypt(&key_material))
+        .with_context(|| format!("failed to decrypt {}", config_path.display()))
+}
+
+/// Reads the key material from the key file.
+pub fn read_key_file(key_path: &Path) -> anyhow::Result<Vec<u8>> {
+    let key_material = std::fs::read(key_path)
+        .with_context(|| format!("failed to read key file {}", key_path.display()))?;
+    if key_material.is_empty() {
+        bail!("key file {} is empty", key_path.display());
+    }
+
+    Ok(key_material)
+}
+
+fn cipher(key_material: &[u8], salt: &[u8]) -> anyhow::Result<Aes256Gcm> {
+    let mut key = Key::<Aes256Gcm>::default();
+    Hkdf::<Sha256>::new(Some(salt), key_material)
+        .expand(HKDF_INFO, &mut key)
+        .map_err(|e| anyhow!("failed to derive config encryption key: {e}"))?;
+
+    Ok(Aes256Gcm::new(&key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &[u8] = br#"{ "logger_filter": "info" }"#;
+
+    #[test]
+    fn should_decrypt_encrypted_config() {
+        let encrypted = EncryptedConfig::encrypt(CONFIG, b"secret key").unwrap();
+        assert_ne!(encrypted.ciphertext, CONFIG);
+
+        let restored = EncryptedConfig::from_bytes(&encrypted.to_bytes()).unwrap();
+        assert_eq!(restored, encrypted);
+        assert_eq!(restored.decrypt(b"secret key").unwrap(), CONFIG);
+    }
+
+    #[test]
+    fn should_reject_wrong_key() {
+        let encrypted = EncryptedConfig::encrypt(CONFIG, b"secret key").unwrap();
+        assert!(encrypted.decrypt(b"other key").is_err());
+    }
+
+    #[test]
+    fn should_reject_altered_config() {
+        let mut bytes = EncryptedConfig::encrypt(CONFIG, b"secret key")
+            .unwrap()
+            .to_bytes();
+        *bytes.last_mut().unwrap() ^= 1;
+
+        let encrypted = EncryptedConfig::from_bytes(&bytes).unwrap();
+        assert!(encrypted.decrypt(b"secret key").is_err());
+    }
+
+    #[test]
+    fn should_reject_not_encrypted_file() {
+        assert!(EncryptedConfig::from_bytes(CONFIG).is_err());
+        assert!(EncryptedConfig::from_bytes(MAGIC).is_err());
+    }
+
+    #[test]
+    fn should_read_encrypted_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("config.key");
+        let config_path = dir.path().join("config.enc");
+        std::fs::write(&key_path, b"secret key").unwrap();
+        std::fs::write(
+            &config_path,
+            EncryptedConfig::encrypt(CONFIG, b"secret key")
+                .unwrap()
+                .to_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_encrypted_config(&config_path, &key_path).unwrap(),
+            CONFIG
+        );
+
+        std::fs::write(&key_path, b"other key").unwrap();
+        assert!(read_encrypted_config(&config_path, &key_path).is_err());
+    }
+}
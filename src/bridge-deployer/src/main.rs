@@ -2,10 +2,12 @@ use cli::Cli;
 
 mod bridge_deployer;
 mod canister_ids;
+mod canister_registry;
 mod cli;
 mod commands;
 mod config;
 mod contracts;
+mod encrypted_config;
 mod evm;
 
 #[tokio::main]
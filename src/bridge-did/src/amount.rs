@@ -0,0 +1,169 @@
+//! Conversion of the token amounts between the base units and the human-readable decimal strings.
+
+use candid::Nat;
+use did::U256;
+use thiserror::Error;
+
+/// Error of the decimal amount string parsing.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum AmountParseError {
+    #[error("amount string is empty")]
+    Empty,
+
+    #[error("amount contains invalid character `{0}`")]
+    InvalidCharacter(char),
+
+    #[error("amount has more than {0} fraction digits")]
+    TooManyFractionDigits(u8),
+
+    #[error("amount overflows U256")]
+    Overflow,
+}
+
+/// Formats the amount of token base units as a decimal number of tokens with the given
+/// `decimals`. Trailing zeros of the fraction are trimmed, e.g. `1.5` instead of `1.500`.
+pub fn format_amount(amount: &U256, decimals: u8) -> String {
+    format_digits(amount.0.to_string(), decimals)
+}
+
+/// Formats the amount of token base units as a decimal number of tokens with the given
+/// `decimals`. Same as [`format_amount`], but for the ICRC ledger amounts.
+pub fn format_nat_amount(amount: &Nat, decimals: u8) -> String {
+    format_digits(amount.0.to_str_radix(10), decimals)
+}
+
+fn format_digits(digits: String, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return digits;
+    }
+
+    let digits = format!("{digits:0>width$}", width = decimals + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{integer}.{fraction}")
+    }
+}
+
+/// Parses the decimal number of tokens with the given `decimals` into the amount of
+/// token base units. Inverse of [`format_amount`].
+///
+/// The amount should have a non-empty integer part, e.g. `0.5` rather than `.5`. Trailing
+/// zeros of the fraction are ignored, other fraction digits beyond `decimals` are rejected.
+pub fn parse_amount(amount: &str, decimals: u8) -> Result<U256, AmountParseError> {
+    let (integer, fraction) = match amount.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (amount, ""),
+    };
+    if integer.is_empty() || (fraction.is_empty() && amount.ends_with('.')) {
+        return Err(AmountParseError::Empty);
+    }
+    if let Some(c) = integer
+        .chars()
+        .chain(fraction.chars())
+        .find(|c| !c.is_ascii_digit())
+    {
+        return Err(AmountParseError::InvalidCharacter(c));
+    }
+
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(AmountParseError::TooManyFractionDigits(decimals));
+    }
+
+    let digits = format!("{integer}{fraction:0<width$}", width = decimals as usize);
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(U256::zero());
+    }
+
+    ethers_core::types::U256::from_dec_str(digits)
+        .map(Into::into)
+        .map_err(|_| AmountParseError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_format_amounts() {
+        assert_eq!(format_amount(&U256::zero(), 0), "0");
+        assert_eq!(format_amount(&U256::zero(), 18), "0");
+        assert_eq!(format_amount(&U256::from(42u64), 0), "42");
+        assert_eq!(format_amount(&U256::from(1u64), 18), "0.000000000000000001");
+        assert_eq!(
+            format_amount(&U256::from(1_500_000_000_000_000_000u64), 18),
+            "1.5"
+        );
+        assert_eq!(format_amount(&U256::from(10u64.pow(18)), 18), "1");
+        assert_eq!(
+            format_amount(&U256::from(1u64), 30),
+            "0.000000000000000000000000000001"
+        );
+        assert_eq!(
+            format_amount(&U256::max_value(), 30),
+            "115792089237316195423570985008687907853269984665.640564039457584007913129639935"
+        );
+        assert_eq!(format_nat_amount(&Nat::from(123_450_000u64), 8), "1.2345");
+    }
+
+    #[test]
+    fn should_parse_amounts() {
+        assert_eq!(parse_amount("0", 0), Ok(U256::zero()));
+        assert_eq!(parse_amount("0.000", 18), Ok(U256::zero()));
+        assert_eq!(parse_amount("42", 0), Ok(U256::from(42u64)));
+        assert_eq!(parse_amount("0042", 0), Ok(U256::from(42u64)));
+        assert_eq!(
+            parse_amount("1.5", 18),
+            Ok(U256::from(1_500_000_000_000_000_000u64))
+        );
+        assert_eq!(parse_amount("1.50", 1), Ok(U256::from(15u64)));
+        assert_eq!(
+            parse_amount("0.000000000000000000000000000001", 30),
+            Ok(U256::from(1u64))
+        );
+
+        for (amount, decimals) in [(U256::max_value(), 0), (U256::max_value(), 30)] {
+            let formatted = format_amount(&amount, decimals);
+            assert_eq!(parse_amount(&formatted, decimals), Ok(amount));
+        }
+    }
+
+    #[test]
+    fn should_reject_invalid_amounts() {
+        assert_eq!(parse_amount("", 18), Err(AmountParseError::Empty));
+        assert_eq!(parse_amount(".5", 18), Err(AmountParseError::Empty));
+        assert_eq!(parse_amount("1.", 18), Err(AmountParseError::Empty));
+        assert_eq!(
+            parse_amount("1,5", 18),
+            Err(AmountParseError::InvalidCharacter(','))
+        );
+        assert_eq!(
+            parse_amount("-1", 18),
+            Err(AmountParseError::InvalidCharacter('-'))
+        );
+        assert_eq!(
+            parse_amount("1.2.3", 18),
+            Err(AmountParseError::InvalidCharacter('.'))
+        );
+        assert_eq!(
+            parse_amount("0.01", 1),
+            Err(AmountParseError::TooManyFractionDigits(1))
+        );
+        assert_eq!(
+            parse_amount("1.5", 0),
+            Err(AmountParseError::TooManyFractionDigits(0))
+        );
+
+        let max = format_amount(&U256::max_value(), 0);
+        assert_eq!(parse_amount(&max, 1), Err(AmountParseError::Overflow));
+        assert_eq!(
+            parse_amount(&format!("{max}0"), 0),
+            Err(AmountParseError::Overflow)
+        );
+    }
+}
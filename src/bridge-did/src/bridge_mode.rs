@@ -0,0 +1,63 @@
+//! Mode of the bridge canister. A replica canister doesn't move funds, but follows the
+//! operation blocks log of the primary canister, so it can take over if the primary fails.
+
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+use crate::operation_block::OperationBlock;
+
+/// Mode of the bridge canister.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum BridgeMode {
+    /// The canister processes the operations.
+    #[default]
+    Primary,
+    /// The canister doesn't process the operations, and imports them from the `primary`
+    /// canister instead.
+    Replica { primary: Principal },
+}
+
+impl BridgeMode {
+    /// Returns the primary canister, if the canister is a replica.
+    pub fn primary(&self) -> Option<Principal> {
+        match self {
+            Self::Primary => None,
+            Self::Replica { primary } => Some(*primary),
+        }
+    }
+
+    /// Checks if the canister is a replica.
+    pub fn is_replica(&self) -> bool {
+        matches!(self, Self::Replica { .. })
+    }
+}
+
+/// Operation blocks, exported by the primary canister to its replicas.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct OperationBlocksExport {
+    /// Length of the operation blocks log of the exporting canister.
+    pub log_length: u64,
+    /// Blocks with their indexes, ordered by index.
+    pub blocks: Vec<(u64, OperationBlock)>,
+}
+
+/// State of the replica synchronization with the primary canister.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct ReplicaSyncStatus {
+    pub primary: Principal,
+    /// Number of the operation blocks in the replica log.
+    pub synced_blocks: u64,
+    /// Length of the primary log, seen by the latest successful sync.
+    pub primary_blocks: Option<u64>,
+    /// IC timestamp in nanoseconds of the latest successful sync.
+    pub last_sync_at: Option<u64>,
+    /// Error of the latest sync, if it failed.
+    pub last_error: Option<String>,
+}
+
+impl ReplicaSyncStatus {
+    /// Checks if all the blocks, seen in the primary log, are imported.
+    pub fn is_synced(&self) -> bool {
+        self.primary_blocks == Some(self.synced_blocks)
+    }
+}
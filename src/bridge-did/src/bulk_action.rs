@@ -0,0 +1,88 @@
+//! Operator actions, applied at once to all the incomplete operations, matching a filter.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use ic_stable_structures::{Bound, Storable};
+use serde::Serialize;
+
+use crate::concurrency::OperationDirection;
+use crate::id256::Id256;
+use crate::op_id::OperationId;
+use crate::operation_status::OperationStateClass;
+
+/// Maximum number of operations, processed by a single bulk action call.
+pub const MAX_BULK_ACTION_OPERATIONS: u64 = 100;
+
+/// Maximum length of the operator note in bytes.
+pub const MAX_OPERATOR_NOTE_LENGTH: usize = 16 * 1024;
+
+/// Action of the operator, applied to the operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum BulkAction {
+    /// Schedules the operation progress immediately. Parked operations are unparked.
+    Retry,
+    /// Moves the operation to its cancelled state, if the operation supports cancellation
+    /// at its current step.
+    Cancel,
+    /// Lets the operation, deferred by the direction concurrency limit, progress
+    /// regardless of the limit.
+    Approve,
+}
+
+/// Filter of the incomplete operations. Operations should match all the set fields.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct OperationFilter {
+    pub state_class: Option<OperationStateClass>,
+    /// Token of the operation. Operations without a known token never match.
+    pub token: Option<Id256>,
+    pub direction: Option<OperationDirection>,
+    /// Minimal time in seconds passed since the operation creation.
+    pub min_age_secs: Option<u64>,
+    /// Maximal time in seconds passed since the operation creation.
+    pub max_age_secs: Option<u64>,
+}
+
+/// Outcome of the bulk action for a single operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum BulkActionOutcome {
+    Applied,
+    /// The action is not applicable to the operation at its current step.
+    Skipped(String),
+}
+
+/// Result of a single bulk action call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct BulkActionResult {
+    /// Outcomes of the processed operations, ordered by id.
+    pub outcomes: Vec<(OperationId, BulkActionOutcome)>,
+    /// If some matching operations are not processed because of the per-call limit,
+    /// id to pass to the next call to process the rest.
+    pub continuation: Option<OperationId>,
+}
+
+/// Record of the bulk action, performed by the operator, or of the operator note.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct OperatorAuditEntry {
+    pub id: u64,
+    pub timestamp: u64,
+    pub caller: Principal,
+    /// `None` for the operator notes and the user data erasures.
+    pub action: Option<BulkAction>,
+    pub filter: OperationFilter,
+    pub outcomes: Vec<(OperationId, BulkActionOutcome)>,
+    /// Free-form note of the operator, e.g. the configuration changes of the canister upgrade.
+    pub note: Option<String>,
+}
+
+impl Storable for OperatorAuditEntry {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode operator audit entry"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode operator audit entry")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -0,0 +1,55 @@
+//! Write-ahead records of the ICRC deposit burns, which keep track of the burnt tokens
+//! if the bridge canister traps after the ledger call.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Nat, Principal};
+use ic_stable_structures::{Bound, Storable};
+use serde::Serialize;
+
+/// Burn of the deposit tokens, recorded before the ledger call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct BurnIntent {
+    pub token: Principal,
+    pub amount: Nat,
+    /// `created_at_time` of the ledger transfer. The ledger rejects the repeated transfer
+    /// with the same arguments as a duplicate, so the burn is never executed twice.
+    pub created_at_time: u64,
+    pub state: BurnIntentState,
+}
+
+/// State of the deposit burn.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum BurnIntentState {
+    /// Ledger call is in progress, or its result is lost because of a trap.
+    Burning,
+    /// Tokens are burnt in the ledger block with the given index.
+    Burned { block_index: Nat },
+    /// The burn can't be reconciled with the ledger anymore, e.g. because the ledger
+    /// deduplication window is passed. The deposit should be reviewed by the operator.
+    Unresolved { reason: String },
+}
+
+impl BurnIntent {
+    /// Creates a new intent in the `Burning` state.
+    pub fn new(token: Principal, amount: Nat, created_at_time: u64) -> Self {
+        Self {
+            token,
+            amount,
+            created_at_time,
+            state: BurnIntentState::Burning,
+        }
+    }
+}
+
+impl Storable for BurnIntent {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode burn intent"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode burn intent")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -0,0 +1,50 @@
+//! Types to limit the number of the concurrently progressing operations per direction,
+//! which protects the downstream canisters from bursts of calls.
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+/// Direction of the bridge operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, CandidType)]
+pub enum OperationDirection {
+    /// Tokens are moved from the base chain to EVM.
+    Deposit,
+    /// Tokens are moved from EVM to the base chain.
+    Withdrawal,
+}
+
+impl OperationDirection {
+    pub const ALL: [Self; 2] = [Self::Deposit, Self::Withdrawal];
+}
+
+/// Max number of the concurrently progressing operations per direction.
+///
+/// `None` means no limit. Zero limit pauses the progress of the direction operations.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct OperationConcurrencyLimits {
+    pub deposit: Option<u32>,
+    pub withdrawal: Option<u32>,
+}
+
+impl OperationConcurrencyLimits {
+    /// Limits, which pause the progress of all the directions.
+    pub const PAUSED: Self = Self {
+        deposit: Some(0),
+        withdrawal: Some(0),
+    };
+
+    /// Returns the limit of the given direction.
+    pub fn limit(&self, direction: OperationDirection) -> Option<u32> {
+        match direction {
+            OperationDirection::Deposit => self.deposit,
+            OperationDirection::Withdrawal => self.withdrawal,
+        }
+    }
+}
+
+/// Number of the operations, deferred because their direction limit is reached.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct DeferredOperationsStats {
+    pub deposit: u64,
+    pub withdrawal: u64,
+}
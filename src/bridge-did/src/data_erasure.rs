@@ -0,0 +1,74 @@
+//! Erasure of the user personal data linkage from the historical operations.
+
+use candid::{CandidType, Principal};
+use did::H160;
+use ethers_core::utils::keccak256;
+use serde::{Deserialize, Serialize};
+
+use crate::op_id::OperationId;
+
+/// Identity of the user, whose data is erased.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum UserIdentity {
+    Principal(Principal),
+    Address(H160),
+}
+
+impl UserIdentity {
+    /// Returns the pseudonym of the identity: the address, taken from the hash
+    /// of the `salt` and the identity bytes.
+    ///
+    /// The salt is never stored, so the pseudonym can't be linked back to the identity.
+    pub fn pseudonym(&self, salt: &[u8]) -> H160 {
+        let (tag, bytes) = match self {
+            Self::Principal(principal) => (0u8, principal.as_slice()),
+            Self::Address(address) => (1u8, address.0.as_bytes()),
+        };
+
+        let mut buf = Vec::with_capacity(salt.len() + 1 + bytes.len());
+        buf.extend_from_slice(salt);
+        buf.push(tag);
+        buf.extend_from_slice(bytes);
+
+        H160::from_slice(&keccak256(&buf)[12..])
+    }
+}
+
+/// Marker of the operation, which wallet address is replaced with the pseudonym
+/// and which memo is removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct AnonymizationMarker {
+    /// Pseudonym, stored instead of the wallet address of the operation.
+    pub pseudonym: H160,
+    /// IC time of the anonymization.
+    pub timestamp: u64,
+    /// Id of the operator audit log entry of the anonymization.
+    pub audit_entry: u64,
+}
+
+/// Result of the user data erasure.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct UserDataErasure {
+    /// Pseudonym, replacing the identity in the anonymized operations.
+    pub pseudonym: H160,
+    /// Anonymized operations.
+    pub operations: Vec<OperationId>,
+    /// Id of the operator audit log entry of the erasure.
+    pub audit_entry: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pseudonym_depends_on_salt_and_identity() {
+        let address = UserIdentity::Address(H160::from_slice(&[1; 20]));
+        let principal = UserIdentity::Principal(Principal::from_slice(&[1; 20]));
+
+        assert_eq!(address.pseudonym(&[1; 32]), address.pseudonym(&[1; 32]));
+        assert_ne!(address.pseudonym(&[1; 32]), address.pseudonym(&[2; 32]));
+        assert_ne!(address.pseudonym(&[1; 32]), principal.pseudonym(&[1; 32]));
+        assert_ne!(address.pseudonym(&[1; 32]), H160::from_slice(&[1; 20]));
+    }
+}
@@ -0,0 +1,138 @@
+//! Events, rejected by the bridge canister event validation or decoding.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode};
+use ic_stable_structures::{Bound, Storable};
+use serde::Serialize;
+
+use crate::error::{BTFResult, Error};
+use crate::event_data::{BurntEventData, NotifyMinterEventData, RawEventLog};
+use crate::versioned::Versioned;
+
+/// Data of the rejected event, stored as received.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum DeadLetterEventData {
+    /// Burnt event, rejected by the strict event validation.
+    Burnt(BurntEventData),
+    /// Event log with a topic, which has no registered decoder.
+    Unknown(RawEventLog),
+    /// Minter notification with a payload, which can't be decoded.
+    Notify(NotifyMinterEventData),
+}
+
+impl DeadLetterEventData {
+    /// Returns the burnt event data, if the rejected event is a burnt event.
+    pub fn burnt(&self) -> Option<&BurntEventData> {
+        match self {
+            Self::Burnt(event) => Some(event),
+            Self::Unknown(_) | Self::Notify(_) => None,
+        }
+    }
+}
+
+impl From<BurntEventData> for DeadLetterEventData {
+    fn from(event: BurntEventData) -> Self {
+        Self::Burnt(event)
+    }
+}
+
+impl From<RawEventLog> for DeadLetterEventData {
+    fn from(log: RawEventLog) -> Self {
+        Self::Unknown(log)
+    }
+}
+
+impl From<NotifyMinterEventData> for DeadLetterEventData {
+    fn from(event: NotifyMinterEventData) -> Self {
+        Self::Notify(event)
+    }
+}
+
+/// Event, rejected by the bridge canister. The event data is stored as received.
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct DeadLetterEvent {
+    pub id: u64,
+    pub event: DeadLetterEventData,
+    /// Reason of the event rejection.
+    pub reason: String,
+    pub timestamp: u64,
+}
+
+/// Dead letter event of the version 1, which could be only a burnt event.
+#[derive(Debug, Clone, Deserialize, CandidType)]
+struct DeadLetterEventV1 {
+    id: u64,
+    event: BurntEventData,
+    reason: String,
+    timestamp: u64,
+}
+
+impl From<DeadLetterEventV1> for DeadLetterEvent {
+    fn from(v1: DeadLetterEventV1) -> Self {
+        Self {
+            id: v1.id,
+            event: DeadLetterEventData::Burnt(v1.event),
+            reason: v1.reason,
+            timestamp: v1.timestamp,
+        }
+    }
+}
+
+impl Versioned for DeadLetterEvent {
+    const CURRENT_VERSION: u16 = 2;
+
+    fn encode_payload(&self) -> Vec<u8> {
+        Encode!(self).expect("failed to encode dead letter event")
+    }
+
+    fn decode_payload(version: u16, payload: &[u8]) -> BTFResult<Self> {
+        match version {
+            1 => Decode!(payload, DeadLetterEventV1)
+                .map(Into::into)
+                .map_err(|e| Error::Serialization(e.to_string())),
+            2 => Decode!(payload, Self).map_err(|e| Error::Serialization(e.to_string())),
+            _ => Err(Error::Serialization(format!(
+                "unknown dead letter event version {version}"
+            ))),
+        }
+    }
+}
+
+impl Storable for DeadLetterEvent {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        self.to_versioned_bytes().into()
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Self::from_versioned_bytes(&bytes).expect("failed to decode dead letter event")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioned;
+
+    #[test]
+    fn v1_dead_letter_event_is_decoded_as_burnt() {
+        let v1 = DeadLetterEventV1 {
+            id: 3,
+            event: BurntEventData {
+                decimals: 200,
+                ..Default::default()
+            },
+            reason: "bad decimals".into(),
+            timestamp: 10,
+        };
+        let bytes = versioned::with_version(1, &Encode!(&v1).unwrap());
+
+        let decoded = DeadLetterEvent::from_versioned_bytes(&bytes).unwrap();
+        assert_eq!(decoded.id, 3);
+        assert_eq!(decoded.event.burnt().unwrap().decimals, 200);
+        assert_eq!(decoded.reason, "bad decimals");
+        assert_eq!(decoded.timestamp, 10);
+    }
+}
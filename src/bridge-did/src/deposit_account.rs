@@ -0,0 +1,33 @@
+use candid::{CandidType, Nat};
+use did::U256;
+use ic_exports::icrc_types::icrc1::account::Account;
+use serde::{Deserialize, Serialize};
+
+/// Bridge account, which deposit senders approve to spend their ICRC-2 tokens,
+/// with the deposit limits of the token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct DepositAccountInfo {
+    /// Spender of the `icrc2_transfer_from` call, which burns the deposited tokens:
+    /// the bridge canister with the subaccount, derived from the deposit recipient.
+    pub spender: Account,
+
+    /// Fee of the token ledger. It's charged from the sender on top of the deposit amount,
+    /// so the approved allowance should include it.
+    pub ledger_fee: Nat,
+
+    /// Minimal deposit amount in the token base units.
+    pub min_amount: U256,
+}
+
+/// Remaining allowance of a deposit sender for the bridge spender account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct DepositAllowance {
+    /// Spender account and deposit limits.
+    pub info: DepositAccountInfo,
+
+    /// Remaining allowance of the spender.
+    pub allowance: Nat,
+
+    /// Expiration time of the allowance, if any.
+    pub expires_at: Option<u64>,
+}
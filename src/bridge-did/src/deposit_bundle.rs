@@ -0,0 +1,195 @@
+//! Deposit bundles: deposits of several ICRC tokens, which wrapped tokens are minted
+//! atomically by a single `batchMint` transaction.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use ic_stable_structures::{Bound, Storable};
+use serde::Serialize;
+
+use crate::op_id::OperationId;
+
+/// Id of the deposit bundle.
+pub type BundleId = u64;
+
+/// Deposit bundle, stored by the bridge canister.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct DepositBundle {
+    /// Sender of all the bundle deposits.
+    pub sender: Principal,
+    /// Deposits of the bundle, in the order of the requested burns.
+    pub items: Vec<BundleItem>,
+    pub created_at: u64,
+    /// Reason of the bundle failure. Tokens of the burnt deposits of the failed bundle
+    /// are refunded to the sender.
+    pub failure: Option<String>,
+}
+
+/// Deposit of the bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct BundleItem {
+    pub operation_id: OperationId,
+    /// Subaccount the deposit tokens are burnt from, and refunded to.
+    pub from_subaccount: Option<[u8; 32]>,
+}
+
+impl DepositBundle {
+    /// Returns ids of the bundle deposit operations.
+    pub fn operation_ids(&self) -> Vec<OperationId> {
+        self.items.iter().map(|item| item.operation_id).collect()
+    }
+}
+
+impl Storable for DepositBundle {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode deposit bundle"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode deposit bundle")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Progress of a bundle deposit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum BundleItemStatus {
+    /// Tokens are not burnt yet.
+    Burning,
+    /// Tokens are burnt, the mint order waits for the other deposits of the bundle.
+    Burnt,
+    /// Mint order is signed, the `batchMint` transaction is not confirmed yet.
+    Minting,
+    /// Wrapped tokens are minted.
+    Minted,
+    /// Deposit is aborted without burning the tokens.
+    Aborted,
+    /// Burnt tokens are being refunded to the sender.
+    Refunding,
+    /// Burnt tokens are refunded to the sender.
+    Refunded,
+    /// Refund of the burnt tokens failed. The deposit should be reviewed by the operator.
+    RefundFailed,
+}
+
+impl BundleItemStatus {
+    /// Checks if the bundle deposit will not progress anymore.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            Self::Minted | Self::Aborted | Self::Refunded | Self::RefundFailed
+        )
+    }
+}
+
+/// Progress of the deposit bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum DepositBundleState {
+    /// Tokens of the bundle deposits are being burnt.
+    Burning,
+    /// Mint orders of the bundle are signed, the `batchMint` transaction is not confirmed yet.
+    Minting,
+    /// Wrapped tokens of all the bundle deposits are minted.
+    Completed,
+    /// Bundle failed, burnt tokens are being refunded.
+    Refunding,
+    /// Bundle failed, all the burnt tokens are refunded or their refund failed.
+    Refunded,
+}
+
+/// Bundle deposit with its progress.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct BundleItemInfo {
+    pub operation_id: OperationId,
+    pub status: BundleItemStatus,
+}
+
+/// Progress of the deposit bundle and its deposits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct DepositBundleStatus {
+    pub id: BundleId,
+    pub state: DepositBundleState,
+    pub items: Vec<BundleItemInfo>,
+    pub failure: Option<String>,
+}
+
+impl DepositBundleStatus {
+    /// Creates the status of the bundle with the given deposits progress.
+    pub fn new(id: BundleId, failure: Option<String>, items: Vec<BundleItemInfo>) -> Self {
+        let state = if failure.is_some() {
+            match items.iter().all(|item| item.status.is_final()) {
+                true => DepositBundleState::Refunded,
+                false => DepositBundleState::Refunding,
+            }
+        } else if items
+            .iter()
+            .all(|item| item.status == BundleItemStatus::Minted)
+        {
+            DepositBundleState::Completed
+        } else if items.iter().all(|item| {
+            matches!(
+                item.status,
+                BundleItemStatus::Minting | BundleItemStatus::Minted
+            )
+        }) {
+            DepositBundleState::Minting
+        } else {
+            DepositBundleState::Burning
+        };
+
+        Self {
+            id,
+            state,
+            items,
+            failure,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(failure: Option<&str>, statuses: &[BundleItemStatus]) -> DepositBundleState {
+        let items = statuses
+            .iter()
+            .enumerate()
+            .map(|(idx, status)| BundleItemInfo {
+                operation_id: OperationId::new(idx as _),
+                status: *status,
+            })
+            .collect();
+        DepositBundleStatus::new(0, failure.map(Into::into), items).state
+    }
+
+    #[test]
+    fn bundle_state_follows_its_deposits() {
+        use BundleItemStatus::*;
+
+        assert_eq!(status(None, &[Burnt, Burning]), DepositBundleState::Burning);
+        assert_eq!(status(None, &[Burnt, Burnt]), DepositBundleState::Burning);
+        assert_eq!(
+            status(None, &[Minting, Minted]),
+            DepositBundleState::Minting
+        );
+        assert_eq!(
+            status(None, &[Minted, Minted]),
+            DepositBundleState::Completed
+        );
+
+        let failure = Some("burn failed");
+        assert_eq!(
+            status(failure, &[Aborted, Refunding]),
+            DepositBundleState::Refunding
+        );
+        assert_eq!(
+            status(failure, &[Aborted, Burning]),
+            DepositBundleState::Refunding
+        );
+        assert_eq!(
+            status(failure, &[Aborted, Refunded, RefundFailed]),
+            DepositBundleState::Refunded
+        );
+    }
+}
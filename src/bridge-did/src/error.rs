@@ -1,4 +1,5 @@
-use candid::CandidType;
+use candid::{CandidType, Principal};
+use did::H160;
 use eth_signer::sign_strategy::TransactionSignerError;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -33,12 +34,81 @@ pub enum Error {
     #[error("operation cannot progress: {0}")]
     CannotProgress(String),
 
+    #[error("operation#{0} has not released its dependents yet")]
+    DependencyNotReleased(OperationId),
+
     #[error("unexpected anonymous principal")]
     AnonymousPrincipal,
 
+    #[error("token {0} is not allowed to be bridged")]
+    TokenNotAllowed(Principal),
+
     #[error("EVM request failed: {0}")]
     EvmRequestFailed(String),
 
+    #[error("parked outbox message#{0} not found")]
+    OutboxMessageNotFound(u64),
+
+    #[error("operation#{0} has no mint order to be sent by the user")]
+    MintOrderNotAwaitingUser(OperationId),
+
+    #[error("invalid burn amount: {0}")]
+    InvalidBurnAmount(String),
+
+    #[error("invalid bridge preferences: {0}")]
+    InvalidBridgePreferences(String),
+
+    #[error("bridge preferences limit of {0} users is reached")]
+    BridgePreferencesLimitReached(u64),
+
+    #[error("decimals of token {0} are changed, bridging is held until the change is resolved")]
+    TokenDecimalsChanged(Principal),
+
+    #[error("decimals change of token {0} is not detected")]
+    DecimalsChangeNotDetected(Principal),
+
+    #[error("wrapped token {0} is retired")]
+    WrappedTokenRetired(H160),
+
+    #[error("invalid burn request: {0}")]
+    InvalidBurnRequest(String),
+
+    #[error("invalid burn operation: {0}")]
+    InvalidBurnOperation(String),
+
+    #[error("burn batch exceeds the limit of {0} burns")]
+    BurnBatchTooLarge(u64),
+
+    #[error("operations status batch exceeds the limit of {0} operations")]
+    OperationsBatchTooLarge(u64),
+
+    #[error("invalid ERC-20 permit: {0}")]
+    InvalidPermit(String),
+
+    #[error("wrapped token {0} doesn't support ERC-20 permit")]
+    PermitNotSupported(H160),
+
+    #[error("operator note exceeds the limit of {0} bytes")]
+    OperatorNoteTooLong(u64),
+
+    #[error("the method is disabled in the replica mode")]
+    ReplicaMode,
+
+    #[error("cannot promote the replica: {0}")]
+    PromotionRejected(String),
+
+    #[error("token ledger doesn't support the required standards: {missing:?}")]
+    UnsupportedLedger { missing: Vec<String> },
+
+    #[error("user data can't be erased while operations {0:?} are in progress")]
+    UserDataErasurePending(Vec<OperationId>),
+
+    #[error("failed to generate random bytes: {0}")]
+    Randomness(String),
+
+    #[error("nonce offset exceeds the max nonce {0}")]
+    NonceOffsetTooLarge(u64),
+
     #[error("generic error: code=={code}, message=`{msg}`")]
     Custom { code: u32, msg: String },
 }
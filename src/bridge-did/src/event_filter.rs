@@ -0,0 +1,74 @@
+//! Filter of the BTF bridge event logs by an indexed event parameter.
+
+use candid::CandidType;
+use did::H256;
+use serde::{Deserialize, Serialize};
+
+/// Max number of the topic values, accepted by the filter.
+pub const MAX_EVENT_TOPIC_FILTER_VALUES: usize = 16;
+
+/// Filter of the collected event logs by the topic of an indexed event parameter, e.g.
+/// the minter address or the bridge id in a BTF bridge contract shared by several bridges.
+///
+/// The filter is added to the `eth_getLogs` request, so the EVM node returns only
+/// the matching logs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct EventTopicFilter {
+    /// Position of the topic in the log topics, from 1 to 3.
+    /// Topic 0 is the event signature hash.
+    pub position: u8,
+    /// A log matches the filter if its topic is equal to any of the values.
+    pub values: Vec<H256>,
+}
+
+impl EventTopicFilter {
+    /// Checks that the position is an indexed parameter topic, and the number of the values
+    /// is within `1..=MAX_EVENT_TOPIC_FILTER_VALUES`.
+    pub fn is_valid(&self) -> bool {
+        (1..=3).contains(&self.position)
+            && !self.values.is_empty()
+            && self.values.len() <= MAX_EVENT_TOPIC_FILTER_VALUES
+    }
+
+    /// Checks if the log with the given topics matches the filter.
+    pub fn matches(&self, topics: &[H256]) -> bool {
+        topics
+            .get(self.position as usize)
+            .is_some_and(|topic| self.values.contains(topic))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(position: u8, values: Vec<H256>) -> EventTopicFilter {
+        EventTopicFilter { position, values }
+    }
+
+    #[test]
+    fn filter_validation() {
+        let value = H256::from_slice(&[1; 32]);
+        assert!(filter(1, vec![value.clone()]).is_valid());
+        assert!(filter(3, vec![value.clone(); MAX_EVENT_TOPIC_FILTER_VALUES]).is_valid());
+
+        assert!(!filter(0, vec![value.clone()]).is_valid());
+        assert!(!filter(4, vec![value.clone()]).is_valid());
+        assert!(!filter(1, vec![]).is_valid());
+        assert!(!filter(1, vec![value; MAX_EVENT_TOPIC_FILTER_VALUES + 1]).is_valid());
+    }
+
+    #[test]
+    fn filter_matches_topic_at_position() {
+        let signature = H256::from_slice(&[9; 32]);
+        let first = H256::from_slice(&[1; 32]);
+        let second = H256::from_slice(&[2; 32]);
+        let filter = filter(1, vec![first.clone(), second.clone()]);
+
+        assert!(filter.matches(&[signature.clone(), first.clone()]));
+        assert!(filter.matches(&[signature.clone(), second.clone(), first.clone()]));
+        assert!(!filter.matches(&[signature.clone(), H256::from_slice(&[3; 32])]));
+        assert!(!filter.matches(&[first.clone(), signature.clone(), second]));
+        assert!(!filter.matches(&[signature]));
+    }
+}
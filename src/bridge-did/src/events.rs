@@ -1,12 +1,10 @@
 use std::fmt::{Display, Formatter};
 
 use alloy_sol_types::sol;
-use candid::{CandidType, Decode};
+use candid::CandidType;
 use serde::{Deserialize, Serialize};
 use BTFBridge::{BurnTokenEvent, MintTokenEvent, NotifyMinterEvent};
 
-use crate::error::{BTFResult, Error};
-use crate::op_id::OperationId;
 use crate::operation_log::Memo;
 
 sol! {
@@ -16,7 +14,7 @@ sol! {
 }
 
 /// Emitted when token is burnt by BTFBridge.
-#[derive(Debug, Default, Clone, CandidType, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, CandidType, Serialize, Deserialize, PartialEq, Eq)]
 pub struct BurntEventData {
     pub sender: did::H160,
     pub amount: did::U256,
@@ -146,21 +144,6 @@ impl NotifyMinterEventData {
             None
         }
     }
-
-    /// Tries to decode the notification into rescheduling operation id.
-    pub fn try_decode_reschedule_operation_id(&self) -> BTFResult<OperationId> {
-        if self.notification_type != MinterNotificationType::RescheduleOperation {
-            return Err(Error::Serialization(format!(
-                "expected MinterNotificationType::RescheduleOperation, got {:?}",
-                self.notification_type,
-            )));
-        }
-
-        let decoded = Decode!(&self.user_data, OperationId).map_err(|e| {
-            Error::Serialization(format!("failed to decode reschedule operation ID: {e}"))
-        })?;
-        Ok(decoded)
-    }
 }
 
 impl From<NotifyMinterEvent> for NotifyMinterEventData {
@@ -173,3 +156,10 @@ impl From<NotifyMinterEvent> for NotifyMinterEventData {
         }
     }
 }
+
+/// Log of the event, which has no registered decoder. Stored as received.
+#[derive(Debug, Default, PartialEq, Eq, Clone, CandidType, Serialize, Deserialize)]
+pub struct RawEventLog {
+    pub topics: Vec<did::H256>,
+    pub data: Vec<u8>,
+}
@@ -0,0 +1,31 @@
+use core::fmt;
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+/// Defines which EVM blocks are considered final by the bridge.
+///
+/// Events are collected only from the blocks up to the last final one.
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, CandidType, PartialEq, Eq)]
+pub enum EvmFinality {
+    /// The latest block of the chain.
+    #[default]
+    Latest,
+    /// The block returned by the `safe` block tag.
+    Safe,
+    /// The block returned by the `finalized` block tag.
+    Finalized,
+    /// The block which has at least the given number of confirmations.
+    Confirmations(u64),
+}
+
+impl fmt::Display for EvmFinality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Latest => write!(f, "latest"),
+            Self::Safe => write!(f, "safe"),
+            Self::Finalized => write!(f, "finalized"),
+            Self::Confirmations(depth) => write!(f, "latest - {depth}"),
+        }
+    }
+}
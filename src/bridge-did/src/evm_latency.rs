@@ -0,0 +1,25 @@
+//! Types to monitor the response time of the EVM node the bridge canister works with.
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+/// Round-trip time of the EVM node requests, calculated from the latest samples.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct LatencyStats {
+    /// Number of the samples the percentiles are calculated from.
+    pub samples_count: u64,
+    /// Percentiles of the latency in nanoseconds. Zero if there are no samples.
+    pub p50_ns: u64,
+    pub p95_ns: u64,
+    pub p99_ns: u64,
+    /// If `p95_ns` exceeds this value, a warning is logged.
+    pub max_acceptable_latency_ms: Option<u64>,
+}
+
+impl LatencyStats {
+    /// Checks if the 95th percentile of the latency exceeds the acceptable value.
+    pub fn is_degraded(&self) -> bool {
+        self.max_acceptable_latency_ms
+            .is_some_and(|max_ms| self.p95_ns > max_ms.saturating_mul(1_000_000))
+    }
+}
@@ -37,6 +37,28 @@ impl Display for EvmLink {
     }
 }
 
+/// JSON-RPC method of the Flashbots-style private transaction relays.
+pub const DEFAULT_PRIVATE_TX_METHOD: &str = "eth_sendPrivateTransaction";
+
+/// Relay, which submits transactions to the block builders, bypassing the public mempool.
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType, PartialEq, Eq)]
+pub struct PrivateTxRelay {
+    /// Link to the JSON-RPC endpoint of the relay.
+    pub link: EvmLink,
+    /// JSON-RPC method to submit a signed transaction, e.g. `eth_sendPrivateTransaction`.
+    pub method: String,
+}
+
+impl PrivateTxRelay {
+    /// Creates a relay with the [`DEFAULT_PRIVATE_TX_METHOD`].
+    pub fn new(link: EvmLink) -> Self {
+        Self {
+            link,
+            method: DEFAULT_PRIVATE_TX_METHOD.into(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, CandidType, Serialize, Deserialize, PartialEq, Eq)]
 pub enum EthSepoliaService {
     Alchemy,
@@ -0,0 +1,19 @@
+//! Types to inspect the EVM params the bridge canister used in the past.
+
+use candid::CandidType;
+use did::U256;
+use serde::{Deserialize, Serialize};
+
+/// EVM params of a bridge side at the moment they were changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct EvmParamsSnapshot {
+    /// Sequential number of the snapshot. Stored in the operation log entries of the
+    /// sent mint transactions.
+    pub index: u64,
+    /// IC timestamp in nanoseconds.
+    pub timestamp: u64,
+    pub chain_id: u64,
+    pub next_block: u64,
+    pub nonce: u64,
+    pub gas_price: U256,
+}
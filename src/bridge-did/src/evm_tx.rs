@@ -0,0 +1,20 @@
+//! Transaction requests, which users send to the EVM themselves.
+
+use candid::CandidType;
+use did::{H160, U256};
+use serde::{Deserialize, Serialize};
+
+/// Fields of the EVM transaction, ready to be sent with `eth_sendTransaction`.
+/// The sender and the nonce are populated by the wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct EvmTxRequest {
+    /// Address of the called contract.
+    pub to: H160,
+    pub chain_id: u64,
+    /// ABI encoded call of the contract function.
+    pub data: Vec<u8>,
+    /// Suggested gas limit of the transaction.
+    pub gas_limit: U256,
+    /// Suggested gas price, as known by the bridge canister.
+    pub gas_price: U256,
+}
@@ -0,0 +1,180 @@
+//! Reconciliation of the fee, charged by the BTF bridge for a mint order, with the actual
+//! cost of the mint transaction.
+//!
+//! The BTF bridge charges the fee payer of a mint order with an estimation, based on the
+//! fixed gas amounts of the `batchMint` call. After the mint transaction is mined, the bridge
+//! canister compares the charged fee with the share of the transaction cost for the order,
+//! and records the difference as an adjustment of the fee payer balance.
+//!
+//! The balance is the accounting of the bridge canister: recording an adjustment doesn't
+//! transfer any funds to or from the fee payer.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode};
+use did::{H160, H256, U256};
+use ic_stable_structures::{Bound, Storable};
+use serde::Serialize;
+
+use crate::op_id::OperationId;
+
+/// Settings of the fee reconciliation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct FeeReconciliationSettings {
+    /// Max extra amount, debited from the fee payer balance if the fee was undercharged.
+    pub max_debit: U256,
+}
+
+/// Adjustment of the fee payer balance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum FeeAdjustmentAmount {
+    /// The fee was overcharged. The amount is owed to the fee payer.
+    Credit(U256),
+    /// The fee was undercharged. The amount is owed by the fee payer.
+    Debit(U256),
+}
+
+impl FeeAdjustmentAmount {
+    /// Computes the adjustment of the `charged` fee to the `actual` cost.
+    /// Debit is capped with `max_debit`.
+    pub fn compute(charged: &U256, actual: &U256, max_debit: &U256) -> Self {
+        if actual > charged {
+            let undercharged: U256 = (actual.0 - charged.0).into();
+            Self::Debit(undercharged.min(max_debit.clone()))
+        } else {
+            Self::Credit((charged.0 - actual.0).into())
+        }
+    }
+}
+
+/// Adjustment of the fee, charged for the mint order of the operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct FeeAdjustment {
+    pub operation_id: OperationId,
+    pub fee_payer: H160,
+    /// Hash of the mint transaction.
+    pub tx_hash: H256,
+    /// Fee, charged by the BTF bridge for the order.
+    pub charged: U256,
+    /// Share of the mint transaction cost for the order.
+    pub actual: U256,
+    pub amount: FeeAdjustmentAmount,
+    /// IC timestamp of the reconciliation.
+    pub timestamp: u64,
+}
+
+impl Storable for FeeAdjustment {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode fee adjustment"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode fee adjustment")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Totals of the fee adjustments of a fee payer.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct FeeAdjustmentBalance {
+    pub credited: U256,
+    pub debited: U256,
+}
+
+impl FeeAdjustmentBalance {
+    /// Adds the adjustment to the totals.
+    pub fn apply(&mut self, amount: &FeeAdjustmentAmount) {
+        match amount {
+            FeeAdjustmentAmount::Credit(value) => {
+                self.credited = self.credited.0.saturating_add(value.0).into()
+            }
+            FeeAdjustmentAmount::Debit(value) => {
+                self.debited = self.debited.0.saturating_add(value.0).into()
+            }
+        }
+    }
+}
+
+impl Storable for FeeAdjustmentBalance {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode fee adjustment balance"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode fee adjustment balance")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Fee adjustments of a fee payer.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct FeePayerAdjustments {
+    pub balance: FeeAdjustmentBalance,
+    /// Adjustments, ordered by operation id.
+    pub adjustments: Vec<FeeAdjustment>,
+}
+
+/// Minted order, which fee is not reconciled yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct PendingFeeReconciliation {
+    pub operation_id: OperationId,
+    /// BTF bridge contract, which minted the order.
+    pub bridge_contract: H160,
+    /// Hash of the mint transaction.
+    pub tx_hash: H256,
+    /// Nonce of the mint order.
+    pub nonce: u32,
+    /// Fee, charged by the BTF bridge for the order.
+    pub charged: U256,
+}
+
+impl Storable for PendingFeeReconciliation {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode pending fee reconciliation"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode pending fee reconciliation")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overcharged_fee_is_credited() {
+        let amount = FeeAdjustmentAmount::compute(&100u64.into(), &70u64.into(), &50u64.into());
+        assert_eq!(amount, FeeAdjustmentAmount::Credit(30u64.into()));
+
+        let amount = FeeAdjustmentAmount::compute(&100u64.into(), &100u64.into(), &50u64.into());
+        assert_eq!(amount, FeeAdjustmentAmount::Credit(0u64.into()));
+    }
+
+    #[test]
+    fn undercharged_fee_is_debited_up_to_cap() {
+        let amount = FeeAdjustmentAmount::compute(&100u64.into(), &120u64.into(), &50u64.into());
+        assert_eq!(amount, FeeAdjustmentAmount::Debit(20u64.into()));
+
+        let amount = FeeAdjustmentAmount::compute(&100u64.into(), &200u64.into(), &50u64.into());
+        assert_eq!(amount, FeeAdjustmentAmount::Debit(50u64.into()));
+
+        let amount = FeeAdjustmentAmount::compute(&100u64.into(), &200u64.into(), &0u64.into());
+        assert_eq!(amount, FeeAdjustmentAmount::Debit(0u64.into()));
+    }
+
+    #[test]
+    fn balance_accumulates_adjustments() {
+        let mut balance = FeeAdjustmentBalance::default();
+        balance.apply(&FeeAdjustmentAmount::Credit(30u64.into()));
+        balance.apply(&FeeAdjustmentAmount::Credit(5u64.into()));
+        balance.apply(&FeeAdjustmentAmount::Debit(20u64.into()));
+
+        assert_eq!(balance.credited, 35u64.into());
+        assert_eq!(balance.debited, 20u64.into());
+    }
+}
@@ -0,0 +1,88 @@
+//! Types to monitor the native token balance of the bridge canister EVM address,
+//! which pays the gas for the sponsored mint transactions.
+
+use candid::CandidType;
+use did::{H160, U256};
+use serde::{Deserialize, Serialize};
+
+/// Balance thresholds of the bridge canister EVM address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct GasBalanceThresholds {
+    /// Balance is `Degraded` below this value.
+    pub low_watermark: U256,
+    /// Balance is `Failing` below this value. Mint transactions sponsorship is disabled
+    /// until the balance is restored above `low_watermark`.
+    pub critical_watermark: U256,
+}
+
+impl GasBalanceThresholds {
+    /// Checks that the critical watermark is not greater than the low watermark.
+    pub fn is_valid(&self) -> bool {
+        self.critical_watermark <= self.low_watermark
+    }
+
+    /// Returns health of the given balance.
+    pub fn health(&self, balance: &U256) -> GasBalanceHealth {
+        if *balance < self.critical_watermark {
+            GasBalanceHealth::Failing
+        } else if *balance < self.low_watermark {
+            GasBalanceHealth::Degraded
+        } else {
+            GasBalanceHealth::Healthy
+        }
+    }
+}
+
+/// Health of the bridge canister EVM address balance.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum GasBalanceHealth {
+    /// Balance was not queried yet, or thresholds are not configured.
+    #[default]
+    Unknown,
+    Healthy,
+    /// Balance is below the low watermark.
+    Degraded,
+    /// Balance is below the critical watermark.
+    Failing,
+}
+
+/// Alert, raised on the balance health change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum GasBalanceAlert {
+    /// Balance dropped below the low watermark.
+    Low,
+    /// Balance dropped below the critical watermark. Sponsorship is disabled.
+    Critical,
+    /// Balance is restored above the low watermark. Sponsorship is enabled.
+    Recovered,
+}
+
+/// Balance of the address at the given time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct GasBalanceRecord {
+    /// IC timestamp in nanoseconds.
+    pub timestamp: u64,
+    pub balance: U256,
+}
+
+/// Alert with the time it was raised at.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct GasBalanceAlertRecord {
+    /// IC timestamp in nanoseconds.
+    pub timestamp: u64,
+    pub alert: GasBalanceAlert,
+}
+
+/// Diagnostic info about the bridge canister EVM address balance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct GasBalanceStatus {
+    /// Monitored address, if known.
+    pub address: Option<H160>,
+    pub health: GasBalanceHealth,
+    /// If false, operations with fee payer fall back to the mint by the user.
+    pub mint_tx_sponsorship_enabled: bool,
+    pub thresholds: Option<GasBalanceThresholds>,
+    /// Latest balance records, oldest first.
+    pub history: Vec<GasBalanceRecord>,
+    pub last_alert: Option<GasBalanceAlertRecord>,
+}
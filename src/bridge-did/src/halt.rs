@@ -0,0 +1,14 @@
+//! Types of the bridge halt, which stops the progress of all the directional operations
+//! until the operator resumes the bridge.
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+/// Reason and time of the bridge halt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct BridgeHalt {
+    /// IC timestamp in nanoseconds.
+    pub timestamp: u64,
+    /// Human readable reason of the halt.
+    pub reason: String,
+}
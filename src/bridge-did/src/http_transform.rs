@@ -0,0 +1,99 @@
+//! Settings of the HTTP outcall response transform.
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+/// Headers, which differ between the replicas and are removed from the HTTP outcall
+/// responses by default.
+pub const DEFAULT_VOLATILE_HEADERS: &[&str] = &[
+    "age",
+    "cf-cache-status",
+    "cf-ray",
+    "date",
+    "etag",
+    "expires",
+    "last-modified",
+    "report-to",
+    "server-timing",
+    "set-cookie",
+    "x-amz-cf-id",
+    "x-amzn-requestid",
+    "x-amzn-trace-id",
+    "x-correlation-id",
+    "x-request-id",
+    "x-served-by",
+    "x-timer",
+    "x-trace-id",
+];
+
+/// Filter of the HTTP outcall response headers. Header names are case insensitive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum HeaderFilter {
+    /// Keeps only the listed headers.
+    Allow(Vec<String>),
+    /// Removes the listed headers and keeps the others.
+    Deny(Vec<String>),
+}
+
+impl HeaderFilter {
+    /// Checks if the header with the given name is kept in the transformed response.
+    pub fn retains(&self, name: &str) -> bool {
+        let listed = |names: &[String]| names.iter().any(|n| n.eq_ignore_ascii_case(name));
+        match self {
+            Self::Allow(names) => listed(names),
+            Self::Deny(names) => !listed(names),
+        }
+    }
+}
+
+impl Default for HeaderFilter {
+    /// Removes the [`DEFAULT_VOLATILE_HEADERS`].
+    fn default() -> Self {
+        Self::Deny(
+            DEFAULT_VOLATILE_HEADERS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        )
+    }
+}
+
+/// Settings of the HTTP outcall response transform, so all the replicas get
+/// the same response and reach consensus.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct HttpTransformSettings {
+    /// Filter of the response headers.
+    pub headers: HeaderFilter,
+    /// If set, the body of the response with a JSON content type is re-encoded
+    /// with the sorted object keys and without whitespaces.
+    pub canonicalize_body: bool,
+}
+
+impl Default for HttpTransformSettings {
+    fn default() -> Self {
+        Self {
+            headers: HeaderFilter::default(),
+            canonicalize_body: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_filter_removes_volatile_headers() {
+        let filter = HeaderFilter::default();
+        assert!(!filter.retains("Date"));
+        assert!(!filter.retains("X-Request-Id"));
+        assert!(filter.retains("Content-Type"));
+    }
+
+    #[test]
+    fn allow_filter_keeps_only_listed_headers() {
+        let filter = HeaderFilter::Allow(vec!["content-type".into()]);
+        assert!(filter.retains("Content-Type"));
+        assert!(!filter.retains("Server"));
+    }
+}
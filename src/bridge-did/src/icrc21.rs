@@ -0,0 +1,214 @@
+//! Types of the ICRC-21 canister call consent messages standard.
+//!
+//! See <https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-21/ICRC-21.md>.
+
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+/// Record of the ICRC-10 `icrc10_supported_standards` response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct StandardRecord {
+    pub name: String,
+    pub url: String,
+}
+
+impl StandardRecord {
+    /// Record of the ICRC-21 standard.
+    pub fn icrc21() -> Self {
+        Self {
+            name: "ICRC-21".into(),
+            url: "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-21/ICRC-21.md".into(),
+        }
+    }
+}
+
+/// Request of a consent message for a canister call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct ConsentMessageRequest {
+    /// Name of the method to be called.
+    pub method: String,
+    /// Candid-encoded arguments of the call.
+    pub arg: Vec<u8>,
+    /// Preferences of the user, which should be respected by the message.
+    pub user_preferences: ConsentMessageSpec,
+}
+
+/// Preferences of the consent message representation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct ConsentMessageSpec {
+    pub metadata: ConsentMessageMetadata,
+    /// Type of the device, which will display the message.
+    pub device_spec: Option<DisplayMessageType>,
+}
+
+/// Metadata of the consent message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct ConsentMessageMetadata {
+    /// BCP-47 language tag.
+    pub language: String,
+    /// Offset of the user timezone from UTC, in minutes.
+    pub utc_offset_minutes: Option<i16>,
+}
+
+/// Type of the device, which will display the message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum DisplayMessageType {
+    /// Device, which can display a text of any length.
+    GenericDisplay,
+    /// Device, which displays the text in pages of fixed size lines.
+    LineDisplay {
+        characters_per_line: u16,
+        lines_per_page: u16,
+    },
+}
+
+/// Consent message, formatted according to the requested device type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum ConsentMessage {
+    GenericDisplayMessage(String),
+    LineDisplayMessage { pages: Vec<LineDisplayPage> },
+}
+
+/// Page of a consent message for a line display.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct LineDisplayPage {
+    pub lines: Vec<String>,
+}
+
+/// Consent message with its metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct ConsentInfo {
+    pub consent_message: ConsentMessage,
+    pub metadata: ConsentMessageMetadata,
+}
+
+/// Details of an ICRC-21 error.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct ErrorInfo {
+    pub description: String,
+}
+
+/// Reason, why a consent message can't be provided.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum Icrc21Error {
+    /// The canister doesn't provide consent messages for the call.
+    UnsupportedCanisterCall(ErrorInfo),
+    /// The consent message can't be created, e.g. because the arguments are invalid.
+    ConsentMessageUnavailable(ErrorInfo),
+    /// The payment for the consent message is insufficient.
+    InsufficientPayment(ErrorInfo),
+    GenericError {
+        error_code: Nat,
+        description: String,
+    },
+}
+
+impl Icrc21Error {
+    /// Creates [`Icrc21Error::UnsupportedCanisterCall`] error.
+    pub fn unsupported_call(description: impl Into<String>) -> Self {
+        Self::UnsupportedCanisterCall(ErrorInfo {
+            description: description.into(),
+        })
+    }
+
+    /// Creates [`Icrc21Error::ConsentMessageUnavailable`] error.
+    pub fn unavailable(description: impl Into<String>) -> Self {
+        Self::ConsentMessageUnavailable(ErrorInfo {
+            description: description.into(),
+        })
+    }
+}
+
+impl ConsentMessage {
+    /// Formats the `text` for the device, specified in `spec`.
+    ///
+    /// For a line display, the text lines are wrapped by words to fit the line width.
+    pub fn format(text: &str, spec: Option<&DisplayMessageType>) -> Self {
+        let Some(&DisplayMessageType::LineDisplay {
+            characters_per_line,
+            lines_per_page,
+        }) = spec
+        else {
+            return Self::GenericDisplayMessage(text.to_string());
+        };
+
+        let width = (characters_per_line as usize).max(1);
+        let lines = text
+            .lines()
+            .flat_map(|line| wrap_line(line, width))
+            .collect::<Vec<_>>();
+        let pages = lines
+            .chunks((lines_per_page as usize).max(1))
+            .map(|lines| LineDisplayPage {
+                lines: lines.to_vec(),
+            })
+            .collect();
+
+        Self::LineDisplayMessage { pages }
+    }
+}
+
+/// Splits the `line` into lines of at most `width` characters, breaking by words if possible.
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut current = String::new();
+    for word in line.split_whitespace() {
+        let chars = word.chars().collect::<Vec<_>>();
+        for part in chars.chunks(width) {
+            let part = part.iter().collect::<String>();
+            let current_len = current.chars().count();
+            if current_len > 0 && current_len + 1 + part.chars().count() > width {
+                lines.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(&part);
+        }
+    }
+
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generic_display_message_is_not_changed() {
+        let text = "first line\nsecond line";
+        assert_eq!(
+            ConsentMessage::format(text, Some(&DisplayMessageType::GenericDisplay)),
+            ConsentMessage::GenericDisplayMessage(text.to_string())
+        );
+        assert_eq!(
+            ConsentMessage::format(text, None),
+            ConsentMessage::GenericDisplayMessage(text.to_string())
+        );
+    }
+
+    #[test]
+    fn line_display_message_is_wrapped_and_paged() {
+        let spec = DisplayMessageType::LineDisplay {
+            characters_per_line: 10,
+            lines_per_page: 2,
+        };
+        let message = ConsentMessage::format("Bridge 100 TKN\n0123456789abc", Some(&spec));
+
+        let ConsentMessage::LineDisplayMessage { pages } = message else {
+            panic!("unexpected message type: {message:?}");
+        };
+        let pages = pages.into_iter().map(|page| page.lines).collect::<Vec<_>>();
+        assert_eq!(
+            pages,
+            vec![
+                vec!["Bridge 100".to_string(), "TKN".to_string()],
+                vec!["0123456789".to_string(), "abc".to_string()],
+            ]
+        );
+    }
+}
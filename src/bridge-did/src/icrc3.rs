@@ -0,0 +1,149 @@
+//! Types of the ICRC-3 block log standard.
+//!
+//! See <https://github.com/dfinity/ICRC-1/blob/main/standards/ICRC-3/README.md>.
+
+use bitcoin::hashes::{sha256, Hash};
+use candid::{CandidType, Int, Nat};
+use serde::{Deserialize, Serialize};
+
+/// Generic value of the ICRC-3 blocks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum Icrc3Value {
+    Blob(Vec<u8>),
+    Text(String),
+    Nat(Nat),
+    Int(Int),
+    Array(Vec<Icrc3Value>),
+    Map(Vec<(String, Icrc3Value)>),
+}
+
+impl Icrc3Value {
+    /// Returns the representation-independent hash of the value, as defined by ICRC-3.
+    pub fn hash(&self) -> [u8; 32] {
+        match self {
+            Self::Blob(bytes) => sha256_hash(bytes),
+            Self::Text(text) => sha256_hash(text.as_bytes()),
+            Self::Nat(nat) => {
+                let mut leb128 = vec![];
+                nat.encode(&mut leb128)
+                    .expect("failed to encode nat as leb128");
+                sha256_hash(&leb128)
+            }
+            Self::Int(int) => {
+                let mut sleb128 = vec![];
+                int.encode(&mut sleb128)
+                    .expect("failed to encode int as sleb128");
+                sha256_hash(&sleb128)
+            }
+            Self::Array(values) => {
+                let hashes: Vec<u8> = values.iter().flat_map(|value| value.hash()).collect();
+                sha256_hash(&hashes)
+            }
+            Self::Map(entries) => {
+                let mut hashes: Vec<Vec<u8>> = entries
+                    .iter()
+                    .map(|(key, value)| [sha256_hash(key.as_bytes()), value.hash()].concat())
+                    .collect();
+                hashes.sort();
+                sha256_hash(&hashes.concat())
+            }
+        }
+    }
+}
+
+fn sha256_hash(data: &[u8]) -> [u8; 32] {
+    sha256::Hash::hash(data).to_byte_array()
+}
+
+/// Range of the requested blocks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct GetBlocksArgs {
+    pub start: Nat,
+    pub length: Nat,
+}
+
+/// Block with its index in the log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct BlockWithId {
+    pub id: Nat,
+    pub block: Icrc3Value,
+}
+
+candid::define_function!(pub GetBlocksCallback : (Vec<GetBlocksArgs>) -> (GetBlocksResult) query);
+
+/// Blocks, which are moved to an archive canister.
+#[derive(Debug, Clone, Deserialize, CandidType)]
+pub struct ArchivedBlocks {
+    pub args: Vec<GetBlocksArgs>,
+    pub callback: GetBlocksCallback,
+}
+
+/// Response of the ICRC-3 `get_blocks` request.
+#[derive(Debug, Clone, Deserialize, CandidType)]
+pub struct GetBlocksResult {
+    /// Total number of the blocks in the log.
+    pub log_length: Nat,
+    pub blocks: Vec<BlockWithId>,
+    /// Bridge canisters never archive their blocks, so the list is always empty.
+    pub archived_blocks: Vec<ArchivedBlocks>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors of the ICRC-3 standard.
+
+    #[test]
+    fn primitive_values_hash() {
+        assert_eq!(
+            hex::encode(Icrc3Value::Nat(42u64.into()).hash()),
+            "684888c0ebb17f374298b65ee2807526c066094c701bcc7ebbe1c1095f494fc1"
+        );
+        assert_eq!(
+            hex::encode(Icrc3Value::Text("Hello, World!".into()).hash()),
+            "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f"
+        );
+        assert_eq!(
+            hex::encode(Icrc3Value::Blob(vec![1, 2, 3, 4]).hash()),
+            "9f64a747e1b97f131fabb6b447296c9b6f0201e79fb3c5356e6c77e89b6a806a"
+        );
+    }
+
+    #[test]
+    fn array_hash() {
+        let value = Icrc3Value::Array(vec![
+            Icrc3Value::Nat(3u64.into()),
+            Icrc3Value::Text("foo".into()),
+            Icrc3Value::Blob(vec![5, 6]),
+        ]);
+        assert_eq!(
+            hex::encode(value.hash()),
+            "514a04011caa503990d446b7dec5d79e19c221ae607fb08b2848c67734d468d6"
+        );
+    }
+
+    #[test]
+    fn map_hash_is_independent_of_entries_order() {
+        let from = hex::decode("00abcdef0012340056789a00bcdef000012345678900abcdef01").unwrap();
+        let to = hex::decode("00ab0def0012340056789a00bcdef000012345678900abcdef01").unwrap();
+        let mut entries = vec![
+            ("from".to_string(), Icrc3Value::Blob(from)),
+            ("to".to_string(), Icrc3Value::Blob(to)),
+            ("amount".to_string(), Icrc3Value::Nat(42u64.into())),
+            (
+                "created_at".to_string(),
+                Icrc3Value::Nat(1699218263u64.into()),
+            ),
+            ("memo".to_string(), Icrc3Value::Nat(0u64.into())),
+        ];
+        let expected = "c56ece650e1de4269c5bdeff7875949e3e2033f85b2d193c2ff4f7f78bdcfc75";
+        assert_eq!(
+            hex::encode(Icrc3Value::Map(entries.clone()).hash()),
+            expected
+        );
+
+        entries.reverse();
+        assert_eq!(hex::encode(Icrc3Value::Map(entries).hash()), expected);
+    }
+}
@@ -0,0 +1,287 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{BTFResult, Error};
+use crate::id256::AccountId256;
+
+/// Max length of the ICRC-1 transfer memo, used if the ledger doesn't report its own limit.
+/// Equals to the default limit of the ICRC-1 reference ledger.
+pub const DEFAULT_MAX_ICRC_MEMO_LENGTH: usize = 32;
+
+/// First byte of the approve delivery header. Differs from the [`Id256`](crate::id256::Id256)
+/// and [`AccountId256`] marks, so it can't be confused with the recipient account.
+pub const APPROVE_DELIVERY_MARK: u8 = 5;
+
+/// Size of the approve delivery header.
+const APPROVE_DELIVERY_HEADER_SIZE: usize = 32;
+
+/// Delivery of the withdrawn ICRC tokens to the recipient.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum WithdrawalDelivery {
+    /// Tokens are transferred to the recipient account.
+    #[default]
+    Transfer,
+    /// Tokens are transferred to an account of the bridge, and the spender is approved to
+    /// transfer them with `icrc2_transfer_from` until the approval expires.
+    /// The recipient account is the spender, unless another `spender` is designated.
+    Approve { spender: Option<AccountId256> },
+}
+
+/// Recipient of the ICRC tokens withdrawal, encoded in the `recipientID` of the burn event.
+///
+/// # Encoding
+/// [0..32] or [0..64] - recipient account, encoded as [`AccountId256`],
+/// [32..] or [64..] - optional ICRC-1 transfer memo.
+///
+/// A recipient with the default subaccount and without memo is encoded exactly as
+/// the principal [`Id256`](crate::id256::Id256).
+///
+/// The [`WithdrawalDelivery::Approve`] recipient is prefixed with a 32 bytes header:
+/// [0] - [`APPROVE_DELIVERY_MARK`],
+/// [1] - 1 if the designated spender account follows the recipient account, 0 otherwise,
+/// [2..32] - zeros.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct IcrcWithdrawalRecipient {
+    pub principal: Principal,
+    /// Subaccount of the recipient. `None` for the default subaccount.
+    pub subaccount: Option<[u8; 32]>,
+    /// Memo of the `icrc1_transfer` call, which credits the withdrawn tokens.
+    /// Exchanges may require it to identify the deposit.
+    pub memo: Option<Vec<u8>>,
+    /// Delivery of the withdrawn tokens.
+    pub delivery: WithdrawalDelivery,
+}
+
+impl IcrcWithdrawalRecipient {
+    pub fn new(principal: Principal, memo: Option<Vec<u8>>) -> Self {
+        Self {
+            principal,
+            subaccount: None,
+            memo,
+            delivery: WithdrawalDelivery::Transfer,
+        }
+    }
+
+    /// Sets the recipient subaccount. The all-zero subaccount is the default one.
+    pub fn with_subaccount(mut self, subaccount: Option<[u8; 32]>) -> Self {
+        self.subaccount = AccountId256::new(self.principal, subaccount).subaccount;
+        self
+    }
+
+    /// Sets the delivery of the withdrawn tokens.
+    pub fn with_delivery(mut self, delivery: WithdrawalDelivery) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    /// Account of the recipient.
+    pub fn account(&self) -> AccountId256 {
+        AccountId256::new(self.principal, self.subaccount)
+    }
+
+    /// Returns the account, which is approved to transfer the withdrawn tokens,
+    /// if the tokens are delivered with an approval.
+    pub fn approval_spender(&self) -> Option<AccountId256> {
+        match self.delivery {
+            WithdrawalDelivery::Transfer => None,
+            WithdrawalDelivery::Approve { spender } => Some(spender.unwrap_or(self.account())),
+        }
+    }
+
+    /// Encodes the recipient into the `recipientID` burn argument.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        if let WithdrawalDelivery::Approve { spender } = &self.delivery {
+            let mut header = [0; APPROVE_DELIVERY_HEADER_SIZE];
+            header[0] = APPROVE_DELIVERY_MARK;
+            header[1] = spender.is_some() as u8;
+            bytes.extend_from_slice(&header);
+            bytes.extend_from_slice(&self.account().encode());
+            if let Some(spender) = spender {
+                bytes.extend_from_slice(&spender.encode());
+            }
+        } else {
+            bytes.extend_from_slice(&self.account().encode());
+        }
+
+        if let Some(memo) = &self.memo {
+            bytes.extend_from_slice(memo);
+        }
+
+        bytes
+    }
+
+    /// Decodes the recipient from the `recipientID` of the burn event.
+    pub fn decode(bytes: &[u8]) -> BTFResult<Self> {
+        let (delivery_header, bytes) = match bytes.first() {
+            Some(&APPROVE_DELIVERY_MARK) if bytes.len() >= APPROVE_DELIVERY_HEADER_SIZE => {
+                let (header, rest) = bytes.split_at(APPROVE_DELIVERY_HEADER_SIZE);
+                (Some(header), rest)
+            }
+            _ => (None, bytes),
+        };
+
+        let (account, rest) = AccountId256::decode_prefix(bytes)?;
+        let (delivery, memo) = match delivery_header {
+            None => (WithdrawalDelivery::Transfer, rest),
+            Some(header) => {
+                if header[2..].iter().any(|byte| *byte != 0) {
+                    return Err(Error::Serialization(
+                        "invalid withdrawal delivery header".into(),
+                    ));
+                }
+
+                match header[1] {
+                    0 => (WithdrawalDelivery::Approve { spender: None }, rest),
+                    1 => {
+                        let (spender, rest) = AccountId256::decode_prefix(rest)?;
+                        let spender = Some(spender);
+                        (WithdrawalDelivery::Approve { spender }, rest)
+                    }
+                    flag => {
+                        return Err(Error::Serialization(format!(
+                            "invalid withdrawal delivery spender flag {flag}"
+                        )))
+                    }
+                }
+            }
+        };
+        let memo = (!memo.is_empty()).then(|| memo.to_vec());
+
+        Ok(Self {
+            principal: account.principal,
+            subaccount: account.subaccount,
+            memo,
+            delivery,
+        })
+    }
+
+    /// Checks that the memo fits into the ledger `max_memo_length`.
+    pub fn validate_memo(&self, max_memo_length: usize) -> BTFResult<()> {
+        match &self.memo {
+            Some(memo) if memo.len() > max_memo_length => Err(Error::Serialization(format!(
+                "ICRC transfer memo length {} exceeds the ledger limit {max_memo_length}",
+                memo.len()
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id256::Id256;
+
+    fn principal() -> Principal {
+        Principal::from_slice(&[7; 29])
+    }
+
+    #[test]
+    fn recipient_without_memo_is_encoded_as_id256() {
+        let recipient = IcrcWithdrawalRecipient::new(principal(), None);
+        let encoded = recipient.encode();
+
+        assert_eq!(encoded, Id256::from(&principal()).0.to_vec());
+        assert_eq!(
+            IcrcWithdrawalRecipient::decode(&encoded).unwrap(),
+            recipient
+        );
+    }
+
+    #[test]
+    fn recipient_with_memo_roundtrip() {
+        let recipient = IcrcWithdrawalRecipient::new(principal(), Some(b"exchange-tag".to_vec()));
+        let decoded = IcrcWithdrawalRecipient::decode(&recipient.encode()).unwrap();
+
+        assert_eq!(decoded, recipient);
+    }
+
+    #[test]
+    fn recipient_with_subaccount_and_memo_roundtrip() {
+        let recipient = IcrcWithdrawalRecipient::new(principal(), Some(b"exchange-tag".to_vec()))
+            .with_subaccount(Some([5; 32]));
+        let encoded = recipient.encode();
+
+        assert_eq!(encoded.len(), 64 + b"exchange-tag".len());
+        assert_eq!(
+            IcrcWithdrawalRecipient::decode(&encoded).unwrap(),
+            recipient
+        );
+    }
+
+    #[test]
+    fn recipient_is_delivered_by_transfer_by_default() {
+        let recipient = IcrcWithdrawalRecipient::new(principal(), None);
+
+        assert_eq!(recipient.delivery, WithdrawalDelivery::Transfer);
+        assert_eq!(recipient.approval_spender(), None);
+    }
+
+    #[test]
+    fn approve_delivery_roundtrip() {
+        let recipient = IcrcWithdrawalRecipient::new(principal(), Some(b"exchange-tag".to_vec()))
+            .with_subaccount(Some([5; 32]))
+            .with_delivery(WithdrawalDelivery::Approve { spender: None });
+        let encoded = recipient.encode();
+
+        assert_eq!(encoded[0], APPROVE_DELIVERY_MARK);
+        assert_eq!(encoded.len(), 32 + 64 + b"exchange-tag".len());
+        assert_eq!(
+            IcrcWithdrawalRecipient::decode(&encoded).unwrap(),
+            recipient
+        );
+        assert_eq!(recipient.approval_spender(), Some(recipient.account()));
+    }
+
+    #[test]
+    fn approve_delivery_with_designated_spender_roundtrip() {
+        let spender = AccountId256::new(Principal::from_slice(&[9; 29]), Some([3; 32]));
+        let recipient = IcrcWithdrawalRecipient::new(principal(), None).with_delivery(
+            WithdrawalDelivery::Approve {
+                spender: Some(spender),
+            },
+        );
+        let decoded = IcrcWithdrawalRecipient::decode(&recipient.encode()).unwrap();
+
+        assert_eq!(decoded, recipient);
+        assert_eq!(decoded.approval_spender(), Some(spender));
+    }
+
+    #[test]
+    fn should_reject_malformed_approve_delivery_header() {
+        let recipient = IcrcWithdrawalRecipient::new(principal(), None)
+            .with_delivery(WithdrawalDelivery::Approve { spender: None });
+
+        let mut encoded = recipient.encode();
+        encoded[1] = 2;
+        assert!(IcrcWithdrawalRecipient::decode(&encoded).is_err());
+
+        let mut encoded = recipient.encode();
+        encoded[31] = 1;
+        assert!(IcrcWithdrawalRecipient::decode(&encoded).is_err());
+
+        // Designated spender is missing.
+        let mut encoded = recipient.encode();
+        encoded[1] = 1;
+        assert!(IcrcWithdrawalRecipient::decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn should_reject_malformed_recipient() {
+        assert!(IcrcWithdrawalRecipient::decode(&[0; 16]).is_err());
+
+        let evm_id = Id256::from_evm_address(&did::H160::from_slice(&[1; 20]), 1);
+        assert!(IcrcWithdrawalRecipient::decode(&evm_id.0).is_err());
+    }
+
+    #[test]
+    fn should_validate_memo_length() {
+        let no_memo = IcrcWithdrawalRecipient::new(principal(), None);
+        assert!(no_memo.validate_memo(0).is_ok());
+
+        let memo = IcrcWithdrawalRecipient::new(principal(), Some(vec![1; 32]));
+        assert!(memo.validate_memo(DEFAULT_MAX_ICRC_MEMO_LENGTH).is_ok());
+        assert!(memo.validate_memo(31).is_err());
+    }
+}
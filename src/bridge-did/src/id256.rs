@@ -39,6 +39,25 @@ pub struct Id256(pub [u8; ID_256_BYTE_SIZE]);
 
 pub const ID_256_BYTE_SIZE: usize = 32;
 
+/// Reason of the `Id256` decoding failure.
+#[derive(Debug, thiserror::Error, Clone, Copy, PartialEq, Eq)]
+pub enum Id256DecodeError {
+    #[error("data of Id256 should contain exactly 32 bytes, got {0}")]
+    WrongLength(usize),
+
+    #[error("invalid Id256 chain tag {0}")]
+    InvalidChainTag(u8),
+
+    #[error("invalid address in Id256: {0}")]
+    InvalidAddress(&'static str),
+}
+
+impl From<Id256DecodeError> for Error {
+    fn from(e: Id256DecodeError) -> Self {
+        Error::Serialization(e.to_string())
+    }
+}
+
 impl Id256 {
     pub const BYTE_SIZE: usize = ID_256_BYTE_SIZE;
     pub const PRINCIPAL_MARK: u8 = 0;
@@ -104,6 +123,73 @@ impl Id256 {
         Self::try_from(bytes).ok()
     }
 
+    /// Creates Self from bytes, returning the reason of the failure.
+    ///
+    /// Unlike [`Id256::from_slice`], checks that the data is encoded by one of the `Id256`
+    /// constructors, so the decoded value is encoded back into the same bytes.
+    pub fn try_from_slice(bytes: &[u8]) -> Result<Self, Id256DecodeError> {
+        let inner: [u8; Self::BYTE_SIZE] = bytes
+            .try_into()
+            .map_err(|_| Id256DecodeError::WrongLength(bytes.len()))?;
+
+        let data_len = match inner[0] {
+            Self::PRINCIPAL_MARK => {
+                let principal_len = inner[1] as usize;
+                if principal_len > Principal::MAX_LENGTH_IN_BYTES {
+                    return Err(Id256DecodeError::InvalidAddress(
+                        "principal data is longer than 29 bytes",
+                    ));
+                }
+                2 + principal_len
+            }
+            Self::EVM_ADDRESS_MARK => 1 + 4 + H160::BYTE_SIZE,
+            Self::BTC_TX_MARK => 1 + 8 + 4,
+            Self::BRC20_TICK_MARK => 1 + 4,
+            tag => return Err(Id256DecodeError::InvalidChainTag(tag)),
+        };
+
+        if inner[data_len..].iter().any(|byte| *byte != 0) {
+            return Err(Id256DecodeError::InvalidAddress(
+                "non-zero padding after the address data",
+            ));
+        }
+
+        Ok(Self(inner))
+    }
+
+    /// Converts Id256 into the IC principal, returning the reason of the failure.
+    pub fn try_to_principal(&self) -> Result<Principal, Id256DecodeError> {
+        if self.0[0] != Self::PRINCIPAL_MARK {
+            return Err(Id256DecodeError::InvalidChainTag(self.0[0]));
+        }
+
+        let principal_len = self.0[1] as usize;
+        if principal_len > Principal::MAX_LENGTH_IN_BYTES {
+            return Err(Id256DecodeError::InvalidAddress(
+                "principal data is longer than 29 bytes",
+            ));
+        }
+
+        Principal::try_from_slice(&self.0[2..][..principal_len])
+            .map_err(|_| Id256DecodeError::InvalidAddress("invalid principal data"))
+    }
+
+    /// Converts Id256 into the `(chain_id, address)` EVM address, returning the reason
+    /// of the failure.
+    pub fn try_to_evm_address(&self) -> Result<(u32, H160), Id256DecodeError> {
+        if self.0[0] != Self::EVM_ADDRESS_MARK {
+            return Err(Id256DecodeError::InvalidChainTag(self.0[0]));
+        }
+
+        let chain_id_bytes = self.0[1..5]
+            .try_into()
+            .expect("we have exactly 4 bytes, as expected for u32");
+        let chain_id = u32::from_be_bytes(chain_id_bytes);
+
+        let address = H160::from_slice(&self.0[5..25]);
+        Ok((chain_id, address))
+    }
+
     pub fn chain_id(&self) -> u32 {
         if self.0[0] == Self::PRINCIPAL_MARK {
             return 0;
@@ -248,6 +334,85 @@ impl TryFrom<Id256> for H160 {
     }
 }
 
+/// IC account: principal with an optional subaccount.
+///
+/// # Encoding
+/// An account with the default subaccount is encoded exactly as the principal [`Id256`].
+/// Otherwise:
+/// [0..32] - principal [`Id256`] with [`AccountId256::SUBACCOUNT_MARK`] instead of
+/// [`Id256::PRINCIPAL_MARK`],
+/// [32..64] - subaccount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, CandidType, Deserialize, serde::Serialize)]
+pub struct AccountId256 {
+    pub principal: Principal,
+    /// `None` for the default subaccount.
+    pub subaccount: Option<[u8; 32]>,
+}
+
+impl AccountId256 {
+    pub const SUBACCOUNT_MARK: u8 = 4;
+    pub const SUBACCOUNT_SIZE: usize = 32;
+
+    /// Creates the account. The all-zero subaccount is the default one, so it is stored as `None`.
+    pub fn new(principal: Principal, subaccount: Option<[u8; 32]>) -> Self {
+        Self {
+            principal,
+            subaccount: subaccount.filter(|subaccount| subaccount != &[0; 32]),
+        }
+    }
+
+    /// Encodes the account into 32 bytes for the default subaccount and into 64 bytes otherwise.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Id256::from(&self.principal).0.to_vec();
+        if let Some(subaccount) = &self.subaccount {
+            bytes[0] = Self::SUBACCOUNT_MARK;
+            bytes.extend_from_slice(subaccount);
+        }
+
+        bytes
+    }
+
+    /// Decodes the account from the beginning of the `bytes`.
+    /// Returns the account and the rest of the bytes.
+    pub fn decode_prefix(bytes: &[u8]) -> BTFResult<(Self, &[u8])> {
+        if bytes.len() < Id256::BYTE_SIZE {
+            return Err(Error::Serialization(
+                "account id should contain at least 32 bytes".into(),
+            ));
+        }
+
+        let (id, rest) = bytes.split_at(Id256::BYTE_SIZE);
+        if id[0] != Self::SUBACCOUNT_MARK {
+            let principal = Id256::try_from(id)?.try_into()?;
+            return Ok((Self::new(principal, None), rest));
+        }
+
+        if rest.len() < Self::SUBACCOUNT_SIZE {
+            return Err(Error::Serialization(
+                "account id with subaccount should contain at least 64 bytes".into(),
+            ));
+        }
+
+        let mut principal_id = id.to_vec();
+        principal_id[0] = Id256::PRINCIPAL_MARK;
+        let principal = Id256::try_from(principal_id.as_slice())?.try_into()?;
+        let (subaccount, rest) = rest.split_at(Self::SUBACCOUNT_SIZE);
+        let subaccount = subaccount.try_into().expect("we have exactly 32 bytes");
+
+        Ok((Self::new(principal, Some(subaccount)), rest))
+    }
+
+    /// Decodes the account, which should take all the `bytes`.
+    pub fn decode(bytes: &[u8]) -> BTFResult<Self> {
+        match Self::decode_prefix(bytes)? {
+            (account, []) => Ok(account),
+            _ => Err(Error::Serialization(
+                "account id contains unexpected trailing bytes".into(),
+            )),
+        }
+    }
+}
+
 impl Storable for Id256 {
     const BOUND: Bound = Bound::Bounded {
         max_size: Self::BYTE_SIZE as _,
@@ -265,6 +430,8 @@ impl Storable for Id256 {
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
 
     #[test]
@@ -362,6 +529,84 @@ mod tests {
         assert_eq!(Id256::from_slice(&slice), Some(id));
     }
 
+    #[test]
+    fn id256_try_from_slice_roundtrip() {
+        let ids = [
+            Id256::from(&Principal::from_slice(&[20; 29])),
+            Id256::from(&Principal::anonymous()),
+            Id256::from_evm_address(&H160::from_slice(&[42; 20]), 31156),
+            Id256::from_btc_tx_index(100500, 42),
+            Id256::from_brc20_tick(*b"ordi"),
+        ];
+
+        for id in ids {
+            assert_eq!(Id256::try_from_slice(&id.0), Ok(id));
+        }
+    }
+
+    #[test]
+    fn id256_try_from_slice_wrong_length() {
+        assert_eq!(
+            Id256::try_from_slice(&[1u8; 33]),
+            Err(Id256DecodeError::WrongLength(33))
+        );
+        assert_eq!(
+            Id256::try_from_slice(&[]),
+            Err(Id256DecodeError::WrongLength(0))
+        );
+    }
+
+    #[test]
+    fn id256_try_from_slice_invalid_chain_tag() {
+        let mut bytes = [0u8; 32];
+        bytes[0] = 42;
+
+        assert_eq!(
+            Id256::try_from_slice(&bytes),
+            Err(Id256DecodeError::InvalidChainTag(42))
+        );
+    }
+
+    #[test]
+    fn id256_try_from_slice_invalid_address() {
+        let mut bytes = Id256::from(&Principal::from_slice(&[20; 29])).0;
+        bytes[1] = 30;
+        assert!(matches!(
+            Id256::try_from_slice(&bytes),
+            Err(Id256DecodeError::InvalidAddress(_))
+        ));
+
+        let mut bytes = Id256::from_evm_address(&H160::from_slice(&[42; 20]), 31156).0;
+        bytes[31] = 1;
+        assert!(matches!(
+            Id256::try_from_slice(&bytes),
+            Err(Id256DecodeError::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    fn id256_typed_conversion_errors() {
+        let principal = Principal::from_slice(&[20; 29]);
+        let address = H160::from_slice(&[42; 20]);
+        let principal_id = Id256::from(&principal);
+        let address_id = Id256::from_evm_address(&address, 31156);
+
+        assert_eq!(principal_id.try_to_principal(), Ok(principal));
+        assert_eq!(address_id.try_to_evm_address(), Ok((31156, address)));
+        assert_eq!(
+            address_id.try_to_principal(),
+            Err(Id256DecodeError::InvalidChainTag(Id256::EVM_ADDRESS_MARK))
+        );
+        assert_eq!(
+            principal_id.try_to_evm_address(),
+            Err(Id256DecodeError::InvalidChainTag(Id256::PRINCIPAL_MARK))
+        );
+        assert_eq!(
+            Error::from(Id256DecodeError::WrongLength(33)),
+            Error::Serialization("data of Id256 should contain exactly 32 bytes, got 33".into())
+        );
+    }
+
     #[test]
     fn btc_tx_index_decode_error() {
         let id = Id256::from_evm_address(&H160::from_slice(&[42; 20]), 31156);
@@ -371,4 +616,63 @@ mod tests {
             id.to_btc_tx_index()
         )
     }
+
+    #[test]
+    fn account_id_with_subaccount_roundtrip() {
+        let principal = Principal::from_slice(&[7; 29]);
+        let account = AccountId256::new(principal, Some([3; 32]));
+        let encoded = account.encode();
+
+        assert_eq!(encoded.len(), 64);
+        assert_eq!(encoded[0], AccountId256::SUBACCOUNT_MARK);
+        assert_eq!(AccountId256::decode(&encoded).unwrap(), account);
+    }
+
+    #[test]
+    fn account_id_with_default_subaccount_is_encoded_as_principal() {
+        let principal = Principal::from_slice(&[7; 29]);
+        let principal_id = Id256::from(&principal).0.to_vec();
+
+        for subaccount in [None, Some([0; 32])] {
+            let account = AccountId256::new(principal, subaccount);
+            assert_eq!(account.subaccount, None);
+            assert_eq!(account.encode(), principal_id);
+            assert_eq!(AccountId256::decode(&principal_id).unwrap(), account);
+        }
+    }
+
+    #[test]
+    fn should_reject_malformed_account_id() {
+        let principal = Principal::from_slice(&[7; 29]);
+        let encoded = AccountId256::new(principal, Some([3; 32])).encode();
+
+        assert!(AccountId256::decode(&encoded[..48]).is_err());
+        assert!(AccountId256::decode(&[encoded.as_slice(), &[1]].concat()).is_err());
+
+        let evm_id = Id256::from_evm_address(&H160::from_slice(&[1; 20]), 1);
+        assert!(AccountId256::decode(&evm_id.0).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn account_id_roundtrip(
+            principal in prop::collection::vec(any::<u8>(), 0..=Principal::MAX_LENGTH_IN_BYTES),
+            subaccount in any::<Option<[u8; 32]>>(),
+        ) {
+            let account = AccountId256::new(Principal::from_slice(&principal), subaccount);
+            prop_assert_eq!(AccountId256::decode(&account.encode()).unwrap(), account);
+        }
+
+        #[test]
+        fn arbitrary_bytes_decoding_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..128)) {
+            let _ = AccountId256::decode(&bytes);
+            if let Ok(id) = Id256::try_from_slice(&bytes) {
+                let _ = id.try_to_principal();
+                let _ = id.try_to_evm_address();
+                let _ = id.to_btc_tx_index();
+                let _ = Principal::try_from(id);
+                let _ = H160::try_from(id);
+            }
+        }
+    }
 }
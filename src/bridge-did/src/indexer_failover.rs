@@ -0,0 +1,126 @@
+//! Types of the failover from the primary to the fallback indexer group, used when the
+//! primary indexers repeatedly fail to reach consensus.
+
+use std::collections::HashSet;
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+/// Default number of consecutive consensus failures of the primary indexer group for a query,
+/// after which the fallback group is consulted.
+pub const DEFAULT_FAILOVER_AFTER_FAILURES: u32 = 3;
+
+/// Group of indexers, consulted when the primary indexers repeatedly fail to reach consensus.
+#[derive(Debug, Clone, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub struct FallbackIndexers {
+    pub indexer_urls: HashSet<String>,
+    /// Minimum quantity of the fallback indexers required to reach agreement on a request.
+    pub consensus_threshold: u8,
+    /// Number of consecutive consensus failures of the primary group for a query, after
+    /// which the fallback group is consulted.
+    pub failover_after_failures: u32,
+}
+
+impl FallbackIndexers {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.indexer_urls.is_empty() {
+            return Err("Fallback indexer urls are empty".to_string());
+        }
+
+        if self
+            .indexer_urls
+            .iter()
+            .any(|url| !url.starts_with("https") && !url.starts_with("http://localhost"))
+        {
+            return Err(
+                "Fallback indexer url must either specify https url or be localhost".to_string(),
+            );
+        }
+
+        if self.consensus_threshold == 0
+            || self.consensus_threshold as usize > self.indexer_urls.len()
+        {
+            return Err(format!(
+                "Fallback consensus threshold must be between 1 and {}",
+                self.indexer_urls.len()
+            ));
+        }
+
+        if self.failover_after_failures == 0 {
+            return Err("Failover must happen after at least one failure".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// Group of indexers, which answers a query.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum IndexerGroup {
+    #[default]
+    Primary,
+    Fallback,
+}
+
+/// Alert, raised when a query is switched between the indexer groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum IndexerFailoverAlert {
+    /// The primary group repeatedly failed to reach consensus, the fallback group is used.
+    SwitchedToFallback,
+    /// The primary group reached consensus again and is used instead of the fallback group.
+    SwitchedToPrimary,
+}
+
+/// Alert with the query it was raised for and the time it was raised at.
+#[derive(Debug, Clone, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub struct IndexerFailoverAlertRecord {
+    pub timestamp: u64,
+    pub query: String,
+    pub alert: IndexerFailoverAlert,
+}
+
+/// Failover state of an indexer query.
+#[derive(Debug, Clone, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub struct IndexerQueryStatus {
+    pub query: String,
+    pub active_group: IndexerGroup,
+    /// Number of consecutive consensus failures of the primary group.
+    pub consecutive_failures: u32,
+}
+
+/// Diagnostic info about the indexer failover.
+#[derive(Debug, Clone, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub struct IndexerFailoverStatus {
+    pub fallback_indexers: Option<FallbackIndexers>,
+    pub queries: Vec<IndexerQueryStatus>,
+    /// Latest alerts, the oldest first.
+    pub alerts: Vec<IndexerFailoverAlertRecord>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fallback(urls: &[&str], consensus_threshold: u8) -> FallbackIndexers {
+        FallbackIndexers {
+            indexer_urls: urls.iter().map(|url| url.to_string()).collect(),
+            consensus_threshold,
+            failover_after_failures: DEFAULT_FAILOVER_AFTER_FAILURES,
+        }
+    }
+
+    #[test]
+    fn fallback_indexers_validation() {
+        assert!(fallback(&["https://a.com", "https://b.com"], 2)
+            .validate()
+            .is_ok());
+        assert!(fallback(&[], 1).validate().is_err());
+        assert!(fallback(&["http://a.com"], 1).validate().is_err());
+        assert!(fallback(&["https://a.com"], 0).validate().is_err());
+        assert!(fallback(&["https://a.com"], 2).validate().is_err());
+
+        let mut no_failures = fallback(&["https://a.com"], 1);
+        no_failures.failover_after_failures = 0;
+        assert!(no_failures.validate().is_err());
+    }
+}
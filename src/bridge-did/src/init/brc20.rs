@@ -10,6 +10,7 @@ use serde::Deserialize;
 
 pub use self::schnorr_key_id::SchnorrKeyIds;
 use super::{DEFAULT_DEPOSIT_FEE, DEFAULT_INDEXER_CONSENSUS_THRESHOLD, DEFAULT_MEMPOOL_TIMEOUT};
+use crate::indexer_failover::FallbackIndexers;
 
 #[derive(Debug, Clone, PartialEq, Eq, CandidType, Deserialize)]
 pub struct Brc20BridgeConfig {
@@ -23,6 +24,9 @@ pub struct Brc20BridgeConfig {
     pub indexer_consensus_threshold: u8,
     /// Schnorr key ID for the management canister
     pub schnorr_key_id: SchnorrKeyIds,
+    /// Indexers consulted when the indexers from `indexer_urls` repeatedly fail to reach
+    /// consensus.
+    pub fallback_indexers: Option<FallbackIndexers>,
 }
 
 impl Storable for Brc20BridgeConfig {
@@ -49,6 +53,7 @@ impl Default for Brc20BridgeConfig {
             mempool_timeout: DEFAULT_MEMPOOL_TIMEOUT,
             indexer_consensus_threshold: DEFAULT_INDEXER_CONSENSUS_THRESHOLD,
             schnorr_key_id: SchnorrKeyIds::TestKey1,
+            fallback_indexers: None,
         }
     }
 }
@@ -67,6 +72,14 @@ impl Brc20BridgeConfig {
             return Err("Indexer url must etiher specify https url or be localhost".to_string());
         }
 
+        if let Some(fallback) = &self.fallback_indexers {
+            fallback.validate()?;
+
+            if !fallback.indexer_urls.is_disjoint(&self.indexer_urls) {
+                return Err("Fallback indexers must not include the primary indexers".to_string());
+            }
+        }
+
         Ok(())
     }
 }
@@ -92,6 +105,7 @@ mod test {
             mempool_timeout: Duration::from_secs(60),
             indexer_consensus_threshold: 2,
             schnorr_key_id: SchnorrKeyIds::TestKey1,
+            fallback_indexers: None,
         };
 
         let bytes = config.to_bytes();
@@ -110,6 +124,7 @@ mod test {
             mempool_timeout: Duration::from_secs(60),
             indexer_consensus_threshold: 2,
             schnorr_key_id: SchnorrKeyIds::TestKey1,
+            fallback_indexers: None,
         };
 
         let bytes = config.to_bytes();
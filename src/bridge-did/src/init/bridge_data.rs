@@ -3,9 +3,14 @@ use eth_signer::sign_strategy::SigningStrategy;
 use ic_log::did::LogCanisterSettings;
 use serde::Deserialize;
 
+use crate::bridge_mode::BridgeMode;
+use crate::evm_finality::EvmFinality;
 use crate::evm_link::EvmLink;
 
 /// Bridge canister initialization data.
+///
+/// Fields added after the first release must be optional and `#[serde(default)]`,
+/// so the init data of the older versions can still be deserialized.
 #[derive(Debug, Deserialize, CandidType, Clone, PartialEq, Eq)]
 pub struct BridgeInitData {
     /// Principal of canister's owner.
@@ -20,4 +25,52 @@ pub struct BridgeInitData {
     /// Log settings
     #[serde(default)]
     pub log_settings: Option<LogCanisterSettings>,
+
+    /// Finality of the EVM blocks to collect events from.
+    /// If not set, `EvmFinality::default()` is used.
+    #[serde(default)]
+    pub evm_finality: Option<EvmFinality>,
+
+    /// Minimal id (and so nonce) of the operations created by the canister.
+    /// Should be set on reinstall to avoid collision with nonces used before.
+    #[serde(default)]
+    pub nonce_offset: Option<u64>,
+
+    /// Mode of the canister. If not set, the canister is started as the primary.
+    #[serde(default)]
+    pub mode: Option<BridgeMode>,
+}
+
+#[cfg(test)]
+mod tests {
+    use eth_signer::ic_sign::SigningKeyId;
+
+    use super::*;
+
+    #[test]
+    fn test_bridge_data_default_deserialization() {
+        // Init data without `log_settings`, `evm_finality`, `nonce_offset` and `mode` fields.
+        let json = r#"{
+            "owner": "aaaaa-aa",
+            "evm_link": { "Ic": "aaaaa-aa" },
+            "signing_strategy": { "ManagementCanister": { "key_id": "Test" } }
+        }"#;
+
+        let data: BridgeInitData = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            data,
+            BridgeInitData {
+                owner: Principal::management_canister(),
+                evm_link: EvmLink::Ic(Principal::management_canister()),
+                signing_strategy: SigningStrategy::ManagementCanister {
+                    key_id: SigningKeyId::Test,
+                },
+                log_settings: None,
+                evm_finality: None,
+                nonce_offset: None,
+                mode: None,
+            }
+        );
+    }
 }
@@ -6,6 +6,7 @@ use eth_signer::sign_strategy::SigningStrategy;
 use ic_stable_structures::{Bound, Storable};
 use serde::{Deserialize, Serialize};
 
+use crate::evm_finality::EvmFinality;
 use crate::evm_link::EvmLink;
 
 #[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, CandidType)]
@@ -31,4 +32,6 @@ pub struct BaseEvmSettings {
     pub evm_link: EvmLink,
     pub signing_strategy: SigningStrategy,
     pub delays: QueryDelays,
+    #[serde(default)]
+    pub evm_finality: Option<EvmFinality>,
 }
@@ -1,13 +1,49 @@
+pub mod amount;
+pub mod bridge_mode;
+pub mod bulk_action;
+pub mod burn_intent;
+pub mod concurrency;
+pub mod data_erasure;
+pub mod dead_letter;
+pub mod deposit_account;
+pub mod deposit_bundle;
 pub mod erc721_mint_order;
 pub mod error;
+pub mod event_filter;
+pub mod evm_finality;
+pub mod evm_latency;
 pub mod evm_link;
+pub mod evm_params_history;
+pub mod evm_tx;
+pub mod fee_adjustment;
+pub mod gas_balance;
+pub mod halt;
+pub mod http_transform;
+pub mod icrc21;
+pub mod icrc3;
+pub mod icrc_withdrawal;
 pub mod id256;
+pub mod indexer_failover;
 pub mod init;
+pub mod manifest;
+pub mod nonce_drift;
 pub mod op_id;
+pub mod operation_block;
 pub mod operation_log;
+pub mod operation_replay;
+pub mod operation_status;
 pub mod order;
+pub mod outbox;
+pub mod pending_mint_batch;
+pub mod preferences;
+pub mod processed_event;
 pub mod reason;
+pub mod retry_profile;
 pub mod schnorr;
+pub mod supply_guard;
+pub mod task_payload;
+pub mod token_decimals;
+pub mod versioned;
 
 pub mod brc20_info;
 pub mod bridge_side;
@@ -21,6 +57,6 @@ pub mod runes;
 pub mod event_data {
     pub use crate::events::BTFBridge::{BurnTokenEvent, MintTokenEvent, NotifyMinterEvent};
     pub use crate::events::{
-        BurntEventData, MintedEventData, MinterNotificationType, NotifyMinterEventData,
+        BurntEventData, MintedEventData, MinterNotificationType, NotifyMinterEventData, RawEventLog,
     };
 }
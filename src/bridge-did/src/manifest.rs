@@ -0,0 +1,36 @@
+//! Bridge configuration, which frontends need to interact with the bridge.
+
+use candid::CandidType;
+use did::H160;
+use serde::{Deserialize, Serialize};
+
+use crate::evm_finality::EvmFinality;
+
+/// User-relevant configuration of the bridge canister, aggregated in a single response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct BridgeManifest {
+    /// Version of the included settings. Increased every time any of the settings is changed,
+    /// so clients can cache the manifest and cheaply check if it is outdated.
+    pub manifest_version: u64,
+    /// Address of the BTF bridge contract in the EVM.
+    pub btf_bridge_contract: Option<H160>,
+    /// Chain id of the EVM. `None` until the EVM params are initialized.
+    pub chain_id: Option<u64>,
+    /// Finality of the EVM blocks, from which the bridge collects events.
+    pub evm_finality: EvmFinality,
+    /// If enabled, burnt events with malformed token metadata are not processed.
+    pub strict_event_validation: bool,
+    /// If disabled, deposits are accepted, but the bridge doesn't collect events and doesn't
+    /// progress operations until the timers are enabled again.
+    pub timers_enabled: bool,
+    pub limits: BridgeLimits,
+}
+
+/// Limits of the bridge canister requests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct BridgeLimits {
+    /// Max number of the operation blocks returned by the `get_operation_blocks` query.
+    pub max_operation_blocks_per_request: u64,
+    /// Max number of the mint orders signed in a single batch.
+    pub max_mint_orders_in_batch: u64,
+}
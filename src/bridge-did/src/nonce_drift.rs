@@ -0,0 +1,62 @@
+//! Types to monitor the drift between the EVM nonce, reserved by the bridge canister for its
+//! transactions, and the pending nonce of the bridge canister EVM address.
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+/// Comparison of the reserved nonce with the pending nonce of the EVM address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct NonceCheck {
+    /// Nonce, reserved by the bridge canister for the next transaction.
+    pub local_nonce: u64,
+    /// Pending transactions count of the EVM address, reported by the EVM node.
+    pub chain_nonce: u64,
+    /// `chain_nonce - local_nonce`. Positive if transactions were sent from the address
+    /// outside the bridge, negative if transactions sent by the bridge are not visible.
+    pub drift: i64,
+    /// IC timestamp in nanoseconds.
+    pub timestamp: u64,
+}
+
+impl NonceCheck {
+    pub fn new(local_nonce: u64, chain_nonce: u64, timestamp: u64) -> Self {
+        Self {
+            local_nonce,
+            chain_nonce,
+            drift: (chain_nonce as i128 - local_nonce as i128) as i64,
+            timestamp,
+        }
+    }
+}
+
+/// The chain nonce is behind the reserved one. Mint transactions sending is paused
+/// until the gap resolves, or the gap is acknowledged by the canister owner.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct NonceGap {
+    /// Reserved nonce at the moment of the latest check.
+    pub local_nonce: u64,
+    /// Chain nonce at the moment of the latest check.
+    pub chain_nonce: u64,
+    /// IC timestamp of the gap detection in nanoseconds.
+    pub detected_at: u64,
+}
+
+/// Move of the reserved nonce forward to the chain nonce.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct NonceFastForward {
+    pub from: u64,
+    pub to: u64,
+    /// IC timestamp in nanoseconds.
+    pub timestamp: u64,
+}
+
+/// Diagnostic info about the nonce drift of the bridge canister EVM address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct NonceDriftStatus {
+    /// Nonce, reserved for the next transaction, if EVM params are initialized.
+    pub local_nonce: Option<u64>,
+    pub last_check: Option<NonceCheck>,
+    /// Open gap. Mint transactions are not sent while the gap is open.
+    pub gap: Option<NonceGap>,
+    pub last_fast_forward: Option<NonceFastForward>,
+}
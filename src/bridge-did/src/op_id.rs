@@ -57,6 +57,17 @@ impl Storable for OperationId {
     const BOUND: Bound = <u64 as Storable>::BOUND;
 }
 
+/// State of the operation nonces of a bridge canister.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, CandidType, Deserialize, Serialize)]
+pub struct NonceState {
+    /// Id which will be assigned to the next operation.
+    pub next_operation_id: OperationId,
+    /// Nonce of the next operation.
+    pub next_nonce: u32,
+    /// Operations created by the canister get ids not less than this offset.
+    pub nonce_offset: u64,
+}
+
 impl fmt::Display for OperationId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.0.fmt(f)
@@ -0,0 +1,129 @@
+//! Blocks of the append-only log of the bridge operations, exported in the ICRC-3 format.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Nat};
+use did::H160;
+use ic_stable_structures::{Bound, Storable};
+use serde::Serialize;
+
+use crate::data_erasure::AnonymizationMarker;
+use crate::error::{BTFResult, Error};
+use crate::icrc3::Icrc3Value;
+use crate::op_id::OperationId;
+use crate::versioned::Versioned;
+
+/// Value of the ICRC-3 `btype` field of the operation blocks.
+pub const OPERATION_BLOCK_TYPE: &str = "btf_op";
+
+/// Block of the operations log. Every block records one state transition of an operation,
+/// and is linked to the previous block by its hash.
+///
+/// An erasure block records the anonymization of the operation instead. The earlier blocks
+/// of the anonymized operation are redacted: their wallet address is replaced with the pseudonym
+/// and the step is removed, but they keep their original hash, so the chain stays verifiable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct OperationBlock {
+    /// Hash of the previous block. `None` for the first block of the log.
+    pub parent_hash: Option<[u8; 32]>,
+    pub timestamp: u64,
+    pub operation_id: OperationId,
+    pub wallet_address: H160,
+    /// Candid encoded state of the operation after the transition, or the error message,
+    /// if the transition failed. Empty for the erasure and the redacted blocks.
+    pub step: Result<Vec<u8>, String>,
+    /// Set for the erasure block of the operation.
+    pub anonymization: Option<AnonymizationMarker>,
+    /// Hash of the block before its user data was redacted.
+    pub redacted_hash: Option<[u8; 32]>,
+}
+
+impl OperationBlock {
+    /// Returns the block as the ICRC-3 value.
+    pub fn to_value(&self) -> Icrc3Value {
+        let mut tx = vec![
+            (
+                "op_id".to_string(),
+                Icrc3Value::Nat(self.operation_id.as_u64().into()),
+            ),
+            (
+                "wallet".to_string(),
+                Icrc3Value::Blob(self.wallet_address.0.as_bytes().to_vec()),
+            ),
+        ];
+        match (&self.anonymization, &self.step) {
+            (Some(marker), _) => tx.push((
+                "erasure".to_string(),
+                Icrc3Value::Nat(marker.audit_entry.into()),
+            )),
+            (None, Ok(state)) => tx.push(("state".to_string(), Icrc3Value::Blob(state.clone()))),
+            (None, Err(e)) => tx.push(("error".to_string(), Icrc3Value::Text(e.clone()))),
+        }
+
+        let mut block = vec![
+            (
+                "btype".to_string(),
+                Icrc3Value::Text(OPERATION_BLOCK_TYPE.to_string()),
+            ),
+            ("ts".to_string(), Icrc3Value::Nat(Nat::from(self.timestamp))),
+            ("tx".to_string(), Icrc3Value::Map(tx)),
+        ];
+        if let Some(parent_hash) = self.parent_hash {
+            block.push(("phash".to_string(), Icrc3Value::Blob(parent_hash.to_vec())));
+        }
+
+        Icrc3Value::Map(block)
+    }
+
+    /// Returns the ICRC-3 hash of the block. For a redacted block it is the hash of the block
+    /// before the redaction.
+    pub fn hash(&self) -> [u8; 32] {
+        self.redacted_hash.unwrap_or_else(|| self.to_value().hash())
+    }
+
+    /// Replaces the wallet address with the `pseudonym` and removes the step, keeping the hash
+    /// of the block. Erasure blocks are not changed.
+    pub fn redact(&mut self, pseudonym: &H160) {
+        if self.anonymization.is_some() || self.redacted_hash.is_some() {
+            return;
+        }
+
+        self.redacted_hash = Some(self.hash());
+        self.wallet_address = pseudonym.clone();
+        self.step = Ok(vec![]);
+    }
+
+    /// Checks if the user data of the block is redacted.
+    pub fn is_redacted(&self) -> bool {
+        self.redacted_hash.is_some()
+    }
+}
+
+impl Versioned for OperationBlock {
+    const CURRENT_VERSION: u16 = 1;
+
+    fn encode_payload(&self) -> Vec<u8> {
+        Encode!(self).expect("failed to encode operation block")
+    }
+
+    fn decode_payload(version: u16, payload: &[u8]) -> BTFResult<Self> {
+        match version {
+            1 => Decode!(payload, Self).map_err(|e| Error::Serialization(e.to_string())),
+            _ => Err(Error::Serialization(format!(
+                "unknown operation block version {version}"
+            ))),
+        }
+    }
+}
+
+impl Storable for OperationBlock {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        self.to_versioned_bytes().into()
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Self::from_versioned_bytes(&bytes).expect("failed to decode operation block")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
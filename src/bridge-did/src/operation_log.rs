@@ -1,14 +1,23 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 
 use candid::{CandidType, Decode, Deserialize, Encode};
 use did::H160;
 use ic_exports::ic_kit::ic;
 use ic_stable_structures::{Bound, Storable};
 
+use crate::data_erasure::AnonymizationMarker;
+use crate::error::{BTFResult, Error};
+use crate::fee_adjustment::FeeAdjustment;
+use crate::versioned::{self, Versioned};
+
 /// Structure that contains full information about the process of an operation execution. This
 /// log will contain every step of an operation execution, whether successfully executed or if it
 /// resulted in an error.
 ///
+/// Consecutive failed steps with the same error message are coalesced into a single entry,
+/// with the number of the failed attempts stored in [`OperationLogEntry::repeat_count`].
+///
 /// The structure itself guarantees that at least one step in the log will be successful (e.g.
 /// the first step - creation of the operation).
 #[derive(Debug, Clone, CandidType, Deserialize)]
@@ -19,6 +28,11 @@ where
     log: Vec<OperationLogEntry<P>>,
     wallet_address: H160,
     memo: Option<Memo>,
+    /// Adjustment of the fee, charged for the mint order of the operation. Set once,
+    /// when the fee is reconciled with the mint transaction cost.
+    fee_adjustment: Option<FeeAdjustment>,
+    /// Set, if the user data of the operation is erased.
+    anonymization: Option<AnonymizationMarker>,
 }
 
 /// The result of a single step taken in the process of an operation execution.
@@ -28,12 +42,19 @@ where
     P: CandidType,
 {
     /// IC timestamp when the step completed. If the operation was executed asynchronously, this
-    /// field will contain the timestamp of the last IC executor run.
+    /// field will contain the timestamp of the last IC executor run. For a repeated step this is
+    /// the timestamp of its last repetition.
     pub time_stamp: u64,
     /// Result of the execution step. If `Ok`, will contain the updated state of the operation. If
     /// `Err` - error message. In case of an error, the state of the operation is guaranteed to
     /// have not been changed.
     pub step_result: Result<P, String>,
+    /// Number of consecutive times the step was taken with this result. Only failed steps
+    /// are coalesced, so it is always `1` for the successful ones.
+    pub repeat_count: u32,
+    /// Index of the EVM params snapshot, used to send the transaction at this step.
+    /// See `get_evm_params_history` method of the bridge canister.
+    pub evm_params_snapshot: Option<u64>,
 }
 
 impl<P> OperationLog<P>
@@ -48,9 +69,13 @@ where
             log: vec![OperationLogEntry {
                 time_stamp: Self::timestamp(),
                 step_result: Ok(payload),
+                repeat_count: 1,
+                evm_params_snapshot: None,
             }],
             wallet_address,
             memo,
+            fee_adjustment: None,
+            anonymization: None,
         }
     }
 
@@ -66,14 +91,40 @@ where
             .expect("operation log does not contain a successful step")
     }
 
-    /// Adds a new entry to the log with the given result.
+    /// Adds a new entry to the log with the given result. If the step failed with the same
+    /// error as the previous one, the previous entry is updated instead.
     pub fn add_step(&mut self, step_result: Result<P, String>) {
-        self.log.push(OperationLogEntry {
+        self.push_entry(OperationLogEntry {
             time_stamp: Self::timestamp(),
             step_result,
+            repeat_count: 1,
+            evm_params_snapshot: None,
         });
     }
 
+    /// Adds a new successful step, which sent a transaction to EVM with the params
+    /// from the given snapshot.
+    pub fn add_step_with_evm_params(&mut self, payload: P, evm_params_snapshot: Option<u64>) {
+        self.push_entry(OperationLogEntry {
+            time_stamp: Self::timestamp(),
+            step_result: Ok(payload),
+            repeat_count: 1,
+            evm_params_snapshot,
+        });
+    }
+
+    fn push_entry(&mut self, entry: OperationLogEntry<P>) {
+        if let (Some(last), Err(err)) = (self.log.last_mut(), &entry.step_result) {
+            if last.step_result.as_ref().err() == Some(err) {
+                last.time_stamp = entry.time_stamp;
+                last.repeat_count = last.repeat_count.saturating_add(entry.repeat_count);
+                return;
+            }
+        }
+
+        self.log.push(entry);
+    }
+
     /// Address of the ETH wallet that initiated this operation.
     pub fn wallet_address(&self) -> &H160 {
         &self.wallet_address
@@ -92,22 +143,456 @@ where
     pub fn memo(&self) -> Option<&Memo> {
         self.memo.as_ref()
     }
+
+    /// Returns the fee adjustment of the operation, if the fee is reconciled.
+    pub fn fee_adjustment(&self) -> Option<&FeeAdjustment> {
+        self.fee_adjustment.as_ref()
+    }
+
+    /// Sets the fee adjustment of the operation. Returns `false` and keeps the log
+    /// unchanged if the fee is already reconciled.
+    pub fn set_fee_adjustment(&mut self, adjustment: FeeAdjustment) -> bool {
+        if self.fee_adjustment.is_some() {
+            return false;
+        }
+
+        self.fee_adjustment = Some(adjustment);
+        true
+    }
+
+    /// Returns the anonymization marker, if the user data of the operation is erased.
+    pub fn anonymization(&self) -> Option<&AnonymizationMarker> {
+        self.anonymization.as_ref()
+    }
+
+    /// Replaces the wallet address with the pseudonym of the marker and removes the memo.
+    /// The steps and the fee adjustment of the operation are kept.
+    pub fn anonymize(&mut self, marker: AnonymizationMarker) {
+        self.wallet_address = marker.pseudonym.clone();
+        self.memo = None;
+        self.anonymization = Some(marker);
+    }
+
+    /// Converts payloads of all the successful steps with the given function.
+    /// The function receives the payload and the timestamp of its step.
+    pub fn map_payload<Q>(self, mut f: impl FnMut(P, u64) -> Q) -> OperationLog<Q>
+    where
+        Q: CandidType,
+    {
+        OperationLog {
+            log: self
+                .log
+                .into_iter()
+                .map(|entry| OperationLogEntry {
+                    time_stamp: entry.time_stamp,
+                    step_result: entry
+                        .step_result
+                        .map(|payload| f(payload, entry.time_stamp)),
+                    repeat_count: entry.repeat_count,
+                    evm_params_snapshot: entry.evm_params_snapshot,
+                })
+                .collect(),
+            wallet_address: self.wallet_address,
+            memo: self.memo,
+            fee_adjustment: self.fee_adjustment,
+            anonymization: self.anonymization,
+        }
+    }
+
+    /// Builds the log from the entries, coalescing the repeated steps.
+    fn from_entries(
+        entries: impl IntoIterator<Item = OperationLogEntry<P>>,
+        wallet_address: H160,
+        memo: Option<Memo>,
+    ) -> Self {
+        let mut log = Self {
+            log: vec![],
+            wallet_address,
+            memo,
+            fee_adjustment: None,
+            anonymization: None,
+        };
+        for entry in entries {
+            log.push_entry(entry);
+        }
+
+        log
+    }
+}
+
+impl<P> OperationLog<P>
+where
+    P: CandidType + Clone + for<'de> Deserialize<'de>,
+{
+    /// Version of the log encoded without compression.
+    pub const UNCOMPRESSED_VERSION: u16 = 4;
+
+    /// Version of the compressed log.
+    pub const COMPRESSED_VERSION: u16 = 5;
+
+    /// Encodes the log without compression.
+    pub fn to_uncompressed_bytes(&self) -> Vec<u8> {
+        let payload = Encode!(self).expect("failed to encode operation log entry");
+        versioned::with_version(Self::UNCOMPRESSED_VERSION, &payload)
+    }
+
+    /// Encodes the log, storing every distinct error message only once.
+    pub fn to_compressed_bytes(&self) -> Vec<u8> {
+        self.to_versioned_bytes()
+    }
+
+    /// Checks if the encoded log is compressed.
+    pub fn is_compressed(bytes: &[u8]) -> bool {
+        versioned::split_version(bytes).0 == Self::COMPRESSED_VERSION
+    }
 }
 
+/// Logs are stored uncompressed by default. Use [`OperationLog::to_compressed_bytes`]
+/// to store the log compressed.
 impl<P> Storable for OperationLog<P>
 where
     P: CandidType + Clone + for<'de> Deserialize<'de>,
 {
     fn to_bytes(&self) -> Cow<[u8]> {
-        Cow::Owned(Encode!(self).expect("failed to encode operation log entry"))
+        Cow::Owned(self.to_uncompressed_bytes())
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        Decode!(&bytes, Self).expect("failed to decode operation log entry")
+        Self::from_versioned_bytes(&bytes).expect("failed to decode operation log entry")
     }
 
     const BOUND: Bound = Bound::Unbounded;
 }
 
+/// Version 1 is the candid encoded [`LegacyOperationLog`] without the version header.
+/// Version 2 is the candid encoded [`LegacyOperationLog`].
+/// Version 3 was never released.
+/// Version 4 is the candid encoded log.
+/// Version 5 is the candid encoded [`CompressedOperationLog`].
+///
+/// Logs of versions 1 and 2 have the repeated steps coalesced on decoding.
+impl<P> Versioned for OperationLog<P>
+where
+    P: CandidType + Clone + for<'de> Deserialize<'de>,
+{
+    const CURRENT_VERSION: u16 = Self::COMPRESSED_VERSION;
+
+    fn encode_payload(&self) -> Vec<u8> {
+        Encode!(&CompressedOperationLog::from(self)).expect("failed to encode operation log entry")
+    }
+
+    fn decode_payload(version: u16, payload: &[u8]) -> BTFResult<Self> {
+        let serialization_err = |e: candid::Error| Error::Serialization(e.to_string());
+        match version {
+            1 | 2 => Decode!(payload, LegacyOperationLog<P>)
+                .map(Self::from)
+                .map_err(serialization_err),
+            4 => Decode!(payload, Self).map_err(serialization_err),
+            5 => Decode!(payload, CompressedOperationLog<P>)
+                .map_err(serialization_err)
+                .and_then(Self::try_from),
+            _ => Err(Error::Serialization(format!(
+                "unknown operation log version {version}"
+            ))),
+        }
+    }
+}
+
+/// Operation log with every distinct error message stored once.
+#[derive(CandidType, Deserialize)]
+struct CompressedOperationLog<P>
+where
+    P: CandidType,
+{
+    errors: Vec<String>,
+    log: Vec<CompressedLogEntry<P>>,
+    wallet_address: H160,
+    memo: Option<Memo>,
+    fee_adjustment: Option<FeeAdjustment>,
+    anonymization: Option<AnonymizationMarker>,
+}
+
+/// Log entry with the error message replaced by its index in [`CompressedOperationLog::errors`].
+#[derive(CandidType, Deserialize)]
+struct CompressedLogEntry<P>
+where
+    P: CandidType,
+{
+    time_stamp: u64,
+    step_result: Result<P, u32>,
+    repeat_count: u32,
+    evm_params_snapshot: Option<u64>,
+}
+
+impl<P> From<&OperationLog<P>> for CompressedOperationLog<P>
+where
+    P: CandidType + Clone,
+{
+    fn from(value: &OperationLog<P>) -> Self {
+        let mut errors = vec![];
+        let mut error_ids = HashMap::new();
+        let log = value
+            .log
+            .iter()
+            .map(|entry| CompressedLogEntry {
+                time_stamp: entry.time_stamp,
+                step_result: entry.step_result.clone().map_err(|err| {
+                    *error_ids.entry(err).or_insert_with_key(|err| {
+                        errors.push(err.clone());
+                        errors.len() as u32 - 1
+                    })
+                }),
+                repeat_count: entry.repeat_count,
+                evm_params_snapshot: entry.evm_params_snapshot,
+            })
+            .collect();
+
+        Self {
+            errors,
+            log,
+            wallet_address: value.wallet_address.clone(),
+            memo: value.memo,
+            fee_adjustment: value.fee_adjustment.clone(),
+            anonymization: value.anonymization.clone(),
+        }
+    }
+}
+
+impl<P> TryFrom<CompressedOperationLog<P>> for OperationLog<P>
+where
+    P: CandidType,
+{
+    type Error = Error;
+
+    fn try_from(value: CompressedOperationLog<P>) -> BTFResult<Self> {
+        let errors = value.errors;
+        let log = value
+            .log
+            .into_iter()
+            .map(|entry| {
+                let step_result = match entry.step_result {
+                    Ok(payload) => Ok(payload),
+                    Err(id) => Err(errors.get(id as usize).cloned().ok_or_else(|| {
+                        Error::Serialization(format!("unknown operation log error id {id}"))
+                    })?),
+                };
+
+                Ok(OperationLogEntry {
+                    time_stamp: entry.time_stamp,
+                    step_result,
+                    repeat_count: entry.repeat_count,
+                    evm_params_snapshot: entry.evm_params_snapshot,
+                })
+            })
+            .collect::<BTFResult<_>>()?;
+
+        Ok(Self {
+            log,
+            wallet_address: value.wallet_address,
+            memo: value.memo,
+            fee_adjustment: value.fee_adjustment,
+            anonymization: value.anonymization,
+        })
+    }
+}
+
+/// Operation log stored before the repeated steps were coalesced.
+#[derive(CandidType, Deserialize)]
+struct LegacyOperationLog<P>
+where
+    P: CandidType,
+{
+    log: Vec<LegacyLogEntry<P>>,
+    wallet_address: H160,
+    memo: Option<Memo>,
+}
+
+#[derive(CandidType, Deserialize)]
+struct LegacyLogEntry<P>
+where
+    P: CandidType,
+{
+    time_stamp: u64,
+    step_result: Result<P, String>,
+}
+
+impl<P> From<LegacyOperationLog<P>> for OperationLog<P>
+where
+    P: CandidType,
+{
+    fn from(value: LegacyOperationLog<P>) -> Self {
+        let entries = value.log.into_iter().map(|entry| OperationLogEntry {
+            time_stamp: entry.time_stamp,
+            step_result: entry.step_result,
+            repeat_count: 1,
+            evm_params_snapshot: None,
+        });
+
+        Self::from_entries(entries, value.wallet_address, value.memo)
+    }
+}
+
 /// Additional metadata for bridge operations
 pub type Memo = [u8; 32];
+
+#[cfg(test)]
+mod tests {
+    use did::H256;
+    use ic_exports::ic_kit::MockContext;
+
+    use super::*;
+    use crate::fee_adjustment::FeeAdjustmentAmount;
+    use crate::op_id::OperationId;
+
+    const TIMEOUT_ERROR: &str = "failed to query EVM: request timed out";
+
+    fn legacy_entry(time_stamp: u64, step_result: Result<u32, String>) -> LegacyLogEntry<u32> {
+        LegacyLogEntry {
+            time_stamp,
+            step_result,
+        }
+    }
+
+    #[test]
+    fn legacy_operation_log_is_decoded() {
+        let log = LegacyOperationLog {
+            log: vec![
+                legacy_entry(1, Ok(1)),
+                legacy_entry(2, Err("error".into())),
+                legacy_entry(3, Err("error".into())),
+                legacy_entry(4, Ok(2)),
+            ],
+            wallet_address: H160::from_slice(&[1; 20]),
+            memo: Some([2; 32]),
+        };
+
+        let legacy_bytes = Encode!(&log).unwrap();
+        let decoded = OperationLog::<u32>::from_bytes(legacy_bytes.into());
+        assert_eq!(decoded.current_step(), &2);
+        assert_eq!(decoded.log().len(), 3);
+        assert_eq!(decoded.log()[1].time_stamp, 3);
+        assert_eq!(decoded.log()[1].repeat_count, 2);
+        assert_eq!(decoded.wallet_address(), &H160::from_slice(&[1; 20]));
+        assert_eq!(decoded.memo(), Some(&[2; 32]));
+
+        let stored = decoded.to_bytes();
+        assert!(stored.starts_with(&crate::versioned::VERSION_MARK));
+        assert_eq!(OperationLog::<u32>::from_bytes(stored).log().len(), 3);
+    }
+
+    #[test]
+    fn identical_failed_steps_are_coalesced() {
+        MockContext::new().inject();
+
+        let mut log = OperationLog::new(1u32, H160::from_slice(&[1; 20]), None);
+        for _ in 0..100 {
+            log.add_step(Err(TIMEOUT_ERROR.into()));
+        }
+
+        assert_eq!(log.log().len(), 2);
+        let step = &log.log()[1];
+        assert_eq!(step.step_result, Err(TIMEOUT_ERROR.to_string()));
+        assert_eq!(step.repeat_count, 100);
+
+        for bytes in [log.to_uncompressed_bytes(), log.to_compressed_bytes()] {
+            let decoded = OperationLog::<u32>::from_bytes(bytes.into());
+            assert_eq!(decoded.log().len(), 2);
+            assert_eq!(decoded.log()[1].repeat_count, 100);
+        }
+    }
+
+    #[test]
+    fn different_steps_are_not_coalesced() {
+        MockContext::new().inject();
+
+        let mut log = OperationLog::new(1u32, H160::from_slice(&[1; 20]), None);
+        log.add_step(Err("error".into()));
+        log.add_step(Err("another error".into()));
+        log.add_step(Err("error".into()));
+        log.add_step(Ok(1));
+        log.add_step(Ok(1));
+
+        assert_eq!(log.log().len(), 6);
+        assert!(log.log().iter().all(|entry| entry.repeat_count == 1));
+    }
+
+    #[test]
+    fn compressed_operation_log_roundtrip() {
+        MockContext::new().inject();
+
+        let mut log = OperationLog::new(1u32, H160::from_slice(&[1; 20]), Some([2; 32]));
+        for _ in 0..100 {
+            log.add_step(Err(TIMEOUT_ERROR.into()));
+            log.add_step(Err("failed to query EVM: nonce is too low".into()));
+        }
+        log.add_step_with_evm_params(2, Some(7));
+        log.add_step(Err("another error".into()));
+
+        let uncompressed = log.to_uncompressed_bytes();
+        let compressed = log.to_compressed_bytes();
+        assert!(!OperationLog::<u32>::is_compressed(&uncompressed));
+        assert!(OperationLog::<u32>::is_compressed(&compressed));
+        assert!(compressed.len() * 2 < uncompressed.len());
+
+        let decoded = OperationLog::<u32>::from_bytes(compressed.into());
+        assert_eq!(decoded.log().len(), log.log().len());
+        for (decoded, original) in decoded.log().iter().zip(log.log()) {
+            assert_eq!(decoded.time_stamp, original.time_stamp);
+            assert_eq!(decoded.step_result, original.step_result);
+            assert_eq!(decoded.repeat_count, original.repeat_count);
+            assert_eq!(decoded.evm_params_snapshot, original.evm_params_snapshot);
+        }
+        assert_eq!(decoded.current_step(), &2);
+        assert_eq!(decoded.wallet_address(), log.wallet_address());
+        assert_eq!(decoded.memo(), log.memo());
+    }
+
+    #[test]
+    fn fee_adjustment_is_set_once() {
+        MockContext::new().inject();
+
+        let adjustment = |charged: u64| FeeAdjustment {
+            operation_id: OperationId::new(1),
+            fee_payer: H160::from_slice(&[1; 20]),
+            tx_hash: H256::from_slice(&[2; 32]),
+            charged: charged.into(),
+            actual: 70u64.into(),
+            amount: FeeAdjustmentAmount::Credit((charged - 70).into()),
+            timestamp: 3,
+        };
+
+        let mut log = OperationLog::new(1u32, H160::from_slice(&[1; 20]), None);
+        assert!(log.fee_adjustment().is_none());
+        assert!(log.set_fee_adjustment(adjustment(100)));
+        assert!(!log.set_fee_adjustment(adjustment(200)));
+        assert_eq!(log.fee_adjustment(), Some(&adjustment(100)));
+
+        for bytes in [log.to_uncompressed_bytes(), log.to_compressed_bytes()] {
+            let decoded = OperationLog::<u32>::from_bytes(bytes.into());
+            assert_eq!(decoded.fee_adjustment(), Some(&adjustment(100)));
+        }
+    }
+
+    #[test]
+    fn anonymization_replaces_wallet_address_and_memo() {
+        MockContext::new().inject();
+
+        let mut log = OperationLog::new(1u32, H160::from_slice(&[1; 20]), Some([2; 32]));
+        log.add_step(Ok(2));
+        let marker = AnonymizationMarker {
+            pseudonym: H160::from_slice(&[3; 20]),
+            timestamp: 4,
+            audit_entry: 5,
+        };
+        log.anonymize(marker.clone());
+
+        for bytes in [log.to_uncompressed_bytes(), log.to_compressed_bytes()] {
+            let decoded = OperationLog::<u32>::from_bytes(bytes.into());
+            assert_eq!(decoded.wallet_address(), &marker.pseudonym);
+            assert_eq!(decoded.memo(), None);
+            assert_eq!(decoded.anonymization(), Some(&marker));
+            assert_eq!(decoded.log().len(), 2);
+            assert_eq!(decoded.current_step(), &2);
+        }
+    }
+}
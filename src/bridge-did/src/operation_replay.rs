@@ -0,0 +1,213 @@
+//! Replay log of the operation progress, captured to debug the operations which end up
+//! in an unexpected state.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode};
+use did::{H160, H256};
+use ethers_core::utils::keccak256;
+use ic_stable_structures::{Bound, Storable};
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::op_id::OperationId;
+
+/// Sampling rate, which captures all the operations.
+pub const FULL_REPLAY_SAMPLE_RATE_PPM: u32 = 1_000_000;
+
+/// Default number of the latest progress invocations kept per operation.
+pub const DEFAULT_REPLAY_LOG_SIZE: u32 = 16;
+
+/// Max number of the progress invocations kept per operation.
+pub const MAX_REPLAY_LOG_SIZE: u32 = 256;
+
+/// Settings of the operation progress capture. The capture is disabled by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct ReplayCaptureSettings {
+    /// Share of the operations to capture, in millionths. If zero, only the operations
+    /// with the capture enabled explicitly are captured.
+    pub sample_rate_ppm: u32,
+    /// Number of the latest progress invocations kept per operation. The oldest ones
+    /// are evicted.
+    pub log_size: u32,
+}
+
+impl ReplayCaptureSettings {
+    /// Checks if the sampling rate is not over [`FULL_REPLAY_SAMPLE_RATE_PPM`], and the log
+    /// size is between 1 and [`MAX_REPLAY_LOG_SIZE`].
+    pub fn is_valid(&self) -> bool {
+        self.sample_rate_ppm <= FULL_REPLAY_SAMPLE_RATE_PPM
+            && (1..=MAX_REPLAY_LOG_SIZE).contains(&self.log_size)
+    }
+
+    /// Checks if the operation is sampled for the capture. The operation is either sampled
+    /// for all its progress invocations, or for none of them.
+    pub fn samples(&self, operation_id: OperationId) -> bool {
+        if self.sample_rate_ppm == 0 {
+            return false;
+        }
+
+        let hash = keccak256(operation_id.as_u64().to_be_bytes());
+        let bucket = u64::from_be_bytes(hash[..8].try_into().expect("hash is 32 bytes long"))
+            % FULL_REPLAY_SAMPLE_RATE_PPM as u64;
+        bucket < self.sample_rate_ppm as u64
+    }
+}
+
+impl Default for ReplayCaptureSettings {
+    fn default() -> Self {
+        Self {
+            sample_rate_ppm: 0,
+            log_size: DEFAULT_REPLAY_LOG_SIZE,
+        }
+    }
+}
+
+/// Digest of the runtime context, which the operation progress observed.
+///
+/// Responses of the EVM RPC and ICRC ledger calls, made by the progress, are not intercepted
+/// by the runtime, so their hashes are not included, and the replay of such progress
+/// requires the calls to be stubbed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct ReplayContextDigest {
+    /// Index of the latest EVM params snapshot.
+    pub evm_params_index: Option<u64>,
+    /// Hash of the candid-encoded EVM params, if they are initialized.
+    pub evm_params_hash: Option<H256>,
+    /// Address of the signer, if it is already resolved.
+    pub signer_address: Option<H160>,
+}
+
+/// Outcome of the operation progress invocation. Operations are candid-encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum ProgressReplayOutcome {
+    Progress(Vec<u8>),
+    ProgressWithDependent {
+        update_to: Vec<u8>,
+        dependent: Vec<u8>,
+    },
+    AddToService(u64),
+    Error(Error),
+}
+
+/// Captured invocation of the operation progress.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct ProgressReplayEntry {
+    /// Sequential number of the captured invocation of the operation.
+    pub index: u64,
+    /// IC time of the invocation.
+    pub timestamp: u64,
+    /// Candid-encoded operation, passed to the progress.
+    pub input: Vec<u8>,
+    pub context: ReplayContextDigest,
+    pub outcome: ProgressReplayOutcome,
+}
+
+/// Latest captured progress invocations of an operation, oldest first.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct OperationReplay {
+    pub entries: Vec<ProgressReplayEntry>,
+    /// Index of the next captured invocation.
+    pub next_index: u64,
+}
+
+impl OperationReplay {
+    /// Appends the invocation, evicting the oldest ones to keep at most `log_size` of them.
+    pub fn push(
+        &mut self,
+        timestamp: u64,
+        input: Vec<u8>,
+        context: ReplayContextDigest,
+        outcome: ProgressReplayOutcome,
+        log_size: usize,
+    ) {
+        self.entries.push(ProgressReplayEntry {
+            index: self.next_index,
+            timestamp,
+            input,
+            context,
+            outcome,
+        });
+        self.next_index += 1;
+
+        let evicted = self.entries.len().saturating_sub(log_size);
+        self.entries.drain(..evicted);
+    }
+}
+
+impl Storable for OperationReplay {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode operation replay"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode operation replay")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> ReplayContextDigest {
+        ReplayContextDigest {
+            evm_params_index: Some(1),
+            evm_params_hash: None,
+            signer_address: None,
+        }
+    }
+
+    #[test]
+    fn sampling_follows_the_rate() {
+        let mut settings = ReplayCaptureSettings::default();
+        assert!(settings.is_valid());
+        assert!((0..1000).all(|id| !settings.samples(OperationId::new(id))));
+
+        settings.sample_rate_ppm = FULL_REPLAY_SAMPLE_RATE_PPM;
+        assert!((0..1000).all(|id| settings.samples(OperationId::new(id))));
+
+        settings.sample_rate_ppm = FULL_REPLAY_SAMPLE_RATE_PPM / 4;
+        let sampled = (0..10_000)
+            .filter(|id| settings.samples(OperationId::new(*id)))
+            .count();
+        assert!((2000..3000).contains(&sampled), "sampled {sampled}");
+
+        settings.sample_rate_ppm = FULL_REPLAY_SAMPLE_RATE_PPM + 1;
+        assert!(!settings.is_valid());
+    }
+
+    #[test]
+    fn log_size_should_be_bounded() {
+        let mut settings = ReplayCaptureSettings::default();
+        settings.log_size = 0;
+        assert!(!settings.is_valid());
+        settings.log_size = MAX_REPLAY_LOG_SIZE + 1;
+        assert!(!settings.is_valid());
+        settings.log_size = MAX_REPLAY_LOG_SIZE;
+        assert!(settings.is_valid());
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted() {
+        let mut replay = OperationReplay::default();
+        for i in 0..5u8 {
+            replay.push(
+                i as _,
+                vec![i],
+                context(),
+                ProgressReplayOutcome::AddToService(1),
+                3,
+            );
+        }
+
+        assert_eq!(replay.next_index, 5);
+        let indexes: Vec<_> = replay.entries.iter().map(|e| e.index).collect();
+        assert_eq!(indexes, vec![2, 3, 4]);
+        assert_eq!(replay.entries[0].input, vec![2]);
+
+        let decoded = OperationReplay::from_bytes(replay.to_bytes());
+        assert_eq!(decoded, replay);
+    }
+}
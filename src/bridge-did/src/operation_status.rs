@@ -0,0 +1,33 @@
+//! Compact status of the bridge operations, which is returned instead of the full
+//! operation logs by the batched status queries.
+
+use candid::CandidType;
+use did::H256;
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of operations, which status can be requested in a single batch.
+pub const MAX_OPERATIONS_STATUS_BATCH: u64 = 200;
+
+/// Class of the operation state, common for all the bridges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum OperationStateClass {
+    /// Operation is being progressed by the bridge.
+    InProgress,
+    /// The latest progress attempt failed. The operation will be retried.
+    Retrying,
+    /// Operation gave up waiting for another operation, and is not progressed until
+    /// the operator retries it.
+    Parked,
+    /// Operation is complete.
+    Complete,
+}
+
+/// Compact status of an operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct OperationStatusView {
+    pub state_class: OperationStateClass,
+    /// IC time in nanoseconds of the latest operation state transition or progress attempt.
+    pub last_transition_timestamp: u64,
+    /// Hashes of the EVM transactions, sent for the operation, in the order of sending.
+    pub tx_hashes: Vec<H256>,
+}
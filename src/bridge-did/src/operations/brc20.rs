@@ -8,7 +8,7 @@ use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::brc20_info::{Brc20Info, Brc20Tick};
 use crate::events::MintedEventData;
-use crate::order::{MintOrder, SignedOrders};
+use crate::order::{MintOrder, MintOrderV1, SignedOrders};
 
 #[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
 pub struct DepositRequest {
@@ -47,6 +47,62 @@ pub enum Brc20BridgeDepositOp {
     MintOrderConfirmed { data: MintedEventData },
 }
 
+/// Shape of [`Brc20BridgeOp`] before the chain ids of the mint orders were extended to 64 bits.
+#[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
+pub enum Brc20BridgeOpV1 {
+    Deposit(Brc20BridgeDepositOpV1),
+    Withdraw(Brc20BridgeWithdrawOp),
+}
+
+/// Shape of [`Brc20BridgeDepositOp`] with the [`MintOrderV1`] order.
+#[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
+pub enum Brc20BridgeDepositOpV1 {
+    AwaitInputs(DepositRequest),
+    AwaitConfirmations {
+        deposit: DepositRequest,
+        utxos: Vec<Utxo>,
+    },
+    SignMintOrder(MintOrderV1),
+    SendMintOrder(SignedOrders),
+    ConfirmMintOrder {
+        orders: SignedOrders,
+        tx_id: H256,
+    },
+    MintOrderConfirmed {
+        data: MintedEventData,
+    },
+}
+
+impl From<Brc20BridgeOpV1> for Brc20BridgeOp {
+    fn from(op: Brc20BridgeOpV1) -> Self {
+        let deposit = match op {
+            Brc20BridgeOpV1::Deposit(deposit) => deposit,
+            Brc20BridgeOpV1::Withdraw(withdraw) => return Brc20BridgeOp::Withdraw(withdraw),
+        };
+
+        Brc20BridgeOp::Deposit(match deposit {
+            Brc20BridgeDepositOpV1::AwaitInputs(request) => {
+                Brc20BridgeDepositOp::AwaitInputs(request)
+            }
+            Brc20BridgeDepositOpV1::AwaitConfirmations { deposit, utxos } => {
+                Brc20BridgeDepositOp::AwaitConfirmations { deposit, utxos }
+            }
+            Brc20BridgeDepositOpV1::SignMintOrder(order) => {
+                Brc20BridgeDepositOp::SignMintOrder(order.into())
+            }
+            Brc20BridgeDepositOpV1::SendMintOrder(orders) => {
+                Brc20BridgeDepositOp::SendMintOrder(orders)
+            }
+            Brc20BridgeDepositOpV1::ConfirmMintOrder { orders, tx_id } => {
+                Brc20BridgeDepositOp::ConfirmMintOrder { orders, tx_id }
+            }
+            Brc20BridgeDepositOpV1::MintOrderConfirmed { data } => {
+                Brc20BridgeDepositOp::MintOrderConfirmed { data }
+            }
+        })
+    }
+}
+
 /// BRC20 bridge withdraw operations
 #[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
 pub enum Brc20BridgeWithdrawOp {
@@ -3,7 +3,7 @@ use did::{H160, H256};
 use serde::{Deserialize, Serialize};
 
 use crate::events::{BurntEventData, MintedEventData};
-use crate::order::{MintOrder, SignedOrders};
+use crate::order::{MintOrder, MintOrderV1, SignedOrders};
 
 #[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
 pub enum BtcBridgeOp {
@@ -21,3 +21,57 @@ pub enum BtcBridgeOp {
     WithdrawBtc(BurntEventData),
     BtcWithdrawConfirmed { eth_address: H160 },
 }
+
+/// Shape of [`BtcBridgeOp`] before the chain ids of the mint orders were extended to 64 bits.
+#[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
+pub enum BtcBridgeOpV1 {
+    UpdateCkBtcBalance { eth_address: H160 },
+    CollectCkBtcBalance { eth_address: H160 },
+    TransferCkBtc { eth_address: H160, amount: u64 },
+    CreateMintOrder { eth_address: H160, amount: u64 },
+    SignMintOrder { order: MintOrderV1 },
+    MintErc20 { order: SignedOrders },
+    ConfirmErc20Mint { order: SignedOrders, tx_id: H256 },
+    Erc20MintConfirmed(MintedEventData),
+    WithdrawBtc(BurntEventData),
+    BtcWithdrawConfirmed { eth_address: H160 },
+}
+
+impl From<BtcBridgeOpV1> for BtcBridgeOp {
+    fn from(op: BtcBridgeOpV1) -> Self {
+        match op {
+            BtcBridgeOpV1::UpdateCkBtcBalance { eth_address } => {
+                BtcBridgeOp::UpdateCkBtcBalance { eth_address }
+            }
+            BtcBridgeOpV1::CollectCkBtcBalance { eth_address } => {
+                BtcBridgeOp::CollectCkBtcBalance { eth_address }
+            }
+            BtcBridgeOpV1::TransferCkBtc {
+                eth_address,
+                amount,
+            } => BtcBridgeOp::TransferCkBtc {
+                eth_address,
+                amount,
+            },
+            BtcBridgeOpV1::CreateMintOrder {
+                eth_address,
+                amount,
+            } => BtcBridgeOp::CreateMintOrder {
+                eth_address,
+                amount,
+            },
+            BtcBridgeOpV1::SignMintOrder { order } => BtcBridgeOp::SignMintOrder {
+                order: order.into(),
+            },
+            BtcBridgeOpV1::MintErc20 { order } => BtcBridgeOp::MintErc20 { order },
+            BtcBridgeOpV1::ConfirmErc20Mint { order, tx_id } => {
+                BtcBridgeOp::ConfirmErc20Mint { order, tx_id }
+            }
+            BtcBridgeOpV1::Erc20MintConfirmed(data) => BtcBridgeOp::Erc20MintConfirmed(data),
+            BtcBridgeOpV1::WithdrawBtc(event) => BtcBridgeOp::WithdrawBtc(event),
+            BtcBridgeOpV1::BtcWithdrawConfirmed { eth_address } => {
+                BtcBridgeOp::BtcWithdrawConfirmed { eth_address }
+            }
+        }
+    }
+}
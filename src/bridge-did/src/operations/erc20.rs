@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::bridge_side::BridgeSide;
 use crate::events::MintedEventData;
-use crate::order::{MintOrder, SignedOrders};
+use crate::order::{MintOrder, MintOrderV1, SignedOrders};
 
 /// Erc20 bridge operation.
 #[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
@@ -37,4 +37,52 @@ impl Erc20OpStage {
             Erc20OpStage::TokenMintConfirmed(_) => String::from("TokenMintConfirmed"),
         }
     }
+
+    /// Returns the signed mint order, if it should be sent to the EVM by the user.
+    pub fn order_awaiting_user(&self) -> Option<&SignedOrders> {
+        match self {
+            Erc20OpStage::ConfirmMint {
+                order,
+                tx_hash: None,
+            } => Some(order),
+            _ => None,
+        }
+    }
+}
+
+/// Shape of [`Erc20BridgeOp`] before the chain ids of the mint orders were extended to 64 bits.
+#[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
+pub struct Erc20BridgeOpV1 {
+    pub side: BridgeSide,
+    pub stage: Erc20OpStageV1,
+}
+
+/// Shape of [`Erc20OpStage`] with the [`MintOrderV1`] order.
+#[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
+pub enum Erc20OpStageV1 {
+    SignMintOrder(MintOrderV1),
+    SendMintTransaction(SignedOrders),
+    ConfirmMint {
+        order: SignedOrders,
+        tx_hash: Option<H256>,
+    },
+    TokenMintConfirmed(MintedEventData),
+}
+
+impl From<Erc20BridgeOpV1> for Erc20BridgeOp {
+    fn from(op: Erc20BridgeOpV1) -> Self {
+        let stage = match op.stage {
+            Erc20OpStageV1::SignMintOrder(order) => Erc20OpStage::SignMintOrder(order.into()),
+            Erc20OpStageV1::SendMintTransaction(order) => Erc20OpStage::SendMintTransaction(order),
+            Erc20OpStageV1::ConfirmMint { order, tx_hash } => {
+                Erc20OpStage::ConfirmMint { order, tx_hash }
+            }
+            Erc20OpStageV1::TokenMintConfirmed(data) => Erc20OpStage::TokenMintConfirmed(data),
+        };
+
+        Self {
+            side: op.side,
+            stage,
+        }
+    }
 }
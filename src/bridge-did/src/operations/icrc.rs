@@ -1,34 +1,298 @@
-use candid::{CandidType, Nat};
+use candid::{CandidType, Nat, Principal};
 use did::{H160, H256};
 use serde::{Deserialize, Serialize};
 
 use crate::events::{BurntEventData, MintedEventData};
-use crate::order::{MintOrder, SignedOrders};
+use crate::id256::{AccountId256, Id256};
+use crate::op_id::OperationId;
+use crate::order::{MintOrder, MintOrderV1, SignedOrders};
 use crate::reason::Icrc2Burn;
 
+/// State of an ICRC bridge operation.
+///
+/// Every in-progress state contains `pending_since` - IC time in nanoseconds,
+/// when the operation entered the state.
 #[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
 pub enum IcrcBridgeOp {
     // Deposit operations:
-    BurnIcrc2Tokens(Icrc2Burn),
+    /// Deposit waits for the sender confirmation until `expires_at`. No tokens are burnt yet.
+    PendingUserConfirmation {
+        burn: Icrc2Burn,
+        expires_at: u64,
+        pending_since: u64,
+    },
+    /// Deposit is aborted by the sender, or is expired before confirmation.
+    /// No tokens were burnt.
+    DepositAborted {
+        burn: Icrc2Burn,
+        expired: bool,
+    },
+    BurnIcrc2Tokens {
+        burn: Icrc2Burn,
+        pending_since: u64,
+    },
     SignMintOrder {
         order: MintOrder,
         is_refund: bool,
+        pending_since: u64,
     },
     SendMintTransaction {
         order: SignedOrders,
         is_refund: bool,
+        pending_since: u64,
     },
     ConfirmMint {
         order: SignedOrders,
         tx_hash: Option<H256>,
         is_refund: bool,
+        pending_since: u64,
     },
     WrappedTokenMintConfirmed(MintedEventData),
 
     // Withdraw operations:
+    MintIcrcTokens {
+        event: BurntEventData,
+        pending_since: u64,
+    },
+    IcrcMintConfirmed {
+        src_address: H160,
+        icrc_tx_id: Nat,
+        /// Memo of the ICRC transfer to the recipient, if it was requested by the burn.
+        icrc_memo: Option<Vec<u8>>,
+    },
+    /// ICRC tokens mint failed and will never be retried.
+    IcrcMintFailed {
+        src_address: H160,
+        reason: String,
+    },
+    /// Withdrawn ICRC tokens are minted to the holder account of the bridge, and the approval
+    /// of the spender is pending. Used by the approve delivery of the withdrawal.
+    ApproveIcrcTokens {
+        event: BurntEventData,
+        approval: IcrcApproval,
+        pending_since: u64,
+    },
+    /// Spender is approved to transfer the withdrawn tokens from the holder account
+    /// until the approval expires. Unclaimed tokens of the expired approval are swept back.
+    IcrcApprovalGranted {
+        src_address: H160,
+        approval: IcrcApproval,
+        icrc_tx_id: Nat,
+    },
+
+    // Sweep-back operations:
+    /// Transfer of the tokens of the expired approval of the `original_op_id` withdrawal
+    /// back to the bridge account. The `event` amount covers the `approval` amount and
+    /// the ledger fees of the delivery, so the refund is reduced by the fees share.
+    SweepIcrcApproval {
+        original_op_id: OperationId,
+        event: BurntEventData,
+        approval: IcrcApproval,
+        pending_since: u64,
+    },
+    /// Tokens of the expired approval are swept back, and the burnt wrapped tokens are refunded.
+    IcrcApprovalSwept {
+        src_address: H160,
+        icrc_tx_id: Nat,
+    },
+    /// Approval was used by the spender before it expired, so no tokens are swept back.
+    IcrcApprovalClaimed {
+        src_address: H160,
+    },
+
+    // Refund operations:
+    /// Refund of the burnt wrapped tokens. Progresses only after the
+    /// `original_op_id` operation mint is definitively failed.
+    RefundMint {
+        original_op_id: OperationId,
+        event: BurntEventData,
+        pending_since: u64,
+    },
+    /// Refund, which gave up waiting for the `original_op_id` operation. It is not
+    /// progressed until the operator retries it.
+    RefundMintParked {
+        original_op_id: OperationId,
+        event: BurntEventData,
+        pending_since: u64,
+    },
+}
+
+impl IcrcBridgeOp {
+    /// Returns IC time in nanoseconds, when the operation entered the current state.
+    /// Returns `None` if the operation is complete.
+    pub fn pending_since(&self) -> Option<u64> {
+        match self {
+            IcrcBridgeOp::PendingUserConfirmation { pending_since, .. }
+            | IcrcBridgeOp::BurnIcrc2Tokens { pending_since, .. }
+            | IcrcBridgeOp::SignMintOrder { pending_since, .. }
+            | IcrcBridgeOp::SendMintTransaction { pending_since, .. }
+            | IcrcBridgeOp::ConfirmMint { pending_since, .. }
+            | IcrcBridgeOp::MintIcrcTokens { pending_since, .. }
+            | IcrcBridgeOp::ApproveIcrcTokens { pending_since, .. }
+            | IcrcBridgeOp::SweepIcrcApproval { pending_since, .. }
+            | IcrcBridgeOp::RefundMint { pending_since, .. }
+            | IcrcBridgeOp::RefundMintParked { pending_since, .. } => Some(*pending_since),
+            IcrcBridgeOp::DepositAborted { .. }
+            | IcrcBridgeOp::WrappedTokenMintConfirmed(_)
+            | IcrcBridgeOp::IcrcMintConfirmed { .. }
+            | IcrcBridgeOp::IcrcMintFailed { .. }
+            | IcrcBridgeOp::IcrcApprovalGranted { .. }
+            | IcrcBridgeOp::IcrcApprovalSwept { .. }
+            | IcrcBridgeOp::IcrcApprovalClaimed { .. } => None,
+        }
+    }
+
+    /// Returns the ICRC token of the operation, if the current state contains it.
+    /// The first state of every operation contains the token.
+    pub fn icrc_token(&self) -> Option<Principal> {
+        match self {
+            IcrcBridgeOp::PendingUserConfirmation { burn, .. }
+            | IcrcBridgeOp::DepositAborted { burn, .. }
+            | IcrcBridgeOp::BurnIcrc2Tokens { burn, .. } => Some(burn.icrc2_token_principal),
+            IcrcBridgeOp::SignMintOrder { order, .. } => order.src_token.try_into().ok(),
+            IcrcBridgeOp::MintIcrcTokens { event, .. }
+            | IcrcBridgeOp::ApproveIcrcTokens { event, .. }
+            | IcrcBridgeOp::SweepIcrcApproval { event, .. }
+            | IcrcBridgeOp::RefundMint { event, .. }
+            | IcrcBridgeOp::RefundMintParked { event, .. } => {
+                Id256::try_from(event.to_token.as_slice())
+                    .and_then(Principal::try_from)
+                    .ok()
+            }
+            IcrcBridgeOp::SendMintTransaction { .. }
+            | IcrcBridgeOp::ConfirmMint { .. }
+            | IcrcBridgeOp::WrappedTokenMintConfirmed(_)
+            | IcrcBridgeOp::IcrcMintConfirmed { .. }
+            | IcrcBridgeOp::IcrcMintFailed { .. }
+            | IcrcBridgeOp::IcrcApprovalGranted { .. }
+            | IcrcBridgeOp::IcrcApprovalSwept { .. }
+            | IcrcBridgeOp::IcrcApprovalClaimed { .. } => None,
+        }
+    }
+
+    /// Returns the signed mint order, if it should be sent to the EVM by the user.
+    pub fn order_awaiting_user(&self) -> Option<&SignedOrders> {
+        match self {
+            IcrcBridgeOp::ConfirmMint {
+                order,
+                tx_hash: None,
+                ..
+            } => Some(order),
+            _ => None,
+        }
+    }
+}
+
+/// Allowance of the withdrawn ICRC tokens, held by the bridge for the spender.
+#[derive(Debug, Serialize, Deserialize, CandidType, Clone, PartialEq, Eq)]
+pub struct IcrcApproval {
+    /// Account of the bridge, which holds the withdrawn tokens.
+    pub holder: AccountId256,
+    /// Account, which is approved to transfer the tokens.
+    pub spender: AccountId256,
+    /// Approved amount. The holder account also keeps the fee of the transfer.
+    pub amount: Nat,
+    /// IC time in nanoseconds, when the approval expires.
+    pub expires_at: u64,
+}
+
+/// Shape of [`IcrcBridgeOp`] before the `pending_since` fields were added.
+///
+/// Operations, stored before the chain ids of the mint orders were extended to 64 bits,
+/// have the [`MintOrderV1`] orders.
+#[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
+pub enum IcrcBridgeOpV1<O = MintOrder> {
+    BurnIcrc2Tokens(Icrc2Burn),
+    SignMintOrder {
+        order: O,
+        is_refund: bool,
+    },
+    SendMintTransaction {
+        order: SignedOrders,
+        is_refund: bool,
+    },
+    ConfirmMint {
+        order: SignedOrders,
+        tx_hash: Option<H256>,
+        is_refund: bool,
+    },
+    WrappedTokenMintConfirmed(MintedEventData),
     MintIcrcTokens(BurntEventData),
     IcrcMintConfirmed {
         src_address: H160,
         icrc_tx_id: Nat,
     },
+    IcrcMintFailed {
+        src_address: H160,
+        reason: String,
+    },
+    RefundMint {
+        original_op_id: OperationId,
+        event: BurntEventData,
+    },
+}
+
+impl<O: Into<MintOrder>> IcrcBridgeOpV1<O> {
+    /// Converts the operation to the current shape. `pending_since` is the time,
+    /// when the operation entered the state.
+    pub fn into_current(self, pending_since: u64) -> IcrcBridgeOp {
+        match self {
+            IcrcBridgeOpV1::BurnIcrc2Tokens(burn) => IcrcBridgeOp::BurnIcrc2Tokens {
+                burn,
+                pending_since,
+            },
+            IcrcBridgeOpV1::SignMintOrder { order, is_refund } => IcrcBridgeOp::SignMintOrder {
+                order: order.into(),
+                is_refund,
+                pending_since,
+            },
+            IcrcBridgeOpV1::SendMintTransaction { order, is_refund } => {
+                IcrcBridgeOp::SendMintTransaction {
+                    order,
+                    is_refund,
+                    pending_since,
+                }
+            }
+            IcrcBridgeOpV1::ConfirmMint {
+                order,
+                tx_hash,
+                is_refund,
+            } => IcrcBridgeOp::ConfirmMint {
+                order,
+                tx_hash,
+                is_refund,
+                pending_since,
+            },
+            IcrcBridgeOpV1::WrappedTokenMintConfirmed(event) => {
+                IcrcBridgeOp::WrappedTokenMintConfirmed(event)
+            }
+            IcrcBridgeOpV1::MintIcrcTokens(event) => IcrcBridgeOp::MintIcrcTokens {
+                event,
+                pending_since,
+            },
+            IcrcBridgeOpV1::IcrcMintConfirmed {
+                src_address,
+                icrc_tx_id,
+            } => IcrcBridgeOp::IcrcMintConfirmed {
+                src_address,
+                icrc_tx_id,
+                icrc_memo: None,
+            },
+            IcrcBridgeOpV1::IcrcMintFailed {
+                src_address,
+                reason,
+            } => IcrcBridgeOp::IcrcMintFailed {
+                src_address,
+                reason,
+            },
+            IcrcBridgeOpV1::RefundMint {
+                original_op_id,
+                event,
+            } => IcrcBridgeOp::RefundMint {
+                original_op_id,
+                event,
+                pending_since,
+            },
+        }
+    }
 }
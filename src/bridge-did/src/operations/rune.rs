@@ -6,7 +6,7 @@ use ic_exports::ic_cdk::api::management_canister::bitcoin::Utxo;
 use serde::{Deserialize, Serialize};
 
 use crate::events::MintedEventData;
-use crate::order::{MintOrder, SignedOrders};
+use crate::order::{MintOrder, MintOrderV1, SignedOrders};
 use crate::runes::{DidTransaction, RuneName, RuneToWrap, RuneWithdrawalPayload};
 
 #[derive(Debug, Serialize, Deserialize, CandidType, Clone, PartialEq, Eq)]
@@ -55,3 +55,77 @@ pub enum RuneBridgeOp {
     Deposit(RuneBridgeDepositOp),
     Withdraw(RuneBridgeWithdrawOp),
 }
+
+/// Shape of [`RuneBridgeOp`] before the chain ids of the mint orders were extended to 64 bits.
+#[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum RuneBridgeOpV1 {
+    Deposit(RuneBridgeDepositOpV1),
+    Withdraw(RuneBridgeWithdrawOp),
+}
+
+/// Shape of [`RuneBridgeDepositOp`] with the [`MintOrderV1`] order.
+#[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
+pub enum RuneBridgeDepositOpV1 {
+    AwaitInputs {
+        dst_address: H160,
+        dst_tokens: HashMap<RuneName, H160>,
+        requested_amounts: Option<HashMap<RuneName, u128>>,
+    },
+    AwaitConfirmations {
+        dst_address: H160,
+        utxo: Utxo,
+        runes_to_wrap: Vec<RuneToWrap>,
+    },
+    SignMintOrder(MintOrderV1),
+    SendMintOrder(SignedOrders),
+    ConfirmMintOrder {
+        order: SignedOrders,
+        tx_id: H256,
+    },
+    MintOrderConfirmed {
+        data: MintedEventData,
+    },
+}
+
+impl From<RuneBridgeOpV1> for RuneBridgeOp {
+    fn from(op: RuneBridgeOpV1) -> Self {
+        let deposit = match op {
+            RuneBridgeOpV1::Deposit(deposit) => deposit,
+            RuneBridgeOpV1::Withdraw(withdraw) => return RuneBridgeOp::Withdraw(withdraw),
+        };
+
+        RuneBridgeOp::Deposit(match deposit {
+            RuneBridgeDepositOpV1::AwaitInputs {
+                dst_address,
+                dst_tokens,
+                requested_amounts,
+            } => RuneBridgeDepositOp::AwaitInputs {
+                dst_address,
+                dst_tokens,
+                requested_amounts,
+            },
+            RuneBridgeDepositOpV1::AwaitConfirmations {
+                dst_address,
+                utxo,
+                runes_to_wrap,
+            } => RuneBridgeDepositOp::AwaitConfirmations {
+                dst_address,
+                utxo,
+                runes_to_wrap,
+            },
+            RuneBridgeDepositOpV1::SignMintOrder(order) => {
+                RuneBridgeDepositOp::SignMintOrder(order.into())
+            }
+            RuneBridgeDepositOpV1::SendMintOrder(order) => {
+                RuneBridgeDepositOp::SendMintOrder(order)
+            }
+            RuneBridgeDepositOpV1::ConfirmMintOrder { order, tx_id } => {
+                RuneBridgeDepositOp::ConfirmMintOrder { order, tx_id }
+            }
+            RuneBridgeDepositOpV1::MintOrderConfirmed { data } => {
+                RuneBridgeDepositOp::MintOrderConfirmed { data }
+            }
+        })
+    }
+}
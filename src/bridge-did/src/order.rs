@@ -36,11 +36,11 @@ pub struct MintOrder {
     pub nonce: u32,
 
     /// ChainId of EVM on which user will send tokens to bridge.
-    pub sender_chain_id: u32,
+    pub sender_chain_id: u64,
 
     /// ChainId of EVM on which will send tokens to user.
     /// Used to prevent several cross-chain mints with the same order.
-    pub recipient_chain_id: u32,
+    pub recipient_chain_id: u64,
 
     /// Name of the token.
     pub name: [u8; 32],
@@ -59,10 +59,41 @@ pub struct MintOrder {
 
     /// Address of wallet from which fee will be charged.
     pub fee_payer: H160,
+
+    /// EIP-2612 permit, signed by the recipient, which should be forwarded
+    /// to the token after mint.
+    pub permit: Option<Erc20Permit>,
+}
+
+/// EIP-2612 permit of the wrapped token, signed by the token owner.
+/// Allows the spender to transfer the owner's tokens without an approval transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct Erc20Permit {
+    /// Amount of tokens the spender is allowed to transfer.
+    pub value: U256,
+
+    /// Timestamp in seconds, after which the permit is not valid.
+    pub deadline: u64,
+
+    /// Recovery id of the owner signature.
+    pub v: u8,
+
+    /// `r` value of the owner signature.
+    pub r: H256,
+
+    /// `s` value of the owner signature.
+    pub s: H256,
+}
+
+impl Erc20Permit {
+    /// Checks if the permit deadline is passed at the given timestamp in seconds.
+    pub fn is_expired(&self, now_secs: u64) -> bool {
+        self.deadline < now_secs
+    }
 }
 
 impl MintOrder {
-    pub const ENCODED_DATA_SIZE: usize = 269;
+    pub const ENCODED_DATA_SIZE: usize = 406;
     pub const SIGNED_ENCODED_DATA_SIZE: usize = Self::ENCODED_DATA_SIZE + 65;
 
     /// Encodes order data and signs it.
@@ -75,18 +106,24 @@ impl MintOrder {
     ///     96..116 bytes of recipient,             }
     ///     116..136 bytes of dst_token,            }
     ///     136..140 bytes of nonce,                } => signed data
-    ///     140..144 bytes of sender_chain_id,      }
-    ///     144..148 bytes of recipient_chain_id,   }
-    ///     148..180 bytes of name,                 }
-    ///     180..196 bytes of symbol,               }
-    ///     196..197 bytes of decimals,             }
-    ///     197..217 bytes of approve_address,      }
-    ///     217..249 bytes of approve_amount,       }
-    ///     249..269 bytes of fee_payer,            }
+    ///     140..148 bytes of sender_chain_id,      }
+    ///     148..156 bytes of recipient_chain_id,   }
+    ///     156..188 bytes of name,                 }
+    ///     188..204 bytes of symbol,               }
+    ///     204..205 bytes of decimals,             }
+    ///     205..225 bytes of approve_address,      }
+    ///     225..257 bytes of approve_amount,       }
+    ///     257..277 bytes of fee_payer,            }
+    ///     277..309 bytes of permit_value,         }
+    ///     309..341 bytes of permit_deadline,      }
+    ///     341..342 bytes of permit_v,             }
+    ///     342..374 bytes of permit_r,             }
+    ///     374..406 bytes of permit_s,             }
     /// ]
     /// ```
     ///
     /// All integers encoded in big-endian format.
+    /// Permit fields are zeroed if there is no permit.
     /// Signature signs KECCAK hash of the signed data.
     pub fn encode(&self) -> [u8; Self::ENCODED_DATA_SIZE] {
         let mut buf = [0; Self::ENCODED_DATA_SIZE];
@@ -97,14 +134,21 @@ impl MintOrder {
         buf[96..116].copy_from_slice(self.recipient.0.as_bytes());
         buf[116..136].copy_from_slice(self.dst_token.0.as_bytes());
         buf[136..140].copy_from_slice(&self.nonce.to_be_bytes());
-        buf[140..144].copy_from_slice(&self.sender_chain_id.to_be_bytes());
-        buf[144..148].copy_from_slice(&self.recipient_chain_id.to_be_bytes());
-        buf[148..180].copy_from_slice(&self.name);
-        buf[180..196].copy_from_slice(&self.symbol);
-        buf[196] = self.decimals;
-        buf[197..217].copy_from_slice(self.approve_spender.0.as_bytes());
-        buf[217..249].copy_from_slice(&self.approve_amount.to_big_endian());
-        buf[249..269].copy_from_slice(self.fee_payer.0.as_bytes());
+        buf[140..148].copy_from_slice(&self.sender_chain_id.to_be_bytes());
+        buf[148..156].copy_from_slice(&self.recipient_chain_id.to_be_bytes());
+        buf[156..188].copy_from_slice(&self.name);
+        buf[188..204].copy_from_slice(&self.symbol);
+        buf[204] = self.decimals;
+        buf[205..225].copy_from_slice(self.approve_spender.0.as_bytes());
+        buf[225..257].copy_from_slice(&self.approve_amount.to_big_endian());
+        buf[257..277].copy_from_slice(self.fee_payer.0.as_bytes());
+        if let Some(permit) = &self.permit {
+            buf[277..309].copy_from_slice(&permit.value.to_big_endian());
+            buf[333..341].copy_from_slice(&permit.deadline.to_be_bytes());
+            buf[341] = permit.v;
+            buf[342..374].copy_from_slice(permit.r.0.as_bytes());
+            buf[374..406].copy_from_slice(permit.s.0.as_bytes());
+        }
 
         buf
     }
@@ -119,15 +163,20 @@ impl MintOrder {
     ///     96..116 bytes of recipient,             }
     ///     116..136 bytes of dst_token,            }
     ///     136..140 bytes of nonce,                } => signed data
-    ///     140..144 bytes of sender_chain_id,      }
-    ///     144..148 bytes of recipient_chain_id,   }
-    ///     148..180 bytes of name,                 }
-    ///     180..196 bytes of symbol,               }
-    ///     196..197 bytes of decimals,             }
-    ///     197..217 bytes of approve_address,      }
-    ///     217..249 bytes of approve_amount,       }
-    ///     249..269 bytes of fee_payer,            }
-    ///     269..334 bytes of signature (r - 32 bytes, s - 32 bytes, v - 1 byte)
+    ///     140..148 bytes of sender_chain_id,      }
+    ///     148..156 bytes of recipient_chain_id,   }
+    ///     156..188 bytes of name,                 }
+    ///     188..204 bytes of symbol,               }
+    ///     204..205 bytes of decimals,             }
+    ///     205..225 bytes of approve_address,      }
+    ///     225..257 bytes of approve_amount,       }
+    ///     257..277 bytes of fee_payer,            }
+    ///     277..309 bytes of permit_value,         }
+    ///     309..341 bytes of permit_deadline,      }
+    ///     341..342 bytes of permit_v,             }
+    ///     342..374 bytes of permit_r,             }
+    ///     374..406 bytes of permit_s,             }
+    ///     406..471 bytes of signature (r - 32 bytes, s - 32 bytes, v - 1 byte)
     /// ]
     /// ```
     ///
@@ -169,14 +218,15 @@ impl MintOrder {
         let recipient = H160::from_slice(&data[96..116]);
         let dst_token = H160::from_slice(&data[116..136]);
         let nonce = u32::from_be_bytes(data[136..140].try_into().unwrap()); // exactly 4 bytes, as expected
-        let sender_chain_id = u32::from_be_bytes(data[140..144].try_into().unwrap()); // exactly 4 bytes, as expected
-        let recipient_chain_id = u32::from_be_bytes(data[144..148].try_into().unwrap()); // exactly 4 bytes, as expected
-        let name = data[148..180].try_into().unwrap(); // exactly 32 bytes, as expected
-        let symbol = data[180..196].try_into().unwrap(); // exactly 16 bytes, as expected
-        let decimals = data[196];
-        let approve_spender = H160::from_slice(&data[197..217]);
-        let approve_amount = U256::from_big_endian(&data[217..249]);
-        let fee_payer = H160::from_slice(&data[249..269]);
+        let sender_chain_id = u64::from_be_bytes(data[140..148].try_into().unwrap()); // exactly 8 bytes, as expected
+        let recipient_chain_id = u64::from_be_bytes(data[148..156].try_into().unwrap()); // exactly 8 bytes, as expected
+        let name = data[156..188].try_into().unwrap(); // exactly 32 bytes, as expected
+        let symbol = data[188..204].try_into().unwrap(); // exactly 16 bytes, as expected
+        let decimals = data[204];
+        let approve_spender = H160::from_slice(&data[205..225]);
+        let approve_amount = U256::from_big_endian(&data[225..257]);
+        let fee_payer = H160::from_slice(&data[257..277]);
+        let permit = EncodedOrderReader(data).get_permit();
 
         Some(Self {
             amount,
@@ -193,6 +243,7 @@ impl MintOrder {
             approve_spender,
             approve_amount,
             fee_payer,
+            permit,
         })
     }
 
@@ -203,6 +254,7 @@ impl MintOrder {
         }
 
         let decoded_data = Self::decode_data(data.as_ref())?;
+        decoded_data.validate_padding().ok()?;
         let signature =
             ethers_core::types::Signature::try_from(&data[Self::ENCODED_DATA_SIZE..][..65])
                 .ok()?
@@ -210,6 +262,149 @@ impl MintOrder {
 
         Some((decoded_data, signature))
     }
+
+    /// Removes the permit, if its deadline is passed at the given timestamp in seconds.
+    /// Returns the removed permit.
+    pub fn remove_expired_permit(&mut self, now_secs: u64) -> Option<Erc20Permit> {
+        match &self.permit {
+            Some(permit) if permit.is_expired(now_secs) => self.permit.take(),
+            _ => None,
+        }
+    }
+
+    /// Checks that the `name` and `symbol` strings are zero-padded up to the array size:
+    /// there are no non-zero bytes after the first null byte.
+    pub fn validate_padding(&self) -> BTFResult<()> {
+        validate_zero_padding("name", &self.name)?;
+        validate_zero_padding("symbol", &self.symbol)
+    }
+}
+
+/// Shape of [`MintOrder`] before the chain ids were extended to 64 bits and the permit was added.
+///
+/// Operations, stored before the change, contain orders of this shape, and the signed orders
+/// of these operations are encoded in its layout. Such orders can't be minted by the current
+/// bridge contract, so they are converted to the [`MintOrder`] and signed again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct MintOrderV1 {
+    pub amount: U256,
+    pub sender: Id256,
+    pub src_token: Id256,
+    pub recipient: H160,
+    pub dst_token: H160,
+    pub nonce: u32,
+    pub sender_chain_id: u32,
+    pub recipient_chain_id: u32,
+    pub name: [u8; 32],
+    pub symbol: [u8; 16],
+    pub decimals: u8,
+    pub approve_spender: H160,
+    pub approve_amount: U256,
+    pub fee_payer: H160,
+}
+
+impl MintOrderV1 {
+    pub const ENCODED_DATA_SIZE: usize = 269;
+
+    /// Encodes order data in the legacy layout:
+    /// ```ignore
+    /// [
+    ///     0..32 bytes of amount,
+    ///     32..64 bytes of sender,
+    ///     64..96 bytes of src_token,
+    ///     96..116 bytes of recipient,
+    ///     116..136 bytes of dst_token,
+    ///     136..140 bytes of nonce,
+    ///     140..144 bytes of sender_chain_id,
+    ///     144..148 bytes of recipient_chain_id,
+    ///     148..180 bytes of name,
+    ///     180..196 bytes of symbol,
+    ///     196..197 bytes of decimals,
+    ///     197..217 bytes of approve_address,
+    ///     217..249 bytes of approve_amount,
+    ///     249..269 bytes of fee_payer,
+    /// ]
+    /// ```
+    pub fn encode(&self) -> [u8; Self::ENCODED_DATA_SIZE] {
+        let mut buf = [0; Self::ENCODED_DATA_SIZE];
+
+        buf[..32].copy_from_slice(&self.amount.to_big_endian());
+        buf[32..64].copy_from_slice(self.sender.0.as_slice());
+        buf[64..96].copy_from_slice(self.src_token.0.as_slice());
+        buf[96..116].copy_from_slice(self.recipient.0.as_bytes());
+        buf[116..136].copy_from_slice(self.dst_token.0.as_bytes());
+        buf[136..140].copy_from_slice(&self.nonce.to_be_bytes());
+        buf[140..144].copy_from_slice(&self.sender_chain_id.to_be_bytes());
+        buf[144..148].copy_from_slice(&self.recipient_chain_id.to_be_bytes());
+        buf[148..180].copy_from_slice(&self.name);
+        buf[180..196].copy_from_slice(&self.symbol);
+        buf[196] = self.decimals;
+        buf[197..217].copy_from_slice(self.approve_spender.0.as_bytes());
+        buf[217..249].copy_from_slice(&self.approve_amount.to_big_endian());
+        buf[249..269].copy_from_slice(self.fee_payer.0.as_bytes());
+
+        buf
+    }
+
+    /// Decode Self from bytes, encoded in the legacy layout.
+    pub fn decode_data(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::ENCODED_DATA_SIZE {
+            return None;
+        }
+
+        Some(Self {
+            amount: U256::from_big_endian(&data[..32]),
+            sender: data[32..64].try_into().unwrap(), // exactly 32 bytes, as expected
+            src_token: data[64..96].try_into().unwrap(), // exactly 32 bytes, as expected
+            recipient: H160::from_slice(&data[96..116]),
+            dst_token: H160::from_slice(&data[116..136]),
+            nonce: u32::from_be_bytes(data[136..140].try_into().unwrap()), // exactly 4 bytes, as expected
+            sender_chain_id: u32::from_be_bytes(data[140..144].try_into().unwrap()), // exactly 4 bytes, as expected
+            recipient_chain_id: u32::from_be_bytes(data[144..148].try_into().unwrap()), // exactly 4 bytes, as expected
+            name: data[148..180].try_into().unwrap(), // exactly 32 bytes, as expected
+            symbol: data[180..196].try_into().unwrap(), // exactly 16 bytes, as expected
+            decimals: data[196],
+            approve_spender: H160::from_slice(&data[197..217]),
+            approve_amount: U256::from_big_endian(&data[217..249]),
+            fee_payer: H160::from_slice(&data[249..269]),
+        })
+    }
+}
+
+impl From<MintOrderV1> for MintOrder {
+    fn from(order: MintOrderV1) -> Self {
+        Self {
+            amount: order.amount,
+            sender: order.sender,
+            src_token: order.src_token,
+            recipient: order.recipient,
+            dst_token: order.dst_token,
+            nonce: order.nonce,
+            sender_chain_id: order.sender_chain_id.into(),
+            recipient_chain_id: order.recipient_chain_id.into(),
+            name: order.name,
+            symbol: order.symbol,
+            decimals: order.decimals,
+            approve_spender: order.approve_spender,
+            approve_amount: order.approve_amount,
+            fee_payer: order.fee_payer,
+            permit: None,
+        }
+    }
+}
+
+fn validate_zero_padding(field: &str, bytes: &[u8]) -> BTFResult<()> {
+    let Some(padding_start) = bytes.iter().position(|byte| *byte == 0) else {
+        return Ok(());
+    };
+
+    match bytes[padding_start..].iter().position(|byte| *byte != 0) {
+        Some(offset) => Err(Error::Serialization(format!(
+            "mint order {field} has non-zero byte in the padding at position {}",
+            padding_start + offset
+        ))),
+        None => Ok(()),
+    }
 }
 
 pub fn fit_str_to_array<const SIZE: usize>(s: &str) -> [u8; SIZE] {
@@ -358,49 +553,88 @@ impl EncodedOrderReader<'_> {
     }
 
     /// Returns sender chain ID.
-    pub fn get_sender_chain_id(&self) -> u32 {
-        u32::from_be_bytes(self.0[140..144].try_into().unwrap()) // exactly 4 bytes, as expected
+    pub fn get_sender_chain_id(&self) -> u64 {
+        u64::from_be_bytes(self.0[140..148].try_into().unwrap()) // exactly 8 bytes, as expected
     }
 
     /// Returns recipient chain ID.
-    pub fn get_recipient_chain_id(&self) -> u32 {
-        u32::from_be_bytes(self.0[144..148].try_into().unwrap()) // exactly 4 bytes, as expected
+    pub fn get_recipient_chain_id(&self) -> u64 {
+        u64::from_be_bytes(self.0[148..156].try_into().unwrap()) // exactly 8 bytes, as expected
     }
 
     /// Returns token name.
     pub fn get_token_name(&self) -> [u8; 32] {
-        self.0[148..180].try_into().unwrap() // exactly 32 bytes, as expected
+        self.0[156..188].try_into().unwrap() // exactly 32 bytes, as expected
     }
 
     /// Returns token symbol.
     pub fn get_token_symbol(&self) -> [u8; 16] {
-        self.0[180..196].try_into().unwrap() // exactly 16 bytes, as expected
+        self.0[188..204].try_into().unwrap() // exactly 16 bytes, as expected
     }
 
     /// Returns token decimals.
     pub fn get_token_decimals(&self) -> u8 {
-        self.0[196]
+        self.0[204]
     }
 
     /// Returns approve spender.
     pub fn get_approve_spender(&self) -> H160 {
-        H160::from_slice(&self.0[197..217])
+        H160::from_slice(&self.0[205..225])
     }
 
     /// Returns approve amount.
     pub fn get_approve_amount(&self) -> U256 {
-        U256::from_big_endian(&self.0[217..249])
+        U256::from_big_endian(&self.0[225..257])
     }
 
     /// Returns fee payer.
     pub fn get_fee_payer(&self) -> H160 {
-        H160::from_slice(&self.0[249..269])
+        H160::from_slice(&self.0[257..277])
+    }
+
+    /// Returns permit, if the order has one. Orders without permit have zero deadline.
+    pub fn get_permit(&self) -> Option<Erc20Permit> {
+        let deadline = u64::from_be_bytes(self.0[333..341].try_into().unwrap()); // exactly 8 bytes, as expected
+        if deadline == 0 {
+            return None;
+        }
+
+        Some(Erc20Permit {
+            value: U256::from_big_endian(&self.0[277..309]),
+            deadline,
+            v: self.0[341],
+            r: H256::from_slice(&self.0[342..374]),
+            s: H256::from_slice(&self.0[374..406]),
+        })
     }
 }
 
 /// Length of ECDSA signature in bytes.
 pub const SIGNATURE_LEN: usize = 65;
 
+/// Gas limit of the `batchMint` transactions.
+pub const DEFAULT_TX_GAS_LIMIT: u64 = 3_000_000;
+
+/// Parameters for EVM transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxParams {
+    pub from: H160,
+    pub to: H160,
+    pub nonce: U256,
+    pub gas_price: U256,
+    pub gas_limit: u64,
+    pub value: U256,
+    pub chain_id: u64,
+}
+
+impl TxParams {
+    /// Sets gas limit of the transaction.
+    pub fn with_gas_limit(mut self, limit: u64) -> Self {
+        self.gas_limit = limit;
+        self
+    }
+}
+
 /// Encoded signed orders data
 #[derive(Debug, Clone, Serialize, Deserialize, CandidType, PartialEq, Eq)]
 pub struct SignedOrdersData {
@@ -416,13 +650,18 @@ impl SignedOrdersData {
 
     /// Read data of MintOrder with the given index.
     pub fn reader(&self, order_idx: usize) -> Option<EncodedOrderReader<'_>> {
+        self.order_data(order_idx).map(EncodedOrderReader)
+    }
+
+    /// Returns encoded data of MintOrder with the given index.
+    pub fn order_data(&self, order_idx: usize) -> Option<&[u8]> {
         let data_start = order_idx * MintOrder::ENCODED_DATA_SIZE;
         let data_end = data_start + MintOrder::ENCODED_DATA_SIZE;
         if data_end > self.orders_data.len() {
             return None;
         }
 
-        Some(EncodedOrderReader(&self.orders_data[data_start..data_end]))
+        Some(&self.orders_data[data_start..data_end])
     }
 
     /// Returns digest of the orders data.
@@ -440,6 +679,9 @@ pub type OrderIdx = usize;
 pub struct SignedOrders {
     all_orders: SignedOrdersData,
     idx: OrderIdx,
+    /// Whether the orders of the batch must be minted together by a single transaction.
+    #[serde(default)]
+    atomic: Option<bool>,
 }
 
 impl SignedOrders {
@@ -449,7 +691,22 @@ impl SignedOrders {
             return None;
         }
 
-        Some(Self { all_orders, idx })
+        Some(Self {
+            all_orders,
+            idx,
+            atomic: None,
+        })
+    }
+
+    /// Marks the batch as atomic: all its orders must be minted by a single transaction.
+    pub fn with_atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic.then_some(true);
+        self
+    }
+
+    /// Checks if all the batch orders must be minted by a single transaction.
+    pub fn is_atomic(&self) -> bool {
+        self.atomic.unwrap_or_default()
     }
 
     /// Returnt reader of encoded order fields.
@@ -473,16 +730,81 @@ impl SignedOrders {
     pub fn idx(&self) -> OrderIdx {
         self.idx
     }
+
+    /// Decodes the order, if the batch is encoded in the layout of the [`MintOrderV1`].
+    /// Returns `None` for the batches of the current layout.
+    pub fn legacy_order(&self) -> Option<MintOrder> {
+        let data = &self.all_orders.orders_data;
+        if data.len() % MintOrder::ENCODED_DATA_SIZE == 0
+            || data.len() % MintOrderV1::ENCODED_DATA_SIZE != 0
+        {
+            return None;
+        }
+
+        let data_start = self.idx * MintOrderV1::ENCODED_DATA_SIZE;
+        data.get(data_start..)
+            .and_then(MintOrderV1::decode_data)
+            .map(MintOrder::from)
+    }
 }
 
+#[cfg(test)]
+mod test_vectors;
+
 #[cfg(test)]
 mod tests {
-    use did::{H160, U256};
+    use did::{H160, H256, U256};
     use eth_signer::sign_strategy::SigningStrategy;
 
-    use super::MintOrder;
+    use super::{
+        fit_str_to_array, Erc20Permit, MintOrder, MintOrderV1, SignedOrders, SignedOrdersData,
+    };
     use crate::id256::Id256;
 
+    /// Offsets of the `name` and `symbol` arrays in the encoded order.
+    const NAME_OFFSET: usize = 156;
+    const SYMBOL_OFFSET: usize = 188;
+
+    /// Deterministic xorshift generator for the fuzz tests.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: usize) -> usize {
+            (self.next() % bound as u64) as usize
+        }
+
+        fn non_zero_byte(&mut self) -> u8 {
+            (self.below(255) + 1) as u8
+        }
+    }
+
+    fn order_with(name: &str, symbol: &str) -> MintOrder {
+        MintOrder {
+            amount: U256::one(),
+            sender: Id256::from_evm_address(&H160::from_slice(&[1; 20]), 1),
+            src_token: Id256::from_evm_address(&H160::from_slice(&[2; 20]), 2),
+            recipient: H160::from_slice(&[3; 20]),
+            dst_token: H160::from_slice(&[4; 20]),
+            nonce: 42,
+            sender_chain_id: 43,
+            recipient_chain_id: 44,
+            name: fit_str_to_array(name),
+            symbol: fit_str_to_array(symbol),
+            decimals: 47,
+            approve_spender: H160::from_slice(&[5; 20]),
+            approve_amount: 48u64.into(),
+            fee_payer: H160::from_slice(&[6; 20]),
+            permit: None,
+        }
+    }
+
     #[tokio::test]
     async fn signed_mint_order_getters() {
         let order = MintOrder {
@@ -500,6 +822,7 @@ mod tests {
             approve_spender: H160::from_slice(&[5; 20]),
             approve_amount: 48u64.into(),
             fee_payer: H160::from_slice(&[6; 20]),
+            permit: None,
         };
 
         let signer = SigningStrategy::Local {
@@ -524,5 +847,249 @@ mod tests {
         assert_eq!(order.approve_spender, reader.get_approve_spender());
         assert_eq!(order.approve_amount, reader.get_approve_amount());
         assert_eq!(order.fee_payer, reader.get_fee_payer());
+        assert_eq!(order.permit, reader.get_permit());
+    }
+
+    #[tokio::test]
+    async fn mint_order_with_max_chain_ids_roundtrip() {
+        let order = MintOrder {
+            amount: U256::one(),
+            sender: Id256::from_evm_address(&H160::from_slice(&[1; 20]), 1),
+            src_token: Id256::from_evm_address(&H160::from_slice(&[2; 20]), 2),
+            recipient: H160::from_slice(&[3; 20]),
+            dst_token: H160::from_slice(&[4; 20]),
+            nonce: 42,
+            sender_chain_id: u64::MAX,
+            recipient_chain_id: u64::MAX - 1,
+            name: [45; 32],
+            symbol: [46; 16],
+            decimals: 47,
+            approve_spender: H160::from_slice(&[5; 20]),
+            approve_amount: 48u64.into(),
+            fee_payer: H160::from_slice(&[6; 20]),
+            permit: None,
+        };
+
+        let encoded = order.encode();
+        assert_eq!(encoded[140..148], u64::MAX.to_be_bytes());
+        assert_eq!(encoded[148..156], (u64::MAX - 1).to_be_bytes());
+        assert_eq!(MintOrder::decode_data(&encoded), Some(order.clone()));
+
+        let signer = SigningStrategy::Local {
+            private_key: [42; 32],
+        }
+        .make_signer(0)
+        .unwrap();
+        let signed_order = order.encode_and_sign(&signer).await.unwrap();
+
+        let reader = signed_order.reader();
+        assert_eq!(reader.get_sender_chain_id(), u64::MAX);
+        assert_eq!(reader.get_recipient_chain_id(), u64::MAX - 1);
+        assert_eq!(reader.get_fee_payer(), order.fee_payer);
+        assert_eq!(
+            MintOrder::decode_signed(&signed_order).map(|(decoded, _)| decoded),
+            Some(order)
+        );
+    }
+
+    #[tokio::test]
+    async fn mint_order_with_permit_roundtrip() {
+        let permit = Erc20Permit {
+            value: U256::from(1000u64),
+            deadline: u64::MAX,
+            v: 27,
+            r: H256::from_slice(&[7; 32]),
+            s: H256::from_slice(&[8; 32]),
+        };
+        let order = MintOrder {
+            permit: Some(permit.clone()),
+            ..order_with("Token", "TKN")
+        };
+
+        let encoded = order.encode();
+        assert_eq!(encoded[277..309], permit.value.to_big_endian());
+        assert_eq!(encoded[309..333], [0; 24]);
+        assert_eq!(encoded[333..341], u64::MAX.to_be_bytes());
+        assert_eq!(encoded[341], 27);
+        assert_eq!(MintOrder::decode_data(&encoded), Some(order.clone()));
+
+        let signer = SigningStrategy::Local {
+            private_key: [42; 32],
+        }
+        .make_signer(0)
+        .unwrap();
+        let signed_order = order.encode_and_sign(&signer).await.unwrap();
+        assert_eq!(signed_order.reader().get_permit(), Some(permit));
+        assert_eq!(
+            MintOrder::decode_signed(&signed_order).map(|(decoded, _)| decoded),
+            Some(order)
+        );
+
+        // Orders without permit have zeroed permit fields.
+        let order = order_with("Token", "TKN");
+        let encoded = order.encode();
+        assert_eq!(encoded[277..], [0; 129]);
+        assert_eq!(MintOrder::decode_data(&encoded), Some(order));
+    }
+
+    #[test]
+    fn permit_should_expire_after_deadline() {
+        let permit = Erc20Permit {
+            value: U256::one(),
+            deadline: 100,
+            v: 28,
+            r: H256::default(),
+            s: H256::default(),
+        };
+
+        assert!(!permit.is_expired(99));
+        assert!(!permit.is_expired(100));
+        assert!(permit.is_expired(101));
+
+        let mut order = MintOrder {
+            permit: Some(permit.clone()),
+            ..order_with("Token", "TKN")
+        };
+        assert_eq!(order.remove_expired_permit(100), None);
+        assert_eq!(order.permit, Some(permit.clone()));
+        assert_eq!(order.remove_expired_permit(101), Some(permit));
+        assert_eq!(order.permit, None);
+    }
+
+    #[test]
+    fn should_accept_zero_padded_strings() {
+        assert!(order_with("", "").validate_padding().is_ok());
+        assert!(order_with("Wrapped ICP", "wICP").validate_padding().is_ok());
+
+        let mut full = order_with("", "");
+        full.name = [45; 32];
+        full.symbol = [46; 16];
+        assert!(full.validate_padding().is_ok());
+    }
+
+    #[test]
+    fn should_reject_non_zero_padding() {
+        let mut order = order_with("ordi", "ORDI");
+        order.name[31] = 1;
+        assert!(order.validate_padding().is_err());
+
+        let mut order = order_with("ordi", "ORDI");
+        order.symbol[5] = b'X';
+        assert!(order.validate_padding().is_err());
+
+        // The first byte is null, so all the array is padding.
+        let mut order = order_with("", "");
+        order.name[1] = 1;
+        assert!(order.validate_padding().is_err());
+    }
+
+    #[test]
+    fn fuzz_non_zero_padding_is_rejected() {
+        let mut rng = Rng(0x5eed_1234_abcd_ef01);
+
+        for _ in 0..1000 {
+            let mut name = [0u8; 32];
+            let name_len = rng.below(32);
+            name[..name_len]
+                .iter_mut()
+                .for_each(|b| *b = rng.non_zero_byte());
+
+            let mut symbol = [0u8; 16];
+            let symbol_len = rng.below(16);
+            symbol[..symbol_len]
+                .iter_mut()
+                .for_each(|b| *b = rng.non_zero_byte());
+
+            let mut order = order_with("", "");
+            order.name = name;
+            order.symbol = symbol;
+            assert!(order.validate_padding().is_ok());
+
+            // Inject a non-zero byte after the terminating null byte.
+            let mut tampered = order.clone();
+            if rng.next() % 2 == 0 && name_len < 31 {
+                let pos = name_len + 1 + rng.below(31 - name_len);
+                tampered.name[pos] = rng.non_zero_byte();
+            } else if symbol_len < 15 {
+                let pos = symbol_len + 1 + rng.below(15 - symbol_len);
+                tampered.symbol[pos] = rng.non_zero_byte();
+            } else {
+                continue;
+            }
+            assert!(
+                tampered.validate_padding().is_err(),
+                "non-zero padding accepted: {tampered:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn fuzz_signed_order_with_non_zero_padding_is_not_decoded() {
+        let order = order_with("Token", "TKN");
+        let signer = SigningStrategy::Local {
+            private_key: [42; 32],
+        }
+        .make_signer(0)
+        .unwrap();
+        let signed_order = order.encode_and_sign(&signer).await.unwrap();
+        assert!(MintOrder::decode_signed(&signed_order).is_some());
+
+        let mut rng = Rng(0xfeed_5678_9abc_def0);
+        for _ in 0..1000 {
+            let pos = match rng.next() % 2 {
+                0 => NAME_OFFSET + "Token".len() + 1 + rng.below(32 - "Token".len() - 1),
+                _ => SYMBOL_OFFSET + "TKN".len() + 1 + rng.below(16 - "TKN".len() - 1),
+            };
+
+            let mut tampered = signed_order;
+            tampered.0[pos] = rng.non_zero_byte();
+            assert!(
+                MintOrder::decode_signed(&tampered).is_none(),
+                "order with non-zero padding at {pos} is decoded"
+            );
+        }
+    }
+
+    #[test]
+    fn legacy_layout_order_is_decoded() {
+        let legacy = MintOrderV1 {
+            amount: U256::one(),
+            sender: Id256::from_evm_address(&H160::from_slice(&[1; 20]), 1),
+            src_token: Id256::from_evm_address(&H160::from_slice(&[2; 20]), 2),
+            recipient: H160::from_slice(&[3; 20]),
+            dst_token: H160::from_slice(&[4; 20]),
+            nonce: 42,
+            sender_chain_id: 43,
+            recipient_chain_id: 44,
+            name: fit_str_to_array("Token"),
+            symbol: fit_str_to_array("TKN"),
+            decimals: 47,
+            approve_spender: H160::from_slice(&[5; 20]),
+            approve_amount: 48u64.into(),
+            fee_payer: H160::from_slice(&[6; 20]),
+        };
+        let mut second = legacy.clone();
+        second.nonce = 43;
+
+        let orders_data = [legacy.encode(), second.encode()].concat();
+        let signed = SignedOrders {
+            all_orders: SignedOrdersData {
+                orders_data,
+                signature: vec![0; 65],
+            },
+            idx: 1,
+            atomic: None,
+        };
+        assert_eq!(signed.legacy_order(), Some(MintOrder::from(second)));
+
+        let current = SignedOrders::new(
+            SignedOrdersData {
+                orders_data: order_with("Token", "TKN").encode().to_vec(),
+                signature: vec![0; 65],
+            },
+            0,
+        )
+        .unwrap();
+        assert_eq!(current.legacy_order(), None);
     }
 }
@@ -0,0 +1,195 @@
+//! Known `MintOrder` encodings, shared with other implementations of the order format
+//! (e.g. the `BTFBridge` contract) to check their compatibility.
+//!
+//! To regenerate the golden file after an intended change of the format, run the test with the
+//! `UPDATE_TEST_VECTORS` environment variable set.
+
+use std::path::PathBuf;
+
+use candid::Principal;
+use did::{H160, H256, U256};
+
+use super::{fit_str_to_array, Erc20Permit, MintOrder};
+use crate::id256::Id256;
+
+const VECTORS_FILE: &str = "test-vectors/mint_order_vectors.hex";
+const UPDATE_VECTORS_ENV: &str = "UPDATE_TEST_VECTORS";
+
+fn address(byte: u8) -> H160 {
+    H160::from_slice(&[byte; 20])
+}
+
+fn base_order() -> MintOrder {
+    MintOrder {
+        amount: U256::one(),
+        sender: Id256::from_evm_address(&address(1), 1),
+        src_token: Id256::from_evm_address(&address(2), 2),
+        recipient: address(3),
+        dst_token: address(4),
+        nonce: 42,
+        sender_chain_id: 43,
+        recipient_chain_id: 44,
+        name: [45; 32],
+        symbol: [46; 16],
+        decimals: 47,
+        approve_spender: address(5),
+        approve_amount: 48u64.into(),
+        fee_payer: address(6),
+        permit: None,
+    }
+}
+
+fn vectors() -> Vec<MintOrder> {
+    let zero = MintOrder {
+        amount: U256::zero(),
+        sender: Id256([0; 32]),
+        src_token: Id256([0; 32]),
+        recipient: H160::default(),
+        dst_token: H160::default(),
+        nonce: 0,
+        sender_chain_id: 0,
+        recipient_chain_id: 0,
+        name: [0; 32],
+        symbol: [0; 16],
+        decimals: 0,
+        approve_spender: H160::default(),
+        approve_amount: U256::zero(),
+        fee_payer: H160::default(),
+        permit: None,
+    };
+
+    let max = MintOrder {
+        amount: U256::from_big_endian(&[0xff; 32]),
+        sender: Id256([0xff; 32]),
+        src_token: Id256([0xff; 32]),
+        recipient: address(0xff),
+        dst_token: address(0xff),
+        nonce: u32::MAX,
+        sender_chain_id: u64::MAX,
+        recipient_chain_id: u64::MAX,
+        name: [0xff; 32],
+        symbol: [0xff; 16],
+        decimals: u8::MAX,
+        approve_spender: address(0xff),
+        approve_amount: U256::from_big_endian(&[0xff; 32]),
+        fee_payer: address(0xff),
+        permit: Some(Erc20Permit {
+            value: U256::from_big_endian(&[0xff; 32]),
+            deadline: u64::MAX,
+            v: u8::MAX,
+            r: H256::from_slice(&[0xff; 32]),
+            s: H256::from_slice(&[0xff; 32]),
+        }),
+    };
+
+    let icrc_deposit = MintOrder {
+        amount: 100_000_000u64.into(),
+        sender: Id256::from(Principal::from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10])),
+        src_token: Id256::from(Principal::from_slice(&[0, 0, 0, 0, 0, 0, 0, 2, 1, 1])),
+        name: fit_str_to_array("Wrapped ICP"),
+        symbol: fit_str_to_array("wICP"),
+        decimals: 8,
+        ..base_order()
+    };
+
+    let brc20_deposit = MintOrder {
+        amount: 1_000_000_000_000_000_000u64.into(),
+        sender: Id256::from_brc20_tick(*b"ordi"),
+        src_token: Id256::from_brc20_tick(*b"ordi"),
+        name: fit_str_to_array("ordi"),
+        symbol: fit_str_to_array("ORDI"),
+        decimals: 18,
+        ..base_order()
+    };
+
+    let cross_chain = MintOrder {
+        amount: u64::MAX.into(),
+        sender: Id256::from_evm_address(&address(0x11), 355113),
+        src_token: Id256::from_evm_address(&address(0x22), 355113),
+        sender_chain_id: 355113,
+        recipient_chain_id: 1,
+        nonce: 1,
+        ..base_order()
+    };
+
+    let native_token = MintOrder {
+        src_token: Id256::from_evm_address(&Id256::native_address(), 355113),
+        dst_token: Id256::native_address(),
+        ..base_order()
+    };
+
+    let with_approve = MintOrder {
+        approve_spender: address(0xa1),
+        approve_amount: U256::from_big_endian(&[0xff; 32]),
+        fee_payer: address(0xfe),
+        ..base_order()
+    };
+
+    let truncated_name = MintOrder {
+        name: fit_str_to_array("A token with name longer than 32 bytes"),
+        symbol: fit_str_to_array("SYMBOL_LONGER_THAN_16"),
+        ..base_order()
+    };
+
+    let utf8_name = MintOrder {
+        name: fit_str_to_array("Токен с юникод именем"),
+        symbol: fit_str_to_array("ТОКЕН"),
+        ..base_order()
+    };
+
+    let with_permit = MintOrder {
+        approve_spender: address(0xa1),
+        approve_amount: U256::zero(),
+        permit: Some(Erc20Permit {
+            value: 1_000_000u64.into(),
+            deadline: 1_700_000_000,
+            v: 27,
+            r: H256::from_slice(&[0x5a; 32]),
+            s: H256::from_slice(&[0x3c; 32]),
+        }),
+        ..base_order()
+    };
+
+    vec![
+        zero,
+        base_order(),
+        max,
+        icrc_deposit,
+        brc20_deposit,
+        cross_chain,
+        native_token,
+        with_approve,
+        truncated_name,
+        utf8_name,
+        with_permit,
+    ]
+}
+
+#[test]
+fn test_mint_order_vectors() {
+    let encoded = vectors()
+        .iter()
+        .map(|order| {
+            let data = order.encode();
+            assert_eq!(MintOrder::decode_data(&data).as_ref(), Some(order));
+            hex::encode(data)
+        })
+        .collect::<Vec<_>>();
+    let encoded = encoded.join("\n") + "\n";
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(VECTORS_FILE);
+    if std::env::var_os(UPDATE_VECTORS_ENV).is_some() {
+        std::fs::write(&path, &encoded).expect("failed to write test vectors file");
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).expect("failed to read test vectors file");
+    for (idx, (actual, expected)) in encoded.lines().zip(expected.lines()).enumerate() {
+        assert_eq!(actual, expected, "mint order test vector {idx} mismatch");
+    }
+    assert_eq!(
+        encoded.lines().count(),
+        expected.lines().count(),
+        "number of mint order test vectors mismatch"
+    );
+}
@@ -0,0 +1,95 @@
+//! Messages of the bridge canister outbox.
+//!
+//! Messages are appended to the outbox together with the operation state transition, and are
+//! delivered to their destinations by the outbox dispatcher with retries.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use did::H160;
+use ic_stable_structures::{Bound, Storable};
+use serde::Serialize;
+
+use crate::error::{BTFResult, Error};
+use crate::op_id::OperationId;
+use crate::operation_log::Memo;
+use crate::versioned::Versioned;
+
+/// Destination of the outbox message. Every kind of destination has its own delivery adapter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum OutboxDestination {
+    /// The canister method is called with the candid encoded [`OperationNotification`].
+    Canister {
+        canister_id: Principal,
+        method: String,
+    },
+    /// The URL is requested with `POST` method and the JSON encoded [`OperationNotification`].
+    Webhook { url: String },
+}
+
+impl OutboxDestination {
+    /// Checks if the messages can be delivered to the destination.
+    /// Webhooks must use HTTPS, as required by the IC HTTPS outcalls.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::Canister {
+                canister_id,
+                method,
+            } => *canister_id != Principal::anonymous() && !method.is_empty(),
+            Self::Webhook { url } => url.len() > "https://".len() && url.starts_with("https://"),
+        }
+    }
+}
+
+/// Notification about the operation reaching its terminal state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct OperationNotification {
+    pub operation_id: OperationId,
+    pub wallet_address: H160,
+    pub memo: Option<Memo>,
+    pub timestamp: u64,
+}
+
+/// Message waiting in the outbox to be delivered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct OutboxMessage {
+    pub id: u64,
+    pub destination: OutboxDestination,
+    pub payload: OperationNotification,
+    pub created_at: u64,
+    /// Number of the delivery attempts made.
+    pub attempts: u32,
+    /// The message is not delivered before this timestamp.
+    pub next_attempt_at: u64,
+    /// Error of the latest failed delivery attempt.
+    pub last_error: Option<String>,
+}
+
+impl Versioned for OutboxMessage {
+    const CURRENT_VERSION: u16 = 1;
+
+    fn encode_payload(&self) -> Vec<u8> {
+        Encode!(self).expect("failed to encode outbox message")
+    }
+
+    fn decode_payload(version: u16, payload: &[u8]) -> BTFResult<Self> {
+        match version {
+            1 => Decode!(payload, Self).map_err(|e| Error::Serialization(e.to_string())),
+            _ => Err(Error::Serialization(format!(
+                "unknown outbox message version {version}"
+            ))),
+        }
+    }
+}
+
+impl Storable for OutboxMessage {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        self.to_versioned_bytes().into()
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Self::from_versioned_bytes(&bytes).expect("failed to decode outbox message")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
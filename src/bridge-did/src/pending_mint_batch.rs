@@ -0,0 +1,115 @@
+//! Types of the signed mint orders batches, which wait to be sent to the EVM by the bridge.
+//! Pending batches are stored in stable memory, so they survive canister upgrades.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode};
+use did::H256;
+use ic_stable_structures::{Bound, Storable};
+use serde::Serialize;
+
+use crate::op_id::OperationId;
+
+/// Key of the pending batch: digest of the signed orders batch, and the operation
+/// if its order is sent in a separate transaction.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, CandidType,
+)]
+pub struct PendingMintBatchKey {
+    pub digest: H256,
+    pub isolated: Option<OperationId>,
+}
+
+impl PendingMintBatchKey {
+    const DIGEST_OFFSET: usize = 0;
+    const ISOLATED_OFFSET: usize = Self::DIGEST_OFFSET + 32;
+    const SIZE: usize = Self::ISOLATED_OFFSET + 9;
+}
+
+impl Storable for PendingMintBatchKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        bytes.extend_from_slice(self.digest.0.as_bytes());
+        match self.isolated {
+            Some(id) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&id.as_u64().to_be_bytes());
+            }
+            None => bytes.extend_from_slice(&[0; 9]),
+        }
+
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let isolated = (bytes[Self::ISOLATED_OFFSET] != 0).then(|| {
+            OperationId::new(u64::from_be_bytes(
+                bytes[Self::ISOLATED_OFFSET + 1..Self::SIZE]
+                    .try_into()
+                    .expect("invalid pending mint batch key size"),
+            ))
+        });
+
+        Self {
+            digest: H256::from_slice(&bytes[Self::DIGEST_OFFSET..Self::ISOLATED_OFFSET]),
+            isolated,
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: Self::SIZE as u32,
+        is_fixed_size: true,
+    };
+}
+
+/// Operations of the pending batch. Signed orders are not stored with the batch: they are
+/// taken from the operations when the batch is restored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct PendingMintBatch {
+    pub operations: Vec<OperationId>,
+    /// IC time of the first push of the batch operations.
+    pub pushed_at: u64,
+}
+
+impl Storable for PendingMintBatch {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode pending mint batch"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode pending mint batch")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Number and age of the batches, which wait to be sent to the EVM.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct PendingMintBatchesStats {
+    /// Number of the pending batches.
+    pub batches: u64,
+    /// Number of the operations in the pending batches.
+    pub operations: u64,
+    /// Age in seconds of the oldest pending batch.
+    pub oldest_batch_age_secs: Option<u64>,
+    /// Age in seconds, after which operations of the pending batch are pushed again.
+    pub max_batch_age_secs: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_batch_key_roundtrip() {
+        for isolated in [None, Some(OperationId::new(42))] {
+            let key = PendingMintBatchKey {
+                digest: H256::from_slice(&[3; 32]),
+                isolated,
+            };
+            let bytes = key.to_bytes();
+            assert_eq!(bytes.len(), PendingMintBatchKey::SIZE);
+            assert_eq!(PendingMintBatchKey::from_bytes(bytes), key);
+        }
+    }
+}
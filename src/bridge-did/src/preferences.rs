@@ -0,0 +1,114 @@
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode};
+use did::H160;
+use ic_stable_structures::{Bound, Storable};
+use serde::Serialize;
+
+use crate::error::{BTFResult, Error};
+
+/// Default values of the deposit fields, stored by a user.
+/// They are used for the fields, absent in the user deposits.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct BridgePreferences {
+    /// Recipient of the minted tokens, used if the deposit recipient is the zero address.
+    pub recipient_address: Option<H160>,
+
+    /// Address from which mint transaction fee is charged, used if the deposit has no fee payer.
+    pub fee_payer: Option<H160>,
+
+    /// Spender of the minted tokens approval, used if the deposit has no approve after mint.
+    /// The whole deposit amount is approved.
+    pub approve_spender: Option<H160>,
+}
+
+/// Deposit field, which value is taken from the user preferences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum PreferenceField {
+    RecipientAddress,
+    FeePayer,
+    ApproveSpender,
+}
+
+impl BridgePreferences {
+    /// Checks that the preferred addresses are valid deposit addresses.
+    pub fn validate(&self) -> BTFResult<()> {
+        for (field, address) in [
+            ("recipient address", &self.recipient_address),
+            ("fee payer", &self.fee_payer),
+            ("approve spender", &self.approve_spender),
+        ] {
+            if address
+                .as_ref()
+                .is_some_and(|address| *address == H160::zero())
+            {
+                return Err(Error::InvalidBridgePreferences(format!(
+                    "{field} should not be the zero address"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Storable for BridgePreferences {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode bridge preferences"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode bridge preferences")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_reject_zero_addresses() {
+        assert!(BridgePreferences::default().validate().is_ok());
+
+        let preferences = BridgePreferences {
+            recipient_address: Some(H160::from_slice(&[1; 20])),
+            fee_payer: Some(H160::from_slice(&[2; 20])),
+            approve_spender: Some(H160::from_slice(&[3; 20])),
+        };
+        assert!(preferences.validate().is_ok());
+
+        for invalid in [
+            BridgePreferences {
+                recipient_address: Some(H160::zero()),
+                ..preferences.clone()
+            },
+            BridgePreferences {
+                fee_payer: Some(H160::zero()),
+                ..preferences.clone()
+            },
+            BridgePreferences {
+                approve_spender: Some(H160::zero()),
+                ..preferences.clone()
+            },
+        ] {
+            assert!(matches!(
+                invalid.validate(),
+                Err(Error::InvalidBridgePreferences(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn storable_roundtrip() {
+        let preferences = BridgePreferences {
+            recipient_address: Some(H160::from_slice(&[1; 20])),
+            fee_payer: None,
+            approve_spender: Some(H160::from_slice(&[3; 20])),
+        };
+
+        let decoded = BridgePreferences::from_bytes(preferences.to_bytes());
+        assert_eq!(decoded, preferences);
+    }
+}
@@ -0,0 +1,253 @@
+//! Types to protect the bridge from processing the same BTF bridge event twice,
+//! and to look up the processed events by their sender.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Deserialize};
+use did::{H160, H256};
+use ic_stable_structures::{Bound, Storable};
+use serde::Serialize;
+
+use crate::op_id::OperationId;
+
+/// Unique key of the BTF bridge event log. The key contains the address of the contract
+/// which emitted the log, so events of a re-deployed contract never collide with the
+/// events of the previous one.
+///
+/// Keys are ordered by the block number first, so the old keys can be pruned by range.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, CandidType)]
+pub struct EventKey {
+    pub block_number: u64,
+    pub contract: H160,
+    pub tx_hash: H256,
+    pub log_index: u64,
+}
+
+impl EventKey {
+    const BLOCK_NUMBER_OFFSET: usize = 0;
+    const CONTRACT_OFFSET: usize = Self::BLOCK_NUMBER_OFFSET + 8;
+    const TX_HASH_OFFSET: usize = Self::CONTRACT_OFFSET + 20;
+    const LOG_INDEX_OFFSET: usize = Self::TX_HASH_OFFSET + 32;
+    const SIZE: usize = Self::LOG_INDEX_OFFSET + 8;
+
+    /// Returns the smallest key of the given block.
+    pub fn first_in_block(block_number: u64) -> Self {
+        Self {
+            block_number,
+            contract: H160::zero(),
+            tx_hash: H256::zero(),
+            log_index: 0,
+        }
+    }
+}
+
+impl Storable for EventKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        // Big endian numbers keep the byte order equal to the key order.
+        bytes.extend_from_slice(&self.block_number.to_be_bytes());
+        bytes.extend_from_slice(self.contract.0.as_bytes());
+        bytes.extend_from_slice(self.tx_hash.0.as_bytes());
+        bytes.extend_from_slice(&self.log_index.to_be_bytes());
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let u64_at = |offset: usize| {
+            u64::from_be_bytes(
+                bytes[offset..offset + 8]
+                    .try_into()
+                    .expect("invalid event key size"),
+            )
+        };
+
+        Self {
+            block_number: u64_at(Self::BLOCK_NUMBER_OFFSET),
+            contract: H160::from_slice(&bytes[Self::CONTRACT_OFFSET..Self::TX_HASH_OFFSET]),
+            tx_hash: H256::from_slice(&bytes[Self::TX_HASH_OFFSET..Self::LOG_INDEX_OFFSET]),
+            log_index: u64_at(Self::LOG_INDEX_OFFSET),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: Self::SIZE as u32,
+        is_fixed_size: true,
+    };
+}
+
+/// Kind of the processed BTF bridge event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum ProcessedEventKind {
+    Burnt,
+    Minted,
+    Notify,
+}
+
+impl ProcessedEventKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::Burnt => 0,
+            Self::Minted => 1,
+            Self::Notify => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Self {
+        match byte {
+            0 => Self::Burnt,
+            1 => Self::Minted,
+            2 => Self::Notify,
+            _ => panic!("invalid processed event kind {byte}"),
+        }
+    }
+}
+
+/// Record of the processed event, stored in the index of the events by their sender.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct ProcessedEvent {
+    pub kind: ProcessedEventKind,
+    /// Operation, created or updated by the event.
+    pub operation_id: Option<OperationId>,
+    /// IC time in nanoseconds, when the event was processed.
+    pub processed_at: u64,
+}
+
+impl ProcessedEvent {
+    const KIND_OFFSET: usize = 0;
+    const OPERATION_ID_OFFSET: usize = Self::KIND_OFFSET + 1;
+    const PROCESSED_AT_OFFSET: usize = Self::OPERATION_ID_OFFSET + 9;
+    const SIZE: usize = Self::PROCESSED_AT_OFFSET + 8;
+
+    /// Returns the information about the event with the given `key`.
+    pub fn info(&self, key: &EventKey) -> ProcessedEventInfo {
+        ProcessedEventInfo {
+            tx_hash: key.tx_hash.clone(),
+            log_index: key.log_index,
+            kind: self.kind,
+            block_number: key.block_number,
+            operation_id: self.operation_id,
+            processed_at: self.processed_at,
+        }
+    }
+}
+
+impl Storable for ProcessedEvent {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        bytes.push(self.kind.to_byte());
+        bytes.push(self.operation_id.is_some() as u8);
+        bytes.extend_from_slice(
+            &self
+                .operation_id
+                .map(|id| id.as_u64())
+                .unwrap_or_default()
+                .to_be_bytes(),
+        );
+        bytes.extend_from_slice(&self.processed_at.to_be_bytes());
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let u64_at = |offset: usize| {
+            u64::from_be_bytes(
+                bytes[offset..offset + 8]
+                    .try_into()
+                    .expect("invalid processed event size"),
+            )
+        };
+
+        let operation_id = (bytes[Self::OPERATION_ID_OFFSET] != 0)
+            .then(|| OperationId::new(u64_at(Self::OPERATION_ID_OFFSET + 1)));
+
+        Self {
+            kind: ProcessedEventKind::from_byte(bytes[Self::KIND_OFFSET]),
+            operation_id,
+            processed_at: u64_at(Self::PROCESSED_AT_OFFSET),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: Self::SIZE as u32,
+        is_fixed_size: true,
+    };
+}
+
+/// Processed BTF bridge event, as returned by the `get_processed_events` query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct ProcessedEventInfo {
+    pub tx_hash: H256,
+    pub log_index: u64,
+    pub kind: ProcessedEventKind,
+    pub block_number: u64,
+    /// Operation, created or updated by the event.
+    pub operation_id: Option<OperationId>,
+    /// IC time in nanoseconds, when the event was processed.
+    pub processed_at: u64,
+}
+
+/// Counters of the BTF bridge events, which were rejected before processing.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct RejectedEventsStats {
+    /// Events emitted by a contract other than the configured BTF bridge contract,
+    /// e.g. by the previous contract after the switchover.
+    pub foreign_contract: u64,
+    /// Events emitted by the configured contract before its activation block.
+    pub before_activation: u64,
+    /// Events which were already processed.
+    pub duplicate: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_key_storable_roundtrip() {
+        let key = EventKey {
+            block_number: 42,
+            contract: H160::from_slice(&[1; 20]),
+            tx_hash: H256::from_slice(&[2; 32]),
+            log_index: 7,
+        };
+
+        let bytes = key.to_bytes();
+        assert_eq!(bytes.len(), EventKey::SIZE);
+        assert_eq!(EventKey::from_bytes(bytes), key);
+    }
+
+    #[test]
+    fn processed_event_storable_roundtrip() {
+        for (kind, operation_id) in [
+            (ProcessedEventKind::Burnt, Some(OperationId::new(5))),
+            (ProcessedEventKind::Minted, None),
+            (ProcessedEventKind::Notify, Some(OperationId::new(0))),
+        ] {
+            let event = ProcessedEvent {
+                kind,
+                operation_id,
+                processed_at: 1_700_000_000_000_000_000,
+            };
+
+            let bytes = event.to_bytes();
+            assert_eq!(bytes.len(), ProcessedEvent::SIZE);
+            assert_eq!(ProcessedEvent::from_bytes(bytes), event);
+        }
+    }
+
+    #[test]
+    fn event_key_bytes_are_ordered_by_block() {
+        let key = |block_number: u64, seed: u8| EventKey {
+            block_number,
+            contract: H160::from_slice(&[seed; 20]),
+            tx_hash: H256::from_slice(&[seed; 32]),
+            log_index: seed as u64,
+        };
+
+        let keys = [key(1, 9), key(2, 1), key(256, 0), key(256, 5)];
+        for pair in keys.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert!(pair[0].to_bytes() < pair[1].to_bytes());
+        }
+        assert!(EventKey::first_in_block(256) <= key(256, 0));
+    }
+}
@@ -1,8 +1,15 @@
-use candid::{CandidType, Principal};
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode, Principal};
 use did::{H160, U256};
 use ic_exports::icrc_types::icrc1::account::Subaccount;
+use ic_stable_structures::{Bound, Storable};
 use serde::{Deserialize, Serialize};
 
+use crate::error::{BTFResult, Error};
+use crate::order::Erc20Permit;
+use crate::preferences::{BridgePreferences, PreferenceField};
+
 /// Information to perform burn operation for ICRC-2 token and create a mint order.
 #[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
 pub struct Icrc2Burn {
@@ -32,6 +39,117 @@ pub struct Icrc2Burn {
     /// performed by bridge canister.
     /// If None, mint transaction will not be sent and user can send it by himself.
     pub fee_payer: Option<H160>,
+
+    /// If set and not zero, tokens are not burnt until the sender confirms the deposit
+    /// with the `confirm_deposit` call. Unconfirmed deposit is cancelled after the delay.
+    /// The delay is limited by the bridge canister configuration.
+    pub confirmation_delay_secs: Option<u64>,
+
+    /// Fields, which values are taken from the sender preferences by the bridge.
+    /// Set by the bridge, the value of the user is ignored.
+    pub preference_fields: Option<Vec<PreferenceField>>,
+}
+
+impl Icrc2Burn {
+    /// Fills the fields, absent in the burn, with the sender `preferences`.
+    /// Values of the burn take precedence. Filled fields are recorded in `preference_fields`.
+    pub fn apply_preferences(&mut self, preferences: &BridgePreferences) {
+        let mut applied = vec![];
+
+        if self.recipient_address == H160::zero() {
+            if let Some(recipient) = &preferences.recipient_address {
+                self.recipient_address = recipient.clone();
+                applied.push(PreferenceField::RecipientAddress);
+            }
+        }
+
+        if self.fee_payer.is_none() {
+            if let Some(fee_payer) = &preferences.fee_payer {
+                self.fee_payer = Some(fee_payer.clone());
+                applied.push(PreferenceField::FeePayer);
+            }
+        }
+
+        if self.approve_after_mint.is_none() {
+            if let Some(spender) = &preferences.approve_spender {
+                self.approve_after_mint = Some(ApproveAfterMint {
+                    approve_spender: spender.clone(),
+                    approve_amount: self.amount.clone(),
+                    permit: None,
+                });
+                applied.push(PreferenceField::ApproveSpender);
+            }
+        }
+
+        self.preference_fields = (!applied.is_empty()).then_some(applied);
+    }
+
+    /// Removes the approve of the minted tokens, including the one taken from the sender
+    /// preferences.
+    pub fn remove_approve_after_mint(&mut self) {
+        self.approve_after_mint = None;
+        self.preference_fields = self
+            .preference_fields
+            .take()
+            .map(|fields| {
+                fields
+                    .into_iter()
+                    .filter(|field| *field != PreferenceField::ApproveSpender)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|fields| !fields.is_empty());
+    }
+
+    /// Returns the recipient address, specified by the sender in the deposit request.
+    ///
+    /// If the recipient is taken from the sender preferences, the requested recipient is
+    /// the zero address.
+    pub fn requested_recipient(&self) -> H160 {
+        let from_preferences = self
+            .preference_fields
+            .as_ref()
+            .is_some_and(|fields| fields.contains(&PreferenceField::RecipientAddress));
+        if from_preferences {
+            H160::zero()
+        } else {
+            self.recipient_address.clone()
+        }
+    }
+
+    /// Checks that the burn amount exceeds the deposit fee.
+    ///
+    /// The `deposit_fee` is set in millionths of a token, so the fee in the token base
+    /// units is `deposit_fee * 10^decimals / 10^6`. The amount should be strictly greater
+    /// than it, so zero amount is never valid.
+    pub fn validate_amount(&self, decimals: u8, deposit_fee: u64) -> BTFResult<()> {
+        let min_amount = Self::min_amount(decimals, deposit_fee)?;
+        if self.amount.0 < min_amount.0 {
+            return Err(Error::InvalidBurnAmount(format!(
+                "amount {} should be greater than the deposit fee {}",
+                self.amount.0,
+                min_amount.0 - U256::from(1u64).0
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the minimal valid burn amount in the token base units, which is the deposit
+    /// fee plus one base unit. See [`Icrc2Burn::validate_amount`].
+    pub fn min_amount(decimals: u8, deposit_fee: u64) -> BTFResult<U256> {
+        U256::from(10u64)
+            .0
+            .checked_pow(U256::from(decimals as u64).0)
+            .and_then(|scale| U256::from(deposit_fee).0.checked_mul(scale))
+            .map(|fee| fee / U256::from(1_000_000u64).0)
+            .and_then(|fee| fee.checked_add(U256::from(1u64).0))
+            .map(Into::into)
+            .ok_or_else(|| {
+                Error::InvalidBurnAmount(format!(
+                    "deposit fee {deposit_fee} overflows for the token with {decimals} decimals"
+                ))
+            })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
@@ -41,6 +159,72 @@ pub struct ApproveAfterMint {
 
     /// Amount to approve.
     pub approve_amount: U256,
+
+    /// EIP-2612 permit, signed by the recipient for the `approve_spender`.
+    /// If set, it is forwarded to the wrapped token instead of the approval by the bridge.
+    ///
+    /// Optional field, so approvals stored before its introduction are decoded with `None`.
+    pub permit: Option<Erc20Permit>,
+}
+
+impl ApproveAfterMint {
+    /// Checks that the permit, if any, is not expired at the given timestamp in seconds.
+    pub fn validate_permit(&self, now_secs: u64) -> BTFResult<()> {
+        match &self.permit {
+            Some(permit) if permit.is_expired(now_secs) => Err(Error::InvalidPermit(format!(
+                "permit deadline {} has passed",
+                permit.deadline
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Limits the approve amount by the minted `amount` according to the `policy`.
+    /// Returns `true` if the approve amount is clamped to the minted amount.
+    ///
+    /// The permit value is signed by the recipient, so it is left unchanged.
+    pub fn limit_approve_amount(
+        &mut self,
+        amount: &U256,
+        policy: ApproveAmountPolicy,
+    ) -> BTFResult<bool> {
+        if self.approve_amount <= *amount {
+            return Ok(false);
+        }
+
+        match policy {
+            ApproveAmountPolicy::Clamp => {
+                self.approve_amount = amount.clone();
+                Ok(true)
+            }
+            ApproveAmountPolicy::Reject => Err(Error::InvalidBurnRequest(format!(
+                "approve amount {} exceeds the minted amount {amount}",
+                self.approve_amount
+            ))),
+        }
+    }
+}
+
+/// Handling of the approve after mint amount, which exceeds the minted amount.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum ApproveAmountPolicy {
+    /// Approve amount is clamped to the minted amount.
+    #[default]
+    Clamp,
+    /// Burn is rejected.
+    Reject,
+}
+
+impl Storable for ApproveAmountPolicy {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode approve amount policy"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode approve amount policy")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
 }
 
 /// Information to perform burn operation for BTC and create a mint order.
@@ -56,3 +240,197 @@ pub struct BtcDeposit {
     /// he can use this field.
     pub approve_after_mint: Option<ApproveAfterMint>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn burn(amount: u128) -> Icrc2Burn {
+        Icrc2Burn {
+            sender: Principal::anonymous(),
+            amount: U256::from(amount),
+            icrc2_token_principal: Principal::anonymous(),
+            erc20_token_address: H160::default(),
+            from_subaccount: None,
+            recipient_address: H160::default(),
+            approve_after_mint: None,
+            fee_payer: None,
+            confirmation_delay_secs: None,
+            preference_fields: None,
+        }
+    }
+
+    fn approve(amount: u64) -> ApproveAfterMint {
+        ApproveAfterMint {
+            approve_spender: H160::from_slice(&[1; 20]),
+            approve_amount: U256::from(amount),
+            permit: None,
+        }
+    }
+
+    #[test]
+    fn over_large_approve_amount_is_clamped() {
+        let minted = U256::from(1_000u64);
+
+        let mut over_large = approve(5_000);
+        let clamped = over_large
+            .limit_approve_amount(&minted, ApproveAmountPolicy::Clamp)
+            .unwrap();
+        assert!(clamped);
+        assert_eq!(over_large.approve_amount, minted);
+
+        let mut within = approve(1_000);
+        let clamped = within
+            .limit_approve_amount(&minted, ApproveAmountPolicy::Clamp)
+            .unwrap();
+        assert!(!clamped);
+        assert_eq!(within.approve_amount, minted);
+    }
+
+    #[test]
+    fn over_large_approve_amount_is_rejected_by_policy() {
+        let minted = U256::from(1_000u64);
+
+        assert!(approve(1_001)
+            .limit_approve_amount(&minted, ApproveAmountPolicy::Reject)
+            .is_err());
+        assert_eq!(
+            approve(999).limit_approve_amount(&minted, ApproveAmountPolicy::Reject),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn zero_amount_is_invalid() {
+        assert!(burn(0).validate_amount(8, 0).is_err());
+        assert!(burn(0).validate_amount(0, 0).is_err());
+        assert!(burn(1).validate_amount(0, 0).is_ok());
+    }
+
+    #[test]
+    fn should_scale_fee_by_decimals() {
+        // 0.5 of the token with 8 decimals is 50_000_000 base units.
+        assert!(burn(50_000_000).validate_amount(8, 500_000).is_err());
+        assert!(burn(50_000_001).validate_amount(8, 500_000).is_ok());
+
+        // 1.0 of the token with 6 decimals is 1_000_000 base units.
+        assert!(burn(1_000_000).validate_amount(6, 1_000_000).is_err());
+        assert!(burn(1_000_001).validate_amount(6, 1_000_000).is_ok());
+
+        // 0.01 of the token with 18 decimals.
+        assert!(burn(10_000_000_000_000_000)
+            .validate_amount(18, 10_000)
+            .is_err());
+        assert!(burn(10_000_000_000_000_001)
+            .validate_amount(18, 10_000)
+            .is_ok());
+    }
+
+    #[test]
+    fn fee_below_token_precision_is_rounded_down() {
+        // 0.5 of the token with 0 decimals rounds down to zero base units.
+        assert!(burn(1).validate_amount(0, 500_000).is_ok());
+        // 2.5 of the token with 0 decimals rounds down to 2 base units.
+        assert!(burn(2).validate_amount(0, 2_500_000).is_err());
+        assert!(burn(3).validate_amount(0, 2_500_000).is_ok());
+    }
+
+    #[test]
+    fn should_reject_overflowing_fee() {
+        assert!(burn(u128::MAX).validate_amount(u8::MAX, 1).is_err());
+        assert!(Icrc2Burn::min_amount(u8::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn min_amount_is_the_smallest_valid_amount() {
+        let min_amount = Icrc2Burn::min_amount(8, 500_000).unwrap();
+        assert_eq!(min_amount, U256::from(50_000_001u64));
+        assert_eq!(Icrc2Burn::min_amount(0, 0).unwrap(), U256::from(1u64));
+    }
+
+    fn preferences() -> BridgePreferences {
+        BridgePreferences {
+            recipient_address: Some(H160::from_slice(&[1; 20])),
+            fee_payer: Some(H160::from_slice(&[2; 20])),
+            approve_spender: Some(H160::from_slice(&[3; 20])),
+        }
+    }
+
+    #[test]
+    fn absent_fields_are_taken_from_preferences() {
+        let mut burn = burn(1000);
+        burn.apply_preferences(&preferences());
+
+        assert_eq!(burn.recipient_address, H160::from_slice(&[1; 20]));
+        assert_eq!(burn.fee_payer, Some(H160::from_slice(&[2; 20])));
+        let approve = burn.approve_after_mint.unwrap();
+        assert_eq!(approve.approve_spender, H160::from_slice(&[3; 20]));
+        assert_eq!(approve.approve_amount, U256::from(1000u64));
+        assert_eq!(
+            burn.preference_fields,
+            Some(vec![
+                PreferenceField::RecipientAddress,
+                PreferenceField::FeePayer,
+                PreferenceField::ApproveSpender,
+            ])
+        );
+    }
+
+    #[test]
+    fn burn_fields_take_precedence_over_preferences() {
+        let mut burn = burn(1000);
+        burn.recipient_address = H160::from_slice(&[4; 20]);
+        burn.approve_after_mint = Some(ApproveAfterMint {
+            approve_spender: H160::from_slice(&[5; 20]),
+            approve_amount: U256::from(10u64),
+            permit: None,
+        });
+        burn.apply_preferences(&preferences());
+
+        assert_eq!(burn.recipient_address, H160::from_slice(&[4; 20]));
+        assert_eq!(
+            burn.approve_after_mint.unwrap().approve_spender,
+            H160::from_slice(&[5; 20])
+        );
+        assert_eq!(burn.fee_payer, Some(H160::from_slice(&[2; 20])));
+        assert_eq!(
+            burn.preference_fields,
+            Some(vec![PreferenceField::FeePayer])
+        );
+    }
+
+    #[test]
+    fn preference_fields_are_set_only_by_bridge() {
+        let mut burn = burn(1000);
+        burn.preference_fields = Some(vec![PreferenceField::FeePayer]);
+        burn.apply_preferences(&BridgePreferences::default());
+
+        assert_eq!(burn.recipient_address, H160::zero());
+        assert_eq!(burn.fee_payer, None);
+        assert!(burn.approve_after_mint.is_none());
+        assert_eq!(burn.preference_fields, None);
+    }
+
+    #[test]
+    fn expired_permit_is_invalid() {
+        let mut approve = ApproveAfterMint {
+            approve_spender: H160::from_slice(&[5; 20]),
+            approve_amount: U256::zero(),
+            permit: None,
+        };
+        assert!(approve.validate_permit(u64::MAX).is_ok());
+
+        approve.permit = Some(Erc20Permit {
+            value: U256::from(10u64),
+            deadline: 1_000,
+            v: 27,
+            r: did::H256::default(),
+            s: did::H256::default(),
+        });
+        assert!(approve.validate_permit(1_000).is_ok());
+        assert!(matches!(
+            approve.validate_permit(1_001),
+            Err(Error::InvalidPermit(_))
+        ));
+    }
+}
@@ -0,0 +1,271 @@
+//! Retry profiles of the scheduled operation tasks.
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+/// Max delay between the task retries.
+pub const MAX_RETRY_BACKOFF_SECS: u32 = 24 * 60 * 60;
+
+/// Default max number of the retries of the operations, waiting for their dependency.
+pub const DEFAULT_DEPENDENCY_WAIT_MAX_RETRIES: u32 = 100;
+
+/// Default delay between the retries of the operations, waiting for their dependency.
+pub const DEFAULT_DEPENDENCY_WAIT_BACKOFF_SECS: u32 = 10;
+
+/// Category of the scheduled task, which defines its retry profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum RetryProfileKind {
+    /// Operations calling the EVM, which have no more specific profile.
+    EvmCall,
+    /// Operations calling the ICRC ledgers.
+    IcrcCall,
+    /// Operations created for the collected EVM events, which have no more specific profile.
+    EventCollection,
+    /// Signing and sending of the mint orders.
+    MintSend,
+    /// Operations waiting for another operation to release them, e.g. refunds.
+    DependencyWait,
+}
+
+/// Max number of the task retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum RetryLimit {
+    MaxRetries(u32),
+    /// Task is retried until it succeeds.
+    Unbounded,
+}
+
+/// Delay between the task retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum RetryBackoff {
+    /// Task is retried on the next scheduler run.
+    None,
+    Fixed {
+        secs: u32,
+    },
+    /// The delay is `secs * multiplier ^ retry`.
+    Exponential {
+        secs: u32,
+        multiplier: u32,
+    },
+}
+
+/// Retry policy and backoff of a task category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct RetryProfile {
+    pub retries: RetryLimit,
+    pub backoff: RetryBackoff,
+}
+
+impl RetryProfile {
+    /// Checks if the profile backoff is within [`MAX_RETRY_BACKOFF_SECS`] and the exponential
+    /// backoff multiplier is not zero.
+    pub fn is_valid(&self) -> bool {
+        match self.backoff {
+            RetryBackoff::None => true,
+            RetryBackoff::Fixed { secs } => secs <= MAX_RETRY_BACKOFF_SECS,
+            RetryBackoff::Exponential { secs, multiplier } => {
+                secs <= MAX_RETRY_BACKOFF_SECS && multiplier > 0
+            }
+        }
+    }
+
+    /// Returns the total delay of all the retries in seconds, or `None` if the retries
+    /// are unbounded. The task makes its last attempt not earlier than this delay after
+    /// the first one.
+    pub fn total_backoff_secs(&self) -> Option<u64> {
+        let RetryLimit::MaxRetries(retries) = self.retries else {
+            return None;
+        };
+
+        let total = match self.backoff {
+            RetryBackoff::None => 0,
+            RetryBackoff::Fixed { secs } => secs as u64 * retries as u64,
+            RetryBackoff::Exponential { secs, multiplier } => {
+                let mut total = 0u64;
+                let mut delay = secs as u64;
+                for retry in 0..retries {
+                    let next = delay.saturating_mul(multiplier as u64);
+                    if next == delay {
+                        // The delay doesn't grow anymore, so the rest of the retries have it.
+                        let rest = delay.saturating_mul((retries - retry) as u64);
+                        return Some(total.saturating_add(rest));
+                    }
+                    total = total.saturating_add(delay);
+                    delay = next;
+                }
+                total
+            }
+        };
+
+        Some(total)
+    }
+}
+
+/// Retry profiles of the scheduled tasks. Changes apply to the newly scheduled tasks only.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct RetryProfiles {
+    pub evm_call: RetryProfile,
+    pub icrc_call: RetryProfile,
+    pub event_collection: RetryProfile,
+    pub mint_send: RetryProfile,
+    pub dependency_wait: RetryProfile,
+    /// If not set, profiles with [`RetryLimit::Unbounded`] are rejected.
+    pub allow_unbounded_retries: bool,
+}
+
+impl RetryProfiles {
+    /// Returns the profile of the given task category.
+    pub fn get(&self, kind: RetryProfileKind) -> &RetryProfile {
+        match kind {
+            RetryProfileKind::EvmCall => &self.evm_call,
+            RetryProfileKind::IcrcCall => &self.icrc_call,
+            RetryProfileKind::EventCollection => &self.event_collection,
+            RetryProfileKind::MintSend => &self.mint_send,
+            RetryProfileKind::DependencyWait => &self.dependency_wait,
+        }
+    }
+
+    /// Checks if all the profiles are valid, and none of them retries the tasks
+    /// without a limit, unless explicitly allowed.
+    pub fn is_valid(&self) -> bool {
+        [
+            &self.evm_call,
+            &self.icrc_call,
+            &self.event_collection,
+            &self.mint_send,
+            &self.dependency_wait,
+        ]
+        .into_iter()
+        .all(|profile| {
+            profile.is_valid()
+                && (self.allow_unbounded_retries || profile.retries != RetryLimit::Unbounded)
+        })
+    }
+}
+
+impl Default for RetryProfiles {
+    fn default() -> Self {
+        let call = RetryProfile {
+            retries: RetryLimit::MaxRetries(3),
+            backoff: RetryBackoff::Exponential {
+                secs: 2,
+                multiplier: 4,
+            },
+        };
+
+        Self {
+            evm_call: call,
+            icrc_call: call,
+            event_collection: RetryProfile {
+                retries: RetryLimit::MaxRetries(0),
+                backoff: RetryBackoff::Fixed { secs: 2 },
+            },
+            mint_send: call,
+            dependency_wait: RetryProfile {
+                retries: RetryLimit::MaxRetries(DEFAULT_DEPENDENCY_WAIT_MAX_RETRIES),
+                backoff: RetryBackoff::Fixed {
+                    secs: DEFAULT_DEPENDENCY_WAIT_BACKOFF_SECS,
+                },
+            },
+            allow_unbounded_retries: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_profiles_are_valid() {
+        assert!(RetryProfiles::default().is_valid());
+    }
+
+    #[test]
+    fn unbounded_retries_should_be_allowed_explicitly() {
+        let mut profiles = RetryProfiles::default();
+        profiles.mint_send.retries = RetryLimit::Unbounded;
+        assert!(!profiles.is_valid());
+
+        profiles.allow_unbounded_retries = true;
+        assert!(profiles.is_valid());
+    }
+
+    #[test]
+    fn backoff_should_be_bounded() {
+        let mut profiles = RetryProfiles::default();
+        profiles.evm_call.backoff = RetryBackoff::Fixed {
+            secs: MAX_RETRY_BACKOFF_SECS + 1,
+        };
+        assert!(!profiles.is_valid());
+
+        profiles.evm_call.backoff = RetryBackoff::Exponential {
+            secs: 2,
+            multiplier: 0,
+        };
+        assert!(!profiles.is_valid());
+
+        profiles.evm_call.backoff = RetryBackoff::None;
+        assert!(profiles.is_valid());
+    }
+
+    #[test]
+    fn total_backoff_covers_all_retries() {
+        let profile = |retries, backoff| RetryProfile { retries, backoff };
+
+        assert_eq!(
+            profile(
+                RetryLimit::MaxRetries(100),
+                RetryBackoff::Fixed { secs: 10 }
+            )
+            .total_backoff_secs(),
+            Some(1000)
+        );
+        assert_eq!(
+            profile(
+                RetryLimit::MaxRetries(3),
+                RetryBackoff::Exponential {
+                    secs: 2,
+                    multiplier: 4
+                }
+            )
+            .total_backoff_secs(),
+            Some(2 + 8 + 32)
+        );
+        assert_eq!(
+            profile(
+                RetryLimit::MaxRetries(u32::MAX),
+                RetryBackoff::Exponential {
+                    secs: 2,
+                    multiplier: 1
+                }
+            )
+            .total_backoff_secs(),
+            Some(2 * u32::MAX as u64)
+        );
+        assert_eq!(
+            profile(RetryLimit::MaxRetries(5), RetryBackoff::None).total_backoff_secs(),
+            Some(0)
+        );
+        assert_eq!(
+            profile(RetryLimit::Unbounded, RetryBackoff::Fixed { secs: 10 }).total_backoff_secs(),
+            None
+        );
+    }
+
+    #[test]
+    fn profile_is_selected_by_kind() {
+        let mut profiles = RetryProfiles::default();
+        profiles.icrc_call.retries = RetryLimit::MaxRetries(7);
+
+        assert_eq!(
+            profiles.get(RetryProfileKind::IcrcCall).retries,
+            RetryLimit::MaxRetries(7)
+        );
+        assert_eq!(
+            profiles.get(RetryProfileKind::EvmCall).retries,
+            RetryLimit::MaxRetries(3)
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Types of the guard, which compares the wrapped tokens supply with the locked base tokens
+//! and halts the bridge on a mismatch.
+
+use candid::{CandidType, Principal};
+use did::{H160, U256};
+use serde::{Deserialize, Serialize};
+
+/// Configuration of the supply guard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct SupplyGuardConfig {
+    /// Max excess of the wrapped supply over the locked amount in the ledger units,
+    /// which doesn't halt the bridge.
+    pub tolerance: U256,
+    /// Min interval between the supply checks.
+    pub check_interval_secs: u64,
+}
+
+/// Locked amount of the base token and the supply of its wrapped tokens.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct SupplyReconciliation {
+    /// Base token ledger.
+    pub token: Principal,
+    /// Wrapped tokens, including the retired ones.
+    pub wrapped_tokens: Vec<H160>,
+    /// Amount of the base token, locked by the bridge.
+    pub locked: U256,
+    /// Total supply of the wrapped tokens, converted to the ledger units.
+    pub wrapped_supply: U256,
+}
+
+impl SupplyReconciliation {
+    /// Returns the excess of the wrapped supply over the locked amount.
+    pub fn excess(&self) -> U256 {
+        self.wrapped_supply
+            .0
+            .checked_sub(self.locked.0)
+            .map(Into::into)
+            .unwrap_or_default()
+    }
+
+    /// Checks if the wrapped supply exceeds the locked amount by more than the tolerance.
+    pub fn is_mismatch(&self, tolerance: &U256) -> bool {
+        self.excess() > *tolerance
+    }
+}
+
+/// Result of the latest supply check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct SupplyCheck {
+    /// IC timestamp in nanoseconds.
+    pub timestamp: u64,
+    pub reconciliations: Vec<SupplyReconciliation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reconciliation(locked: u64, wrapped_supply: u64) -> SupplyReconciliation {
+        SupplyReconciliation {
+            token: Principal::anonymous(),
+            wrapped_tokens: vec![H160::from_slice(&[1; 20])],
+            locked: locked.into(),
+            wrapped_supply: wrapped_supply.into(),
+        }
+    }
+
+    #[test]
+    fn should_detect_excess_beyond_tolerance() {
+        let tolerance = U256::from(10u64);
+
+        assert_eq!(reconciliation(100, 50).excess(), U256::zero());
+        assert!(!reconciliation(100, 50).is_mismatch(&tolerance));
+        assert!(!reconciliation(100, 110).is_mismatch(&tolerance));
+        assert!(reconciliation(100, 111).is_mismatch(&tolerance));
+        assert!(reconciliation(100, 101).is_mismatch(&U256::zero()));
+    }
+}
@@ -0,0 +1,21 @@
+//! Statistics of the scheduler task payloads, which exceed the inline size limit and are
+//! stored once in a separate stable store.
+
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+/// Sizes of the scheduler task payloads, moved out of the tasks to the payload store.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct TaskPayloadStats {
+    /// Pending tasks, which reference a payload in the store.
+    pub spilled_tasks: u64,
+    /// Total size in bytes of the payloads of the spilled tasks, if they were stored inline.
+    pub spilled_bytes: u64,
+    /// Number of the distinct payloads in the store.
+    pub stored_payloads: u64,
+    /// Total size in bytes of the payloads in the store.
+    pub stored_bytes: u64,
+    /// Pending tasks, which keep a payload over the inline size limit, e.g. tasks stored
+    /// before the limit was introduced. Such tasks are moved to the store on their next step.
+    pub oversized_inline_tasks: u64,
+}
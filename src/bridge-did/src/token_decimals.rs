@@ -0,0 +1,84 @@
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode};
+use did::H160;
+use ic_stable_structures::{Bound, Storable};
+use serde::Serialize;
+
+/// Operator resolution of the ICRC token decimals change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub enum DecimalsChangeStrategy {
+    /// Keep the wrapped token and scale amounts between the new ledger decimals
+    /// and the wrapped token decimals.
+    Rescale,
+    /// Retire the wrapped token. Deposits to it are rejected, so a new wrapped token
+    /// with the new ledger decimals should be deployed.
+    Migrate,
+}
+
+/// Change of the ICRC ledger decimals, waiting for the operator resolution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct DecimalsChange {
+    /// Ledger decimals, known to the bridge before the change.
+    pub previous: u8,
+    /// Ledger decimals, reported by the ledger.
+    pub current: u8,
+    /// IC time of the change detection.
+    pub detected_at: u64,
+}
+
+/// Wrapped token, retired by the [`DecimalsChangeStrategy::Migrate`] resolution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct RetiredWrappedToken {
+    pub address: H160,
+    /// Decimals of the wrapped token. Withdrawals of the retired token are scaled from them.
+    pub decimals: u8,
+}
+
+/// Decimals of an ICRC token and its wrapped token, tracked by the bridge.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct TokenDecimals {
+    /// Decimals of the ICRC token ledger.
+    pub ledger_decimals: u8,
+    /// Decimals of the wrapped token. Differ from the ledger decimals after the
+    /// [`DecimalsChangeStrategy::Rescale`] resolution.
+    pub wrapped_decimals: u8,
+    /// Wrapped token, registered with the first deposit or withdrawal.
+    pub wrapped_token: Option<H160>,
+    /// Wrapped tokens, retired by the [`DecimalsChangeStrategy::Migrate`] resolution.
+    pub retired_wrapped_tokens: Vec<RetiredWrappedToken>,
+    /// Detected ledger decimals change. Operations with the token are held until it's resolved.
+    pub pending_change: Option<DecimalsChange>,
+}
+
+impl TokenDecimals {
+    /// Creates the record of a token, first seen with the given ledger decimals.
+    pub fn new(decimals: u8) -> Self {
+        Self {
+            ledger_decimals: decimals,
+            wrapped_decimals: decimals,
+            wrapped_token: None,
+            retired_wrapped_tokens: vec![],
+            pending_change: None,
+        }
+    }
+
+    /// Returns the retired wrapped token with the given address.
+    pub fn retired(&self, address: &H160) -> Option<&RetiredWrappedToken> {
+        self.retired_wrapped_tokens
+            .iter()
+            .find(|retired| retired.address == *address)
+    }
+}
+
+impl Storable for TokenDecimals {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode token decimals"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode token decimals")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
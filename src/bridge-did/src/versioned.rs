@@ -0,0 +1,115 @@
+//! Versioned encoding of the values stored in stable memory.
+//!
+//! An encoded value starts with the [`VERSION_MARK`] and a big-endian `u16` version of the value
+//! shape, followed by the payload. Values stored before the versioning was introduced have no
+//! header and are decoded as [`LEGACY_VERSION`].
+//!
+//! When a stored type changes its shape, its [`Versioned::CURRENT_VERSION`] should be increased,
+//! and [`Versioned::decode_payload`] should migrate the previous shapes to the new one.
+
+use crate::error::{BTFResult, Error};
+
+/// Marks the beginning of a versioned value.
+pub const VERSION_MARK: [u8; 4] = *b"BTFV";
+
+/// Version of the values stored without the version header.
+pub const LEGACY_VERSION: u16 = 1;
+
+const HEADER_SIZE: usize = VERSION_MARK.len() + 2;
+
+/// A value, stored with explicit version of its shape.
+pub trait Versioned: Sized {
+    /// Version of the current shape of the value.
+    const CURRENT_VERSION: u16;
+
+    /// Encodes the value in its current shape.
+    fn encode_payload(&self) -> Vec<u8>;
+
+    /// Decodes the value of the given `version`, and migrates it to the current shape.
+    fn decode_payload(version: u16, payload: &[u8]) -> BTFResult<Self>;
+
+    /// Encodes the value with the version header.
+    fn to_versioned_bytes(&self) -> Vec<u8> {
+        with_version(Self::CURRENT_VERSION, &self.encode_payload())
+    }
+
+    /// Decodes the value of any supported version.
+    fn from_versioned_bytes(bytes: &[u8]) -> BTFResult<Self> {
+        let (version, payload) = split_version(bytes);
+        if version > Self::CURRENT_VERSION {
+            return Err(Error::Serialization(format!(
+                "unsupported version {version}, the latest known version is {}",
+                Self::CURRENT_VERSION
+            )));
+        }
+
+        Self::decode_payload(version, payload)
+    }
+}
+
+/// Prepends the version header to the payload.
+pub fn with_version(version: u16, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(&VERSION_MARK);
+    bytes.extend_from_slice(&version.to_be_bytes());
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// Splits the encoded value into the version and the payload.
+pub fn split_version(bytes: &[u8]) -> (u16, &[u8]) {
+    match bytes.strip_prefix(VERSION_MARK.as_slice()) {
+        Some([high, low, payload @ ..]) => (u16::from_be_bytes([*high, *low]), payload),
+        _ => (LEGACY_VERSION, bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Value {
+        a: u8,
+        b: u8,
+    }
+
+    impl Versioned for Value {
+        const CURRENT_VERSION: u16 = 2;
+
+        fn encode_payload(&self) -> Vec<u8> {
+            vec![self.a, self.b]
+        }
+
+        fn decode_payload(version: u16, payload: &[u8]) -> BTFResult<Self> {
+            match (version, payload) {
+                (1, [a]) => Ok(Self { a: *a, b: 0 }),
+                (2, [a, b]) => Ok(Self { a: *a, b: *b }),
+                _ => Err(Error::Serialization("invalid value".into())),
+            }
+        }
+    }
+
+    #[test]
+    fn versioned_value_roundtrip() {
+        let value = Value { a: 1, b: 2 };
+        let bytes = value.to_versioned_bytes();
+
+        assert_eq!(split_version(&bytes), (2, [1, 2].as_slice()));
+        assert_eq!(Value::from_versioned_bytes(&bytes), Ok(value));
+    }
+
+    #[test]
+    fn legacy_value_is_migrated() {
+        assert_eq!(Value::from_versioned_bytes(&[7]), Ok(Value { a: 7, b: 0 }));
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let mut bytes = VERSION_MARK.to_vec();
+        bytes.extend_from_slice(&3u16.to_be_bytes());
+        bytes.extend_from_slice(&[1, 2, 3]);
+
+        assert!(Value::from_versioned_bytes(&bytes).is_err());
+    }
+}
@@ -555,9 +555,11 @@ impl Erc20BridgeFlow<'_> {
         let (_, _, to_token) = self.get_side(evm_side.other());
 
         let to_chain_id = self.chain_id(evm_side.other()).await?;
-        let to_token_id = Id256::from_evm_address(&(*to_token).into(), to_chain_id as u32);
+        let to_chain_id = u32::try_from(to_chain_id)
+            .map_err(|_| anyhow::anyhow!("chain id {to_chain_id} doesn't fit into Id256"))?;
+        let to_token_id = Id256::from_evm_address(&(*to_token).into(), to_chain_id);
 
-        let recipient_id = Id256::from_evm_address(&(*recipient).into(), to_chain_id as u32);
+        let recipient_id = Id256::from_evm_address(&(*recipient).into(), to_chain_id);
         let recipient = recipient_id.0;
 
         let amount: U256 = amount.into();
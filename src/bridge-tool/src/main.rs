@@ -370,6 +370,8 @@ async fn deposit_icrc(args: DepositIcrcArgs) {
         approve_after_mint: None,
         fee_payer: None,
         erc20_token_address: args.erc20_token_address.into(),
+        confirmation_delay_secs: None,
+        preference_fields: None,
     };
     let memo = alloy_sol_types::private::FixedBytes::ZERO;
 
@@ -3,9 +3,12 @@ use alloy_sol_types::{SolCall, SolEvent};
 use anyhow::anyhow;
 use bridge_did::error::{BTFResult, Error};
 use bridge_did::event_data::*;
+use bridge_did::event_filter::EventTopicFilter;
+use bridge_did::order::{SignedOrdersData, TxParams};
+use bridge_did::processed_event::{EventKey, ProcessedEventKind};
 use candid::CandidType;
 use ethereum_json_rpc_client::{Client, EthGetLogsParams, EthJsonRpcClient};
-use ethers_core::types::{BlockNumber as EthBlockNumber, Log, Transaction, H160, U256};
+use ethers_core::types::{BlockNumber as EthBlockNumber, Log, Transaction, H160, H256};
 use serde::{Deserialize, Serialize};
 
 use crate::BTFBridge;
@@ -16,17 +19,196 @@ pub enum BridgeEvent {
     Burnt(BurntEventData),
     Minted(MintedEventData),
     Notify(NotifyMinterEventData),
+    /// Log with a topic, which has no registered decoder.
+    Unknown {
+        raw: RawEventLog,
+    },
+}
+
+/// Decodes the log data of a registered event into the bridge event.
+type EventDecoder = Box<dyn Fn(&LogData) -> anyhow::Result<BridgeEvent>>;
+
+/// Registry of the event decoders by the event signature hash (topic0).
+///
+/// The `eth_getLogs` topic filter is derived from the registry, so the collected logs
+/// and their decoding can't drift. A new event is supported by a single [`Self::register`] call.
+#[derive(Default)]
+pub struct EventRegistry {
+    decoders: Vec<([u8; 32], EventDecoder)>,
+    topic_filter: Option<EventTopicFilter>,
+}
+
+impl EventRegistry {
+    /// Registry of the events, emitted by the BTF bridge contract.
+    pub fn btf_bridge() -> Self {
+        Self::default()
+            .register(|event: BurnTokenEvent| BridgeEvent::Burnt(event.into()))
+            .register(|event: MintTokenEvent| BridgeEvent::Minted(event.into()))
+            .register(|event: NotifyMinterEvent| BridgeEvent::Notify(event.into()))
+    }
+
+    /// Registers the event `E`, which is converted into the bridge event with `into_event`.
+    pub fn register<E: SolEvent + 'static>(mut self, into_event: fn(E) -> BridgeEvent) -> Self {
+        let decoder = move |log: &LogData| -> anyhow::Result<BridgeEvent> {
+            Ok(into_event(E::decode_log_data(log, true)?))
+        };
+        self.decoders.push((E::SIGNATURE_HASH.0, Box::new(decoder)));
+        self
+    }
+
+    /// Restricts the collected logs to the ones, matching the indexed parameter topic filter.
+    pub fn with_topic_filter(mut self, topic_filter: Option<EventTopicFilter>) -> Self {
+        self.topic_filter = topic_filter;
+        self
+    }
+
+    /// Signature hashes of the registered events.
+    pub fn topics(&self) -> Vec<H256> {
+        self.decoders
+            .iter()
+            .map(|(topic, _)| H256::from(*topic))
+            .collect()
+    }
+
+    /// Returns the `eth_getLogs` params to collect the registered events
+    /// of the `contract` in the given range of blocks.
+    pub fn logs_filter(
+        &self,
+        contract: H160,
+        from_block: EthBlockNumber,
+        to_block: EthBlockNumber,
+    ) -> EthGetLogsParams {
+        let mut topics = vec![self.topics()];
+        if let Some(filter) = &self.topic_filter {
+            // Empty topic list matches any value of the skipped topics.
+            topics.resize(filter.position as usize, vec![]);
+            topics.push(filter.values.iter().map(|value| value.0).collect());
+        }
+
+        EthGetLogsParams {
+            address: Some(vec![contract]),
+            from_block,
+            to_block,
+            topics: Some(topics),
+        }
+    }
+
+    /// Decodes the log with the decoder of its topic0. Logs without registered decoder
+    /// are returned as [`BridgeEvent::Unknown`].
+    pub fn decode(&self, log: &Log) -> anyhow::Result<BridgeEvent> {
+        let decoder = log.topics.first().and_then(|topic0| {
+            self.decoders
+                .iter()
+                .find(|(topic, _)| topic == topic0.as_fixed_bytes())
+                .map(|(_, decoder)| decoder)
+        });
+
+        let Some(decoder) = decoder else {
+            return Ok(BridgeEvent::Unknown {
+                raw: RawEventLog {
+                    topics: log.topics.iter().map(|topic| (*topic).into()).collect(),
+                    data: log.data.to_vec(),
+                },
+            });
+        };
+
+        let topics = log.topics.iter().map(|topic| topic.0.into()).collect();
+        let log_data = LogData::new(topics, Bytes(log.data.0.clone()))
+            .ok_or_else(|| anyhow!("failed to decode log"))?;
+
+        decoder(&log_data)
+    }
+}
+
+/// Bridge event with the location of the log, which emitted it.
+#[derive(Debug, Clone)]
+pub struct BridgeEventLog {
+    pub event: BridgeEvent,
+    /// Address of the contract, which emitted the log.
+    pub contract: did::H160,
+    pub block_number: Option<u64>,
+    pub tx_hash: Option<did::H256>,
+    pub log_index: Option<u64>,
+}
+
+impl BridgeEventLog {
+    /// Returns the deduplication key of the log, if the log location is known.
+    pub fn key(&self) -> Option<EventKey> {
+        Some(EventKey {
+            block_number: self.block_number?,
+            contract: self.contract.clone(),
+            tx_hash: self.tx_hash.clone()?,
+            log_index: self.log_index?,
+        })
+    }
+}
+
+/// Sorts the event logs in the order they were emitted: by block number, then by the log
+/// index in the block. Some EVM nodes return logs unordered. Logs with unknown block number
+/// are moved to the end, keeping their relative order.
+pub fn sort_event_logs(logs: &mut [BridgeEventLog]) {
+    logs.sort_by_key(|log| (log.block_number.is_none(), log.block_number, log.log_index));
+}
+
+impl TryFrom<Log> for BridgeEventLog {
+    type Error = anyhow::Error;
+
+    fn try_from(log: Log) -> Result<Self, Self::Error> {
+        let contract = log.address.into();
+        let block_number = log.block_number.map(|number| number.as_u64());
+        let tx_hash = log.transaction_hash.map(Into::into);
+        let log_index = log.log_index.map(|index| index.as_u64());
+
+        Ok(Self {
+            event: BridgeEvent::try_from(log)?,
+            contract,
+            block_number,
+            tx_hash,
+            log_index,
+        })
+    }
 }
 
 impl BridgeEvent {
+    /// Returns the kind of the event. Unknown events have no kind.
+    pub fn kind(&self) -> Option<ProcessedEventKind> {
+        match self {
+            Self::Burnt(_) => Some(ProcessedEventKind::Burnt),
+            Self::Minted(_) => Some(ProcessedEventKind::Minted),
+            Self::Notify(_) => Some(ProcessedEventKind::Notify),
+            Self::Unknown { .. } => None,
+        }
+    }
+
+    /// Returns the EVM address of the user, the event belongs to: the sender of the burnt
+    /// tokens, the recipient of the minted tokens, or the sender of the notification.
+    /// Unknown events don't belong to any user.
+    pub fn user_address(&self) -> Option<did::H160> {
+        match self {
+            Self::Burnt(event) => Some(event.sender.clone()),
+            Self::Minted(event) => Some(event.recipient.clone()),
+            Self::Notify(event) => Some(event.tx_sender.clone()),
+            Self::Unknown { .. } => None,
+        }
+    }
+
+    /// Collects the bridge events, emitted by the `bridge_contract` in the given range
+    /// of blocks. If `topic_filter` is set, only the matching logs are requested.
     pub async fn collect(
         evm_client: &EthJsonRpcClient<impl Client>,
         from_block: u64,
         to_block: u64,
         bridge_contract: H160,
-    ) -> BTFResult<Vec<Self>> {
-        let logs_result =
-            Self::collect_logs(evm_client, from_block, to_block, bridge_contract).await;
+        topic_filter: Option<&EventTopicFilter>,
+    ) -> BTFResult<Vec<BridgeEventLog>> {
+        let logs_result = Self::collect_logs(
+            evm_client,
+            from_block,
+            to_block,
+            bridge_contract,
+            topic_filter,
+        )
+        .await;
 
         let logs = match logs_result {
             Ok(l) => l,
@@ -38,9 +220,9 @@ impl BridgeEvent {
 
         log::debug!("Got evm logs between blocks {from_block} and {to_block}: {logs:?}",);
 
-        let events = logs
+        let mut events: Vec<_> = logs
             .into_iter()
-            .filter_map(|log| match BridgeEvent::from_log(log) {
+            .filter_map(|log| match BridgeEventLog::try_from(log) {
                 Ok(l) => Some(l),
                 Err(e) => {
                     log::warn!("failed to decode log into event: {e}");
@@ -48,6 +230,7 @@ impl BridgeEvent {
                 }
             })
             .collect();
+        sort_event_logs(&mut events);
         Ok(events)
     }
 
@@ -56,6 +239,7 @@ impl BridgeEvent {
         mut from_block: u64,
         to_block: u64,
         bridge_contract: H160,
+        topic_filter: Option<&EventTopicFilter>,
     ) -> Result<Vec<Log>, anyhow::Error> {
         const DEFAULT_BLOCKS_TO_COLLECT_PER_PAGE: u64 = 128;
         log::debug!("collecting logs from {from_block} to {to_block}",);
@@ -69,6 +253,7 @@ impl BridgeEvent {
             match Self::collect_logs_from_to(
                 evm_client,
                 bridge_contract,
+                topic_filter,
                 EthBlockNumber::Number(from_block.into()),
                 EthBlockNumber::Number(to_block_for_page.into()),
             )
@@ -104,19 +289,13 @@ impl BridgeEvent {
     async fn collect_logs_from_to(
         evm_client: &EthJsonRpcClient<impl Client>,
         bridge_contract: H160,
+        topic_filter: Option<&EventTopicFilter>,
         from_block: EthBlockNumber,
         to_block: EthBlockNumber,
     ) -> Result<Vec<Log>, anyhow::Error> {
-        let params = EthGetLogsParams {
-            address: Some(vec![bridge_contract]),
-            from_block,
-            to_block,
-            topics: Some(vec![vec![
-                BurnTokenEvent::SIGNATURE_HASH.0.into(),
-                MintTokenEvent::SIGNATURE_HASH.0.into(),
-                NotifyMinterEvent::SIGNATURE_HASH.0.into(),
-            ]]),
-        };
+        let params = EventRegistry::btf_bridge()
+            .with_topic_filter(topic_filter.cloned())
+            .logs_filter(bridge_contract, from_block, to_block);
         evm_client.get_logs(params).await
     }
 
@@ -129,39 +308,10 @@ impl TryFrom<Log> for BridgeEvent {
     type Error = anyhow::Error;
 
     fn try_from(value: Log) -> Result<Self, Self::Error> {
-        let topics = value
-            .topics
-            .iter()
-            .map(|topic| topic.0.into())
-            .collect::<Vec<_>>();
-
-        let log = LogData::new(topics, Bytes(value.data.0))
-            .ok_or_else(|| anyhow!("failed to decode log"))?;
-
-        let event = BurnTokenEvent::decode_log_data(&log, true)
-            .map(|event| Self::Burnt(event.into()))
-            .or_else(|_| {
-                MintTokenEvent::decode_log_data(&log, true).map(|event| Self::Minted(event.into()))
-            })
-            .or_else(|_| {
-                NotifyMinterEvent::decode_log_data(&log, true)
-                    .map(|event| Self::Notify(event.into()))
-            })?;
-
-        Ok(event)
+        EventRegistry::btf_bridge().decode(&value)
     }
 }
 
-/// Parameters for EVM transaction.
-#[derive(Debug, Clone)]
-pub struct TxParams {
-    pub sender: H160,
-    pub bridge: H160,
-    pub nonce: U256,
-    pub gas_price: U256,
-    pub chain_id: u32,
-}
-
 /// Sends transaction with given params to call `batchMint` function
 /// in Btfbridge contract.
 pub fn batch_mint_transaction(
@@ -170,32 +320,135 @@ pub fn batch_mint_transaction(
     signature: &[u8],
     orders_to_process: &[u32],
 ) -> Transaction {
-    let data = BTFBridge::batchMintCall {
-        encodedOrders: mint_orders_data.to_vec().into(),
-        signature: signature.to_vec().into(),
-        ordersToProcess: orders_to_process.into(),
-    }
-    .abi_encode();
+    let data = batch_mint_calldata(mint_orders_data, signature, orders_to_process);
 
-    pub const DEFAULT_TX_GAS_LIMIT: u64 = 3_000_000;
     ethers_core::types::Transaction {
-        from: params.sender,
-        to: params.bridge.into(),
-        nonce: params.nonce,
-        value: U256::zero(),
-        gas: DEFAULT_TX_GAS_LIMIT.into(),
-        gas_price: Some(params.gas_price),
+        from: params.from.0,
+        to: Some(params.to.0),
+        nonce: params.nonce.0,
+        value: params.value.0,
+        gas: params.gas_limit.into(),
+        gas_price: Some(params.gas_price.0),
         input: data.into(),
         chain_id: Some(params.chain_id.into()),
         ..Default::default()
     }
 }
 
+/// Returns ABI encoded call of the `batchMint` function in Btfbridge contract.
+/// If `orders_to_process` is empty, all the orders are processed.
+pub fn batch_mint_calldata(
+    mint_orders_data: &[u8],
+    signature: &[u8],
+    orders_to_process: &[u32],
+) -> Vec<u8> {
+    BTFBridge::batchMintCall {
+        encodedOrders: mint_orders_data.to_vec().into(),
+        signature: signature.to_vec().into(),
+        ordersToProcess: orders_to_process.into(),
+    }
+    .abi_encode()
+}
+
+/// Decodes the signed orders data from the ABI encoded call of the `batchMint` function
+/// in Btfbridge contract.
+pub fn decode_batch_mint_calldata(calldata: &[u8]) -> anyhow::Result<SignedOrdersData> {
+    let call = BTFBridge::batchMintCall::abi_decode(calldata, true)?;
+    Ok(SignedOrdersData {
+        orders_data: call.encodedOrders.to_vec(),
+        signature: call.signature.to_vec(),
+    })
+}
+
+/// Size of the token name in the BTF bridge events and mint orders.
+pub const TOKEN_NAME_SIZE: usize = 32;
+
+/// Size of the token symbol in the BTF bridge events and mint orders.
+pub const TOKEN_SYMBOL_SIZE: usize = 16;
+
+/// Max token decimals. `10^77` is the largest power of ten which fits into `U256`.
+pub const MAX_TOKEN_DECIMALS: u8 = 77;
+
+/// Token metadata of the burnt event, fitted to the mint order fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurntTokenMetadata {
+    pub name: [u8; TOKEN_NAME_SIZE],
+    pub symbol: [u8; TOKEN_SYMBOL_SIZE],
+    pub decimals: u8,
+}
+
+impl BurntTokenMetadata {
+    /// Fits the event token metadata to the mint order fields:
+    /// name and symbol are truncated or padded with zeros, decimals are clamped.
+    pub fn normalized(event: &BurntEventData) -> Self {
+        Self {
+            name: fit_to_array(&event.name),
+            symbol: fit_to_array(&event.symbol),
+            decimals: event.decimals.min(MAX_TOKEN_DECIMALS),
+        }
+    }
+
+    /// Returns the event token metadata, if it fits the mint order fields as is.
+    pub fn strict(event: &BurntEventData) -> Result<Self, MalformedEventError> {
+        if event.name.len() != TOKEN_NAME_SIZE {
+            return Err(MalformedEventError::NameSize(event.name.len()));
+        }
+        if event.symbol.len() != TOKEN_SYMBOL_SIZE {
+            return Err(MalformedEventError::SymbolSize(event.symbol.len()));
+        }
+        if event.decimals > MAX_TOKEN_DECIMALS {
+            return Err(MalformedEventError::Decimals(event.decimals));
+        }
+
+        Ok(Self::normalized(event))
+    }
+}
+
+/// Reason of the burnt event rejection in the strict mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum MalformedEventError {
+    #[error("token name has {0} bytes, expected {TOKEN_NAME_SIZE}")]
+    NameSize(usize),
+    #[error("token symbol has {0} bytes, expected {TOKEN_SYMBOL_SIZE}")]
+    SymbolSize(usize),
+    #[error("token decimals {0} are greater than {MAX_TOKEN_DECIMALS}")]
+    Decimals(u8),
+}
+
+/// Sanitizes token metadata of the burnt event.
+///
+/// In the strict mode malformed events are rejected. Otherwise, the metadata is normalized
+/// with [`BurntTokenMetadata::normalized`].
+pub fn sanitize_burnt_event(
+    mut event: BurntEventData,
+    strict: bool,
+) -> Result<BurntEventData, MalformedEventError> {
+    let metadata = if strict {
+        BurntTokenMetadata::strict(&event)?
+    } else {
+        BurntTokenMetadata::normalized(&event)
+    };
+
+    event.name = metadata.name.to_vec();
+    event.symbol = metadata.symbol.to_vec();
+    event.decimals = metadata.decimals;
+
+    Ok(event)
+}
+
+fn fit_to_array<const N: usize>(data: &[u8]) -> [u8; N] {
+    let mut buf = [0; N];
+    let size = N.min(data.len());
+    buf[..size].copy_from_slice(&data[..size]);
+    buf
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
 
     use alloy_sol_types::private::{Address, FixedBytes, Uint};
+    use bridge_did::order::MintOrder;
     use did::H256;
     use ethers_core::abi::{Bytes, RawLog};
 
@@ -279,6 +532,131 @@ mod tests {
         assert_eq!(event.sender, event.sender);
     }
 
+    alloy_sol_types::sol! {
+        #[derive(Debug)]
+        event MockDepositEvent(address indexed user, uint256 amount);
+    }
+
+    fn log_of(event: &impl SolEvent) -> Log {
+        let log_data = event.encode_log_data();
+        Log {
+            topics: log_data
+                .topics()
+                .iter()
+                .map(|topic| topic.0.into())
+                .collect(),
+            data: log_data.data.to_vec().into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn registry_dispatches_registered_events() {
+        let registry = EventRegistry::btf_bridge();
+        let addr = Address(FixedBytes([41; 20]));
+
+        let burnt = BurnTokenEvent {
+            sender: addr,
+            amount: Uint::from(5u64),
+            fromERC20: addr,
+            recipientID: vec![1, 2, 3].into(),
+            toToken: FixedBytes([3; 32]),
+            operationID: 1,
+            name: FixedBytes([2; 32]),
+            symbol: FixedBytes([1; 16]),
+            decimals: 18,
+            memo: FixedBytes([0; 32]),
+        };
+        let BridgeEvent::Burnt(event) = registry.decode(&log_of(&burnt)).unwrap() else {
+            panic!("expected burnt event");
+        };
+        assert_eq!(event.recipient_id, vec![1, 2, 3]);
+        assert_eq!(event.decimals, 18);
+
+        let minted = MintTokenEvent {
+            amount: Uint::from(7u64),
+            fromToken: FixedBytes([42; 32]),
+            senderID: FixedBytes([42; 32]),
+            toERC20: addr,
+            recipient: addr,
+            nonce: 32,
+            chargedFee: Uint::from(2u64),
+        };
+        let BridgeEvent::Minted(event) = registry.decode(&log_of(&minted)).unwrap() else {
+            panic!("expected minted event");
+        };
+        assert_eq!(event.nonce, 32);
+
+        let notify = NotifyMinterEvent {
+            notificationType: 1,
+            txSender: addr,
+            userData: vec![4, 5].into(),
+            memo: FixedBytes([0; 32]),
+        };
+        let BridgeEvent::Notify(event) = registry.decode(&log_of(&notify)).unwrap() else {
+            panic!("expected notify event");
+        };
+        assert_eq!(event.user_data, vec![4, 5]);
+    }
+
+    #[test]
+    fn unknown_topic_is_captured_as_raw_log() {
+        let fee_updated = crate::BTFBridge::BurnFeeUpdated {
+            oldFee: Uint::from(1u64),
+            newFee: Uint::from(2u64),
+        };
+        let log = log_of(&fee_updated);
+
+        let event = EventRegistry::btf_bridge().decode(&log).unwrap();
+        assert!(event.kind().is_none());
+        assert!(event.user_address().is_none());
+
+        let BridgeEvent::Unknown { raw } = event else {
+            panic!("expected unknown event");
+        };
+        assert_eq!(raw.topics, vec![H256::from(log.topics[0])]);
+        assert_eq!(raw.data, log.data.to_vec());
+    }
+
+    #[test]
+    fn registered_event_extends_topic_filter() {
+        let registry = EventRegistry::btf_bridge();
+        let filter = registry.logs_filter(
+            H160::default(),
+            EthBlockNumber::Number(1.into()),
+            EthBlockNumber::Number(2.into()),
+        );
+        let expected_topics: Vec<ethers_core::types::H256> = vec![
+            BurnTokenEvent::SIGNATURE_HASH.0.into(),
+            MintTokenEvent::SIGNATURE_HASH.0.into(),
+            NotifyMinterEvent::SIGNATURE_HASH.0.into(),
+        ];
+        assert_eq!(filter.topics.unwrap(), vec![expected_topics]);
+
+        let registry =
+            registry.register(|_: MockDepositEvent| BridgeEvent::Burnt(BurntEventData::default()));
+        let filter = registry.logs_filter(
+            H160::default(),
+            EthBlockNumber::Number(1.into()),
+            EthBlockNumber::Number(2.into()),
+        );
+        let topics = &filter.topics.unwrap()[0];
+        assert_eq!(topics.len(), 4);
+        assert_eq!(
+            topics[3],
+            ethers_core::types::H256::from(MockDepositEvent::SIGNATURE_HASH.0)
+        );
+
+        let deposit = MockDepositEvent {
+            user: Address(FixedBytes([1; 20])),
+            amount: Uint::from(3u64),
+        };
+        assert!(matches!(
+            registry.decode(&log_of(&deposit)).unwrap(),
+            BridgeEvent::Burnt(_)
+        ));
+    }
+
     #[tokio::test]
     async fn test_should_get_paginated_logs() {
         env_logger::init();
@@ -311,39 +689,207 @@ mod tests {
         let evm_client = EthJsonRpcClient::new(client);
 
         // get from 0 to 100
-        let logs =
-            BridgeEvent::collect_logs(&evm_client, 0, 100, ethers_core::types::H160::default())
-                .await
-                .unwrap();
+        let logs = BridgeEvent::collect_logs(
+            &evm_client,
+            0,
+            100,
+            ethers_core::types::H160::default(),
+            None,
+        )
+        .await
+        .unwrap();
         assert_eq!(logs.len(), 0);
 
         // get from 80 to 220 (first result will be empty)
-        let logs =
-            BridgeEvent::collect_logs(&evm_client, 80, 220, ethers_core::types::H160::default())
-                .await
-                .unwrap();
+        let logs = BridgeEvent::collect_logs(
+            &evm_client,
+            80,
+            220,
+            ethers_core::types::H160::default(),
+            None,
+        )
+        .await
+        .unwrap();
         assert_eq!(logs.len(), 21);
 
         // get from 100 to 800 (multiple requests)
-        let logs =
-            BridgeEvent::collect_logs(&evm_client, 100, 800, ethers_core::types::H160::default())
-                .await
-                .unwrap();
+        let logs = BridgeEvent::collect_logs(
+            &evm_client,
+            100,
+            800,
+            ethers_core::types::H160::default(),
+            None,
+        )
+        .await
+        .unwrap();
         assert_eq!(logs.len(), 601);
 
         // get error block
-        let logs =
-            BridgeEvent::collect_logs(&evm_client, 801, 950, ethers_core::types::H160::default())
-                .await
-                .unwrap();
+        let logs = BridgeEvent::collect_logs(
+            &evm_client,
+            801,
+            950,
+            ethers_core::types::H160::default(),
+            None,
+        )
+        .await
+        .unwrap();
         assert_eq!(logs.len(), 950 - 801); // error will be skipped
 
         // get with more blocks than available
+        let logs = BridgeEvent::collect_logs(
+            &evm_client,
+            10,
+            2000,
+            ethers_core::types::H160::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(logs.len(), 800);
+    }
+
+    fn located_log(block_number: Option<u64>, log_index: u64) -> Log {
+        Log {
+            address: ethers_core::types::H160::default(),
+            topics: vec![],
+            data: ethers_core::types::Bytes::default(),
+            block_hash: None,
+            block_number: block_number.map(Into::into),
+            transaction_hash: Some(ethers_core::types::H256::from_low_u64_be(log_index)),
+            transaction_index: None,
+            log_index: Some(log_index.into()),
+            transaction_log_index: None,
+            log_type: None,
+            removed: None,
+        }
+    }
+
+    #[test]
+    fn topic_filter_is_added_to_logs_filter() {
+        let values = vec![
+            did::H256::from_slice(&[1; 32]),
+            did::H256::from_slice(&[2; 32]),
+        ];
+        let signatures = EventRegistry::btf_bridge().topics();
+
+        let filter = EventRegistry::btf_bridge()
+            .with_topic_filter(Some(EventTopicFilter {
+                position: 1,
+                values: values.clone(),
+            }))
+            .logs_filter(
+                H160::default(),
+                EthBlockNumber::Number(1.into()),
+                EthBlockNumber::Number(2.into()),
+            );
+        let expected_values: Vec<_> = values.iter().map(|value| value.0).collect();
+        assert_eq!(
+            filter.topics.unwrap(),
+            vec![signatures.clone(), expected_values.clone()]
+        );
+
+        // Topics before the filtered one match any value.
+        let filter = EventRegistry::btf_bridge()
+            .with_topic_filter(Some(EventTopicFilter {
+                position: 3,
+                values,
+            }))
+            .logs_filter(
+                H160::default(),
+                EthBlockNumber::Number(1.into()),
+                EthBlockNumber::Number(2.into()),
+            );
+        assert_eq!(
+            filter.topics.unwrap(),
+            vec![signatures, vec![], vec![], expected_values]
+        );
+    }
+
+    #[tokio::test]
+    async fn logs_not_matching_topic_filter_are_not_returned() {
+        let signature = ethers_core::types::H256::from(MintTokenEvent::SIGNATURE_HASH.0);
+        let relevant = ethers_core::types::H256::from_low_u64_be(1);
+        let irrelevant = ethers_core::types::H256::from_low_u64_be(2);
+        let log_with_topic = |topic, log_index| Log {
+            topics: vec![signature, topic],
+            ..located_log(Some(10), log_index)
+        };
+
+        let mut logs = HashMap::new();
+        logs.insert(
+            10,
+            vec![
+                log_with_topic(relevant, 0),
+                log_with_topic(irrelevant, 1),
+                log_with_topic(relevant, 2),
+            ],
+        );
+        let client = FakeEthJsonRpcClient { logs, error: None };
+        let evm_client = EthJsonRpcClient::new(client);
+
+        let topic_filter = EventTopicFilter {
+            position: 1,
+            values: vec![relevant.into()],
+        };
         let logs =
-            BridgeEvent::collect_logs(&evm_client, 10, 2000, ethers_core::types::H160::default())
+            BridgeEvent::collect_logs(&evm_client, 10, 10, H160::default(), Some(&topic_filter))
                 .await
                 .unwrap();
-        assert_eq!(logs.len(), 800);
+        let indices: Vec<_> = logs
+            .iter()
+            .map(|log| log.log_index.unwrap().as_u64())
+            .collect();
+        assert_eq!(indices, vec![0, 2]);
+
+        let logs = BridgeEvent::collect_logs(&evm_client, 10, 10, H160::default(), None)
+            .await
+            .unwrap();
+        assert_eq!(logs.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn unordered_logs_are_sorted() {
+        // The node returns logs of the blocks 10..=12 unordered, within and across blocks.
+        let mut logs = HashMap::new();
+        logs.insert(
+            10,
+            vec![
+                located_log(Some(12), 1),
+                located_log(Some(10), 3),
+                located_log(None, 7),
+            ],
+        );
+        logs.insert(
+            11,
+            vec![
+                located_log(Some(11), 2),
+                located_log(Some(10), 0),
+                located_log(Some(12), 0),
+            ],
+        );
+        let client = FakeEthJsonRpcClient { logs, error: None };
+        let evm_client = EthJsonRpcClient::new(client);
+
+        let events = BridgeEvent::collect(&evm_client, 10, 12, H160::default(), None)
+            .await
+            .unwrap();
+
+        let positions: Vec<_> = events
+            .iter()
+            .map(|event| (event.block_number, event.log_index))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                (Some(10), Some(0)),
+                (Some(10), Some(3)),
+                (Some(11), Some(2)),
+                (Some(12), Some(0)),
+                (Some(12), Some(1)),
+                (None, Some(7)),
+            ]
+        );
     }
 
     #[derive(Clone)]
@@ -360,14 +906,18 @@ mod tests {
         ) -> std::pin::Pin<
             Box<dyn std::future::Future<Output = anyhow::Result<jsonrpc_core::Response>> + Send>,
         > {
-            // get block number for eth_getLogs request
-            let (id, from_block, to_block) = match request {
+            // get block number and topics for eth_getLogs request
+            let (id, from_block, to_block, topics) = match request {
                 jsonrpc_core::Request::Single(jsonrpc_core::Call::MethodCall(method_call)) => {
                     match method_call.params {
                         jsonrpc_core::Params::Array(params) => {
                             let obj = params[0].as_object().unwrap();
                             let from_block = obj.get("fromBlock").unwrap();
                             let to_block = obj.get("toBlock").unwrap();
+                            let topics: Vec<Vec<ethers_core::types::H256>> = obj
+                                .get("topics")
+                                .map(|topics| serde_json::from_value(topics.clone()).unwrap())
+                                .unwrap_or_default();
 
                             let to_block = match to_block.as_str().unwrap() {
                                 "latest" => u64::MAX,
@@ -386,6 +936,7 @@ mod tests {
                                 )
                                 .unwrap(),
                                 to_block,
+                                topics,
                             )
                         }
                         params => unimplemented!("expected array params: {params:?}"),
@@ -415,7 +966,16 @@ mod tests {
                     });
                 }
                 if let Some(block_logs) = self.logs.get(&block_number) {
-                    logs.extend_from_slice(block_logs);
+                    // Topic 0 filter is not applied, so the test logs may have no topics.
+                    logs.extend(block_logs.iter().cloned().filter(|log| {
+                        topics.iter().enumerate().skip(1).all(|(position, values)| {
+                            values.is_empty()
+                                || log
+                                    .topics
+                                    .get(position)
+                                    .is_some_and(|topic| values.contains(topic))
+                        })
+                    }));
                 }
             }
 
@@ -430,4 +990,84 @@ mod tests {
             Box::pin(async { Ok(response) })
         }
     }
+
+    fn burnt_event(name: &[u8], symbol: &[u8], decimals: u8) -> BurntEventData {
+        BurntEventData {
+            name: name.to_vec(),
+            symbol: symbol.to_vec(),
+            decimals,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn batch_mint_calldata_roundtrip() {
+        let orders_data = vec![7; MintOrder::ENCODED_DATA_SIZE * 2];
+        let signature = vec![8; 65];
+        let calldata = batch_mint_calldata(&orders_data, &signature, &[1]);
+
+        let decoded = decode_batch_mint_calldata(&calldata).unwrap();
+        assert_eq!(decoded.orders_data, orders_data);
+        assert_eq!(decoded.signature, signature);
+
+        assert!(decode_batch_mint_calldata(&calldata[..calldata.len() - 1]).is_err());
+        assert!(decode_batch_mint_calldata(&[]).is_err());
+    }
+
+    #[test]
+    fn burnt_event_metadata_is_truncated() {
+        let event = burnt_event(&[1; 40], &[2; 20], 18);
+
+        let sanitized = sanitize_burnt_event(event, false).unwrap();
+        assert_eq!(sanitized.name, vec![1; TOKEN_NAME_SIZE]);
+        assert_eq!(sanitized.symbol, vec![2; TOKEN_SYMBOL_SIZE]);
+        assert_eq!(sanitized.decimals, 18);
+    }
+
+    #[test]
+    fn burnt_event_metadata_is_padded() {
+        let event = burnt_event(b"Token", b"TKN", 6);
+
+        let metadata = BurntTokenMetadata::normalized(&event);
+        assert_eq!(&metadata.name[..5], b"Token");
+        assert!(metadata.name[5..].iter().all(|b| *b == 0));
+        assert_eq!(&metadata.symbol[..3], b"TKN");
+        assert!(metadata.symbol[3..].iter().all(|b| *b == 0));
+
+        let sanitized = sanitize_burnt_event(event, false).unwrap();
+        assert_eq!(sanitized.name, metadata.name.to_vec());
+        assert_eq!(sanitized.symbol, metadata.symbol.to_vec());
+    }
+
+    #[test]
+    fn burnt_event_decimals_are_clamped() {
+        let event = burnt_event(&[1; 32], &[2; 16], u8::MAX);
+
+        let sanitized = sanitize_burnt_event(event, false).unwrap();
+        assert_eq!(sanitized.decimals, MAX_TOKEN_DECIMALS);
+    }
+
+    #[test]
+    fn malformed_burnt_event_is_rejected_in_strict_mode() {
+        let event = burnt_event(&[1; 32], &[2; 16], 18);
+        assert!(sanitize_burnt_event(event, true).is_ok());
+
+        let event = burnt_event(&[1; 31], &[2; 16], 18);
+        assert_eq!(
+            sanitize_burnt_event(event, true).unwrap_err(),
+            MalformedEventError::NameSize(31)
+        );
+
+        let event = burnt_event(&[1; 32], &[2; 17], 18);
+        assert_eq!(
+            sanitize_burnt_event(event, true).unwrap_err(),
+            MalformedEventError::SymbolSize(17)
+        );
+
+        let event = burnt_event(&[1; 32], &[2; 16], 78);
+        assert_eq!(
+            sanitize_burnt_event(event, true).unwrap_err(),
+            MalformedEventError::Decimals(78)
+        );
+    }
 }
@@ -0,0 +1,189 @@
+//! Decoding of the user payloads, carried by the BTF bridge events.
+//!
+//! The payloads are set by the event emitters, so any bytes may come here. The size of
+//! the payload is checked before the decoding, and the decoding work is bounded by the
+//! candid decoder quotas, so a crafted payload can't exhaust the canister instruction limit.
+
+use bridge_did::error::Error;
+use candid::de::DecoderConfig;
+use candid::{CandidType, Decode};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// Maximum size of the event payload in bytes.
+pub const MAX_EVENT_PAYLOAD_SIZE: usize = 8 * 1024;
+
+/// Candid decoding quota for the event payload. The decoding cost grows with the payload
+/// size and the depth of the types, so the quota bounds both.
+pub const EVENT_PAYLOAD_DECODING_QUOTA: usize = 200_000;
+
+/// Candid quota for the skipped values of the event payload, e.g. unknown record fields.
+pub const EVENT_PAYLOAD_SKIPPING_QUOTA: usize = 10_000;
+
+/// Error of the event payload decoding.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PayloadDecodeError {
+    #[error("payload size {size} exceeds the limit of {limit} bytes")]
+    TooLarge { size: usize, limit: usize },
+
+    #[error("failed to decode payload: {0}")]
+    Candid(String),
+}
+
+impl From<PayloadDecodeError> for Error {
+    fn from(e: PayloadDecodeError) -> Self {
+        Error::Serialization(e.to_string())
+    }
+}
+
+/// Returns an error if the `payload` is longer than `limit` bytes.
+pub fn check_payload_size(payload: &[u8], limit: usize) -> Result<(), PayloadDecodeError> {
+    if payload.len() > limit {
+        return Err(PayloadDecodeError::TooLarge {
+            size: payload.len(),
+            limit,
+        });
+    }
+
+    Ok(())
+}
+
+/// Candid decoder configuration for the event payloads.
+pub fn decoder_config() -> DecoderConfig {
+    let mut config = DecoderConfig::new();
+    config
+        .set_decoding_quota(EVENT_PAYLOAD_DECODING_QUOTA)
+        .set_skipping_quota(EVENT_PAYLOAD_SKIPPING_QUOTA)
+        .set_full_error_message(false);
+    config
+}
+
+/// Decodes the candid encoded value of type `T` from the event payload.
+///
+/// The payload should contain exactly one value and should not be longer than
+/// [`MAX_EVENT_PAYLOAD_SIZE`].
+pub fn decode_candid_payload<T>(payload: &[u8]) -> Result<T, PayloadDecodeError>
+where
+    T: CandidType + DeserializeOwned,
+{
+    check_payload_size(payload, MAX_EVENT_PAYLOAD_SIZE)?;
+    Decode!([decoder_config()]; payload, T).map_err(|e| PayloadDecodeError::Candid(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::icrc_withdrawal::IcrcWithdrawalRecipient;
+    use bridge_did::id256::Id256;
+    use bridge_did::op_id::OperationId;
+    use bridge_did::reason::{ApproveAfterMint, BtcDeposit, Icrc2Burn};
+    use candid::{Encode, Principal};
+    use did::H160;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn icrc2_burn() -> Icrc2Burn {
+        Icrc2Burn {
+            sender: Principal::from_slice(&[1; 29]),
+            amount: 1000u64.into(),
+            icrc2_token_principal: Principal::from_slice(&[2; 29]),
+            erc20_token_address: H160::from_slice(&[3; 20]),
+            from_subaccount: Some([4; 32]),
+            recipient_address: H160::from_slice(&[5; 20]),
+            approve_after_mint: Some(ApproveAfterMint {
+                approve_spender: H160::from_slice(&[6; 20]),
+                approve_amount: 100u64.into(),
+            }),
+            fee_payer: Some(H160::from_slice(&[7; 20])),
+            confirmation_delay_secs: Some(60),
+            preference_fields: None,
+        }
+    }
+
+    /// Decodes the payload with every decoder, reachable from the event data.
+    fn decode_with_all_decoders(payload: &[u8]) {
+        let _ = decode_candid_payload::<Icrc2Burn>(payload);
+        let _ = decode_candid_payload::<BtcDeposit>(payload);
+        let _ = decode_candid_payload::<OperationId>(payload);
+        let _ = IcrcWithdrawalRecipient::decode(payload);
+        let _ = Id256::try_from_slice(payload);
+    }
+
+    #[test]
+    fn valid_payload_is_decoded() {
+        let burn = icrc2_burn();
+        let decoded: Icrc2Burn = decode_candid_payload(&Encode!(&burn).unwrap()).unwrap();
+        assert_eq!(decoded.sender, burn.sender);
+        assert_eq!(decoded.from_subaccount, burn.from_subaccount);
+
+        let id: OperationId =
+            decode_candid_payload(&Encode!(&OperationId::new(42)).unwrap()).unwrap();
+        assert_eq!(id, OperationId::new(42));
+    }
+
+    #[test]
+    fn oversized_payload_is_rejected_before_decoding() {
+        let payload = Encode!(&vec![0u8; MAX_EVENT_PAYLOAD_SIZE]).unwrap();
+        assert_eq!(
+            decode_candid_payload::<Vec<u8>>(&payload),
+            Err(PayloadDecodeError::TooLarge {
+                size: payload.len(),
+                limit: MAX_EVENT_PAYLOAD_SIZE,
+            })
+        );
+
+        let payload = Encode!(&vec![0u8; 1024]).unwrap();
+        assert!(decode_candid_payload::<Vec<u8>>(&payload).is_ok());
+    }
+
+    #[test]
+    fn payload_with_trailing_bytes_is_rejected() {
+        let mut payload = Encode!(&icrc2_burn()).unwrap();
+        payload.push(0);
+        assert!(matches!(
+            decode_candid_payload::<Icrc2Burn>(&payload),
+            Err(PayloadDecodeError::Candid(_))
+        ));
+    }
+
+    #[test]
+    fn payload_exceeding_decoding_quota_is_rejected() {
+        // A long vector of empty values is small on the wire, but expensive to decode.
+        let payload = Encode!(&vec![(); MAX_EVENT_PAYLOAD_SIZE * 100]).unwrap();
+        assert!(payload.len() <= MAX_EVENT_PAYLOAD_SIZE);
+        assert!(matches!(
+            decode_candid_payload::<Vec<()>>(&payload),
+            Err(PayloadDecodeError::Candid(_))
+        ));
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_payload_never_panics(payload in prop::collection::vec(any::<u8>(), 0..1024)) {
+            decode_with_all_decoders(&payload);
+        }
+
+        #[test]
+        fn candid_prefixed_payload_never_panics(body in prop::collection::vec(any::<u8>(), 0..512)) {
+            let mut payload = b"DIDL".to_vec();
+            payload.extend_from_slice(&body);
+            decode_with_all_decoders(&payload);
+        }
+
+        #[test]
+        fn mutated_payload_never_panics(
+            mutations in prop::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 1..8),
+            truncate in any::<prop::sample::Index>(),
+        ) {
+            let mut payload = Encode!(&icrc2_burn()).unwrap();
+            for (index, byte) in mutations {
+                let index = index.index(payload.len());
+                payload[index] = byte;
+            }
+            decode_with_all_decoders(&payload);
+
+            payload.truncate(truncate.index(payload.len()));
+            decode_with_all_decoders(&payload);
+        }
+    }
+}
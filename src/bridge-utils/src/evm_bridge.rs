@@ -1,12 +1,17 @@
+use alloy_sol_types::SolEvent;
+use bridge_did::event_data::MintTokenEvent;
+use bridge_did::evm_finality::EvmFinality;
 use bridge_did::evm_link::EvmLink;
+use bridge_did::order::{SignedOrdersData, TxParams, DEFAULT_TX_GAS_LIMIT};
 use candid::CandidType;
 use did::{H160, U256};
 use ethereum_json_rpc_client::{Client, EthJsonRpcClient};
-use ethers_core::types::{BlockNumber, U256 as EthU256};
-use jsonrpc_core::Id;
+use ethers_core::types::{BlockNumber, Transaction, TransactionReceipt, H256, U256 as EthU256};
+use jsonrpc_core::{Call, Id, MethodCall, Output, Params, Request, Response, Version};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use crate::btf_events::TxParams;
+use crate::btf_events::decode_batch_mint_calldata;
 use crate::query::{batch_query, Query, QueryType, CHAINID_ID, LATEST_BLOCK_ID, NONCE_ID};
 
 /// Information about EVM on a bridge side.
@@ -20,14 +25,14 @@ pub struct EvmInfo {
 /// Parameters to query from EVM.
 #[derive(Default, Debug, Clone, Serialize, Deserialize, CandidType, PartialEq, Eq)]
 pub struct EvmParams {
-    pub chain_id: u32,
+    pub chain_id: u64,
     pub next_block: u64,
     pub nonce: u64,
     pub gas_price: U256,
 }
 
 impl EvmParams {
-    pub fn new(chain_id: u32, next_block: u64, nonce: u64, gas_price: U256) -> Self {
+    pub fn new(chain_id: u64, next_block: u64, nonce: u64, gas_price: U256) -> Self {
         Self {
             chain_id,
             next_block,
@@ -36,13 +41,16 @@ impl EvmParams {
         }
     }
 
-    /// Returns transaction parameters for the EVM.
-    pub fn create_tx_params(&self, sender: H160, bridge: H160) -> TxParams {
+    /// Returns parameters of the transaction from `sender` to `contract` without value.
+    /// Gas limit is [`DEFAULT_TX_GAS_LIMIT`].
+    pub fn create_tx_params(&self, sender: H160, contract: H160) -> TxParams {
         TxParams {
-            sender: sender.0,
-            bridge: bridge.0,
+            from: sender,
+            to: contract,
             nonce: self.nonce.into(),
-            gas_price: self.gas_price.0,
+            gas_price: self.gas_price.clone(),
+            gas_limit: DEFAULT_TX_GAS_LIMIT,
+            value: U256::zero(),
             chain_id: self.chain_id,
         }
     }
@@ -89,10 +97,684 @@ impl EvmParams {
         };
 
         Ok(Self {
-            chain_id: chain_id.0.as_u32(),
+            chain_id: chain_id.0.as_u64(),
             next_block: next_block.0.as_u64(),
             nonce: nonce.0.as_u64(),
             gas_price,
         })
     }
 }
+
+/// Returns number of the last EVM block, which satisfies the given finality.
+pub async fn query_final_block_number(
+    evm_client: &EthJsonRpcClient<impl Client>,
+    finality: EvmFinality,
+) -> anyhow::Result<u64> {
+    let tag = match finality {
+        EvmFinality::Latest => return evm_client.get_block_number().await,
+        EvmFinality::Confirmations(depth) => {
+            let latest_block = evm_client.get_block_number().await?;
+            return Ok(latest_block.saturating_sub(depth));
+        }
+        EvmFinality::Safe => BlockNumber::Safe,
+        EvmFinality::Finalized => BlockNumber::Finalized,
+    };
+
+    evm_client
+        .get_block_by_number(tag)
+        .await?
+        .number
+        .map(|number| number.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("EVM returned `{finality}` block without number"))
+}
+
+/// Sends the signed transaction to the private relay `method` instead of the public mempool.
+/// Returns the transaction hash.
+pub async fn send_private_transaction(
+    relay_client: &EthJsonRpcClient<impl Client>,
+    method: &str,
+    tx: &Transaction,
+) -> anyhow::Result<H256> {
+    let raw_tx = format!("0x{}", hex::encode(tx.rlp()));
+    let request = Request::Single(Call::MethodCall(MethodCall {
+        jsonrpc: Some(Version::V2),
+        method: method.into(),
+        params: Params::Array(vec![serde_json::json!({ "tx": raw_tx })]),
+        id: Id::Num(1),
+    }));
+
+    match relay_client.request(request).await? {
+        Response::Single(Output::Success(success)) => Ok(serde_json::from_value(success.result)?),
+        Response::Single(Output::Failure(failure)) => Err(anyhow::anyhow!(
+            "private relay rejected the transaction: {}",
+            failure.error
+        )),
+        Response::Batch(_) => Err(anyhow::anyhow!("Unexpected response format")),
+    }
+}
+
+/// Sends the signed transaction through the private relay, if it is given. If the relay
+/// rejects the transaction, it is sent to the public mempool. Returns the transaction hash.
+pub async fn send_transaction(
+    evm_client: &EthJsonRpcClient<impl Client>,
+    private_relay: Option<(&EthJsonRpcClient<impl Client>, &str)>,
+    tx: Transaction,
+) -> anyhow::Result<H256> {
+    if let Some((relay_client, method)) = private_relay {
+        match send_private_transaction(relay_client, method, &tx).await {
+            Ok(hash) => return Ok(hash),
+            Err(e) => log::warn!(
+                "Failed to send transaction to the private relay, sending it to the public mempool: {e}"
+            ),
+        }
+    }
+
+    evm_client.send_raw_transaction(tx).await
+}
+
+/// Checks if the transaction is known to the EVM node: pending in the mempool or mined.
+/// Transactions, dropped from the mempool, are unknown.
+pub async fn is_transaction_known(
+    evm_client: &EthJsonRpcClient<impl Client>,
+    hash: H256,
+) -> anyhow::Result<bool> {
+    let request = Request::Single(Call::MethodCall(MethodCall {
+        jsonrpc: Some(Version::V2),
+        method: "eth_getTransactionByHash".into(),
+        params: Params::Array(vec![serde_json::to_value(hash)?]),
+        id: Id::Num(1),
+    }));
+
+    match evm_client.request(request).await? {
+        Response::Single(Output::Success(success)) => Ok(!success.result.is_null()),
+        Response::Single(Output::Failure(failure)) => Err(anyhow::anyhow!(
+            "failed to query transaction {hash:#x}: {}",
+            failure.error
+        )),
+        Response::Batch(_) => Err(anyhow::anyhow!("Unexpected response format")),
+    }
+}
+
+/// Cost of the mined `batchMint` transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MintTxCost {
+    /// Gas used by the transaction, multiplied by its effective gas price.
+    pub total: U256,
+    /// Number of the orders, minted by the transaction.
+    pub minted_orders: u64,
+    /// Orders, sent in the transaction.
+    pub orders: SignedOrdersData,
+}
+
+impl MintTxCost {
+    /// Builds the cost of the mined `batchMint` transaction to the `bridge_contract`.
+    pub fn from_mined_tx(
+        tx: &Transaction,
+        receipt: &TransactionReceipt,
+        bridge_contract: &H160,
+    ) -> anyhow::Result<Self> {
+        let gas_used = receipt
+            .gas_used
+            .ok_or_else(|| anyhow::anyhow!("gas used is missing in the receipt"))?;
+        let gas_price = receipt
+            .effective_gas_price
+            .or(tx.gas_price)
+            .ok_or_else(|| anyhow::anyhow!("gas price is missing in the receipt"))?;
+        let total = gas_used
+            .checked_mul(gas_price)
+            .ok_or_else(|| anyhow::anyhow!("transaction cost overflow"))?;
+
+        let minted_topic = H256::from(MintTokenEvent::SIGNATURE_HASH.0);
+        let minted_orders = receipt
+            .logs
+            .iter()
+            .filter(|log| {
+                H160::from(log.address) == *bridge_contract
+                    && log.topics.first() == Some(&minted_topic)
+            })
+            .count() as u64;
+
+        Ok(Self {
+            total: total.into(),
+            minted_orders,
+            orders: decode_batch_mint_calldata(&tx.input)?,
+        })
+    }
+
+    /// Share of the transaction cost for a single minted order.
+    pub fn per_order(&self) -> U256 {
+        if self.minted_orders == 0 {
+            return self.total.clone();
+        }
+
+        (self.total.0 / EthU256::from(self.minted_orders)).into()
+    }
+
+    /// Returns the fee payer of the order with the given nonce, if the order
+    /// is sent in the transaction.
+    pub fn fee_payer(&self, nonce: u32) -> Option<H160> {
+        (0..self.orders.orders_number())
+            .filter_map(|idx| self.orders.reader(idx))
+            .find(|order| order.get_nonce() == nonce)
+            .map(|order| order.get_fee_payer())
+    }
+}
+
+/// Queries the mined `batchMint` transaction to the `bridge_contract` and its receipt.
+/// Returns `None` if the node doesn't know the transaction or its receipt.
+pub async fn query_mint_tx_cost(
+    evm_client: &EthJsonRpcClient<impl Client>,
+    hash: H256,
+    bridge_contract: &H160,
+) -> anyhow::Result<Option<MintTxCost>> {
+    let tx: Option<Transaction> =
+        query_by_hash(evm_client, "eth_getTransactionByHash", hash).await?;
+    let receipt: Option<TransactionReceipt> =
+        query_by_hash(evm_client, "eth_getTransactionReceipt", hash).await?;
+    let (Some(tx), Some(receipt)) = (tx, receipt) else {
+        return Ok(None);
+    };
+
+    MintTxCost::from_mined_tx(&tx, &receipt, bridge_contract).map(Some)
+}
+
+/// Calls the JSON RPC `method` with the transaction hash and deserializes its nullable result.
+async fn query_by_hash<T: DeserializeOwned>(
+    evm_client: &EthJsonRpcClient<impl Client>,
+    method: &str,
+    hash: H256,
+) -> anyhow::Result<Option<T>> {
+    let request = Request::Single(Call::MethodCall(MethodCall {
+        jsonrpc: Some(Version::V2),
+        method: method.into(),
+        params: Params::Array(vec![serde_json::to_value(hash)?]),
+        id: Id::Num(1),
+    }));
+
+    match evm_client.request(request).await? {
+        Response::Single(Output::Success(success)) => Ok(serde_json::from_value(success.result)?),
+        Response::Single(Output::Failure(failure)) => Err(anyhow::anyhow!(
+            "failed to query transaction {hash:#x}: {}",
+            failure.error
+        )),
+        Response::Batch(_) => Err(anyhow::anyhow!("Unexpected response format")),
+    }
+}
+
+/// Selector of the ERC-20 `totalSupply()` function.
+const TOTAL_SUPPLY_SELECTOR: [u8; 4] = [0x18, 0x16, 0x0d, 0xdd];
+
+/// Queries the total supply of the ERC-20 token at the latest block.
+pub async fn query_total_supply(
+    evm_client: &EthJsonRpcClient<impl Client>,
+    token: H160,
+) -> anyhow::Result<U256> {
+    let call = serde_json::json!({
+        "to": token,
+        "data": format!("0x{}", hex::encode(TOTAL_SUPPLY_SELECTOR)),
+    });
+    let request = Request::Single(Call::MethodCall(MethodCall {
+        jsonrpc: Some(Version::V2),
+        method: "eth_call".into(),
+        params: Params::Array(vec![call, serde_json::json!("latest")]),
+        id: Id::Num(1),
+    }));
+
+    match evm_client.request(request).await? {
+        Response::Single(Output::Success(success)) => {
+            let data = success.result.as_str().unwrap_or_default();
+            let data = hex::decode(data.strip_prefix("0x").unwrap_or(data))?;
+            if data.len() != 32 {
+                anyhow::bail!("unexpected total supply of token {token}: {data:?}");
+            }
+            Ok(U256::from_big_endian(&data))
+        }
+        Response::Single(Output::Failure(failure)) => Err(anyhow::anyhow!(
+            "failed to query total supply of token {token}: {}",
+            failure.error
+        )),
+        Response::Batch(_) => Err(anyhow::anyhow!("Unexpected response format")),
+    }
+}
+
+/// Selector of the EIP-2612 `DOMAIN_SEPARATOR()` function.
+const DOMAIN_SEPARATOR_SELECTOR: [u8; 4] = [0x36, 0x44, 0xe5, 0x15];
+
+/// Checks if the token contract supports EIP-2612 permits, by calling its `DOMAIN_SEPARATOR()`.
+/// Tokens, which revert the call or return no data, don't support permits.
+pub async fn supports_permit(
+    evm_client: &EthJsonRpcClient<impl Client>,
+    token: H160,
+) -> anyhow::Result<bool> {
+    let call = serde_json::json!({
+        "to": token,
+        "data": format!("0x{}", hex::encode(DOMAIN_SEPARATOR_SELECTOR)),
+    });
+    let request = Request::Single(Call::MethodCall(MethodCall {
+        jsonrpc: Some(Version::V2),
+        method: "eth_call".into(),
+        params: Params::Array(vec![call, serde_json::json!("latest")]),
+        id: Id::Num(1),
+    }));
+
+    match evm_client.request(request).await? {
+        Response::Single(Output::Success(success)) => {
+            let data = success.result.as_str().unwrap_or_default();
+            let data = data.strip_prefix("0x").unwrap_or(data);
+            Ok(data.len() == 64)
+        }
+        Response::Single(Output::Failure(failure)) => {
+            log::debug!("token {token} doesn't support permit: {}", failure.error);
+            Ok(false)
+        }
+        Response::Batch(_) => Err(anyhow::anyhow!("Unexpected response format")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+
+    use bridge_did::order::MintOrder;
+    use ethers_core::types::{Block, Log};
+    use serde_json::Value;
+
+    use super::*;
+    use crate::btf_events::batch_mint_calldata;
+
+    const LATEST_BLOCK: u64 = 100;
+    const TAGGED_BLOCK: u64 = 90;
+    const TX_HASH: u64 = 42;
+    /// Token, which implements EIP-2612.
+    const PERMIT_TOKEN: [u8; 20] = [1; 20];
+
+    /// Client which records all the requests it receives.
+    #[derive(Clone, Default)]
+    struct RecordingClient {
+        requests: Arc<Mutex<Vec<(String, Vec<Value>)>>>,
+        /// Method, which requests are rejected with an error.
+        rejected_method: Option<&'static str>,
+    }
+
+    impl RecordingClient {
+        fn take_requests(&self) -> Vec<(String, Vec<Value>)> {
+            std::mem::take(&mut *self.requests.lock().unwrap())
+        }
+    }
+
+    impl Client for RecordingClient {
+        fn send_rpc_request(
+            &self,
+            request: jsonrpc_core::Request,
+        ) -> Pin<Box<dyn Future<Output = anyhow::Result<jsonrpc_core::Response>> + Send>> {
+            let call = match request {
+                jsonrpc_core::Request::Single(jsonrpc_core::Call::MethodCall(call)) => call,
+                _ => unimplemented!("expected single method call request"),
+            };
+            let params = match call.params {
+                jsonrpc_core::Params::Array(params) => params,
+                jsonrpc_core::Params::None => vec![],
+                params => unimplemented!("expected array params: {params:?}"),
+            };
+
+            self.requests
+                .lock()
+                .unwrap()
+                .push((call.method.clone(), params.clone()));
+
+            if self.rejected_method == Some(call.method.as_str()) {
+                let response = jsonrpc_core::Response::Single(jsonrpc_core::Output::Failure(
+                    jsonrpc_core::Failure {
+                        jsonrpc: None,
+                        error: jsonrpc_core::Error::invalid_request(),
+                        id: call.id,
+                    },
+                ));
+                return Box::pin(async { Ok(response) });
+            }
+
+            let result = match call.method.as_str() {
+                "eth_blockNumber" => serde_json::json!(format!("{LATEST_BLOCK:#x}")),
+                "eth_getBlockByNumber" => serde_json::to_value(Block::<H256> {
+                    number: Some(TAGGED_BLOCK.into()),
+                    ..Default::default()
+                })
+                .unwrap(),
+                "eth_sendRawTransaction" | "eth_sendPrivateTransaction" => {
+                    serde_json::to_value(H256::from_low_u64_be(TX_HASH)).unwrap()
+                }
+                // Only the transaction with `TX_HASH` is known to the node.
+                "eth_getTransactionByHash" => {
+                    let known = serde_json::to_value(H256::from_low_u64_be(TX_HASH)).unwrap();
+                    if params[0] == known {
+                        serde_json::to_value(Transaction::default()).unwrap()
+                    } else {
+                        Value::Null
+                    }
+                }
+                // Every token has the supply of `TX_HASH`.
+                "eth_call" if params[0]["data"] == "0x18160ddd" => {
+                    serde_json::to_value(H256::from_low_u64_be(TX_HASH)).unwrap()
+                }
+                // Only the `PERMIT_TOKEN` has the domain separator.
+                "eth_call" => {
+                    let permit_token = serde_json::to_value(H160::from_slice(&PERMIT_TOKEN));
+                    if params[0]["to"] == permit_token.unwrap() {
+                        serde_json::to_value(H256::from_low_u64_be(TX_HASH)).unwrap()
+                    } else {
+                        serde_json::json!("0x")
+                    }
+                }
+                method => unimplemented!("unexpected method: {method}"),
+            };
+
+            let response = jsonrpc_core::Response::Single(jsonrpc_core::Output::Success(
+                jsonrpc_core::Success {
+                    jsonrpc: None,
+                    result,
+                    id: call.id,
+                },
+            ));
+
+            Box::pin(async { Ok(response) })
+        }
+    }
+
+    #[tokio::test]
+    async fn should_query_block_by_configured_tag() {
+        let client = RecordingClient::default();
+        let evm_client = EthJsonRpcClient::new(client.clone());
+
+        for (finality, tag) in [
+            (EvmFinality::Safe, "safe"),
+            (EvmFinality::Finalized, "finalized"),
+        ] {
+            let block = query_final_block_number(&evm_client, finality)
+                .await
+                .unwrap();
+            assert_eq!(block, TAGGED_BLOCK);
+
+            let requests = client.take_requests();
+            assert_eq!(requests.len(), 1);
+            assert_eq!(requests[0].0, "eth_getBlockByNumber");
+            assert_eq!(requests[0].1[0], Value::String(tag.into()));
+        }
+    }
+
+    #[tokio::test]
+    async fn should_query_latest_block_number() {
+        let client = RecordingClient::default();
+        let evm_client = EthJsonRpcClient::new(client.clone());
+
+        let block = query_final_block_number(&evm_client, EvmFinality::Latest)
+            .await
+            .unwrap();
+        assert_eq!(block, LATEST_BLOCK);
+
+        let block = query_final_block_number(&evm_client, EvmFinality::Confirmations(12))
+            .await
+            .unwrap();
+        assert_eq!(block, LATEST_BLOCK - 12);
+
+        let block = query_final_block_number(&evm_client, EvmFinality::Confirmations(1000))
+            .await
+            .unwrap();
+        assert_eq!(block, 0);
+
+        let requests = client.take_requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests
+            .iter()
+            .all(|(method, _)| method == "eth_blockNumber"));
+    }
+
+    #[tokio::test]
+    async fn should_send_transaction_to_private_relay() {
+        let public = RecordingClient::default();
+        let relay = RecordingClient::default();
+
+        let tx = Transaction::default();
+        let hash = send_transaction(
+            &EthJsonRpcClient::new(public.clone()),
+            Some((
+                &EthJsonRpcClient::new(relay.clone()),
+                "eth_sendPrivateTransaction",
+            )),
+            tx.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(hash, H256::from_low_u64_be(TX_HASH));
+
+        assert!(public.take_requests().is_empty());
+        let requests = relay.take_requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].0, "eth_sendPrivateTransaction");
+        assert_eq!(
+            requests[0].1[0]["tx"],
+            Value::String(format!("0x{}", hex::encode(tx.rlp())))
+        );
+    }
+
+    #[tokio::test]
+    async fn should_fall_back_to_public_mempool() {
+        let public = RecordingClient::default();
+        let relay = RecordingClient {
+            rejected_method: Some("eth_sendPrivateTransaction"),
+            ..Default::default()
+        };
+
+        let hash = send_transaction(
+            &EthJsonRpcClient::new(public.clone()),
+            Some((
+                &EthJsonRpcClient::new(relay.clone()),
+                "eth_sendPrivateTransaction",
+            )),
+            Transaction::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(hash, H256::from_low_u64_be(TX_HASH));
+
+        assert_eq!(relay.take_requests().len(), 1);
+        let requests = public.take_requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].0, "eth_sendRawTransaction");
+    }
+
+    #[tokio::test]
+    async fn should_send_to_public_mempool_without_relay() {
+        let public = RecordingClient::default();
+
+        send_transaction(
+            &EthJsonRpcClient::new(public.clone()),
+            None::<(&EthJsonRpcClient<RecordingClient>, &str)>,
+            Transaction::default(),
+        )
+        .await
+        .unwrap();
+
+        let requests = public.take_requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].0, "eth_sendRawTransaction");
+    }
+
+    #[tokio::test]
+    async fn should_detect_dropped_transaction() {
+        let client = RecordingClient::default();
+        let evm_client = EthJsonRpcClient::new(client.clone());
+
+        assert!(
+            is_transaction_known(&evm_client, H256::from_low_u64_be(TX_HASH))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !is_transaction_known(&evm_client, H256::from_low_u64_be(TX_HASH + 1))
+                .await
+                .unwrap()
+        );
+
+        let requests = client.take_requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests
+            .iter()
+            .all(|(method, _)| method == "eth_getTransactionByHash"));
+    }
+
+    #[tokio::test]
+    async fn should_fail_transaction_query_on_node_error() {
+        let client = RecordingClient {
+            rejected_method: Some("eth_getTransactionByHash"),
+            ..Default::default()
+        };
+        let evm_client = EthJsonRpcClient::new(client);
+
+        assert!(
+            is_transaction_known(&evm_client, H256::from_low_u64_be(TX_HASH))
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn tx_params_are_created_from_evm_params() {
+        let params = EvmParams::new(355113, 10, 42, 1_000u64.into());
+        let sender = H160::from_slice(&[1; 20]);
+        let contract = H160::from_slice(&[2; 20]);
+
+        let tx_params = params.create_tx_params(sender.clone(), contract.clone());
+        assert_eq!(tx_params.from, sender);
+        assert_eq!(tx_params.to, contract);
+        assert_eq!(tx_params.nonce, 42u64.into());
+        assert_eq!(tx_params.gas_price, 1_000u64.into());
+        assert_eq!(tx_params.chain_id, 355113);
+        assert_eq!(tx_params.gas_limit, DEFAULT_TX_GAS_LIMIT);
+        assert_eq!(tx_params.value, U256::zero());
+
+        let tx_params = tx_params.with_gas_limit(21_000);
+        assert_eq!(tx_params.gas_limit, 21_000);
+        assert_eq!(tx_params.nonce, 42u64.into());
+    }
+
+    #[tokio::test]
+    async fn should_detect_permit_support() {
+        let client = RecordingClient::default();
+        let evm_client = EthJsonRpcClient::new(client.clone());
+
+        let permit_token = H160::from_slice(&PERMIT_TOKEN);
+        assert!(supports_permit(&evm_client, permit_token.clone())
+            .await
+            .unwrap());
+        assert!(!supports_permit(&evm_client, H160::from_slice(&[2; 20]))
+            .await
+            .unwrap());
+
+        let requests = client.take_requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].0, "eth_call");
+        assert_eq!(requests[0].1[0]["data"], Value::String("0x3644e515".into()));
+        assert_eq!(requests[0].1[1], Value::String("latest".into()));
+
+        // Reverted call means that the token has no `DOMAIN_SEPARATOR()`.
+        let client = RecordingClient {
+            rejected_method: Some("eth_call"),
+            ..Default::default()
+        };
+        assert!(
+            !supports_permit(&EthJsonRpcClient::new(client), permit_token)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn should_query_total_supply() {
+        let client = RecordingClient::default();
+        let evm_client = EthJsonRpcClient::new(client.clone());
+
+        let supply = query_total_supply(&evm_client, H160::from_slice(&[2; 20]))
+            .await
+            .unwrap();
+        assert_eq!(supply, U256::from(TX_HASH));
+
+        let requests = client.take_requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].1[0]["data"], Value::String("0x18160ddd".into()));
+
+        let client = RecordingClient {
+            rejected_method: Some("eth_call"),
+            ..Default::default()
+        };
+        assert!(
+            query_total_supply(&EthJsonRpcClient::new(client), H160::from_slice(&[2; 20]))
+                .await
+                .is_err()
+        );
+    }
+
+    /// Encoded mint order with the given nonce and fee payer.
+    fn encoded_order(nonce: u32, fee_payer: u8) -> Vec<u8> {
+        let mut order = vec![0; MintOrder::ENCODED_DATA_SIZE];
+        order[136..140].copy_from_slice(&nonce.to_be_bytes());
+        order[257..277].copy_from_slice(&[fee_payer; 20]);
+        order
+    }
+
+    fn mint_log(address: H160, topic: H256) -> Log {
+        Log {
+            address: address.0,
+            topics: vec![topic],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn mint_tx_cost_is_shared_between_minted_orders() {
+        let bridge = H160::from_slice(&[1; 20]);
+        let minted_topic = H256::from(MintTokenEvent::SIGNATURE_HASH.0);
+
+        let mut orders_data = encoded_order(10, 2);
+        orders_data.extend(encoded_order(11, 3));
+        orders_data.extend(encoded_order(12, 4));
+        let tx = Transaction {
+            input: batch_mint_calldata(&orders_data, &[5; 65], &[]).into(),
+            gas_price: Some(7.into()),
+            ..Default::default()
+        };
+        let receipt = TransactionReceipt {
+            gas_used: Some(300_000.into()),
+            effective_gas_price: Some(5.into()),
+            logs: vec![
+                mint_log(bridge.clone(), minted_topic),
+                mint_log(bridge.clone(), H256::from_low_u64_be(1)),
+                mint_log(H160::from_slice(&[2; 20]), minted_topic),
+                mint_log(bridge.clone(), minted_topic),
+            ],
+            ..Default::default()
+        };
+
+        let cost = MintTxCost::from_mined_tx(&tx, &receipt, &bridge).unwrap();
+        assert_eq!(cost.total, U256::from(1_500_000u64));
+        assert_eq!(cost.minted_orders, 2);
+        assert_eq!(cost.per_order(), U256::from(750_000u64));
+        assert_eq!(cost.fee_payer(11), Some(H160::from_slice(&[3; 20])));
+        assert_eq!(cost.fee_payer(13), None);
+
+        // Legacy transactions have no effective gas price in the receipt.
+        let receipt = TransactionReceipt {
+            effective_gas_price: None,
+            ..receipt
+        };
+        let cost = MintTxCost::from_mined_tx(&tx, &receipt, &bridge).unwrap();
+        assert_eq!(cost.total, U256::from(2_100_000u64));
+
+        let receipt = TransactionReceipt {
+            gas_used: None,
+            ..receipt
+        };
+        assert!(MintTxCost::from_mined_tx(&tx, &receipt, &bridge).is_err());
+    }
+}
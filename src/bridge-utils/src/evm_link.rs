@@ -2,6 +2,7 @@ mod evm_rpc_canister_client;
 
 use std::future::Future;
 use std::pin::Pin;
+use std::time::Duration;
 
 use bridge_did::evm_link::EvmLink;
 use candid::Principal;
@@ -9,12 +10,15 @@ use ethereum_json_rpc_client::http_outcall::HttpOutcallClient;
 use ethereum_json_rpc_client::{Client, EthJsonRpcClient};
 use ethers_core::types::H160;
 use ic_canister_client::IcCanisterClient;
+use ic_exports::ic_cdk::api::management_canister::http_request::TransformContext;
 use jsonrpc_core::{Request, Response};
 
 use self::evm_rpc_canister_client::EvmRpcCanisterClient;
 pub use self::evm_rpc_canister_client::{
     EthMainnetService, EthSepoliaService, L2MainnetService, RpcApi, RpcService,
 };
+use crate::http_transform;
+use crate::timeout::with_timeout;
 
 #[derive(Debug, Clone)]
 pub enum Clients {
@@ -44,16 +48,58 @@ impl Client for Clients {
     ) -> Pin<Box<dyn Future<Output = anyhow::Result<Response>> + Send>> {
         match self {
             Clients::Canister(client) => client.send_rpc_request(request),
-            Clients::HttpOutCall(client) => client.send_rpc_request(request),
+            Clients::HttpOutCall(client) => {
+                // Use the response transform specific for the request method, if any.
+                let transform = http_transform::transform_name(&request)
+                    .unwrap_or(http_transform::TRANSFORM_HTTP_RESPONSE);
+                let mut client = client.clone();
+                client.set_transform_context(Some(TransformContext::from_name(
+                    transform.to_string(),
+                    vec![],
+                )));
+                client.send_rpc_request(request)
+            }
             Clients::EvmRpcCanister(client) => client.send_rpc_request(request),
         }
     }
 }
 
+/// Client, which fails the requests not completed in the given timeout.
+#[derive(Debug, Clone)]
+pub struct TimeoutClient<C> {
+    inner: C,
+    timeout: Duration,
+}
+
+impl<C: Client> TimeoutClient<C> {
+    pub fn new(inner: C, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+}
+
+impl<C: Client> Client for TimeoutClient<C> {
+    fn send_rpc_request(
+        &self,
+        request: Request,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<Response>> + Send>> {
+        let response = self.inner.send_rpc_request(request);
+        let timeout = self.timeout;
+        Box::pin(async move { with_timeout(response, timeout).await? })
+    }
+}
+
 pub trait EvmLinkClient {
     /// Returns the JSON-RPC client.
     fn get_json_rpc_client(&self) -> EthJsonRpcClient<impl Client>;
 
+    /// Returns the JSON-RPC client, which fails the requests not completed in `timeout`.
+    fn get_json_rpc_client_with_timeout(
+        &self,
+        timeout: Duration,
+    ) -> EthJsonRpcClient<TimeoutClient<impl Client>> {
+        EthJsonRpcClient::new(TimeoutClient::new(self.get_client(), timeout))
+    }
+
     /// Returns the underlying client.
     fn get_client(&self) -> impl Client;
 }
@@ -98,3 +144,43 @@ pub fn address_to_icrc_subaccount(address: &H160) -> [u8; 32] {
     subaccount[..20].copy_from_slice(address.as_bytes());
     subaccount
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use jsonrpc_core::{Id, MethodCall, Params, Version};
+
+    use super::*;
+
+    /// Client, which never responds.
+    #[derive(Clone)]
+    struct HungClient;
+
+    impl Client for HungClient {
+        fn send_rpc_request(
+            &self,
+            _request: Request,
+        ) -> Pin<Box<dyn Future<Output = anyhow::Result<Response>> + Send>> {
+            Box::pin(std::future::pending())
+        }
+    }
+
+    #[tokio::test]
+    async fn hung_request_fails_after_timeout() {
+        let timeout = Duration::from_millis(50);
+        let client = TimeoutClient::new(HungClient, timeout);
+        let request = Request::Single(jsonrpc_core::Call::MethodCall(MethodCall {
+            jsonrpc: Some(Version::V2),
+            method: "eth_blockNumber".into(),
+            params: Params::Array(vec![]),
+            id: Id::Num(1),
+        }));
+        let started = Instant::now();
+
+        let err = client.send_rpc_request(request).await.unwrap_err();
+
+        assert!(err.to_string().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
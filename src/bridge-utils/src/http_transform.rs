@@ -0,0 +1,453 @@
+//! Transform functions of the EVM JSON-RPC HTTP outcalls.
+//!
+//! All the replicas must get the same response of an HTTP outcall to reach consensus,
+//! but the responses may differ in headers or in provider-specific fields. The transforms
+//! keep only the JSON-RPC fields of the response, which are the same for all the replicas,
+//! and remove the volatile headers, like the date or the request id.
+
+use bridge_did::http_transform::HttpTransformSettings;
+use ic_exports::ic_cdk::api::management_canister::http_request::{
+    HttpHeader, HttpResponse, TransformArgs,
+};
+use jsonrpc_core::{Call, Request};
+use serde_json::{Map, Value};
+
+/// Name of the canister query, which transforms the responses without a method-specific
+/// transform.
+pub const TRANSFORM_HTTP_RESPONSE: &str = "transform";
+/// Name of the canister query, which transforms `eth_getLogs` responses.
+pub const TRANSFORM_ETH_GET_LOGS: &str = "transform_eth_get_logs";
+/// Name of the canister query, which transforms `eth_blockNumber` responses.
+pub const TRANSFORM_ETH_BLOCK_NUMBER: &str = "transform_eth_block_number";
+/// Name of the canister query, which transforms `eth_chainId` responses.
+pub const TRANSFORM_ETH_CHAIN_ID: &str = "transform_eth_chain_id";
+/// Name of the canister query, which transforms the outbox webhook responses.
+pub const TRANSFORM_WEBHOOK_RESPONSE: &str = "transform_webhook_response";
+
+/// Fields of the log object, defined by the JSON-RPC specification.
+const LOG_FIELDS: &[&str] = &[
+    "address",
+    "topics",
+    "data",
+    "blockNumber",
+    "blockHash",
+    "transactionHash",
+    "transactionIndex",
+    "logIndex",
+    "removed",
+];
+
+/// Returns the name of the transform function for the request, if the request method
+/// has a specific one. Batch requests use the generic transform.
+pub fn transform_name(request: &Request) -> Option<&'static str> {
+    let Request::Single(Call::MethodCall(call)) = request else {
+        return None;
+    };
+
+    match call.method.as_str() {
+        "eth_getLogs" => Some(TRANSFORM_ETH_GET_LOGS),
+        "eth_blockNumber" => Some(TRANSFORM_ETH_BLOCK_NUMBER),
+        "eth_chainId" => Some(TRANSFORM_ETH_CHAIN_ID),
+        _ => None,
+    }
+}
+
+/// Transforms a generic HTTP response, removing the filtered headers and, if enabled,
+/// canonicalizing the body of the response with a JSON content type.
+pub fn transform(args: TransformArgs, settings: &HttpTransformSettings) -> HttpResponse {
+    let response = args.response;
+    let body = match settings.canonicalize_body && has_json_content_type(&response.headers) {
+        true => canonicalize_body(response.body),
+        false => response.body,
+    };
+
+    HttpResponse {
+        status: response.status,
+        headers: filter_headers(response.headers, settings),
+        body,
+    }
+}
+
+/// Transforms `eth_getLogs` response, keeping only the specified fields of every log.
+pub fn transform_eth_get_logs(
+    args: TransformArgs,
+    settings: &HttpTransformSettings,
+) -> HttpResponse {
+    transform_response(args.response, settings, |result| match result {
+        Value::Array(logs) => Value::Array(logs.into_iter().map(retain_log_fields).collect()),
+        result => result,
+    })
+}
+
+/// Transforms `eth_blockNumber` response, keeping only the block number.
+pub fn transform_eth_block_number(
+    args: TransformArgs,
+    settings: &HttpTransformSettings,
+) -> HttpResponse {
+    transform_response(args.response, settings, |result| result)
+}
+
+/// Transforms `eth_chainId` response, keeping only the chain id.
+pub fn transform_eth_chain_id(
+    args: TransformArgs,
+    settings: &HttpTransformSettings,
+) -> HttpResponse {
+    transform_response(args.response, settings, |result| result)
+}
+
+/// Transforms a webhook response, keeping only the status. The bridge checks only
+/// the status of the webhook delivery, and the headers or the body may differ between
+/// the replicas.
+pub fn transform_webhook_response(args: TransformArgs) -> HttpResponse {
+    HttpResponse {
+        status: args.response.status,
+        headers: vec![],
+        body: vec![],
+    }
+}
+
+/// Removes the filtered headers and the non JSON-RPC fields of the response,
+/// and transforms the `result` field with the given function.
+fn transform_response(
+    response: HttpResponse,
+    settings: &HttpTransformSettings,
+    transform_result: fn(Value) -> Value,
+) -> HttpResponse {
+    HttpResponse {
+        status: response.status,
+        headers: filter_headers(response.headers, settings),
+        body: transform_body(&response.body, transform_result),
+    }
+}
+
+fn filter_headers(headers: Vec<HttpHeader>, settings: &HttpTransformSettings) -> Vec<HttpHeader> {
+    headers
+        .into_iter()
+        .filter(|header| settings.headers.retains(&header.name))
+        .collect()
+}
+
+/// Checks if the `Content-Type` header is a JSON media type, e.g. `application/json`
+/// or `application/problem+json`.
+fn has_json_content_type(headers: &[HttpHeader]) -> bool {
+    headers
+        .iter()
+        .filter(|header| header.name.eq_ignore_ascii_case("content-type"))
+        .any(|header| {
+            let media_type = header.value.split(';').next().unwrap_or_default().trim();
+            let subtype = media_type.rsplit(['/', '+']).next().unwrap_or_default();
+            subtype.eq_ignore_ascii_case("json")
+        })
+}
+
+/// Re-encodes a JSON body with the sorted object keys and without whitespaces.
+/// Invalid JSON body is returned as is.
+fn canonicalize_body(body: Vec<u8>) -> Vec<u8> {
+    match serde_json::from_slice::<Value>(&body) {
+        Ok(value) => serde_json::to_vec(&value).unwrap_or(body),
+        Err(_) => body,
+    }
+}
+
+fn transform_body(body: &[u8], transform_result: fn(Value) -> Value) -> Vec<u8> {
+    let Ok(Value::Object(mut response)) = serde_json::from_slice(body) else {
+        return body.to_vec();
+    };
+
+    let mut transformed = Map::new();
+    for field in ["jsonrpc", "id", "error"] {
+        if let Some(value) = response.remove(field) {
+            transformed.insert(field.to_string(), value);
+        }
+    }
+    if let Some(result) = response.remove("result") {
+        transformed.insert("result".to_string(), transform_result(result));
+    }
+
+    serde_json::to_vec(&Value::Object(transformed)).unwrap_or_else(|_| body.to_vec())
+}
+
+fn retain_log_fields(log: Value) -> Value {
+    match log {
+        Value::Object(mut log) => {
+            log.retain(|field, _| LOG_FIELDS.contains(&field.as_str()));
+            Value::Object(log)
+        }
+        log => log,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::http_transform::HeaderFilter;
+    use candid::Nat;
+    use jsonrpc_core::{Id, MethodCall, Params, Version};
+
+    use super::*;
+
+    fn transform_args(body: &str) -> TransformArgs {
+        TransformArgs {
+            response: HttpResponse {
+                status: Nat::from(200u32),
+                headers: vec![HttpHeader {
+                    name: "Date".into(),
+                    value: "Mon, 01 Jan 2024 00:00:00 GMT".into(),
+                }],
+                body: body.as_bytes().to_vec(),
+            },
+            context: vec![],
+        }
+    }
+
+    fn body_json(response: &HttpResponse) -> Value {
+        serde_json::from_slice(&response.body).unwrap()
+    }
+
+    fn request(method: &str) -> Request {
+        Request::Single(Call::MethodCall(MethodCall {
+            jsonrpc: Some(Version::V2),
+            method: method.into(),
+            params: Params::Array(vec![]),
+            id: Id::Num(1),
+        }))
+    }
+
+    #[test]
+    fn should_transform_get_logs_response() {
+        let response = transform_eth_get_logs(
+            transform_args(
+                r#"{
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": [{
+                        "address": "0x0000000000000000000000000000000000000001",
+                        "topics": ["0x01"],
+                        "data": "0x",
+                        "blockNumber": "0x10",
+                        "blockHash": "0x02",
+                        "blockTimestamp": "0x65920080",
+                        "transactionHash": "0x03",
+                        "transactionIndex": "0x0",
+                        "logIndex": "0x1",
+                        "removed": false
+                    }],
+                    "served_by": "node-7"
+                }"#,
+            ),
+            &HttpTransformSettings::default(),
+        );
+
+        assert!(response.headers.is_empty());
+        assert_eq!(response.status, Nat::from(200u32));
+        assert_eq!(
+            body_json(&response),
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": [{
+                    "address": "0x0000000000000000000000000000000000000001",
+                    "topics": ["0x01"],
+                    "data": "0x",
+                    "blockNumber": "0x10",
+                    "blockHash": "0x02",
+                    "transactionHash": "0x03",
+                    "transactionIndex": "0x0",
+                    "logIndex": "0x1",
+                    "removed": false
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn should_transform_block_number_response() {
+        let response = transform_eth_block_number(
+            transform_args(r#"{"jsonrpc": "2.0", "id": 2, "result": "0x1b4", "latency_ms": 12}"#),
+            &HttpTransformSettings::default(),
+        );
+
+        assert!(response.headers.is_empty());
+        assert_eq!(
+            body_json(&response),
+            serde_json::json!({"jsonrpc": "2.0", "id": 2, "result": "0x1b4"})
+        );
+    }
+
+    #[test]
+    fn should_transform_chain_id_response() {
+        let response = transform_eth_chain_id(
+            transform_args(r#"{"jsonrpc": "2.0", "id": 3, "result": "0x56b29", "node": "eu-1"}"#),
+            &HttpTransformSettings::default(),
+        );
+
+        assert!(response.headers.is_empty());
+        assert_eq!(
+            body_json(&response),
+            serde_json::json!({"jsonrpc": "2.0", "id": 3, "result": "0x56b29"})
+        );
+    }
+
+    #[test]
+    fn should_keep_error_response() {
+        let response = transform_eth_chain_id(
+            transform_args(
+                r#"{"jsonrpc": "2.0", "id": 4, "error": {"code": -32000, "message": "busy"}}"#,
+            ),
+            &HttpTransformSettings::default(),
+        );
+
+        assert_eq!(
+            body_json(&response),
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 4,
+                "error": {"code": -32000, "message": "busy"}
+            })
+        );
+    }
+
+    #[test]
+    fn should_keep_non_json_body() {
+        let response = transform_eth_block_number(
+            transform_args("Bad Gateway"),
+            &HttpTransformSettings::default(),
+        );
+        assert_eq!(response.body, b"Bad Gateway");
+    }
+
+    fn header(name: &str, value: &str) -> HttpHeader {
+        HttpHeader {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    fn header_names(response: &HttpResponse) -> Vec<&str> {
+        response.headers.iter().map(|h| h.name.as_str()).collect()
+    }
+
+    #[test]
+    fn should_remove_volatile_headers() {
+        let mut args = transform_args(r#"{"jsonrpc": "2.0", "id": 5, "result": "0x1"}"#);
+        args.response.headers.extend([
+            header("X-Request-Id", "4f1c2a"),
+            header("CF-RAY", "8a1b2c3d4e5f-AMS"),
+            header("Set-Cookie", "session=1"),
+            header("Content-Type", "application/json"),
+        ]);
+
+        let response = transform_eth_block_number(args.clone(), &HttpTransformSettings::default());
+        assert_eq!(header_names(&response), vec!["Content-Type"]);
+
+        let settings = HttpTransformSettings {
+            headers: HeaderFilter::Deny(vec!["content-type".into(), "date".into()]),
+            canonicalize_body: false,
+        };
+        let response = transform(args.clone(), &settings);
+        assert_eq!(
+            header_names(&response),
+            vec!["X-Request-Id", "CF-RAY", "Set-Cookie"]
+        );
+
+        let settings = HttpTransformSettings {
+            headers: HeaderFilter::Allow(vec!["set-cookie".into()]),
+            canonicalize_body: false,
+        };
+        let response = transform(args, &settings);
+        assert_eq!(header_names(&response), vec!["Set-Cookie"]);
+    }
+
+    fn json_transform_args(body: &str, content_type: &str) -> TransformArgs {
+        let mut args = transform_args(body);
+        args.response
+            .headers
+            .push(header("Content-Type", content_type));
+        args
+    }
+
+    #[test]
+    fn should_canonicalize_equivalent_json_bodies_identically() {
+        let compact = r#"{"jsonrpc":"2.0","id":1,"result":{"number":"0x10","hash":"0x01"}}"#;
+        let formatted = r#"{
+            "result": { "hash": "0x01",   "number": "0x10" },
+            "id": 1,
+            "jsonrpc": "2.0"
+        }"#;
+        let settings = HttpTransformSettings::default();
+
+        let first = transform(json_transform_args(compact, "application/json"), &settings);
+        let second = transform(
+            json_transform_args(formatted, "application/json; charset=utf-8"),
+            &settings,
+        );
+
+        assert_eq!(first.body, second.body);
+        assert_eq!(
+            first.body,
+            br#"{"id":1,"jsonrpc":"2.0","result":{"hash":"0x01","number":"0x10"}}"#
+        );
+    }
+
+    #[test]
+    fn should_canonicalize_only_json_content_type() {
+        let body = r#"{ "b": 1,  "a": {"d": 2, "c": 3} }"#;
+        let canonical = br#"{"a":{"c":3,"d":2},"b":1}"#;
+        let settings = HttpTransformSettings::default();
+
+        for content_type in [
+            "application/json",
+            "application/problem+json",
+            "Application/JSON",
+        ] {
+            let response = transform(json_transform_args(body, content_type), &settings);
+            assert_eq!(response.body, canonical);
+        }
+
+        // Body without a JSON content type is not changed.
+        let response = transform(transform_args(body), &settings);
+        assert_eq!(response.body, body.as_bytes());
+        let response = transform(json_transform_args(body, "text/plain"), &settings);
+        assert_eq!(response.body, body.as_bytes());
+
+        // Invalid JSON is not changed.
+        let response = transform(
+            json_transform_args("Bad Gateway", "application/json"),
+            &settings,
+        );
+        assert_eq!(response.body, b"Bad Gateway");
+
+        let settings = HttpTransformSettings {
+            canonicalize_body: false,
+            ..Default::default()
+        };
+        let response = transform(json_transform_args(body, "application/json"), &settings);
+        assert_eq!(response.body, body.as_bytes());
+    }
+
+    #[test]
+    fn should_select_transform_by_method() {
+        assert_eq!(
+            transform_name(&request("eth_getLogs")),
+            Some(TRANSFORM_ETH_GET_LOGS)
+        );
+        assert_eq!(
+            transform_name(&request("eth_blockNumber")),
+            Some(TRANSFORM_ETH_BLOCK_NUMBER)
+        );
+        assert_eq!(
+            transform_name(&request("eth_chainId")),
+            Some(TRANSFORM_ETH_CHAIN_ID)
+        );
+        assert_eq!(transform_name(&request("eth_gasPrice")), None);
+        assert_eq!(transform_name(&Request::Batch(vec![])), None);
+    }
+
+    #[test]
+    fn should_keep_only_status_of_webhook_response() {
+        let response = transform_webhook_response(transform_args(r#"{"request_id": "a1b2"}"#));
+
+        assert_eq!(response.status, Nat::from(200u32));
+        assert!(response.headers.is_empty());
+        assert!(response.body.is_empty());
+    }
+}
@@ -1,9 +1,12 @@
 use alloy_sol_types::sol;
 pub mod btf_events;
 pub mod common;
+pub mod event_payload;
 pub mod evm_bridge;
 pub mod evm_link;
+pub mod http_transform;
 pub mod query;
+pub mod timeout;
 
 #[cfg(feature = "native")]
 pub mod native;
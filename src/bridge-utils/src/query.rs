@@ -13,6 +13,7 @@ pub const CHAINID_ID: &str = "chainID";
 pub const GAS_PRICE_ID: &str = "gasPrice";
 pub const LATEST_BLOCK_ID: &str = "latestBlock";
 pub const NONCE_ID: &str = "nonce";
+pub const BALANCE_ID: &str = "balance";
 
 /// Represents different types of queries that can be made to an EVM node
 pub enum QueryType {
@@ -20,6 +21,7 @@ pub enum QueryType {
     Nonce { address: H160 },
     LatestBlock,
     ChainID,
+    Balance { address: H160 },
 }
 
 impl QueryType {
@@ -36,6 +38,14 @@ impl QueryType {
             ),
             QueryType::LatestBlock => ("eth_blockNumber", vec![], LATEST_BLOCK_ID),
             QueryType::ChainID => ("eth_chainId", vec![], CHAINID_ID),
+            QueryType::Balance { address } => (
+                "eth_getBalance",
+                vec![
+                    serde_json::to_value(address).expect("should be able to convert"),
+                    serde_json::to_value(BlockNumber::Latest).expect("should be able to convert"),
+                ],
+                BALANCE_ID,
+            ),
         };
 
         Call::MethodCall(MethodCall {
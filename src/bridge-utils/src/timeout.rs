@@ -0,0 +1,132 @@
+//! Timeout of the outgoing requests, so a hung endpoint fails fast instead of blocking
+//! the caller until the system timeout of the call.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+/// Error returned if the future is not completed before the timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("request timed out after {0:?}")]
+pub struct Elapsed(pub Duration);
+
+/// Completes the `future`, or returns [`Elapsed`] error if it is not completed in `timeout`.
+pub fn with_timeout<F: Future>(future: F, timeout: Duration) -> Timeout<F> {
+    Timeout {
+        future: Box::pin(future),
+        delay: Delay::new(timeout),
+    }
+}
+
+/// Future returned by [`with_timeout`].
+pub struct Timeout<F: Future> {
+    future: Pin<Box<F>>,
+    delay: Delay,
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(output) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+
+        let duration = self.delay.duration;
+        Pin::new(&mut self.delay)
+            .poll(cx)
+            .map(|_| Err(Elapsed(duration)))
+    }
+}
+
+#[derive(Default)]
+struct DelayState {
+    elapsed: bool,
+    waker: Option<Waker>,
+}
+
+/// Future completed after the given duration. The timer is started on the first poll.
+struct Delay {
+    duration: Duration,
+    state: Option<Arc<Mutex<DelayState>>>,
+}
+
+impl Delay {
+    fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            state: None,
+        }
+    }
+
+    fn fire(state: &Mutex<DelayState>) {
+        let mut state = state.lock().expect("delay state poisoned");
+        state.elapsed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn start_timer(duration: Duration, state: Arc<Mutex<DelayState>>) {
+        ic_exports::ic_cdk_timers::set_timer(duration, move || Self::fire(&state));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_timer(duration: Duration, state: Arc<Mutex<DelayState>>) {
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            Self::fire(&state);
+        });
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let state = match &self.state {
+            Some(state) => state.clone(),
+            None => {
+                let state = Arc::new(Mutex::new(DelayState::default()));
+                Self::start_timer(self.duration, state.clone());
+                self.state = Some(state.clone());
+                state
+            }
+        };
+
+        let mut state = state.lock().expect("delay state poisoned");
+        if state.elapsed {
+            return Poll::Ready(());
+        }
+
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn completed_future_output_is_returned() {
+        let result = with_timeout(async { 42 }, Duration::from_secs(10)).await;
+        assert_eq!(result, Ok(42));
+    }
+
+    #[tokio::test]
+    async fn hung_future_is_timed_out() {
+        let timeout = Duration::from_millis(50);
+        let started = Instant::now();
+
+        let result = with_timeout(std::future::pending::<()>(), timeout).await;
+
+        assert_eq!(result, Err(Elapsed(timeout)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -3,6 +3,7 @@ mod mint_order_handler;
 mod mint_tx_handler;
 
 use std::cell::RefCell;
+use std::mem;
 
 use bridge_canister::bridge::{Operation, OperationContext, OperationProgress};
 use bridge_canister::runtime::service::ServiceId;
@@ -11,10 +12,12 @@ use bridge_did::error::{BTFResult, Error};
 use bridge_did::event_data::*;
 use bridge_did::id256::Id256;
 use bridge_did::op_id::OperationId;
-use bridge_did::operations::BtcBridgeOp;
+use bridge_did::operation_log::OperationLog;
+use bridge_did::operations::{BtcBridgeOp, BtcBridgeOpV1};
 use bridge_did::order::{MintOrder, SignedOrders};
+use bridge_did::versioned::Versioned;
 use candid::{CandidType, Principal};
-use did::H160;
+use did::{H160, H256};
 use ic_canister::virtual_canister_call;
 use ic_exports::ic_kit::ic;
 use ic_exports::icrc_types::icrc1::account::Account as IcrcAccount;
@@ -39,6 +42,10 @@ pub const REFRESH_PARAMS_SERVICE_ID: ServiceId = 0;
 pub const FETCH_BTF_EVENTS_SERVICE_ID: ServiceId = 1;
 pub const SIGN_MINT_ORDER_SERVICE_ID: ServiceId = 2;
 pub const SEND_MINT_TX_SERVICE_ID: ServiceId = 3;
+pub const MONITOR_GAS_BALANCE_SERVICE_ID: ServiceId = 4;
+pub const PING_EVM_SERVICE_ID: ServiceId = 5;
+pub const DISPATCH_OUTBOX_SERVICE_ID: ServiceId = 6;
+pub const MIGRATE_RECORDS_SERVICE_ID: ServiceId = 7;
 
 #[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
 pub struct BtcBridgeOpImpl(pub BtcBridgeOp);
@@ -128,6 +135,26 @@ impl Operation for BtcBridgeOpImpl {
         Ok(OperationProgress::Progress(next_step?))
     }
 
+    /// Operations stored before the chain ids of the mint orders were extended to 64 bits are
+    /// decoded with the legacy shape. An operation, which sends or confirms the order signed in
+    /// the legacy layout, is moved back to signing of the same order.
+    fn decode_legacy_log(bytes: &[u8]) -> Option<OperationLog<Self>> {
+        let log = OperationLog::<BtcBridgeOpV1>::from_versioned_bytes(bytes).ok()?;
+        let mut log = log.map_payload(|op, _| Self(op.into()));
+
+        let legacy_order = match &log.current_step().0 {
+            BtcBridgeOp::MintErc20 { order } | BtcBridgeOp::ConfirmErc20Mint { order, .. } => {
+                order.legacy_order()
+            }
+            _ => None,
+        };
+        if let Some(order) = legacy_order {
+            log.add_step(Ok(Self(BtcBridgeOp::SignMintOrder { order })));
+        }
+
+        Some(log)
+    }
+
     fn is_complete(&self) -> bool {
         match self.0 {
             BtcBridgeOp::UpdateCkBtcBalance { .. } => false,
@@ -183,6 +210,53 @@ impl Operation for BtcBridgeOpImpl {
             | BtcBridgeOp::Erc20MintConfirmed(_) => None,
         }
     }
+
+    fn evm_tx_hash(&self) -> Option<H256> {
+        match &self.0 {
+            BtcBridgeOp::ConfirmErc20Mint { tx_id, .. } => Some(tx_id.clone()),
+            _ => None,
+        }
+    }
+
+    /// Operation can stay in its state with updated data, or move along its deposit
+    /// or withdrawal flow.
+    fn can_transition(from: &Self, to: &Self) -> bool {
+        if mem::discriminant(&from.0) == mem::discriminant(&to.0) {
+            return true;
+        }
+
+        matches!(
+            (&from.0, &to.0),
+            // Deposit flow.
+            (
+                BtcBridgeOp::UpdateCkBtcBalance { .. },
+                BtcBridgeOp::CollectCkBtcBalance { .. }
+            ) | (
+                BtcBridgeOp::CollectCkBtcBalance { .. },
+                BtcBridgeOp::TransferCkBtc { .. }
+            ) | (
+                BtcBridgeOp::TransferCkBtc { .. },
+                BtcBridgeOp::CreateMintOrder { .. }
+            ) | (
+                BtcBridgeOp::CreateMintOrder { .. },
+                BtcBridgeOp::SignMintOrder { .. }
+            ) | (
+                BtcBridgeOp::SignMintOrder { .. },
+                BtcBridgeOp::MintErc20 { .. }
+            ) | (
+                BtcBridgeOp::MintErc20 { .. },
+                BtcBridgeOp::ConfirmErc20Mint { .. } | BtcBridgeOp::Erc20MintConfirmed(_)
+            ) | (
+                BtcBridgeOp::ConfirmErc20Mint { .. },
+                BtcBridgeOp::Erc20MintConfirmed(_)
+            )
+            // Withdrawal flow.
+            | (
+                BtcBridgeOp::WithdrawBtc(_),
+                BtcBridgeOp::BtcWithdrawConfirmed { .. }
+            )
+        )
+    }
 }
 
 impl BtcBridgeOpImpl {
@@ -378,7 +452,7 @@ impl BtcBridgeOpImpl {
             recipient: eth_address,
             dst_token: state_ref.token_address().clone(),
             nonce,
-            sender_chain_id,
+            sender_chain_id: sender_chain_id.into(),
             recipient_chain_id,
             name: state_ref.token_name(),
             symbol: state_ref.token_symbol(),
@@ -386,6 +460,7 @@ impl BtcBridgeOpImpl {
             approve_spender: Default::default(),
             approve_amount: Default::default(),
             fee_payer: H160::zero(),
+            permit: None,
         };
 
         Ok(mint_order)
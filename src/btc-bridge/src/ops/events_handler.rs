@@ -3,7 +3,7 @@ use bridge_canister::runtime::service::fetch_logs::BtfBridgeEventHandler;
 use bridge_did::event_data::{BurntEventData, MintedEventData, NotifyMinterEventData};
 use bridge_did::operations::BtcBridgeOp;
 use bridge_did::reason::BtcDeposit;
-use candid::Decode;
+use bridge_utils::event_payload::decode_candid_payload;
 
 use super::BtcBridgeOpImpl;
 
@@ -36,11 +36,14 @@ impl BtfBridgeEventHandler<BtcBridgeOpImpl> for BtcEventsHandler {
     ) -> Option<OperationAction<BtcBridgeOpImpl>> {
         log::debug!("on_minter_notification {event:?}");
 
-        let mut btc_deposit = match Decode!(&event.user_data, BtcDeposit) {
-            Ok(icrc_burn) => icrc_burn,
+        let mut btc_deposit = match decode_candid_payload::<BtcDeposit>(&event.user_data) {
+            Ok(btc_deposit) => btc_deposit,
             Err(e) => {
                 log::warn!("failed to decode Btfbridge notification into BtcDeposit: {e}");
-                return None;
+                return Some(OperationAction::reject(
+                    event,
+                    format!("failed to decode BtcDeposit: {e}"),
+                ));
             }
         };
 
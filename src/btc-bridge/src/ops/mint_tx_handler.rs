@@ -45,7 +45,7 @@ impl MintTxHandler for BtcMintTxHandler {
         Some(order)
     }
 
-    fn mint_tx_sent(&self, id: OperationId, tx_hash: H256) {
+    fn mint_tx_sent(&self, id: OperationId, tx_hash: H256, evm_params_snapshot: Option<u64>) {
         let op = self.state.borrow().operations.get(id);
         let Some(BtcBridgeOp::MintErc20 { order, .. }) = op.map(|op| op.0) else {
             log::info!(
@@ -54,12 +54,13 @@ impl MintTxHandler for BtcMintTxHandler {
             return;
         };
 
-        self.state.borrow_mut().operations.update(
+        self.state.borrow_mut().operations.update_with_evm_params(
             id,
             BtcBridgeOpImpl(BtcBridgeOp::ConfirmErc20Mint {
                 order,
                 tx_id: tx_hash,
             }),
+            evm_params_snapshot,
         )
     }
 }
@@ -1,9 +1,22 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use bridge_canister::memory::{memory_by_id, StableMemory};
+use bridge_canister::memory::{memory_by_id, StableMemory, PENDING_MINT_BATCHES_MEMORY_ID};
+use bridge_canister::operator_audit::MAX_AUDIT_ENTRIES_PER_REQUEST;
+use bridge_canister::pending_mint_batches::PendingMintBatchStore;
+use bridge_canister::runtime::bulk_action;
 use bridge_canister::runtime::service::fetch_logs::FetchBtfBridgeEventsService;
-use bridge_canister::runtime::service::mint_tx::SendMintTxService;
+use bridge_canister::runtime::service::gas_balance::{
+    MonitorGasBalanceService, GAS_BALANCE_QUERY_DELAY,
+};
+use bridge_canister::runtime::service::migrate_records::{
+    MigrateRecordsService, MIGRATE_RECORDS_DELAY,
+};
+use bridge_canister::runtime::service::mint_tx::{
+    mint_tx_request, SendMintTxService, DEFAULT_MAX_PENDING_BATCH_AGE,
+};
+use bridge_canister::runtime::service::outbox::{DispatchOutboxService, OUTBOX_DISPATCH_DELAY};
+use bridge_canister::runtime::service::ping_evm::{PingEvmService, PING_EVM_DELAY};
 use bridge_canister::runtime::service::sign_orders::SignMintOrdersService;
 use bridge_canister::runtime::service::timer::ServiceTimer;
 use bridge_canister::runtime::service::update_evm_params::RefreshEvmParamsService;
@@ -12,28 +25,49 @@ use bridge_canister::runtime::state::config::ConfigStorage;
 use bridge_canister::runtime::state::SharedConfig;
 use bridge_canister::runtime::{BridgeRuntime, RuntimeState};
 use bridge_canister::BridgeCanister;
+use bridge_did::bridge_mode::{OperationBlocksExport, ReplicaSyncStatus};
 use bridge_did::bridge_side::BridgeSide;
+use bridge_did::bulk_action::{BulkAction, BulkActionResult, OperationFilter, OperatorAuditEntry};
+use bridge_did::concurrency::DeferredOperationsStats;
+use bridge_did::data_erasure::{UserDataErasure, UserIdentity};
+use bridge_did::dead_letter::DeadLetterEvent;
 use bridge_did::error::{BTFResult, Error};
+use bridge_did::evm_latency::LatencyStats;
+use bridge_did::evm_link::PrivateTxRelay;
+use bridge_did::evm_params_history::EvmParamsSnapshot;
+use bridge_did::evm_tx::EvmTxRequest;
+use bridge_did::fee_adjustment::FeePayerAdjustments;
+use bridge_did::gas_balance::{GasBalanceStatus, GasBalanceThresholds};
+use bridge_did::icrc3::GetBlocksResult;
 use bridge_did::init::erc20::BaseEvmSettings;
 use bridge_did::init::BridgeInitData;
-use bridge_did::op_id::OperationId;
+use bridge_did::nonce_drift::{NonceDriftStatus, NonceGap};
+use bridge_did::op_id::{NonceState, OperationId};
 use bridge_did::operation_log::{Memo, OperationLog};
+use bridge_did::operation_replay::ProgressReplayEntry;
+use bridge_did::operation_status::OperationStatusView;
+use bridge_did::outbox::OutboxMessage;
+use bridge_did::pending_mint_batch::PendingMintBatchesStats;
+use bridge_did::processed_event::{ProcessedEventInfo, RejectedEventsStats};
+use bridge_did::task_payload::TaskPayloadStats;
 use bridge_utils::common::Pagination;
-use candid::Principal;
+use candid::{Nat, Principal};
 use did::build::BuildData;
 use did::H160;
-use eth_signer::sign_strategy::TransactionSigner;
 use ic_canister::{generate_idl, init, post_upgrade, query, update, Canister, Idl, PreUpdate};
+use ic_exports::ic_kit::ic;
 use ic_log::canister::{LogCanister, LogState};
 use ic_metrics::{Metrics, MetricsStorage};
-use ic_stable_structures::StableCell;
+use ic_stable_structures::{CellStructure, StableCell};
 use ic_storage::IcStorage;
 
-use crate::memory::NONCE_COUNTER_MEMORY_ID;
+use crate::memory::{BASE_PENDING_MINT_BATCHES_MEMORY_ID, NONCE_COUNTER_MEMORY_ID};
 use crate::ops::events_handler::Erc20EventsHandler;
 use crate::ops::{
-    Erc20BridgeOpImpl, Erc20OrderHandler, Erc20ServiceSelector, FETCH_BASE_LOGS_SERVICE_ID,
-    FETCH_WRAPPED_LOGS_SERVICE_ID, REFRESH_BASE_PARAMS_SERVICE_ID,
+    Erc20BridgeOpImpl, Erc20OrderHandler, Erc20ServiceSelector, DISPATCH_OUTBOX_SERVICE_ID,
+    FETCH_BASE_LOGS_SERVICE_ID, FETCH_WRAPPED_LOGS_SERVICE_ID, MIGRATE_RECORDS_SERVICE_ID,
+    MONITOR_BASE_GAS_BALANCE_SERVICE_ID, MONITOR_WRAPPED_GAS_BALANCE_SERVICE_ID,
+    PING_BASE_EVM_SERVICE_ID, PING_WRAPPED_EVM_SERVICE_ID, REFRESH_BASE_PARAMS_SERVICE_ID,
     REFRESH_WRAPPED_PARAMS_SERVICE_ID, SEND_MINT_TX_SERVICE_ID, SIGN_MINT_ORDER_SERVICE_ID,
 };
 use crate::state::SharedBaseEvmState;
@@ -61,7 +95,11 @@ impl Erc20Bridge {
     #[init]
     pub fn init(&mut self, bridge_settings: BridgeInitData, base_evm_settings: BaseEvmSettings) {
         get_base_evm_state().0.borrow_mut().reset(base_evm_settings);
+        let nonce_offset =
+            mint_order_nonce_offset(bridge_settings.nonce_offset.unwrap_or_default())
+                .expect("invalid nonce offset");
         self.init_bridge(bridge_settings, Self::run_scheduler);
+        apply_mint_order_nonce_offset(nonce_offset);
     }
 
     #[post_upgrade]
@@ -85,12 +123,139 @@ impl Erc20Bridge {
         log::info!("Bridge canister base EVM BTF bridge contract address changed to {address}");
     }
 
+    /// Returns balance monitoring status of the bridge canister base EVM address.
+    #[query]
+    pub fn get_base_gas_balance_status(&self) -> GasBalanceStatus {
+        get_base_evm_config().borrow().get_gas_balance_status()
+    }
+
+    /// Sets balance thresholds of the bridge canister base EVM address.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_base_gas_balance_thresholds(&mut self, thresholds: Option<GasBalanceThresholds>) {
+        bridge_canister::inspect::inspect_set_gas_balance_thresholds(self.config());
+        bridge_canister::inspect::inspect_gas_balance_thresholds_are_valid(thresholds.as_ref());
+        get_base_evm_config()
+            .borrow_mut()
+            .set_gas_balance_thresholds(thresholds.clone());
+
+        log::info!("Bridge canister base EVM gas balance thresholds changed to {thresholds:?}");
+    }
+
+    /// Returns snapshots of the base EVM params, newest first.
+    /// If `pagination` is `None`, all the stored snapshots are returned.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_base_evm_params_history(
+        &self,
+        pagination: Option<Pagination>,
+    ) -> Vec<EvmParamsSnapshot> {
+        bridge_canister::inspect::inspect_get_evm_params_history(self.config());
+        let config = get_base_evm_config();
+        let config = config.borrow();
+        let pagination = pagination
+            .unwrap_or_else(|| Pagination::new(0, config.get_evm_params_history_size() as _));
+        config.get_evm_params_history(pagination.offset, pagination.count)
+    }
+
+    /// Sets max number of the base EVM params snapshots to keep.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_base_evm_params_history_size(&mut self, size: u32) {
+        bridge_canister::inspect::inspect_set_evm_params_history_size(self.config());
+        get_base_evm_config()
+            .borrow_mut()
+            .set_evm_params_history_size(size);
+
+        log::info!("Bridge canister base EVM params history size changed to {size}");
+    }
+
+    /// Returns percentiles of the base EVM node round-trip time.
+    #[query]
+    pub fn get_base_evm_latency_stats(&self) -> LatencyStats {
+        get_base_evm_config().borrow().get_evm_latency_stats()
+    }
+
+    /// Returns the number and age of the signed mint orders batches, which wait to be sent
+    /// to the base EVM by the bridge.
+    #[query]
+    pub fn get_base_pending_mint_batches_stats(&self) -> PendingMintBatchesStats {
+        PendingMintBatchStore::with_memory(memory_by_id(BASE_PENDING_MINT_BATCHES_MEMORY_ID))
+            .stats(ic::time(), DEFAULT_MAX_PENDING_BATCH_AGE)
+    }
+
+    /// Sets the latency of the base EVM node, above which a warning is logged.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_base_max_acceptable_evm_latency_ms(&mut self, max_latency_ms: Option<u64>) {
+        bridge_canister::inspect::inspect_set_max_acceptable_evm_latency_ms(self.config());
+        get_base_evm_config()
+            .borrow_mut()
+            .set_max_acceptable_evm_latency_ms(max_latency_ms);
+
+        log::info!(
+            "Bridge canister base EVM max acceptable latency changed to {max_latency_ms:?}ms"
+        );
+    }
+
+    /// Enables or disables the strict validation of the base EVM BTF bridge events.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_base_strict_event_validation(&mut self, enabled: bool) {
+        bridge_canister::inspect::inspect_set_strict_event_validation(self.config());
+        get_base_evm_config()
+            .borrow_mut()
+            .set_strict_event_validation(enabled);
+
+        log::info!("Bridge canister base EVM strict event validation enabled: {enabled}");
+    }
+
+    /// Sets the relay, which base EVM mint transactions are sent to instead of the public mempool.
+    /// If `None`, mint transactions are sent to the public mempool.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_base_private_tx_relay(&mut self, relay: Option<PrivateTxRelay>) {
+        bridge_canister::inspect::inspect_set_private_tx_relay(self.config());
+        get_base_evm_config()
+            .borrow_mut()
+            .set_private_tx_relay(relay.clone());
+
+        log::info!("Bridge canister base EVM private transaction relay changed to {relay:?}");
+    }
+
+    /// Returns the drift between the nonce, reserved by the bridge canister, and the pending
+    /// nonce of the bridge canister base EVM address.
+    #[query]
+    pub fn get_base_nonce_drift_status(&self) -> NonceDriftStatus {
+        get_base_evm_config().borrow().get_nonce_drift_status()
+    }
+
+    /// Closes the open base EVM nonce gap and resumes base EVM mint transactions sending.
+    /// Returns the closed gap, if it was open.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn acknowledge_base_nonce_gap(&mut self) -> Option<NonceGap> {
+        bridge_canister::inspect::inspect_acknowledge_nonce_gap(self.config());
+        let gap = get_base_evm_config().borrow_mut().acknowledge_nonce_gap();
+
+        log::info!("Bridge canister base EVM nonce gap acknowledged: {gap:?}");
+        gap
+    }
+
     /// Retrieves all operations for the given ETH wallet address whose
     /// id is greater than or equal to `min_included_id` if provided.
     /// The operations are then paginated with the given `pagination` parameters,
     /// starting from `offset` returning a max of `count` items
     /// If `offset` is `None`, it starts from the beginning (i.e. the first entry is the min_included_id).
-    /// If `count` is `None`, it returns all operations.
+    /// If `pagination` is `None`, only the most recent operations are returned, 1000 by default
+    /// (see `set_operations_list_default_limit`), so callers should paginate to get the full history.
     #[query]
     pub fn get_operations_list(
         &self,
@@ -98,11 +263,16 @@ impl Erc20Bridge {
         min_included_id: Option<OperationId>,
         pagination: Option<Pagination>,
     ) -> Vec<(OperationId, Erc20BridgeOpImpl)> {
-        get_runtime_state().borrow().operations.get_for_address(
-            &wallet_address,
-            min_included_id,
-            pagination,
-        )
+        let default_limit = self.config().borrow().get_operations_list_default_limit();
+        get_runtime_state()
+            .borrow()
+            .operations
+            .get_recent_for_address(
+                &wallet_address,
+                min_included_id,
+                pagination,
+                default_limit as usize,
+            )
     }
 
     /// Returns operation by memo and user.
@@ -127,7 +297,8 @@ impl Erc20Bridge {
             .get_memos_by_user_address(&user_id)
     }
 
-    /// Returns log of an operation by its ID.
+    /// Returns log of an operation by its ID. Logs of the operations with erased user data
+    /// are not returned.
     #[query]
     pub fn get_operation_log(
         &self,
@@ -136,15 +307,167 @@ impl Erc20Bridge {
         get_runtime_state()
             .borrow()
             .operations
-            .get_log(operation_id)
+            .get_public_log(operation_id)
+    }
+
+    /// Returns the compact statuses of the operations in the order of `operation_ids`,
+    /// with `None` for the unknown operations. Up to 200 operations can be requested at once.
+    #[query]
+    pub fn get_operations_status_batch(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> BTFResult<Vec<(OperationId, Option<OperationStatusView>)>> {
+        get_runtime_state()
+            .borrow()
+            .operations
+            .get_status_batch(operation_ids)
+    }
+
+    /// Schedules the progress of the incomplete operations, matching the `filter`, immediately.
+    ///
+    /// Up to 100 operations are processed per call. If more operations match the filter,
+    /// the returned `continuation` should be passed to the next call. The call is recorded
+    /// in the operator audit log.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn bulk_retry(
+        &mut self,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+    ) -> BulkActionResult {
+        bridge_canister::inspect::inspect_bulk_action(self.config());
+        get_runtime().borrow().bulk_action(
+            BulkAction::Retry,
+            filter,
+            continuation,
+            ic::caller(),
+            ic::time(),
+        )
+    }
+
+    /// Cancels the incomplete operations, matching the `filter`, if the operations can be
+    /// cancelled at their current step. See `bulk_retry` for the per-call limit.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn bulk_cancel(
+        &mut self,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+    ) -> BulkActionResult {
+        bridge_canister::inspect::inspect_bulk_action(self.config());
+        get_runtime().borrow().bulk_action(
+            BulkAction::Cancel,
+            filter,
+            continuation,
+            ic::caller(),
+            ic::time(),
+        )
+    }
+
+    /// Lets the operations, matching the `filter` and deferred by the direction concurrency
+    /// limit, progress regardless of the limit. See `bulk_retry` for the per-call limit.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn bulk_approve(
+        &mut self,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+    ) -> BulkActionResult {
+        bridge_canister::inspect::inspect_bulk_action(self.config());
+        get_runtime().borrow().bulk_action(
+            BulkAction::Approve,
+            filter,
+            continuation,
+            ic::caller(),
+            ic::time(),
+        )
+    }
+
+    /// Returns the audit log of the operator bulk actions and notes, starting from the oldest
+    /// entries. At most [`MAX_AUDIT_ENTRIES_PER_REQUEST`] entries are returned.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_operator_audit_log(
+        &self,
+        pagination: Option<Pagination>,
+    ) -> Vec<OperatorAuditEntry> {
+        bridge_canister::inspect::inspect_get_operator_audit_log(self.config());
+        let pagination =
+            pagination.unwrap_or_else(|| Pagination::new(0, MAX_AUDIT_ENTRIES_PER_REQUEST));
+        get_runtime_state()
+            .borrow()
+            .operator_audit
+            .get(pagination.offset, pagination.count)
+    }
+
+    /// Adds the note to the operator audit log, e.g. the configuration changes of the canister
+    /// upgrade. Returns id of the audit log entry.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn add_operator_audit_note(&mut self, note: String) -> BTFResult<u64> {
+        bridge_canister::inspect::inspect_add_operator_audit_note(self.config());
+        get_runtime()
+            .borrow()
+            .add_operator_note(ic::caller(), note, ic::time())
+    }
+
+    /// Erases the personal data linkage of the user from the historical operations. The wallet
+    /// address of the operations is replaced with a salted hash and the memos are removed.
+    /// The operation steps are kept as the financial records, but the operation logs are not
+    /// returned anymore and the operation blocks are redacted. The erasure is irreversible
+    /// and is recorded in the operator audit log.
+    ///
+    /// Fails if the user has incomplete operations.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub async fn anonymize_user_data(
+        &mut self,
+        identity: UserIdentity,
+    ) -> BTFResult<UserDataErasure> {
+        bridge_canister::inspect::inspect_anonymize_user_data(self.config());
+        let salt = bulk_action::random_salt().await?;
+        get_runtime()
+            .borrow()
+            .anonymize_user_data(&identity, &salt, ic::caller(), ic::time())
+    }
+
+    /// Returns the `batchMint` transaction request of the operation, which signed mint order
+    /// should be sent to the EVM by the user, e.g. because no fee payer was specified.
+    /// The request is built for the EVM of the operation side.
+    #[query]
+    pub fn get_mint_tx_request(&self, operation_id: OperationId) -> BTFResult<EvmTxRequest> {
+        let state = get_runtime_state();
+        let state = state.borrow();
+        let op = state
+            .operations
+            .get(operation_id)
+            .ok_or(Error::OperationNotFound(operation_id))?;
+        let order =
+            op.0.stage
+                .order_awaiting_user()
+                .ok_or(Error::MintOrderNotAwaitingUser(operation_id))?;
+
+        let config = match op.0.side {
+            BridgeSide::Base => get_base_evm_config(),
+            BridgeSide::Wrapped => state.config.clone(),
+        };
+        let config = config.borrow();
+        mint_tx_request(&config, order)
     }
 
     #[update]
     pub async fn get_bridge_canister_base_evm_address(&self) -> BTFResult<H160> {
-        let signer = get_base_evm_config().borrow().get_signer()?;
-        signer.get_address().await.map_err(|e| {
-            Error::Initialization(format!("failed to get bridge canister address: {e}"))
-        })
+        ConfigStorage::get_signer_address(get_base_evm_config())
+            .await
+            .map_err(|e| {
+                Error::Initialization(format!("failed to get bridge canister address: {e}"))
+            })
     }
 
     /// Returns the build data of the canister
@@ -153,6 +476,248 @@ impl Erc20Bridge {
         bridge_canister::build_data!()
     }
 
+    /// Returns current state of the operation nonces.
+    #[query]
+    pub fn get_nonce_state(&self) -> NonceState {
+        let mut nonce_state = get_runtime_state().borrow().nonce_state();
+        let next_nonce = *get_mint_order_nonce_counter().borrow().get();
+        nonce_state.next_nonce = next_nonce;
+        nonce_state
+    }
+
+    /// Sets minimal nonce of the mint orders signed by the canister.
+    /// The nonce counter is never moved backwards.
+    /// Fails if the offset exceeds the max mint order nonce, which is `u32::MAX`.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_nonce_offset(&mut self, offset: u64) -> BTFResult<()> {
+        bridge_canister::inspect::inspect_set_nonce_offset(self.config());
+        let mint_order_offset = mint_order_nonce_offset(offset)?;
+        get_runtime_state().borrow_mut().set_nonce_offset(offset);
+        apply_mint_order_nonce_offset(mint_order_offset);
+
+        log::info!("Bridge canister nonce offset changed to {offset}");
+        Ok(())
+    }
+
+    /// Returns the operation log bytes, as they are stored in stable memory, without decoding.
+    /// Intended to diagnose the operations which can't be decoded after an upgrade.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_operation_raw_bytes(&self, operation_id: OperationId) -> Option<Vec<u8>> {
+        bridge_canister::inspect::inspect_get_operation_raw_bytes(self.config());
+        get_runtime_state()
+            .borrow()
+            .operations
+            .get_raw_bytes(operation_id)
+    }
+
+    /// Replays the operation log steps through the operation transition graph and checks
+    /// that the result is consistent with the stored state of the operation. Returns the
+    /// description of the first inconsistency found.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn verify_operation_consistency(&self, operation_id: OperationId) -> Result<(), String> {
+        bridge_canister::inspect::inspect_verify_operation_consistency(self.config());
+        get_runtime_state()
+            .borrow()
+            .operations
+            .verify_consistency(operation_id)
+    }
+
+    /// Returns the captured progress invocations of the operation, oldest first.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_operation_replay(&self, operation_id: OperationId) -> Vec<ProgressReplayEntry> {
+        bridge_canister::inspect::inspect_get_operation_replay(self.config());
+        get_runtime_state()
+            .borrow()
+            .operation_replays
+            .get(operation_id)
+    }
+
+    /// Enables or disables the capture of the operation progress to the replay log,
+    /// regardless of the sampling. Already captured invocations are kept.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_operation_replay_capture(&mut self, operation_id: OperationId, enabled: bool) {
+        bridge_canister::inspect::inspect_set_operation_replay_capture(self.config());
+        get_runtime_state()
+            .borrow_mut()
+            .operation_replays
+            .set_capture(operation_id, enabled, ic::time());
+
+        log::info!("Replay capture of operation {operation_id} is set to {enabled}");
+    }
+
+    /// Returns the outbox messages, parked after too many failed delivery attempts, oldest first.
+    /// If `pagination` is `None`, only the oldest parked messages are returned, 1000 by default
+    /// (see `set_operations_list_default_limit`), so callers should paginate to get all of them.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_parked_outbox_messages(&self, pagination: Option<Pagination>) -> Vec<OutboxMessage> {
+        bridge_canister::inspect::inspect_get_parked_outbox_messages(self.config());
+        let default_limit = self.config().borrow().get_operations_list_default_limit();
+        let pagination = pagination.unwrap_or_else(|| Pagination::new(0, default_limit as usize));
+        get_runtime_state()
+            .borrow()
+            .operations
+            .outbox()
+            .get_parked(pagination.offset, pagination.count)
+    }
+
+    /// Moves the parked outbox message back to the delivery queue.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn requeue_outbox_message(&mut self, id: u64) -> BTFResult<()> {
+        bridge_canister::inspect::inspect_requeue_outbox_message(self.config());
+        get_runtime_state()
+            .borrow_mut()
+            .operations
+            .outbox_mut()
+            .requeue(id, ic::time())?;
+
+        log::info!("Parked outbox message {id} is requeued");
+        Ok(())
+    }
+
+    /// Removes the parked outbox message without delivery.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn drop_outbox_message(&mut self, id: u64) -> BTFResult<()> {
+        bridge_canister::inspect::inspect_drop_outbox_message(self.config());
+        get_runtime_state()
+            .borrow_mut()
+            .operations
+            .outbox_mut()
+            .drop_parked(id)?;
+
+        log::info!("Parked outbox message {id} is dropped");
+        Ok(())
+    }
+
+    /// Returns the burnt events, rejected by the strict event validation, oldest first.
+    /// Only the most recent 10000 rejected events are kept. If `pagination` is `None`,
+    /// only the oldest kept events are returned, 1000 by default
+    /// (see `set_operations_list_default_limit`), so callers should paginate to get all of them.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_dead_letter_events(&self, pagination: Option<Pagination>) -> Vec<DeadLetterEvent> {
+        bridge_canister::inspect::inspect_get_dead_letter_events(self.config());
+        let default_limit = self.config().borrow().get_operations_list_default_limit();
+        let pagination = pagination.unwrap_or_else(|| Pagination::new(0, default_limit as usize));
+        get_runtime_state()
+            .borrow()
+            .dead_letters
+            .get(pagination.offset, pagination.count)
+    }
+
+    /// Returns the blocks of the operations log in the ICRC-3 `get_blocks` format.
+    /// Every block records one state transition of an operation and contains the hash
+    /// of the previous block. At most 100 blocks are returned per request.
+    #[query]
+    pub fn get_operation_blocks(&self, start: Nat, length: Nat) -> GetBlocksResult {
+        get_runtime_state()
+            .borrow()
+            .operations
+            .blocks()
+            .get_blocks(&start, &length)
+    }
+
+    /// Returns up to `length` blocks of the operations log, starting from the `start` index,
+    /// in the format imported by the replica canisters. At most 100 blocks are returned
+    /// per request.
+    #[query]
+    pub fn export_operation_blocks(&self, start: u64, length: u64) -> OperationBlocksExport {
+        get_runtime_state()
+            .borrow()
+            .operations
+            .blocks()
+            .export(start, length)
+    }
+
+    /// Returns the sync status of the replica canister with its primary canister,
+    /// or `None` if the canister is the primary.
+    #[query]
+    pub fn get_replica_sync_status(&self) -> Option<ReplicaSyncStatus> {
+        get_runtime_state().borrow().replica_sync_status()
+    }
+
+    /// Promotes the replica canister to the primary mode, if the primary canister is halted
+    /// or doesn't respond. Operation blocks of the primary are synced before the promotion.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub async fn promote_to_primary(&mut self) -> BTFResult<()> {
+        bridge_canister::inspect::inspect_promote_to_primary(self.config());
+        BridgeRuntime::promote_to_primary(get_runtime()).await
+    }
+
+    /// Returns counters of the BTF bridge events, which were rejected before processing,
+    /// e.g. events of the previous BTF bridge contract. Counters are reset on upgrade.
+    #[query]
+    pub fn get_rejected_events_stats(&self) -> RejectedEventsStats {
+        get_runtime_state()
+            .borrow()
+            .processed_events
+            .rejected_stats()
+    }
+
+    /// Returns the sizes of the scheduler task payloads, moved to the task payloads store
+    /// because they exceed the inline size limit. Stats are updated on each scheduler run
+    /// and reset on upgrade.
+    #[query]
+    pub fn get_task_payload_stats(&self) -> TaskPayloadStats {
+        get_runtime_state().borrow().task_payloads.stats()
+    }
+
+    /// Returns the number of the operations per direction, deferred because the direction
+    /// concurrency limit is reached. Counters are reset on upgrade.
+    #[query]
+    pub fn get_deferred_operations_stats(&self) -> DeferredOperationsStats {
+        get_runtime_state().borrow().concurrency.deferred_stats()
+    }
+
+    /// Returns the BTF bridge events of the user with the given EVM address, which were
+    /// processed by the bridge, ordered by block. Events are kept for the same number of
+    /// blocks as the processed events deduplication index.
+    /// If `pagination` is `None`, all the stored events are returned.
+    #[query]
+    pub fn get_processed_events(
+        &self,
+        address: H160,
+        pagination: Option<Pagination>,
+    ) -> Vec<ProcessedEventInfo> {
+        get_runtime_state()
+            .borrow()
+            .processed_events
+            .get_for_sender(&address, pagination)
+    }
+
+    /// Returns the adjustments of the mint fees, paid by the given EVM address, to the actual
+    /// cost of the mint transactions, with the totals of the credited and debited amounts.
+    /// The number of the returned adjustments is capped, so a long history is read page by page.
+    #[query]
+    pub fn get_fee_adjustments(
+        &self,
+        fee_payer: H160,
+        pagination: Option<Pagination>,
+    ) -> FeePayerAdjustments {
+        get_runtime_state()
+            .borrow()
+            .fee_adjustments
+            .get_for_payer(&fee_payer, pagination)
+    }
+
     /// Returns candid IDL.
     /// This should be the last fn to see previous endpoints in macro.
     pub fn idl() -> Idl {
@@ -190,6 +755,32 @@ fn init_runtime() -> SharedRuntime {
     );
     let refresh_wrapped_params_service = RefreshEvmParamsService::new(wrapped_config.clone());
 
+    // Init gas balance monitoring services
+    let base_gas_balance_service = ServiceTimer::new(
+        MonitorGasBalanceService::new(base_config.clone()),
+        GAS_BALANCE_QUERY_DELAY,
+    );
+    let wrapped_gas_balance_service = ServiceTimer::new(
+        MonitorGasBalanceService::new(wrapped_config.clone()),
+        GAS_BALANCE_QUERY_DELAY,
+    );
+
+    // Init outbox dispatch service
+    let outbox_service = ServiceTimer::new(
+        DispatchOutboxService::new(wrapped_state.clone()),
+        OUTBOX_DISPATCH_DELAY,
+    );
+    let migrate_records_service = ServiceTimer::new(
+        MigrateRecordsService::new(wrapped_state.clone(), scheduler.clone()),
+        MIGRATE_RECORDS_DELAY,
+    );
+
+    // Init EVM latency monitoring services
+    let base_ping_evm_service =
+        ServiceTimer::new(PingEvmService::new(base_config.clone()), PING_EVM_DELAY);
+    let wrapped_ping_evm_service =
+        ServiceTimer::new(PingEvmService::new(wrapped_config.clone()), PING_EVM_DELAY);
+
     // Init event listener services
     let base_event_handler = Erc20EventsHandler::new(
         get_mint_order_nonce_counter(),
@@ -225,8 +816,14 @@ fn init_runtime() -> SharedRuntime {
     let sign_service = Erc20ServiceSelector::new(base_sign_service, wrapped_sign_service);
 
     // Init mint tx service
-    let base_mint_tx_service = SendMintTxService::new(base_handler);
-    let wrapped_mint_tx_service = SendMintTxService::new(wrapped_handler);
+    let base_mint_tx_service = SendMintTxService::new(
+        base_handler,
+        memory_by_id(BASE_PENDING_MINT_BATCHES_MEMORY_ID),
+    );
+    let wrapped_mint_tx_service = SendMintTxService::new(
+        wrapped_handler,
+        memory_by_id(PENDING_MINT_BATCHES_MEMORY_ID),
+    );
     let send_mint_tx_service =
         Erc20ServiceSelector::new(base_mint_tx_service, wrapped_mint_tx_service);
 
@@ -261,6 +858,36 @@ fn init_runtime() -> SharedRuntime {
         SEND_MINT_TX_SERVICE_ID,
         Rc::new(send_mint_tx_service),
     );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        MONITOR_BASE_GAS_BALANCE_SERVICE_ID,
+        Rc::new(base_gas_balance_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        MONITOR_WRAPPED_GAS_BALANCE_SERVICE_ID,
+        Rc::new(wrapped_gas_balance_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        PING_BASE_EVM_SERVICE_ID,
+        Rc::new(base_ping_evm_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        PING_WRAPPED_EVM_SERVICE_ID,
+        Rc::new(wrapped_ping_evm_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        DISPATCH_OUTBOX_SERVICE_ID,
+        Rc::new(outbox_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        MIGRATE_RECORDS_SERVICE_ID,
+        Rc::new(migrate_records_service),
+    );
 
     runtime
 }
@@ -298,3 +925,34 @@ pub fn get_base_evm_config() -> Rc<RefCell<ConfigStorage>> {
 pub fn get_mint_order_nonce_counter() -> SharedNonceCounter {
     MINT_ORDER_NONCE_COUNTER.with(|c| c.clone())
 }
+
+/// Converts the nonce offset to the mint order nonce.
+/// Fails if the offset exceeds the max mint order nonce.
+fn mint_order_nonce_offset(offset: u64) -> BTFResult<u32> {
+    u32::try_from(offset).map_err(|_| Error::NonceOffsetTooLarge(u32::MAX as u64))
+}
+
+/// Moves the mint order nonce counter to the given offset, if it is behind.
+fn apply_mint_order_nonce_offset(offset: u32) {
+    let counter = get_mint_order_nonce_counter();
+    let mut counter = counter.borrow_mut();
+    if *counter.get() < offset {
+        counter.set(offset).expect("failed to update nonce counter");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nonce_offset_above_max_mint_order_nonce_is_rejected() {
+        assert_eq!(mint_order_nonce_offset(42), Ok(42));
+        assert_eq!(mint_order_nonce_offset(u32::MAX as u64), Ok(u32::MAX));
+        assert_eq!(
+            mint_order_nonce_offset(u32::MAX as u64 + 1),
+            Err(Error::NonceOffsetTooLarge(u32::MAX as u64))
+        );
+        assert!(mint_order_nonce_offset(u64::MAX).is_err());
+    }
+}
@@ -22,6 +22,12 @@ async fn inspect_method(method: &str) -> BTFResult<()> {
     let config = canister::get_runtime_state().borrow().config.clone();
     match method {
         "set_base_btf_bridge_contract" => config.borrow().check_owner(ic::caller()),
+        "set_base_gas_balance_thresholds" => config.borrow().check_owner(ic::caller()),
+        "set_base_evm_params_history_size" => config.borrow().check_owner(ic::caller()),
+        "set_base_max_acceptable_evm_latency_ms" => config.borrow().check_owner(ic::caller()),
+        "set_base_strict_event_validation" => config.borrow().check_owner(ic::caller()),
+        "set_base_private_tx_relay" => config.borrow().check_owner(ic::caller()),
+        "acknowledge_base_nonce_gap" => config.borrow().check_owner(ic::caller()),
         _ => Ok(()),
     }
 }
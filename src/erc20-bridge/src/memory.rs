@@ -3,3 +3,4 @@ use ic_stable_structures::MemoryId;
 pub const BASE_EVM_CONFIG_MEMORY_ID: MemoryId = MemoryId::new(10);
 pub const NONCE_COUNTER_MEMORY_ID: MemoryId = MemoryId::new(11);
 pub const DELAYS_MEMORY_ID: MemoryId = MemoryId::new(12);
+pub const BASE_PENDING_MINT_BATCHES_MEMORY_ID: MemoryId = MemoryId::new(13);
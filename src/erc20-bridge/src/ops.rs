@@ -1,20 +1,27 @@
+use std::mem;
+
 use bridge_canister::bridge::{Operation, OperationProgress};
 use bridge_canister::memory::StableMemory;
-use bridge_canister::runtime::scheduler::{BridgeTask, SharedScheduler};
+use bridge_canister::runtime::scheduler::SharedScheduler;
 use bridge_canister::runtime::service::mint_tx::MintTxHandler;
 use bridge_canister::runtime::service::sign_orders::MintOrderHandler;
 use bridge_canister::runtime::service::{BridgeService, ServiceId};
+use bridge_canister::runtime::state::config::ConfigStorage;
 use bridge_canister::runtime::state::SharedConfig;
 use bridge_canister::runtime::RuntimeState;
 use bridge_did::bridge_side::BridgeSide;
 use bridge_did::error::{BTFResult, Error};
 use bridge_did::id256::Id256;
 use bridge_did::op_id::OperationId;
-use bridge_did::operations::{Erc20BridgeOp, Erc20OpStage};
+use bridge_did::operation_log::OperationLog;
+use bridge_did::operations::{Erc20BridgeOp, Erc20BridgeOpV1, Erc20OpStage};
 use bridge_did::order::{MintOrder, SignedOrders};
+use bridge_did::retry_profile::RetryProfileKind;
+use bridge_did::versioned::Versioned;
 use candid::CandidType;
-use did::H160;
+use did::{H160, H256};
 use eth_signer::sign_strategy::TransactionSigner;
+use ic_storage::IcStorage;
 use ic_task_scheduler::scheduler::TaskScheduler;
 use ic_task_scheduler::task::{ScheduledTask, TaskOptions};
 use serde::{Deserialize, Serialize};
@@ -29,6 +36,12 @@ pub const FETCH_BASE_LOGS_SERVICE_ID: ServiceId = 2;
 pub const FETCH_WRAPPED_LOGS_SERVICE_ID: ServiceId = 3;
 pub const SIGN_MINT_ORDER_SERVICE_ID: ServiceId = 4;
 pub const SEND_MINT_TX_SERVICE_ID: ServiceId = 5;
+pub const MONITOR_BASE_GAS_BALANCE_SERVICE_ID: ServiceId = 6;
+pub const MONITOR_WRAPPED_GAS_BALANCE_SERVICE_ID: ServiceId = 7;
+pub const PING_BASE_EVM_SERVICE_ID: ServiceId = 8;
+pub const PING_WRAPPED_EVM_SERVICE_ID: ServiceId = 9;
+pub const DISPATCH_OUTBOX_SERVICE_ID: ServiceId = 10;
+pub const MIGRATE_RECORDS_SERVICE_ID: ServiceId = 11;
 
 #[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
 pub struct Erc20BridgeOpImpl(pub Erc20BridgeOp);
@@ -53,10 +66,48 @@ impl Operation for Erc20BridgeOpImpl {
                 }))
             }
             OperationProgress::AddToService(op_id) => OperationProgress::AddToService(op_id),
+            OperationProgress::ProgressWithDependent {
+                update_to,
+                dependent,
+            } => OperationProgress::ProgressWithDependent {
+                update_to: Self(Erc20BridgeOp {
+                    side: self.0.side,
+                    stage: update_to.0,
+                }),
+                dependent: Self(Erc20BridgeOp {
+                    side: self.0.side,
+                    stage: dependent.0,
+                }),
+            },
         };
         Ok(progress)
     }
 
+    /// Operations stored before the chain ids of the mint orders were extended to 64 bits are
+    /// decoded with the legacy shape. An operation, which sends or confirms the order signed in
+    /// the legacy layout, is moved back to signing of the same order.
+    fn decode_legacy_log(bytes: &[u8]) -> Option<OperationLog<Self>> {
+        let log = OperationLog::<Erc20BridgeOpV1>::from_versioned_bytes(bytes).ok()?;
+        let mut log = log.map_payload(|op, _| Self(op.into()));
+
+        let op = &log.current_step().0;
+        let legacy_order = match &op.stage {
+            Erc20OpStage::SendMintTransaction(order) | Erc20OpStage::ConfirmMint { order, .. } => {
+                order.legacy_order()
+            }
+            _ => None,
+        };
+        if let Some(order) = legacy_order {
+            let side = op.side;
+            log.add_step(Ok(Self(Erc20BridgeOp {
+                side,
+                stage: Erc20OpStage::SignMintOrder(order),
+            })));
+        }
+
+        Some(log)
+    }
+
     fn is_complete(&self) -> bool {
         match self.0.stage {
             Erc20OpStage::SignMintOrder(_) => false,
@@ -111,12 +162,48 @@ impl Operation for Erc20BridgeOpImpl {
 
     fn scheduling_options(&self) -> Option<TaskOptions> {
         match self.0.stage {
-            Erc20OpStage::SignMintOrder(_) => Some(TaskOptions::default()),
-            Erc20OpStage::SendMintTransaction(_) => Some(TaskOptions::default()),
+            Erc20OpStage::SignMintOrder(_) | Erc20OpStage::SendMintTransaction(_) => Some(
+                ConfigStorage::get()
+                    .borrow()
+                    .get_task_options(RetryProfileKind::MintSend),
+            ),
             Erc20OpStage::ConfirmMint { .. } => None,
             Erc20OpStage::TokenMintConfirmed(_) => None,
         }
     }
+
+    fn evm_tx_hash(&self) -> Option<H256> {
+        match &self.0.stage {
+            Erc20OpStage::ConfirmMint { tx_hash, .. } => tx_hash.clone(),
+            _ => None,
+        }
+    }
+
+    /// Deposits and withdrawals share the mint flow, and never change their side.
+    /// Operation can stay in its stage with updated data, e.g. the mint transaction hash.
+    fn can_transition(from: &Self, to: &Self) -> bool {
+        if from.0.side != to.0.side {
+            return false;
+        }
+
+        if mem::discriminant(&from.0.stage) == mem::discriminant(&to.0.stage) {
+            return true;
+        }
+
+        matches!(
+            (&from.0.stage, &to.0.stage),
+            (
+                Erc20OpStage::SignMintOrder(_),
+                Erc20OpStage::SendMintTransaction(_) | Erc20OpStage::ConfirmMint { .. }
+            ) | (
+                Erc20OpStage::SendMintTransaction(_),
+                Erc20OpStage::ConfirmMint { .. } | Erc20OpStage::TokenMintConfirmed(_)
+            ) | (
+                Erc20OpStage::ConfirmMint { .. },
+                Erc20OpStage::TokenMintConfirmed(_)
+            )
+        )
+    }
 }
 
 pub struct Erc20OpStageImpl(pub Erc20OpStage);
@@ -231,7 +318,14 @@ impl MintOrderHandler for Erc20OrderHandler {
             return;
         };
 
-        let should_send_mint_tx = order.fee_payer != H160::zero();
+        let will_pay_fee = order.fee_payer != H160::zero();
+        let sponsorship_enabled = self.config.borrow().is_mint_tx_sponsorship_enabled();
+        if will_pay_fee && !sponsorship_enabled {
+            log::warn!(
+                "Mint transactions sponsorship is disabled due to low gas balance. The mint order should be sent by the user."
+            );
+        }
+        let should_send_mint_tx = will_pay_fee && sponsorship_enabled;
         let new_stage = match should_send_mint_tx {
             true => Erc20OpStage::SendMintTransaction(signed),
             false => Erc20OpStage::ConfirmMint {
@@ -251,7 +345,8 @@ impl MintOrderHandler for Erc20OrderHandler {
             .update(id, new_op.clone());
 
         if let Some(options) = scheduling_options {
-            let scheduled_task = ScheduledTask::with_options(BridgeTask::new(id, new_op), options);
+            let task = self.state.borrow_mut().new_task(id, new_op);
+            let scheduled_task = ScheduledTask::with_options(task, options);
             self.scheduler.append_task(scheduled_task);
         }
     }
@@ -279,7 +374,7 @@ impl MintTxHandler for Erc20OrderHandler {
         Some(order)
     }
 
-    fn mint_tx_sent(&self, id: OperationId, tx_hash: did::H256) {
+    fn mint_tx_sent(&self, id: OperationId, tx_hash: did::H256, evm_params_snapshot: Option<u64>) {
         let Some(op) = self.state.borrow().operations.get(id) else {
             log::info!("MintTxHandler failed to update operation: not found.");
             return;
@@ -289,7 +384,7 @@ impl MintTxHandler for Erc20OrderHandler {
             return;
         };
 
-        self.state.borrow_mut().operations.update(
+        self.state.borrow_mut().operations.update_with_evm_params(
             id,
             Erc20BridgeOpImpl(Erc20BridgeOp {
                 side: op.0.side,
@@ -298,6 +393,28 @@ impl MintTxHandler for Erc20OrderHandler {
                     tx_hash: Some(tx_hash),
                 },
             }),
+            evm_params_snapshot,
+        );
+    }
+
+    fn mint_tx_not_sponsored(&self, id: OperationId) -> bool {
+        let Some(op) = self.state.borrow().operations.get(id) else {
+            return false;
+        };
+        let Erc20OpStage::SendMintTransaction(order) = op.0.stage else {
+            return false;
+        };
+
+        self.state.borrow_mut().operations.update(
+            id,
+            Erc20BridgeOpImpl(Erc20BridgeOp {
+                side: op.0.side,
+                stage: Erc20OpStage::ConfirmMint {
+                    order,
+                    tx_hash: None,
+                },
+            }),
         );
+        true
     }
 }
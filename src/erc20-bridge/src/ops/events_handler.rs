@@ -7,6 +7,7 @@ use bridge_did::id256::Id256;
 use bridge_did::op_id::OperationId;
 use bridge_did::operations::{Erc20BridgeOp, Erc20OpStage};
 use bridge_did::order::MintOrder;
+use bridge_utils::btf_events::BurntTokenMetadata;
 use bridge_utils::evm_bridge::EvmParams;
 use did::{H160, U256};
 use ic_stable_structures::CellStructure;
@@ -115,10 +116,19 @@ pub fn mint_order_from_burnt_event(
     mint_side_evm_params: EvmParams,
     nonce: u32,
 ) -> Option<MintOrder> {
-    let sender = Id256::from_evm_address(&event.sender, burn_side_evm_params.chain_id);
-    let src_token = Id256::from_evm_address(&event.from_erc20, burn_side_evm_params.chain_id);
-    let recipient = Id256::from_slice(&event.recipient_id)?
-        .to_evm_address()
+    // Token and user ids are created with 32 bits chain id.
+    let burn_side_chain_id = u32::try_from(burn_side_evm_params.chain_id)
+        .inspect_err(|_| {
+            log::warn!(
+                "Burn side chain id {} doesn't fit into Id256",
+                burn_side_evm_params.chain_id
+            )
+        })
+        .ok()?;
+    let sender = Id256::from_evm_address(&event.sender, burn_side_chain_id);
+    let src_token = Id256::from_evm_address(&event.from_erc20, burn_side_chain_id);
+    let recipient = Id256::try_from_slice(&event.recipient_id)
+        .and_then(|id| id.try_to_evm_address())
         .inspect_err(|err| {
             log::info!(
                 "Failed to parse recipeint_id {:?}: {}",
@@ -128,11 +138,12 @@ pub fn mint_order_from_burnt_event(
         })
         .ok()?
         .1;
-    let dst_token = Id256::from_slice(&event.to_token)?
-        .to_evm_address()
+    let dst_token = Id256::try_from_slice(&event.to_token)
+        .and_then(|id| id.try_to_evm_address())
         .inspect_err(|err| log::info!("Failed to parse to_token {:?}: {}", event.to_token, err))
         .ok()?
         .1;
+    let metadata = BurntTokenMetadata::normalized(&event);
 
     let order = MintOrder {
         amount: event.amount,
@@ -143,23 +154,14 @@ pub fn mint_order_from_burnt_event(
         nonce,
         sender_chain_id: burn_side_evm_params.chain_id,
         recipient_chain_id: mint_side_evm_params.chain_id,
-        name: to_array(&event.name)?,
-        symbol: to_array(&event.symbol)?,
-        decimals: event.decimals,
+        name: metadata.name,
+        symbol: metadata.symbol,
+        decimals: metadata.decimals,
         approve_spender: H160::default(),
         approve_amount: U256::default(),
         fee_payer: event.sender,
+        permit: None,
     };
 
     Some(order)
 }
-
-fn to_array<const N: usize>(data: &[u8]) -> Option<[u8; N]> {
-    match data.try_into() {
-        Ok(arr) => Some(arr),
-        Err(e) => {
-            log::warn!("failed to convert token metadata into array: {e}");
-            None
-        }
-    }
-}
@@ -7,6 +7,8 @@ use bridge_canister::memory::{memory_by_id, StableMemory};
 use bridge_canister::runtime::state::config::ConfigStorage;
 use bridge_canister::runtime::state::SharedConfig;
 use bridge_did::error::{BTFResult, Error};
+use bridge_did::event_filter::EventTopicFilter;
+use bridge_did::evm_finality::EvmFinality;
 use bridge_did::evm_link::EvmLink;
 use bridge_did::init::erc20::{BaseEvmSettings, QueryDelays};
 use bridge_utils::evm_bridge::EvmParams;
@@ -44,6 +46,7 @@ impl BaseEvmState {
             config.signing_strategy = settings.signing_strategy;
             config.evm_params = None;
             config.btf_bridge_contract_address = None;
+            config.evm_finality = settings.evm_finality;
         })
     }
 }
@@ -63,6 +66,10 @@ impl OperationContext for SharedBaseEvmState {
         self.0.borrow().config.borrow().get_evm_link()
     }
 
+    fn get_rpc_request_timeout(&self) -> Duration {
+        self.0.borrow().config.borrow().get_rpc_request_timeout()
+    }
+
     fn get_bridge_contract_address(&self) -> BTFResult<did::H160> {
         self.0
             .borrow()
@@ -79,4 +86,12 @@ impl OperationContext for SharedBaseEvmState {
     fn get_signer(&self) -> BTFResult<impl TransactionSigner> {
         self.0.borrow().config.borrow().get_signer()
     }
+
+    fn get_evm_finality(&self) -> EvmFinality {
+        self.0.borrow().config.borrow().get_evm_finality()
+    }
+
+    fn get_event_topic_filter(&self) -> Option<EventTopicFilter> {
+        self.0.borrow().config.borrow().get_event_topic_filter()
+    }
 }
@@ -1,38 +1,90 @@
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 
+use bridge_canister::memory::{memory_by_id, PENDING_MINT_BATCHES_MEMORY_ID};
+use bridge_canister::operator_audit::MAX_AUDIT_ENTRIES_PER_REQUEST;
+use bridge_canister::runtime::bulk_action;
+use bridge_canister::runtime::service::dropped_tx::{
+    CheckDroppedMintTxService, CHECK_DROPPED_TX_DELAY,
+};
 use bridge_canister::runtime::service::fetch_logs::FetchBtfBridgeEventsService;
-use bridge_canister::runtime::service::mint_tx::SendMintTxService;
+use bridge_canister::runtime::service::gas_balance::{
+    MonitorGasBalanceService, GAS_BALANCE_QUERY_DELAY,
+};
+use bridge_canister::runtime::service::migrate_records::{
+    MigrateRecordsService, MIGRATE_RECORDS_DELAY,
+};
+use bridge_canister::runtime::service::mint_tx::{mint_tx_request, SendMintTxService};
+use bridge_canister::runtime::service::outbox::{DispatchOutboxService, OUTBOX_DISPATCH_DELAY};
+use bridge_canister::runtime::service::ping_evm::{PingEvmService, PING_EVM_DELAY};
 use bridge_canister::runtime::service::sign_orders::SignMintOrdersService;
+use bridge_canister::runtime::service::timer::ServiceTimer;
 use bridge_canister::runtime::service::update_evm_params::RefreshEvmParamsService;
 use bridge_canister::runtime::service::ServiceOrder;
 use bridge_canister::runtime::state::config::ConfigStorage;
 use bridge_canister::runtime::state::SharedConfig;
 use bridge_canister::runtime::{BridgeRuntime, RuntimeState};
 use bridge_canister::BridgeCanister;
+use bridge_did::bridge_mode::{OperationBlocksExport, ReplicaSyncStatus};
+use bridge_did::bulk_action::{BulkAction, BulkActionResult, OperationFilter, OperatorAuditEntry};
+use bridge_did::burn_intent::BurnIntent;
+use bridge_did::concurrency::DeferredOperationsStats;
+use bridge_did::data_erasure::{UserDataErasure, UserIdentity};
+use bridge_did::dead_letter::DeadLetterEvent;
+use bridge_did::deposit_account::{DepositAccountInfo, DepositAllowance};
+use bridge_did::deposit_bundle::{BundleId, BundleItem, DepositBundleStatus};
 use bridge_did::error::{BTFResult, Error};
+use bridge_did::evm_tx::EvmTxRequest;
+use bridge_did::fee_adjustment::FeePayerAdjustments;
+use bridge_did::icrc21::{ConsentInfo, ConsentMessageRequest, Icrc21Error, StandardRecord};
+use bridge_did::icrc3::GetBlocksResult;
 use bridge_did::init::BridgeInitData;
-use bridge_did::op_id::OperationId;
+use bridge_did::op_id::{NonceState, OperationId};
 use bridge_did::operation_log::{Memo, OperationLog};
+use bridge_did::operation_replay::ProgressReplayEntry;
+use bridge_did::operation_status::OperationStatusView;
 use bridge_did::operations::IcrcBridgeOp;
+use bridge_did::outbox::OutboxMessage;
+use bridge_did::preferences::BridgePreferences;
+use bridge_did::processed_event::{ProcessedEventInfo, RejectedEventsStats};
+use bridge_did::reason::{ApproveAmountPolicy, Icrc2Burn};
+use bridge_did::supply_guard::{SupplyCheck, SupplyGuardConfig};
+use bridge_did::task_payload::TaskPayloadStats;
+use bridge_did::token_decimals::{DecimalsChangeStrategy, TokenDecimals};
 use bridge_utils::common::Pagination;
-use candid::Principal;
+use candid::{Nat, Principal};
 use did::build::BuildData;
-use did::H160;
+use did::{H160, U256};
 use ic_canister::{
     generate_idl, init, post_upgrade, query, update, Canister, Idl, MethodType, PreUpdate,
 };
 use ic_exports::ic_kit::ic;
 use ic_log::canister::{LogCanister, LogState};
 use ic_metrics::{Metrics, MetricsStorage};
+use ic_stable_structures::CellStructure;
 use ic_storage::IcStorage;
+use icrc_client::account::{Account, Subaccount};
 
+use crate::constant::{COMPLETION_ESTIMATE_SAMPLE_SIZE, DEPOSIT_STANDARDS};
+use crate::ops::approval_delivery::{SweepApprovalsService, SWEEP_APPROVALS_DELAY};
+use crate::ops::completion_estimate::{self, PhaseLatencies};
+use crate::ops::deposit_bundle::{self, DepositBundlesService, DEPOSIT_BUNDLES_DELAY};
+use crate::ops::deposit_confirmation::{self, ExpirePendingDepositsService, EXPIRE_DEPOSITS_DELAY};
 use crate::ops::events_handler::IcrcEventsHandler;
+use crate::ops::supply_guard::{SupplyGuardService, SUPPLY_GUARD_DELAY};
+use crate::ops::{approval_cancellation, burn_batch};
 use crate::ops::{
-    IcrcBridgeOpImpl, IcrcMintOrderHandler, IcrcMintTxHandler, FETCH_BTF_EVENTS_SERVICE_ID,
-    REFRESH_PARAMS_SERVICE_ID, SEND_MINT_TX_SERVICE_ID, SIGN_MINT_ORDER_SERVICE_ID,
+    ErrorCodes, IcrcBridgeOpImpl, IcrcDroppedMintTxHandler, IcrcMintOrderHandler,
+    IcrcMintTxHandler, CHECK_DROPPED_MINT_TX_SERVICE_ID, DEPOSIT_BUNDLES_SERVICE_ID,
+    DISPATCH_OUTBOX_SERVICE_ID, EXPIRE_PENDING_DEPOSITS_SERVICE_ID, FETCH_BTF_EVENTS_SERVICE_ID,
+    MONITOR_GAS_BALANCE_SERVICE_ID, PING_EVM_SERVICE_ID, REFRESH_PARAMS_SERVICE_ID,
+    SEND_MINT_TX_SERVICE_ID, SIGN_MINT_ORDER_SERVICE_ID, SUPPLY_GUARD_SERVICE_ID,
+    SWEEP_APPROVALS_SERVICE_ID,
 };
 use crate::state::IcrcState;
+use crate::tokens::{capabilities, icrc1, icrc2};
+use crate::{consent, ops};
 
 #[cfg(feature = "export-api")]
 mod inspect;
@@ -66,6 +118,7 @@ impl Icrc2BridgeCanister {
     #[post_upgrade]
     pub fn post_upgrade(&mut self) {
         self.bridge_post_upgrade(Self::run_scheduler);
+        ops::burn_intent::recover_burn_intents(&get_runtime().borrow());
     }
 
     fn run_scheduler() {
@@ -78,7 +131,8 @@ impl Icrc2BridgeCanister {
     /// The operations are then paginated with the given `pagination` parameters,
     /// starting from `offset` returning a max of `count` items
     /// If `offset` is `None`, it starts from the beginning (i.e. the first entry is the min_included_id).
-    /// If `count` is `None`, it returns all operations.
+    /// If `pagination` is `None`, only the most recent operations are returned, 1000 by default
+    /// (see `set_operations_list_default_limit`), so callers should paginate to get the full history.
     #[query]
     pub fn get_operations_list(
         &self,
@@ -86,11 +140,45 @@ impl Icrc2BridgeCanister {
         min_included_id: Option<OperationId>,
         pagination: Option<Pagination>,
     ) -> Vec<(OperationId, IcrcBridgeOpImpl)> {
-        get_runtime_state().borrow().operations.get_for_address(
-            &wallet_address,
-            min_included_id,
-            pagination,
-        )
+        let default_limit = self.config().borrow().get_operations_list_default_limit();
+        get_runtime_state()
+            .borrow()
+            .operations
+            .get_recent_for_address(
+                &wallet_address,
+                min_included_id,
+                pagination,
+                default_limit as usize,
+            )
+    }
+
+    /// Retrieves all operations, which use the given ICRC token, ordered by id.
+    /// The operations are then paginated with the given `pagination` parameters.
+    /// If `pagination` is `None`, all the operations are returned.
+    ///
+    /// The method scans all the stored operations.
+    #[query]
+    pub fn get_operations_by_src_token(
+        &self,
+        token: Principal,
+        pagination: Option<Pagination>,
+    ) -> Vec<(OperationId, IcrcBridgeOp)> {
+        get_runtime_state()
+            .borrow()
+            .operations
+            .get_filtered(
+                |log| {
+                    log.log()
+                        .iter()
+                        .filter_map(|entry| entry.step_result.as_ref().ok())
+                        .find_map(|op| op.0.icrc_token())
+                        == Some(token)
+                },
+                pagination,
+            )
+            .into_iter()
+            .map(|(id, op)| (id, op.0))
+            .collect()
     }
 
     #[query]
@@ -107,7 +195,45 @@ impl Icrc2BridgeCanister {
             .map(|op| (op.0, op.1 .0))
     }
 
-    /// Returns log of an operation by its ID.
+    /// Returns incomplete operations, which are pending in their current state
+    /// for longer than `max_age_secs` seconds.
+    #[query]
+    pub fn get_operations_exceeding_sla(
+        &self,
+        max_age_secs: u64,
+    ) -> Vec<(OperationId, IcrcBridgeOp)> {
+        let now = ic::time();
+        let max_age = Duration::from_secs(max_age_secs);
+        get_runtime_state()
+            .borrow()
+            .operations
+            .get_incomplete()
+            .into_iter()
+            .filter(|(_, op)| op.exceeds_sla(now, max_age))
+            .map(|(id, op)| (id, op.0))
+            .collect()
+    }
+
+    /// Returns the estimated number of seconds till the operation completion.
+    ///
+    /// The estimate is based on the phase latencies of the latest complete operations.
+    /// Returns `None` if the operation is not found, is complete, waits for the user action,
+    /// or if there is not enough history to estimate its remaining phases.
+    #[query]
+    pub fn estimate_completion(&self, operation_id: OperationId) -> Option<u64> {
+        let state = get_runtime_state();
+        let state = state.borrow();
+        let op = state.operations.get(operation_id)?;
+        let logs = state
+            .operations
+            .get_latest_complete_logs(COMPLETION_ESTIMATE_SAMPLE_SIZE);
+        let latencies = PhaseLatencies::from_logs(&logs);
+
+        completion_estimate::estimate_completion(&op.0, &latencies, ic::time())
+    }
+
+    /// Returns log of an operation by its ID. Logs of the operations with erased user data
+    /// are not returned.
     #[query]
     pub fn get_operation_log(
         &self,
@@ -116,7 +242,150 @@ impl Icrc2BridgeCanister {
         get_runtime_state()
             .borrow()
             .operations
-            .get_log(operation_id)
+            .get_public_log(operation_id)
+    }
+
+    /// Returns the compact statuses of the operations in the order of `operation_ids`,
+    /// with `None` for the unknown operations. Up to 200 operations can be requested at once.
+    #[query]
+    pub fn get_operations_status_batch(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> BTFResult<Vec<(OperationId, Option<OperationStatusView>)>> {
+        get_runtime_state()
+            .borrow()
+            .operations
+            .get_status_batch(operation_ids)
+    }
+
+    /// Schedules the progress of the incomplete operations, matching the `filter`, immediately.
+    ///
+    /// Up to 100 operations are processed per call. If more operations match the filter,
+    /// the returned `continuation` should be passed to the next call. The call is recorded
+    /// in the operator audit log.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn bulk_retry(
+        &mut self,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+    ) -> BulkActionResult {
+        bridge_canister::inspect::inspect_bulk_action(self.config());
+        get_runtime().borrow().bulk_action(
+            BulkAction::Retry,
+            filter,
+            continuation,
+            ic::caller(),
+            ic::time(),
+        )
+    }
+
+    /// Cancels the incomplete operations, matching the `filter`, if the operations can be
+    /// cancelled at their current step. See `bulk_retry` for the per-call limit.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn bulk_cancel(
+        &mut self,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+    ) -> BulkActionResult {
+        bridge_canister::inspect::inspect_bulk_action(self.config());
+        get_runtime().borrow().bulk_action(
+            BulkAction::Cancel,
+            filter,
+            continuation,
+            ic::caller(),
+            ic::time(),
+        )
+    }
+
+    /// Lets the operations, matching the `filter` and deferred by the direction concurrency
+    /// limit, progress regardless of the limit. See `bulk_retry` for the per-call limit.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn bulk_approve(
+        &mut self,
+        filter: OperationFilter,
+        continuation: Option<OperationId>,
+    ) -> BulkActionResult {
+        bridge_canister::inspect::inspect_bulk_action(self.config());
+        get_runtime().borrow().bulk_action(
+            BulkAction::Approve,
+            filter,
+            continuation,
+            ic::caller(),
+            ic::time(),
+        )
+    }
+
+    /// Returns the audit log of the operator bulk actions and notes, starting from the oldest
+    /// entries. At most [`MAX_AUDIT_ENTRIES_PER_REQUEST`] entries are returned.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_operator_audit_log(
+        &self,
+        pagination: Option<Pagination>,
+    ) -> Vec<OperatorAuditEntry> {
+        bridge_canister::inspect::inspect_get_operator_audit_log(self.config());
+        let pagination =
+            pagination.unwrap_or_else(|| Pagination::new(0, MAX_AUDIT_ENTRIES_PER_REQUEST));
+        get_runtime_state()
+            .borrow()
+            .operator_audit
+            .get(pagination.offset, pagination.count)
+    }
+
+    /// Adds the note to the operator audit log, e.g. the configuration changes of the canister
+    /// upgrade. Returns id of the audit log entry.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn add_operator_audit_note(&mut self, note: String) -> BTFResult<u64> {
+        bridge_canister::inspect::inspect_add_operator_audit_note(self.config());
+        get_runtime()
+            .borrow()
+            .add_operator_note(ic::caller(), note, ic::time())
+    }
+
+    /// Erases the personal data linkage of the user from the historical operations. The wallet
+    /// address of the operations is replaced with a salted hash and the memos are removed.
+    /// The operation steps are kept as the financial records, but the operation logs are not
+    /// returned anymore and the operation blocks are redacted. For a principal, its deposits and
+    /// withdrawals are anonymized, and its deposit preferences are removed. The erasure is
+    /// irreversible and is recorded in the operator audit log.
+    ///
+    /// Fails if the user has incomplete operations.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub async fn anonymize_user_data(
+        &mut self,
+        identity: UserIdentity,
+    ) -> BTFResult<UserDataErasure> {
+        bridge_canister::inspect::inspect_anonymize_user_data(self.config());
+        let salt = bulk_action::random_salt().await?;
+        erase_user_data(&identity, &salt, ic::caller())
+    }
+
+    /// Returns the `batchMint` transaction request of the operation, which signed mint order
+    /// should be sent to the EVM by the user, e.g. because no fee payer was specified.
+    #[query]
+    pub fn get_mint_tx_request(&self, operation_id: OperationId) -> BTFResult<EvmTxRequest> {
+        let state = get_runtime_state();
+        let state = state.borrow();
+        let op = state
+            .operations
+            .get(operation_id)
+            .ok_or(Error::OperationNotFound(operation_id))?;
+        let order =
+            op.0.order_awaiting_user()
+                .ok_or(Error::MintOrderNotAwaitingUser(operation_id))?;
+
+        mint_tx_request(&state.config.borrow(), order)
     }
 
     /// Returns all memos for a given user_id.
@@ -128,6 +397,571 @@ impl Icrc2BridgeCanister {
             .get_memos_by_user_address(&user_id)
     }
 
+    /// Creates deposits for the burns of the caller's ICRC-2 tokens.
+    ///
+    /// Every burn is checked and processed separately, so the result contains the created
+    /// operation id or the rejection error for each burn, in the order of the burns.
+    /// The caller should be the sender of every burn. Approve after mint is not supported.
+    /// The batch size is limited by `MAX_BURN_BATCH_SIZE`.
+    #[update]
+    pub fn burn_icrc2_batch(
+        &mut self,
+        reasons: Vec<Icrc2Burn>,
+    ) -> BTFResult<Vec<BTFResult<OperationId>>> {
+        self.config().borrow().check_primary_mode()?;
+        let caller = ic::caller();
+        check_anonymous_principal(caller)?;
+        burn_batch::check_batch_size(reasons.len())?;
+
+        let runtime = get_runtime();
+        let state = get_runtime_state();
+        let results = reasons
+            .into_iter()
+            .map(|burn| {
+                let deposit =
+                    burn_batch::new_deposit(caller, burn, &get_icrc_state().borrow(), ic::time())?;
+                let operation = IcrcBridgeOpImpl(deposit);
+                let id = state
+                    .borrow_mut()
+                    .operations
+                    .new_operation(operation.clone(), None);
+                runtime.borrow().schedule_operation(id, operation);
+
+                log::debug!("Deposit {id} is created by the batch burn of {caller}");
+
+                Ok(id)
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Creates an atomic bundle of deposits of different ICRC tokens and returns its id.
+    ///
+    /// Wrapped tokens of all the bundle deposits are minted by a single `batchMint` transaction,
+    /// or none of them is minted: if the burn of any deposit fails, the already burnt tokens
+    /// are refunded to the sender. The caller should be the sender of every burn. Burns are
+    /// checked like in the `burn_icrc2_batch`, and the bundle is rejected if any of them
+    /// is invalid. Confirmation delay is not supported.
+    #[update]
+    pub fn create_deposit_bundle(&mut self, reasons: Vec<Icrc2Burn>) -> BTFResult<BundleId> {
+        self.config().borrow().check_primary_mode()?;
+        let caller = ic::caller();
+        check_anonymous_principal(caller)?;
+        deposit_bundle::check_bundle(&reasons)?;
+
+        let now = ic::time();
+        let deposits = reasons
+            .into_iter()
+            .map(|burn| {
+                let burn = Icrc2Burn {
+                    confirmation_delay_secs: None,
+                    ..burn
+                };
+                let from_subaccount = burn.from_subaccount;
+                let deposit =
+                    burn_batch::new_deposit(caller, burn, &get_icrc_state().borrow(), now)?;
+                Ok((IcrcBridgeOpImpl(deposit), from_subaccount))
+            })
+            .collect::<BTFResult<Vec<_>>>()?;
+
+        let runtime = get_runtime();
+        let state = get_runtime_state();
+        let mut operations = Vec::with_capacity(deposits.len());
+        let mut items = Vec::with_capacity(deposits.len());
+        for (operation, from_subaccount) in deposits {
+            let operation_id = state
+                .borrow_mut()
+                .operations
+                .new_operation(operation.clone(), None);
+            operations.push((operation_id, operation));
+            items.push(BundleItem {
+                operation_id,
+                from_subaccount,
+            });
+        }
+
+        let bundle_id = get_icrc_state()
+            .borrow_mut()
+            .deposit_bundles
+            .insert(caller, items, now);
+        for (id, operation) in operations {
+            runtime.borrow().schedule_operation(id, operation);
+        }
+
+        log::debug!("Deposit bundle {bundle_id} is created by {caller}");
+
+        Ok(bundle_id)
+    }
+
+    /// Returns the progress of the deposit bundle and its deposits.
+    #[query]
+    pub fn get_bundle_status(&self, bundle_id: BundleId) -> Option<DepositBundleStatus> {
+        let bundle = get_icrc_state().borrow().deposit_bundles.get(bundle_id)?;
+        Some(deposit_bundle::bundle_status(
+            &get_runtime_state(),
+            bundle_id,
+            bundle,
+        ))
+    }
+
+    /// Confirms the deposit, waiting for the user confirmation, and starts the tokens burn.
+    ///
+    /// This method is only for the deposit sender.
+    #[update]
+    pub fn confirm_deposit(&mut self, operation_id: OperationId) -> BTFResult<()> {
+        self.config().borrow().check_primary_mode()?;
+        let runtime = get_runtime();
+        let state = get_runtime_state();
+        let op = state
+            .borrow()
+            .operations
+            .get(operation_id)
+            .ok_or(Error::OperationNotFound(operation_id))?;
+
+        let confirmed = IcrcBridgeOpImpl(deposit_confirmation::confirm(
+            op.0,
+            ic::caller(),
+            ic::time(),
+        )?);
+        state
+            .borrow_mut()
+            .operations
+            .update(operation_id, confirmed.clone());
+        runtime.borrow().schedule_operation(operation_id, confirmed);
+
+        log::info!("Deposit {operation_id} is confirmed by the user");
+
+        Ok(())
+    }
+
+    /// Aborts the deposit, waiting for the user confirmation. No tokens are burnt.
+    ///
+    /// This method is only for the deposit sender.
+    #[update]
+    pub fn abort_deposit(&mut self, operation_id: OperationId) -> BTFResult<()> {
+        self.config().borrow().check_primary_mode()?;
+        let state = get_runtime_state();
+        let op = state
+            .borrow()
+            .operations
+            .get(operation_id)
+            .ok_or(Error::OperationNotFound(operation_id))?;
+
+        let aborted = deposit_confirmation::abort(op.0, ic::caller(), ic::time())?;
+        state
+            .borrow_mut()
+            .operations
+            .update(operation_id, IcrcBridgeOpImpl(aborted));
+
+        log::info!("Deposit {operation_id} is aborted by the user");
+
+        Ok(())
+    }
+
+    /// Cancels the approval after mint of the deposit, so the wrapped tokens are minted
+    /// without the approval. The approval can be cancelled until the mint order is taken
+    /// for signing.
+    ///
+    /// This method is only for the deposit sender.
+    #[update]
+    pub fn cancel_approval(&mut self, operation_id: OperationId) -> BTFResult<()> {
+        self.config().borrow().check_primary_mode()?;
+        let state = get_runtime_state();
+        let op = state
+            .borrow()
+            .operations
+            .get(operation_id)
+            .ok_or(Error::OperationNotFound(operation_id))?;
+
+        let signing_delay_secs = *get_icrc_state()
+            .borrow()
+            .mint_order_signing_delay_secs
+            .get();
+        let updated =
+            approval_cancellation::cancel(op.0, ic::caller(), ic::time(), signing_delay_secs)?;
+        approval_cancellation::record(operation_id, ic::time());
+        state
+            .borrow_mut()
+            .operations
+            .update(operation_id, IcrcBridgeOpImpl(updated));
+
+        log::info!("Approval after mint of the deposit {operation_id} is cancelled by the user");
+
+        Ok(())
+    }
+
+    /// Returns maximum delay in seconds, which deposits can wait for the user confirmation.
+    #[query]
+    pub fn get_max_deposit_confirmation_delay_secs(&self) -> u64 {
+        *get_icrc_state()
+            .borrow()
+            .max_deposit_confirmation_delay_secs
+            .get()
+    }
+
+    /// Sets maximum delay in seconds, which deposits can wait for the user confirmation.
+    /// Longer requested delays are shortened to the maximum. If zero, all deposits are
+    /// burnt immediately.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_max_deposit_confirmation_delay_secs(&mut self, delay_secs: u64) -> BTFResult<()> {
+        inspect_check_is_owner(ic::caller())?;
+        get_icrc_state()
+            .borrow_mut()
+            .max_deposit_confirmation_delay_secs
+            .set(delay_secs)
+            .expect("failed to update deposit confirmation delay");
+
+        log::info!("Maximum deposit confirmation delay changed to {delay_secs} seconds");
+
+        Ok(())
+    }
+
+    /// Returns deposit fee in millionths of a token.
+    #[query]
+    pub fn get_deposit_fee(&self) -> u64 {
+        *get_icrc_state().borrow().deposit_fee.get()
+    }
+
+    /// Sets deposit fee in millionths of a token. Deposits with amount not greater than
+    /// the fee are rejected. If zero, any nonzero amount is accepted.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_deposit_fee(&mut self, fee: u64) -> BTFResult<()> {
+        inspect_check_is_owner(ic::caller())?;
+        get_icrc_state()
+            .borrow_mut()
+            .deposit_fee
+            .set(fee)
+            .expect("failed to update deposit fee");
+
+        log::info!("Deposit fee changed to {fee}");
+
+        Ok(())
+    }
+
+    /// Returns the bridge account, which deposit senders approve as the spender of the
+    /// `token`, with the ledger fee and the minimal deposit amount.
+    ///
+    /// The spender subaccount is derived from the deposit `recipient_address`. If the
+    /// deposit recipient is taken from the sender preferences, the zero address should be
+    /// used. The token configuration is read from the cache, so for the tokens, which were
+    /// not bridged yet, use the `get_deposit_allowance` call.
+    #[query]
+    pub fn get_deposit_account_info(
+        &self,
+        recipient_address: H160,
+        token: Principal,
+    ) -> BTFResult<DepositAccountInfo> {
+        let config = icrc1::get_cached_token_configuration(token).ok_or(Error::Custom {
+            code: ErrorCodes::IcrcMetadataRequestFailed as _,
+            msg: format!("configuration of token {token} is not cached"),
+        })?;
+
+        IcrcBridgeOpImpl::deposit_account_info(&recipient_address, &config)
+    }
+
+    /// Returns the allowance, which the caller should approve for the deposit spender
+    /// before the deposit of the `amount` of the `token`: the amount with the ledger fee.
+    ///
+    /// The token configuration is read from the cache, like in `get_deposit_account_info`.
+    #[query]
+    pub fn get_required_allowance(&self, token: Principal, amount: U256) -> BTFResult<U256> {
+        let config = icrc1::get_cached_token_configuration(token).ok_or(Error::Custom {
+            code: ErrorCodes::IcrcMetadataRequestFailed as _,
+            msg: format!("configuration of token {token} is not cached"),
+        })?;
+
+        IcrcBridgeOpImpl::required_allowance(&amount, &config)
+    }
+
+    /// Returns the deposit account info, like `get_deposit_account_info`, with the remaining
+    /// allowance of the caller account with the given subaccount for the spender.
+    #[update]
+    pub async fn get_deposit_allowance(
+        &self,
+        recipient_address: H160,
+        token: Principal,
+        from_subaccount: Option<Subaccount>,
+    ) -> BTFResult<DepositAllowance> {
+        let caller = ic::caller();
+        check_anonymous_principal(caller)?;
+
+        let config = icrc1::get_token_configuration(token)
+            .await
+            .map_err(|e| Error::Custom {
+                code: ErrorCodes::IcrcMetadataRequestFailed as _,
+                msg: format!("failed to get token configuration: {e}"),
+            })?;
+        let info = IcrcBridgeOpImpl::deposit_account_info(&recipient_address, &config)?;
+
+        let account = Account {
+            owner: caller,
+            subaccount: from_subaccount,
+        };
+        icrc2::deposit_allowance(token, account, info)
+            .await
+            .map_err(|e| Error::Custom {
+                code: ErrorCodes::IcrcAllowanceRequestFailed as _,
+                msg: format!("failed to query allowance: {e}"),
+            })
+    }
+
+    /// Returns delay in seconds between the deposit burn and the mint order signing.
+    #[query]
+    pub fn get_mint_order_signing_delay_secs(&self) -> u64 {
+        *get_icrc_state()
+            .borrow()
+            .mint_order_signing_delay_secs
+            .get()
+    }
+
+    /// Sets delay in seconds between the deposit burn and the mint order signing,
+    /// which makes the mint time less predictable for front-running.
+    /// If zero, mint orders are signed right after the burn.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_mint_order_signing_delay_secs(&mut self, delay_secs: u64) -> BTFResult<()> {
+        inspect_check_is_owner(ic::caller())?;
+        get_icrc_state()
+            .borrow_mut()
+            .mint_order_signing_delay_secs
+            .set(delay_secs)
+            .expect("failed to update mint order signing delay");
+
+        log::info!("Mint order signing delay changed to {delay_secs} seconds");
+
+        Ok(())
+    }
+
+    /// Returns handling of the approve after mint amounts, which exceed the minted amount.
+    #[query]
+    pub fn get_approve_amount_policy(&self) -> ApproveAmountPolicy {
+        *get_icrc_state().borrow().approve_amount_policy.get()
+    }
+
+    /// Sets handling of the approve after mint amounts, which exceed the minted amount:
+    /// the approve amount is either clamped to the minted amount, or the burn is rejected.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_approve_amount_policy(&mut self, policy: ApproveAmountPolicy) -> BTFResult<()> {
+        inspect_check_is_owner(ic::caller())?;
+        get_icrc_state()
+            .borrow_mut()
+            .approve_amount_policy
+            .set(policy)
+            .expect("failed to update approve amount policy");
+
+        log::info!("Approve amount policy changed to {policy:?}");
+
+        Ok(())
+    }
+
+    /// Returns time in seconds, which the spender has to claim the withdrawal, delivered
+    /// with an approval.
+    #[query]
+    pub fn get_approval_delivery_expiry_secs(&self) -> u64 {
+        *get_icrc_state()
+            .borrow()
+            .approval_delivery_expiry_secs
+            .get()
+    }
+
+    /// Sets time in seconds, which the spender has to claim the withdrawal, delivered
+    /// with an approval. Unclaimed tokens of the expired approval are swept back to the bridge,
+    /// and the burnt wrapped tokens are refunded. Applies to the newly granted approvals.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_approval_delivery_expiry_secs(&mut self, expiry_secs: u64) -> BTFResult<()> {
+        inspect_check_is_owner(ic::caller())?;
+        get_icrc_state()
+            .borrow_mut()
+            .approval_delivery_expiry_secs
+            .set(expiry_secs)
+            .expect("failed to update approval delivery expiry");
+
+        log::info!("Approval delivery expiry changed to {expiry_secs} seconds");
+
+        Ok(())
+    }
+
+    /// Adds the ICRC-2 token to the list of tokens, allowed to be bridged.
+    /// If the list is disabled, enables it, so only the added token is allowed.
+    ///
+    /// The token ledger is asked for its capabilities, and the token is rejected with
+    /// `UnsupportedLedger` error, if the ledger doesn't support ICRC-1 and ICRC-2 standards,
+    /// or with `InvalidBurnOperation` error, if the ledger supports ICRC-1 only.
+    /// The stored capabilities of the ledger are updated, so re-adding the token after
+    /// the ledger upgrade allows the deposits of the token again.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub async fn add_allowed_token(&mut self, token: Principal) -> BTFResult<()> {
+        inspect_check_is_owner(ic::caller())?;
+        capabilities::check_ledger(token, DEPOSIT_STANDARDS, true).await?;
+        get_icrc_state().borrow_mut().token_allow_list.add(token)?;
+
+        log::info!("Token {token} is added to the allow list");
+
+        Ok(())
+    }
+
+    /// Removes the ICRC-2 token from the list of tokens, allowed to be bridged.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn remove_allowed_token(&mut self, token: Principal) -> BTFResult<()> {
+        inspect_check_is_owner(ic::caller())?;
+        get_icrc_state()
+            .borrow_mut()
+            .token_allow_list
+            .remove(&token);
+
+        log::info!("Token {token} is removed from the allow list");
+
+        Ok(())
+    }
+
+    /// Disables the token allow list, so all the ICRC-2 tokens are allowed to be bridged.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn disable_token_allow_list(&mut self) -> BTFResult<()> {
+        inspect_check_is_owner(ic::caller())?;
+        get_icrc_state().borrow_mut().token_allow_list.disable();
+
+        log::info!("Token allow list is disabled");
+
+        Ok(())
+    }
+
+    /// Returns the ICRC-2 tokens, allowed to be bridged, or `None` if all tokens are allowed.
+    #[query]
+    pub fn get_allowed_tokens(&self) -> Option<Vec<Principal>> {
+        get_icrc_state().borrow().token_allow_list.get_all()
+    }
+
+    /// Returns decimals of the ICRC token and its wrapped token, tracked by the bridge,
+    /// with the detected decimals change of the ledger, if any.
+    #[query]
+    pub fn get_token_decimals(&self, token: Principal) -> Option<TokenDecimals> {
+        get_icrc_state().borrow().token_decimals.get(&token)
+    }
+
+    /// Resolves the detected decimals change of the ICRC token ledger and resumes
+    /// the operations with the token, held since the detection.
+    ///
+    /// With the `Rescale` strategy the wrapped token is kept, and the amounts are scaled
+    /// between the new ledger decimals and the wrapped token decimals. With the `Migrate`
+    /// strategy the wrapped token is retired, so a new wrapped token should be deployed.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn resolve_decimals_change(
+        &mut self,
+        token: Principal,
+        strategy: DecimalsChangeStrategy,
+    ) -> BTFResult<()> {
+        inspect_check_is_owner(ic::caller())?;
+        let resolved = get_icrc_state()
+            .borrow_mut()
+            .token_decimals
+            .resolve(token, strategy)?;
+
+        log::info!("Decimals change of token {token} is resolved with {strategy:?}: {resolved:?}");
+
+        let held = get_runtime_state()
+            .borrow()
+            .operations
+            .get_incomplete()
+            .into_iter()
+            .filter(|(_, op)| op.decimals_dependent_token() == Some(token))
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        let runtime = get_runtime();
+        for id in held {
+            runtime.borrow().reschedule_operation(id);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the supply guard configuration, or `None` if the guard is disabled.
+    #[query]
+    pub fn get_supply_guard_config(&self) -> Option<SupplyGuardConfig> {
+        get_icrc_state().borrow().supply_guard.get_config()
+    }
+
+    /// Sets the supply guard configuration. `None` disables the guard.
+    ///
+    /// The guard periodically compares the supply of the wrapped tokens with the ICRC tokens,
+    /// locked by the bridge, and halts the bridge if the supply exceeds the locked amount
+    /// by more than the tolerance. The halted bridge is resumed with `resume_bridge`.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_supply_guard_config(&mut self, config: Option<SupplyGuardConfig>) -> BTFResult<()> {
+        inspect_check_is_owner(ic::caller())?;
+        log::info!("Supply guard configuration changed to {config:?}");
+        get_icrc_state()
+            .borrow_mut()
+            .supply_guard
+            .set_config(config);
+
+        Ok(())
+    }
+
+    /// Returns the latest supply check of the supply guard.
+    #[query]
+    pub fn get_last_supply_check(&self) -> Option<SupplyCheck> {
+        get_icrc_state().borrow().supply_guard.last_check().cloned()
+    }
+
+    /// Returns the burn intent of the deposit operation, recorded before the ICRC tokens burn.
+    #[query]
+    pub fn get_burn_intent(&self, operation_id: OperationId) -> Option<BurnIntent> {
+        get_icrc_state().borrow().burn_intents.get(&operation_id)
+    }
+
+    /// Stores the deposit preferences of the caller, replacing the previous ones.
+    /// Deposits of the caller use the preferred recipient, fee payer and approve spender,
+    /// if the deposit doesn't specify them. The preferred addresses should not be zero.
+    #[update]
+    pub fn set_bridge_preferences(&mut self, preferences: BridgePreferences) -> BTFResult<()> {
+        get_icrc_state()
+            .borrow_mut()
+            .bridge_preferences
+            .set(ic::caller(), preferences)
+    }
+
+    /// Returns the deposit preferences of the caller.
+    #[query]
+    pub fn get_bridge_preferences(&self) -> Option<BridgePreferences> {
+        get_icrc_state()
+            .borrow()
+            .bridge_preferences
+            .get(&ic::caller())
+    }
+
+    /// Deletes the deposit preferences of the caller.
+    #[update]
+    pub fn delete_bridge_preferences(&mut self) -> BTFResult<()> {
+        let caller = ic::caller();
+        check_anonymous_principal(caller)?;
+        get_icrc_state()
+            .borrow_mut()
+            .bridge_preferences
+            .remove(&caller);
+
+        Ok(())
+    }
+
     /// Adds the provided principal to the whitelist.
     #[update]
     pub fn add_to_whitelist(&mut self, icrc2_principal: Principal) -> BTFResult<()> {
@@ -178,6 +1012,258 @@ impl Icrc2BridgeCanister {
         bridge_canister::build_data!()
     }
 
+    /// Returns current state of the operation nonces.
+    #[query]
+    pub fn get_nonce_state(&self) -> NonceState {
+        get_runtime_state().borrow().nonce_state()
+    }
+
+    /// Sets minimal id (and so nonce) of the operations created by the canister.
+    /// The operation id counter is never moved backwards.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_nonce_offset(&mut self, offset: u64) -> BTFResult<()> {
+        bridge_canister::inspect::inspect_set_nonce_offset(self.config());
+        get_runtime_state().borrow_mut().set_nonce_offset(offset);
+
+        log::info!("Bridge canister nonce offset changed to {offset}");
+        Ok(())
+    }
+
+    /// Returns the operation log bytes, as they are stored in stable memory, without decoding.
+    /// Intended to diagnose the operations which can't be decoded after an upgrade.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_operation_raw_bytes(&self, operation_id: OperationId) -> Option<Vec<u8>> {
+        bridge_canister::inspect::inspect_get_operation_raw_bytes(self.config());
+        get_runtime_state()
+            .borrow()
+            .operations
+            .get_raw_bytes(operation_id)
+    }
+
+    /// Replays the operation log steps through the operation transition graph and checks
+    /// that the result is consistent with the stored state of the operation. Returns the
+    /// description of the first inconsistency found.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn verify_operation_consistency(&self, operation_id: OperationId) -> Result<(), String> {
+        bridge_canister::inspect::inspect_verify_operation_consistency(self.config());
+        get_runtime_state()
+            .borrow()
+            .operations
+            .verify_consistency(operation_id)
+    }
+
+    /// Returns the captured progress invocations of the operation, oldest first.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_operation_replay(&self, operation_id: OperationId) -> Vec<ProgressReplayEntry> {
+        bridge_canister::inspect::inspect_get_operation_replay(self.config());
+        get_runtime_state()
+            .borrow()
+            .operation_replays
+            .get(operation_id)
+    }
+
+    /// Enables or disables the capture of the operation progress to the replay log,
+    /// regardless of the sampling. Already captured invocations are kept.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn set_operation_replay_capture(&mut self, operation_id: OperationId, enabled: bool) {
+        bridge_canister::inspect::inspect_set_operation_replay_capture(self.config());
+        get_runtime_state()
+            .borrow_mut()
+            .operation_replays
+            .set_capture(operation_id, enabled, ic::time());
+
+        log::info!("Replay capture of operation {operation_id} is set to {enabled}");
+    }
+
+    /// Returns the outbox messages, parked after too many failed delivery attempts, oldest first.
+    /// If `pagination` is `None`, only the oldest parked messages are returned, 1000 by default
+    /// (see `set_operations_list_default_limit`), so callers should paginate to get all of them.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_parked_outbox_messages(&self, pagination: Option<Pagination>) -> Vec<OutboxMessage> {
+        bridge_canister::inspect::inspect_get_parked_outbox_messages(self.config());
+        let default_limit = self.config().borrow().get_operations_list_default_limit();
+        let pagination = pagination.unwrap_or_else(|| Pagination::new(0, default_limit as usize));
+        get_runtime_state()
+            .borrow()
+            .operations
+            .outbox()
+            .get_parked(pagination.offset, pagination.count)
+    }
+
+    /// Moves the parked outbox message back to the delivery queue.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn requeue_outbox_message(&mut self, id: u64) -> BTFResult<()> {
+        bridge_canister::inspect::inspect_requeue_outbox_message(self.config());
+        get_runtime_state()
+            .borrow_mut()
+            .operations
+            .outbox_mut()
+            .requeue(id, ic::time())?;
+
+        log::info!("Parked outbox message {id} is requeued");
+        Ok(())
+    }
+
+    /// Removes the parked outbox message without delivery.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub fn drop_outbox_message(&mut self, id: u64) -> BTFResult<()> {
+        bridge_canister::inspect::inspect_drop_outbox_message(self.config());
+        get_runtime_state()
+            .borrow_mut()
+            .operations
+            .outbox_mut()
+            .drop_parked(id)?;
+
+        log::info!("Parked outbox message {id} is dropped");
+        Ok(())
+    }
+
+    /// Returns the burnt events, rejected by the strict event validation, oldest first.
+    /// Only the most recent 10000 rejected events are kept. If `pagination` is `None`,
+    /// only the oldest kept events are returned, 1000 by default
+    /// (see `set_operations_list_default_limit`), so callers should paginate to get all of them.
+    ///
+    /// This method is only for canister owner.
+    #[query]
+    pub fn get_dead_letter_events(&self, pagination: Option<Pagination>) -> Vec<DeadLetterEvent> {
+        bridge_canister::inspect::inspect_get_dead_letter_events(self.config());
+        let default_limit = self.config().borrow().get_operations_list_default_limit();
+        let pagination = pagination.unwrap_or_else(|| Pagination::new(0, default_limit as usize));
+        get_runtime_state()
+            .borrow()
+            .dead_letters
+            .get(pagination.offset, pagination.count)
+    }
+
+    /// Returns the blocks of the operations log in the ICRC-3 `get_blocks` format.
+    /// Every block records one state transition of an operation and contains the hash
+    /// of the previous block. At most 100 blocks are returned per request.
+    #[query]
+    pub fn get_operation_blocks(&self, start: Nat, length: Nat) -> GetBlocksResult {
+        get_runtime_state()
+            .borrow()
+            .operations
+            .blocks()
+            .get_blocks(&start, &length)
+    }
+
+    /// Returns up to `length` blocks of the operations log, starting from the `start` index,
+    /// in the format imported by the replica canisters. At most 100 blocks are returned
+    /// per request.
+    #[query]
+    pub fn export_operation_blocks(&self, start: u64, length: u64) -> OperationBlocksExport {
+        get_runtime_state()
+            .borrow()
+            .operations
+            .blocks()
+            .export(start, length)
+    }
+
+    /// Returns the sync status of the replica canister with its primary canister,
+    /// or `None` if the canister is the primary.
+    #[query]
+    pub fn get_replica_sync_status(&self) -> Option<ReplicaSyncStatus> {
+        get_runtime_state().borrow().replica_sync_status()
+    }
+
+    /// Promotes the replica canister to the primary mode, if the primary canister is halted
+    /// or doesn't respond. Operation blocks of the primary are synced before the promotion.
+    ///
+    /// This method is only for canister owner.
+    #[update]
+    pub async fn promote_to_primary(&mut self) -> BTFResult<()> {
+        bridge_canister::inspect::inspect_promote_to_primary(self.config());
+        BridgeRuntime::promote_to_primary(get_runtime()).await
+    }
+
+    /// Returns counters of the BTF bridge events, which were rejected before processing,
+    /// e.g. events of the previous BTF bridge contract. Counters are reset on upgrade.
+    #[query]
+    pub fn get_rejected_events_stats(&self) -> RejectedEventsStats {
+        get_runtime_state()
+            .borrow()
+            .processed_events
+            .rejected_stats()
+    }
+
+    /// Returns the sizes of the scheduler task payloads, moved to the task payloads store
+    /// because they exceed the inline size limit. Stats are updated on each scheduler run
+    /// and reset on upgrade.
+    #[query]
+    pub fn get_task_payload_stats(&self) -> TaskPayloadStats {
+        get_runtime_state().borrow().task_payloads.stats()
+    }
+
+    /// Returns the number of the operations per direction, deferred because the direction
+    /// concurrency limit is reached. Counters are reset on upgrade.
+    #[query]
+    pub fn get_deferred_operations_stats(&self) -> DeferredOperationsStats {
+        get_runtime_state().borrow().concurrency.deferred_stats()
+    }
+
+    /// Returns the BTF bridge events of the user with the given EVM address, which were
+    /// processed by the bridge, ordered by block. Events are kept for the same number of
+    /// blocks as the processed events deduplication index.
+    /// If `pagination` is `None`, all the stored events are returned.
+    #[query]
+    pub fn get_processed_events(
+        &self,
+        address: H160,
+        pagination: Option<Pagination>,
+    ) -> Vec<ProcessedEventInfo> {
+        get_runtime_state()
+            .borrow()
+            .processed_events
+            .get_for_sender(&address, pagination)
+    }
+
+    /// Returns the adjustments of the mint fees, paid by the given EVM address, to the actual
+    /// cost of the mint transactions, with the totals of the credited and debited amounts.
+    /// The number of the returned adjustments is capped, so a long history is read page by page.
+    #[query]
+    pub fn get_fee_adjustments(
+        &self,
+        fee_payer: H160,
+        pagination: Option<Pagination>,
+    ) -> FeePayerAdjustments {
+        get_runtime_state()
+            .borrow()
+            .fee_adjustments
+            .get_for_payer(&fee_payer, pagination)
+    }
+
+    /// Returns ICRC-21 consent message for the `burn_icrc2` call, which burns `Icrc2Burn`
+    /// tokens to mint them on EVM.
+    #[update]
+    pub async fn icrc21_canister_call_consent_message(
+        &self,
+        request: ConsentMessageRequest,
+    ) -> Result<ConsentInfo, Icrc21Error> {
+        consent::consent_message(request).await
+    }
+
+    /// Returns the list of ICRC standards, supported by the canister.
+    #[query]
+    pub fn icrc10_supported_standards(&self) -> Vec<StandardRecord> {
+        vec![StandardRecord::icrc21()]
+    }
+
     /// Returns candid IDL.
     /// This should be the last fn to see previous endpoints in macro.
     pub fn idl() -> Idl {
@@ -217,6 +1303,26 @@ fn check_anonymous_principal(principal: Principal) -> BTFResult<()> {
     Ok(())
 }
 
+/// Erases the user data of the `identity` from the operations and the bridge state.
+fn erase_user_data(
+    identity: &UserIdentity,
+    salt: &[u8],
+    caller: Principal,
+) -> BTFResult<UserDataErasure> {
+    let erasure = get_runtime()
+        .borrow()
+        .anonymize_user_data(identity, salt, caller, ic::time())?;
+
+    if let UserIdentity::Principal(principal) = identity {
+        get_icrc_state()
+            .borrow_mut()
+            .bridge_preferences
+            .remove(principal);
+    }
+
+    Ok(erasure)
+}
+
 fn init_runtime() -> SharedRuntime {
     let runtime = BridgeRuntime::default(ConfigStorage::get());
     let state = runtime.state().clone();
@@ -225,15 +1331,54 @@ fn init_runtime() -> SharedRuntime {
     let config = state.borrow().config.clone();
 
     let refresh_params_service = RefreshEvmParamsService::new(config.clone());
+    let gas_balance_service = ServiceTimer::new(
+        MonitorGasBalanceService::new(config.clone()),
+        GAS_BALANCE_QUERY_DELAY,
+    );
+    let ping_evm_service = ServiceTimer::new(PingEvmService::new(config.clone()), PING_EVM_DELAY);
+    let outbox_service = ServiceTimer::new(
+        DispatchOutboxService::new(state.clone()),
+        OUTBOX_DISPATCH_DELAY,
+    );
+    let migrate_records_service = ServiceTimer::new(
+        MigrateRecordsService::new(state.clone(), scheduler.clone()),
+        MIGRATE_RECORDS_DELAY,
+    );
+    let supply_guard_service =
+        ServiceTimer::new(SupplyGuardService::new(config.clone()), SUPPLY_GUARD_DELAY);
 
     let fetch_btf_events_service =
         FetchBtfBridgeEventsService::new(IcrcEventsHandler, runtime.clone(), config);
 
-    let sign_orders_handler = IcrcMintOrderHandler::new(state.clone(), scheduler);
+    let sign_orders_handler = IcrcMintOrderHandler::new(state.clone(), scheduler.clone());
     let sign_mint_orders_service = SignMintOrdersService::new(sign_orders_handler);
 
     let mint_tx_handler = IcrcMintTxHandler::new(state.clone());
-    let mint_tx_service = SendMintTxService::new(mint_tx_handler);
+    let mint_tx_service = SendMintTxService::new(
+        mint_tx_handler,
+        memory_by_id(PENDING_MINT_BATCHES_MEMORY_ID),
+    );
+
+    let dropped_mint_tx_handler = IcrcDroppedMintTxHandler::new(state.clone(), scheduler.clone());
+    let dropped_mint_tx_service = ServiceTimer::new(
+        CheckDroppedMintTxService::new(dropped_mint_tx_handler),
+        CHECK_DROPPED_TX_DELAY,
+    );
+
+    let expire_deposits_service = ServiceTimer::new(
+        ExpirePendingDepositsService::new(state.clone()),
+        EXPIRE_DEPOSITS_DELAY,
+    );
+
+    let deposit_bundles_service = ServiceTimer::new(
+        DepositBundlesService::new(state.clone(), scheduler.clone()),
+        DEPOSIT_BUNDLES_DELAY,
+    );
+
+    let sweep_approvals_service = ServiceTimer::new(
+        SweepApprovalsService::new(state.clone(), scheduler),
+        SWEEP_APPROVALS_DELAY,
+    );
 
     let services = state.borrow().services.clone();
     services.borrow_mut().add_service(
@@ -256,6 +1401,51 @@ fn init_runtime() -> SharedRuntime {
         SEND_MINT_TX_SERVICE_ID,
         Rc::new(mint_tx_service),
     );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        MONITOR_GAS_BALANCE_SERVICE_ID,
+        Rc::new(gas_balance_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        PING_EVM_SERVICE_ID,
+        Rc::new(ping_evm_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        DISPATCH_OUTBOX_SERVICE_ID,
+        Rc::new(outbox_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        EXPIRE_PENDING_DEPOSITS_SERVICE_ID,
+        Rc::new(expire_deposits_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        CHECK_DROPPED_MINT_TX_SERVICE_ID,
+        Rc::new(dropped_mint_tx_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        SUPPLY_GUARD_SERVICE_ID,
+        Rc::new(supply_guard_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        DEPOSIT_BUNDLES_SERVICE_ID,
+        Rc::new(deposit_bundles_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        SWEEP_APPROVALS_SERVICE_ID,
+        Rc::new(sweep_approvals_service),
+    );
+    services.borrow_mut().add_service(
+        ServiceOrder::ConcurrentWithOperations,
+        MIGRATE_RECORDS_SERVICE_ID,
+        Rc::new(migrate_records_service),
+    );
 
     runtime
 }
@@ -280,13 +1470,19 @@ pub fn get_icrc_state() -> Rc<RefCell<IcrcState>> {
 
 #[cfg(test)]
 mod test {
+    use bridge_did::bridge_mode::BridgeMode;
+    use bridge_did::events::BurntEventData;
     use bridge_did::evm_link::EvmLink;
+    use bridge_did::id256::Id256;
+    use bridge_did::order::{MintOrder, SignedOrders, SignedOrdersData, SIGNATURE_LEN};
     use candid::Principal;
+    use did::H256;
     use eth_signer::sign_strategy::SigningStrategy;
     use ic_canister::{canister_call, Canister};
     use ic_exports::ic_kit::{inject, MockContext};
 
     use super::*;
+    use crate::constant::MAX_BURN_BATCH_SIZE;
     use crate::Icrc2BridgeCanister;
 
     fn owner() -> Principal {
@@ -294,6 +1490,10 @@ mod test {
     }
 
     async fn init_canister() -> Icrc2BridgeCanister {
+        init_canister_with_mode(None).await
+    }
+
+    async fn init_canister_with_mode(mode: Option<BridgeMode>) -> Icrc2BridgeCanister {
         MockContext::new().inject();
 
         const MOCK_PRINCIPAL: &str = "mfufu-x6j4c-gomzb-geilq";
@@ -307,6 +1507,9 @@ mod test {
                 private_key: [1u8; 32],
             },
             log_settings: None,
+            evm_finality: None,
+            nonce_offset: None,
+            mode,
         };
         canister_call!(canister.init(init_data), ()).await.unwrap();
         canister
@@ -359,4 +1562,440 @@ mod test {
 
         assert!(whitelist.is_empty());
     }
+
+    #[tokio::test]
+    async fn decimals_change_is_resolved_by_owner() {
+        let mut canister = init_canister().await;
+        let token = Principal::from_slice(&[5; 29]);
+        let wrapped = H160::from_slice(&[6; 20]);
+
+        inject::get_context().update_id(owner());
+        let result = canister_call!(
+            canister.resolve_decimals_change(token, DecimalsChangeStrategy::Rescale),
+            BTFResult<()>
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, Err(Error::DecimalsChangeNotDetected(token)));
+
+        {
+            let state = get_icrc_state();
+            let mut state = state.borrow_mut();
+            state
+                .token_decimals
+                .observe_deposit(token, 8, &wrapped, 0)
+                .unwrap();
+            state
+                .token_decimals
+                .observe_deposit(token, 12, &wrapped, 0)
+                .unwrap_err();
+        }
+
+        inject::get_context().update_id(Principal::from_slice(&[3; 29]));
+        let result = canister_call!(
+            canister.resolve_decimals_change(token, DecimalsChangeStrategy::Migrate),
+            BTFResult<()>
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, Err(Error::AccessDenied));
+
+        inject::get_context().update_id(owner());
+        canister_call!(
+            canister.resolve_decimals_change(token, DecimalsChangeStrategy::Migrate),
+            BTFResult<()>
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        let decimals = canister_call!(canister.get_token_decimals(token), Option<TokenDecimals>)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(decimals.ledger_decimals, 12);
+        assert_eq!(decimals.wrapped_decimals, 12);
+        assert_eq!(decimals.pending_change, None);
+        assert_eq!(
+            decimals.retired(&wrapped).map(|retired| retired.decimals),
+            Some(8)
+        );
+    }
+
+    #[tokio::test]
+    async fn bridge_preferences_are_set_per_caller_and_deleted() {
+        let mut canister = init_canister().await;
+        let user = Principal::from_slice(&[3; 29]);
+        let preferences = BridgePreferences {
+            recipient_address: Some(H160::from_slice(&[4; 20])),
+            fee_payer: None,
+            approve_spender: None,
+        };
+
+        inject::get_context().update_id(user);
+        canister_call!(
+            canister.set_bridge_preferences(preferences.clone()),
+            BTFResult<()>
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let stored = canister_call!(canister.get_bridge_preferences(), Option<BridgePreferences>)
+            .await
+            .unwrap();
+        assert_eq!(stored, Some(preferences.clone()));
+
+        // Preferences of other users are not visible.
+        inject::get_context().update_id(owner());
+        let stored = canister_call!(canister.get_bridge_preferences(), Option<BridgePreferences>)
+            .await
+            .unwrap();
+        assert_eq!(stored, None);
+
+        // Invalid preferences don't replace the stored ones.
+        inject::get_context().update_id(user);
+        let invalid = BridgePreferences {
+            fee_payer: Some(H160::zero()),
+            ..preferences.clone()
+        };
+        let result = canister_call!(canister.set_bridge_preferences(invalid), BTFResult<()>)
+            .await
+            .unwrap();
+        assert!(matches!(result, Err(Error::InvalidBridgePreferences(_))));
+
+        canister_call!(canister.delete_bridge_preferences(), BTFResult<()>)
+            .await
+            .unwrap()
+            .unwrap();
+        let stored = canister_call!(canister.get_bridge_preferences(), Option<BridgePreferences>)
+            .await
+            .unwrap();
+        assert_eq!(stored, None);
+    }
+
+    #[tokio::test]
+    async fn erased_principal_preferences_are_removed() {
+        let mut canister = init_canister().await;
+        let user = Principal::from_slice(&[3; 29]);
+        let preferences = BridgePreferences {
+            recipient_address: Some(H160::from_slice(&[4; 20])),
+            fee_payer: None,
+            approve_spender: None,
+        };
+
+        inject::get_context().update_id(user);
+        canister_call!(canister.set_bridge_preferences(preferences), BTFResult<()>)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let erasure = erase_user_data(&UserIdentity::Principal(user), &[9; 32], owner()).unwrap();
+        assert!(erasure.operations.is_empty());
+
+        let stored = canister_call!(canister.get_bridge_preferences(), Option<BridgePreferences>)
+            .await
+            .unwrap();
+        assert_eq!(stored, None);
+
+        let audit = get_runtime_state()
+            .borrow()
+            .operator_audit
+            .get(erasure.audit_entry as _, 1);
+        assert_eq!(audit[0].caller, owner());
+        assert_eq!(
+            audit[0].note,
+            Some(format!("user data erased, pseudonym {}", erasure.pseudonym))
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn anonymize_user_data_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let identity = UserIdentity::Address(H160::from_slice(&[4; 20]));
+        let _ = canister_call!(
+            canister.anonymize_user_data(identity),
+            BTFResult<UserDataErasure>
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn mint_tx_request_is_returned_only_for_orders_awaiting_user() {
+        let canister = init_canister().await;
+
+        let state = get_runtime_state();
+        let bridge = H160::from_slice(&[3; 20]);
+        state
+            .borrow()
+            .config
+            .borrow_mut()
+            .set_btf_bridge_contract(bridge.clone());
+        state
+            .borrow()
+            .config
+            .borrow_mut()
+            .update_evm_params(|p| p.chain_id = 5);
+
+        let order = SignedOrders::new(
+            SignedOrdersData {
+                orders_data: vec![1; MintOrder::ENCODED_DATA_SIZE],
+                signature: vec![2; SIGNATURE_LEN],
+            },
+            0,
+        )
+        .unwrap();
+        let new_operation = |op| {
+            state
+                .borrow_mut()
+                .operations
+                .new_operation(IcrcBridgeOpImpl(op), None)
+        };
+        let awaiting_user = new_operation(IcrcBridgeOp::ConfirmMint {
+            order: order.clone(),
+            tx_hash: None,
+            is_refund: false,
+            pending_since: 0,
+        });
+        let sent_by_canister = new_operation(IcrcBridgeOp::ConfirmMint {
+            order: order.clone(),
+            tx_hash: Some(H256::from_slice(&[4; 32])),
+            is_refund: false,
+            pending_since: 0,
+        });
+        let to_be_sent = new_operation(IcrcBridgeOp::SendMintTransaction {
+            order,
+            is_refund: false,
+            pending_since: 0,
+        });
+
+        let request = canister_call!(
+            canister.get_mint_tx_request(awaiting_user),
+            BTFResult<EvmTxRequest>
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(request.to, bridge);
+        assert_eq!(request.chain_id, 5);
+
+        for id in [sent_by_canister, to_be_sent] {
+            let result = canister_call!(canister.get_mint_tx_request(id), BTFResult<EvmTxRequest>)
+                .await
+                .unwrap();
+            assert_eq!(result, Err(Error::MintOrderNotAwaitingUser(id)));
+        }
+
+        let unknown = OperationId::new(1000);
+        let result = canister_call!(
+            canister.get_mint_tx_request(unknown),
+            BTFResult<EvmTxRequest>
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, Err(Error::OperationNotFound(unknown)));
+    }
+
+    #[tokio::test]
+    async fn operations_are_filtered_by_src_token() {
+        let canister = init_canister().await;
+
+        let token = Principal::from_slice(&[5; 29]);
+        let other_token = Principal::from_slice(&[6; 29]);
+        let burn = |token| IcrcBridgeOp::BurnIcrc2Tokens {
+            burn: Icrc2Burn {
+                sender: Principal::from_slice(&[7; 29]),
+                amount: 1000u64.into(),
+                icrc2_token_principal: token,
+                erc20_token_address: H160::from_slice(&[8; 20]),
+                from_subaccount: None,
+                recipient_address: H160::from_slice(&[9; 20]),
+                approve_after_mint: None,
+                fee_payer: None,
+                confirmation_delay_secs: None,
+                preference_fields: None,
+            },
+            pending_since: 0,
+        };
+        let withdraw = |token: Principal| IcrcBridgeOp::MintIcrcTokens {
+            event: BurntEventData {
+                to_token: Id256::from(token).0.to_vec(),
+                ..Default::default()
+            },
+            pending_since: 0,
+        };
+
+        let state = get_runtime_state();
+        let new_operation = |op| {
+            state
+                .borrow_mut()
+                .operations
+                .new_operation(IcrcBridgeOpImpl(op), None)
+        };
+        let deposit = new_operation(burn(token));
+        new_operation(burn(other_token));
+        let withdrawal = new_operation(withdraw(token));
+        new_operation(withdraw(other_token));
+
+        // The token is found in the operation log after the current state loses it.
+        state.borrow_mut().operations.update(
+            withdrawal,
+            IcrcBridgeOpImpl(IcrcBridgeOp::IcrcMintFailed {
+                src_address: H160::default(),
+                reason: "test".into(),
+            }),
+        );
+
+        let operations = canister_call!(
+            canister.get_operations_by_src_token(token, None),
+            Vec<(OperationId, IcrcBridgeOp)>
+        )
+        .await
+        .unwrap();
+        let ids: Vec<_> = operations.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![deposit, withdrawal]);
+
+        let page = canister_call!(
+            canister.get_operations_by_src_token(token, Some(Pagination::new(1, 1))),
+            Vec<(OperationId, IcrcBridgeOp)>
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].0, withdrawal);
+
+        let unknown = canister_call!(
+            canister.get_operations_by_src_token(Principal::from_slice(&[10; 29]), None),
+            Vec<(OperationId, IcrcBridgeOp)>
+        )
+        .await
+        .unwrap();
+        assert!(unknown.is_empty());
+    }
+
+    #[tokio::test]
+    async fn burn_batch_returns_result_per_burn() {
+        let mut canister = init_canister().await;
+
+        let sender = Principal::from_slice(&[7; 29]);
+        let burn = Icrc2Burn {
+            sender,
+            amount: 1000u64.into(),
+            icrc2_token_principal: Principal::from_slice(&[5; 29]),
+            erc20_token_address: H160::from_slice(&[8; 20]),
+            from_subaccount: None,
+            recipient_address: H160::from_slice(&[9; 20]),
+            approve_after_mint: None,
+            fee_payer: None,
+            confirmation_delay_secs: None,
+            preference_fields: None,
+        };
+        let reasons = vec![
+            burn.clone(),
+            Icrc2Burn {
+                sender: Principal::from_slice(&[10; 29]),
+                ..burn.clone()
+            },
+            Icrc2Burn {
+                amount: 0u64.into(),
+                ..burn.clone()
+            },
+            Icrc2Burn {
+                recipient_address: H160::from_slice(&[11; 20]),
+                ..burn.clone()
+            },
+        ];
+
+        inject::get_context().update_id(sender);
+        let results = canister_call!(
+            canister.burn_icrc2_batch(reasons),
+            BTFResult<Vec<BTFResult<OperationId>>>
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[1], Err(Error::AccessDenied));
+        assert!(matches!(results[2], Err(Error::InvalidBurnAmount(_))));
+
+        let state = get_runtime_state();
+        for (result, recipient) in [(&results[0], [9; 20]), (&results[3], [11; 20])] {
+            let id = result.clone().unwrap();
+            let op = state.borrow().operations.get(id).unwrap();
+            assert!(matches!(
+                op.0,
+                IcrcBridgeOp::BurnIcrc2Tokens { burn, .. }
+                    if burn.recipient_address == H160::from_slice(&recipient)
+            ));
+        }
+
+        let too_large = vec![burn.clone(); MAX_BURN_BATCH_SIZE as usize + 1];
+        let result = canister_call!(
+            canister.burn_icrc2_batch(too_large),
+            BTFResult<Vec<BTFResult<OperationId>>>
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, Err(Error::BurnBatchTooLarge(MAX_BURN_BATCH_SIZE)));
+
+        inject::get_context().update_id(Principal::anonymous());
+        let result = canister_call!(
+            canister.burn_icrc2_batch(vec![burn]),
+            BTFResult<Vec<BTFResult<OperationId>>>
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, Err(Error::AnonymousPrincipal));
+    }
+
+    #[tokio::test]
+    async fn fund_moving_methods_are_disabled_in_replica_mode() {
+        let primary = Principal::from_slice(&[3; 29]);
+        let mut canister = init_canister_with_mode(Some(BridgeMode::Replica { primary })).await;
+
+        let result = canister_call!(
+            canister.burn_icrc2_batch(vec![]),
+            BTFResult<Vec<BTFResult<OperationId>>>
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, Err(Error::ReplicaMode));
+
+        let result = canister_call!(canister.confirm_deposit(OperationId::new(0)), BTFResult<()>)
+            .await
+            .unwrap();
+        assert_eq!(result, Err(Error::ReplicaMode));
+
+        let status = canister_call!(
+            canister.get_replica_sync_status(),
+            Option<ReplicaSyncStatus>
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(status.primary, primary);
+        assert_eq!(status.synced_blocks, 0);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "The method is disabled in the replica mode")]
+    async fn bulk_actions_are_disabled_in_replica_mode() {
+        let primary = Principal::from_slice(&[3; 29]);
+        let mut canister = init_canister_with_mode(Some(BridgeMode::Replica { primary })).await;
+
+        inject::get_context().update_id(owner());
+        let _ = canister_call!(
+            canister.bulk_retry(OperationFilter::default(), None),
+            BulkActionResult
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Running this method is only allowed for the owner of the canister")]
+    async fn promote_to_primary_rejected_for_non_owner() {
+        let mut canister = init_canister().await;
+        let _ = canister_call!(canister.promote_to_primary(), BTFResult<()>).await;
+    }
 }
@@ -1,10 +1,12 @@
 use bridge_canister::bridge_inspect;
 use bridge_did::error::BTFResult;
+use bridge_did::reason::Icrc2Burn;
 use candid::Principal;
 use ic_exports::ic_cdk;
 use ic_exports::ic_cdk::{api, inspect_message};
 use ic_exports::ic_kit::ic;
 
+use crate::ops::{burn_batch, deposit_bundle};
 use crate::Icrc2BridgeCanister;
 
 #[inspect_message]
@@ -25,6 +27,53 @@ async fn inspect_method(method: &str) -> BTFResult<()> {
             let (principal,) = api::call::arg_data::<(Principal,)>(Default::default());
             Icrc2BridgeCanister::access_control_inspect_message_check(ic::caller(), principal)
         }
+        "burn_icrc2_batch" => {
+            super::check_anonymous_principal(ic::caller())?;
+            let (reasons,) = api::call::arg_data::<(Vec<Icrc2Burn>,)>(Default::default());
+            burn_batch::check_batch_size(reasons.len())
+        }
+        "create_deposit_bundle" => {
+            super::check_anonymous_principal(ic::caller())?;
+            let (reasons,) = api::call::arg_data::<(Vec<Icrc2Burn>,)>(Default::default());
+            deposit_bundle::check_bundle(&reasons)
+        }
+        "confirm_deposit"
+        | "abort_deposit"
+        | "cancel_approval"
+        | "set_bridge_preferences"
+        | "delete_bridge_preferences"
+        | "get_deposit_allowance" => super::check_anonymous_principal(ic::caller()),
+        "set_max_deposit_confirmation_delay_secs"
+        | "set_deposit_fee"
+        | "set_mint_order_signing_delay_secs"
+        | "set_approval_delivery_expiry_secs"
+        | "add_allowed_token"
+        | "remove_allowed_token"
+        | "disable_token_allow_list"
+        | "resolve_decimals_change"
+        | "set_supply_guard_config"
+        | "set_approve_amount_policy" => super::inspect_check_is_owner(ic::caller()),
         _ => Ok(()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::error::Error;
+    use ic_exports::ic_kit::{inject, MockContext};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn anonymous_caller_cannot_confirm_or_abort_deposit() {
+        MockContext::new().inject();
+        inject::get_context().update_id(Principal::anonymous());
+
+        for method in ["confirm_deposit", "abort_deposit"] {
+            assert_eq!(inspect_method(method).await, Err(Error::AnonymousPrincipal));
+        }
+
+        inject::get_context().update_id(Principal::from_slice(&[3; 20]));
+        assert_eq!(inspect_method("confirm_deposit").await, Ok(()));
+    }
+}
@@ -0,0 +1,231 @@
+//! ICRC-21 consent messages for the bridge canister calls.
+
+use bridge_did::amount::{format_amount, format_nat_amount};
+use bridge_did::icrc21::{
+    ConsentInfo, ConsentMessage, ConsentMessageMetadata, ConsentMessageRequest, Icrc21Error,
+};
+use bridge_did::reason::Icrc2Burn;
+use candid::Decode;
+
+use crate::tokens::icrc1::{self, TokenConfiguration};
+
+/// Name of the call, which burns ICRC-2 tokens to mint them on EVM.
+pub const BURN_ICRC2_METHOD: &str = "burn_icrc2";
+
+/// Language of the consent messages.
+const CONSENT_LANGUAGE: &str = "en";
+
+/// Creates a consent message for the requested call.
+pub async fn consent_message(request: ConsentMessageRequest) -> Result<ConsentInfo, Icrc21Error> {
+    if request.method != BURN_ICRC2_METHOD {
+        return Err(Icrc21Error::unsupported_call(format!(
+            "consent message for method `{}` is not supported",
+            request.method
+        )));
+    }
+
+    let burn = Decode!(&request.arg, Icrc2Burn)
+        .map_err(|e| Icrc21Error::unavailable(format!("failed to decode Icrc2Burn: {e}")))?;
+    let token = icrc1::get_token_configuration(burn.icrc2_token_principal)
+        .await
+        .map_err(|e| Icrc21Error::unavailable(format!("failed to get token configuration: {e}")))?;
+
+    Ok(burn_consent_info(&burn, &token, &request))
+}
+
+/// Creates a consent message for the given burn, formatted as requested.
+fn burn_consent_info(
+    burn: &Icrc2Burn,
+    token: &TokenConfiguration,
+    request: &ConsentMessageRequest,
+) -> ConsentInfo {
+    let text = burn_consent_text(burn, token);
+    let spec = request.user_preferences.device_spec.as_ref();
+
+    ConsentInfo {
+        consent_message: ConsentMessage::format(&text, spec),
+        metadata: ConsentMessageMetadata {
+            language: CONSENT_LANGUAGE.into(),
+            utc_offset_minutes: request.user_preferences.metadata.utc_offset_minutes,
+        },
+    }
+}
+
+/// Human-readable description of the burn with all its parameters.
+fn burn_consent_text(burn: &Icrc2Burn, token: &TokenConfiguration) -> String {
+    let symbol = &token.info.symbol;
+    let decimals = token.info.decimals;
+    let amount = format_amount(&burn.amount, decimals);
+    let fee = format_nat_amount(&token.fee, decimals);
+
+    let mut text = format!(
+        "Bridge {amount} {symbol} tokens from {} to EVM address {:#x}. Ledger fee: {fee} {symbol}.",
+        burn.icrc2_token_principal, burn.recipient_address.0,
+    );
+
+    text.push_str(&format!("\nSender: {}", burn.sender));
+    if let Some(subaccount) = &burn.from_subaccount {
+        text.push_str(&format!("\nFrom subaccount: 0x{}", hex_bytes(subaccount)));
+    }
+    text.push_str(&format!(
+        "\nWrapped ERC-20 token: {:#x}",
+        burn.erc20_token_address.0
+    ));
+    match &burn.fee_payer {
+        Some(fee_payer) => text.push_str(&format!("\nMint fee payer: {:#x}", fee_payer.0)),
+        None => text.push_str("\nMint transaction will be sent by the recipient."),
+    }
+    if let Some(delay_secs) = burn.confirmation_delay_secs.filter(|delay| *delay > 0) {
+        text.push_str(&format!(
+            "\nTokens are burnt only after your confirmation, given within {delay_secs} seconds."
+        ));
+    }
+    if let Some(approve) = &burn.approve_after_mint {
+        text.push_str(&format!(
+            "\nApprove {} wrapped tokens to {:#x} after mint.",
+            format_amount(&approve.approve_amount, decimals),
+            approve.approve_spender.0,
+        ));
+    }
+
+    text
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::icrc21::{ConsentMessageSpec, DisplayMessageType};
+    use bridge_did::reason::ApproveAfterMint;
+    use candid::{Encode, Nat, Principal};
+    use did::H160;
+    use icrc_client::account::Account;
+
+    use super::*;
+    use crate::tokens::icrc1::TokenInfo;
+
+    fn token() -> TokenConfiguration {
+        TokenConfiguration {
+            principal: Principal::from_slice(&[1; 10]),
+            fee: Nat::from(10_000u64),
+            minting_account: Account {
+                owner: Principal::management_canister(),
+                subaccount: None,
+            },
+            info: TokenInfo {
+                name: "Test Token".into(),
+                symbol: "TKN".into(),
+                decimals: 8,
+            },
+            max_memo_length: 32,
+        }
+    }
+
+    fn burn() -> Icrc2Burn {
+        Icrc2Burn {
+            sender: Principal::from_slice(&[2; 29]),
+            amount: 150_000_000u64.into(),
+            icrc2_token_principal: token().principal,
+            erc20_token_address: H160::from_slice(&[3; 20]),
+            from_subaccount: Some([4; 32]),
+            recipient_address: H160::from_slice(&[5; 20]),
+            approve_after_mint: Some(ApproveAfterMint {
+                approve_spender: H160::from_slice(&[6; 20]),
+                approve_amount: 100_000_000u64.into(),
+                permit: None,
+            }),
+            fee_payer: Some(H160::from_slice(&[7; 20])),
+            confirmation_delay_secs: Some(300),
+            preference_fields: None,
+        }
+    }
+
+    fn request(device_spec: Option<DisplayMessageType>) -> ConsentMessageRequest {
+        ConsentMessageRequest {
+            method: BURN_ICRC2_METHOD.into(),
+            arg: Encode!(&burn()).unwrap(),
+            user_preferences: ConsentMessageSpec {
+                metadata: ConsentMessageMetadata {
+                    language: "en".into(),
+                    utc_offset_minutes: Some(60),
+                },
+                device_spec,
+            },
+        }
+    }
+
+    #[test]
+    fn burn_consent_message_contains_all_fields() {
+        let burn = burn();
+        let info = burn_consent_info(&burn, &token(), &request(None));
+
+        let ConsentMessage::GenericDisplayMessage(text) = info.consent_message else {
+            panic!("unexpected message type: {:?}", info.consent_message);
+        };
+        let expected_parts = [
+            "Bridge 1.5 TKN tokens".to_string(),
+            format!("from {}", burn.icrc2_token_principal),
+            format!("to EVM address 0x{}", "05".repeat(20)),
+            "Ledger fee: 0.0001 TKN".to_string(),
+            format!("Sender: {}", burn.sender),
+            format!("From subaccount: 0x{}", "04".repeat(32)),
+            format!("Wrapped ERC-20 token: 0x{}", "03".repeat(20)),
+            format!("Mint fee payer: 0x{}", "07".repeat(20)),
+            format!("Approve 1 wrapped tokens to 0x{}", "06".repeat(20)),
+            "confirmation, given within 300 seconds".to_string(),
+        ];
+        for part in expected_parts {
+            assert!(text.contains(&part), "`{part}` is missing in: {text}");
+        }
+
+        assert_eq!(info.metadata.language, "en");
+        assert_eq!(info.metadata.utc_offset_minutes, Some(60));
+    }
+
+    #[test]
+    fn burn_consent_message_for_line_display() {
+        let spec = DisplayMessageType::LineDisplay {
+            characters_per_line: 20,
+            lines_per_page: 4,
+        };
+        let info = burn_consent_info(&burn(), &token(), &request(Some(spec)));
+
+        let ConsentMessage::LineDisplayMessage { pages } = info.consent_message else {
+            panic!("unexpected message type: {:?}", info.consent_message);
+        };
+        assert!(pages.len() > 1);
+        for page in &pages {
+            assert!(page.lines.len() <= 4);
+            assert!(page.lines.iter().all(|line| line.chars().count() <= 20));
+        }
+        let text = pages
+            .iter()
+            .flat_map(|page| page.lines.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(text.contains("Bridge 1.5 TKN tokens"));
+    }
+
+    #[tokio::test]
+    async fn unsupported_method_is_rejected() {
+        let request = ConsentMessageRequest {
+            method: "transfer".into(),
+            ..request(None)
+        };
+
+        assert!(matches!(
+            consent_message(request).await,
+            Err(Icrc21Error::UnsupportedCanisterCall(_))
+        ));
+    }
+
+    #[test]
+    fn amount_is_formatted_with_decimals() {
+        assert_eq!(format_nat_amount(&Nat::from(0u64), 8), "0");
+        assert_eq!(format_nat_amount(&Nat::from(1u64), 8), "0.00000001");
+        assert_eq!(format_nat_amount(&Nat::from(123_450_000u64), 8), "1.2345");
+        assert_eq!(format_nat_amount(&Nat::from(42u64), 0), "42");
+    }
+}
@@ -1,5 +1,58 @@
 use ic_stable_structures::MemoryId;
 
 pub const ACCESS_LIST_MEMORY_ID: MemoryId = MemoryId::new(20);
+pub const DEPOSIT_CONFIRMATION_DELAY_MEMORY_ID: MemoryId = MemoryId::new(21);
+pub const TOKEN_ALLOW_LIST_MEMORY_ID: MemoryId = MemoryId::new(22);
+pub const DEPOSIT_FEE_MEMORY_ID: MemoryId = MemoryId::new(23);
+pub const MINT_ORDER_SIGNING_DELAY_MEMORY_ID: MemoryId = MemoryId::new(24);
+pub const BRIDGE_PREFERENCES_MEMORY_ID: MemoryId = MemoryId::new(25);
+pub const TOKEN_DECIMALS_MEMORY_ID: MemoryId = MemoryId::new(26);
+pub const PERMIT_SUPPORT_MEMORY_ID: MemoryId = MemoryId::new(27);
+pub const SUPPLY_GUARD_MEMORY_ID: MemoryId = MemoryId::new(28);
+pub const BURN_INTENTS_MEMORY_ID: MemoryId = MemoryId::new(29);
+pub const DEPOSIT_BUNDLES_MEMORY_ID: MemoryId = MemoryId::new(43);
+pub const DEPOSIT_BUNDLE_ITEMS_MEMORY_ID: MemoryId = MemoryId::new(44);
+pub const APPROVE_AMOUNT_POLICY_MEMORY_ID: MemoryId = MemoryId::new(45);
+pub const APPROVAL_CANCELLATIONS_MEMORY_ID: MemoryId = MemoryId::new(47);
+pub const LEDGER_CAPABILITIES_MEMORY_ID: MemoryId = MemoryId::new(48);
+pub const APPROVAL_DELIVERIES_MEMORY_ID: MemoryId = MemoryId::new(49);
+pub const APPROVAL_DELIVERY_EXPIRY_MEMORY_ID: MemoryId = MemoryId::new(50);
+pub const APPROVAL_DELIVERIES_BY_EXPIRY_MEMORY_ID: MemoryId = MemoryId::new(55);
 
-pub const IC_CHAIN_ID: u32 = 0;
+/// Default limit of the delay, which deposits can wait for the user confirmation.
+pub const DEFAULT_MAX_DEPOSIT_CONFIRMATION_DELAY_SECS: u64 = 60 * 60;
+
+/// Default deposit fee in millionths of a token.
+pub const DEFAULT_DEPOSIT_FEE: u64 = 0;
+
+/// Default delay between the deposit burn and the mint order signing.
+pub const DEFAULT_MINT_ORDER_SIGNING_DELAY_SECS: u64 = 0;
+
+/// Default time, which the spender has to claim the withdrawal, delivered with an approval.
+pub const DEFAULT_APPROVAL_DELIVERY_EXPIRY_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Max number of the users, which deposit preferences are stored.
+pub const MAX_BRIDGE_PREFERENCES_USERS: u64 = 100_000;
+
+/// Max number of the burns in a `burn_icrc2_batch` call.
+pub const MAX_BURN_BATCH_SIZE: u64 = 20;
+
+/// Number of the latest complete operations, which phase latencies are used to estimate
+/// the completion time of the incomplete ones.
+pub const COMPLETION_ESTIMATE_SAMPLE_SIZE: usize = 100;
+
+pub const IC_CHAIN_ID: u64 = 0;
+
+/// Name of the ICRC-1 standard in the `icrc1_supported_standards` response.
+pub const ICRC1_STANDARD: &str = "ICRC-1";
+
+/// Name of the ICRC-2 standard in the `icrc1_supported_standards` response.
+pub const ICRC2_STANDARD: &str = "ICRC-2";
+
+/// Standards, which the token ledger should support to be deposited: the deposit tokens are
+/// burnt with `icrc2_transfer_from`.
+pub const DEPOSIT_STANDARDS: &[&str] = &[ICRC1_STANDARD, ICRC2_STANDARD];
+
+/// Standards, which the token ledger should support to deliver the withdrawal with
+/// an approval: the withdrawn tokens are approved with `icrc2_approve`.
+pub const APPROVE_DELIVERY_STANDARDS: &[&str] = &[ICRC1_STANDARD, ICRC2_STANDARD];
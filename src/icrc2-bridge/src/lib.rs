@@ -3,6 +3,7 @@ pub use state::SigningStrategy;
 pub use crate::canister::Icrc2BridgeCanister;
 
 pub mod canister;
+pub mod consent;
 mod constant;
 pub mod ops;
 pub mod state;
@@ -1,40 +1,72 @@
+use std::mem;
+use std::time::Duration;
+
 use bridge_canister::bridge::{Operation, OperationContext, OperationProgress};
 use bridge_canister::memory::StableMemory;
-use bridge_canister::runtime::scheduler::{BridgeTask, SharedScheduler};
+use bridge_canister::runtime::scheduler::SharedScheduler;
+use bridge_canister::runtime::service::dropped_tx::DroppedMintTxHandler;
 use bridge_canister::runtime::service::mint_tx::MintTxHandler;
 use bridge_canister::runtime::service::sign_orders::MintOrderHandler;
 use bridge_canister::runtime::service::ServiceId;
+use bridge_canister::runtime::state::config::ConfigStorage;
 use bridge_canister::runtime::state::SharedConfig;
 use bridge_canister::runtime::RuntimeState;
+use bridge_did::concurrency::OperationDirection;
+use bridge_did::deposit_account::DepositAccountInfo;
 use bridge_did::error::{BTFResult, Error};
 use bridge_did::event_data::BurntEventData;
+use bridge_did::icrc_withdrawal::IcrcWithdrawalRecipient;
 use bridge_did::id256::Id256;
 use bridge_did::op_id::OperationId;
-use bridge_did::operations::IcrcBridgeOp;
-use bridge_did::order::{self, MintOrder, SignedOrders};
+use bridge_did::operation_log::OperationLog;
+use bridge_did::operations::{IcrcBridgeOp, IcrcBridgeOpV1};
+use bridge_did::order::{self, MintOrder, MintOrderV1, SignedOrders};
 use bridge_did::reason::Icrc2Burn;
-use bridge_utils::evm_link::address_to_icrc_subaccount;
-use candid::{CandidType, Nat};
+use bridge_did::retry_profile::RetryProfileKind;
+use bridge_did::versioned::Versioned;
+use bridge_utils::btf_events::BurntTokenMetadata;
+use bridge_utils::evm_bridge;
+use bridge_utils::evm_link::{address_to_icrc_subaccount, EvmLinkClient};
+use candid::{CandidType, Nat, Principal};
 use did::{H160, H256, U256};
 use eth_signer::sign_strategy::TransactionSigner;
-use ic_exports::ic_kit::RejectionCode;
-use ic_task_scheduler::retry::BackoffPolicy;
+use ic_exports::ic_kit::{ic, RejectionCode};
+use ic_storage::IcStorage;
 use ic_task_scheduler::scheduler::TaskScheduler;
 use ic_task_scheduler::task::{ScheduledTask, TaskOptions};
 use icrc_client::account::Account;
 use icrc_client::transfer::TransferError;
 use serde::{Deserialize, Serialize};
 
-use crate::constant::IC_CHAIN_ID;
-use crate::tokens::icrc1::{self, IcrcCanisterError};
+use crate::canister::get_icrc_state;
+use crate::constant::{DEPOSIT_STANDARDS, IC_CHAIN_ID};
+use crate::tokens::capabilities;
+use crate::tokens::icrc1::{self, IcrcCanisterError, TokenConfiguration};
 use crate::tokens::icrc2::{self, Success};
 
+pub mod approval_cancellation;
+pub mod approval_delivery;
+pub mod burn_batch;
+pub mod burn_intent;
+pub mod completion_estimate;
+pub mod deposit_bundle;
+pub mod deposit_confirmation;
 pub mod events_handler;
+pub mod supply_guard;
 
 pub const REFRESH_PARAMS_SERVICE_ID: ServiceId = 0;
 pub const FETCH_BTF_EVENTS_SERVICE_ID: ServiceId = 1;
 pub const SIGN_MINT_ORDER_SERVICE_ID: ServiceId = 2;
 pub const SEND_MINT_TX_SERVICE_ID: ServiceId = 3;
+pub const MONITOR_GAS_BALANCE_SERVICE_ID: ServiceId = 4;
+pub const EXPIRE_PENDING_DEPOSITS_SERVICE_ID: ServiceId = 5;
+pub const PING_EVM_SERVICE_ID: ServiceId = 6;
+pub const DISPATCH_OUTBOX_SERVICE_ID: ServiceId = 7;
+pub const CHECK_DROPPED_MINT_TX_SERVICE_ID: ServiceId = 8;
+pub const SUPPLY_GUARD_SERVICE_ID: ServiceId = 9;
+pub const DEPOSIT_BUNDLES_SERVICE_ID: ServiceId = 10;
+pub const SWEEP_APPROVALS_SERVICE_ID: ServiceId = 11;
+pub const MIGRATE_RECORDS_SERVICE_ID: ServiceId = 12;
 
 #[derive(Debug, Serialize, Deserialize, CandidType, Clone)]
 pub struct IcrcBridgeOpImpl(pub IcrcBridgeOp);
@@ -45,11 +77,54 @@ impl Operation for IcrcBridgeOpImpl {
         id: OperationId,
         ctx: RuntimeState<Self>,
     ) -> BTFResult<OperationProgress<Self>> {
+        if let Some(token) = self.decimals_dependent_token() {
+            get_icrc_state()
+                .borrow()
+                .token_decimals
+                .check_not_held(&token)?;
+        }
+
         let next_step = match self.0 {
-            IcrcBridgeOp::BurnIcrc2Tokens(burn_info) => {
-                Self::burn_icrc_tokens(ctx, burn_info, id.nonce()).await
+            IcrcBridgeOp::PendingUserConfirmation { .. } => Err(Error::FailedToProgress(
+                "PendingUserConfirmation task should progress only on the user confirmation".into(),
+            )),
+            IcrcBridgeOp::DepositAborted { .. } => Err(Error::FailedToProgress(
+                "DepositAborted task should not progress".into(),
+            )),
+            IcrcBridgeOp::BurnIcrc2Tokens { burn, .. } => {
+                let bundle = get_icrc_state()
+                    .borrow()
+                    .deposit_bundles
+                    .get_by_operation(id);
+                match bundle {
+                    Some((bundle_id, bundle)) => {
+                        deposit_bundle::burn_item(ctx, id, bundle_id, bundle, burn).await
+                    }
+                    None => Self::burn_icrc_tokens(ctx, burn, id).await,
+                }
             }
-            IcrcBridgeOp::SignMintOrder { .. } => {
+            IcrcBridgeOp::SignMintOrder {
+                order,
+                is_refund,
+                pending_since,
+            } => {
+                if !is_refund {
+                    // The deposit burn is recorded in the operation, so its intent is not needed.
+                    get_icrc_state().borrow_mut().burn_intents.remove(&id);
+                    Self::check_mint_order_signing_delay(pending_since)?;
+
+                    // The approval could be cancelled while the deposit tokens were burnt.
+                    if let Some(order) = approval_cancellation::take_cancelled(id, &order) {
+                        log::info!("Cancelled approval is removed from the mint order {id}");
+                        return Ok(OperationProgress::Progress(Self(
+                            IcrcBridgeOp::SignMintOrder {
+                                order,
+                                is_refund,
+                                pending_since,
+                            },
+                        )));
+                    }
+                }
                 return Ok(OperationProgress::AddToService(SIGN_MINT_ORDER_SERVICE_ID));
             }
             IcrcBridgeOp::SendMintTransaction { .. } => {
@@ -61,12 +136,50 @@ impl Operation for IcrcBridgeOpImpl {
             IcrcBridgeOp::WrappedTokenMintConfirmed(_) => Err(Error::FailedToProgress(
                 "WrappedTokenMintConfirmed task should not progress".into(),
             )),
-            IcrcBridgeOp::MintIcrcTokens(event) => {
-                Self::mint_icrc_tokens(ctx, event, id.nonce()).await
+            IcrcBridgeOp::MintIcrcTokens { event, .. } => {
+                return Self::mint_icrc_tokens(event, id).await;
             }
             IcrcBridgeOp::IcrcMintConfirmed { .. } => Err(Error::FailedToProgress(
                 "IcrcMintConfirmed task should not progress".into(),
             )),
+            IcrcBridgeOp::IcrcMintFailed { .. } => Err(Error::FailedToProgress(
+                "IcrcMintFailed task should not progress".into(),
+            )),
+            IcrcBridgeOp::ApproveIcrcTokens {
+                event,
+                approval,
+                pending_since,
+            } => approval_delivery::approve(id, event, approval, pending_since).await,
+            IcrcBridgeOp::IcrcApprovalGranted { .. } => Err(Error::FailedToProgress(
+                "IcrcApprovalGranted task should not progress".into(),
+            )),
+            IcrcBridgeOp::SweepIcrcApproval {
+                original_op_id,
+                event,
+                approval,
+                pending_since,
+            } => {
+                return approval_delivery::sweep(
+                    id,
+                    original_op_id,
+                    event,
+                    approval,
+                    pending_since,
+                )
+                .await;
+            }
+            IcrcBridgeOp::IcrcApprovalSwept { .. } => Err(Error::FailedToProgress(
+                "IcrcApprovalSwept task should not progress".into(),
+            )),
+            IcrcBridgeOp::IcrcApprovalClaimed { .. } => Err(Error::FailedToProgress(
+                "IcrcApprovalClaimed task should not progress".into(),
+            )),
+            IcrcBridgeOp::RefundMint { event, .. } => {
+                Self::prepare_refund_mint_order(ctx, event, id.nonce())
+            }
+            IcrcBridgeOp::RefundMintParked { .. } => Err(Error::FailedToProgress(
+                "RefundMintParked task should not progress".into(),
+            )),
         };
 
         Ok(OperationProgress::Progress(Self(next_step?)))
@@ -74,59 +187,468 @@ impl Operation for IcrcBridgeOpImpl {
 
     fn is_complete(&self) -> bool {
         match self.0 {
-            IcrcBridgeOp::BurnIcrc2Tokens(_) => false,
+            IcrcBridgeOp::PendingUserConfirmation { .. } => false,
+            IcrcBridgeOp::DepositAborted { .. } => true,
+            IcrcBridgeOp::BurnIcrc2Tokens { .. } => false,
             IcrcBridgeOp::SignMintOrder { .. } => false,
             IcrcBridgeOp::SendMintTransaction { .. } => false,
             IcrcBridgeOp::ConfirmMint { .. } => false,
             IcrcBridgeOp::WrappedTokenMintConfirmed(_) => true,
-            IcrcBridgeOp::MintIcrcTokens(_) => false,
+            IcrcBridgeOp::MintIcrcTokens { .. } => false,
             IcrcBridgeOp::IcrcMintConfirmed { .. } => true,
+            IcrcBridgeOp::IcrcMintFailed { .. } => true,
+            IcrcBridgeOp::ApproveIcrcTokens { .. } => false,
+            IcrcBridgeOp::IcrcApprovalGranted { .. } => true,
+            IcrcBridgeOp::SweepIcrcApproval { .. } => false,
+            IcrcBridgeOp::IcrcApprovalSwept { .. } => true,
+            IcrcBridgeOp::IcrcApprovalClaimed { .. } => true,
+            IcrcBridgeOp::RefundMint { .. } => false,
+            IcrcBridgeOp::RefundMintParked { .. } => false,
         }
     }
 
     fn evm_wallet_address(&self) -> H160 {
         match &self.0 {
-            IcrcBridgeOp::BurnIcrc2Tokens(burn) => burn.recipient_address.clone(),
+            IcrcBridgeOp::PendingUserConfirmation { burn, .. } => burn.recipient_address.clone(),
+            IcrcBridgeOp::DepositAborted { burn, .. } => burn.recipient_address.clone(),
+            IcrcBridgeOp::BurnIcrc2Tokens { burn, .. } => burn.recipient_address.clone(),
             IcrcBridgeOp::SignMintOrder { order, .. } => order.recipient.clone(),
             IcrcBridgeOp::SendMintTransaction { order, .. } => order.reader().get_recipient(),
             IcrcBridgeOp::ConfirmMint { order, .. } => order.reader().get_recipient(),
             IcrcBridgeOp::WrappedTokenMintConfirmed(event) => event.recipient.clone(),
-            IcrcBridgeOp::MintIcrcTokens(event) => event.sender.clone(),
+            IcrcBridgeOp::MintIcrcTokens { event, .. } => event.sender.clone(),
             IcrcBridgeOp::IcrcMintConfirmed { src_address, .. } => src_address.clone(),
+            IcrcBridgeOp::IcrcMintFailed { src_address, .. } => src_address.clone(),
+            IcrcBridgeOp::ApproveIcrcTokens { event, .. } => event.sender.clone(),
+            IcrcBridgeOp::IcrcApprovalGranted { src_address, .. } => src_address.clone(),
+            IcrcBridgeOp::SweepIcrcApproval { event, .. } => event.sender.clone(),
+            IcrcBridgeOp::IcrcApprovalSwept { src_address, .. } => src_address.clone(),
+            IcrcBridgeOp::IcrcApprovalClaimed { src_address } => src_address.clone(),
+            IcrcBridgeOp::RefundMint { event, .. } => event.sender.clone(),
+            IcrcBridgeOp::RefundMintParked { event, .. } => event.sender.clone(),
         }
     }
 
     fn scheduling_options(&self) -> Option<TaskOptions> {
+        let profile = match self.0 {
+            IcrcBridgeOp::PendingUserConfirmation { .. } => return None,
+            IcrcBridgeOp::DepositAborted { .. } => return None,
+            IcrcBridgeOp::ConfirmMint { .. } => return None,
+            IcrcBridgeOp::WrappedTokenMintConfirmed(_) => return None,
+            IcrcBridgeOp::IcrcMintConfirmed { .. } => return None,
+            IcrcBridgeOp::IcrcMintFailed { .. } => return None,
+            IcrcBridgeOp::IcrcApprovalGranted { .. } => return None,
+            IcrcBridgeOp::IcrcApprovalSwept { .. } => return None,
+            IcrcBridgeOp::IcrcApprovalClaimed { .. } => return None,
+            IcrcBridgeOp::RefundMintParked { .. } => return None,
+            IcrcBridgeOp::BurnIcrc2Tokens { .. } => RetryProfileKind::IcrcCall,
+            IcrcBridgeOp::MintIcrcTokens { .. } => RetryProfileKind::IcrcCall,
+            IcrcBridgeOp::ApproveIcrcTokens { .. } => RetryProfileKind::IcrcCall,
+            IcrcBridgeOp::SweepIcrcApproval { .. } => RetryProfileKind::IcrcCall,
+            IcrcBridgeOp::SignMintOrder { .. } => RetryProfileKind::MintSend,
+            IcrcBridgeOp::SendMintTransaction { .. } => RetryProfileKind::MintSend,
+            // Refund waits for the original operation, so it needs more attempts.
+            IcrcBridgeOp::RefundMint { .. } => RetryProfileKind::DependencyWait,
+        };
+        let options = ConfigStorage::get().borrow().get_task_options(profile);
+
+        match self.0 {
+            // Signing of the deposit mint order is retried after the configured delay.
+            IcrcBridgeOp::SignMintOrder {
+                is_refund: false, ..
+            } if Self::mint_order_signing_delay_secs() > 0 => {
+                Some(options.with_fixed_backoff_policy(
+                    u32::try_from(Self::mint_order_signing_delay_secs()).unwrap_or(u32::MAX),
+                ))
+            }
+            _ => Some(options),
+        }
+    }
+
+    fn dependency(&self) -> Option<OperationId> {
+        match self.0 {
+            IcrcBridgeOp::RefundMint { original_op_id, .. }
+            | IcrcBridgeOp::RefundMintParked { original_op_id, .. } => Some(original_op_id),
+            _ => None,
+        }
+    }
+
+    /// Refund, which original operation is not released within the dependency wait,
+    /// is parked for the operator review instead of waiting forever.
+    fn park(&self) -> Option<Self> {
+        match &self.0 {
+            IcrcBridgeOp::RefundMint {
+                original_op_id,
+                event,
+                ..
+            } => Some(Self(IcrcBridgeOp::RefundMintParked {
+                original_op_id: *original_op_id,
+                event: event.clone(),
+                pending_since: ic::time(),
+            })),
+            _ => None,
+        }
+    }
+
+    fn unpark(&self) -> Option<Self> {
+        match &self.0 {
+            IcrcBridgeOp::RefundMintParked {
+                original_op_id,
+                event,
+                ..
+            } => Some(Self(IcrcBridgeOp::RefundMint {
+                original_op_id: *original_op_id,
+                event: event.clone(),
+                pending_since: ic::time(),
+            })),
+            _ => None,
+        }
+    }
+
+    fn releases_dependents(&self) -> bool {
+        // Only refunds depend on other operations, and a refund is safe only if the original
+        // mint will never happen, or the tokens of the expired approval are returned.
+        matches!(
+            self.0,
+            IcrcBridgeOp::IcrcMintFailed { .. } | IcrcBridgeOp::IcrcApprovalSwept { .. }
+        )
+    }
+
+    fn direction(&self) -> Option<OperationDirection> {
         match self.0 {
-            IcrcBridgeOp::ConfirmMint { .. } => None,
-            IcrcBridgeOp::WrappedTokenMintConfirmed(_) => None,
-            IcrcBridgeOp::IcrcMintConfirmed { .. } => None,
-            _ => Some(
-                TaskOptions::new()
-                    .with_max_retries_policy(3)
-                    .with_backoff_policy(BackoffPolicy::Exponential {
-                        secs: 2,
-                        multiplier: 4,
-                    }),
-            ),
+            IcrcBridgeOp::BurnIcrc2Tokens { .. }
+            | IcrcBridgeOp::SignMintOrder {
+                is_refund: false, ..
+            }
+            | IcrcBridgeOp::SendMintTransaction {
+                is_refund: false, ..
+            } => Some(OperationDirection::Deposit),
+            IcrcBridgeOp::MintIcrcTokens { .. }
+            | IcrcBridgeOp::ApproveIcrcTokens { .. }
+            | IcrcBridgeOp::SweepIcrcApproval { .. }
+            | IcrcBridgeOp::RefundMint { .. }
+            | IcrcBridgeOp::SignMintOrder {
+                is_refund: true, ..
+            }
+            | IcrcBridgeOp::SendMintTransaction {
+                is_refund: true, ..
+            } => Some(OperationDirection::Withdrawal),
+            _ => None,
+        }
+    }
+
+    fn evm_tx_hash(&self) -> Option<H256> {
+        match &self.0 {
+            IcrcBridgeOp::ConfirmMint { tx_hash, .. } => tx_hash.clone(),
+            _ => None,
+        }
+    }
+
+    fn token(&self) -> Option<Id256> {
+        match &self.0 {
+            IcrcBridgeOp::PendingUserConfirmation { burn, .. }
+            | IcrcBridgeOp::DepositAborted { burn, .. } => {
+                Some(Id256::from(&burn.icrc2_token_principal))
+            }
+            _ => self.decimals_dependent_token().map(Id256::from),
+        }
+    }
+
+    /// Deposits are linked to the sender of the ICRC tokens, and withdrawals are linked
+    /// to the recipient of the ICRC tokens.
+    fn principal(&self) -> Option<Principal> {
+        match &self.0 {
+            IcrcBridgeOp::PendingUserConfirmation { burn, .. }
+            | IcrcBridgeOp::DepositAborted { burn, .. }
+            | IcrcBridgeOp::BurnIcrc2Tokens { burn, .. } => Some(burn.sender),
+            IcrcBridgeOp::MintIcrcTokens { event, .. }
+            | IcrcBridgeOp::ApproveIcrcTokens { event, .. } => {
+                IcrcWithdrawalRecipient::decode(&event.recipient_id)
+                    .ok()
+                    .map(|recipient| recipient.principal)
+            }
+            _ => None,
         }
     }
+
+    /// Deposits are cancelled, while no tokens are burnt. The deposit with a burn intent
+    /// could have its tokens burnt already, so it can't be cancelled.
+    fn cancel(&self, id: OperationId) -> Option<Self> {
+        let burn = match &self.0 {
+            IcrcBridgeOp::PendingUserConfirmation { burn, .. } => burn,
+            IcrcBridgeOp::BurnIcrc2Tokens { burn, .. }
+                if get_icrc_state().borrow().burn_intents.get(&id).is_none() =>
+            {
+                burn
+            }
+            _ => return None,
+        };
+
+        Some(Self(IcrcBridgeOp::DepositAborted {
+            burn: burn.clone(),
+            expired: false,
+        }))
+    }
+
+    /// Operations stored before the `pending_since` fields were added are decoded with the
+    /// legacy shape. The oldest of them also have the legacy shape of the mint order.
+    fn decode_legacy_log(bytes: &[u8]) -> Option<OperationLog<Self>> {
+        match OperationLog::<IcrcBridgeOpV1>::from_versioned_bytes(bytes) {
+            Ok(log) => Some(migrate_legacy_operation_log(log)),
+            Err(_) => OperationLog::<IcrcBridgeOpV1<MintOrderV1>>::from_versioned_bytes(bytes)
+                .ok()
+                .map(migrate_legacy_operation_log),
+        }
+    }
+
+    /// Operation can stay in its state with updated data, e.g. the mint transaction hash,
+    /// or move along its deposit, withdrawal or refund flow.
+    fn can_transition(from: &Self, to: &Self) -> bool {
+        if mem::discriminant(&from.0) == mem::discriminant(&to.0) {
+            return true;
+        }
+
+        matches!(
+            (&from.0, &to.0),
+            // Deposit flow.
+            (
+                IcrcBridgeOp::PendingUserConfirmation { .. },
+                IcrcBridgeOp::BurnIcrc2Tokens { .. } | IcrcBridgeOp::DepositAborted { .. }
+            ) | (
+                IcrcBridgeOp::BurnIcrc2Tokens { .. },
+                IcrcBridgeOp::SignMintOrder {
+                    is_refund: false,
+                    ..
+                } | IcrcBridgeOp::DepositAborted { .. }
+            )
+            // Wrapped tokens mint of the deposit and refund flows.
+            | (
+                IcrcBridgeOp::SignMintOrder { .. },
+                IcrcBridgeOp::SendMintTransaction { .. } | IcrcBridgeOp::ConfirmMint { .. }
+            ) | (
+                IcrcBridgeOp::SendMintTransaction { .. } | IcrcBridgeOp::ConfirmMint { .. },
+                IcrcBridgeOp::SendMintTransaction { .. }
+                    | IcrcBridgeOp::ConfirmMint { .. }
+                    | IcrcBridgeOp::WrappedTokenMintConfirmed(_)
+            )
+            // Refund of the burnt deposit of the failed deposit bundle.
+            | (
+                IcrcBridgeOp::SignMintOrder {
+                    is_refund: false,
+                    ..
+                },
+                IcrcBridgeOp::MintIcrcTokens { .. }
+            )
+            // Withdrawal flow.
+            | (
+                IcrcBridgeOp::MintIcrcTokens { .. },
+                IcrcBridgeOp::IcrcMintConfirmed { .. }
+                    | IcrcBridgeOp::IcrcMintFailed { .. }
+                    | IcrcBridgeOp::ApproveIcrcTokens { .. }
+            ) | (
+                IcrcBridgeOp::ApproveIcrcTokens { .. },
+                IcrcBridgeOp::IcrcApprovalGranted { .. }
+            )
+            // Sweep-back of the expired approval.
+            | (
+                IcrcBridgeOp::SweepIcrcApproval { .. },
+                IcrcBridgeOp::IcrcApprovalSwept { .. } | IcrcBridgeOp::IcrcApprovalClaimed { .. }
+            )
+            // Refund flow.
+            | (
+                IcrcBridgeOp::RefundMint { .. },
+                IcrcBridgeOp::SignMintOrder {
+                    is_refund: true,
+                    ..
+                } | IcrcBridgeOp::RefundMintParked { .. }
+            ) | (
+                IcrcBridgeOp::RefundMintParked { .. },
+                IcrcBridgeOp::RefundMint { .. }
+            )
+        )
+    }
 }
 
 impl IcrcBridgeOpImpl {
+    /// Checks if the operation is pending in its current state for longer than `max_age`
+    /// at the `now` IC time. Deposits waiting for the user confirmation never exceed SLA.
+    pub fn exceeds_sla(&self, now: u64, max_age: Duration) -> bool {
+        if let IcrcBridgeOp::PendingUserConfirmation { .. } = self.0 {
+            return false;
+        }
+
+        self.0
+            .pending_since()
+            .is_some_and(|since| now.saturating_sub(since) > max_age.as_nanos() as u64)
+    }
+
+    /// Returns the ICRC token, which decimals define the amounts of the operation step.
+    /// Such steps are held while the token decimals change is not resolved.
+    pub fn decimals_dependent_token(&self) -> Option<Principal> {
+        match &self.0 {
+            IcrcBridgeOp::BurnIcrc2Tokens { burn, .. } => Some(burn.icrc2_token_principal),
+            IcrcBridgeOp::SignMintOrder {
+                order,
+                is_refund: false,
+                ..
+            } => order.src_token.try_into().ok(),
+            IcrcBridgeOp::SendMintTransaction {
+                order,
+                is_refund: false,
+                ..
+            } => order.reader().get_src_token_id().try_into().ok(),
+            IcrcBridgeOp::MintIcrcTokens { event, .. } => {
+                Self::decode_burnt_event(event).ok().map(|(token, _)| token)
+            }
+            _ => None,
+        }
+    }
+
+    fn mint_order_signing_delay_secs() -> u64 {
+        *get_icrc_state()
+            .borrow()
+            .mint_order_signing_delay_secs
+            .get()
+    }
+
+    /// Checks that the configured delay since the deposit burn has elapsed, so the mint order
+    /// can be signed. Otherwise the operation fails to progress and is retried later.
+    fn check_mint_order_signing_delay(burnt_at: u64) -> BTFResult<()> {
+        let delay = Duration::from_secs(Self::mint_order_signing_delay_secs());
+        let sign_after = burnt_at.saturating_add(delay.as_nanos() as u64);
+        if ic::time() < sign_after {
+            return Err(Error::FailedToProgress(format!(
+                "mint order signing is delayed until {sign_after}"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the ICRC-2 accounts of the deposit burn: the sender account, from which
+    /// the tokens are transferred, and the bridge account, approved by the sender as
+    /// the spender.
+    ///
+    /// The sender account uses the deposit `from_subaccount`. The spender is derived only
+    /// from the recipient requested in the deposit, so the sender approves deposits per
+    /// recipient, independently of the subaccount the tokens come from.
+    fn deposit_burn_accounts(burn_info: &Icrc2Burn) -> (Account, Account) {
+        let sender_account = Account {
+            owner: burn_info.sender,
+            subaccount: burn_info.from_subaccount,
+        };
+        let spender = Self::deposit_spender(&burn_info.requested_recipient());
+
+        (sender_account, spender)
+    }
+
+    /// Returns the bridge account, which the deposit senders approve as the spender of
+    /// the deposits to the `recipient`.
+    pub fn deposit_spender(recipient: &H160) -> Account {
+        Account {
+            owner: ic::id(),
+            subaccount: Some(address_to_icrc_subaccount(&recipient.0)),
+        }
+    }
+
+    /// Returns the deposit spender account for the `recipient` with the deposit limits
+    /// of the token with the given configuration.
+    pub fn deposit_account_info(
+        recipient: &H160,
+        token: &TokenConfiguration,
+    ) -> BTFResult<DepositAccountInfo> {
+        let deposit_fee = *get_icrc_state().borrow().deposit_fee.get();
+
+        Ok(DepositAccountInfo {
+            spender: Self::deposit_spender(recipient),
+            ledger_fee: token.fee.clone(),
+            min_amount: Icrc2Burn::min_amount(token.info.decimals, deposit_fee)?,
+        })
+    }
+
+    /// Returns the allowance, which the deposit sender should approve for the deposit spender
+    /// to deposit the `amount` of the token with the given configuration.
+    ///
+    /// The allowance is the amount with the ledger fee of the `icrc2_transfer_from` call.
+    /// The deposit fee is not transferred, so it doesn't increase the allowance, but deposits
+    /// of amounts not exceeding it are rejected.
+    pub fn required_allowance(amount: &U256, token: &TokenConfiguration) -> BTFResult<U256> {
+        let deposit_fee = *get_icrc_state().borrow().deposit_fee.get();
+        let min_amount = Icrc2Burn::min_amount(token.info.decimals, deposit_fee)?;
+        if amount.0 < min_amount.0 {
+            return Err(Error::InvalidBurnAmount(format!(
+                "amount {} should be at least {}",
+                amount.0, min_amount.0
+            )));
+        }
+
+        let allowance = Nat::from(amount) + token.fee.clone();
+        let bytes = allowance.0.to_bytes_be();
+        if bytes.len() > 32 {
+            return Err(Error::InvalidBurnAmount(format!(
+                "allowance {allowance} for amount {} overflows",
+                amount.0
+            )));
+        }
+
+        Ok(U256::from_big_endian(&bytes))
+    }
+
+    /// Checks that the permit of the deposit, if any, is not expired, and the wrapped token
+    /// supports permits. The token capability is discovered once and stored in the registry.
+    async fn validate_permit(ctx: &impl OperationContext, burn_info: &Icrc2Burn) -> BTFResult<()> {
+        let Some(approve) = &burn_info.approve_after_mint else {
+            return Ok(());
+        };
+        if approve.permit.is_none() {
+            return Ok(());
+        }
+
+        approve.validate_permit(Duration::from_nanos(ic::time()).as_secs())?;
+
+        let token = &burn_info.erc20_token_address;
+        let known_support = get_icrc_state().borrow().permit_support.get(token);
+        let supported = match known_support {
+            Some(supported) => supported,
+            None => {
+                let client = ctx
+                    .get_evm_link()
+                    .get_json_rpc_client_with_timeout(ctx.get_rpc_request_timeout());
+                let supported = evm_bridge::supports_permit(&client, token.clone())
+                    .await
+                    .map_err(|e| Error::EvmRequestFailed(e.to_string()))?;
+                get_icrc_state()
+                    .borrow_mut()
+                    .permit_support
+                    .set(token.clone(), supported);
+                supported
+            }
+        };
+
+        if !supported {
+            return Err(Error::PermitNotSupported(token.clone()));
+        }
+
+        Ok(())
+    }
+
     async fn burn_icrc_tokens(
         ctx: impl OperationContext,
-        burn_info: Icrc2Burn,
-        nonce: u32,
+        mut burn_info: Icrc2Burn,
+        id: OperationId,
     ) -> BTFResult<IcrcBridgeOp> {
         log::trace!("burning icrc tokens due to: {burn_info:?}");
 
+        get_icrc_state()
+            .borrow()
+            .token_allow_list
+            .check(&burn_info.icrc2_token_principal)?;
+        capabilities::check_ledger(burn_info.icrc2_token_principal, DEPOSIT_STANDARDS, false)
+            .await?;
+
         let evm_params = ctx.get_evm_params()?;
 
-        let caller_account = Account {
-            owner: burn_info.sender,
-            subaccount: burn_info.from_subaccount,
-        };
+        let (caller_account, spender) = Self::deposit_burn_accounts(&burn_info);
 
         let token_info =
             icrc1::query_token_info_or_read_from_cache(burn_info.icrc2_token_principal)
@@ -138,22 +660,49 @@ impl IcrcBridgeOpImpl {
 
         log::trace!("got token info: {token_info:?}");
 
+        let deposit_fee = *get_icrc_state().borrow().deposit_fee.get();
+        burn_info.validate_amount(token_info.decimals, deposit_fee)?;
+
+        let conversion = get_icrc_state()
+            .borrow_mut()
+            .token_decimals
+            .observe_deposit(
+                burn_info.icrc2_token_principal,
+                token_info.decimals,
+                &burn_info.erc20_token_address,
+                ic::time(),
+            )?;
+        let wrapped_amount = conversion.to_wrapped(&burn_info.amount)?;
+
+        if let Some(approve) = &mut burn_info.approve_after_mint {
+            let policy = *get_icrc_state().borrow().approve_amount_policy.get();
+            let requested_amount = approve.approve_amount.clone();
+            if approve.limit_approve_amount(&wrapped_amount, policy)? {
+                log::warn!(
+                    "operation {id}: approve amount {requested_amount} is clamped to the minted amount {wrapped_amount}"
+                );
+            }
+        }
+
         let name = order::fit_str_to_array(&token_info.name);
         let symbol = order::fit_str_to_array(&token_info.symbol);
 
-        let spender_subaccount = address_to_icrc_subaccount(&burn_info.recipient_address.0);
-        icrc2::burn(
-            burn_info.icrc2_token_principal,
-            caller_account,
-            Some(spender_subaccount),
-            (&burn_info.amount).into(),
-            true,
-        )
-        .await
-        .map_err(|e| Error::Custom {
-            code: ErrorCodes::IcrcBurnFailed as _,
-            msg: format!("failed to burn ICRC token: {e}"),
-        })?;
+        Self::validate_permit(&ctx, &burn_info).await?;
+
+        let token = burn_info.icrc2_token_principal;
+        let amount = Nat::from(&burn_info.amount);
+        burn_intent::burn_with_intent(id, token, amount.clone(), ic::time(), |created_at_time| {
+            icrc2::burn(
+                token,
+                caller_account,
+                spender.subaccount,
+                amount,
+                Some(created_at_time),
+                Some(burn_intent::burn_memo(id)),
+                true,
+            )
+        })
+        .await?;
 
         log::trace!("transferred icrc tokens to the bridge account");
 
@@ -165,26 +714,33 @@ impl IcrcBridgeOpImpl {
 
         let fee_payer = burn_info.fee_payer.unwrap_or_default();
 
-        let (approve_spender, approve_amount) = burn_info
+        let (approve_spender, approve_amount, permit) = burn_info
             .approve_after_mint
-            .map(|approve| (approve.approve_spender, approve.approve_amount))
+            .map(|approve| {
+                (
+                    approve.approve_spender,
+                    approve.approve_amount,
+                    approve.permit,
+                )
+            })
             .unwrap_or_default();
 
         let order = MintOrder {
-            amount: burn_info.amount,
+            amount: wrapped_amount,
             sender,
             src_token,
             recipient: burn_info.recipient_address,
             dst_token: burn_info.erc20_token_address,
-            nonce,
+            nonce: id.nonce(),
             sender_chain_id,
             recipient_chain_id,
             name,
             symbol,
-            decimals: token_info.decimals,
+            decimals: conversion.wrapped_decimals,
             approve_spender,
             approve_amount,
             fee_payer,
+            permit,
         };
 
         log::debug!("prepared mint order: {:?}", order);
@@ -192,102 +748,269 @@ impl IcrcBridgeOpImpl {
         Ok(IcrcBridgeOp::SignMintOrder {
             order,
             is_refund: false,
+            pending_since: ic::time(),
         })
     }
 
     async fn mint_icrc_tokens(
-        ctx: impl OperationContext,
         event: BurntEventData,
-        nonce: u32,
-    ) -> BTFResult<IcrcBridgeOp> {
+        id: OperationId,
+    ) -> BTFResult<OperationProgress<Self>> {
         log::trace!("Minting Icrc2 tokens");
 
-        let evm_params = ctx.get_evm_params()?;
+        let (to_token, recipient) = Self::decode_burnt_event(&event)?;
 
-        let Some(to_token) = Id256::from_slice(&event.to_token).and_then(|id| id.try_into().ok())
-        else {
-            log::warn!("Failed to decode token id256 from erc20 minted event");
-            return Err(Error::Serialization(
-                "failed to decode token id256 from erc20 minted event".into(),
-            ));
-        };
-
-        let Some(recipient) =
-            Id256::from_slice(&event.recipient_id).and_then(|id| id.try_into().ok())
-        else {
-            log::warn!("Failed to decode recipient id from minted event");
-            return Err(Error::Serialization(
-                "Failed to decode recipient id from minted event".into(),
-            ));
-        };
+        let token_config = icrc1::get_token_configuration(to_token)
+            .await
+            .map_err(|e| Error::Custom {
+                code: ErrorCodes::IcrcMetadataRequestFailed as _,
+                msg: format!("failed to query Icrc token configuration: {e}"),
+            })?;
+        let conversion = get_icrc_state()
+            .borrow_mut()
+            .token_decimals
+            .observe_withdrawal(
+                to_token,
+                token_config.info.decimals,
+                &event.from_erc20,
+                ic::time(),
+            )?;
 
         // Transfer icrc2 tokens to the recipient.
-        let amount = Nat::from(&event.amount);
+        let amount = Nat::from(&conversion.to_ledger(&event.amount)?);
 
-        let mint_result = icrc2::mint(to_token, recipient, amount.clone(), true).await;
+        let approval_spender = recipient.approval_spender();
+        if approval_spender.is_some() {
+            match approval_delivery::validate(to_token, &amount, &token_config.fee).await {
+                Ok(()) => {}
+                // Capabilities of the ledger are failed to be queried.
+                Err(e @ Error::Custom { .. }) => return Err(e),
+                Err(e) => {
+                    log::warn!(
+                        "Withdrawal {id} can't be delivered with approval: {e}. Creating refund operation..."
+                    );
+                    return Ok(Self::mint_failed_with_refund(id, event, e.to_string()));
+                }
+            }
+        }
+
+        // Tokens, delivered with an approval, are transferred to the holder account of the bridge.
+        let recipient_account = match approval_spender {
+            Some(_) => Account {
+                owner: ic::id(),
+                subaccount: Some(approval_delivery::holder_subaccount(id)),
+            },
+            None => Account {
+                owner: recipient.principal,
+                subaccount: recipient.subaccount,
+            },
+        };
+        let mint_result = icrc2::mint(
+            to_token,
+            recipient_account,
+            amount.clone(),
+            recipient.memo.clone(),
+            true,
+        )
+        .await;
 
         match mint_result {
-            Ok(Success { tx_id, .. }) => {
-                log::trace!("Finished icrc2 mint to principal: {}", recipient);
-                Ok(IcrcBridgeOp::IcrcMintConfirmed {
-                    src_address: event.sender,
-                    icrc_tx_id: tx_id,
-                })
+            Ok(Success { tx_id, amount }) => {
+                log::trace!("Finished icrc2 mint to account: {recipient_account:?}");
+                let next_step = match approval_spender {
+                    Some(spender) => IcrcBridgeOp::ApproveIcrcTokens {
+                        event,
+                        approval: approval_delivery::new_approval(
+                            id,
+                            spender,
+                            amount,
+                            &token_config.fee,
+                            ic::time(),
+                        ),
+                        pending_since: ic::time(),
+                    },
+                    None => IcrcBridgeOp::IcrcMintConfirmed {
+                        src_address: event.sender,
+                        icrc_tx_id: tx_id,
+                        icrc_memo: recipient.memo,
+                    },
+                };
+                Ok(OperationProgress::Progress(Self(next_step)))
             }
-            Err(
-                e @ IcrcCanisterError::TransferFailed(TransferError::TooOld)
-                | e @ IcrcCanisterError::TransferFailed(TransferError::CreatedInFuture { .. })
-                | e @ IcrcCanisterError::TransferFailed(TransferError::TemporarilyUnavailable)
-                | e @ IcrcCanisterError::TransferFailed(TransferError::GenericError { .. })
-                | e @ IcrcCanisterError::CanisterError(RejectionCode::SysTransient, _),
-            ) => {
+            Err(e) => Self::mint_error_progress(id, event, e),
+        }
+    }
+
+    /// Handles the failed ICRC mint of the `id` withdrawal.
+    ///
+    /// Transient failures are retried. The mint is failed with a refund only if the ledger
+    /// definitely did not apply the transfer: the transfer is invalid, the ledger returned
+    /// a transfer error, or the call was not executed. Otherwise, e.g. if the ledger response can't be decoded, the
+    /// transfer could be applied, so neither retry, nor refund is safe, and the operation
+    /// needs the manual review.
+    fn mint_error_progress(
+        id: OperationId,
+        event: BurntEventData,
+        error: IcrcCanisterError,
+    ) -> BTFResult<OperationProgress<Self>> {
+        match error {
+            e @ (IcrcCanisterError::TransferFailed(TransferError::TooOld)
+            | IcrcCanisterError::TransferFailed(TransferError::CreatedInFuture { .. })
+            | IcrcCanisterError::TransferFailed(TransferError::TemporarilyUnavailable)
+            | IcrcCanisterError::TransferFailed(TransferError::GenericError { .. })
+            | IcrcCanisterError::CanisterError(RejectionCode::SysTransient, _)) => {
                 log::warn!("Failed to perform icrc token mint due to: {e}. Retrying...");
                 Err(Error::Custom {
                     code: ErrorCodes::IcrcMintFailed as _,
                     msg: format!("ICRC token mint failed: {e}"),
                 })
             }
-            Err(e) => {
+            // A trapped or missing ledger canister does not apply the transfer.
+            e @ (IcrcCanisterError::TransferFailed(_)
+            | IcrcCanisterError::InvalidTransfer(_)
+            | IcrcCanisterError::CanisterError(
+                RejectionCode::DestinationInvalid | RejectionCode::CanisterError,
+                _,
+            )) => {
                 log::warn!(
-                    "Impossible to mint icrc token due to: {e}. Preparing refund MintOrder..."
+                    "Impossible to mint icrc token due to: {e}. Creating refund operation..."
                 );
+                Ok(Self::mint_failed_with_refund(id, event, e.to_string()))
+            }
+            e => {
+                log::error!("Unknown result of icrc token mint: {e}");
+                Err(Error::CannotProgress(format!(
+                    "unknown result of ICRC token mint: {e}"
+                )))
+            }
+        }
+    }
 
-                let sender_chain_id = IC_CHAIN_ID;
-                let recipient_chain_id = evm_params.chain_id;
-
-                // If we pass zero name or symbol, it will not be applied.
-                let name = event.name.try_into().unwrap_or_default();
-                let symbol = event.symbol.try_into().unwrap_or_default();
-
-                let sender = Id256::from(&recipient);
-                let src_token = Id256::from(&to_token);
-
-                let order = MintOrder {
-                    amount: event.amount,
-                    sender,
-                    src_token,
-                    recipient: event.sender,
-                    dst_token: event.from_erc20,
-                    nonce,
-                    sender_chain_id,
-                    recipient_chain_id,
-                    name,
-                    symbol,
-                    decimals: event.decimals,
-                    approve_spender: H160::default(),
-                    approve_amount: U256::zero(),
-                    fee_payer: H160::default(),
-                };
+    /// Fails the mint of the `id` withdrawal with the `reason`, and creates the refund
+    /// of the burnt wrapped tokens.
+    fn mint_failed_with_refund(
+        id: OperationId,
+        event: BurntEventData,
+        reason: String,
+    ) -> OperationProgress<Self> {
+        let refund = IcrcBridgeOp::RefundMint {
+            original_op_id: id,
+            event: event.clone(),
+            pending_since: ic::time(),
+        };
+
+        OperationProgress::ProgressWithDependent {
+            update_to: Self(IcrcBridgeOp::IcrcMintFailed {
+                src_address: event.sender,
+                reason,
+            }),
+            dependent: Self(refund),
+        }
+    }
 
-                log::debug!("prepared refund mint order: {:?}", order);
+    fn prepare_refund_mint_order(
+        ctx: impl OperationContext,
+        event: BurntEventData,
+        nonce: u32,
+    ) -> BTFResult<IcrcBridgeOp> {
+        log::trace!("Preparing refund MintOrder");
 
-                Ok(IcrcBridgeOp::SignMintOrder {
-                    order,
-                    is_refund: true,
-                })
-            }
+        let evm_params = ctx.get_evm_params()?;
+        let (to_token, recipient) = Self::decode_burnt_event(&event)?;
+
+        let sender_chain_id = IC_CHAIN_ID;
+        let recipient_chain_id = evm_params.chain_id;
+
+        let metadata = BurntTokenMetadata::normalized(&event);
+
+        // Mint order sender is a single `Id256`, so the recipient subaccount is not included.
+        let sender = Id256::from(&recipient.principal);
+        let src_token = Id256::from(&to_token);
+
+        let order = MintOrder {
+            amount: event.amount,
+            sender,
+            src_token,
+            recipient: event.sender,
+            dst_token: event.from_erc20,
+            nonce,
+            sender_chain_id,
+            recipient_chain_id,
+            name: metadata.name,
+            symbol: metadata.symbol,
+            decimals: metadata.decimals,
+            approve_spender: H160::default(),
+            approve_amount: U256::zero(),
+            fee_payer: H160::default(),
+            permit: None,
+        };
+
+        log::debug!("prepared refund mint order: {:?}", order);
+
+        Ok(IcrcBridgeOp::SignMintOrder {
+            order,
+            is_refund: true,
+            pending_since: ic::time(),
+        })
+    }
+
+    /// Returns ICRC token and recipient principals of the burnt event.
+    fn decode_burnt_event(
+        event: &BurntEventData,
+    ) -> BTFResult<(Principal, IcrcWithdrawalRecipient)> {
+        let to_token = Id256::try_from_slice(&event.to_token)
+            .and_then(|id| id.try_to_principal())
+            .map_err(|e| {
+                log::warn!("Failed to decode token id256 from erc20 minted event: {e}");
+                Error::Serialization(format!(
+                    "failed to decode token id256 from erc20 minted event: {e}"
+                ))
+            })?;
+
+        let Ok(recipient) = IcrcWithdrawalRecipient::decode(&event.recipient_id) else {
+            log::warn!("Failed to decode recipient id from minted event");
+            return Err(Error::Serialization(
+                "Failed to decode recipient id from minted event".into(),
+            ));
+        };
+
+        Ok((to_token, recipient))
+    }
+}
+
+/// Converts log of the operation, stored before the `pending_since` fields were added.
+/// The `pending_since` of every step is the time the step was logged at.
+///
+/// Orders, signed before the chain ids were extended to 64 bits, are encoded in the legacy
+/// layout and can't be minted anymore. An operation, which sends or confirms such an order,
+/// is moved back to signing of the same order. The order keeps its nonce, so the bridge
+/// contract rejects it, if the legacy order is minted already.
+pub fn migrate_legacy_operation_log<O>(
+    log: OperationLog<IcrcBridgeOpV1<O>>,
+) -> OperationLog<IcrcBridgeOpImpl>
+where
+    O: Into<MintOrder> + CandidType,
+{
+    let mut log = log.map_payload(|op, timestamp| IcrcBridgeOpImpl(op.into_current(timestamp)));
+
+    let legacy_order = match &log.current_step().0 {
+        IcrcBridgeOp::SendMintTransaction {
+            order, is_refund, ..
         }
+        | IcrcBridgeOp::ConfirmMint {
+            order, is_refund, ..
+        } => order.legacy_order().map(|order| (order, *is_refund)),
+        _ => None,
+    };
+    if let Some((order, is_refund)) = legacy_order {
+        log.add_step(Ok(IcrcBridgeOpImpl(IcrcBridgeOp::SignMintOrder {
+            order,
+            is_refund,
+            pending_since: ic::time(),
+        })));
     }
+
+    log
 }
 
 /// ICRC token related errors.
@@ -295,6 +1018,10 @@ pub enum ErrorCodes {
     IcrcMetadataRequestFailed = 0,
     IcrcBurnFailed = 1,
     IcrcMintFailed = 2,
+    IcrcAllowanceRequestFailed = 3,
+    IcrcBalanceRequestFailed = 4,
+    IcrcApproveFailed = 5,
+    IcrcSweepFailed = 6,
 }
 
 /// Allows Signing service to handle MintOrders of ICRC bridge.
@@ -334,22 +1061,38 @@ impl MintOrderHandler for IcrcMintOrderHandler {
             return;
         };
 
-        let IcrcBridgeOp::SignMintOrder { is_refund, order } = op.0 else {
+        let IcrcBridgeOp::SignMintOrder {
+            is_refund, order, ..
+        } = op.0
+        else {
             log::info!("Mint order handler failed to set MintOrder: unexpected state.");
             return;
         };
 
         let will_pay_fee = order.fee_payer != H160::zero();
-        let should_send_mint_tx = !is_refund && will_pay_fee;
+        let sponsorship_enabled = self
+            .state
+            .borrow()
+            .config
+            .borrow()
+            .is_mint_tx_sponsorship_enabled();
+        if !is_refund && will_pay_fee && !sponsorship_enabled {
+            log::warn!(
+                "Mint transactions sponsorship is disabled due to low gas balance. The mint order should be sent by the user."
+            );
+        }
+        let should_send_mint_tx = !is_refund && will_pay_fee && sponsorship_enabled;
         let new_op = match should_send_mint_tx {
             true => IcrcBridgeOp::SendMintTransaction {
                 order: signed,
                 is_refund,
+                pending_since: ic::time(),
             },
             false => IcrcBridgeOp::ConfirmMint {
                 order: signed,
                 is_refund,
                 tx_hash: None,
+                pending_since: ic::time(),
             },
         };
 
@@ -361,10 +1104,19 @@ impl MintOrderHandler for IcrcMintOrderHandler {
             .update(id, new_op.clone());
 
         if let Some(options) = scheduling_options {
-            let scheduled_task = ScheduledTask::with_options(BridgeTask::new(id, new_op), options);
+            let task = self.state.borrow_mut().new_task(id, new_op);
+            let scheduled_task = ScheduledTask::with_options(task, options);
             self.scheduler.append_task(scheduled_task);
         }
     }
+
+    fn get_atomic_group(&self, id: OperationId) -> Option<Vec<OperationId>> {
+        get_icrc_state()
+            .borrow()
+            .deposit_bundles
+            .get_by_operation(id)
+            .map(|(_, bundle)| bundle.operation_ids())
+    }
 }
 
 /// Allows MintTxService to handle IcrcOperations.
@@ -398,20 +1150,1069 @@ impl MintTxHandler for IcrcMintTxHandler {
         Some(order)
     }
 
-    fn mint_tx_sent(&self, id: OperationId, tx_hash: H256) {
+    fn mint_tx_sent(&self, id: OperationId, tx_hash: H256, evm_params_snapshot: Option<u64>) {
         let op = self.state.borrow().operations.get(id);
-        let Some(IcrcBridgeOp::SendMintTransaction { order, is_refund }) = op.map(|op| op.0) else {
+        let Some(IcrcBridgeOp::SendMintTransaction {
+            order, is_refund, ..
+        }) = op.map(|op| op.0)
+        else {
             log::info!("MintTxHandler failed to update operation: unexpected operation state.");
             return;
         };
 
-        self.state.borrow_mut().operations.update(
+        self.state.borrow_mut().operations.update_with_evm_params(
             id,
             IcrcBridgeOpImpl(IcrcBridgeOp::ConfirmMint {
                 order,
                 tx_hash: Some(tx_hash),
                 is_refund,
+                pending_since: ic::time(),
+            }),
+            evm_params_snapshot,
+        );
+    }
+
+    fn mint_tx_not_sponsored(&self, id: OperationId) -> bool {
+        let op = self.state.borrow().operations.get(id);
+        let Some(IcrcBridgeOp::SendMintTransaction {
+            order, is_refund, ..
+        }) = op.map(|op| op.0)
+        else {
+            return false;
+        };
+
+        self.state.borrow_mut().operations.update(
+            id,
+            IcrcBridgeOpImpl(IcrcBridgeOp::ConfirmMint {
+                order,
+                tx_hash: None,
+                is_refund,
+                pending_since: ic::time(),
             }),
         );
+        true
+    }
+}
+
+/// Allows CheckDroppedMintTxService to re-send the dropped mint transactions of IcrcOperations.
+pub struct IcrcDroppedMintTxHandler {
+    state: RuntimeState<IcrcBridgeOpImpl>,
+    scheduler: SharedScheduler<StableMemory, IcrcBridgeOpImpl>,
+}
+
+impl IcrcDroppedMintTxHandler {
+    /// Creates new handler instance.
+    pub fn new(
+        state: RuntimeState<IcrcBridgeOpImpl>,
+        scheduler: SharedScheduler<StableMemory, IcrcBridgeOpImpl>,
+    ) -> Self {
+        Self { state, scheduler }
+    }
+}
+
+impl DroppedMintTxHandler for IcrcDroppedMintTxHandler {
+    fn get_evm_config(&self) -> SharedConfig {
+        self.state.borrow().config.clone()
+    }
+
+    fn get_unconfirmed_mint_txs(&self, sent_before: u64) -> Vec<(OperationId, H256, Option<u64>)> {
+        let state = self.state.borrow();
+        state
+            .operations
+            .get_incomplete()
+            .into_iter()
+            .filter_map(|(id, op)| match op.0 {
+                IcrcBridgeOp::ConfirmMint {
+                    tx_hash: Some(tx_hash),
+                    pending_since,
+                    ..
+                } if pending_since < sent_before => {
+                    // The transaction is sent at the latest successful step.
+                    let evm_params_snapshot = state.operations.get_log(id).and_then(|log| {
+                        log.log()
+                            .iter()
+                            .rev()
+                            .find(|entry| entry.step_result.is_ok())
+                            .and_then(|entry| entry.evm_params_snapshot)
+                    });
+                    Some((id, tx_hash, evm_params_snapshot))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn mint_tx_dropped(&self, id: OperationId, tx_hash: H256) {
+        let op = self.state.borrow().operations.get(id);
+        let Some(IcrcBridgeOp::ConfirmMint {
+            order,
+            tx_hash: Some(sent_tx_hash),
+            is_refund,
+            ..
+        }) = op.map(|op| op.0)
+        else {
+            log::info!(
+                "Dropped mint tx handler failed to update operation: unexpected operation state."
+            );
+            return;
+        };
+
+        if sent_tx_hash != tx_hash {
+            log::info!(
+                "Dropped mint tx handler skipped operation: mint transaction is already re-sent."
+            );
+            return;
+        }
+
+        let new_op = IcrcBridgeOpImpl(IcrcBridgeOp::SendMintTransaction {
+            order,
+            is_refund,
+            pending_since: ic::time(),
+        });
+        let scheduling_options = new_op.scheduling_options();
+        self.state
+            .borrow_mut()
+            .operations
+            .update(id, new_op.clone());
+
+        if let Some(options) = scheduling_options {
+            let task = self.state.borrow_mut().new_task(id, new_op);
+            let scheduled_task = ScheduledTask::with_options(task, options);
+            self.scheduler.append_task(scheduled_task);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_canister::runtime::scheduler::BridgeTask;
+    use bridge_canister::runtime::BridgeRuntime;
+    use bridge_did::bulk_action::{BulkAction, BulkActionOutcome, OperationFilter};
+    use bridge_did::burn_intent::BurnIntent;
+    use bridge_did::id256::AccountId256;
+    use bridge_did::operation_status::OperationStateClass;
+    use bridge_did::operations::IcrcApproval;
+    use bridge_did::order::{SignedOrdersData, SIGNATURE_LEN};
+    use bridge_did::preferences::PreferenceField;
+    use bridge_did::retry_profile::{RetryLimit, RetryProfiles};
+    use bridge_did::token_decimals::DecimalsChangeStrategy;
+    use ic_exports::ic_kit::MockContext;
+    use ic_task_scheduler::task::Task;
+
+    use super::*;
+
+    fn burnt_event() -> BurntEventData {
+        BurntEventData {
+            sender: H160::from_slice(&[1; 20]),
+            amount: 1000u64.into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn refund_should_not_progress_while_original_mint_can_be_sent() {
+        MockContext::new().inject();
+
+        let runtime: BridgeRuntime<IcrcBridgeOpImpl> = BridgeRuntime::default(ConfigStorage::get());
+        let state = runtime.state().clone();
+
+        let original = IcrcBridgeOpImpl(IcrcBridgeOp::MintIcrcTokens {
+            event: burnt_event(),
+            pending_since: 0,
+        });
+        let original_id = state.borrow_mut().operations.new_operation(original, None);
+        let refund = IcrcBridgeOpImpl(IcrcBridgeOp::RefundMint {
+            original_op_id: original_id,
+            event: burnt_event(),
+            pending_since: 0,
+        });
+        let refund_id = state
+            .borrow_mut()
+            .operations
+            .new_operation(refund.clone(), None);
+
+        let err = BridgeTask::new(refund_id, refund.clone())
+            .execute(state.clone(), Box::new(runtime.scheduler().clone()))
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&Error::DependencyNotReleased(original_id).to_string()));
+
+        let refund_state = state.borrow().operations.get(refund_id).unwrap();
+        assert!(matches!(refund_state.0, IcrcBridgeOp::RefundMint { .. }));
+    }
+
+    #[tokio::test]
+    async fn refund_is_parked_after_dependency_wait_and_retried_by_operator() {
+        let context = MockContext::new().inject();
+
+        let runtime: BridgeRuntime<IcrcBridgeOpImpl> = BridgeRuntime::default(ConfigStorage::get());
+        let state = runtime.state().clone();
+        let original = IcrcBridgeOpImpl(IcrcBridgeOp::MintIcrcTokens {
+            event: burnt_event(),
+            pending_since: 0,
+        });
+        let original_id = state.borrow_mut().operations.new_operation(original, None);
+        let refund = IcrcBridgeOpImpl(IcrcBridgeOp::RefundMint {
+            original_op_id: original_id,
+            event: burnt_event(),
+            pending_since: 0,
+        });
+        let refund_id = state
+            .borrow_mut()
+            .operations
+            .new_operation(refund.clone(), None);
+
+        let max_wait_secs = RetryProfiles::default()
+            .dependency_wait
+            .total_backoff_secs()
+            .unwrap();
+        context.add_time(Duration::from_secs(max_wait_secs).as_nanos() as u64);
+        BridgeTask::new(refund_id, refund)
+            .execute(state.clone(), Box::new(runtime.scheduler().clone()))
+            .await
+            .unwrap();
+
+        let parked = state.borrow().operations.get(refund_id).unwrap();
+        assert!(matches!(parked.0, IcrcBridgeOp::RefundMintParked { .. }));
+        assert!(parked.scheduling_options().is_none());
+        let log = state.borrow().operations.get_log(refund_id).unwrap();
+        assert_eq!(
+            bridge_canister::operation_store::state_class(&log),
+            OperationStateClass::Parked
+        );
+
+        let result = runtime.bulk_action(
+            BulkAction::Retry,
+            OperationFilter {
+                state_class: Some(OperationStateClass::Parked),
+                ..Default::default()
+            },
+            None,
+            Principal::anonymous(),
+            ic::time(),
+        );
+        assert_eq!(
+            result.outcomes,
+            vec![(refund_id, BulkActionOutcome::Applied)]
+        );
+        let retried = state.borrow().operations.get(refund_id).unwrap();
+        assert_eq!(
+            retried.0,
+            IcrcBridgeOp::RefundMint {
+                original_op_id: original_id,
+                event: burnt_event(),
+                pending_since: ic::time(),
+            }
+        );
+    }
+
+    fn sign_mint_order(is_refund: bool, pending_since: u64) -> IcrcBridgeOpImpl {
+        IcrcBridgeOpImpl(IcrcBridgeOp::SignMintOrder {
+            order: MintOrder {
+                amount: 1000u64.into(),
+                sender: Id256::from(&Principal::anonymous()),
+                src_token: Id256::from(&Principal::anonymous()),
+                recipient: H160::from_slice(&[1; 20]),
+                dst_token: H160::from_slice(&[2; 20]),
+                nonce: 0,
+                sender_chain_id: IC_CHAIN_ID,
+                recipient_chain_id: 5,
+                name: [0; 32],
+                symbol: [0; 16],
+                decimals: 8,
+                approve_spender: H160::default(),
+                approve_amount: U256::zero(),
+                fee_payer: H160::default(),
+                permit: None,
+            },
+            is_refund,
+            pending_since,
+        })
+    }
+
+    #[test]
+    fn scheduling_options_follow_retry_profiles() {
+        MockContext::new().inject();
+        let config = ConfigStorage::get();
+        let mint = IcrcBridgeOpImpl(IcrcBridgeOp::MintIcrcTokens {
+            event: burnt_event(),
+            pending_since: 0,
+        });
+        let refund = IcrcBridgeOpImpl(IcrcBridgeOp::RefundMint {
+            original_op_id: OperationId::new(0),
+            event: burnt_event(),
+            pending_since: 0,
+        });
+        let sign = sign_mint_order(true, 0);
+
+        let mut profiles = RetryProfiles::default();
+        profiles.icrc_call.retries = RetryLimit::MaxRetries(5);
+        profiles.mint_send.retries = RetryLimit::MaxRetries(6);
+        profiles.dependency_wait.retries = RetryLimit::MaxRetries(7);
+        config.borrow_mut().set_retry_profiles(Some(profiles));
+
+        for (op, profile) in [
+            (mint, RetryProfileKind::IcrcCall),
+            (sign, RetryProfileKind::MintSend),
+            (refund, RetryProfileKind::DependencyWait),
+        ] {
+            assert_eq!(
+                op.scheduling_options(),
+                Some(config.borrow().get_task_options(profile))
+            );
+        }
+
+        let confirm = IcrcBridgeOpImpl(IcrcBridgeOp::IcrcMintConfirmed {
+            src_address: H160::from_slice(&[1; 20]),
+            icrc_tx_id: 0u64.into(),
+            icrc_memo: None,
+        });
+        assert!(confirm.scheduling_options().is_none());
+    }
+
+    #[test]
+    fn deposit_mint_order_signing_is_retried_after_delay() {
+        MockContext::new().inject();
+        get_icrc_state()
+            .borrow_mut()
+            .mint_order_signing_delay_secs
+            .set(30)
+            .unwrap();
+
+        assert_eq!(
+            sign_mint_order(false, 0).scheduling_options(),
+            Some(
+                ConfigStorage::get()
+                    .borrow()
+                    .get_task_options(RetryProfileKind::MintSend)
+                    .with_fixed_backoff_policy(30)
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn operations_are_held_until_decimals_change_is_resolved() {
+        MockContext::new().inject();
+
+        let runtime: BridgeRuntime<IcrcBridgeOpImpl> = BridgeRuntime::default(ConfigStorage::get());
+        let state = runtime.state().clone();
+        let token = Principal::anonymous();
+        let wrapped = H160::from_slice(&[2; 20]);
+        {
+            let icrc_state = get_icrc_state();
+            let mut icrc_state = icrc_state.borrow_mut();
+            icrc_state
+                .token_decimals
+                .observe_deposit(token, 8, &wrapped, 0)
+                .unwrap();
+            icrc_state
+                .token_decimals
+                .observe_deposit(token, 12, &wrapped, 0)
+                .unwrap_err();
+        }
+
+        let deposit = sign_mint_order(false, 0);
+        let refund = sign_mint_order(true, 0);
+        assert_eq!(deposit.decimals_dependent_token(), Some(token));
+        assert_eq!(refund.decimals_dependent_token(), None);
+
+        let id = state
+            .borrow_mut()
+            .operations
+            .new_operation(deposit.clone(), None);
+        assert!(matches!(
+            deposit.clone().progress(id, state.clone()).await,
+            Err(Error::TokenDecimalsChanged(held)) if held == token
+        ));
+        // Refunds return the wrapped tokens, so they don't depend on the ledger decimals.
+        assert!(matches!(
+            refund.progress(id, state.clone()).await,
+            Ok(OperationProgress::AddToService(SIGN_MINT_ORDER_SERVICE_ID))
+        ));
+
+        get_icrc_state()
+            .borrow_mut()
+            .token_decimals
+            .resolve(token, DecimalsChangeStrategy::Rescale)
+            .unwrap();
+        assert!(matches!(
+            deposit.progress(id, state).await,
+            Ok(OperationProgress::AddToService(SIGN_MINT_ORDER_SERVICE_ID))
+        ));
+    }
+
+    #[tokio::test]
+    async fn deposit_mint_order_is_not_signed_until_delay_elapses() {
+        let context = MockContext::new().inject();
+
+        let runtime: BridgeRuntime<IcrcBridgeOpImpl> = BridgeRuntime::default(ConfigStorage::get());
+        let state = runtime.state().clone();
+        get_icrc_state()
+            .borrow_mut()
+            .mint_order_signing_delay_secs
+            .set(30)
+            .unwrap();
+
+        let op = sign_mint_order(false, ic::time());
+        assert!(op.scheduling_options().is_some());
+        let id = state
+            .borrow_mut()
+            .operations
+            .new_operation(op.clone(), None);
+
+        context.add_time(29 * 1_000_000_000);
+        let err = op.clone().progress(id, state.clone()).await.unwrap_err();
+        assert!(matches!(err, Error::FailedToProgress(_)));
+
+        context.add_time(1_000_000_000);
+        let progress = op.progress(id, state.clone()).await.unwrap();
+        assert!(matches!(
+            progress,
+            OperationProgress::AddToService(SIGN_MINT_ORDER_SERVICE_ID)
+        ));
+
+        // Refund mint orders are not delayed.
+        let refund = sign_mint_order(true, ic::time());
+        let refund_id = state
+            .borrow_mut()
+            .operations
+            .new_operation(refund.clone(), None);
+        assert!(refund.progress(refund_id, state).await.is_ok());
+    }
+
+    #[test]
+    fn deposit_spender_is_derived_from_recipient_not_subaccount() {
+        MockContext::new().inject();
+        let recipient = H160::from_slice(&[9; 20]);
+        let from_subaccount = [4; 32];
+        let mut burn = Icrc2Burn {
+            sender: Principal::from_slice(&[7; 29]),
+            amount: 1000u64.into(),
+            icrc2_token_principal: Principal::from_slice(&[5; 29]),
+            erc20_token_address: H160::from_slice(&[8; 20]),
+            from_subaccount: Some(from_subaccount),
+            recipient_address: recipient.clone(),
+            approve_after_mint: None,
+            fee_payer: None,
+            confirmation_delay_secs: None,
+            preference_fields: None,
+        };
+
+        let (sender_account, spender) = IcrcBridgeOpImpl::deposit_burn_accounts(&burn);
+        assert_eq!(sender_account.owner, burn.sender);
+        assert_eq!(sender_account.subaccount, Some(from_subaccount));
+        assert_eq!(spender.owner, ic::id());
+        assert_eq!(
+            spender.subaccount,
+            Some(address_to_icrc_subaccount(&recipient.0))
+        );
+        assert_ne!(spender.subaccount, Some(from_subaccount));
+
+        // Deposit subaccount doesn't affect the spender.
+        burn.from_subaccount = None;
+        let (sender_account, same_spender) = IcrcBridgeOpImpl::deposit_burn_accounts(&burn);
+        assert_eq!(sender_account.subaccount, None);
+        assert_eq!(same_spender, spender);
+
+        // Recipient from the sender preferences is not the requested one.
+        burn.preference_fields = Some(vec![PreferenceField::RecipientAddress]);
+        let (_, spender) = IcrcBridgeOpImpl::deposit_burn_accounts(&burn);
+        assert_eq!(spender, IcrcBridgeOpImpl::deposit_spender(&H160::zero()));
+    }
+
+    #[test]
+    fn deposit_account_info_matches_burn_path() {
+        MockContext::new().inject();
+        get_icrc_state()
+            .borrow_mut()
+            .deposit_fee
+            .set(500_000)
+            .unwrap();
+
+        let token = TokenConfiguration {
+            principal: Principal::from_slice(&[5; 29]),
+            fee: 10_000u64.into(),
+            minting_account: Account {
+                owner: Principal::management_canister(),
+                subaccount: None,
+            },
+            info: icrc1::TokenInfo {
+                name: "Token".into(),
+                symbol: "TKN".into(),
+                decimals: 8,
+            },
+            max_memo_length: 32,
+        };
+        let recipient = H160::from_slice(&[9; 20]);
+        let info = IcrcBridgeOpImpl::deposit_account_info(&recipient, &token).unwrap();
+
+        let mut burn = Icrc2Burn {
+            sender: Principal::from_slice(&[7; 29]),
+            amount: info.min_amount.clone(),
+            icrc2_token_principal: token.principal,
+            erc20_token_address: H160::from_slice(&[8; 20]),
+            from_subaccount: Some([4; 32]),
+            recipient_address: recipient,
+            approve_after_mint: None,
+            fee_payer: None,
+            confirmation_delay_secs: None,
+            preference_fields: None,
+        };
+        let (_, spender) = IcrcBridgeOpImpl::deposit_burn_accounts(&burn);
+        assert_eq!(info.spender, spender);
+        assert_eq!(info.ledger_fee, token.fee);
+
+        // Minimal amount is the smallest amount, accepted by the burn.
+        assert!(burn.validate_amount(8, 500_000).is_ok());
+        burn.amount = (info.min_amount.0 - U256::from(1u64).0).into();
+        assert!(burn.validate_amount(8, 500_000).is_err());
+    }
+
+    #[test]
+    fn required_allowance_includes_all_fees() {
+        MockContext::new().inject();
+        get_icrc_state()
+            .borrow_mut()
+            .deposit_fee
+            .set(500_000)
+            .unwrap();
+
+        let token = TokenConfiguration {
+            principal: Principal::from_slice(&[5; 29]),
+            fee: 10_000u64.into(),
+            minting_account: Account {
+                owner: Principal::management_canister(),
+                subaccount: None,
+            },
+            info: icrc1::TokenInfo {
+                name: "Token".into(),
+                symbol: "TKN".into(),
+                decimals: 8,
+            },
+            max_memo_length: 32,
+        };
+
+        let amount = U256::from(100_000_000u64);
+        let allowance = IcrcBridgeOpImpl::required_allowance(&amount, &token).unwrap();
+        assert_eq!(allowance, U256::from(100_010_000u64));
+
+        // Amount, accepted by the burn, is accepted with the ledger fee only.
+        let min_amount = Icrc2Burn::min_amount(8, 500_000).unwrap();
+        let allowance = IcrcBridgeOpImpl::required_allowance(&min_amount, &token).unwrap();
+        assert_eq!(allowance, U256::from(50_010_001u64));
+
+        // Amount, rejected by the burn, is rejected.
+        let amount = U256::from(50_000_000u64);
+        assert!(IcrcBridgeOpImpl::required_allowance(&amount, &token).is_err());
+
+        // Allowance should fit into U256.
+        let err = IcrcBridgeOpImpl::required_allowance(&U256::max_value(), &token).unwrap_err();
+        assert!(matches!(err, Error::InvalidBurnAmount(_)));
+    }
+
+    #[test]
+    fn operation_direction() {
+        let mint = IcrcBridgeOpImpl(IcrcBridgeOp::MintIcrcTokens {
+            event: burnt_event(),
+            pending_since: 0,
+        });
+        let confirmed = IcrcBridgeOpImpl(IcrcBridgeOp::IcrcMintConfirmed {
+            src_address: H160::from_slice(&[1; 20]),
+            icrc_tx_id: 1u64.into(),
+            icrc_memo: None,
+        });
+
+        assert_eq!(
+            sign_mint_order(false, 0).direction(),
+            Some(OperationDirection::Deposit)
+        );
+        assert_eq!(
+            sign_mint_order(true, 0).direction(),
+            Some(OperationDirection::Withdrawal)
+        );
+        assert_eq!(mint.direction(), Some(OperationDirection::Withdrawal));
+        assert_eq!(confirmed.direction(), None);
+    }
+
+    #[test]
+    fn deposit_is_cancelled_only_before_burn() {
+        MockContext::new().inject();
+        let burn = Icrc2Burn {
+            sender: Principal::from_slice(&[7; 29]),
+            amount: 1000u64.into(),
+            icrc2_token_principal: Principal::from_slice(&[5; 29]),
+            erc20_token_address: H160::from_slice(&[8; 20]),
+            from_subaccount: None,
+            recipient_address: H160::from_slice(&[9; 20]),
+            approve_after_mint: None,
+            fee_payer: None,
+            confirmation_delay_secs: None,
+            preference_fields: None,
+        };
+        let id = OperationId::new(1);
+        let deposit = IcrcBridgeOpImpl(IcrcBridgeOp::BurnIcrc2Tokens {
+            burn: burn.clone(),
+            pending_since: 0,
+        });
+        assert_eq!(
+            deposit.token(),
+            Some(Id256::from(&burn.icrc2_token_principal))
+        );
+
+        let cancelled = deposit.cancel(id).unwrap();
+        assert!(cancelled.is_complete());
+        assert!(matches!(
+            cancelled.0,
+            IcrcBridgeOp::DepositAborted { expired: false, .. }
+        ));
+
+        get_icrc_state().borrow_mut().burn_intents.get_or_insert(
+            id,
+            BurnIntent::new(burn.icrc2_token_principal, 1000u64.into(), 0),
+        );
+        assert!(deposit.cancel(id).is_none());
+        assert!(sign_mint_order(false, 0).cancel(id).is_none());
+    }
+
+    #[test]
+    fn only_failed_mint_releases_refund() {
+        let pending = IcrcBridgeOpImpl(IcrcBridgeOp::MintIcrcTokens {
+            event: burnt_event(),
+            pending_since: 0,
+        });
+        let confirmed = IcrcBridgeOpImpl(IcrcBridgeOp::IcrcMintConfirmed {
+            src_address: H160::from_slice(&[1; 20]),
+            icrc_tx_id: 1u64.into(),
+            icrc_memo: None,
+        });
+        let failed = IcrcBridgeOpImpl(IcrcBridgeOp::IcrcMintFailed {
+            src_address: H160::from_slice(&[1; 20]),
+            reason: "canister is stopped".into(),
+        });
+
+        assert!(!pending.releases_dependents());
+        assert!(!confirmed.releases_dependents());
+        assert!(failed.releases_dependents());
+    }
+
+    #[test]
+    fn mint_with_unknown_result_is_never_refunded() {
+        MockContext::new().inject();
+        let id = OperationId::new(1);
+        let progress = |error| IcrcBridgeOpImpl::mint_error_progress(id, burnt_event(), error);
+
+        for unknown in [
+            IcrcCanisterError::CandidFailed(candid::Error::msg("invalid reply")),
+            IcrcCanisterError::Generic("reply is lost".into()),
+            IcrcCanisterError::CanisterError(RejectionCode::SysFatal, "fatal".into()),
+            IcrcCanisterError::CanisterError(RejectionCode::CanisterReject, "rejected".into()),
+            IcrcCanisterError::CanisterError(RejectionCode::Unknown, "unknown".into()),
+        ] {
+            assert!(matches!(progress(unknown), Err(Error::CannotProgress(_))));
+        }
+
+        let transient =
+            IcrcCanisterError::CanisterError(RejectionCode::SysTransient, "busy".into());
+        assert!(matches!(progress(transient), Err(Error::Custom { .. })));
+
+        for not_applied in [
+            IcrcCanisterError::TransferFailed(TransferError::BadBurn {
+                min_burn_amount: 1u64.into(),
+            }),
+            IcrcCanisterError::CanisterError(RejectionCode::CanisterError, "trapped".into()),
+            IcrcCanisterError::CanisterError(RejectionCode::DestinationInvalid, "stopped".into()),
+            IcrcCanisterError::InvalidTransfer("effective amount is zero".into()),
+        ] {
+            let Ok(OperationProgress::ProgressWithDependent { dependent, .. }) =
+                progress(not_applied)
+            else {
+                panic!("mint, which is not applied, should be refunded");
+            };
+            assert!(matches!(dependent.0, IcrcBridgeOp::RefundMint { .. }));
+        }
+    }
+
+    #[test]
+    fn operations_follow_their_flow() {
+        let pending = IcrcBridgeOpImpl(IcrcBridgeOp::MintIcrcTokens {
+            event: burnt_event(),
+            pending_since: 0,
+        });
+        let failed = IcrcBridgeOpImpl(IcrcBridgeOp::IcrcMintFailed {
+            src_address: H160::from_slice(&[1; 20]),
+            reason: "canister is stopped".into(),
+        });
+        let refund = IcrcBridgeOpImpl(IcrcBridgeOp::RefundMint {
+            original_op_id: OperationId::new(42),
+            event: burnt_event(),
+            pending_since: 0,
+        });
+
+        assert!(IcrcBridgeOpImpl::can_transition(&pending, &failed));
+        assert!(IcrcBridgeOpImpl::can_transition(
+            &refund,
+            &sign_mint_order(true, 0)
+        ));
+        assert!(IcrcBridgeOpImpl::can_transition(
+            &sign_mint_order(false, 0),
+            &sign_mint_order(false, 1)
+        ));
+        // Burnt deposit of the failed bundle is refunded.
+        assert!(IcrcBridgeOpImpl::can_transition(
+            &sign_mint_order(false, 0),
+            &pending
+        ));
+
+        assert!(!IcrcBridgeOpImpl::can_transition(
+            &refund,
+            &sign_mint_order(false, 0)
+        ));
+        assert!(!IcrcBridgeOpImpl::can_transition(
+            &pending,
+            &sign_mint_order(true, 0)
+        ));
+        assert!(!IcrcBridgeOpImpl::can_transition(
+            &sign_mint_order(true, 0),
+            &pending
+        ));
+        assert!(!IcrcBridgeOpImpl::can_transition(
+            &sign_mint_order(false, 0),
+            &failed
+        ));
+    }
+
+    #[test]
+    fn approval_delivery_follows_its_flow() {
+        let approval = IcrcApproval {
+            holder: AccountId256::new(Principal::from_slice(&[2; 29]), Some([3; 32])),
+            spender: AccountId256::new(Principal::from_slice(&[4; 29]), None),
+            amount: 970u64.into(),
+            expires_at: 42,
+        };
+        let pending = IcrcBridgeOpImpl(IcrcBridgeOp::MintIcrcTokens {
+            event: burnt_event(),
+            pending_since: 0,
+        });
+        let approving = IcrcBridgeOpImpl(IcrcBridgeOp::ApproveIcrcTokens {
+            event: burnt_event(),
+            approval: approval.clone(),
+            pending_since: 0,
+        });
+        let granted = IcrcBridgeOpImpl(IcrcBridgeOp::IcrcApprovalGranted {
+            src_address: H160::from_slice(&[1; 20]),
+            approval: approval.clone(),
+            icrc_tx_id: 1u64.into(),
+        });
+        let sweep = IcrcBridgeOpImpl(IcrcBridgeOp::SweepIcrcApproval {
+            original_op_id: OperationId::new(42),
+            event: burnt_event(),
+            approval,
+            pending_since: 0,
+        });
+        let swept = IcrcBridgeOpImpl(IcrcBridgeOp::IcrcApprovalSwept {
+            src_address: H160::from_slice(&[1; 20]),
+            icrc_tx_id: 2u64.into(),
+        });
+        let claimed = IcrcBridgeOpImpl(IcrcBridgeOp::IcrcApprovalClaimed {
+            src_address: H160::from_slice(&[1; 20]),
+        });
+
+        assert!(IcrcBridgeOpImpl::can_transition(&pending, &approving));
+        assert!(IcrcBridgeOpImpl::can_transition(&approving, &granted));
+        assert!(IcrcBridgeOpImpl::can_transition(&sweep, &swept));
+        assert!(IcrcBridgeOpImpl::can_transition(&sweep, &claimed));
+        assert!(!IcrcBridgeOpImpl::can_transition(&pending, &granted));
+        assert!(!IcrcBridgeOpImpl::can_transition(&granted, &sweep));
+
+        // Withdrawal completes, when the approval is granted.
+        assert!(!approving.is_complete());
+        assert!(granted.is_complete());
+        assert!(!sweep.is_complete());
+        assert!(swept.is_complete() && claimed.is_complete());
+
+        // Only returned tokens release the refund.
+        assert!(swept.releases_dependents());
+        assert!(!claimed.releases_dependents());
+        assert!(!granted.releases_dependents());
+    }
+
+    #[test]
+    fn refund_depends_on_original_operation() {
+        let original_op_id = OperationId::new(42);
+        let refund = IcrcBridgeOpImpl(IcrcBridgeOp::RefundMint {
+            original_op_id,
+            event: burnt_event(),
+            pending_since: 0,
+        });
+
+        assert_eq!(refund.dependency(), Some(original_op_id));
+    }
+
+    #[test]
+    fn operations_exceeding_sla() {
+        const SECOND: u64 = 1_000_000_000;
+        let max_age = Duration::from_secs(60);
+
+        let pending = |pending_since| {
+            IcrcBridgeOpImpl(IcrcBridgeOp::MintIcrcTokens {
+                event: burnt_event(),
+                pending_since,
+            })
+        };
+        let confirmed = IcrcBridgeOpImpl(IcrcBridgeOp::IcrcMintConfirmed {
+            src_address: H160::from_slice(&[1; 20]),
+            icrc_tx_id: 1u64.into(),
+            icrc_memo: None,
+        });
+
+        let now = 1000 * SECOND;
+        assert!(pending(now - 61 * SECOND).exceeds_sla(now, max_age));
+        assert!(!pending(now - 60 * SECOND).exceeds_sla(now, max_age));
+        assert!(!pending(now).exceeds_sla(now, max_age));
+        // Operations created after `now` never exceed SLA.
+        assert!(!pending(now + SECOND).exceeds_sla(now, max_age));
+        assert!(!confirmed.exceeds_sla(now, max_age));
+    }
+
+    #[tokio::test]
+    async fn incomplete_operations_exceeding_sla_are_selected() {
+        let context = MockContext::new().inject();
+
+        let runtime: BridgeRuntime<IcrcBridgeOpImpl> = BridgeRuntime::default(ConfigStorage::get());
+        let state = runtime.state().clone();
+
+        let created_at = ic::time();
+        let original = IcrcBridgeOpImpl(IcrcBridgeOp::MintIcrcTokens {
+            event: burnt_event(),
+            pending_since: created_at,
+        });
+        let original_id = state.borrow_mut().operations.new_operation(original, None);
+
+        context.add_time(120 * 1_000_000_000);
+        let refund = IcrcBridgeOpImpl(IcrcBridgeOp::RefundMint {
+            original_op_id: original_id,
+            event: burnt_event(),
+            pending_since: ic::time(),
+        });
+        let refund_id = state.borrow_mut().operations.new_operation(refund, None);
+
+        let now = ic::time();
+        let max_age = Duration::from_secs(60);
+        let exceeding: Vec<_> = state
+            .borrow()
+            .operations
+            .get_incomplete()
+            .into_iter()
+            .filter(|(_, op)| op.exceeds_sla(now, max_age))
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(exceeding, vec![original_id]);
+
+        context.add_time(61 * 1_000_000_000);
+        let now = ic::time();
+        let exceeding: Vec<_> = state
+            .borrow()
+            .operations
+            .get_incomplete()
+            .into_iter()
+            .filter(|(_, op)| op.exceeds_sla(now, max_age))
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(exceeding, vec![original_id, refund_id]);
+    }
+
+    #[test]
+    fn sent_mint_tx_is_linked_to_evm_params_snapshot() {
+        MockContext::new().inject();
+
+        let runtime: BridgeRuntime<IcrcBridgeOpImpl> = BridgeRuntime::default(ConfigStorage::get());
+        let state = runtime.state().clone();
+
+        let orders = SignedOrdersData {
+            orders_data: vec![0; MintOrder::ENCODED_DATA_SIZE],
+            signature: vec![0; SIGNATURE_LEN],
+        };
+        let op = IcrcBridgeOpImpl(IcrcBridgeOp::SendMintTransaction {
+            order: SignedOrders::new(orders, 0).unwrap(),
+            is_refund: false,
+            pending_since: 0,
+        });
+        let id = state.borrow_mut().operations.new_operation(op, None);
+
+        let tx_hash = H256::from_slice(&[1; 32]);
+        IcrcMintTxHandler::new(state.clone()).mint_tx_sent(id, tx_hash, Some(3));
+
+        let log = state.borrow().operations.get_log(id).unwrap();
+        let step = log.log().last().unwrap();
+        assert!(matches!(
+            step.step_result,
+            Ok(IcrcBridgeOpImpl(IcrcBridgeOp::ConfirmMint { .. }))
+        ));
+        assert_eq!(step.evm_params_snapshot, Some(3));
+        assert_eq!(log.log()[0].evm_params_snapshot, None);
+    }
+
+    #[test]
+    fn dropped_mint_tx_is_sent_again() {
+        let context = MockContext::new().inject();
+
+        let runtime: BridgeRuntime<IcrcBridgeOpImpl> = BridgeRuntime::default(ConfigStorage::get());
+        let state = runtime.state().clone();
+        let handler = IcrcDroppedMintTxHandler::new(state.clone(), runtime.scheduler().clone());
+
+        let orders = SignedOrdersData {
+            orders_data: vec![0; MintOrder::ENCODED_DATA_SIZE],
+            signature: vec![0; SIGNATURE_LEN],
+        };
+        let order = SignedOrders::new(orders, 0).unwrap();
+        let tx_hash = H256::from_slice(&[1; 32]);
+        let sent_at = ic::time();
+        let confirm_mint = |tx_hash| {
+            IcrcBridgeOpImpl(IcrcBridgeOp::ConfirmMint {
+                order: order.clone(),
+                tx_hash,
+                is_refund: false,
+                pending_since: sent_at,
+            })
+        };
+        let sent = state
+            .borrow_mut()
+            .operations
+            .new_operation(confirm_mint(Some(tx_hash.clone())), None);
+        state.borrow_mut().operations.update_with_evm_params(
+            sent,
+            confirm_mint(Some(tx_hash.clone())),
+            Some(3),
+        );
+        // Mint transaction, which should be sent by the user, is never checked.
+        state
+            .borrow_mut()
+            .operations
+            .new_operation(confirm_mint(None), None);
+
+        assert!(handler.get_unconfirmed_mint_txs(sent_at).is_empty());
+        context.add_time(1);
+        assert_eq!(
+            handler.get_unconfirmed_mint_txs(ic::time()),
+            vec![(sent, tx_hash.clone(), Some(3))]
+        );
+
+        // Operation, which mint transaction is already re-sent, is not updated.
+        handler.mint_tx_dropped(sent, H256::from_slice(&[2; 32]));
+        assert!(matches!(
+            state.borrow().operations.get(sent).unwrap().0,
+            IcrcBridgeOp::ConfirmMint { .. }
+        ));
+
+        handler.mint_tx_dropped(sent, tx_hash);
+        assert!(matches!(
+            state.borrow().operations.get(sent).unwrap().0,
+            IcrcBridgeOp::SendMintTransaction {
+                is_refund: false,
+                ..
+            }
+        ));
+        assert_eq!(
+            IcrcMintTxHandler::new(state.clone())
+                .get_signed_orders(sent)
+                .map(|order| order.into_inner().digest()),
+            Some(order.into_inner().digest())
+        );
+        assert!(handler.get_unconfirmed_mint_txs(ic::time()).is_empty());
+    }
+
+    #[test]
+    fn legacy_operation_log_gets_pending_since_of_its_steps() {
+        let context = MockContext::new().inject();
+
+        let mut log = OperationLog::<IcrcBridgeOpV1>::new(
+            IcrcBridgeOpV1::MintIcrcTokens(burnt_event()),
+            H160::from_slice(&[1; 20]),
+            None,
+        );
+        let created_at = ic::time();
+        context.add_time(1_000);
+        log.add_step(Ok(IcrcBridgeOpV1::RefundMint {
+            original_op_id: OperationId::new(1),
+            event: burnt_event(),
+        }));
+
+        let log = migrate_legacy_operation_log(log);
+
+        let steps: Vec<_> = log
+            .log()
+            .iter()
+            .map(|entry| entry.step_result.as_ref().unwrap().0.pending_since())
+            .collect();
+        assert_eq!(steps, vec![Some(created_at), Some(created_at + 1_000)]);
+    }
+
+    #[test]
+    fn operation_log_with_legacy_mint_order_is_decoded() {
+        MockContext::new().inject();
+
+        let legacy_order = MintOrderV1 {
+            amount: 1000u64.into(),
+            sender: Id256::from(&Principal::anonymous()),
+            src_token: Id256::from(&Principal::anonymous()),
+            recipient: H160::from_slice(&[1; 20]),
+            dst_token: H160::from_slice(&[2; 20]),
+            nonce: 7,
+            sender_chain_id: 0,
+            recipient_chain_id: 5,
+            name: [0; 32],
+            symbol: [0; 16],
+            decimals: 8,
+            approve_spender: H160::default(),
+            approve_amount: U256::zero(),
+            fee_payer: H160::default(),
+        };
+
+        // Signed orders of the legacy layout, as they were stored before the change.
+        #[derive(CandidType)]
+        struct LegacySignedOrders {
+            all_orders: SignedOrdersData,
+            idx: usize,
+        }
+        let legacy_signed = LegacySignedOrders {
+            all_orders: SignedOrdersData {
+                orders_data: legacy_order.encode().to_vec(),
+                signature: vec![0; SIGNATURE_LEN],
+            },
+            idx: 0,
+        };
+        let signed_order: SignedOrders =
+            candid::decode_one(&candid::encode_one(legacy_signed).unwrap()).unwrap();
+
+        let mut log = OperationLog::new(
+            IcrcBridgeOpV1::SignMintOrder {
+                order: legacy_order.clone(),
+                is_refund: false,
+            },
+            H160::from_slice(&[1; 20]),
+            None,
+        );
+        log.add_step(Ok(IcrcBridgeOpV1::<MintOrderV1>::ConfirmMint {
+            order: signed_order,
+            tx_hash: None,
+            is_refund: false,
+        }));
+        let bytes = log.to_uncompressed_bytes();
+
+        assert!(OperationLog::<IcrcBridgeOpImpl>::from_versioned_bytes(&bytes).is_err());
+        let log = IcrcBridgeOpImpl::decode_legacy_log(&bytes).unwrap();
+
+        assert_eq!(log.log().len(), 3);
+        let IcrcBridgeOp::SignMintOrder {
+            order, is_refund, ..
+        } = &log.current_step().0
+        else {
+            panic!("unexpected state: {:?}", log.current_step());
+        };
+        assert_eq!(*order, MintOrder::from(legacy_order));
+        assert_eq!(order.recipient_chain_id, 5);
+        assert!(!is_refund);
     }
 }
@@ -0,0 +1,272 @@
+//! Cancellation of the approval after mint, requested by the deposit sender.
+//!
+//! The approval is cancelled until the mint order is taken for signing. A burn task could
+//! be in flight while the approval is cancelled, so the cancellation is also recorded,
+//! and the approval is removed from the mint order before it is signed.
+
+use std::time::Duration;
+
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::id256::Id256;
+use bridge_did::op_id::OperationId;
+use bridge_did::operations::IcrcBridgeOp;
+use bridge_did::order::MintOrder;
+use bridge_did::reason::Icrc2Burn;
+use candid::Principal;
+use did::{H160, U256};
+use ic_stable_structures::BTreeMapStructure;
+
+use crate::canister::get_icrc_state;
+
+/// Returns the state of the deposit without the approval after mint, cancelled by the `caller`.
+///
+/// The approval of the mint order is cancelled only while the order waits for the signing
+/// delay, which ends `signing_delay_secs` after the burn, at the `now` IC time.
+pub fn cancel(
+    op: IcrcBridgeOp,
+    caller: Principal,
+    now: u64,
+    signing_delay_secs: u64,
+) -> BTFResult<IcrcBridgeOp> {
+    match op {
+        IcrcBridgeOp::PendingUserConfirmation {
+            burn,
+            expires_at,
+            pending_since,
+        } => Ok(IcrcBridgeOp::PendingUserConfirmation {
+            burn: cancel_burn_approval(burn, caller)?,
+            expires_at,
+            pending_since,
+        }),
+        IcrcBridgeOp::BurnIcrc2Tokens {
+            burn,
+            pending_since,
+        } => Ok(IcrcBridgeOp::BurnIcrc2Tokens {
+            burn: cancel_burn_approval(burn, caller)?,
+            pending_since,
+        }),
+        IcrcBridgeOp::SignMintOrder {
+            mut order,
+            is_refund: false,
+            pending_since,
+        } => {
+            if order.sender != Id256::from(&caller) {
+                return Err(Error::AccessDenied);
+            }
+
+            let delay = Duration::from_secs(signing_delay_secs).as_nanos() as u64;
+            if now >= pending_since.saturating_add(delay) {
+                return Err(Error::FailedToProgress(
+                    "mint order is already taken for signing".into(),
+                ));
+            }
+
+            if !clear_order_approval(&mut order) {
+                return Err(Error::FailedToProgress(
+                    "deposit has no approval after mint".into(),
+                ));
+            }
+
+            Ok(IcrcBridgeOp::SignMintOrder {
+                order,
+                is_refund: false,
+                pending_since,
+            })
+        }
+        _ => Err(Error::FailedToProgress(
+            "approval can't be cancelled after the mint order is signed".into(),
+        )),
+    }
+}
+
+fn cancel_burn_approval(mut burn: Icrc2Burn, caller: Principal) -> BTFResult<Icrc2Burn> {
+    if burn.sender != caller {
+        return Err(Error::AccessDenied);
+    }
+
+    if burn.approve_after_mint.take().is_none() {
+        return Err(Error::FailedToProgress(
+            "deposit has no approval after mint".into(),
+        ));
+    }
+
+    Ok(burn)
+}
+
+/// Zeroes the approve fields of the mint order. Returns `false` if the order has no approval.
+pub fn clear_order_approval(order: &mut MintOrder) -> bool {
+    let has_approval = order.approve_spender != H160::default() || order.permit.is_some();
+    order.approve_spender = H160::default();
+    order.approve_amount = U256::zero();
+    order.permit = None;
+
+    has_approval
+}
+
+/// Records the approval cancellation of the operation at the `now` IC time.
+pub fn record(id: OperationId, now: u64) {
+    get_icrc_state()
+        .borrow_mut()
+        .approval_cancellations
+        .insert(id, now);
+}
+
+/// Removes the cancellation record of the operation. Returns the mint order without
+/// the approval, if the approval was cancelled, but the order still has it.
+pub fn take_cancelled(id: OperationId, order: &MintOrder) -> Option<MintOrder> {
+    get_icrc_state()
+        .borrow_mut()
+        .approval_cancellations
+        .remove(&id)?;
+
+    let mut order = order.clone();
+    clear_order_approval(&mut order).then_some(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::order::Erc20Permit;
+    use bridge_did::reason::ApproveAfterMint;
+    use did::H256;
+    use ic_exports::ic_kit::MockContext;
+
+    use super::*;
+    use crate::constant::IC_CHAIN_ID;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    fn sender() -> Principal {
+        Principal::from_slice(&[1; 29])
+    }
+
+    fn approve() -> ApproveAfterMint {
+        ApproveAfterMint {
+            approve_spender: H160::from_slice(&[5; 20]),
+            approve_amount: 500u64.into(),
+            permit: None,
+        }
+    }
+
+    fn burn() -> Icrc2Burn {
+        Icrc2Burn {
+            sender: sender(),
+            amount: 1000u64.into(),
+            icrc2_token_principal: Principal::from_slice(&[2; 29]),
+            erc20_token_address: H160::from_slice(&[3; 20]),
+            from_subaccount: None,
+            recipient_address: H160::from_slice(&[4; 20]),
+            approve_after_mint: Some(approve()),
+            fee_payer: None,
+            confirmation_delay_secs: None,
+            preference_fields: None,
+        }
+    }
+
+    fn order() -> MintOrder {
+        let approve = approve();
+        MintOrder {
+            amount: 1000u64.into(),
+            sender: Id256::from(&sender()),
+            src_token: Id256::from(&Principal::from_slice(&[2; 29])),
+            recipient: H160::from_slice(&[4; 20]),
+            dst_token: H160::from_slice(&[3; 20]),
+            nonce: 0,
+            sender_chain_id: IC_CHAIN_ID,
+            recipient_chain_id: 5,
+            name: [0; 32],
+            symbol: [0; 16],
+            decimals: 8,
+            approve_spender: approve.approve_spender,
+            approve_amount: approve.approve_amount,
+            fee_payer: H160::default(),
+            permit: Some(Erc20Permit {
+                value: 500u64.into(),
+                deadline: 100,
+                v: 27,
+                r: H256::from_slice(&[6; 32]),
+                s: H256::from_slice(&[7; 32]),
+            }),
+        }
+    }
+
+    fn sign_mint_order(pending_since: u64) -> IcrcBridgeOp {
+        IcrcBridgeOp::SignMintOrder {
+            order: order(),
+            is_refund: false,
+            pending_since,
+        }
+    }
+
+    fn assert_no_approval(order: &MintOrder) {
+        assert_eq!(order.approve_spender, H160::default());
+        assert_eq!(order.approve_amount, U256::zero());
+        assert_eq!(order.permit, None);
+    }
+
+    #[test]
+    fn approval_is_removed_from_the_burn() {
+        let op = IcrcBridgeOp::BurnIcrc2Tokens {
+            burn: burn(),
+            pending_since: 0,
+        };
+        let IcrcBridgeOp::BurnIcrc2Tokens { burn, .. } = cancel(op, sender(), 0, 0).unwrap() else {
+            panic!("unexpected state");
+        };
+        assert!(burn.approve_after_mint.is_none());
+
+        // The approval is cancelled once.
+        let op = IcrcBridgeOp::BurnIcrc2Tokens {
+            burn,
+            pending_since: 0,
+        };
+        assert!(matches!(
+            cancel(op, sender(), 0, 0),
+            Err(Error::FailedToProgress(_))
+        ));
+    }
+
+    #[test]
+    fn approval_is_cancelled_only_by_the_sender() {
+        let op = IcrcBridgeOp::PendingUserConfirmation {
+            burn: burn(),
+            expires_at: 10,
+            pending_since: 0,
+        };
+        let other = Principal::from_slice(&[9; 29]);
+        assert!(matches!(cancel(op, other, 0, 0), Err(Error::AccessDenied)));
+        assert!(matches!(
+            cancel(sign_mint_order(0), other, 0, 30),
+            Err(Error::AccessDenied)
+        ));
+    }
+
+    #[test]
+    fn mint_order_carries_zero_approve_fields() {
+        let op = cancel(sign_mint_order(0), sender(), 29 * SECOND, 30).unwrap();
+        let IcrcBridgeOp::SignMintOrder { order, .. } = op else {
+            panic!("unexpected state");
+        };
+        assert_no_approval(&order);
+        assert_eq!(order.amount, 1000u64.into());
+
+        // After the signing delay the order could be already signed.
+        assert!(matches!(
+            cancel(sign_mint_order(0), sender(), 30 * SECOND, 30),
+            Err(Error::FailedToProgress(_))
+        ));
+    }
+
+    #[test]
+    fn recorded_cancellation_is_applied_to_the_order() {
+        MockContext::new().inject();
+        let id = OperationId::new(1);
+        assert_eq!(take_cancelled(id, &order()), None);
+
+        record(id, 0);
+        let cleared = take_cancelled(id, &order()).unwrap();
+        assert_no_approval(&cleared);
+
+        // The record is removed once applied.
+        assert_eq!(take_cancelled(id, &order()), None);
+    }
+}
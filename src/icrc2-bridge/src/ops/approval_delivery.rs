@@ -0,0 +1,694 @@
+//! Delivery of the withdrawn ICRC tokens with an approval.
+//!
+//! Instead of the transfer to the recipient, the tokens are transferred to a holder subaccount
+//! of the bridge, derived from the operation id, and the spender is approved to transfer them
+//! until the approval expires. The withdrawal completes, when the approval is granted.
+//!
+//! Granted approvals are tracked until they expire. Then a sweep-back operation returns
+//! the unclaimed tokens to the bridge account and refunds the burnt wrapped tokens
+//! proportionally to the returned amount. The ledger fees of the delivery are not refunded.
+
+use std::time::Duration;
+
+use bridge_canister::bridge::{Operation, OperationProgress};
+use bridge_canister::memory::StableMemory;
+use bridge_canister::runtime::scheduler::SharedScheduler;
+use bridge_canister::runtime::service::BridgeService;
+use bridge_canister::runtime::RuntimeState;
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::event_data::BurntEventData;
+use bridge_did::id256::AccountId256;
+use bridge_did::op_id::OperationId;
+use bridge_did::operations::{IcrcApproval, IcrcBridgeOp};
+use candid::{Nat, Principal};
+use did::U256;
+use ic_exports::ic_kit::ic;
+use ic_stable_structures::CellStructure;
+use ic_task_scheduler::scheduler::TaskScheduler;
+use ic_task_scheduler::task::ScheduledTask;
+use icrc_client::account::{Account, Subaccount};
+use icrc_client::transfer::TransferError;
+
+use super::supply_guard::nat_to_u256;
+use super::{ErrorCodes, IcrcBridgeOpImpl};
+use crate::canister::get_icrc_state;
+use crate::constant::APPROVE_DELIVERY_STANDARDS;
+use crate::tokens::capabilities;
+use crate::tokens::icrc1::{self, IcrcCanisterError};
+use crate::tokens::icrc2;
+
+/// Delay between the checks of the withdrawal approvals expiration.
+pub const SWEEP_APPROVALS_DELAY: Duration = Duration::from_secs(60);
+
+/// Mark of the holder subaccounts. Deposit spender subaccounts contain an EVM address
+/// followed by zeros, so the mark keeps the holder subaccounts distinct from them.
+const HOLDER_SUBACCOUNT_MARK: &[u8; 4] = b"aprv";
+
+/// Number of the ledger fees, spent by the delivery with an approval and its sweep-back:
+/// the transfer to the holder, the approval and the transfer back to the bridge.
+const DELIVERY_FEES: u64 = 3;
+
+/// Returns the subaccount of the bridge, which holds the tokens of the `id` withdrawal approval.
+pub fn holder_subaccount(id: OperationId) -> Subaccount {
+    let mut subaccount = [0; 32];
+    subaccount[20..24].copy_from_slice(HOLDER_SUBACCOUNT_MARK);
+    subaccount[24..].copy_from_slice(&id.as_u64().to_be_bytes());
+    subaccount
+}
+
+/// Checks that the withdrawal of the `amount` can be delivered with an approval.
+///
+/// The token ledger should support ICRC-2, and the amount should cover the `fee` of the
+/// transfer to the holder, of the approval and of the transfer by the spender.
+pub async fn validate(token: Principal, amount: &Nat, fee: &Nat) -> BTFResult<()> {
+    capabilities::check_ledger(token, APPROVE_DELIVERY_STANDARDS, false).await?;
+    check_amount(amount, fee)
+}
+
+fn check_amount(amount: &Nat, fee: &Nat) -> BTFResult<()> {
+    let fees = fee.clone() * Nat::from(DELIVERY_FEES);
+    if *amount <= fees {
+        return Err(Error::InvalidBurnAmount(format!(
+            "amount {amount} should exceed the approve delivery fees {fees}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the approval of the `held` tokens, transferred to the holder subaccount of
+/// the `id` withdrawal at the `now` IC time. The holder pays the approve fee, and keeps
+/// the fee of the transfer by the spender.
+pub fn new_approval(
+    id: OperationId,
+    spender: AccountId256,
+    held: Nat,
+    fee: &Nat,
+    now: u64,
+) -> IcrcApproval {
+    let fees = fee.clone() * Nat::from(2u64);
+    let amount = if held > fees {
+        held - fees
+    } else {
+        Nat::from(0u64)
+    };
+    let expiry_secs = *get_icrc_state()
+        .borrow()
+        .approval_delivery_expiry_secs
+        .get();
+
+    IcrcApproval {
+        holder: AccountId256::new(ic::id(), Some(holder_subaccount(id))),
+        spender,
+        amount,
+        expires_at: now.saturating_add(Duration::from_secs(expiry_secs).as_nanos() as u64),
+    }
+}
+
+/// Grants the approval of the `id` withdrawal and tracks it until it expires.
+///
+/// The approval is deduplicated by the ledger, so it is safe to retry.
+pub async fn approve(
+    id: OperationId,
+    event: BurntEventData,
+    approval: IcrcApproval,
+    pending_since: u64,
+) -> BTFResult<IcrcBridgeOp> {
+    let (token, _) = IcrcBridgeOpImpl::decode_burnt_event(&event)?;
+    let spender = Account {
+        owner: approval.spender.principal,
+        subaccount: approval.spender.subaccount,
+    };
+
+    let icrc_tx_id = icrc2::approve(
+        token,
+        holder(&approval),
+        spender,
+        approval.amount.clone(),
+        approval.expires_at,
+        pending_since,
+    )
+    .await
+    .map_err(|e| Error::Custom {
+        code: ErrorCodes::IcrcApproveFailed as _,
+        msg: format!("ICRC tokens approval failed: {e}"),
+    })?;
+
+    get_icrc_state()
+        .borrow_mut()
+        .approval_deliveries
+        .insert(id, approval.expires_at);
+    log::info!(
+        "Withdrawal {id} is delivered with approval to {spender:?} until {}",
+        approval.expires_at
+    );
+
+    Ok(IcrcBridgeOp::IcrcApprovalGranted {
+        src_address: event.sender,
+        approval,
+        icrc_tx_id,
+    })
+}
+
+/// Returns the unclaimed tokens of the expired approval to the bridge account.
+///
+/// The transfer is deduplicated by the ledger, so it is safe to retry.
+pub async fn sweep(
+    id: OperationId,
+    original_op_id: OperationId,
+    event: BurntEventData,
+    approval: IcrcApproval,
+    pending_since: u64,
+) -> BTFResult<OperationProgress<IcrcBridgeOpImpl>> {
+    let (token, _) = IcrcBridgeOpImpl::decode_burnt_event(&event)?;
+    let fee = icrc1::get_token_configuration(token)
+        .await
+        .map_err(|e| Error::Custom {
+            code: ErrorCodes::IcrcMetadataRequestFailed as _,
+            msg: format!("failed to query Icrc token configuration: {e}"),
+        })?
+        .fee;
+    let result = icrc2::sweep(
+        token,
+        holder(&approval),
+        approval.amount.clone(),
+        pending_since,
+    )
+    .await;
+
+    sweep_progress(
+        id,
+        original_op_id,
+        event,
+        approval,
+        pending_since,
+        &fee,
+        result,
+    )
+}
+
+/// If the tokens are returned, the sweep-back releases the refund of the burnt wrapped tokens.
+///
+/// If the holder has not enough tokens, the approval was partially or fully used by
+/// the spender. The rest of the holder balance, except the transfer fee, is swept back
+/// with the next step, and the refund is reduced proportionally. If the balance doesn't
+/// exceed the fee, nothing is refunded, and the balance is left to the holder.
+///
+/// The `event` amount of the sweep-back covers the approved amount and the delivery fees,
+/// so the fees are deducted from the refund.
+fn sweep_progress(
+    id: OperationId,
+    original_op_id: OperationId,
+    mut event: BurntEventData,
+    approval: IcrcApproval,
+    pending_since: u64,
+    fee: &Nat,
+    result: Result<Nat, IcrcCanisterError>,
+) -> BTFResult<OperationProgress<IcrcBridgeOpImpl>> {
+    let fees = fee.clone() * Nat::from(DELIVERY_FEES);
+    match result {
+        Ok(icrc_tx_id) => {
+            let refund_amount = proportion(
+                &event.amount,
+                &approval.amount,
+                &(approval.amount.clone() + fees),
+            );
+            let swept = IcrcBridgeOpImpl(IcrcBridgeOp::IcrcApprovalSwept {
+                src_address: event.sender.clone(),
+                icrc_tx_id,
+            });
+            if refund_amount.0.is_zero() {
+                log::info!(
+                    "Unclaimed tokens of the sweep-back {id} are returned, nothing to refund"
+                );
+                return Ok(OperationProgress::Progress(swept));
+            }
+
+            log::info!(
+                "Unclaimed tokens of the sweep-back {id} are returned, refunding {}...",
+                refund_amount.0
+            );
+            event.amount = refund_amount;
+            let refund = IcrcBridgeOp::RefundMint {
+                original_op_id: id,
+                event,
+                pending_since: ic::time(),
+            };
+
+            Ok(OperationProgress::ProgressWithDependent {
+                update_to: swept,
+                dependent: IcrcBridgeOpImpl(refund),
+            })
+        }
+        Err(IcrcCanisterError::TransferFailed(TransferError::InsufficientFunds { balance }))
+            if balance > *fee =>
+        {
+            let unclaimed = balance - fee.clone();
+            log::info!(
+                "Approval of the sweep-back {id} is partially claimed, sweeping {unclaimed} back"
+            );
+
+            // The event amount keeps covering the approved amount and the delivery fees.
+            event.amount = proportion(
+                &event.amount,
+                &(unclaimed.clone() + fees.clone()),
+                &(approval.amount.clone() + fees),
+            );
+            let IcrcApproval {
+                holder,
+                spender,
+                expires_at,
+                ..
+            } = approval;
+
+            // The transfer of the other amount is not a duplicate of the failed one,
+            // so the same `pending_since` keeps the step deduplicated.
+            Ok(OperationProgress::Progress(IcrcBridgeOpImpl(
+                IcrcBridgeOp::SweepIcrcApproval {
+                    original_op_id,
+                    event,
+                    approval: IcrcApproval {
+                        holder,
+                        spender,
+                        amount: unclaimed,
+                        expires_at,
+                    },
+                    pending_since,
+                },
+            )))
+        }
+        Err(IcrcCanisterError::TransferFailed(TransferError::InsufficientFunds { balance })) => {
+            log::info!("Approval of the sweep-back {id} is claimed, holder balance is {balance}");
+            Ok(OperationProgress::Progress(IcrcBridgeOpImpl(
+                IcrcBridgeOp::IcrcApprovalClaimed {
+                    src_address: event.sender,
+                },
+            )))
+        }
+        Err(e) => Err(Error::Custom {
+            code: ErrorCodes::IcrcSweepFailed as _,
+            msg: format!("ICRC tokens sweep-back failed: {e}"),
+        }),
+    }
+}
+
+/// Returns the `part / whole` share of the wrapped `amount`, rounded down.
+fn proportion(amount: &U256, part: &Nat, whole: &Nat) -> U256 {
+    if *whole == 0u64 {
+        return U256::zero();
+    }
+
+    nat_to_u256(&(Nat::from(amount) * part.clone() / whole.clone()))
+}
+
+fn holder(approval: &IcrcApproval) -> Subaccount {
+    approval.holder.subaccount.unwrap_or_default()
+}
+
+/// Service to sweep back the tokens of the expired withdrawal approvals.
+pub struct SweepApprovalsService {
+    state: RuntimeState<IcrcBridgeOpImpl>,
+    scheduler: SharedScheduler<StableMemory, IcrcBridgeOpImpl>,
+}
+
+impl SweepApprovalsService {
+    pub fn new(
+        state: RuntimeState<IcrcBridgeOpImpl>,
+        scheduler: SharedScheduler<StableMemory, IcrcBridgeOpImpl>,
+    ) -> Self {
+        Self { state, scheduler }
+    }
+
+    /// Returns the sweep-back of the granted approval of the `id` withdrawal.
+    fn sweep_operation(&self, id: OperationId) -> Option<IcrcBridgeOp> {
+        let log = self.state.borrow().operations.get_log(id)?;
+        let IcrcBridgeOp::IcrcApprovalGranted { approval, .. } = &log.current_step().0 else {
+            return None;
+        };
+
+        let event = log
+            .log()
+            .iter()
+            .rev()
+            .find_map(|entry| match &entry.step_result {
+                Ok(IcrcBridgeOpImpl(IcrcBridgeOp::ApproveIcrcTokens { event, .. })) => {
+                    Some(event.clone())
+                }
+                _ => None,
+            })?;
+
+        Some(IcrcBridgeOp::SweepIcrcApproval {
+            original_op_id: id,
+            event,
+            approval: approval.clone(),
+            pending_since: ic::time(),
+        })
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl BridgeService for SweepApprovalsService {
+    async fn run(&self) -> BTFResult<()> {
+        let expired = get_icrc_state()
+            .borrow()
+            .approval_deliveries
+            .expired(ic::time());
+        for id in expired {
+            get_icrc_state().borrow_mut().approval_deliveries.remove(id);
+
+            let Some(sweep) = self.sweep_operation(id) else {
+                log::warn!("Expired approval of the withdrawal {id} is not found");
+                continue;
+            };
+
+            let sweep = IcrcBridgeOpImpl(sweep);
+            let sweep_id = self
+                .state
+                .borrow_mut()
+                .operations
+                .new_operation(sweep.clone(), None);
+            log::info!(
+                "Approval of the withdrawal {id} is expired, sweep-back {sweep_id} is created"
+            );
+
+            if let Some(options) = sweep.scheduling_options() {
+                let task = self.state.borrow_mut().new_task(sweep_id, sweep);
+                self.scheduler
+                    .append_task(ScheduledTask::with_options(task, options));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn push_operation(&self, _: OperationId) -> BTFResult<()> {
+        let msg = "Operations should not be pushed to the SweepApprovalsService service";
+        log::warn!("{msg}");
+        Err(Error::FailedToProgress(msg.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_canister::runtime::state::config::ConfigStorage;
+    use bridge_canister::runtime::BridgeRuntime;
+    use bridge_did::icrc_withdrawal::{IcrcWithdrawalRecipient, WithdrawalDelivery};
+    use bridge_did::id256::Id256;
+    use bridge_utils::evm_link::address_to_icrc_subaccount;
+    use did::H160;
+    use ic_exports::ic_kit::MockContext;
+    use ic_storage::IcStorage;
+
+    use super::*;
+    use crate::constant::ICRC1_STANDARD;
+    use crate::state::LedgerCapabilities;
+
+    fn token() -> Principal {
+        Principal::from_slice(&[2; 29])
+    }
+
+    fn spender() -> AccountId256 {
+        AccountId256::new(Principal::from_slice(&[3; 29]), Some([4; 32]))
+    }
+
+    fn event() -> BurntEventData {
+        let recipient = IcrcWithdrawalRecipient::new(Principal::from_slice(&[5; 29]), None)
+            .with_delivery(WithdrawalDelivery::Approve { spender: None });
+        BurntEventData {
+            sender: H160::from_slice(&[1; 20]),
+            amount: 1000u64.into(),
+            to_token: Id256::from(&token()).0.to_vec(),
+            recipient_id: recipient.encode(),
+            ..Default::default()
+        }
+    }
+
+    fn approval(id: OperationId, expires_at: u64) -> IcrcApproval {
+        IcrcApproval {
+            holder: AccountId256::new(ic::id(), Some(holder_subaccount(id))),
+            spender: spender(),
+            amount: 970u64.into(),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn holder_subaccount_is_unique_per_operation() {
+        let holder = holder_subaccount(OperationId::new(1));
+
+        assert_ne!(holder, holder_subaccount(OperationId::new(2)));
+        // Holder subaccounts can't be confused with the deposit spender ones.
+        assert_ne!(holder, address_to_icrc_subaccount(&H160::zero().0));
+    }
+
+    #[test]
+    fn amount_should_cover_approve_delivery_fees() {
+        assert!(check_amount(&31u64.into(), &10u64.into()).is_ok());
+        assert!(matches!(
+            check_amount(&30u64.into(), &10u64.into()),
+            Err(Error::InvalidBurnAmount(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn ledger_without_icrc2_is_rejected_at_validation() {
+        MockContext::new().inject();
+        get_icrc_state().borrow_mut().ledger_capabilities.set(
+            token(),
+            LedgerCapabilities {
+                standards: vec![ICRC1_STANDARD.into()],
+                core_metadata: true,
+            },
+        );
+
+        assert_eq!(
+            validate(token(), &1000u64.into(), &10u64.into()).await,
+            Err(Error::InvalidBurnOperation(
+                "ledger does not support ICRC-2".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn approval_keeps_fees_and_expires_after_configured_time() {
+        MockContext::new().inject();
+        get_icrc_state()
+            .borrow_mut()
+            .approval_delivery_expiry_secs
+            .set(60)
+            .unwrap();
+
+        let id = OperationId::new(7);
+        let approval = new_approval(id, spender(), 990u64.into(), &10u64.into(), 1_000);
+
+        assert_eq!(
+            approval,
+            IcrcApproval {
+                holder: AccountId256::new(ic::id(), Some(holder_subaccount(id))),
+                spender: spender(),
+                amount: 970u64.into(),
+                expires_at: 1_000 + 60_000_000_000,
+            }
+        );
+    }
+
+    fn refund_of(progress: OperationProgress<IcrcBridgeOpImpl>) -> BurntEventData {
+        let OperationProgress::ProgressWithDependent {
+            update_to,
+            dependent,
+        } = progress
+        else {
+            panic!("sweep-back should create the refund");
+        };
+
+        assert!(matches!(
+            &update_to.0,
+            IcrcBridgeOp::IcrcApprovalSwept { icrc_tx_id, .. } if *icrc_tx_id == 42u64
+        ));
+        assert!(update_to.releases_dependents());
+        let IcrcBridgeOp::RefundMint { event, .. } = dependent.0 else {
+            panic!("sweep-back dependent should be the refund");
+        };
+        event
+    }
+
+    #[test]
+    fn unclaimed_tokens_are_swept_back_and_refunded_without_fees() {
+        MockContext::new().inject();
+        let id = OperationId::new(8);
+        let original_op_id = OperationId::new(7);
+
+        let progress = sweep_progress(
+            id,
+            original_op_id,
+            event(),
+            approval(original_op_id, 0),
+            5,
+            &10u64.into(),
+            Ok(42u64.into()),
+        )
+        .unwrap();
+        if let OperationProgress::ProgressWithDependent { dependent, .. } = &progress {
+            assert_eq!(dependent.dependency(), Some(id));
+        }
+
+        // 1000 burnt tokens cover 970 approved tokens and 3 fees of 10 tokens.
+        let refund = refund_of(progress);
+        assert_eq!(refund.amount, 970u64.into());
+        assert_eq!(refund.sender, event().sender);
+    }
+
+    #[test]
+    fn partially_claimed_approval_is_swept_back_and_refunded_proportionally() {
+        MockContext::new().inject();
+        let id = OperationId::new(8);
+        let original_op_id = OperationId::new(7);
+        let partially_claimed = Err(IcrcCanisterError::TransferFailed(
+            TransferError::InsufficientFunds {
+                balance: 510u64.into(),
+            },
+        ));
+
+        let progress = sweep_progress(
+            id,
+            original_op_id,
+            event(),
+            approval(original_op_id, 0),
+            5,
+            &10u64.into(),
+            partially_claimed,
+        )
+        .unwrap();
+        let OperationProgress::Progress(IcrcBridgeOpImpl(IcrcBridgeOp::SweepIcrcApproval {
+            original_op_id: swept_op_id,
+            event: swept_event,
+            approval: swept_approval,
+            pending_since,
+        })) = progress
+        else {
+            panic!("rest of the holder balance should be swept back");
+        };
+        assert_eq!(swept_op_id, original_op_id);
+        assert_eq!(swept_approval.amount, 500u64);
+        assert_eq!(swept_approval.holder, approval(original_op_id, 0).holder);
+        assert_eq!(pending_since, 5);
+
+        let progress = sweep_progress(
+            id,
+            swept_op_id,
+            swept_event,
+            swept_approval,
+            pending_since,
+            &10u64.into(),
+            Ok(42u64.into()),
+        )
+        .unwrap();
+        assert_eq!(refund_of(progress).amount, 500u64.into());
+    }
+
+    #[test]
+    fn claimed_approval_is_not_refunded() {
+        MockContext::new().inject();
+        let original_op_id = OperationId::new(7);
+        let sweep = |result| {
+            sweep_progress(
+                OperationId::new(8),
+                original_op_id,
+                event(),
+                approval(original_op_id, 0),
+                5,
+                &10u64.into(),
+                result,
+            )
+        };
+
+        let claimed = Err(IcrcCanisterError::TransferFailed(
+            TransferError::InsufficientFunds {
+                balance: 10u64.into(),
+            },
+        ));
+        let OperationProgress::Progress(claimed) = sweep(claimed).unwrap() else {
+            panic!("claimed approval should not be refunded");
+        };
+        assert!(matches!(
+            claimed.0,
+            IcrcBridgeOp::IcrcApprovalClaimed { .. }
+        ));
+        assert!(!claimed.releases_dependents());
+
+        let unavailable = Err(IcrcCanisterError::TransferFailed(
+            TransferError::TemporarilyUnavailable,
+        ));
+        assert!(sweep(unavailable).is_err());
+    }
+
+    #[tokio::test]
+    async fn expired_approvals_are_swept_back() {
+        MockContext::new().inject();
+        let runtime: BridgeRuntime<IcrcBridgeOpImpl> = BridgeRuntime::default(ConfigStorage::get());
+        let state = runtime.state().clone();
+
+        let mut granted = vec![];
+        for expires_at in [0, u64::MAX] {
+            let id = state.borrow_mut().operations.new_operation(
+                IcrcBridgeOpImpl(IcrcBridgeOp::MintIcrcTokens {
+                    event: event(),
+                    pending_since: 0,
+                }),
+                None,
+            );
+            for step in [
+                IcrcBridgeOp::ApproveIcrcTokens {
+                    event: event(),
+                    approval: approval(id, expires_at),
+                    pending_since: 0,
+                },
+                IcrcBridgeOp::IcrcApprovalGranted {
+                    src_address: event().sender,
+                    approval: approval(id, expires_at),
+                    icrc_tx_id: 1u64.into(),
+                },
+            ] {
+                assert!(state
+                    .borrow_mut()
+                    .operations
+                    .update(id, IcrcBridgeOpImpl(step)));
+            }
+            get_icrc_state()
+                .borrow_mut()
+                .approval_deliveries
+                .insert(id, expires_at);
+            granted.push(id);
+        }
+
+        let service = SweepApprovalsService::new(state.clone(), runtime.scheduler().clone());
+        service.run().await.unwrap();
+
+        let sweeps: Vec<_> = state
+            .borrow()
+            .operations
+            .get_incomplete()
+            .into_iter()
+            .filter_map(|(_, op)| match op.0 {
+                IcrcBridgeOp::SweepIcrcApproval {
+                    original_op_id,
+                    approval,
+                    event,
+                    ..
+                } => Some((original_op_id, approval, event)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sweeps.len(), 1);
+        assert_eq!(sweeps[0].0, granted[0]);
+        assert_eq!(sweeps[0].1, approval(granted[0], 0));
+        assert_eq!(sweeps[0].2, event());
+
+        let tracked = get_icrc_state().borrow().approval_deliveries.get_all();
+        assert_eq!(tracked, vec![(granted[1], u64::MAX)]);
+
+        // Sweep-back is created once.
+        service.run().await.unwrap();
+        assert_eq!(state.borrow().operations.get_incomplete().len(), 1);
+    }
+}
@@ -0,0 +1,249 @@
+//! Deposits, requested by the senders with the direct `burn_icrc2_batch` canister call.
+
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::operations::IcrcBridgeOp;
+use bridge_did::reason::Icrc2Burn;
+use candid::Principal;
+use did::H160;
+use ic_stable_structures::CellStructure;
+
+use super::deposit_confirmation;
+use crate::constant::{DEPOSIT_STANDARDS, MAX_BURN_BATCH_SIZE};
+use crate::state::IcrcState;
+
+/// Checks that the batch doesn't exceed the [`MAX_BURN_BATCH_SIZE`].
+pub fn check_batch_size(size: usize) -> BTFResult<()> {
+    if size as u64 > MAX_BURN_BATCH_SIZE {
+        return Err(Error::BurnBatchTooLarge(MAX_BURN_BATCH_SIZE));
+    }
+
+    Ok(())
+}
+
+/// Checks the burn, requested by the `caller`, and creates the initial state of its deposit.
+///
+/// The caller can burn only its own tokens. The burn is checked like the deposits, requested
+/// with the BTF bridge notification. Approve after mint is removed, because the caller
+/// can't prove that it owns the recipient wallet.
+pub fn new_deposit(
+    caller: Principal,
+    mut burn: Icrc2Burn,
+    state: &IcrcState,
+    now: u64,
+) -> BTFResult<IcrcBridgeOp> {
+    if burn.sender != caller {
+        return Err(Error::AccessDenied);
+    }
+
+    if burn.amount.0.is_zero() {
+        return Err(Error::InvalidBurnAmount("amount should not be zero".into()));
+    }
+
+    state.token_allow_list.check(&burn.icrc2_token_principal)?;
+    state
+        .ledger_capabilities
+        .check(&burn.icrc2_token_principal, DEPOSIT_STANDARDS)?;
+    state
+        .token_decimals
+        .check_not_held(&burn.icrc2_token_principal)?;
+
+    let preferences = state
+        .bridge_preferences
+        .get(&burn.sender)
+        .unwrap_or_default();
+    burn.apply_preferences(&preferences);
+    burn.remove_approve_after_mint();
+
+    if burn.recipient_address == H160::zero() {
+        return Err(Error::InvalidBurnRequest(
+            "recipient address should not be zero".into(),
+        ));
+    }
+
+    if burn.erc20_token_address == H160::zero() {
+        return Err(Error::InvalidBurnRequest(
+            "wrapped token address should not be zero".into(),
+        ));
+    }
+
+    let max_delay_secs = *state.max_deposit_confirmation_delay_secs.get();
+    Ok(deposit_confirmation::new_deposit(burn, max_delay_secs, now))
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::preferences::{BridgePreferences, PreferenceField};
+    use bridge_did::reason::ApproveAfterMint;
+    use ic_exports::ic_kit::MockContext;
+
+    use super::*;
+    use crate::constant::ICRC1_STANDARD;
+    use crate::state::LedgerCapabilities;
+
+    fn sender() -> Principal {
+        Principal::from_slice(&[1; 29])
+    }
+
+    fn burn() -> Icrc2Burn {
+        Icrc2Burn {
+            sender: sender(),
+            amount: 1000u64.into(),
+            icrc2_token_principal: Principal::from_slice(&[2; 29]),
+            erc20_token_address: H160::from_slice(&[3; 20]),
+            from_subaccount: None,
+            recipient_address: H160::from_slice(&[4; 20]),
+            approve_after_mint: Some(ApproveAfterMint {
+                approve_spender: H160::from_slice(&[5; 20]),
+                approve_amount: 1000u64.into(),
+                permit: None,
+            }),
+            fee_payer: None,
+            confirmation_delay_secs: None,
+            preference_fields: None,
+        }
+    }
+
+    fn state() -> IcrcState {
+        MockContext::new().inject();
+        IcrcState::default()
+    }
+
+    #[test]
+    fn batch_size_is_limited() {
+        assert!(check_batch_size(0).is_ok());
+        assert!(check_batch_size(MAX_BURN_BATCH_SIZE as usize).is_ok());
+        assert_eq!(
+            check_batch_size(MAX_BURN_BATCH_SIZE as usize + 1),
+            Err(Error::BurnBatchTooLarge(MAX_BURN_BATCH_SIZE))
+        );
+    }
+
+    #[test]
+    fn deposit_is_created_without_approve_after_mint() {
+        let mut state = state();
+        state
+            .bridge_preferences
+            .set(
+                sender(),
+                BridgePreferences {
+                    recipient_address: Some(H160::from_slice(&[6; 20])),
+                    approve_spender: Some(H160::from_slice(&[7; 20])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let op = new_deposit(sender(), burn(), &state, 10).unwrap();
+        let IcrcBridgeOp::BurnIcrc2Tokens {
+            burn: deposit,
+            pending_since,
+        } = op
+        else {
+            panic!("deposit should be burnt immediately");
+        };
+        assert_eq!(pending_since, 10);
+        assert_eq!(deposit.recipient_address, H160::from_slice(&[4; 20]));
+        assert!(deposit.approve_after_mint.is_none());
+        assert!(deposit.preference_fields.is_none());
+
+        // Recipient is taken from the preferences, approve spender is not.
+        let zero_recipient = Icrc2Burn {
+            recipient_address: H160::zero(),
+            approve_after_mint: None,
+            ..burn()
+        };
+        let op = new_deposit(sender(), zero_recipient, &state, 10).unwrap();
+        let IcrcBridgeOp::BurnIcrc2Tokens { burn: deposit, .. } = op else {
+            panic!("deposit should be burnt immediately");
+        };
+        assert_eq!(deposit.recipient_address, H160::from_slice(&[6; 20]));
+        assert!(deposit.approve_after_mint.is_none());
+        assert_eq!(
+            deposit.preference_fields,
+            Some(vec![PreferenceField::RecipientAddress])
+        );
+    }
+
+    #[test]
+    fn invalid_burns_are_rejected() {
+        let mut state = state();
+
+        assert!(matches!(
+            new_deposit(Principal::from_slice(&[9; 29]), burn(), &state, 0),
+            Err(Error::AccessDenied)
+        ));
+
+        let zero_amount = Icrc2Burn {
+            amount: 0u64.into(),
+            ..burn()
+        };
+        assert!(matches!(
+            new_deposit(sender(), zero_amount, &state, 0),
+            Err(Error::InvalidBurnAmount(_))
+        ));
+
+        let zero_recipient = Icrc2Burn {
+            recipient_address: H160::zero(),
+            ..burn()
+        };
+        assert!(matches!(
+            new_deposit(sender(), zero_recipient, &state, 0),
+            Err(Error::InvalidBurnRequest(_))
+        ));
+
+        let zero_wrapped_token = Icrc2Burn {
+            erc20_token_address: H160::zero(),
+            ..burn()
+        };
+        assert!(matches!(
+            new_deposit(sender(), zero_wrapped_token, &state, 0),
+            Err(Error::InvalidBurnRequest(_))
+        ));
+
+        let token = burn().icrc2_token_principal;
+        state
+            .token_allow_list
+            .add(Principal::from_slice(&[8; 29]))
+            .unwrap();
+        assert!(matches!(
+            new_deposit(sender(), burn(), &state, 0),
+            Err(Error::TokenNotAllowed(t)) if t == token
+        ));
+    }
+
+    #[test]
+    fn unsupported_ledger_is_rejected_immediately() {
+        let mut state = state();
+        state.ledger_capabilities.set(
+            burn().icrc2_token_principal,
+            LedgerCapabilities {
+                standards: vec![],
+                core_metadata: false,
+            },
+        );
+
+        assert_eq!(
+            new_deposit(sender(), burn(), &state, 0).unwrap_err(),
+            Error::UnsupportedLedger {
+                missing: vec!["ICRC-1".into(), "ICRC-2".into()]
+            }
+        );
+    }
+
+    #[test]
+    fn icrc1_only_ledger_is_rejected_as_invalid_burn() {
+        let mut state = state();
+        state.ledger_capabilities.set(
+            burn().icrc2_token_principal,
+            LedgerCapabilities {
+                standards: vec![ICRC1_STANDARD.into()],
+                core_metadata: true,
+            },
+        );
+
+        assert_eq!(
+            new_deposit(sender(), burn(), &state, 0).unwrap_err(),
+            Error::InvalidBurnOperation("ledger does not support ICRC-2".into())
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//! Write-ahead records of the deposit burns.
+//!
+//! The ledger burn and the deposit operation update happen in different messages, so
+//! a trap after the ledger call would leave the burnt tokens without a record. To prevent
+//! this, a `Burning` intent is stored before the call and is committed together with it.
+//! The retried burn reuses the `created_at_time` of the intent, so the ledger either
+//! executes it or reports the duplicate of the already executed burn.
+
+use std::future::Future;
+
+use bridge_canister::runtime::BridgeRuntime;
+use bridge_did::burn_intent::{BurnIntent, BurnIntentState};
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::op_id::OperationId;
+use bridge_did::operations::IcrcBridgeOp;
+use candid::{Nat, Principal};
+use icrc_client::transfer_from::TransferFromError;
+
+use super::{ErrorCodes, IcrcBridgeOpImpl};
+use crate::canister::get_icrc_state;
+use crate::tokens::icrc1::IcrcCanisterError;
+use crate::tokens::icrc2::Success;
+
+/// Returns the memo of the ledger transfer, which burns the tokens of the deposit `id`.
+pub fn burn_memo(id: OperationId) -> Vec<u8> {
+    id.as_u64().to_be_bytes().to_vec()
+}
+
+/// Burns the `amount` of the `token` for the deposit `id` with the given `burn` ledger call,
+/// which receives the transfer `created_at_time`. Returns the burn block index.
+///
+/// If the deposit has an intent from the previous attempt, its `created_at_time` is reused,
+/// and the already burnt tokens are not burnt again.
+pub async fn burn_with_intent<F, Fut>(
+    id: OperationId,
+    token: Principal,
+    amount: Nat,
+    now: u64,
+    burn: F,
+) -> BTFResult<Nat>
+where
+    F: FnOnce(u64) -> Fut,
+    Fut: Future<Output = Result<Success, IcrcCanisterError>>,
+{
+    let intent = get_icrc_state()
+        .borrow_mut()
+        .burn_intents
+        .get_or_insert(id, BurnIntent::new(token, amount, now));
+
+    match intent.state {
+        BurnIntentState::Burning => {}
+        BurnIntentState::Burned { block_index } => {
+            log::info!("Deposit {id} tokens are already burnt in block {block_index}");
+            return Ok(block_index);
+        }
+        BurnIntentState::Unresolved { reason } => {
+            return Err(burn_failed(format!("burn is unresolved: {reason}")));
+        }
+    }
+
+    let result = burn(intent.created_at_time).await;
+
+    let icrc_state = get_icrc_state();
+    let mut icrc_state = icrc_state.borrow_mut();
+    match result {
+        Ok(success) => {
+            icrc_state
+                .burn_intents
+                .mark_burned(id, success.tx_id.clone());
+            Ok(success.tx_id)
+        }
+        Err(IcrcCanisterError::TransferFromFailed(TransferFromError::TooOld)) => {
+            let reason = "ledger deduplication window is passed".to_string();
+            log::error!("Deposit {id} burn can't be reconciled with the ledger: {reason}");
+            icrc_state.burn_intents.mark_unresolved(id, reason.clone());
+            Err(burn_failed(format!("burn is unresolved: {reason}")))
+        }
+        Err(e @ IcrcCanisterError::TransferFromFailed(_)) => {
+            // The ledger rejected the transfer, so nothing is burnt.
+            icrc_state.burn_intents.remove(&id);
+            Err(burn_failed(e.to_string()))
+        }
+        Err(e) => {
+            // The burn outcome is unknown, so the intent is kept to be reconciled on retry.
+            Err(burn_failed(e.to_string()))
+        }
+    }
+}
+
+fn burn_failed(reason: String) -> Error {
+    Error::Custom {
+        code: ErrorCodes::IcrcBurnFailed as _,
+        msg: format!("failed to burn ICRC token: {reason}"),
+    }
+}
+
+/// Sweeps the burn intents, left after the traps, on the canister upgrade.
+///
+/// Intents of the deposits, which passed the burn step or are removed from the store, are
+/// dropped. Deposits stuck in the burn step are rescheduled to reconcile the burn with
+/// the ledger and resume. Unresolved intents are kept for the operator review.
+///
+/// Returns ids of the rescheduled deposits.
+pub fn recover_burn_intents(runtime: &BridgeRuntime<IcrcBridgeOpImpl>) -> Vec<OperationId> {
+    let intents = get_icrc_state().borrow().burn_intents.get_all();
+
+    let mut rescheduled = vec![];
+    for (id, intent) in intents {
+        let operation = runtime.state().borrow().operations.get(id);
+        match (operation.map(|op| op.0), intent.state) {
+            (
+                Some(IcrcBridgeOp::BurnIcrc2Tokens { .. }),
+                BurnIntentState::Unresolved { reason },
+            ) => {
+                log::error!("Deposit {id} has unresolved burn: {reason}");
+            }
+            (Some(IcrcBridgeOp::BurnIcrc2Tokens { .. }), state) => {
+                log::warn!("Deposit {id} is stuck in the burn step with {state:?} intent");
+                runtime.reschedule_operation(id);
+                rescheduled.push(id);
+            }
+            _ => get_icrc_state().borrow_mut().burn_intents.remove(&id),
+        }
+    }
+
+    rescheduled
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::future::ready;
+
+    use bridge_canister::runtime::state::config::ConfigStorage;
+    use bridge_did::reason::Icrc2Burn;
+    use did::H160;
+    use ic_exports::ic_kit::{MockContext, RejectionCode};
+    use ic_task_scheduler::scheduler::TaskScheduler;
+
+    use super::*;
+
+    const TOKEN: Principal = Principal::anonymous();
+    const NOW: u64 = 1_000;
+
+    /// Ledger, which records the `created_at_time` of every burn call.
+    #[derive(Default)]
+    struct MockLedger {
+        calls: RefCell<Vec<u64>>,
+    }
+
+    impl MockLedger {
+        fn burn(
+            &self,
+            created_at_time: u64,
+            result: Result<u64, IcrcCanisterError>,
+        ) -> impl Future<Output = Result<Success, IcrcCanisterError>> {
+            self.calls.borrow_mut().push(created_at_time);
+            ready(result.map(|block_index| Success {
+                tx_id: block_index.into(),
+                amount: 100u64.into(),
+            }))
+        }
+    }
+
+    fn id(n: u64) -> OperationId {
+        OperationId::new(n)
+    }
+
+    fn intent_state(id: OperationId) -> Option<BurnIntentState> {
+        get_icrc_state()
+            .borrow()
+            .burn_intents
+            .get(&id)
+            .map(|intent| intent.state)
+    }
+
+    /// Simulates the trap in the ledger call callback: the intent, stored before the call,
+    /// is committed, while the call result is lost.
+    fn trap_after_ledger_call(id: OperationId) {
+        get_icrc_state()
+            .borrow_mut()
+            .burn_intents
+            .get_or_insert(id, BurnIntent::new(TOKEN, 100u64.into(), NOW));
+    }
+
+    #[tokio::test]
+    async fn burn_should_be_recorded() {
+        MockContext::new().inject();
+        let ledger = MockLedger::default();
+
+        let block =
+            burn_with_intent(id(1), TOKEN, 100u64.into(), NOW, |t| ledger.burn(t, Ok(5))).await;
+
+        assert_eq!(block, Ok(5u64.into()));
+        assert_eq!(*ledger.calls.borrow(), vec![NOW]);
+        assert_eq!(
+            intent_state(id(1)),
+            Some(BurnIntentState::Burned {
+                block_index: 5u64.into()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn trap_after_ledger_burn_should_be_reconciled_by_deduplication() {
+        MockContext::new().inject();
+        let ledger = MockLedger::default();
+        trap_after_ledger_call(id(1));
+
+        // The retry is made later, but reuses the `created_at_time` of the intent,
+        // so the ledger reports the duplicate of the executed burn as success.
+        let block = burn_with_intent(id(1), TOKEN, 100u64.into(), NOW + 60, |t| {
+            ledger.burn(t, Ok(5))
+        })
+        .await;
+
+        assert_eq!(block, Ok(5u64.into()));
+        assert_eq!(*ledger.calls.borrow(), vec![NOW]);
+        assert_eq!(
+            intent_state(id(1)),
+            Some(BurnIntentState::Burned {
+                block_index: 5u64.into()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn trap_after_burn_is_recorded_should_not_burn_again() {
+        MockContext::new().inject();
+        let ledger = MockLedger::default();
+        trap_after_ledger_call(id(1));
+        get_icrc_state()
+            .borrow_mut()
+            .burn_intents
+            .mark_burned(id(1), 5u64.into());
+
+        let block = burn_with_intent(id(1), TOKEN, 100u64.into(), NOW + 60, |t| {
+            ledger.burn(t, Ok(6))
+        })
+        .await;
+
+        assert_eq!(block, Ok(5u64.into()));
+        assert!(ledger.calls.borrow().is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejected_burn_should_drop_intent() {
+        MockContext::new().inject();
+        let ledger = MockLedger::default();
+
+        let result = burn_with_intent(id(1), TOKEN, 100u64.into(), NOW, |t| {
+            ledger.burn(
+                t,
+                Err(IcrcCanisterError::TransferFromFailed(
+                    TransferFromError::TemporarilyUnavailable,
+                )),
+            )
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(intent_state(id(1)), None);
+
+        // The next attempt is a new transfer.
+        burn_with_intent(id(1), TOKEN, 100u64.into(), NOW + 60, |t| {
+            ledger.burn(t, Ok(5))
+        })
+        .await
+        .unwrap();
+        assert_eq!(*ledger.calls.borrow(), vec![NOW, NOW + 60]);
+    }
+
+    #[tokio::test]
+    async fn burn_with_unknown_outcome_should_keep_intent() {
+        MockContext::new().inject();
+        let ledger = MockLedger::default();
+
+        let result = burn_with_intent(id(1), TOKEN, 100u64.into(), NOW, |t| {
+            ledger.burn(
+                t,
+                Err(IcrcCanisterError::CanisterError(
+                    RejectionCode::SysTransient,
+                    "timeout".into(),
+                )),
+            )
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(intent_state(id(1)), Some(BurnIntentState::Burning));
+
+        burn_with_intent(id(1), TOKEN, 100u64.into(), NOW + 60, |t| {
+            ledger.burn(t, Ok(5))
+        })
+        .await
+        .unwrap();
+        assert_eq!(*ledger.calls.borrow(), vec![NOW, NOW]);
+    }
+
+    #[tokio::test]
+    async fn burn_outside_deduplication_window_should_be_unresolved() {
+        MockContext::new().inject();
+        let ledger = MockLedger::default();
+        trap_after_ledger_call(id(1));
+
+        let result = burn_with_intent(id(1), TOKEN, 100u64.into(), NOW + 60, |t| {
+            ledger.burn(
+                t,
+                Err(IcrcCanisterError::TransferFromFailed(
+                    TransferFromError::TooOld,
+                )),
+            )
+        })
+        .await;
+        assert!(result.is_err());
+        assert!(matches!(
+            intent_state(id(1)),
+            Some(BurnIntentState::Unresolved { .. })
+        ));
+
+        // Unresolved burn is never retried.
+        let result = burn_with_intent(id(1), TOKEN, 100u64.into(), NOW + 120, |t| {
+            ledger.burn(t, Ok(5))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(*ledger.calls.borrow(), vec![NOW]);
+    }
+
+    fn burn() -> Icrc2Burn {
+        Icrc2Burn {
+            sender: Principal::anonymous(),
+            amount: 100u64.into(),
+            icrc2_token_principal: TOKEN,
+            erc20_token_address: H160::from_slice(&[1; 20]),
+            from_subaccount: None,
+            recipient_address: H160::from_slice(&[2; 20]),
+            approve_after_mint: None,
+            fee_payer: None,
+            confirmation_delay_secs: None,
+            preference_fields: None,
+        }
+    }
+
+    fn deposit() -> IcrcBridgeOpImpl {
+        IcrcBridgeOpImpl(IcrcBridgeOp::BurnIcrc2Tokens {
+            burn: burn(),
+            pending_since: 0,
+        })
+    }
+
+    #[test]
+    fn recovery_should_reschedule_deposits_stuck_in_burn() {
+        MockContext::new().inject();
+        let runtime: BridgeRuntime<IcrcBridgeOpImpl> = BridgeRuntime::default(ConfigStorage::get());
+        let state = runtime.state().clone();
+
+        let stuck = state.borrow_mut().operations.new_operation(deposit(), None);
+        let burned = state.borrow_mut().operations.new_operation(deposit(), None);
+        let unresolved = state.borrow_mut().operations.new_operation(deposit(), None);
+        // The operation was updated after the intent was stored.
+        let aborted = state.borrow_mut().operations.new_operation(
+            IcrcBridgeOpImpl(IcrcBridgeOp::DepositAborted {
+                burn: burn(),
+                expired: false,
+            }),
+            None,
+        );
+        let removed = id(42);
+        for id in [stuck, burned, unresolved, aborted, removed] {
+            trap_after_ledger_call(id);
+        }
+        {
+            let icrc_state = get_icrc_state();
+            let mut icrc_state = icrc_state.borrow_mut();
+            icrc_state.burn_intents.mark_burned(burned, 5u64.into());
+            icrc_state
+                .burn_intents
+                .mark_unresolved(unresolved, "too old".into());
+        }
+
+        let rescheduled = recover_burn_intents(&runtime);
+
+        assert_eq!(rescheduled, vec![stuck, burned]);
+        for id in [stuck, burned] {
+            assert!(runtime
+                .scheduler()
+                .find_id(&|task| task.op_id == id)
+                .is_some());
+        }
+        let remaining: Vec<_> = get_icrc_state()
+            .borrow()
+            .burn_intents
+            .get_all()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(remaining, vec![stuck, burned, unresolved]);
+    }
+}
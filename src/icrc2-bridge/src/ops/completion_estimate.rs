@@ -0,0 +1,246 @@
+//! Estimation of the operation completion time, based on the phase latencies
+//! of the latest complete operations.
+
+use std::collections::HashMap;
+
+use bridge_did::operation_log::OperationLog;
+use bridge_did::operations::IcrcBridgeOp;
+
+use super::IcrcBridgeOpImpl;
+
+/// Phase of the incomplete operation, which duration doesn't depend on the user actions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    BurnIcrc2Tokens,
+    SignMintOrder,
+    SendMintTransaction,
+    ConfirmMint,
+    MintIcrcTokens,
+    RefundMint,
+}
+
+impl Phase {
+    /// Returns the phase of the operation state.
+    ///
+    /// Complete operations, deposits waiting for the sender confirmation, mint orders,
+    /// which should be sent to the EVM by the user, sweep-backs of the expired approvals
+    /// and parked refunds have no phase. Approval of the withdrawn tokens is a part of their mint.
+    pub fn of(op: &IcrcBridgeOp) -> Option<Self> {
+        match op {
+            IcrcBridgeOp::BurnIcrc2Tokens { .. } => Some(Self::BurnIcrc2Tokens),
+            IcrcBridgeOp::SignMintOrder { .. } => Some(Self::SignMintOrder),
+            IcrcBridgeOp::SendMintTransaction { .. } => Some(Self::SendMintTransaction),
+            IcrcBridgeOp::ConfirmMint { tx_hash: None, .. } => None,
+            IcrcBridgeOp::ConfirmMint { .. } => Some(Self::ConfirmMint),
+            IcrcBridgeOp::MintIcrcTokens { .. } | IcrcBridgeOp::ApproveIcrcTokens { .. } => {
+                Some(Self::MintIcrcTokens)
+            }
+            IcrcBridgeOp::RefundMint { .. } => Some(Self::RefundMint),
+            IcrcBridgeOp::PendingUserConfirmation { .. }
+            | IcrcBridgeOp::DepositAborted { .. }
+            | IcrcBridgeOp::WrappedTokenMintConfirmed(_)
+            | IcrcBridgeOp::IcrcMintConfirmed { .. }
+            | IcrcBridgeOp::IcrcMintFailed { .. }
+            | IcrcBridgeOp::IcrcApprovalGranted { .. }
+            | IcrcBridgeOp::SweepIcrcApproval { .. }
+            | IcrcBridgeOp::IcrcApprovalSwept { .. }
+            | IcrcBridgeOp::IcrcApprovalClaimed { .. }
+            | IcrcBridgeOp::RefundMintParked { .. } => None,
+        }
+    }
+
+    /// Returns the phases the operation passes through till completion, starting with `self`.
+    fn remaining(self) -> &'static [Self] {
+        match self {
+            Self::BurnIcrc2Tokens => &[
+                Self::BurnIcrc2Tokens,
+                Self::SignMintOrder,
+                Self::SendMintTransaction,
+                Self::ConfirmMint,
+            ],
+            Self::RefundMint => &[
+                Self::RefundMint,
+                Self::SignMintOrder,
+                Self::SendMintTransaction,
+                Self::ConfirmMint,
+            ],
+            Self::SignMintOrder => &[
+                Self::SignMintOrder,
+                Self::SendMintTransaction,
+                Self::ConfirmMint,
+            ],
+            Self::SendMintTransaction => &[Self::SendMintTransaction, Self::ConfirmMint],
+            Self::ConfirmMint => &[Self::ConfirmMint],
+            Self::MintIcrcTokens => &[Self::MintIcrcTokens],
+        }
+    }
+}
+
+/// Average durations of the operation phases.
+#[derive(Debug, Default, Clone)]
+pub struct PhaseLatencies {
+    /// Total duration in nanoseconds and number of the samples per phase.
+    samples: HashMap<Phase, (u64, u64)>,
+}
+
+impl PhaseLatencies {
+    /// Collects the phase durations from the operation logs.
+    ///
+    /// A phase lasts from the step, which moved the operation into it, till the next
+    /// successful step. The time of the failed attempts is included.
+    pub fn from_logs(logs: &[OperationLog<IcrcBridgeOpImpl>]) -> Self {
+        let mut latencies = Self::default();
+        for log in logs {
+            let steps: Vec<_> = log
+                .log()
+                .iter()
+                .filter_map(|entry| {
+                    entry
+                        .step_result
+                        .as_ref()
+                        .ok()
+                        .map(|op| (entry.time_stamp, &op.0))
+                })
+                .collect();
+
+            for window in steps.windows(2) {
+                let (started, op) = window[0];
+                let (finished, _) = window[1];
+                if let Some(phase) = Phase::of(op) {
+                    latencies.record(phase, finished.saturating_sub(started));
+                }
+            }
+        }
+
+        latencies
+    }
+
+    /// Records a sample of the phase duration in nanoseconds.
+    pub fn record(&mut self, phase: Phase, duration: u64) {
+        let (total, count) = self.samples.entry(phase).or_default();
+        *total = total.saturating_add(duration);
+        *count += 1;
+    }
+
+    /// Returns the average duration of the phase in nanoseconds, if there are samples.
+    pub fn average(&self, phase: Phase) -> Option<u64> {
+        self.samples
+            .get(&phase)
+            .filter(|(_, count)| *count > 0)
+            .map(|(total, count)| total / count)
+    }
+}
+
+/// Returns the estimated number of seconds till the operation completion at the `now` IC time.
+///
+/// The estimate is the average duration of the remaining phases, reduced by the time
+/// the operation already spent in the current one. Returns `None` if the operation has
+/// no phase, or if there are no samples for one of the remaining phases.
+pub fn estimate_completion(op: &IcrcBridgeOp, latencies: &PhaseLatencies, now: u64) -> Option<u64> {
+    let phase = Phase::of(op)?;
+    let elapsed = now.saturating_sub(op.pending_since()?);
+
+    let mut remaining = 0u64;
+    for (index, phase) in phase.remaining().iter().enumerate() {
+        let mut average = latencies.average(*phase)?;
+        if index == 0 {
+            average = average.saturating_sub(elapsed);
+        }
+        remaining = remaining.saturating_add(average);
+    }
+
+    Some(remaining.div_ceil(1_000_000_000))
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::event_data::BurntEventData;
+    use bridge_did::reason::Icrc2Burn;
+    use candid::Principal;
+    use did::H160;
+    use ic_exports::ic_kit::MockContext;
+
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    fn mint_icrc_tokens(pending_since: u64) -> IcrcBridgeOp {
+        IcrcBridgeOp::MintIcrcTokens {
+            event: BurntEventData::default(),
+            pending_since,
+        }
+    }
+
+    fn burn() -> Icrc2Burn {
+        Icrc2Burn {
+            sender: Principal::anonymous(),
+            amount: 1000u64.into(),
+            icrc2_token_principal: Principal::anonymous(),
+            erc20_token_address: H160::from_slice(&[1; 20]),
+            from_subaccount: None,
+            recipient_address: H160::from_slice(&[2; 20]),
+            approve_after_mint: None,
+            fee_payer: None,
+            confirmation_delay_secs: None,
+            preference_fields: None,
+        }
+    }
+
+    #[test]
+    fn latencies_are_collected_from_logs() {
+        let context = MockContext::new().inject();
+
+        let mut logs = vec![];
+        for secs in [10, 20, 30] {
+            let mut log =
+                OperationLog::new(IcrcBridgeOpImpl(mint_icrc_tokens(0)), H160::default(), None);
+            context.add_time(secs * SECOND);
+            log.add_step(Err("ledger is unavailable".into()));
+            log.add_step(Ok(IcrcBridgeOpImpl(IcrcBridgeOp::IcrcMintConfirmed {
+                src_address: H160::default(),
+                icrc_tx_id: 1u64.into(),
+                icrc_memo: None,
+            })));
+            logs.push(log);
+        }
+
+        let latencies = PhaseLatencies::from_logs(&logs);
+        assert_eq!(latencies.average(Phase::MintIcrcTokens), Some(20 * SECOND));
+        assert_eq!(latencies.average(Phase::BurnIcrc2Tokens), None);
+
+        // Withdrawal is pending for 5 seconds.
+        let op = mint_icrc_tokens(100 * SECOND);
+        assert_eq!(estimate_completion(&op, &latencies, 105 * SECOND), Some(15));
+        // Operation, pending for longer than average, is expected to complete soon.
+        assert_eq!(estimate_completion(&op, &latencies, 200 * SECOND), Some(0));
+    }
+
+    #[test]
+    fn deposit_estimate_includes_remaining_phases() {
+        let mut latencies = PhaseLatencies::default();
+        latencies.record(Phase::BurnIcrc2Tokens, 4 * SECOND);
+        latencies.record(Phase::SignMintOrder, 2 * SECOND);
+        latencies.record(Phase::SendMintTransaction, 6 * SECOND);
+
+        let op = IcrcBridgeOp::BurnIcrc2Tokens {
+            burn: burn(),
+            pending_since: 0,
+        };
+        // No samples for the mint confirmation.
+        assert_eq!(estimate_completion(&op, &latencies, SECOND), None);
+
+        latencies.record(Phase::ConfirmMint, 30 * SECOND);
+        latencies.record(Phase::ConfirmMint, 10 * SECOND);
+        assert_eq!(
+            estimate_completion(&op, &latencies, SECOND),
+            Some(3 + 2 + 6 + 20)
+        );
+
+        // Operations without a phase are not estimated.
+        let aborted = IcrcBridgeOp::DepositAborted {
+            burn: burn(),
+            expired: false,
+        };
+        assert_eq!(estimate_completion(&aborted, &latencies, SECOND), None);
+    }
+}
@@ -0,0 +1,551 @@
+//! Deposit bundles: deposits of several ICRC tokens, requested with a single
+//! `create_deposit_bundle` call, which wrapped tokens are minted atomically.
+//!
+//! Mint orders of the bundle deposits are signed together in one atomic batch, so all of them
+//! are minted by a single `batchMint` transaction. If the burn of any bundle deposit fails,
+//! the bundle fails: deposits, which tokens are not burnt yet, are aborted, and the burnt
+//! tokens are refunded to the sender by the [`DepositBundlesService`].
+
+use std::time::Duration;
+
+use bridge_canister::bridge::{Operation, OperationContext};
+use bridge_canister::memory::StableMemory;
+use bridge_canister::runtime::scheduler::SharedScheduler;
+use bridge_canister::runtime::service::sign_orders::MAX_MINT_ORDERS_IN_BATCH;
+use bridge_canister::runtime::service::BridgeService;
+use bridge_canister::runtime::RuntimeState;
+use bridge_did::deposit_bundle::{
+    BundleId, BundleItemInfo, BundleItemStatus, DepositBundle, DepositBundleStatus,
+};
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::event_data::BurntEventData;
+use bridge_did::icrc_withdrawal::IcrcWithdrawalRecipient;
+use bridge_did::op_id::OperationId;
+use bridge_did::operations::IcrcBridgeOp;
+use bridge_did::order::MintOrder;
+use bridge_did::reason::Icrc2Burn;
+use candid::Principal;
+use ic_exports::ic_kit::ic;
+use ic_task_scheduler::scheduler::TaskScheduler;
+use ic_task_scheduler::task::ScheduledTask;
+
+use super::IcrcBridgeOpImpl;
+use crate::canister::get_icrc_state;
+
+/// Delay between the checks of the failed bundles refunds.
+pub const DEPOSIT_BUNDLES_DELAY: Duration = Duration::from_secs(60);
+
+/// Checks that the bundle of the burns can be minted by a single `batchMint` transaction.
+///
+/// Mint transaction of the bundle is either sent by the bridge or by the user, so the fee
+/// payer should be set for all the burns or for none of them.
+pub fn check_bundle(burns: &[Icrc2Burn]) -> BTFResult<()> {
+    if burns.len() < 2 || burns.len() > MAX_MINT_ORDERS_IN_BATCH {
+        return Err(Error::InvalidBurnRequest(format!(
+            "deposit bundle should contain from 2 to {MAX_MINT_ORDERS_IN_BATCH} burns"
+        )));
+    }
+
+    let with_fee_payer = burns.iter().filter(|burn| burn.fee_payer.is_some()).count();
+    if with_fee_payer != 0 && with_fee_payer != burns.len() {
+        return Err(Error::InvalidBurnRequest(
+            "fee payer should be set for all the bundle burns or for none of them".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Burns the tokens of the bundle deposit.
+///
+/// If the burn fails and the tokens are surely not burnt, the bundle fails, and the deposit
+/// is aborted. Deposits of the failed bundle are aborted without burning their tokens.
+pub async fn burn_item(
+    ctx: impl OperationContext,
+    id: OperationId,
+    bundle_id: BundleId,
+    bundle: DepositBundle,
+    burn: Icrc2Burn,
+) -> BTFResult<IcrcBridgeOp> {
+    let has_burn_intent = || get_icrc_state().borrow().burn_intents.get(&id).is_some();
+    let aborted = |burn| IcrcBridgeOp::DepositAborted {
+        burn,
+        expired: false,
+    };
+
+    if bundle.failure.is_some() && !has_burn_intent() {
+        log::info!("Deposit {id} is aborted, because its bundle {bundle_id} failed.");
+        return Ok(aborted(burn));
+    }
+
+    match IcrcBridgeOpImpl::burn_icrc_tokens(ctx, burn.clone(), id).await {
+        Err(e) if !has_burn_intent() => {
+            log::warn!("Deposit bundle {bundle_id} failed: burn of deposit {id} failed: {e}");
+            get_icrc_state()
+                .borrow_mut()
+                .deposit_bundles
+                .fail(bundle_id, format!("burn of deposit {id} failed: {e}"));
+            Ok(aborted(burn))
+        }
+        result => result,
+    }
+}
+
+/// Returns the withdrawal event, which refunds the burnt tokens of the bundle deposit
+/// to the account they are burnt from.
+pub fn refund_event(
+    id: OperationId,
+    order: MintOrder,
+    sender: Principal,
+    from_subaccount: Option<[u8; 32]>,
+) -> BurntEventData {
+    let recipient = IcrcWithdrawalRecipient::new(sender, None).with_subaccount(from_subaccount);
+    BurntEventData {
+        sender: order.recipient,
+        amount: order.amount,
+        from_erc20: order.dst_token,
+        recipient_id: recipient.encode(),
+        to_token: order.src_token.0.to_vec(),
+        operation_id: id.nonce(),
+        name: order.name.to_vec(),
+        symbol: order.symbol.to_vec(),
+        decimals: order.decimals,
+        memo: vec![],
+    }
+}
+
+/// Returns the progress of the bundle deposit in the given state.
+pub fn item_status(op: &IcrcBridgeOp, bundle_failed: bool) -> BundleItemStatus {
+    match op {
+        IcrcBridgeOp::PendingUserConfirmation { .. } | IcrcBridgeOp::BurnIcrc2Tokens { .. } => {
+            BundleItemStatus::Burning
+        }
+        IcrcBridgeOp::DepositAborted { .. } => BundleItemStatus::Aborted,
+        IcrcBridgeOp::SignMintOrder {
+            is_refund: false, ..
+        } if bundle_failed => BundleItemStatus::Refunding,
+        IcrcBridgeOp::SignMintOrder {
+            is_refund: false, ..
+        } => BundleItemStatus::Burnt,
+        IcrcBridgeOp::SendMintTransaction {
+            is_refund: false, ..
+        }
+        | IcrcBridgeOp::ConfirmMint {
+            is_refund: false, ..
+        } => BundleItemStatus::Minting,
+        IcrcBridgeOp::WrappedTokenMintConfirmed(_) => BundleItemStatus::Minted,
+        IcrcBridgeOp::MintIcrcTokens { .. } => BundleItemStatus::Refunding,
+        IcrcBridgeOp::IcrcMintConfirmed { .. } => BundleItemStatus::Refunded,
+        // Deposit operation doesn't reach the refund steps other than the failed ICRC mint.
+        // Burnt deposits are refunded by transfer, so they never reach the approval steps.
+        IcrcBridgeOp::IcrcMintFailed { .. }
+        | IcrcBridgeOp::ApproveIcrcTokens { .. }
+        | IcrcBridgeOp::IcrcApprovalGranted { .. }
+        | IcrcBridgeOp::SweepIcrcApproval { .. }
+        | IcrcBridgeOp::IcrcApprovalSwept { .. }
+        | IcrcBridgeOp::IcrcApprovalClaimed { .. }
+        | IcrcBridgeOp::RefundMint { .. }
+        | IcrcBridgeOp::RefundMintParked { .. }
+        | IcrcBridgeOp::SignMintOrder { .. }
+        | IcrcBridgeOp::SendMintTransaction { .. }
+        | IcrcBridgeOp::ConfirmMint { .. } => BundleItemStatus::RefundFailed,
+    }
+}
+
+/// Returns the progress of the bundle and its deposits.
+pub fn bundle_status(
+    state: &RuntimeState<IcrcBridgeOpImpl>,
+    id: BundleId,
+    bundle: DepositBundle,
+) -> DepositBundleStatus {
+    let bundle_failed = bundle.failure.is_some();
+    let items = bundle
+        .operation_ids()
+        .into_iter()
+        .filter_map(|operation_id| {
+            let op = state.borrow().operations.get(operation_id)?;
+            Some(BundleItemInfo {
+                operation_id,
+                status: item_status(&op.0, bundle_failed),
+            })
+        })
+        .collect();
+
+    DepositBundleStatus::new(id, bundle.failure, items)
+}
+
+/// Service to refund the burnt tokens of the failed deposit bundles.
+///
+/// Mint orders of the failed bundle are never signed, because the bundle misses the orders
+/// of its aborted deposits. Such deposits are moved to the ICRC mint of the burnt tokens
+/// back to the sender.
+pub struct DepositBundlesService {
+    state: RuntimeState<IcrcBridgeOpImpl>,
+    scheduler: SharedScheduler<StableMemory, IcrcBridgeOpImpl>,
+}
+
+impl DepositBundlesService {
+    pub fn new(
+        state: RuntimeState<IcrcBridgeOpImpl>,
+        scheduler: SharedScheduler<StableMemory, IcrcBridgeOpImpl>,
+    ) -> Self {
+        Self { state, scheduler }
+    }
+
+    /// Returns the refunds of the burnt deposits of the failed bundles.
+    ///
+    /// Bundle with a deposit, aborted by the operator, is failed here, because such deposit
+    /// never reaches the burn step.
+    fn pending_refunds(&self) -> Vec<(OperationId, IcrcBridgeOp)> {
+        let incomplete = self.state.borrow().operations.get_incomplete();
+
+        let mut refunds = vec![];
+        for (id, op) in incomplete {
+            let IcrcBridgeOp::SignMintOrder {
+                order,
+                is_refund: false,
+                pending_since,
+            } = op.0
+            else {
+                continue;
+            };
+
+            // The signing task retries until the delay is elapsed, so the deposit
+            // is refunded after it, not to be progressed twice.
+            if IcrcBridgeOpImpl::check_mint_order_signing_delay(pending_since).is_err() {
+                continue;
+            }
+
+            let bundle = get_icrc_state()
+                .borrow()
+                .deposit_bundles
+                .get_by_operation(id);
+            let Some((bundle_id, bundle)) = bundle else {
+                continue;
+            };
+
+            if bundle.failure.is_none() {
+                let Some(aborted_id) = self.find_aborted(&bundle) else {
+                    continue;
+                };
+                log::warn!("Deposit bundle {bundle_id} failed: deposit {aborted_id} is aborted");
+                get_icrc_state()
+                    .borrow_mut()
+                    .deposit_bundles
+                    .fail(bundle_id, format!("deposit {aborted_id} is aborted"));
+            }
+
+            let Some(item) = bundle.items.iter().find(|item| item.operation_id == id) else {
+                continue;
+            };
+            let event = refund_event(id, order, bundle.sender, item.from_subaccount);
+            refunds.push((
+                id,
+                IcrcBridgeOp::MintIcrcTokens {
+                    event,
+                    pending_since: ic::time(),
+                },
+            ));
+        }
+
+        refunds
+    }
+
+    /// Returns the aborted deposit of the bundle, if any.
+    fn find_aborted(&self, bundle: &DepositBundle) -> Option<OperationId> {
+        let state = self.state.borrow();
+        bundle.operation_ids().into_iter().find(|id| {
+            matches!(
+                state.operations.get(*id).map(|op| op.0),
+                Some(IcrcBridgeOp::DepositAborted { .. })
+            )
+        })
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl BridgeService for DepositBundlesService {
+    async fn run(&self) -> BTFResult<()> {
+        for (id, refund) in self.pending_refunds() {
+            let refund = IcrcBridgeOpImpl(refund);
+            let updated = self
+                .state
+                .borrow_mut()
+                .operations
+                .update(id, refund.clone());
+            if !updated {
+                continue;
+            }
+
+            log::info!("Burnt tokens of deposit {id} of the failed bundle are refunded.");
+            if let Some(options) = refund.scheduling_options() {
+                let task = self.state.borrow_mut().new_task(id, refund);
+                self.scheduler
+                    .append_task(ScheduledTask::with_options(task, options));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn push_operation(&self, _: OperationId) -> BTFResult<()> {
+        let msg = "Operations should not be pushed to the DepositBundlesService service";
+        log::warn!("{msg}");
+        Err(Error::FailedToProgress(msg.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_canister::bridge::OperationProgress;
+    use bridge_canister::runtime::service::sign_orders::SignMintOrdersService;
+    use bridge_canister::runtime::state::config::ConfigStorage;
+    use bridge_canister::runtime::BridgeRuntime;
+    use bridge_did::deposit_bundle::{BundleItem, DepositBundleState};
+    use bridge_did::event_data::MintedEventData;
+    use bridge_did::id256::Id256;
+    use candid::Nat;
+    use did::{H160, U256};
+    use eth_signer::sign_strategy::SigningStrategy;
+    use ic_exports::ic_kit::MockContext;
+    use ic_storage::IcStorage;
+
+    use super::*;
+    use crate::constant::IC_CHAIN_ID;
+    use crate::ops::IcrcMintOrderHandler;
+
+    fn sender() -> Principal {
+        Principal::from_slice(&[1; 29])
+    }
+
+    fn token(seed: u8) -> Principal {
+        Principal::from_slice(&[seed; 29])
+    }
+
+    fn burn(seed: u8) -> Icrc2Burn {
+        Icrc2Burn {
+            sender: sender(),
+            amount: 1000u64.into(),
+            icrc2_token_principal: token(seed),
+            erc20_token_address: H160::from_slice(&[seed; 20]),
+            from_subaccount: Some([seed; 32]),
+            recipient_address: H160::from_slice(&[4; 20]),
+            approve_after_mint: None,
+            fee_payer: None,
+            confirmation_delay_secs: None,
+            preference_fields: None,
+        }
+    }
+
+    fn burnt(seed: u8) -> IcrcBridgeOpImpl {
+        IcrcBridgeOpImpl(IcrcBridgeOp::SignMintOrder {
+            order: MintOrder {
+                amount: 1000u64.into(),
+                sender: Id256::from(&sender()),
+                src_token: Id256::from(&token(seed)),
+                recipient: H160::from_slice(&[4; 20]),
+                dst_token: H160::from_slice(&[seed; 20]),
+                nonce: seed as _,
+                sender_chain_id: IC_CHAIN_ID,
+                recipient_chain_id: 5,
+                name: [seed; 32],
+                symbol: [seed; 16],
+                decimals: 8,
+                approve_spender: H160::default(),
+                approve_amount: U256::zero(),
+                fee_payer: H160::default(),
+                permit: None,
+            },
+            is_refund: false,
+            pending_since: 0,
+        })
+    }
+
+    fn not_burnt(seed: u8) -> IcrcBridgeOpImpl {
+        IcrcBridgeOpImpl(IcrcBridgeOp::BurnIcrc2Tokens {
+            burn: burn(seed),
+            pending_since: 0,
+        })
+    }
+
+    fn runtime() -> BridgeRuntime<IcrcBridgeOpImpl> {
+        MockContext::new().inject();
+        let config = ConfigStorage::get();
+        config
+            .borrow_mut()
+            .set_signing_strategy(SigningStrategy::Local {
+                private_key: [3; 32],
+            });
+        config.borrow_mut().update_evm_params(|p| p.chain_id = 5);
+        BridgeRuntime::default(config)
+    }
+
+    /// Creates operations of the bundle items with the given states.
+    fn create_bundle(
+        state: &RuntimeState<IcrcBridgeOpImpl>,
+        items: Vec<(u8, IcrcBridgeOpImpl)>,
+    ) -> (BundleId, Vec<OperationId>) {
+        let mut ids = vec![];
+        let mut bundle_items = vec![];
+        for (seed, op) in items {
+            let operation_id = state.borrow_mut().operations.new_operation(op, None);
+            ids.push(operation_id);
+            bundle_items.push(BundleItem {
+                operation_id,
+                from_subaccount: Some([seed; 32]),
+            });
+        }
+
+        let bundle_id =
+            get_icrc_state()
+                .borrow_mut()
+                .deposit_bundles
+                .insert(sender(), bundle_items, 0);
+        (bundle_id, ids)
+    }
+
+    fn current_status(state: &RuntimeState<IcrcBridgeOpImpl>, id: BundleId) -> DepositBundleStatus {
+        let bundle = get_icrc_state().borrow().deposit_bundles.get(id).unwrap();
+        bundle_status(state, id, bundle)
+    }
+
+    #[test]
+    fn bundle_should_fit_into_single_batch() {
+        assert!(check_bundle(&[burn(2), burn(3)]).is_ok());
+        assert!(check_bundle(&[burn(2)]).is_err());
+        assert!(check_bundle(&vec![burn(2); MAX_MINT_ORDERS_IN_BATCH + 1]).is_err());
+
+        let sponsored = Icrc2Burn {
+            fee_payer: Some(H160::from_slice(&[9; 20])),
+            ..burn(3)
+        };
+        assert!(check_bundle(&[sponsored.clone(), sponsored.clone()]).is_ok());
+        assert!(check_bundle(&[burn(2), sponsored]).is_err());
+    }
+
+    #[tokio::test]
+    async fn bundle_is_minted_by_single_atomic_batch() {
+        let runtime = runtime();
+        let state = runtime.state().clone();
+        let (bundle_id, ids) = create_bundle(&state, vec![(2, burnt(2)), (3, burnt(3))]);
+        assert_eq!(
+            current_status(&state, bundle_id).state,
+            DepositBundleState::Burning
+        );
+
+        let handler = IcrcMintOrderHandler::new(state.clone(), runtime.scheduler().clone());
+        let service = SignMintOrdersService::new(handler);
+
+        // The bundle order is not signed until all the bundle orders are ready.
+        service.push_operation(ids[0]).unwrap();
+        service.run().await.unwrap();
+        assert!(matches!(
+            state.borrow().operations.get(ids[0]).unwrap().0,
+            IcrcBridgeOp::SignMintOrder { .. }
+        ));
+
+        service.push_operation(ids[1]).unwrap();
+        service.run().await.unwrap();
+
+        let signed: Vec<_> = ids
+            .iter()
+            .map(|id| match state.borrow().operations.get(*id).unwrap().0 {
+                IcrcBridgeOp::ConfirmMint { order, .. } => order,
+                op => panic!("unexpected operation state: {op:?}"),
+            })
+            .collect();
+        assert_eq!(signed[0].all_orders(), signed[1].all_orders());
+        assert!(signed.iter().all(|order| order.is_atomic()));
+        assert_eq!(
+            current_status(&state, bundle_id).state,
+            DepositBundleState::Minting
+        );
+
+        for id in &ids {
+            let minted = IcrcBridgeOpImpl(IcrcBridgeOp::WrappedTokenMintConfirmed(
+                MintedEventData::default(),
+            ));
+            assert!(state.borrow_mut().operations.update(*id, minted));
+        }
+        let status = current_status(&state, bundle_id);
+        assert_eq!(status.state, DepositBundleState::Completed);
+        assert!(status
+            .items
+            .iter()
+            .all(|item| item.status == BundleItemStatus::Minted));
+    }
+
+    #[tokio::test]
+    async fn failed_bundle_burnt_deposits_are_refunded() {
+        let runtime = runtime();
+        let state = runtime.state().clone();
+        let (bundle_id, ids) = create_bundle(
+            &state,
+            vec![(2, burnt(2)), (3, not_burnt(3)), (4, not_burnt(4))],
+        );
+
+        // The burn fails upfront, because the token is not allowed.
+        get_icrc_state()
+            .borrow_mut()
+            .token_allow_list
+            .add(token(9))
+            .unwrap();
+        let progress = not_burnt(3).progress(ids[1], state.clone()).await.unwrap();
+        let OperationProgress::Progress(aborted) = progress else {
+            panic!("deposit should be aborted");
+        };
+        assert!(matches!(aborted.0, IcrcBridgeOp::DepositAborted { .. }));
+        assert!(state.borrow_mut().operations.update(ids[1], aborted));
+        assert!(current_status(&state, bundle_id).failure.is_some());
+
+        // Deposits of the failed bundle are aborted without the burn.
+        let progress = not_burnt(4).progress(ids[2], state.clone()).await.unwrap();
+        let OperationProgress::Progress(aborted) = progress else {
+            panic!("deposit should be aborted");
+        };
+        assert!(matches!(aborted.0, IcrcBridgeOp::DepositAborted { .. }));
+        assert!(state.borrow_mut().operations.update(ids[2], aborted));
+        assert!(get_icrc_state()
+            .borrow()
+            .burn_intents
+            .get(&ids[2])
+            .is_none());
+
+        // Burnt tokens are refunded to the account they are burnt from.
+        DepositBundlesService::new(state.clone(), runtime.scheduler().clone())
+            .run()
+            .await
+            .unwrap();
+        let IcrcBridgeOp::MintIcrcTokens { event, .. } =
+            state.borrow().operations.get(ids[0]).unwrap().0
+        else {
+            panic!("burnt deposit should be refunded");
+        };
+        let recipient = IcrcWithdrawalRecipient::decode(&event.recipient_id).unwrap();
+        assert_eq!(recipient.principal, sender());
+        assert_eq!(recipient.subaccount, Some([2; 32]));
+        assert_eq!(event.to_token, Id256::from(&token(2)).0.to_vec());
+        assert_eq!(event.amount, U256::from(1000u64));
+
+        let status = current_status(&state, bundle_id);
+        assert_eq!(status.state, DepositBundleState::Refunding);
+        let statuses: Vec<_> = status.items.iter().map(|item| item.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                BundleItemStatus::Refunding,
+                BundleItemStatus::Aborted,
+                BundleItemStatus::Aborted
+            ]
+        );
+
+        let refunded = IcrcBridgeOpImpl(IcrcBridgeOp::IcrcMintConfirmed {
+            src_address: H160::from_slice(&[4; 20]),
+            icrc_tx_id: Nat::from(1u64),
+            icrc_memo: None,
+        });
+        assert!(state.borrow_mut().operations.update(ids[0], refunded));
+        let status = current_status(&state, bundle_id);
+        assert_eq!(status.state, DepositBundleState::Refunded);
+    }
+}
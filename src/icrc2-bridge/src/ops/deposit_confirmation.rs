@@ -0,0 +1,281 @@
+//! Two-step deposits, which wait for the sender confirmation before the tokens are burnt.
+
+use std::time::Duration;
+
+use bridge_canister::runtime::service::BridgeService;
+use bridge_canister::runtime::RuntimeState;
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::op_id::OperationId;
+use bridge_did::operations::IcrcBridgeOp;
+use bridge_did::reason::Icrc2Burn;
+use candid::Principal;
+use ic_exports::ic_kit::ic;
+
+use super::IcrcBridgeOpImpl;
+
+/// Delay between the checks of the pending deposits expiration.
+pub const EXPIRE_DEPOSITS_DELAY: Duration = Duration::from_secs(60);
+
+/// Creates the initial state of the deposit.
+///
+/// If the burn requests a confirmation delay, the deposit waits for the sender confirmation
+/// for the requested delay, limited by `max_delay_secs`. Otherwise the tokens are burnt immediately.
+pub fn new_deposit(burn: Icrc2Burn, max_delay_secs: u64, now: u64) -> IcrcBridgeOp {
+    let delay_secs = burn
+        .confirmation_delay_secs
+        .unwrap_or_default()
+        .min(max_delay_secs);
+    if delay_secs == 0 {
+        return IcrcBridgeOp::BurnIcrc2Tokens {
+            burn,
+            pending_since: now,
+        };
+    }
+
+    IcrcBridgeOp::PendingUserConfirmation {
+        burn,
+        expires_at: now.saturating_add(Duration::from_secs(delay_secs).as_nanos() as u64),
+        pending_since: now,
+    }
+}
+
+/// Returns the state of the deposit, confirmed by the `caller`.
+pub fn confirm(op: IcrcBridgeOp, caller: Principal, now: u64) -> BTFResult<IcrcBridgeOp> {
+    let burn = pending_burn(op, caller, now)?;
+    Ok(IcrcBridgeOp::BurnIcrc2Tokens {
+        burn,
+        pending_since: now,
+    })
+}
+
+/// Returns the state of the deposit, aborted by the `caller`.
+pub fn abort(op: IcrcBridgeOp, caller: Principal, now: u64) -> BTFResult<IcrcBridgeOp> {
+    let burn = pending_burn(op, caller, now)?;
+    Ok(IcrcBridgeOp::DepositAborted {
+        burn,
+        expired: false,
+    })
+}
+
+/// Returns the aborted state of the deposit, if its confirmation is expired.
+pub fn expire(op: &IcrcBridgeOp, now: u64) -> Option<IcrcBridgeOp> {
+    match op {
+        IcrcBridgeOp::PendingUserConfirmation {
+            burn, expires_at, ..
+        } if now >= *expires_at => Some(IcrcBridgeOp::DepositAborted {
+            burn: burn.clone(),
+            expired: true,
+        }),
+        _ => None,
+    }
+}
+
+fn pending_burn(op: IcrcBridgeOp, caller: Principal, now: u64) -> BTFResult<Icrc2Burn> {
+    let IcrcBridgeOp::PendingUserConfirmation {
+        burn, expires_at, ..
+    } = op
+    else {
+        return Err(Error::FailedToProgress(
+            "deposit is not waiting for the user confirmation".into(),
+        ));
+    };
+
+    if burn.sender != caller {
+        return Err(Error::AccessDenied);
+    }
+
+    if now >= expires_at {
+        return Err(Error::FailedToProgress(
+            "deposit confirmation is expired".into(),
+        ));
+    }
+
+    Ok(burn)
+}
+
+/// Service to abort the deposits, which were not confirmed in time.
+pub struct ExpirePendingDepositsService {
+    state: RuntimeState<IcrcBridgeOpImpl>,
+}
+
+impl ExpirePendingDepositsService {
+    pub fn new(state: RuntimeState<IcrcBridgeOpImpl>) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl BridgeService for ExpirePendingDepositsService {
+    async fn run(&self) -> BTFResult<()> {
+        let now = ic::time();
+        let expired: Vec<_> = self
+            .state
+            .borrow()
+            .operations
+            .get_incomplete()
+            .into_iter()
+            .filter_map(|(id, op)| expire(&op.0, now).map(|aborted| (id, aborted)))
+            .collect();
+
+        for (id, aborted) in expired {
+            log::info!("Deposit {id} is not confirmed in time and is aborted.");
+            self.state
+                .borrow_mut()
+                .operations
+                .update(id, IcrcBridgeOpImpl(aborted));
+        }
+
+        Ok(())
+    }
+
+    fn push_operation(&self, _: OperationId) -> BTFResult<()> {
+        let msg = "Operations should not be pushed to the ExpirePendingDepositsService service";
+        log::warn!("{msg}");
+        Err(Error::FailedToProgress(msg.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_canister::bridge::Operation;
+    use bridge_canister::runtime::state::config::ConfigStorage;
+    use bridge_canister::runtime::BridgeRuntime;
+    use did::H160;
+    use ic_exports::ic_kit::MockContext;
+    use ic_storage::IcStorage;
+
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+    const MAX_DELAY_SECS: u64 = 600;
+
+    fn sender() -> Principal {
+        Principal::from_slice(&[1; 29])
+    }
+
+    fn burn(confirmation_delay_secs: Option<u64>) -> Icrc2Burn {
+        Icrc2Burn {
+            sender: sender(),
+            amount: 1000u64.into(),
+            icrc2_token_principal: Principal::from_slice(&[2; 29]),
+            erc20_token_address: H160::from_slice(&[3; 20]),
+            from_subaccount: None,
+            recipient_address: H160::from_slice(&[4; 20]),
+            approve_after_mint: None,
+            fee_payer: None,
+            confirmation_delay_secs,
+            preference_fields: None,
+        }
+    }
+
+    #[test]
+    fn deposit_without_delay_is_burnt_immediately() {
+        for delay in [None, Some(0)] {
+            let op = new_deposit(burn(delay), MAX_DELAY_SECS, 10);
+            assert!(matches!(
+                op,
+                IcrcBridgeOp::BurnIcrc2Tokens {
+                    pending_since: 10,
+                    ..
+                }
+            ));
+        }
+
+        // Delayed deposits are disabled by the configuration.
+        let op = new_deposit(burn(Some(60)), 0, 10);
+        assert!(matches!(op, IcrcBridgeOp::BurnIcrc2Tokens { .. }));
+    }
+
+    #[test]
+    fn confirmation_delay_is_limited_by_config() {
+        let op = new_deposit(burn(Some(60)), MAX_DELAY_SECS, 10);
+        assert!(matches!(
+            op,
+            IcrcBridgeOp::PendingUserConfirmation { expires_at, .. } if expires_at == 10 + 60 * SECOND
+        ));
+
+        let op = new_deposit(burn(Some(u64::MAX)), MAX_DELAY_SECS, 10);
+        assert!(matches!(
+            op,
+            IcrcBridgeOp::PendingUserConfirmation { expires_at, .. }
+                if expires_at == 10 + MAX_DELAY_SECS * SECOND
+        ));
+    }
+
+    #[test]
+    fn pending_deposit_is_confirmed_by_sender() {
+        let op = new_deposit(burn(Some(60)), MAX_DELAY_SECS, 0);
+
+        let other = Principal::from_slice(&[9; 29]);
+        assert_eq!(
+            confirm(op.clone(), other, SECOND).unwrap_err(),
+            Error::AccessDenied
+        );
+
+        let confirmed = confirm(op, sender(), SECOND).unwrap();
+        assert!(matches!(
+            confirmed,
+            IcrcBridgeOp::BurnIcrc2Tokens { pending_since, .. } if pending_since == SECOND
+        ));
+
+        // Confirmed deposit can't be confirmed or aborted again.
+        assert!(confirm(confirmed.clone(), sender(), SECOND).is_err());
+        assert!(abort(confirmed, sender(), SECOND).is_err());
+    }
+
+    #[test]
+    fn pending_deposit_is_aborted_by_sender() {
+        let op = new_deposit(burn(Some(60)), MAX_DELAY_SECS, 0);
+
+        let aborted = abort(op, sender(), SECOND).unwrap();
+        assert!(matches!(
+            aborted,
+            IcrcBridgeOp::DepositAborted { expired: false, .. }
+        ));
+        assert!(IcrcBridgeOpImpl(aborted.clone()).is_complete());
+        assert!(confirm(aborted, sender(), SECOND).is_err());
+    }
+
+    #[test]
+    fn expired_deposit_cannot_be_confirmed() {
+        let op = new_deposit(burn(Some(60)), MAX_DELAY_SECS, 0);
+
+        assert!(expire(&op, 59 * SECOND).is_none());
+        assert!(matches!(
+            expire(&op, 60 * SECOND),
+            Some(IcrcBridgeOp::DepositAborted { expired: true, .. })
+        ));
+        assert!(confirm(op.clone(), sender(), 60 * SECOND).is_err());
+        assert!(abort(op, sender(), 60 * SECOND).is_err());
+    }
+
+    #[tokio::test]
+    async fn expired_deposits_are_aborted_by_service() {
+        let context = MockContext::new().inject();
+
+        let runtime: BridgeRuntime<IcrcBridgeOpImpl> = BridgeRuntime::default(ConfigStorage::get());
+        let state = runtime.state().clone();
+
+        let now = ic::time();
+        let short = IcrcBridgeOpImpl(new_deposit(burn(Some(60)), MAX_DELAY_SECS, now));
+        let long = IcrcBridgeOpImpl(new_deposit(burn(Some(300)), MAX_DELAY_SECS, now));
+        let short_id = state.borrow_mut().operations.new_operation(short, None);
+        let long_id = state.borrow_mut().operations.new_operation(long, None);
+
+        let service = ExpirePendingDepositsService::new(state.clone());
+        context.add_time(120 * SECOND);
+        service.run().await.unwrap();
+
+        let short = state.borrow().operations.get(short_id).unwrap();
+        assert!(matches!(
+            short.0,
+            IcrcBridgeOp::DepositAborted { expired: true, .. }
+        ));
+        let long = state.borrow().operations.get(long_id).unwrap();
+        assert!(matches!(
+            long.0,
+            IcrcBridgeOp::PendingUserConfirmation { .. }
+        ));
+        assert_eq!(state.borrow().operations.get_incomplete().len(), 1);
+    }
+}
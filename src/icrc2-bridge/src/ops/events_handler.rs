@@ -3,9 +3,12 @@ use bridge_canister::runtime::service::fetch_logs::BtfBridgeEventHandler;
 use bridge_did::event_data::{BurntEventData, MintedEventData, NotifyMinterEventData};
 use bridge_did::operations::IcrcBridgeOp;
 use bridge_did::reason::Icrc2Burn;
-use candid::Decode;
+use bridge_utils::event_payload::decode_candid_payload;
+use ic_exports::ic_kit::ic;
+use ic_stable_structures::CellStructure;
 
-use super::IcrcBridgeOpImpl;
+use super::{deposit_confirmation, IcrcBridgeOpImpl};
+use crate::canister::get_icrc_state;
 
 pub struct IcrcEventsHandler;
 
@@ -26,7 +29,10 @@ impl BtfBridgeEventHandler<IcrcBridgeOpImpl> for IcrcEventsHandler {
     ) -> Option<OperationAction<IcrcBridgeOpImpl>> {
         log::trace!("wrapped token burnt");
         let memo = event.memo();
-        let operation = IcrcBridgeOpImpl(IcrcBridgeOp::MintIcrcTokens(event));
+        let operation = IcrcBridgeOpImpl(IcrcBridgeOp::MintIcrcTokens {
+            event,
+            pending_since: ic::time(),
+        });
 
         Some(OperationAction::Create(operation, memo))
     }
@@ -37,21 +43,173 @@ impl BtfBridgeEventHandler<IcrcBridgeOpImpl> for IcrcEventsHandler {
     ) -> Option<OperationAction<IcrcBridgeOpImpl>> {
         log::debug!("on_minter_notification {event:?}");
 
-        let mut icrc_burn = match Decode!(&event.user_data, Icrc2Burn) {
+        let mut icrc_burn = match decode_candid_payload::<Icrc2Burn>(&event.user_data) {
             Ok(icrc_burn) => icrc_burn,
             Err(e) => {
                 log::warn!("failed to decode Btfbridge notification into Icrc2Burn: {e}");
-                return None;
+                return Some(OperationAction::reject(
+                    event,
+                    format!("failed to decode Icrc2Burn: {e}"),
+                ));
             }
         };
 
+        let preferences = get_icrc_state()
+            .borrow()
+            .bridge_preferences
+            .get(&icrc_burn.sender)
+            .unwrap_or_default();
+        icrc_burn.apply_preferences(&preferences);
+
         // Approve tokens only if the burner owns recipient wallet.
         if event.tx_sender != icrc_burn.recipient_address {
-            icrc_burn.approve_after_mint = None;
+            icrc_burn.remove_approve_after_mint();
+        }
+
+        if let Some(fields) = &icrc_burn.preference_fields {
+            log::debug!("deposit fields {fields:?} are taken from the sender preferences");
         }
 
         let memo = event.memo();
-        let operation = IcrcBridgeOpImpl(IcrcBridgeOp::BurnIcrc2Tokens(icrc_burn));
+        let max_delay_secs = *get_icrc_state()
+            .borrow()
+            .max_deposit_confirmation_delay_secs
+            .get();
+        let operation = IcrcBridgeOpImpl(deposit_confirmation::new_deposit(
+            icrc_burn,
+            max_delay_secs,
+            ic::time(),
+        ));
         Some(OperationAction::Create(operation, memo))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bridge_did::dead_letter::DeadLetterEventData;
+    use bridge_did::event_data::MinterNotificationType;
+    use bridge_did::preferences::{BridgePreferences, PreferenceField};
+    use candid::{Encode, Principal};
+    use did::H160;
+    use ic_exports::ic_kit::MockContext;
+
+    use super::*;
+
+    fn notification(burn: &Icrc2Burn, tx_sender: H160) -> NotifyMinterEventData {
+        NotifyMinterEventData {
+            notification_type: MinterNotificationType::DepositRequest,
+            tx_sender,
+            user_data: Encode!(burn).unwrap(),
+            memo: vec![],
+        }
+    }
+
+    fn created_burn(action: Option<OperationAction<IcrcBridgeOpImpl>>) -> Icrc2Burn {
+        match action {
+            Some(OperationAction::Create(
+                IcrcBridgeOpImpl(IcrcBridgeOp::BurnIcrc2Tokens { burn, .. }),
+                _,
+            )) => burn,
+            action => panic!("unexpected action: {action:?}"),
+        }
+    }
+
+    #[test]
+    fn deposit_falls_back_to_sender_preferences() {
+        MockContext::new().inject();
+        let sender = Principal::from_slice(&[1; 29]);
+        let recipient = H160::from_slice(&[2; 20]);
+        get_icrc_state()
+            .borrow_mut()
+            .bridge_preferences
+            .set(
+                sender,
+                BridgePreferences {
+                    recipient_address: Some(recipient.clone()),
+                    fee_payer: Some(H160::from_slice(&[3; 20])),
+                    approve_spender: Some(H160::from_slice(&[4; 20])),
+                },
+            )
+            .unwrap();
+
+        let burn = Icrc2Burn {
+            sender,
+            amount: 1000u64.into(),
+            icrc2_token_principal: Principal::from_slice(&[5; 29]),
+            erc20_token_address: H160::from_slice(&[6; 20]),
+            from_subaccount: None,
+            recipient_address: H160::zero(),
+            approve_after_mint: None,
+            fee_payer: Some(H160::from_slice(&[7; 20])),
+            confirmation_delay_secs: None,
+            preference_fields: None,
+        };
+
+        let created = created_burn(
+            IcrcEventsHandler.on_minter_notification(notification(&burn, recipient.clone())),
+        );
+        assert_eq!(created.recipient_address, recipient);
+        assert_eq!(created.fee_payer, Some(H160::from_slice(&[7; 20])));
+        assert_eq!(
+            created.approve_after_mint.unwrap().approve_spender,
+            H160::from_slice(&[4; 20])
+        );
+        // The operation log records the fields, taken from the preferences.
+        assert_eq!(
+            created.preference_fields,
+            Some(vec![
+                PreferenceField::RecipientAddress,
+                PreferenceField::ApproveSpender
+            ])
+        );
+
+        // Approve from the preferences is dropped, if the burner doesn't own the recipient.
+        let created = created_burn(
+            IcrcEventsHandler
+                .on_minter_notification(notification(&burn, H160::from_slice(&[8; 20]))),
+        );
+        assert!(created.approve_after_mint.is_none());
+        assert_eq!(
+            created.preference_fields,
+            Some(vec![PreferenceField::RecipientAddress])
+        );
+
+        // Deleted preferences are not used.
+        get_icrc_state()
+            .borrow_mut()
+            .bridge_preferences
+            .remove(&sender);
+        let created =
+            created_burn(IcrcEventsHandler.on_minter_notification(notification(&burn, recipient)));
+        assert_eq!(created.recipient_address, H160::zero());
+        assert_eq!(created.preference_fields, None);
+    }
+
+    #[test]
+    fn malformed_deposit_is_rejected() {
+        MockContext::new().inject();
+        let mut event = notification(
+            &Icrc2Burn {
+                sender: Principal::from_slice(&[1; 29]),
+                amount: 1000u64.into(),
+                icrc2_token_principal: Principal::from_slice(&[5; 29]),
+                erc20_token_address: H160::from_slice(&[6; 20]),
+                from_subaccount: None,
+                recipient_address: H160::from_slice(&[2; 20]),
+                approve_after_mint: None,
+                fee_payer: None,
+                confirmation_delay_secs: None,
+                preference_fields: None,
+            },
+            H160::from_slice(&[2; 20]),
+        );
+        event.user_data.truncate(event.user_data.len() - 1);
+
+        let action = IcrcEventsHandler.on_minter_notification(event.clone());
+        assert!(matches!(
+            action,
+            Some(OperationAction::Reject { event: DeadLetterEventData::Notify(rejected), .. })
+                if rejected == event
+        ));
+    }
+}
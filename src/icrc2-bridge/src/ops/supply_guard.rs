@@ -0,0 +1,246 @@
+//! Guard, which halts the bridge if the supply of the wrapped tokens exceeds the ICRC tokens,
+//! locked by the bridge. Such excess means that the wrapped tokens were minted without deposit.
+
+use std::time::Duration;
+
+use bridge_canister::runtime::service::BridgeService;
+use bridge_canister::runtime::state::SharedConfig;
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::halt::BridgeHalt;
+use bridge_did::op_id::OperationId;
+use bridge_did::supply_guard::{SupplyCheck, SupplyGuardConfig, SupplyReconciliation};
+use bridge_did::token_decimals::TokenDecimals;
+use bridge_utils::evm_bridge;
+use candid::{Nat, Principal};
+use did::U256;
+use ic_exports::ic_kit::ic;
+use icrc_client::account::Account;
+
+use super::ErrorCodes;
+use crate::canister::get_icrc_state;
+use crate::state::DecimalsConversion;
+use crate::tokens::icrc1;
+
+/// Delay between the checks if the configured supply check interval is passed.
+pub const SUPPLY_GUARD_DELAY: Duration = Duration::from_secs(60);
+
+/// Service to reconcile the wrapped tokens supply with the locked ICRC tokens.
+///
+/// Does nothing, if the supply guard is not configured.
+pub struct SupplyGuardService {
+    config: SharedConfig,
+}
+
+impl SupplyGuardService {
+    pub fn new(config: SharedConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl BridgeService for SupplyGuardService {
+    async fn run(&self) -> BTFResult<()> {
+        let Some(guard) = get_icrc_state().borrow().supply_guard.get_config() else {
+            log::trace!("Supply guard is not configured. Skipping supply check.");
+            return Ok(());
+        };
+
+        let now = ic::time();
+        let is_due = get_icrc_state()
+            .borrow()
+            .supply_guard
+            .is_check_due(guard.check_interval_secs, now);
+        if !is_due {
+            return Ok(());
+        }
+
+        // The state is not borrowed across the ledger and EVM requests.
+        let tokens = get_icrc_state().borrow().token_decimals.get_all();
+        let mut reconciliations = vec![];
+        for (token, decimals) in tokens {
+            if let Some(reconciliation) = reconcile_token(&self.config, token, decimals).await? {
+                reconciliations.push(reconciliation);
+            }
+        }
+
+        apply_supply_check(
+            &self.config,
+            &guard,
+            SupplyCheck {
+                timestamp: now,
+                reconciliations,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn push_operation(&self, _: OperationId) -> BTFResult<()> {
+        let msg = "Operations should not be pushed to the SupplyGuardService service";
+        log::warn!("{msg}");
+        Err(Error::FailedToProgress(msg.into()))
+    }
+}
+
+/// Queries the locked amount of the token and the supply of its wrapped tokens.
+/// Returns `None` if the token has no wrapped token yet.
+async fn reconcile_token(
+    config: &SharedConfig,
+    token: Principal,
+    decimals: TokenDecimals,
+) -> BTFResult<Option<SupplyReconciliation>> {
+    let Some(wrapped_token) = decimals.wrapped_token.clone() else {
+        return Ok(None);
+    };
+
+    let client = config.borrow().get_json_rpc_client();
+    let wrapped = std::iter::once((wrapped_token, decimals.wrapped_decimals)).chain(
+        decimals
+            .retired_wrapped_tokens
+            .iter()
+            .map(|retired| (retired.address.clone(), retired.decimals)),
+    );
+
+    let mut wrapped_tokens = vec![];
+    let mut wrapped_supply = U256::zero();
+    for (address, wrapped_decimals) in wrapped {
+        let supply = evm_bridge::query_total_supply(&client, address.clone())
+            .await
+            .map_err(|e| Error::EvmRequestFailed(e.to_string()))?;
+        let conversion = DecimalsConversion {
+            ledger_decimals: decimals.ledger_decimals,
+            wrapped_decimals,
+        };
+        wrapped_supply = wrapped_supply
+            .0
+            .saturating_add(conversion.to_ledger(&supply)?.0)
+            .into();
+        wrapped_tokens.push(address);
+    }
+
+    let locked = icrc1::query_balance(token, Account::from(ic::id()))
+        .await
+        .map_err(|e| Error::Custom {
+            code: ErrorCodes::IcrcBalanceRequestFailed as _,
+            msg: format!("failed to query locked balance of token {token}: {e}"),
+        })?;
+
+    Ok(Some(SupplyReconciliation {
+        token,
+        wrapped_tokens,
+        locked: nat_to_u256(&locked),
+        wrapped_supply,
+    }))
+}
+
+/// Converts the ledger balance to `U256`, saturating at `U256::max_value()`.
+pub(crate) fn nat_to_u256(amount: &Nat) -> U256 {
+    let bytes = amount.0.to_bytes_be();
+    if bytes.len() > 32 {
+        return U256::max_value();
+    }
+    U256::from_big_endian(&bytes)
+}
+
+/// Stores the supply check result and halts the bridge, if the wrapped supply of any token
+/// exceeds its locked amount by more than the guard tolerance.
+///
+/// Returns the reconciliation, which caused the halt.
+pub fn apply_supply_check(
+    config: &SharedConfig,
+    guard: &SupplyGuardConfig,
+    check: SupplyCheck,
+) -> Option<SupplyReconciliation> {
+    let mismatch = check
+        .reconciliations
+        .iter()
+        .find(|reconciliation| reconciliation.is_mismatch(&guard.tolerance))
+        .cloned();
+    let timestamp = check.timestamp;
+    get_icrc_state()
+        .borrow_mut()
+        .supply_guard
+        .record_check(check);
+
+    let mismatch = mismatch?;
+    let reason = format!(
+        "supply of the wrapped tokens {:?} exceeds the locked amount of token {} by {}",
+        mismatch.wrapped_tokens,
+        mismatch.token,
+        mismatch.excess().0
+    );
+    log::error!("ALERT: {reason}");
+    config
+        .borrow_mut()
+        .halt_bridge(BridgeHalt { timestamp, reason });
+
+    Some(mismatch)
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_canister::runtime::state::config::ConfigStorage;
+    use bridge_did::concurrency::OperationConcurrencyLimits;
+    use did::H160;
+    use ic_exports::ic_kit::MockContext;
+    use ic_storage::IcStorage;
+
+    use super::*;
+
+    fn guard() -> SupplyGuardConfig {
+        SupplyGuardConfig {
+            tolerance: U256::from(10u64),
+            check_interval_secs: 60,
+        }
+    }
+
+    fn check(locked: u64, wrapped_supply: u64) -> SupplyCheck {
+        SupplyCheck {
+            timestamp: 42,
+            reconciliations: vec![SupplyReconciliation {
+                token: Principal::from_slice(&[1; 29]),
+                wrapped_tokens: vec![H160::from_slice(&[2; 20])],
+                locked: locked.into(),
+                wrapped_supply: wrapped_supply.into(),
+            }],
+        }
+    }
+
+    #[test]
+    fn supply_within_tolerance_does_not_halt() {
+        MockContext::new().inject();
+        let config = ConfigStorage::get();
+
+        assert_eq!(
+            apply_supply_check(&config, &guard(), check(1000, 1000)),
+            None
+        );
+        assert_eq!(
+            apply_supply_check(&config, &guard(), check(1000, 1010)),
+            None
+        );
+
+        assert_eq!(config.borrow().get_bridge_halt(), None);
+        assert_eq!(
+            get_icrc_state().borrow().supply_guard.last_check().cloned(),
+            Some(check(1000, 1010))
+        );
+    }
+
+    #[test]
+    fn injected_mismatch_halts_bridge() {
+        MockContext::new().inject();
+        let config = ConfigStorage::get();
+
+        let mismatch = apply_supply_check(&config, &guard(), check(1000, 1011));
+        assert_eq!(mismatch, Some(check(1000, 1011).reconciliations[0].clone()));
+
+        let halt = config.borrow().get_bridge_halt().unwrap();
+        assert_eq!(halt.timestamp, 42);
+        assert!(halt.reason.contains("exceeds the locked amount"));
+        assert_eq!(
+            config.borrow().get_effective_operation_concurrency_limits(),
+            OperationConcurrencyLimits::PAUSED
+        );
+    }
+}
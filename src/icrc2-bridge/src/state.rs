@@ -1,16 +1,82 @@
 use access_list::AccessList;
+use approval_deliveries::ApprovalDeliveryStorage;
+use bridge_did::op_id::OperationId;
+use bridge_did::reason::ApproveAmountPolicy;
+use bridge_preferences::BridgePreferencesStorage;
+use burn_intents::BurnIntentStorage;
+use deposit_bundles::DepositBundleStorage;
 pub use eth_signer::sign_strategy::{SigningStrategy, TransactionSigner};
 use ic_stable_structures::stable_structures::DefaultMemoryImpl;
-use ic_stable_structures::{default_ic_memory_manager, VirtualMemory};
+use ic_stable_structures::{default_ic_memory_manager, StableBTreeMap, StableCell, VirtualMemory};
+pub use ledger_capabilities::LedgerCapabilities;
+use ledger_capabilities::LedgerCapabilityRegistry;
+use permit_support::PermitSupportRegistry;
+use supply_guard::SupplyGuardStorage;
+use token_allow_list::TokenAllowList;
+pub use token_decimals::DecimalsConversion;
+use token_decimals::TokenDecimalsStorage;
 
-use crate::constant::ACCESS_LIST_MEMORY_ID;
+use crate::constant::{
+    ACCESS_LIST_MEMORY_ID, APPROVAL_CANCELLATIONS_MEMORY_ID,
+    APPROVAL_DELIVERIES_BY_EXPIRY_MEMORY_ID, APPROVAL_DELIVERIES_MEMORY_ID,
+    APPROVAL_DELIVERY_EXPIRY_MEMORY_ID, APPROVE_AMOUNT_POLICY_MEMORY_ID,
+    BRIDGE_PREFERENCES_MEMORY_ID, BURN_INTENTS_MEMORY_ID, DEFAULT_APPROVAL_DELIVERY_EXPIRY_SECS,
+    DEFAULT_DEPOSIT_FEE, DEFAULT_MAX_DEPOSIT_CONFIRMATION_DELAY_SECS,
+    DEFAULT_MINT_ORDER_SIGNING_DELAY_SECS, DEPOSIT_BUNDLES_MEMORY_ID,
+    DEPOSIT_BUNDLE_ITEMS_MEMORY_ID, DEPOSIT_CONFIRMATION_DELAY_MEMORY_ID, DEPOSIT_FEE_MEMORY_ID,
+    LEDGER_CAPABILITIES_MEMORY_ID, MAX_BRIDGE_PREFERENCES_USERS,
+    MINT_ORDER_SIGNING_DELAY_MEMORY_ID, PERMIT_SUPPORT_MEMORY_ID, SUPPLY_GUARD_MEMORY_ID,
+    TOKEN_ALLOW_LIST_MEMORY_ID, TOKEN_DECIMALS_MEMORY_ID,
+};
 
 mod access_list;
+mod approval_deliveries;
+mod bridge_preferences;
+mod burn_intents;
+mod deposit_bundles;
+mod ledger_capabilities;
+mod permit_support;
+mod supply_guard;
+mod token_allow_list;
+mod token_decimals;
 
 /// State of a bridge canister.
 pub struct IcrcState {
     /// Bridge canister configuration.
     pub access_list: AccessList<VirtualMemory<DefaultMemoryImpl>>,
+    /// Maximum delay, which deposits can wait for the user confirmation.
+    pub max_deposit_confirmation_delay_secs: StableCell<u64, VirtualMemory<DefaultMemoryImpl>>,
+    /// ICRC-2 tokens, allowed to be bridged.
+    pub token_allow_list: TokenAllowList<VirtualMemory<DefaultMemoryImpl>>,
+    /// Deposit fee in millionths of a token. Deposits should exceed it.
+    pub deposit_fee: StableCell<u64, VirtualMemory<DefaultMemoryImpl>>,
+    /// Delay between the deposit burn and the mint order signing, which makes the mint
+    /// time less predictable for front-running.
+    pub mint_order_signing_delay_secs: StableCell<u64, VirtualMemory<DefaultMemoryImpl>>,
+    /// Handling of the approve after mint amounts, which exceed the minted amount.
+    pub approve_amount_policy: StableCell<ApproveAmountPolicy, VirtualMemory<DefaultMemoryImpl>>,
+    /// Deposit preferences of the users.
+    pub bridge_preferences: BridgePreferencesStorage<VirtualMemory<DefaultMemoryImpl>>,
+    /// Decimals of the bridged ICRC tokens and their wrapped tokens.
+    pub token_decimals: TokenDecimalsStorage<VirtualMemory<DefaultMemoryImpl>>,
+    /// EIP-2612 permit support of the wrapped tokens.
+    pub permit_support: PermitSupportRegistry<VirtualMemory<DefaultMemoryImpl>>,
+    /// Standards support of the ICRC token ledgers.
+    pub ledger_capabilities: LedgerCapabilityRegistry<VirtualMemory<DefaultMemoryImpl>>,
+    /// Guard, which halts the bridge if the wrapped supply exceeds the locked tokens.
+    pub supply_guard: SupplyGuardStorage<VirtualMemory<DefaultMemoryImpl>>,
+    /// Write-ahead records of the deposit burns.
+    pub burn_intents: BurnIntentStorage<VirtualMemory<DefaultMemoryImpl>>,
+    /// Deposit bundles, which wrapped tokens are minted atomically.
+    pub deposit_bundles: DepositBundleStorage<VirtualMemory<DefaultMemoryImpl>>,
+    /// IC time of the approval after mint cancellations by the operation, which are not
+    /// applied to the mint orders yet.
+    pub approval_cancellations: StableBTreeMap<OperationId, u64, VirtualMemory<DefaultMemoryImpl>>,
+    /// Time, which the spender has to claim the withdrawal, delivered with an approval.
+    pub approval_delivery_expiry_secs: StableCell<u64, VirtualMemory<DefaultMemoryImpl>>,
+    /// IC time of the expiration of the granted withdrawal approvals by the operation,
+    /// which are not swept back yet.
+    pub approval_deliveries: ApprovalDeliveryStorage<VirtualMemory<DefaultMemoryImpl>>,
 }
 
 impl Default for IcrcState {
@@ -18,6 +84,56 @@ impl Default for IcrcState {
         let memory_manager = default_ic_memory_manager();
         Self {
             access_list: AccessList::new(memory_manager.get(ACCESS_LIST_MEMORY_ID)),
+            max_deposit_confirmation_delay_secs: StableCell::new(
+                memory_manager.get(DEPOSIT_CONFIRMATION_DELAY_MEMORY_ID),
+                DEFAULT_MAX_DEPOSIT_CONFIRMATION_DELAY_SECS,
+            )
+            .expect("failed to initialize deposit confirmation delay"),
+            token_allow_list: TokenAllowList::new(memory_manager.get(TOKEN_ALLOW_LIST_MEMORY_ID)),
+            deposit_fee: StableCell::new(
+                memory_manager.get(DEPOSIT_FEE_MEMORY_ID),
+                DEFAULT_DEPOSIT_FEE,
+            )
+            .expect("failed to initialize deposit fee"),
+            mint_order_signing_delay_secs: StableCell::new(
+                memory_manager.get(MINT_ORDER_SIGNING_DELAY_MEMORY_ID),
+                DEFAULT_MINT_ORDER_SIGNING_DELAY_SECS,
+            )
+            .expect("failed to initialize mint order signing delay"),
+            approve_amount_policy: StableCell::new(
+                memory_manager.get(APPROVE_AMOUNT_POLICY_MEMORY_ID),
+                ApproveAmountPolicy::default(),
+            )
+            .expect("failed to initialize approve amount policy"),
+            bridge_preferences: BridgePreferencesStorage::new(
+                memory_manager.get(BRIDGE_PREFERENCES_MEMORY_ID),
+                MAX_BRIDGE_PREFERENCES_USERS,
+            ),
+            token_decimals: TokenDecimalsStorage::new(memory_manager.get(TOKEN_DECIMALS_MEMORY_ID)),
+            permit_support: PermitSupportRegistry::new(
+                memory_manager.get(PERMIT_SUPPORT_MEMORY_ID),
+            ),
+            ledger_capabilities: LedgerCapabilityRegistry::new(
+                memory_manager.get(LEDGER_CAPABILITIES_MEMORY_ID),
+            ),
+            supply_guard: SupplyGuardStorage::new(memory_manager.get(SUPPLY_GUARD_MEMORY_ID)),
+            burn_intents: BurnIntentStorage::new(memory_manager.get(BURN_INTENTS_MEMORY_ID)),
+            deposit_bundles: DepositBundleStorage::new(
+                memory_manager.get(DEPOSIT_BUNDLES_MEMORY_ID),
+                memory_manager.get(DEPOSIT_BUNDLE_ITEMS_MEMORY_ID),
+            ),
+            approval_cancellations: StableBTreeMap::new(
+                memory_manager.get(APPROVAL_CANCELLATIONS_MEMORY_ID),
+            ),
+            approval_delivery_expiry_secs: StableCell::new(
+                memory_manager.get(APPROVAL_DELIVERY_EXPIRY_MEMORY_ID),
+                DEFAULT_APPROVAL_DELIVERY_EXPIRY_SECS,
+            )
+            .expect("failed to initialize approval delivery expiry"),
+            approval_deliveries: ApprovalDeliveryStorage::new(
+                memory_manager.get(APPROVAL_DELIVERIES_MEMORY_ID),
+                memory_manager.get(APPROVAL_DELIVERIES_BY_EXPIRY_MEMORY_ID),
+            ),
         }
     }
 }
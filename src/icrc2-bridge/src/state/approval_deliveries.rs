@@ -0,0 +1,155 @@
+use std::borrow::Cow;
+
+use bridge_did::op_id::OperationId;
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, Bound, StableBTreeMap, Storable};
+
+/// Key of the granted approval, ordered by the time of its expiration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct ExpiryKey {
+    expires_at: u64,
+    operation_id: u64,
+}
+
+impl ExpiryKey {
+    const SIZE: usize = 16;
+
+    fn new(operation_id: OperationId, expires_at: u64) -> Self {
+        Self {
+            expires_at,
+            operation_id: operation_id.as_u64(),
+        }
+    }
+}
+
+impl Storable for ExpiryKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        // Big endian numbers keep the byte order equal to the key order.
+        bytes.extend_from_slice(&self.expires_at.to_be_bytes());
+        bytes.extend_from_slice(&self.operation_id.to_be_bytes());
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let u64_at = |offset: usize| {
+            u64::from_be_bytes(
+                bytes[offset..offset + 8]
+                    .try_into()
+                    .expect("invalid approval expiry key size"),
+            )
+        };
+
+        Self {
+            expires_at: u64_at(0),
+            operation_id: u64_at(8),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: Self::SIZE as u32,
+        is_fixed_size: true,
+    };
+}
+
+/// IC time of the expiration of the granted withdrawal approvals by the operation,
+/// which are not swept back yet, with the index of the approvals by the expiration time.
+pub struct ApprovalDeliveryStorage<M: Memory> {
+    deliveries: StableBTreeMap<OperationId, u64, M>,
+    by_expiry: StableBTreeMap<ExpiryKey, (), M>,
+}
+
+impl<M: Memory> ApprovalDeliveryStorage<M> {
+    /// Creates the storage. The expiration index is built, if the storage has approvals,
+    /// tracked before the index was introduced.
+    pub fn new(deliveries_memory: M, by_expiry_memory: M) -> Self {
+        let mut storage = Self {
+            deliveries: StableBTreeMap::new(deliveries_memory),
+            by_expiry: StableBTreeMap::new(by_expiry_memory),
+        };
+
+        if storage.by_expiry.is_empty() {
+            for (id, expires_at) in storage.deliveries.iter() {
+                storage.by_expiry.insert(ExpiryKey::new(id, expires_at), ());
+            }
+        }
+
+        storage
+    }
+
+    /// Tracks the approval of the `id` withdrawal until it expires.
+    pub fn insert(&mut self, id: OperationId, expires_at: u64) {
+        if let Some(previous) = self.deliveries.insert(id, expires_at) {
+            self.by_expiry.remove(&ExpiryKey::new(id, previous));
+        }
+        self.by_expiry.insert(ExpiryKey::new(id, expires_at), ());
+    }
+
+    /// Stops tracking the approval of the `id` withdrawal.
+    pub fn remove(&mut self, id: OperationId) -> Option<u64> {
+        let expires_at = self.deliveries.remove(&id)?;
+        self.by_expiry.remove(&ExpiryKey::new(id, expires_at));
+        Some(expires_at)
+    }
+
+    /// Returns the withdrawals, which approvals are expired at the `now` IC time.
+    pub fn expired(&self, now: u64) -> Vec<OperationId> {
+        self.by_expiry
+            .iter()
+            .take_while(|(key, _)| key.expires_at <= now)
+            .map(|(key, _)| OperationId::new(key.operation_id))
+            .collect()
+    }
+
+    /// Returns the tracked withdrawals with the expiration time of their approvals.
+    pub fn get_all(&self) -> Vec<(OperationId, u64)> {
+        self.deliveries.iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_stable_structures::VectorMemory;
+
+    use super::*;
+
+    #[test]
+    fn expired_approvals_are_returned_by_expiration_time() {
+        let mut storage =
+            ApprovalDeliveryStorage::new(VectorMemory::default(), VectorMemory::default());
+        storage.insert(OperationId::new(1), 30);
+        storage.insert(OperationId::new(2), 10);
+        storage.insert(OperationId::new(3), 20);
+
+        assert_eq!(
+            storage.expired(20),
+            vec![OperationId::new(2), OperationId::new(3)]
+        );
+
+        // Expiration is updated, and the stale index entry is removed.
+        storage.insert(OperationId::new(2), 40);
+        assert_eq!(storage.expired(20), vec![OperationId::new(3)]);
+
+        assert_eq!(storage.remove(OperationId::new(3)), Some(20));
+        assert!(storage.expired(30 - 1).is_empty());
+        assert_eq!(
+            storage.expired(u64::MAX),
+            vec![OperationId::new(1), OperationId::new(2)]
+        );
+    }
+
+    #[test]
+    fn expiry_index_is_built_for_tracked_approvals() {
+        let deliveries = VectorMemory::default();
+        let mut tracked = StableBTreeMap::<OperationId, u64, _>::new(deliveries.clone());
+        tracked.insert(OperationId::new(1), 20);
+        tracked.insert(OperationId::new(2), 10);
+
+        let storage = ApprovalDeliveryStorage::new(deliveries, VectorMemory::default());
+
+        assert_eq!(
+            storage.expired(u64::MAX),
+            vec![OperationId::new(2), OperationId::new(1)]
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::preferences::BridgePreferences;
+use candid::Principal;
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, StableBTreeMap};
+
+/// Deposit preferences of the users.
+pub struct BridgePreferencesStorage<M: Memory> {
+    preferences: StableBTreeMap<Principal, BridgePreferences, M>,
+    max_users: u64,
+}
+
+impl<M: Memory> BridgePreferencesStorage<M> {
+    /// Creates the storage, which keeps preferences of up to `max_users` users.
+    pub fn new(m: M, max_users: u64) -> Self {
+        Self {
+            preferences: StableBTreeMap::new(m),
+            max_users,
+        }
+    }
+
+    /// Returns preferences of the user.
+    pub fn get(&self, user: &Principal) -> Option<BridgePreferences> {
+        self.preferences.get(user)
+    }
+
+    /// Validates and stores preferences of the user, replacing the previous ones.
+    pub fn set(&mut self, user: Principal, preferences: BridgePreferences) -> BTFResult<()> {
+        if user == Principal::anonymous() {
+            return Err(Error::AnonymousPrincipal);
+        }
+
+        preferences.validate()?;
+
+        if !self.preferences.contains_key(&user) && self.preferences.len() >= self.max_users {
+            return Err(Error::BridgePreferencesLimitReached(self.max_users));
+        }
+
+        self.preferences.insert(user, preferences);
+
+        Ok(())
+    }
+
+    /// Removes preferences of the user. Returns the removed preferences.
+    pub fn remove(&mut self, user: &Principal) -> Option<BridgePreferences> {
+        self.preferences.remove(user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_canister::memory::MEMORY_MANAGER;
+    use did::H160;
+    use ic_exports::ic_kit::MockContext;
+
+    use super::*;
+    use crate::constant::BRIDGE_PREFERENCES_MEMORY_ID;
+
+    fn user(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    fn preferences(recipient: u8) -> BridgePreferences {
+        BridgePreferences {
+            recipient_address: Some(H160::from_slice(&[recipient; 20])),
+            ..Default::default()
+        }
+    }
+
+    fn storage(max_users: u64) -> BridgePreferencesStorage<impl Memory> {
+        MockContext::new().inject();
+        BridgePreferencesStorage::new(
+            MEMORY_MANAGER.with(|mm| mm.get(BRIDGE_PREFERENCES_MEMORY_ID)),
+            max_users,
+        )
+    }
+
+    #[test]
+    fn preferences_are_stored_per_user() {
+        let mut storage = storage(10);
+        storage.set(user(1), preferences(1)).unwrap();
+        storage.set(user(2), preferences(2)).unwrap();
+
+        assert_eq!(storage.get(&user(1)), Some(preferences(1)));
+        assert_eq!(storage.get(&user(2)), Some(preferences(2)));
+        assert_eq!(storage.get(&user(3)), None);
+
+        storage.set(user(1), preferences(3)).unwrap();
+        assert_eq!(storage.get(&user(1)), Some(preferences(3)));
+    }
+
+    #[test]
+    fn invalid_preferences_are_rejected() {
+        let mut storage = storage(10);
+
+        let zero_recipient = BridgePreferences {
+            recipient_address: Some(H160::zero()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            storage.set(user(1), zero_recipient),
+            Err(Error::InvalidBridgePreferences(_))
+        ));
+        assert_eq!(
+            storage.set(Principal::anonymous(), preferences(1)),
+            Err(Error::AnonymousPrincipal)
+        );
+        assert_eq!(storage.get(&user(1)), None);
+    }
+
+    #[test]
+    fn users_count_is_capped() {
+        let mut storage = storage(2);
+        storage.set(user(1), preferences(1)).unwrap();
+        storage.set(user(2), preferences(2)).unwrap();
+
+        assert_eq!(
+            storage.set(user(3), preferences(3)),
+            Err(Error::BridgePreferencesLimitReached(2))
+        );
+        // Existing users can update their preferences.
+        storage.set(user(2), preferences(4)).unwrap();
+
+        assert_eq!(storage.remove(&user(1)), Some(preferences(1)));
+        assert_eq!(storage.get(&user(1)), None);
+        storage.set(user(3), preferences(3)).unwrap();
+        assert_eq!(storage.remove(&user(1)), None);
+    }
+}
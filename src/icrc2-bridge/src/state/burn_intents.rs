@@ -0,0 +1,63 @@
+use bridge_did::burn_intent::{BurnIntent, BurnIntentState};
+use bridge_did::op_id::OperationId;
+use candid::Nat;
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, StableBTreeMap};
+
+/// Write-ahead records of the deposit burns, keyed by the deposit operation id.
+///
+/// An intent is stored before the ledger call, so the burnt tokens are tracked even if
+/// the canister traps before the deposit operation is updated.
+pub struct BurnIntentStorage<M: Memory> {
+    intents: StableBTreeMap<OperationId, BurnIntent, M>,
+}
+
+impl<M: Memory> BurnIntentStorage<M> {
+    pub fn new(m: M) -> Self {
+        Self {
+            intents: StableBTreeMap::new(m),
+        }
+    }
+
+    /// Returns the burn intent of the operation.
+    pub fn get(&self, id: &OperationId) -> Option<BurnIntent> {
+        self.intents.get(id)
+    }
+
+    /// Returns the burn intent of the operation. If there is no intent yet, stores the `new` one.
+    pub fn get_or_insert(&mut self, id: OperationId, new: BurnIntent) -> BurnIntent {
+        if let Some(intent) = self.intents.get(&id) {
+            return intent;
+        }
+
+        self.intents.insert(id, new.clone());
+        new
+    }
+
+    /// Records that the operation tokens are burnt in the ledger block with the given index.
+    pub fn mark_burned(&mut self, id: OperationId, block_index: Nat) {
+        self.set_state(id, BurnIntentState::Burned { block_index });
+    }
+
+    /// Records that the operation burn can't be reconciled with the ledger.
+    pub fn mark_unresolved(&mut self, id: OperationId, reason: String) {
+        self.set_state(id, BurnIntentState::Unresolved { reason });
+    }
+
+    fn set_state(&mut self, id: OperationId, state: BurnIntentState) {
+        if let Some(mut intent) = self.intents.get(&id) {
+            intent.state = state;
+            self.intents.insert(id, intent);
+        }
+    }
+
+    /// Removes the burn intent of the operation.
+    pub fn remove(&mut self, id: &OperationId) {
+        self.intents.remove(id);
+    }
+
+    /// Returns all the stored burn intents, ordered by the operation id.
+    pub fn get_all(&self) -> Vec<(OperationId, BurnIntent)> {
+        self.intents.iter().collect()
+    }
+}
@@ -0,0 +1,105 @@
+use bridge_did::deposit_bundle::{BundleId, BundleItem, DepositBundle};
+use bridge_did::op_id::OperationId;
+use candid::Principal;
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, StableBTreeMap};
+
+/// Deposit bundles, which wrapped tokens are minted atomically, with the index of the bundle
+/// deposit operations.
+pub struct DepositBundleStorage<M: Memory> {
+    bundles: StableBTreeMap<BundleId, DepositBundle, M>,
+    items: StableBTreeMap<OperationId, BundleId, M>,
+}
+
+impl<M: Memory> DepositBundleStorage<M> {
+    pub fn new(bundles_memory: M, items_memory: M) -> Self {
+        Self {
+            bundles: StableBTreeMap::new(bundles_memory),
+            items: StableBTreeMap::new(items_memory),
+        }
+    }
+
+    /// Stores a new bundle of the given deposits and returns its id.
+    pub fn insert(&mut self, sender: Principal, items: Vec<BundleItem>, now: u64) -> BundleId {
+        let id = self
+            .bundles
+            .last_key_value()
+            .map(|(id, _)| id + 1)
+            .unwrap_or_default();
+        for item in &items {
+            self.items.insert(item.operation_id, id);
+        }
+        self.bundles.insert(
+            id,
+            DepositBundle {
+                sender,
+                items,
+                created_at: now,
+                failure: None,
+            },
+        );
+
+        id
+    }
+
+    /// Returns the bundle with the given id.
+    pub fn get(&self, id: BundleId) -> Option<DepositBundle> {
+        self.bundles.get(&id)
+    }
+
+    /// Returns the bundle, which contains the deposit operation.
+    pub fn get_by_operation(&self, operation_id: OperationId) -> Option<(BundleId, DepositBundle)> {
+        let id = self.items.get(&operation_id)?;
+        self.bundles.get(&id).map(|bundle| (id, bundle))
+    }
+
+    /// Records the bundle failure. The reason of the first failure is kept.
+    pub fn fail(&mut self, id: BundleId, reason: String) {
+        let Some(mut bundle) = self.bundles.get(&id) else {
+            return;
+        };
+
+        if bundle.failure.is_none() {
+            bundle.failure = Some(reason);
+            self.bundles.insert(id, bundle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_stable_structures::VectorMemory;
+
+    use super::*;
+
+    fn item(id: u64) -> BundleItem {
+        BundleItem {
+            operation_id: OperationId::new(id),
+            from_subaccount: None,
+        }
+    }
+
+    #[test]
+    fn bundles_are_found_by_their_operations() {
+        let mut storage =
+            DepositBundleStorage::new(VectorMemory::default(), VectorMemory::default());
+        let sender = Principal::from_slice(&[1; 29]);
+
+        let first = storage.insert(sender, vec![item(1), item(2)], 10);
+        let second = storage.insert(sender, vec![item(3), item(4)], 20);
+        assert_eq!(second, first + 1);
+
+        let (id, bundle) = storage.get_by_operation(OperationId::new(4)).unwrap();
+        assert_eq!(id, second);
+        assert_eq!(
+            bundle.operation_ids(),
+            vec![OperationId::new(3), OperationId::new(4)]
+        );
+        assert!(storage.get_by_operation(OperationId::new(5)).is_none());
+
+        storage.fail(first, "first".into());
+        storage.fail(first, "second".into());
+        assert_eq!(storage.get(first).unwrap().failure, Some("first".into()));
+        assert_eq!(storage.get(second).unwrap().failure, None);
+    }
+}
@@ -0,0 +1,158 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use bridge_did::error::{BTFResult, Error};
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{Bound, CellStructure, StableCell, Storable};
+
+use crate::constant::{ICRC1_STANDARD, ICRC2_STANDARD};
+
+/// Capabilities of an ICRC token ledger, discovered on the first use of the token.
+#[derive(Debug, Default, Clone, PartialEq, Eq, CandidType, Deserialize)]
+pub struct LedgerCapabilities {
+    /// Standards, declared by the ledger in the `icrc1_supported_standards` response.
+    /// ICRC-2 is kept only if the ledger also answers the `icrc2_allowance` probe.
+    pub standards: Vec<String>,
+    /// Whether the `icrc1_metadata` response contains the token name, symbol and decimals.
+    pub core_metadata: bool,
+}
+
+impl LedgerCapabilities {
+    /// Returns the `required` standards, which the ledger doesn't support.
+    /// ICRC-1 is supported only if the ledger also returns the core metadata.
+    pub fn missing(&self, required: &[&str]) -> Vec<String> {
+        required
+            .iter()
+            .filter(|standard| !self.supports(standard))
+            .map(|standard| standard.to_string())
+            .collect()
+    }
+
+    /// Returns `UnsupportedLedger` error, if any of the `required` standards is not supported.
+    ///
+    /// ICRC-2 is needed only to burn the deposited tokens and to approve the withdrawn ones,
+    /// so a ledger, which misses only ICRC-2, gets the `InvalidBurnOperation` error.
+    pub fn check(&self, required: &[&str]) -> BTFResult<()> {
+        let missing = self.missing(required);
+        if missing == [ICRC2_STANDARD] {
+            return Err(Error::InvalidBurnOperation(
+                "ledger does not support ICRC-2".into(),
+            ));
+        }
+
+        if !missing.is_empty() {
+            return Err(Error::UnsupportedLedger { missing });
+        }
+
+        Ok(())
+    }
+
+    fn supports(&self, standard: &str) -> bool {
+        let declared = self.standards.iter().any(|declared| declared == standard);
+        if standard == ICRC1_STANDARD {
+            declared && self.core_metadata
+        } else {
+            declared
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, CandidType, Deserialize)]
+struct Ledgers(BTreeMap<Principal, LedgerCapabilities>);
+
+impl Storable for Ledgers {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode ledger capabilities"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode ledger capabilities")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Registry of the ICRC token ledgers capabilities.
+pub struct LedgerCapabilityRegistry<M: Memory> {
+    ledgers: StableCell<Ledgers, M>,
+}
+
+impl<M: Memory> LedgerCapabilityRegistry<M> {
+    pub fn new(m: M) -> Self {
+        Self {
+            ledgers: StableCell::new(m, Ledgers::default())
+                .expect("failed to initialize ledger capability registry"),
+        }
+    }
+
+    /// Returns the capabilities of the token ledger, or `None` if they are not discovered yet.
+    pub fn get(&self, token: &Principal) -> Option<LedgerCapabilities> {
+        self.ledgers.get().0.get(token).cloned()
+    }
+
+    /// Stores the discovered capabilities of the token ledger.
+    pub fn set(&mut self, token: Principal, capabilities: LedgerCapabilities) {
+        let mut ledgers = self.ledgers.get().clone();
+        ledgers.0.insert(token, capabilities);
+        self.ledgers
+            .set(ledgers)
+            .expect("failed to update ledger capability registry");
+    }
+
+    /// Checks that the token ledger supports the `required` standards.
+    /// Ledgers with not discovered capabilities pass the check.
+    pub fn check(&self, token: &Principal, required: &[&str]) -> BTFResult<()> {
+        match self.get(token) {
+            Some(capabilities) => capabilities.check(required),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_canister::memory::MEMORY_MANAGER;
+    use ic_exports::ic_kit::MockContext;
+
+    use super::*;
+    use crate::constant::{DEPOSIT_STANDARDS, LEDGER_CAPABILITIES_MEMORY_ID};
+
+    #[test]
+    fn recorded_unsupported_ledger_is_rejected() {
+        MockContext::new().inject();
+        let mut registry = LedgerCapabilityRegistry::new(
+            MEMORY_MANAGER.with(|mm| mm.get(LEDGER_CAPABILITIES_MEMORY_ID)),
+        );
+
+        let token = Principal::from_slice(&[1; 29]);
+        assert_eq!(registry.check(&token, DEPOSIT_STANDARDS), Ok(()));
+
+        registry.set(
+            token,
+            LedgerCapabilities {
+                standards: vec![ICRC1_STANDARD.into()],
+                core_metadata: true,
+            },
+        );
+        assert_eq!(
+            registry.check(&token, DEPOSIT_STANDARDS),
+            Err(Error::InvalidBurnOperation(
+                "ledger does not support ICRC-2".into()
+            ))
+        );
+        assert_eq!(registry.check(&token, &[ICRC1_STANDARD]), Ok(()));
+    }
+
+    #[test]
+    fn icrc1_requires_core_metadata() {
+        let capabilities = LedgerCapabilities {
+            standards: vec![ICRC1_STANDARD.into(), ICRC2_STANDARD.into()],
+            core_metadata: false,
+        };
+        assert_eq!(
+            capabilities.missing(DEPOSIT_STANDARDS),
+            vec![ICRC1_STANDARD.to_string()]
+        );
+    }
+}
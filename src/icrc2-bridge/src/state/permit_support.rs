@@ -0,0 +1,78 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use candid::{CandidType, Decode, Deserialize, Encode};
+use did::H160;
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{Bound, CellStructure, StableCell, Storable};
+
+/// EIP-2612 permit support of the wrapped tokens, discovered on the EVM.
+#[derive(Debug, Default, Clone, PartialEq, Eq, CandidType, Deserialize)]
+struct PermitSupport(BTreeMap<H160, bool>);
+
+impl Storable for PermitSupport {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode permit support"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode permit support")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Registry of the wrapped tokens capability to accept EIP-2612 permits.
+pub struct PermitSupportRegistry<M: Memory> {
+    tokens: StableCell<PermitSupport, M>,
+}
+
+impl<M: Memory> PermitSupportRegistry<M> {
+    pub fn new(m: M) -> Self {
+        Self {
+            tokens: StableCell::new(m, PermitSupport::default())
+                .expect("failed to initialize permit support registry"),
+        }
+    }
+
+    /// Returns the permit support of the wrapped token, or `None` if it's not discovered yet.
+    pub fn get(&self, token: &H160) -> Option<bool> {
+        self.tokens.get().0.get(token).copied()
+    }
+
+    /// Stores the discovered permit support of the wrapped token.
+    pub fn set(&mut self, token: H160, supported: bool) {
+        let mut tokens = self.tokens.get().clone();
+        tokens.0.insert(token, supported);
+        self.tokens
+            .set(tokens)
+            .expect("failed to update permit support registry");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_canister::memory::MEMORY_MANAGER;
+    use ic_exports::ic_kit::MockContext;
+
+    use super::*;
+    use crate::constant::PERMIT_SUPPORT_MEMORY_ID;
+
+    #[test]
+    fn should_store_permit_support() {
+        MockContext::new().inject();
+        let mut registry =
+            PermitSupportRegistry::new(MEMORY_MANAGER.with(|mm| mm.get(PERMIT_SUPPORT_MEMORY_ID)));
+
+        let token = H160::from_slice(&[1; 20]);
+        assert_eq!(registry.get(&token), None);
+
+        registry.set(token.clone(), true);
+        registry.set(H160::from_slice(&[2; 20]), false);
+        assert_eq!(registry.get(&token), Some(true));
+        assert_eq!(registry.get(&H160::from_slice(&[2; 20])), Some(false));
+
+        registry.set(token.clone(), false);
+        assert_eq!(registry.get(&token), Some(false));
+    }
+}
@@ -0,0 +1,105 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use bridge_did::supply_guard::{SupplyCheck, SupplyGuardConfig};
+use candid::{CandidType, Decode, Deserialize, Encode};
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{Bound, CellStructure, StableCell, Storable};
+
+/// Supply guard configuration. If `None`, the supply is not checked.
+#[derive(Debug, Default, Clone, PartialEq, Eq, CandidType, Deserialize)]
+struct StoredSupplyGuardConfig(Option<SupplyGuardConfig>);
+
+impl Storable for StoredSupplyGuardConfig {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode supply guard config"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode supply guard config")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Configuration and the latest result of the supply guard checks.
+pub struct SupplyGuardStorage<M: Memory> {
+    config: StableCell<StoredSupplyGuardConfig, M>,
+    /// Not persisted across upgrades, so the supply is checked after upgrade.
+    last_check: Option<SupplyCheck>,
+}
+
+impl<M: Memory> SupplyGuardStorage<M> {
+    pub fn new(m: M) -> Self {
+        Self {
+            config: StableCell::new(m, StoredSupplyGuardConfig::default())
+                .expect("failed to initialize supply guard config"),
+            last_check: None,
+        }
+    }
+
+    /// Returns the guard configuration, or `None` if the guard is disabled.
+    pub fn get_config(&self) -> Option<SupplyGuardConfig> {
+        self.config.get().0.clone()
+    }
+
+    /// Sets the guard configuration. `None` disables the guard.
+    pub fn set_config(&mut self, config: Option<SupplyGuardConfig>) {
+        self.config
+            .set(StoredSupplyGuardConfig(config))
+            .expect("failed to update supply guard config");
+    }
+
+    /// Returns the latest supply check.
+    pub fn last_check(&self) -> Option<&SupplyCheck> {
+        self.last_check.as_ref()
+    }
+
+    /// Checks if the check interval is passed since the latest check at the `now` IC time.
+    pub fn is_check_due(&self, check_interval_secs: u64, now: u64) -> bool {
+        let interval = Duration::from_secs(check_interval_secs).as_nanos() as u64;
+        self.last_check
+            .as_ref()
+            .map_or(true, |check| now >= check.timestamp.saturating_add(interval))
+    }
+
+    /// Stores the latest supply check.
+    pub fn record_check(&mut self, check: SupplyCheck) {
+        self.last_check = Some(check);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_canister::memory::MEMORY_MANAGER;
+    use did::U256;
+    use ic_exports::ic_kit::MockContext;
+
+    use super::*;
+    use crate::constant::SUPPLY_GUARD_MEMORY_ID;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    #[test]
+    fn check_is_due_after_interval() {
+        MockContext::new().inject();
+        let mut storage =
+            SupplyGuardStorage::new(MEMORY_MANAGER.with(|mm| mm.get(SUPPLY_GUARD_MEMORY_ID)));
+        assert_eq!(storage.get_config(), None);
+
+        let config = SupplyGuardConfig {
+            tolerance: U256::from(10u64),
+            check_interval_secs: 60,
+        };
+        storage.set_config(Some(config.clone()));
+        assert_eq!(storage.get_config(), Some(config));
+        assert!(storage.is_check_due(60, 0));
+
+        storage.record_check(SupplyCheck {
+            timestamp: 100 * SECOND,
+            reconciliations: vec![],
+        });
+        assert!(!storage.is_check_due(60, 159 * SECOND));
+        assert!(storage.is_check_due(60, 160 * SECOND));
+    }
+}
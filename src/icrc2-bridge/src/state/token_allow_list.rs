@@ -0,0 +1,163 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+use bridge_did::error::{BTFResult, Error};
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{Bound, CellStructure, StableCell, Storable};
+
+/// ICRC-2 tokens, allowed to be bridged. If `None`, all tokens are allowed.
+#[derive(Debug, Default, Clone, PartialEq, Eq, CandidType, Deserialize)]
+struct AllowedTokens(Option<BTreeSet<Principal>>);
+
+impl Storable for AllowedTokens {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode allowed tokens"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode allowed tokens")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+pub struct TokenAllowList<M: Memory> {
+    allowed_tokens: StableCell<AllowedTokens, M>,
+}
+
+impl<M: Memory> TokenAllowList<M> {
+    pub fn new(m: M) -> Self {
+        Self {
+            allowed_tokens: StableCell::new(m, AllowedTokens::default())
+                .expect("failed to initialize token allow list"),
+        }
+    }
+
+    /// Adds the token to the list. If the list is disabled, enables it with the only token.
+    pub fn add(&mut self, token: Principal) -> BTFResult<()> {
+        if token == Principal::anonymous() {
+            return Err(Error::AnonymousPrincipal);
+        }
+
+        self.update(|tokens| {
+            tokens.get_or_insert_with(BTreeSet::new).insert(token);
+        });
+
+        Ok(())
+    }
+
+    /// Removes the token from the list. If the list is disabled, does nothing.
+    pub fn remove(&mut self, token: &Principal) {
+        self.update(|tokens| {
+            if let Some(tokens) = tokens {
+                tokens.remove(token);
+            }
+        });
+    }
+
+    /// Allows all the tokens to be bridged.
+    pub fn disable(&mut self) {
+        self.update(|tokens| *tokens = None);
+    }
+
+    /// Returns the allowed tokens, or `None` if all tokens are allowed.
+    pub fn get_all(&self) -> Option<Vec<Principal>> {
+        self.allowed_tokens
+            .get()
+            .0
+            .as_ref()
+            .map(|tokens| tokens.iter().copied().collect())
+    }
+
+    /// Checks if the token is allowed to be bridged.
+    pub fn check(&self, token: &Principal) -> BTFResult<()> {
+        match &self.allowed_tokens.get().0 {
+            Some(tokens) if !tokens.contains(token) => Err(Error::TokenNotAllowed(*token)),
+            _ => Ok(()),
+        }
+    }
+
+    fn update(&mut self, f: impl FnOnce(&mut Option<BTreeSet<Principal>>)) {
+        let mut tokens = self.allowed_tokens.get().clone();
+        f(&mut tokens.0);
+        self.allowed_tokens
+            .set(tokens)
+            .expect("failed to update token allow list");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_canister::memory::MEMORY_MANAGER;
+    use ic_exports::ic_kit::MockContext;
+
+    use super::*;
+    use crate::constant::TOKEN_ALLOW_LIST_MEMORY_ID;
+
+    fn token(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    fn allow_list() -> TokenAllowList<impl Memory> {
+        MockContext::new().inject();
+        TokenAllowList::new(MEMORY_MANAGER.with(|mm| mm.get(TOKEN_ALLOW_LIST_MEMORY_ID)))
+    }
+
+    #[test]
+    fn token_in_list_is_allowed() {
+        let mut allow_list = allow_list();
+        allow_list.add(token(1)).unwrap();
+        allow_list.add(token(2)).unwrap();
+
+        assert_eq!(allow_list.check(&token(1)), Ok(()));
+        assert_eq!(allow_list.check(&token(2)), Ok(()));
+        assert_eq!(allow_list.get_all(), Some(vec![token(1), token(2)]));
+    }
+
+    #[test]
+    fn token_not_in_list_is_rejected() {
+        let mut allow_list = allow_list();
+        allow_list.add(token(1)).unwrap();
+
+        assert_eq!(
+            allow_list.check(&token(2)),
+            Err(Error::TokenNotAllowed(token(2)))
+        );
+
+        allow_list.remove(&token(1));
+        assert_eq!(
+            allow_list.check(&token(1)),
+            Err(Error::TokenNotAllowed(token(1)))
+        );
+        assert_eq!(allow_list.get_all(), Some(vec![]));
+    }
+
+    #[test]
+    fn all_tokens_are_allowed_when_list_is_disabled() {
+        let mut allow_list = allow_list();
+        assert_eq!(allow_list.get_all(), None);
+        assert_eq!(allow_list.check(&token(1)), Ok(()));
+
+        // Removing from the disabled list doesn't enable it.
+        allow_list.remove(&token(1));
+        assert_eq!(allow_list.get_all(), None);
+
+        allow_list.add(token(1)).unwrap();
+        assert!(allow_list.check(&token(2)).is_err());
+
+        allow_list.disable();
+        assert_eq!(allow_list.get_all(), None);
+        assert_eq!(allow_list.check(&token(2)), Ok(()));
+    }
+
+    #[test]
+    fn anonymous_token_is_rejected() {
+        let mut allow_list = allow_list();
+        assert_eq!(
+            allow_list.add(Principal::anonymous()),
+            Err(Error::AnonymousPrincipal)
+        );
+        assert_eq!(allow_list.get_all(), None);
+    }
+}
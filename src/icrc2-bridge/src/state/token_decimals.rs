@@ -0,0 +1,449 @@
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::token_decimals::{
+    DecimalsChange, DecimalsChangeStrategy, RetiredWrappedToken, TokenDecimals,
+};
+use candid::Principal;
+use did::{H160, U256};
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{BTreeMapStructure, StableBTreeMap};
+
+/// Decimals of the bridged ICRC tokens and their wrapped tokens.
+///
+/// Ledger decimals are checked on every token info refresh. If the ledger reports
+/// other decimals, operations with the token are held until the operator resolves the change.
+pub struct TokenDecimalsStorage<M: Memory> {
+    decimals: StableBTreeMap<Principal, TokenDecimals, M>,
+}
+
+impl<M: Memory> TokenDecimalsStorage<M> {
+    pub fn new(m: M) -> Self {
+        Self {
+            decimals: StableBTreeMap::new(m),
+        }
+    }
+
+    /// Returns decimals of the token and its wrapped token.
+    pub fn get(&self, token: &Principal) -> Option<TokenDecimals> {
+        self.decimals.get(token)
+    }
+
+    /// Returns decimals of all the tracked tokens.
+    pub fn get_all(&self) -> Vec<(Principal, TokenDecimals)> {
+        self.decimals.iter().collect()
+    }
+
+    /// Checks that operations with the token are not held because of the decimals change.
+    pub fn check_not_held(&self, token: &Principal) -> BTFResult<()> {
+        match self.decimals.get(token) {
+            Some(record) if record.pending_change.is_some() => {
+                Err(Error::TokenDecimalsChanged(*token))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the `ledger_decimals` and returns the conversion of the deposit amount
+    /// to the `wrapped_token` amount. Deposits to retired wrapped tokens are rejected.
+    pub fn observe_deposit(
+        &mut self,
+        token: Principal,
+        ledger_decimals: u8,
+        wrapped_token: &H160,
+        now: u64,
+    ) -> BTFResult<DecimalsConversion> {
+        let mut record = self.observe(token, ledger_decimals, now)?;
+        if record.retired(wrapped_token).is_some() {
+            return Err(Error::WrappedTokenRetired(wrapped_token.clone()));
+        }
+
+        if record.wrapped_token.is_none() {
+            record.wrapped_token = Some(wrapped_token.clone());
+            self.decimals.insert(token, record.clone());
+        }
+
+        Ok(DecimalsConversion {
+            ledger_decimals: record.ledger_decimals,
+            wrapped_decimals: record.wrapped_decimals,
+        })
+    }
+
+    /// Checks the `ledger_decimals` and returns the conversion of the `wrapped_token`
+    /// withdrawal amount to the ledger amount. Retired wrapped tokens are withdrawn
+    /// with their own decimals.
+    pub fn observe_withdrawal(
+        &mut self,
+        token: Principal,
+        ledger_decimals: u8,
+        wrapped_token: &H160,
+        now: u64,
+    ) -> BTFResult<DecimalsConversion> {
+        let mut record = self.observe(token, ledger_decimals, now)?;
+        if let Some(retired) = record.retired(wrapped_token) {
+            return Ok(DecimalsConversion {
+                ledger_decimals: record.ledger_decimals,
+                wrapped_decimals: retired.decimals,
+            });
+        }
+
+        if record.wrapped_token.is_none() {
+            record.wrapped_token = Some(wrapped_token.clone());
+            self.decimals.insert(token, record.clone());
+        }
+
+        Ok(DecimalsConversion {
+            ledger_decimals: record.ledger_decimals,
+            wrapped_decimals: record.wrapped_decimals,
+        })
+    }
+
+    /// Resolves the detected decimals change of the token with the given strategy.
+    pub fn resolve(
+        &mut self,
+        token: Principal,
+        strategy: DecimalsChangeStrategy,
+    ) -> BTFResult<TokenDecimals> {
+        let Some(mut record) = self.decimals.get(&token) else {
+            return Err(Error::DecimalsChangeNotDetected(token));
+        };
+        let Some(change) = record.pending_change.take() else {
+            return Err(Error::DecimalsChangeNotDetected(token));
+        };
+
+        match strategy {
+            DecimalsChangeStrategy::Rescale => {
+                record.ledger_decimals = change.current;
+            }
+            DecimalsChangeStrategy::Migrate => {
+                if let Some(address) = record.wrapped_token.take() {
+                    record.retired_wrapped_tokens.push(RetiredWrappedToken {
+                        address,
+                        decimals: record.wrapped_decimals,
+                    });
+                }
+                record.ledger_decimals = change.current;
+                record.wrapped_decimals = change.current;
+            }
+        }
+
+        self.decimals.insert(token, record.clone());
+
+        Ok(record)
+    }
+
+    /// Compares the `ledger_decimals` with the known ones and records the change.
+    /// Returns an error while the change is not resolved.
+    fn observe(
+        &mut self,
+        token: Principal,
+        ledger_decimals: u8,
+        now: u64,
+    ) -> BTFResult<TokenDecimals> {
+        let Some(mut record) = self.decimals.get(&token) else {
+            let record = TokenDecimals::new(ledger_decimals);
+            self.decimals.insert(token, record.clone());
+            return Ok(record);
+        };
+
+        match &mut record.pending_change {
+            Some(change) if change.current != ledger_decimals => {
+                log::error!(
+                    "CRITICAL: decimals of token {token} changed again from {} to {ledger_decimals}",
+                    change.current
+                );
+                change.current = ledger_decimals;
+                self.decimals.insert(token, record.clone());
+            }
+            Some(_) => {}
+            None if record.ledger_decimals != ledger_decimals => {
+                log::error!(
+                    "CRITICAL: decimals of token {token} changed from {} to {ledger_decimals}, wrapped token decimals are {}. Operations with the token are held until the change is resolved",
+                    record.ledger_decimals,
+                    record.wrapped_decimals
+                );
+                record.pending_change = Some(DecimalsChange {
+                    previous: record.ledger_decimals,
+                    current: ledger_decimals,
+                    detected_at: now,
+                });
+                self.decimals.insert(token, record.clone());
+            }
+            None => return Ok(record),
+        }
+
+        Err(Error::TokenDecimalsChanged(token))
+    }
+}
+
+/// Conversion of the amounts between the ICRC ledger and the wrapped token decimals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalsConversion {
+    pub ledger_decimals: u8,
+    pub wrapped_decimals: u8,
+}
+
+impl DecimalsConversion {
+    /// Converts the ledger `amount` to the wrapped token amount.
+    /// The amount should be exactly representable with the wrapped token decimals.
+    pub fn to_wrapped(&self, amount: &U256) -> BTFResult<U256> {
+        let scaled = if self.ledger_decimals >= self.wrapped_decimals {
+            let scale = Self::scale(self.ledger_decimals - self.wrapped_decimals)?;
+            if !(amount.0 % scale.0).is_zero() {
+                return Err(Error::InvalidBurnAmount(format!(
+                    "amount {} is not representable with {} decimals of the wrapped token",
+                    amount.0, self.wrapped_decimals
+                )));
+            }
+            amount.0 / scale.0
+        } else {
+            let scale = Self::scale(self.wrapped_decimals - self.ledger_decimals)?;
+            amount.0.checked_mul(scale.0).ok_or_else(|| {
+                Error::InvalidBurnAmount(format!(
+                    "amount {} overflows with {} decimals of the wrapped token",
+                    amount.0, self.wrapped_decimals
+                ))
+            })?
+        };
+
+        if scaled.is_zero() {
+            return Err(Error::InvalidBurnAmount(
+                "amount is zero in the wrapped token decimals".into(),
+            ));
+        }
+
+        Ok(scaled.into())
+    }
+
+    /// Converts the wrapped token `amount` to the ledger amount.
+    /// The fraction, not representable with the ledger decimals, is truncated.
+    pub fn to_ledger(&self, amount: &U256) -> BTFResult<U256> {
+        let scaled = if self.wrapped_decimals >= self.ledger_decimals {
+            amount.0 / Self::scale(self.wrapped_decimals - self.ledger_decimals)?.0
+        } else {
+            let scale = Self::scale(self.ledger_decimals - self.wrapped_decimals)?;
+            amount.0.checked_mul(scale.0).ok_or_else(|| {
+                Error::InvalidBurnAmount(format!(
+                    "amount {} overflows with {} decimals of the ledger",
+                    amount.0, self.ledger_decimals
+                ))
+            })?
+        };
+
+        Ok(scaled.into())
+    }
+
+    /// Returns `10^decimals`.
+    fn scale(decimals: u8) -> BTFResult<U256> {
+        U256::from(10u64)
+            .0
+            .checked_pow(U256::from(decimals as u64).0)
+            .map(Into::into)
+            .ok_or_else(|| {
+                Error::InvalidBurnAmount(format!("decimals difference {decimals} is too big"))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bridge_canister::memory::MEMORY_MANAGER;
+    use ic_exports::ic_kit::MockContext;
+
+    use super::*;
+    use crate::constant::TOKEN_DECIMALS_MEMORY_ID;
+
+    fn token() -> Principal {
+        Principal::from_slice(&[1; 29])
+    }
+
+    fn wrapped(id: u8) -> H160 {
+        H160::from_slice(&[id; 20])
+    }
+
+    fn storage() -> TokenDecimalsStorage<impl Memory> {
+        MockContext::new().inject();
+        TokenDecimalsStorage::new(MEMORY_MANAGER.with(|mm| mm.get(TOKEN_DECIMALS_MEMORY_ID)))
+    }
+
+    fn conversion(ledger_decimals: u8, wrapped_decimals: u8) -> DecimalsConversion {
+        DecimalsConversion {
+            ledger_decimals,
+            wrapped_decimals,
+        }
+    }
+
+    #[test]
+    fn decimals_change_is_detected_and_holds_token() {
+        let mut storage = storage();
+        assert_eq!(
+            storage.observe_deposit(token(), 8, &wrapped(1), 10),
+            Ok(conversion(8, 8))
+        );
+        assert_eq!(
+            storage.get(&token()).unwrap().wrapped_token,
+            Some(wrapped(1))
+        );
+        assert_eq!(storage.check_not_held(&token()), Ok(()));
+
+        assert_eq!(
+            storage.observe_deposit(token(), 12, &wrapped(1), 20),
+            Err(Error::TokenDecimalsChanged(token()))
+        );
+        assert_eq!(
+            storage.get(&token()).unwrap().pending_change,
+            Some(DecimalsChange {
+                previous: 8,
+                current: 12,
+                detected_at: 20,
+            })
+        );
+        assert_eq!(
+            storage.check_not_held(&token()),
+            Err(Error::TokenDecimalsChanged(token()))
+        );
+
+        // Token stays held even if the ledger reports the previous decimals again.
+        assert_eq!(
+            storage.observe_withdrawal(token(), 8, &wrapped(1), 30),
+            Err(Error::TokenDecimalsChanged(token()))
+        );
+        assert_eq!(
+            storage
+                .get(&token())
+                .unwrap()
+                .pending_change
+                .map(|change| change.current),
+            Some(8)
+        );
+    }
+
+    #[test]
+    fn rescale_keeps_wrapped_token_decimals() {
+        let mut storage = storage();
+        storage
+            .observe_deposit(token(), 8, &wrapped(1), 10)
+            .unwrap();
+        storage
+            .observe_deposit(token(), 12, &wrapped(1), 20)
+            .unwrap_err();
+
+        let resolved = storage
+            .resolve(token(), DecimalsChangeStrategy::Rescale)
+            .unwrap();
+        assert_eq!(resolved.ledger_decimals, 12);
+        assert_eq!(resolved.wrapped_decimals, 8);
+        assert_eq!(resolved.pending_change, None);
+
+        assert_eq!(
+            storage.observe_deposit(token(), 12, &wrapped(1), 30),
+            Ok(conversion(12, 8))
+        );
+        assert_eq!(
+            storage.observe_withdrawal(token(), 12, &wrapped(1), 30),
+            Ok(conversion(12, 8))
+        );
+        assert_eq!(
+            storage.resolve(token(), DecimalsChangeStrategy::Rescale),
+            Err(Error::DecimalsChangeNotDetected(token()))
+        );
+    }
+
+    #[test]
+    fn migrate_retires_wrapped_token() {
+        let mut storage = storage();
+        storage
+            .observe_deposit(token(), 8, &wrapped(1), 10)
+            .unwrap();
+        storage
+            .observe_deposit(token(), 12, &wrapped(1), 20)
+            .unwrap_err();
+
+        let resolved = storage
+            .resolve(token(), DecimalsChangeStrategy::Migrate)
+            .unwrap();
+        assert_eq!(resolved.ledger_decimals, 12);
+        assert_eq!(resolved.wrapped_decimals, 12);
+        assert_eq!(resolved.wrapped_token, None);
+        assert_eq!(
+            resolved.retired_wrapped_tokens,
+            vec![RetiredWrappedToken {
+                address: wrapped(1),
+                decimals: 8,
+            }]
+        );
+
+        // Deposits require a new wrapped token.
+        assert_eq!(
+            storage.observe_deposit(token(), 12, &wrapped(1), 30),
+            Err(Error::WrappedTokenRetired(wrapped(1)))
+        );
+        assert_eq!(
+            storage.observe_deposit(token(), 12, &wrapped(2), 30),
+            Ok(conversion(12, 12))
+        );
+        assert_eq!(
+            storage.get(&token()).unwrap().wrapped_token,
+            Some(wrapped(2))
+        );
+
+        // Retired wrapped token can still be withdrawn with its decimals.
+        assert_eq!(
+            storage.observe_withdrawal(token(), 12, &wrapped(1), 30),
+            Ok(conversion(12, 8))
+        );
+    }
+
+    #[test]
+    fn resolve_requires_detected_change() {
+        let mut storage = storage();
+        assert_eq!(
+            storage.resolve(token(), DecimalsChangeStrategy::Migrate),
+            Err(Error::DecimalsChangeNotDetected(token()))
+        );
+
+        storage
+            .observe_deposit(token(), 8, &wrapped(1), 10)
+            .unwrap();
+        assert_eq!(
+            storage.resolve(token(), DecimalsChangeStrategy::Migrate),
+            Err(Error::DecimalsChangeNotDetected(token()))
+        );
+    }
+
+    #[test]
+    fn amounts_are_scaled_between_decimals() {
+        let amount = |value: u64| U256::from(value);
+
+        assert_eq!(conversion(8, 8).to_wrapped(&amount(123)), Ok(amount(123)));
+        assert_eq!(conversion(8, 8).to_ledger(&amount(123)), Ok(amount(123)));
+
+        // Ledger has more decimals than the wrapped token.
+        assert_eq!(
+            conversion(12, 8).to_wrapped(&amount(1_230_000)),
+            Ok(amount(123))
+        );
+        assert!(matches!(
+            conversion(12, 8).to_wrapped(&amount(1_230_001)),
+            Err(Error::InvalidBurnAmount(_))
+        ));
+        assert!(matches!(
+            conversion(12, 8).to_wrapped(&amount(9_999)),
+            Err(Error::InvalidBurnAmount(_))
+        ));
+        assert_eq!(
+            conversion(12, 8).to_ledger(&amount(123)),
+            Ok(amount(1_230_000))
+        );
+
+        // Ledger has less decimals than the wrapped token.
+        assert_eq!(
+            conversion(8, 12).to_wrapped(&amount(123)),
+            Ok(amount(1_230_000))
+        );
+        assert_eq!(
+            conversion(8, 12).to_ledger(&amount(1_239_999)),
+            Ok(amount(123))
+        );
+
+        assert!(conversion(0, 255).to_wrapped(&amount(1)).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+//! Validation of the ICRC token ledgers. On the first use of a token, its ledger is asked
+//! for the supported standards and the core metadata, and the discovered capabilities are
+//! stored, so the later requests with an unsupported ledger are rejected immediately.
+
+use bridge_did::error::{BTFResult, Error};
+use bridge_did::icrc21::StandardRecord;
+use candid::Principal;
+use evm_canister_client::{CanisterClient, IcCanisterClient};
+use ic_exports::ic_kit::RejectionCode;
+use icrc_client::account::Account;
+use icrc_client::allowance::{Allowance, AllowanceArgs};
+
+use super::icrc1::{self, IcrcCanisterError};
+use crate::canister::get_icrc_state;
+use crate::constant::ICRC2_STANDARD;
+use crate::ops::ErrorCodes;
+use crate::state::LedgerCapabilities;
+
+/// Checks that the ledger of the `token` supports the `required` standards.
+///
+/// Capabilities of the ledger are queried on the first check, or every time if `refresh`
+/// is set, and stored for the next checks.
+pub async fn check_ledger(token: Principal, required: &[&str], refresh: bool) -> BTFResult<()> {
+    let cached = get_icrc_state().borrow().ledger_capabilities.get(&token);
+    let capabilities = match cached {
+        Some(capabilities) if !refresh => capabilities,
+        _ => {
+            let capabilities = query_capabilities(&IcCanisterClient::new(token))
+                .await
+                .map_err(|e| Error::Custom {
+                    code: ErrorCodes::IcrcMetadataRequestFailed as _,
+                    msg: format!("failed to query ledger capabilities: {e}"),
+                })?;
+
+            log::debug!("Discovered capabilities of the token {token} ledger: {capabilities:?}");
+
+            get_icrc_state()
+                .borrow_mut()
+                .ledger_capabilities
+                .set(token, capabilities.clone());
+            capabilities
+        }
+    };
+
+    capabilities.check(required)
+}
+
+/// Queries the supported standards and the core metadata of the ledger, and probes
+/// the declared ICRC-2 support.
+///
+/// A canister, which doesn't implement the queries, is reported as a ledger without
+/// capabilities. Transient call failures are returned as errors, so they are not stored.
+async fn query_capabilities<C: CanisterClient>(
+    client: &C,
+) -> Result<LedgerCapabilities, IcrcCanisterError> {
+    let mut standards: Vec<String> = match client
+        .query::<_, Vec<StandardRecord>>("icrc1_supported_standards", ())
+        .await
+        .map_err(IcrcCanisterError::from)
+    {
+        Ok(records) => records.into_iter().map(|record| record.name).collect(),
+        Err(e) if is_not_implemented(&e) => return Ok(LedgerCapabilities::default()),
+        Err(e) => return Err(e),
+    };
+
+    // Ledger can declare ICRC-2 without implementing it, so the declaration is checked
+    // with an allowance query of an arbitrary account.
+    if standards.iter().any(|standard| standard == ICRC2_STANDARD) {
+        let probe = AllowanceArgs {
+            account: Account::from(Principal::anonymous()),
+            spender: Account::from(Principal::anonymous()),
+        };
+        match client
+            .query::<_, Allowance>("icrc2_allowance", (probe,))
+            .await
+            .map_err(IcrcCanisterError::from)
+        {
+            Ok(_) => {}
+            Err(e) if is_not_implemented(&e) => standards.retain(|s| s != ICRC2_STANDARD),
+            Err(e) => return Err(e),
+        }
+    }
+
+    let core_metadata = match client
+        .query::<_, Vec<(String, icrc_client::Value)>>("icrc1_metadata", ())
+        .await
+        .map_err(IcrcCanisterError::from)
+    {
+        Ok(metadata) => icrc1::token_info_from_metadata(&metadata).is_ok(),
+        Err(e) if is_not_implemented(&e) => false,
+        Err(e) => return Err(e),
+    };
+
+    Ok(LedgerCapabilities {
+        standards,
+        core_metadata,
+    })
+}
+
+/// Checks if the call failure means that the canister doesn't implement the method
+/// in the expected way, so the retry gives the same result.
+fn is_not_implemented(e: &IcrcCanisterError) -> bool {
+    match e {
+        IcrcCanisterError::CanisterError(code, _) => *code != RejectionCode::SysTransient,
+        IcrcCanisterError::CandidFailed(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::{CandidType, Nat};
+    use evm_canister_client::{CanisterClientError, CanisterClientResult};
+
+    use super::*;
+    use crate::constant::{DEPOSIT_STANDARDS, ICRC1_STANDARD};
+
+    /// Canister, which answers the ledger queries according to its kind.
+    #[derive(Debug, Clone, Copy)]
+    enum MockCanister {
+        NotLedger,
+        Icrc1Ledger,
+        Icrc2Ledger,
+        /// Declares ICRC-2, but doesn't implement `icrc2_allowance`.
+        Icrc2DeclaredOnly,
+    }
+
+    impl MockCanister {
+        fn standards(self) -> Vec<StandardRecord> {
+            let record = |name: &str| StandardRecord {
+                name: name.into(),
+                url: format!("https://github.com/dfinity/ICRC-1/standards/{name}"),
+            };
+            match self {
+                Self::NotLedger => vec![],
+                Self::Icrc1Ledger => vec![record(ICRC1_STANDARD)],
+                Self::Icrc2Ledger | Self::Icrc2DeclaredOnly => {
+                    vec![record(ICRC1_STANDARD), record(ICRC2_STANDARD)]
+                }
+            }
+        }
+
+        fn metadata(self) -> Vec<(String, icrc_client::Value)> {
+            vec![
+                (
+                    "icrc1:name".into(),
+                    icrc_client::Value::Text("Token".into()),
+                ),
+                (
+                    "icrc1:symbol".into(),
+                    icrc_client::Value::Text("TKN".into()),
+                ),
+                (
+                    "icrc1:decimals".into(),
+                    icrc_client::Value::Nat(Nat::from(8u8)),
+                ),
+            ]
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl CanisterClient for MockCanister {
+        async fn query<T, R>(&self, method: &str, _args: T) -> CanisterClientResult<R>
+        where
+            T: candid::utils::ArgumentEncoder + Send + Sync,
+            R: serde::de::DeserializeOwned + CandidType,
+        {
+            let json = match (self, method) {
+                (Self::NotLedger, _) | (Self::Icrc2DeclaredOnly, "icrc2_allowance") => {
+                    return Err(CanisterClientError::CandidError(candid::Error::msg(
+                        "unexpected response type",
+                    )))
+                }
+                (_, "icrc1_supported_standards") => serde_json::to_value(self.standards()),
+                (_, "icrc1_metadata") => serde_json::to_value(self.metadata()),
+                (Self::Icrc2Ledger, "icrc2_allowance") => {
+                    let allowance = Allowance {
+                        allowance: 0u64.into(),
+                        expires_at: None,
+                    };
+                    return Ok(candid::decode_one(&candid::encode_one(allowance).unwrap()).unwrap());
+                }
+                _ => panic!("Unexpected method: {method}"),
+            };
+
+            Ok(serde_json::from_value(json.unwrap()).unwrap())
+        }
+
+        async fn update<T, R>(&self, _method: &str, _args: T) -> CanisterClientResult<R>
+        where
+            T: candid::utils::ArgumentEncoder + Send + Sync,
+            R: serde::de::DeserializeOwned + CandidType,
+        {
+            unimplemented!()
+        }
+    }
+
+    async fn check(canister: MockCanister) -> BTFResult<()> {
+        query_capabilities(&canister)
+            .await
+            .unwrap()
+            .check(DEPOSIT_STANDARDS)
+    }
+
+    #[tokio::test]
+    async fn non_ledger_canister_is_unsupported() {
+        assert_eq!(
+            check(MockCanister::NotLedger).await,
+            Err(Error::UnsupportedLedger {
+                missing: vec![ICRC1_STANDARD.into(), ICRC2_STANDARD.into()]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn icrc1_ledger_is_not_supported_for_deposits() {
+        assert_eq!(
+            check(MockCanister::Icrc1Ledger).await,
+            Err(Error::InvalidBurnOperation(
+                "ledger does not support ICRC-2".into()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn ledger_failing_allowance_probe_is_not_supported_for_deposits() {
+        let capabilities = query_capabilities(&MockCanister::Icrc2DeclaredOnly)
+            .await
+            .unwrap();
+        assert_eq!(capabilities.standards, vec![ICRC1_STANDARD.to_string()]);
+        assert_eq!(
+            capabilities.check(DEPOSIT_STANDARDS),
+            Err(Error::InvalidBurnOperation(
+                "ledger does not support ICRC-2".into()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn icrc2_ledger_is_supported() {
+        assert_eq!(check(MockCanister::Icrc2Ledger).await, Ok(()));
+    }
+
+    #[test]
+    fn transient_failures_are_not_treated_as_unsupported() {
+        assert!(!is_not_implemented(&IcrcCanisterError::CanisterError(
+            RejectionCode::SysTransient,
+            "timeout".into()
+        )));
+        assert!(is_not_implemented(&IcrcCanisterError::CanisterError(
+            RejectionCode::DestinationInvalid,
+            "method not found".into()
+        )));
+    }
+}
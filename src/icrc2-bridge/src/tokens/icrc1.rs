@@ -1,9 +1,11 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 
+use bridge_did::icrc_withdrawal::DEFAULT_MAX_ICRC_MEMO_LENGTH;
 use candid::{CandidType, Nat, Principal};
 use evm_canister_client::{CanisterClient, CanisterClientError, IcCanisterClient};
 use ic_exports::ic_kit::RejectionCode;
+use ic_exports::icrc_types::icrc2::approve::ApproveError;
 use icrc_client::account::Account;
 use icrc_client::transfer::TransferError;
 use icrc_client::transfer_from::TransferFromError;
@@ -15,6 +17,7 @@ use thiserror::Error;
 const ICRC1_METADATA_DECIMALS: &str = "icrc1:decimals";
 const ICRC1_METADATA_NAME: &str = "icrc1:name";
 const ICRC1_METADATA_SYMBOL: &str = "icrc1:symbol";
+const ICRC1_METADATA_MAX_MEMO_LENGTH: &str = "icrc1:max_memo_length";
 
 thread_local! {
     static TOKEN_CONFIGURATION: RefCell<HashMap<Principal, TokenConfiguration>> = RefCell::new(HashMap::default());
@@ -69,6 +72,12 @@ pub async fn refresh_token_configuration(
     Ok(config)
 }
 
+/// Queries the token balance of the account.
+pub async fn query_balance(token: Principal, account: Account) -> Result<Nat, IcrcCanisterError> {
+    let icrc_client = IcrcCanisterClient::new(IcCanisterClient::new(token));
+    Ok(icrc_client.icrc1_balance_of(account).await?)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, CandidType)]
 pub struct TokenInfo {
     pub name: String,
@@ -82,6 +91,8 @@ pub struct TokenConfiguration {
     pub fee: Nat,
     pub minting_account: Account,
     pub info: TokenInfo,
+    /// Max length of the transfer memo, accepted by the token canister.
+    pub max_memo_length: usize,
 }
 
 /// Requests fee and minting account configuration from an ICRC-1 canister.
@@ -101,13 +112,16 @@ async fn query_icrc1_configuration(
             subaccount: None,
         });
 
-    let info = query_icrc1_token_info(&icrc_client).await?;
+    let token_metadata = icrc_client.icrc1_metadata().await?;
+    let info = token_info_from_metadata(&token_metadata)?;
+    let max_memo_length = max_memo_length_from_metadata(&token_metadata);
 
     Ok(TokenConfiguration {
         principal: token,
         fee,
         minting_account,
         info,
+        max_memo_length,
     })
 }
 
@@ -119,6 +133,12 @@ where
     C: CanisterClient,
 {
     let token_metadata = client.icrc1_metadata().await?;
+    token_info_from_metadata(&token_metadata)
+}
+
+pub(crate) fn token_info_from_metadata(
+    token_metadata: &[(String, icrc_client::Value)],
+) -> Result<TokenInfo, IcrcCanisterError> {
     let name = match get_metadata_value(&token_metadata, ICRC1_METADATA_NAME) {
         Some(icrc_client::Value::Text(name)) => name.clone(),
         _ => return Err(IcrcCanisterError::Generic("Bad icrc1 metadata".to_string())),
@@ -142,6 +162,17 @@ where
     })
 }
 
+/// Returns the max memo length from the token metadata,
+/// or the ICRC-1 reference ledger default, if the token doesn't report it.
+fn max_memo_length_from_metadata(token_metadata: &[(String, icrc_client::Value)]) -> usize {
+    match get_metadata_value(token_metadata, ICRC1_METADATA_MAX_MEMO_LENGTH) {
+        Some(icrc_client::Value::Nat(length)) => {
+            length.0.to_usize().unwrap_or(DEFAULT_MAX_ICRC_MEMO_LENGTH)
+        }
+        _ => DEFAULT_MAX_ICRC_MEMO_LENGTH,
+    }
+}
+
 /// Get the value of a metadata key from a list of metadata key-value pairs.
 fn get_metadata_value<'a>(
     metadata: &'a [(String, icrc_client::Value)],
@@ -188,6 +219,7 @@ mod test {
                 symbol: "TEST".to_string(),
                 decimals: 18,
             },
+            max_memo_length: DEFAULT_MAX_ICRC_MEMO_LENGTH,
         };
 
         cache_ic_token_configuration(config.clone());
@@ -218,6 +250,19 @@ mod test {
         assert_eq!(token_info.decimals, 18);
     }
 
+    #[test]
+    fn should_read_max_memo_length_from_metadata() {
+        let metadata = vec![(
+            ICRC1_METADATA_MAX_MEMO_LENGTH.to_string(),
+            icrc_client::Value::Nat(Nat::from(80u64)),
+        )];
+        assert_eq!(max_memo_length_from_metadata(&metadata), 80);
+        assert_eq!(
+            max_memo_length_from_metadata(&[]),
+            DEFAULT_MAX_ICRC_MEMO_LENGTH
+        );
+    }
+
     #[derive(Debug, Clone)]
     struct FakeIcrcCanisterClient {
         name: String,
@@ -289,6 +334,9 @@ pub enum IcrcCanisterError {
     #[error("failed to transfer from ICRC token: {0:?}")]
     TransferFromFailed(TransferFromError),
 
+    #[error("failed to approve ICRC token: {0:?}")]
+    ApproveFailed(ApproveError),
+
     #[error("failed to call ICRC canister: {0:?} with message: {1}")]
     CanisterError(RejectionCode, String),
 
@@ -297,6 +345,10 @@ pub enum IcrcCanisterError {
 
     #[error("candid failure: {0}")]
     Generic(String),
+
+    /// The transfer is rejected by the bridge before the ledger call.
+    #[error("invalid ICRC transfer: {0}")]
+    InvalidTransfer(String),
 }
 
 #[allow(unreachable_patterns)] // because in wasm the `e => ...` pattern is impossible.
@@ -321,3 +373,9 @@ impl From<TransferFromError> for IcrcCanisterError {
         Self::TransferFromFailed(value)
     }
 }
+
+impl From<ApproveError> for IcrcCanisterError {
+    fn from(value: ApproveError) -> Self {
+        Self::ApproveFailed(value)
+    }
+}
@@ -1,7 +1,10 @@
-use evm_canister_client::IcCanisterClient;
+use bridge_did::deposit_account::{DepositAccountInfo, DepositAllowance};
+use evm_canister_client::{CanisterClient, IcCanisterClient};
 use ic_exports::candid::{CandidType, Nat, Principal};
 use ic_exports::ic_kit::ic;
+use ic_exports::icrc_types::icrc2::approve::{ApproveArgs, ApproveError};
 use icrc_client::account::{Account, Subaccount};
+use icrc_client::allowance::AllowanceArgs;
 use icrc_client::transfer::{TransferArg, TransferError};
 use icrc_client::transfer_from::{TransferFromArgs, TransferFromError};
 use icrc_client::IcrcCanisterClient;
@@ -29,19 +32,33 @@ pub struct Success {
 /// - If approval fails, returns `Error::Icrc2ApproveError`.
 ///
 /// - If token canister is not available, returns `Error::InternalError`.
+///
+/// - If `memo` is longer than the token max memo length, returns `Error::InvalidTransfer`.
 #[async_recursion::async_recursion]
 pub async fn mint(
     token: Principal,
-    recipient: Principal,
+    recipient: Account,
     amount: Nat,
+    memo: Option<Vec<u8>>,
     repeat_on_bad_fee: bool,
 ) -> Result<Success, IcrcCanisterError> {
-    let fee = get_token_configuration(token).await?.fee;
+    let config = get_token_configuration(token).await?;
+    let fee = config.fee;
+
+    if let Some(memo) = &memo {
+        if memo.len() > config.max_memo_length {
+            return Err(IcrcCanisterError::InvalidTransfer(format!(
+                "memo length {} exceeds the token max memo length {}",
+                memo.len(),
+                config.max_memo_length
+            )));
+        }
+    }
 
     let icrc_client = IcrcCanisterClient::new(IcCanisterClient::new(token));
 
     if amount < fee {
-        return Err(IcrcCanisterError::Generic(format!(
+        return Err(IcrcCanisterError::InvalidTransfer(format!(
             "amount should be greater than fee. Expected fee is {fee}"
         )));
     }
@@ -50,26 +67,22 @@ pub async fn mint(
     let effective_amount = amount.clone() - fee.clone();
 
     if effective_amount == 0_u64 {
-        return Err(IcrcCanisterError::Generic(
+        return Err(IcrcCanisterError::InvalidTransfer(
             "effective amount is zero".into(),
         ));
     }
 
-    let args = TransferArg {
-        to: recipient.into(),
-        memo: None,
-        amount: effective_amount.clone(),
-        fee: Some(fee),
-        from_subaccount: None,
-        created_at_time: None, // Todo: set the time to prevent double spend
-    };
+    let args = mint_transfer_arg(recipient, effective_amount.clone(), fee, memo.clone());
 
     let transfer_result = icrc_client.icrc1_transfer(args).await?;
 
     if repeat_on_bad_fee {
         if let Err(TransferError::BadFee { .. }) = &transfer_result {
-            icrc1::refresh_token_configuration(token).await?;
-            return mint(token, recipient, amount, false).await;
+            // If the configuration is failed to be refreshed, the bad fee error is returned.
+            match icrc1::refresh_token_configuration(token).await {
+                Ok(_) => return mint(token, recipient, amount, memo, false).await,
+                Err(e) => log::warn!("Failed to refresh ICRC token {token} configuration: {e}"),
+            }
         }
     }
 
@@ -79,13 +92,130 @@ pub async fn mint(
     })
 }
 
+/// Arguments of the `icrc1_transfer` call, which transfers the withdrawn tokens to the recipient.
+fn mint_transfer_arg(
+    recipient: Account,
+    amount: Nat,
+    fee: Nat,
+    memo: Option<Vec<u8>>,
+) -> TransferArg {
+    TransferArg {
+        to: recipient,
+        memo: memo.map(Into::into),
+        amount,
+        fee: Some(fee),
+        from_subaccount: None,
+        created_at_time: None, // Todo: set the time to prevent double spend
+    }
+}
+
+/// Approves the `spender` to transfer the `amount` of tokens from the `holder` subaccount
+/// of the bridge canister until `expires_at`. The approve fee is paid by the holder.
+///
+/// The ledger deduplicates the approval with the same `created_at_time`, so a duplicate
+/// of the already executed approval is treated as success with its block index.
+pub async fn approve(
+    token: Principal,
+    holder: Subaccount,
+    spender: Account,
+    amount: Nat,
+    expires_at: u64,
+    created_at_time: u64,
+) -> Result<Nat, IcrcCanisterError> {
+    let fee = get_token_configuration(token).await?.fee;
+    let icrc_client = IcrcCanisterClient::new(IcCanisterClient::new(token));
+
+    let args = approve_arg(holder, spender, amount, expires_at, fee, created_at_time);
+    let approve_result = icrc_client.icrc2_approve(args).await?;
+
+    if let Err(ApproveError::BadFee { .. }) = &approve_result {
+        icrc1::refresh_token_configuration(token).await?;
+    }
+
+    match approve_result {
+        Err(ApproveError::Duplicate { duplicate_of }) => Ok(duplicate_of),
+        result => Ok(result?),
+    }
+}
+
+/// Arguments of the `icrc2_approve` call, which approves the withdrawn tokens to the spender.
+fn approve_arg(
+    holder: Subaccount,
+    spender: Account,
+    amount: Nat,
+    expires_at: u64,
+    fee: Nat,
+    created_at_time: u64,
+) -> ApproveArgs {
+    ApproveArgs {
+        from_subaccount: Some(holder),
+        spender,
+        amount,
+        expected_allowance: None,
+        expires_at: Some(expires_at),
+        fee: Some(fee),
+        memo: None,
+        created_at_time: Some(created_at_time),
+    }
+}
+
+/// Transfers the `amount` of tokens from the `holder` subaccount of the bridge canister
+/// back to its main account. The transfer fee is paid by the holder.
+///
+/// The ledger deduplicates the transfer with the same `created_at_time`, so a duplicate
+/// of the already executed transfer is treated as success with its block index.
+pub async fn sweep(
+    token: Principal,
+    holder: Subaccount,
+    amount: Nat,
+    created_at_time: u64,
+) -> Result<Nat, IcrcCanisterError> {
+    let fee = get_token_configuration(token).await?.fee;
+    let icrc_client = IcrcCanisterClient::new(IcCanisterClient::new(token));
+
+    let args = sweep_transfer_arg(holder, amount, fee, created_at_time);
+    let transfer_result = icrc_client.icrc1_transfer(args).await?;
+
+    if let Err(TransferError::BadFee { .. }) = &transfer_result {
+        icrc1::refresh_token_configuration(token).await?;
+    }
+
+    match transfer_result {
+        Err(TransferError::Duplicate { duplicate_of }) => Ok(duplicate_of),
+        result => Ok(result?),
+    }
+}
+
+/// Arguments of the `icrc1_transfer` call, which returns the tokens of the expired approval
+/// to the bridge canister main account.
+fn sweep_transfer_arg(
+    holder: Subaccount,
+    amount: Nat,
+    fee: Nat,
+    created_at_time: u64,
+) -> TransferArg {
+    TransferArg {
+        to: Account::from(ic::id()),
+        memo: None,
+        amount,
+        fee: Some(fee),
+        from_subaccount: Some(holder),
+        created_at_time: Some(created_at_time),
+    }
+}
+
 /// Performs a transfer from the `from` account to the bridge canister main account.
+///
+/// If `created_at_time` is set, the ledger deduplicates the transfer with the same arguments.
+/// A duplicate of the already executed transfer is treated as success with its block index.
 #[async_recursion::async_recursion]
 pub async fn burn(
     token: Principal,
     from: Account,
     spender_subaccount: Option<Subaccount>,
     amount: Nat,
+    created_at_time: Option<u64>,
+    memo: Option<Vec<u8>>,
     repeat_on_bad_fee: bool,
 ) -> Result<Success, IcrcCanisterError> {
     let icrc_client = IcrcCanisterClient::new(IcCanisterClient::new(token));
@@ -104,8 +234,8 @@ pub async fn burn(
         to: bridge_canister_account,
         amount: amount.clone(),
         fee: None,
-        memo: None,
-        created_at_time: None,
+        memo: memo.clone().map(Into::into),
+        created_at_time,
     };
 
     let transfer_result = icrc_client.icrc2_transfer_from(args).await?;
@@ -113,12 +243,205 @@ pub async fn burn(
     if repeat_on_bad_fee {
         if let Err(TransferFromError::BadFee { .. }) = &transfer_result {
             icrc1::refresh_token_configuration(token).await?;
-            return burn(token, from, spender_subaccount, amount, false).await;
+            return burn(
+                token,
+                from,
+                spender_subaccount,
+                amount,
+                created_at_time,
+                memo,
+                false,
+            )
+            .await;
         }
     }
 
-    Ok(Success {
-        tx_id: transfer_result?,
-        amount,
+    let tx_id = match transfer_result {
+        Err(TransferFromError::Duplicate { duplicate_of }) => duplicate_of,
+        result => result?,
+    };
+
+    Ok(Success { tx_id, amount })
+}
+
+/// Queries the remaining allowance of the deposit spender to transfer the tokens
+/// from the sender `account`.
+pub async fn deposit_allowance(
+    token: Principal,
+    account: Account,
+    info: DepositAccountInfo,
+) -> Result<DepositAllowance, IcrcCanisterError> {
+    let icrc_client = IcrcCanisterClient::new(IcCanisterClient::new(token));
+    query_deposit_allowance(&icrc_client, account, info).await
+}
+
+async fn query_deposit_allowance<C: CanisterClient>(
+    icrc_client: &IcrcCanisterClient<C>,
+    account: Account,
+    info: DepositAccountInfo,
+) -> Result<DepositAllowance, IcrcCanisterError> {
+    let args = AllowanceArgs {
+        account,
+        spender: info.spender,
+    };
+    let allowance = icrc_client.icrc2_allowance(args).await?;
+
+    Ok(DepositAllowance {
+        info,
+        allowance: allowance.allowance,
+        expires_at: allowance.expires_at,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use evm_canister_client::CanisterClientResult;
+    use icrc_client::allowance::Allowance;
+
+    use super::*;
+
+    #[test]
+    fn mint_transfer_arg_should_contain_memo() {
+        let recipient = Account::from(Principal::from_slice(&[1; 29]));
+        let args = mint_transfer_arg(
+            recipient,
+            100u64.into(),
+            10u64.into(),
+            Some(b"exchange-tag".to_vec()),
+        );
+
+        assert_eq!(args.to, recipient);
+        assert_eq!(args.memo, Some(b"exchange-tag".to_vec().into()));
+    }
+
+    #[test]
+    fn mint_transfer_arg_should_target_subaccount() {
+        let recipient = Account {
+            owner: Principal::from_slice(&[1; 29]),
+            subaccount: Some([5; 32]),
+        };
+        let args = mint_transfer_arg(recipient, 100u64.into(), 10u64.into(), None);
+
+        assert_eq!(args.to, recipient);
+        assert_eq!(args.from_subaccount, None);
+    }
+
+    #[test]
+    fn mint_transfer_arg_without_memo() {
+        let recipient = Account::from(Principal::from_slice(&[1; 29]));
+        let args = mint_transfer_arg(recipient, 100u64.into(), 10u64.into(), None);
+
+        assert_eq!(args.memo, None);
+        assert_eq!(args.amount, Nat::from(100u64));
+        assert_eq!(args.fee, Some(Nat::from(10u64)));
+        assert_eq!(args.from_subaccount, None);
+    }
+
+    #[test]
+    fn approve_arg_should_expire_and_be_deduplicated() {
+        let spender = Account {
+            owner: Principal::from_slice(&[1; 29]),
+            subaccount: Some([5; 32]),
+        };
+        let args = approve_arg([2; 32], spender, 100u64.into(), 42, 10u64.into(), 7);
+
+        assert_eq!(args.from_subaccount, Some([2; 32]));
+        assert_eq!(args.spender, spender);
+        assert_eq!(args.amount, Nat::from(100u64));
+        assert_eq!(args.expires_at, Some(42));
+        assert_eq!(args.expected_allowance, None);
+        assert_eq!(args.fee, Some(Nat::from(10u64)));
+        assert_eq!(args.created_at_time, Some(7));
+    }
+
+    #[test]
+    fn sweep_transfer_arg_should_return_tokens_to_bridge() {
+        ic_exports::ic_kit::MockContext::new().inject();
+        let args = sweep_transfer_arg([2; 32], 100u64.into(), 10u64.into(), 7);
+
+        assert_eq!(args.to, Account::from(ic::id()));
+        assert_eq!(args.from_subaccount, Some([2; 32]));
+        assert_eq!(args.amount, Nat::from(100u64));
+        assert_eq!(args.created_at_time, Some(7));
+    }
+
+    #[tokio::test]
+    async fn deposit_allowance_is_queried_for_the_spender() {
+        let sender = Account {
+            owner: Principal::from_slice(&[1; 29]),
+            subaccount: Some([2; 32]),
+        };
+        let info = DepositAccountInfo {
+            spender: Account {
+                owner: Principal::from_slice(&[3; 29]),
+                subaccount: Some([4; 32]),
+            },
+            ledger_fee: 10u64.into(),
+            min_amount: 1u64.into(),
+        };
+        let client = IcrcCanisterClient::new(FakeLedger {
+            account: sender,
+            spender: info.spender,
+        });
+
+        let allowance = query_deposit_allowance(&client, sender, info.clone())
+            .await
+            .unwrap();
+        assert_eq!(allowance.info, info);
+        assert_eq!(allowance.allowance, Nat::from(500u64));
+        assert_eq!(allowance.expires_at, Some(42));
+
+        // Allowance of another account is not the sender one.
+        let other = Account::from(Principal::from_slice(&[5; 29]));
+        let allowance = query_deposit_allowance(&client, other, info).await.unwrap();
+        assert_eq!(allowance.allowance, Nat::from(0u64));
+        assert_eq!(allowance.expires_at, None);
+    }
+
+    /// Ledger, which has an allowance of the `spender` for the `account` only.
+    #[derive(Debug, Clone)]
+    struct FakeLedger {
+        account: Account,
+        spender: Account,
+    }
+
+    impl FakeLedger {
+        fn allowance<T: candid::utils::ArgumentEncoder>(&self, args: T) -> Allowance {
+            let (args,) =
+                candid::decode_args::<(AllowanceArgs,)>(&candid::encode_args(args).unwrap())
+                    .unwrap();
+            if args.account == self.account && args.spender == self.spender {
+                Allowance {
+                    allowance: 500u64.into(),
+                    expires_at: Some(42),
+                }
+            } else {
+                Allowance {
+                    allowance: 0u64.into(),
+                    expires_at: None,
+                }
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl CanisterClient for FakeLedger {
+        async fn query<T, R>(&self, method: &str, args: T) -> CanisterClientResult<R>
+        where
+            T: candid::utils::ArgumentEncoder + Send + Sync,
+            R: serde::de::DeserializeOwned + CandidType,
+        {
+            assert_eq!(method, "icrc2_allowance");
+            let allowance = candid::encode_one(self.allowance(args)).unwrap();
+            Ok(candid::decode_one(&allowance).unwrap())
+        }
+
+        async fn update<T, R>(&self, method: &str, args: T) -> CanisterClientResult<R>
+        where
+            T: candid::utils::ArgumentEncoder + Send + Sync,
+            R: serde::de::DeserializeOwned + CandidType,
+        {
+            self.query(method, args).await
+        }
+    }
+}
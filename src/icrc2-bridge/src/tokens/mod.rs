@@ -1,5 +1,6 @@
 use did::H256;
 
+pub mod capabilities;
 pub mod icrc1;
 pub mod icrc2;
 
@@ -217,6 +217,7 @@ where
             approve_after_mint: Some(ApproveAfterMint {
                 approve_spender: wallet.address().into(),
                 approve_amount: U256::from(1000_u64),
+                permit: None,
             }),
             fee_payer: Some(eth_address.clone()),
         };
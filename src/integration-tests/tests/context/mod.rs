@@ -835,6 +835,8 @@ pub trait TestContext {
             recipient_address,
             fee_payer,
             approve_after_mint,
+            confirmation_delay_secs: None,
+            preference_fields: None,
         };
 
         let encoded_reason = Encode!(&reason).unwrap();
@@ -1251,6 +1253,7 @@ pub trait TestContext {
                         evm_params_query: Duration::from_secs(2),
                         logs_query: Duration::from_secs(2),
                     },
+                    evm_finality: None,
                 };
                 self.install_canister(
                     self.canisters().erc20_bridge(),
@@ -1425,6 +1428,9 @@ pub fn icrc_bridge_canister_init_data(
             log_filter: Some("trace".to_string()),
             ..Default::default()
         }),
+        evm_finality: None,
+        nonce_offset: None,
+        mode: None,
     }
 }
 
@@ -1443,6 +1449,9 @@ pub fn erc20_bridge_canister_init_data(
             log_filter: Some("trace".to_string()),
             ..Default::default()
         }),
+        evm_finality: None,
+        nonce_offset: None,
+        mode: None,
     }
 }
 
@@ -1506,6 +1515,7 @@ pub fn brc20_bridge_canister_init_data(
             mempool_timeout: Duration::from_secs(60),
             indexer_consensus_threshold: 1,
             schnorr_key_id: SchnorrKeyIds::TestKeyLocalDevelopment,
+            fallback_indexers: None,
         },
     )
 }
@@ -178,6 +178,8 @@ impl<Ctx: TestContext + Send + Sync> BaseTokens for IcrcBaseTokens<Ctx> {
             recipient_address: to.into(),
             fee_payer: Some(to.into()),
             approve_after_mint: None,
+            confirmation_delay_secs: None,
+            preference_fields: None,
         };
 
         let encoded_reason = Encode!(&reason).unwrap();
@@ -217,6 +217,7 @@ async fn test_icrc2_token_canister_stopped() {
         order,
         tx_hash,
         is_refund,
+        ..
     } = operation
     else {
         panic!("expected ConfirmMint operation state");
@@ -362,6 +363,7 @@ async fn test_icrc2_tokens_approve_after_mint() {
         Some(ApproveAfterMint {
             approve_spender: spender_wallet.address().into(),
             approve_amount: approve_amount.clone(),
+            permit: None,
         }),
     )
     .await
@@ -33,12 +33,60 @@ pub fn inspect_configure_indexers(config: SharedConfig) {
     inspect_caller_is_owner(owner, caller)
 }
 
+pub fn inspect_set_indexer_consensus_threshold(config: SharedConfig) {
+    let caller = ic::caller();
+    let owner = config.borrow().get_owner();
+    inspect_caller_is_owner(owner, caller)
+}
+
+/// Inspects if the consensus can be reached with the threshold.
+pub fn inspect_indexer_consensus_threshold_is_valid(threshold: u8) {
+    if threshold == 0 {
+        ic::trap("Indexer consensus threshold must be greater than zero");
+    }
+}
+
 #[cfg(feature = "export-api")]
 fn inspect_method(method: &str) {
     let config = ConfigStorage::get();
     match method {
         "admin_configure_ecdsa" => inspect_configure_ecdsa(config),
         "admin_configure_indexers" => inspect_configure_indexers(config),
+        "admin_set_indexer_consensus_threshold" => {
+            inspect_set_indexer_consensus_threshold(config);
+            let (threshold,) = api::call::arg_data::<(u8,)>(Default::default());
+            inspect_indexer_consensus_threshold_is_valid(threshold);
+        }
         _ => {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bridge_canister::runtime::state::config::ConfigStorage;
+    use candid::Principal;
+    use ic_exports::ic_kit::{inject, MockContext};
+    use ic_storage::IcStorage;
+
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "only allowed for the owner")]
+    fn anonymous_caller_cannot_set_indexer_consensus_threshold() {
+        MockContext::new().inject();
+        let config = ConfigStorage::get();
+        config
+            .borrow_mut()
+            .set_owner(Principal::from_slice(&[1; 20]));
+
+        inject::get_context().update_id(Principal::anonymous());
+        inspect_set_indexer_consensus_threshold(config);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be greater than zero")]
+    fn zero_indexer_consensus_threshold_is_rejected() {
+        MockContext::new().inject();
+        inspect_indexer_consensus_threshold_is_valid(0);
+    }
+}
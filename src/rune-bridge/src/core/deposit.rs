@@ -131,18 +131,19 @@ impl RuneDeposit<IcUtxoProvider> {
     ) -> Result<Self, DepositError> {
         let state_ref = state.borrow();
 
-        let signer_strategy = get_runtime_state()
-            .borrow()
-            .config
-            .borrow()
-            .get_signing_strategy();
+        let runtime_config = get_runtime_state().borrow().config.clone();
+        let signer_strategy = runtime_config.borrow().get_signing_strategy();
+        let request_timeout = runtime_config.borrow().get_rpc_request_timeout();
 
         let network = state_ref.network();
         let ic_network = state_ref.ic_btc_network();
         let cache_timeout = state_ref.utxo_cache_timeout();
 
         let indexer_configs = state_ref.indexers_config();
-        let indexers = indexer_configs.into_iter().map(get_indexer).collect();
+        let indexers = indexer_configs
+            .into_iter()
+            .map(|config| get_indexer(config, request_timeout))
+            .collect();
 
         let signer = state_ref
             .btc_signer(&signer_strategy)
@@ -441,7 +442,7 @@ impl<UTXO: UtxoProvider> RuneDeposit<UTXO> {
             recipient: dst_address.clone(),
             dst_token: token_address.clone(),
             nonce,
-            sender_chain_id,
+            sender_chain_id: sender_chain_id.into(),
             recipient_chain_id,
             name: rune_info.name_array(),
             symbol: rune_info.symbol_array(),
@@ -449,6 +450,7 @@ impl<UTXO: UtxoProvider> RuneDeposit<UTXO> {
             approve_spender: Default::default(),
             approve_amount: Default::default(),
             fee_payer: H160::default(),
+            permit: None,
         }
     }
 
@@ -482,7 +484,9 @@ mod tests {
     use bitcoin::secp256k1::Secp256k1;
     use bitcoin::{FeeRate, PrivateKey, Transaction};
     use bridge_canister::memory::{memory_by_id, StableMemory};
+    use bridge_canister::operation_blocks::OperationBlocksMemory;
     use bridge_canister::operation_store::OperationsMemory;
+    use bridge_canister::outbox::OutboxMemory;
     use bridge_canister::runtime::state::config::ConfigStorage;
     use bridge_canister::runtime::state::{SharedConfig, State};
     use ic_stable_structures::MemoryId;
@@ -499,6 +503,16 @@ mod tests {
             operations_log: memory_by_id(MemoryId::new(3)),
             operations_map: memory_by_id(MemoryId::new(4)),
             memo_operations_map: memory_by_id(MemoryId::new(5)),
+            outbox: OutboxMemory {
+                id_counter: memory_by_id(MemoryId::new(6)),
+                pending: memory_by_id(MemoryId::new(7)),
+                parked: memory_by_id(MemoryId::new(8)),
+                due: memory_by_id(MemoryId::new(10)),
+            },
+            blocks: OperationBlocksMemory {
+                blocks: memory_by_id(MemoryId::new(9)),
+                operation_index: memory_by_id(MemoryId::new(11)),
+            },
         }
     }
 
@@ -2,13 +2,16 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 use std::future::Future;
 use std::str::FromStr as _;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use bridge_did::init::IndexerType;
 use bridge_did::runes::RuneName;
+use bridge_utils::http_transform;
+use bridge_utils::timeout::with_timeout;
 use ic_exports::ic_cdk::api::management_canister::bitcoin::{Outpoint, Utxo};
 use ic_exports::ic_cdk::api::management_canister::http_request::{
-    http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod,
+    http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, TransformContext,
 };
 use ordinals::{RuneId, SpacedRune};
 use serde::de::DeserializeOwned;
@@ -28,9 +31,14 @@ pub(crate) trait RuneIndexProvider {
     async fn get_rune_list(&self) -> Result<Vec<(RuneId, SpacedRune, u8)>, GetInputsError>;
 }
 
-pub(crate) fn get_indexer(indexer_type: IndexerType) -> Box<dyn RuneIndexProvider> {
+pub(crate) fn get_indexer(
+    indexer_type: IndexerType,
+    timeout: Duration,
+) -> Box<dyn RuneIndexProvider> {
     match indexer_type {
-        IndexerType::OrdHttp { url } => Box::new(OrdIndexProvider::new(IcHttpClient, url)),
+        IndexerType::OrdHttp { url } => {
+            Box::new(OrdIndexProvider::new(IcHttpClient::new(timeout), url))
+        }
     }
 }
 
@@ -47,7 +55,16 @@ pub trait HttpClient {
 }
 
 /// HTTP client implementation for the Internet Computer canisters.
-pub struct IcHttpClient;
+pub struct IcHttpClient {
+    /// Requests not completed in the timeout fail with [`DepositError::Unavailable`].
+    timeout: Duration,
+}
+
+impl IcHttpClient {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
 
 impl HttpClient for IcHttpClient {
     async fn http_request<R: DeserializeOwned>(
@@ -68,13 +85,20 @@ impl HttpClient for IcHttpClient {
                 value: "application/json".to_string(),
             }],
             body: None,
-            transform: None,
+            transform: Some(TransformContext::from_name(
+                http_transform::TRANSFORM_HTTP_RESPONSE.to_string(),
+                vec![],
+            )),
         };
 
-        let result = http_request(request_params, CYCLES_PER_HTTP_REQUEST)
-            .await
-            .map_err(|err| DepositError::Unavailable(format!("Indexer unavailable: {err:?}")))?
-            .0;
+        let result = with_timeout(
+            http_request(request_params, CYCLES_PER_HTTP_REQUEST),
+            self.timeout,
+        )
+        .await
+        .map_err(|err| DepositError::Unavailable(format!("Indexer unavailable: {err}")))?
+        .map_err(|err| DepositError::Unavailable(format!("Indexer unavailable: {err:?}")))?
+        .0;
 
         log::trace!(
             "Indexer responded with: {} {:?} BODY: {}",
@@ -100,6 +100,7 @@ pub mod test {
                         approve_spender: Default::default(),
                         approve_amount: Default::default(),
                         fee_payer: Default::default(),
+                        permit: None,
                     })
                     .collect();
 
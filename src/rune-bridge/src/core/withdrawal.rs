@@ -48,11 +48,11 @@ impl RuneWithdrawalPayloadImpl {
             )));
         };
 
-        let Some(token_id) = Id256::from_slice(&to_token) else {
-            return Err(WithdrawError::InvalidRequest(format!(
-                "Failed to decode token id from the value {to_token:?}"
-            )));
-        };
+        let token_id = Id256::try_from_slice(&to_token).map_err(|e| {
+            WithdrawError::InvalidRequest(format!(
+                "Failed to decode token id from the value {to_token:?}: {e}"
+            ))
+        })?;
 
         let Ok(rune_id) = token_id.try_into() else {
             return Err(WithdrawError::InvalidRequest(format!(
@@ -2,16 +2,21 @@ mod mint_order_handler;
 mod mint_tx_handler;
 
 use std::collections::HashMap;
+use std::mem;
 
 use bridge_canister::bridge::{Operation, OperationProgress};
 use bridge_canister::runtime::service::ServiceId;
 use bridge_canister::runtime::RuntimeState;
 use bridge_did::error::{BTFResult, Error};
 use bridge_did::op_id::OperationId;
-use bridge_did::operations::{RuneBridgeDepositOp, RuneBridgeOp, RuneBridgeWithdrawOp};
+use bridge_did::operation_log::OperationLog;
+use bridge_did::operations::{
+    RuneBridgeDepositOp, RuneBridgeOp, RuneBridgeOpV1, RuneBridgeWithdrawOp,
+};
 use bridge_did::runes::{DidTransaction, RuneName, RuneToWrap, RuneWithdrawalPayload};
+use bridge_did::versioned::Versioned;
 use candid::{CandidType, Deserialize};
-use did::H160;
+use did::{H160, H256};
 use ic_exports::ic_cdk::api::management_canister::bitcoin::Utxo;
 use ic_task_scheduler::task::TaskOptions;
 use serde::Serialize;
@@ -28,6 +33,10 @@ pub const REFRESH_PARAMS_SERVICE_ID: ServiceId = 0;
 pub const FETCH_BTF_EVENTS_SERVICE_ID: ServiceId = 1;
 pub const SIGN_MINT_ORDER_SERVICE_ID: ServiceId = 2;
 pub const SEND_MINT_TX_SERVICE_ID: ServiceId = 3;
+pub const MONITOR_GAS_BALANCE_SERVICE_ID: ServiceId = 4;
+pub const PING_EVM_SERVICE_ID: ServiceId = 5;
+pub const DISPATCH_OUTBOX_SERVICE_ID: ServiceId = 6;
+pub const MIGRATE_RECORDS_SERVICE_ID: ServiceId = 7;
 
 pub mod events_handler;
 
@@ -128,6 +137,29 @@ impl Operation for RuneBridgeOpImpl {
         Ok(OperationProgress::Progress(next_step?))
     }
 
+    /// Operations stored before the chain ids of the mint orders were extended to 64 bits are
+    /// decoded with the legacy shape. An operation, which sends or confirms the order signed in
+    /// the legacy layout, is moved back to signing of the same order.
+    fn decode_legacy_log(bytes: &[u8]) -> Option<OperationLog<Self>> {
+        let log = OperationLog::<RuneBridgeOpV1>::from_versioned_bytes(bytes).ok()?;
+        let mut log = log.map_payload(|op, _| Self(op.into()));
+
+        let legacy_order = match &log.current_step().0 {
+            RuneBridgeOp::Deposit(
+                RuneBridgeDepositOp::SendMintOrder(order)
+                | RuneBridgeDepositOp::ConfirmMintOrder { order, .. },
+            ) => order.legacy_order(),
+            _ => None,
+        };
+        if let Some(order) = legacy_order {
+            log.add_step(Ok(Self(RuneBridgeOp::Deposit(
+                RuneBridgeDepositOp::SignMintOrder(order),
+            ))));
+        }
+
+        Some(log)
+    }
+
     fn is_complete(&self) -> bool {
         match self.0 {
             RuneBridgeOp::Deposit(RuneBridgeDepositOp::AwaitInputs { .. }) => false,
@@ -189,6 +221,59 @@ impl Operation for RuneBridgeOpImpl {
             ),
         }
     }
+
+    fn evm_tx_hash(&self) -> Option<H256> {
+        match &self.0 {
+            RuneBridgeOp::Deposit(RuneBridgeDepositOp::ConfirmMintOrder { tx_id, .. }) => {
+                Some(tx_id.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Operation can stay in its state with updated data, e.g. the mint order nonce,
+    /// or move along its deposit or withdrawal flow.
+    fn can_transition(from: &Self, to: &Self) -> bool {
+        match (&from.0, &to.0) {
+            (RuneBridgeOp::Deposit(from), RuneBridgeOp::Deposit(to)) => {
+                mem::discriminant(from) == mem::discriminant(to)
+                    || matches!(
+                        (from, to),
+                        (
+                            RuneBridgeDepositOp::AwaitInputs { .. },
+                            RuneBridgeDepositOp::AwaitConfirmations { .. }
+                        ) | (
+                            RuneBridgeDepositOp::AwaitConfirmations { .. },
+                            RuneBridgeDepositOp::SignMintOrder(_)
+                        ) | (
+                            RuneBridgeDepositOp::SignMintOrder(_),
+                            RuneBridgeDepositOp::SendMintOrder(_)
+                        ) | (
+                            RuneBridgeDepositOp::SendMintOrder(_),
+                            RuneBridgeDepositOp::ConfirmMintOrder { .. }
+                                | RuneBridgeDepositOp::MintOrderConfirmed { .. }
+                        ) | (
+                            RuneBridgeDepositOp::ConfirmMintOrder { .. },
+                            RuneBridgeDepositOp::MintOrderConfirmed { .. }
+                        )
+                    )
+            }
+            (RuneBridgeOp::Withdraw(from), RuneBridgeOp::Withdraw(to)) => {
+                mem::discriminant(from) == mem::discriminant(to)
+                    || matches!(
+                        (from, to),
+                        (
+                            RuneBridgeWithdrawOp::CreateTransaction { .. },
+                            RuneBridgeWithdrawOp::SendTransaction { .. }
+                        ) | (
+                            RuneBridgeWithdrawOp::SendTransaction { .. },
+                            RuneBridgeWithdrawOp::TransactionSent { .. }
+                        )
+                    )
+            }
+            _ => false,
+        }
+    }
 }
 
 impl RuneBridgeOpImpl {
@@ -207,18 +292,20 @@ impl RuneBridgeOpImpl {
         let runtime = get_runtime();
         let runtime_mut = runtime.borrow_mut();
 
-        let mut state = state.borrow_mut();
-        operations
+        // The state must not be borrowed while scheduling, as tasks are created in the state.
+        let created: Vec<_> = operations
             .into_iter()
             .flat_map(|op| {
+                let mut state = state.borrow_mut();
                 let id = state.operations.new_operation(op, None);
                 log::debug!("created new operation on split {id}");
                 state.operations.get(id).map(|op| (id, op))
             })
-            .for_each(|(id, operation)| {
-                log::debug!("scheduling split operation {id}");
-                runtime_mut.schedule_operation(id, operation);
-            });
+            .collect();
+        for (id, operation) in created {
+            log::debug!("scheduling split operation {id}");
+            runtime_mut.schedule_operation(id, operation);
+        }
 
         self_update
     }
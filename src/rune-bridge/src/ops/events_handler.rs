@@ -7,7 +7,7 @@ use bridge_did::event_data::{
     BurntEventData, MintedEventData, MinterNotificationType, NotifyMinterEventData,
 };
 use bridge_did::operations::{RuneBridgeDepositOp, RuneBridgeOp, RuneBridgeWithdrawOp};
-use candid::Decode;
+use bridge_utils::event_payload;
 
 use super::RuneBridgeOpImpl;
 use crate::core::withdrawal::RuneWithdrawalPayloadImpl;
@@ -27,7 +27,7 @@ impl RuneEventsHandler {
         &self,
         event: NotifyMinterEventData,
     ) -> Option<OperationAction<RuneBridgeOpImpl>> {
-        match Decode!(&event.user_data, RuneDepositRequestData) {
+        match event_payload::decode_candid_payload::<RuneDepositRequestData>(&event.user_data) {
             Ok(data) => {
                 let operation =
                     RuneBridgeOpImpl(RuneBridgeOp::Deposit(RuneBridgeDepositOp::AwaitInputs {
@@ -37,12 +37,12 @@ impl RuneEventsHandler {
                     }));
                 Some(OperationAction::Create(operation, event.memo()))
             }
-            _ => {
-                log::warn!(
-                    "Invalid encoded deposit request: {}",
-                    hex::encode(&event.user_data)
-                );
-                None
+            Err(e) => {
+                log::warn!("Invalid encoded deposit request: {e}");
+                Some(OperationAction::reject(
+                    event,
+                    format!("failed to decode deposit request: {e}"),
+                ))
             }
         }
     }
@@ -1,6 +1,6 @@
 use bridge_canister::bridge::{Operation as _, OperationContext};
 use bridge_canister::memory::StableMemory;
-use bridge_canister::runtime::scheduler::{BridgeTask, SharedScheduler};
+use bridge_canister::runtime::scheduler::SharedScheduler;
 use bridge_canister::runtime::service::sign_orders::MintOrderHandler;
 use bridge_canister::runtime::RuntimeState;
 use bridge_did::error::BTFResult;
@@ -72,7 +72,8 @@ impl MintOrderHandler for RuneMintOrderHandler {
             .update(id, new_op.clone());
 
         if let Some(options) = scheduling_options {
-            let scheduled_task = ScheduledTask::with_options(BridgeTask::new(id, new_op), options);
+            let task = self.state.borrow_mut().new_task(id, new_op);
+            let scheduled_task = ScheduledTask::with_options(task, options);
             self.scheduler.append_task(scheduled_task);
         }
     }
@@ -45,7 +45,7 @@ impl MintTxHandler for RuneMintTxHandler {
         Some(order)
     }
 
-    fn mint_tx_sent(&self, id: OperationId, tx_hash: H256) {
+    fn mint_tx_sent(&self, id: OperationId, tx_hash: H256, evm_params_snapshot: Option<u64>) {
         let op = self.state.borrow().operations.get(id);
         let Some(RuneBridgeOp::Deposit(RuneBridgeDepositOp::SendMintOrder(order))) =
             op.map(|op| op.0)
@@ -56,7 +56,7 @@ impl MintTxHandler for RuneMintTxHandler {
             return;
         };
 
-        self.state.borrow_mut().operations.update(
+        self.state.borrow_mut().operations.update_with_evm_params(
             id,
             RuneBridgeOpImpl(RuneBridgeOp::Deposit(
                 RuneBridgeDepositOp::ConfirmMintOrder {
@@ -64,6 +64,7 @@ impl MintTxHandler for RuneMintTxHandler {
                     tx_id: tx_hash,
                 },
             )),
+            evm_params_snapshot,
         )
     }
 }
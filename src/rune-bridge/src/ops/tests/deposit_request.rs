@@ -2,9 +2,11 @@ use std::collections::HashMap;
 
 use bridge_canister::bridge::OperationAction;
 use bridge_canister::runtime::service::fetch_logs::BtfBridgeEventHandler;
+use bridge_did::dead_letter::DeadLetterEventData;
 use bridge_did::event_data::*;
 use bridge_did::runes::RuneName;
 use candid::Encode;
+use proptest::prelude::*;
 use tests::events_handler::RuneEventsHandler;
 
 use crate::ops::{
@@ -38,8 +40,19 @@ async fn invalid_notification_type_is_noop() {
     assert!(result.is_none());
 }
 
+fn is_rejected(
+    result: &Option<OperationAction<RuneBridgeOpImpl>>,
+    event: &NotifyMinterEventData,
+) -> bool {
+    matches!(
+        result,
+        Some(OperationAction::Reject { event: DeadLetterEventData::Notify(rejected), .. })
+            if rejected == event
+    )
+}
+
 #[tokio::test]
-async fn invalid_notification_payload_is_noop() {
+async fn invalid_notification_payload_is_rejected() {
     let notification = RuneDepositRequestData {
         dst_address: tests::sender(),
         dst_tokens: tests::dst_tokens(),
@@ -57,14 +70,34 @@ async fn invalid_notification_payload_is_noop() {
 
     let handler = RuneEventsHandler::new(tests::test_rune_state());
     let result = handler.on_minter_notification(event.clone());
-    assert!(result.is_none());
+    assert!(is_rejected(&result, &event));
 
     let event = NotifyMinterEventData {
         user_data: vec![],
         ..event
     };
     let result = handler.on_minter_notification(event.clone());
-    assert!(result.is_none());
+    assert!(is_rejected(&result, &event));
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_deposit_payload_is_rejected_or_decoded(
+        user_data in prop::collection::vec(any::<u8>(), 0..1024),
+    ) {
+        let event = NotifyMinterEventData {
+            notification_type: MinterNotificationType::DepositRequest,
+            tx_sender: tests::sender(),
+            user_data,
+            memo: vec![],
+        };
+
+        let handler = RuneEventsHandler::new(tests::test_rune_state());
+        let result = handler.on_minter_notification(event.clone());
+        prop_assert!(
+            is_rejected(&result, &event) || matches!(result, Some(OperationAction::Create(..)))
+        );
+    }
 }
 
 #[tokio::test]
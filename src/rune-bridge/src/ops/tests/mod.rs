@@ -3,7 +3,9 @@ use std::rc::Rc;
 use std::str::FromStr;
 
 use bridge_canister::memory::{memory_by_id, StableMemory};
+use bridge_canister::operation_blocks::OperationBlocksMemory;
 use bridge_canister::operation_store::OperationsMemory;
+use bridge_canister::outbox::OutboxMemory;
 use bridge_canister::runtime::state::config::ConfigStorage;
 use bridge_canister::runtime::state::{SharedConfig, State};
 use ic_stable_structures::MemoryId;
@@ -22,6 +24,16 @@ fn op_memory() -> OperationsMemory<StableMemory> {
         operations_log: memory_by_id(MemoryId::new(3)),
         operations_map: memory_by_id(MemoryId::new(4)),
         memo_operations_map: memory_by_id(MemoryId::new(5)),
+        outbox: OutboxMemory {
+            id_counter: memory_by_id(MemoryId::new(6)),
+            pending: memory_by_id(MemoryId::new(7)),
+            parked: memory_by_id(MemoryId::new(8)),
+            due: memory_by_id(MemoryId::new(10)),
+        },
+        blocks: OperationBlocksMemory {
+            blocks: memory_by_id(MemoryId::new(9)),
+            operation_index: memory_by_id(MemoryId::new(11)),
+        },
     }
 }
 
@@ -98,7 +110,7 @@ pub mod minter_notification {
     }
 
     #[tokio::test]
-    async fn incorrect_data_returns_none() {
+    async fn incorrect_data_is_rejected() {
         let mut data = test_user_data();
         data.push(1);
         let event = NotifyMinterEventData {
@@ -110,7 +122,7 @@ pub mod minter_notification {
 
         let handler = RuneEventsHandler::new(test_rune_state());
         let result = handler.on_minter_notification(event);
-        assert!(result.is_none())
+        assert!(matches!(result, Some(OperationAction::Reject { .. })))
     }
 
     #[tokio::test]